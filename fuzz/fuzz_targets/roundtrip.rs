@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|nbt: simdnbt::owned::BaseNbt| {
+    if let Err(mismatch) = simdnbt::differential::check_roundtrip(&nbt) {
+        panic!("{mismatch}");
+    }
+});
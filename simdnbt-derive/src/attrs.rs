@@ -3,17 +3,35 @@ use syn::parse::{Parse, ParseStream};
 #[derive(Default, Debug)]
 pub struct FieldAttrs {
     pub rename: Option<String>,
+    pub aliases: Vec<String>,
     pub flatten: bool,
+    /// A module with `from_nbt_tag` and `to_nbt_tag` functions that are used
+    /// instead of the `FromNbtTag` and `ToNbtTag` impls for the field.
+    pub with: Option<String>,
+    /// Read the field with `FromNbtTag::from_nbt_tag_lenient`, which accepts
+    /// more tag types.
+    pub lenient: bool,
 }
 
 #[derive(Default, Debug)]
 pub struct UnitAttrs {
     pub rename: Option<String>,
+    pub aliases: Vec<String>,
+    pub other: bool,
 }
 
 #[derive(Default, Debug)]
 pub struct StructAttrs {
     pub deny_unknown_fields: bool,
+    pub transparent: bool,
+    /// Read every field like it has `#[simdnbt(lenient)]`.
+    pub lenient: bool,
+}
+
+#[derive(Default, Debug)]
+pub struct EnumAttrs {
+    pub case_insensitive: bool,
+    pub tag: Option<String>,
 }
 
 impl Parse for FieldAttrs {
@@ -29,11 +47,27 @@ impl Parse for FieldAttrs {
 
                     attrs.rename = Some(rename.value());
                 }
+                "alias" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let alias = input.parse::<syn::LitStr>()?;
+
+                    attrs.aliases.push(alias.value());
+                }
                 "flatten" => {
                     attrs.flatten = true;
                 }
-                _ => todo!(),
+                "with" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let with = input.parse::<syn::LitStr>()?;
+
+                    attrs.with = Some(with.value());
+                }
+                "lenient" => {
+                    attrs.lenient = true;
+                }
+                _ => return Err(syn::Error::new(attr.span(), "unknown simdnbt attribute")),
             }
+            input.parse::<Option<syn::Token![,]>>()?;
         }
 
         Ok(attrs)
@@ -53,8 +87,18 @@ impl Parse for UnitAttrs {
 
                     attrs.rename = Some(rename.value());
                 }
-                _ => todo!(),
+                "alias" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let alias = input.parse::<syn::LitStr>()?;
+
+                    attrs.aliases.push(alias.value());
+                }
+                "other" => {
+                    attrs.other = true;
+                }
+                _ => return Err(syn::Error::new(attr.span(), "unknown simdnbt attribute")),
             }
+            input.parse::<Option<syn::Token![,]>>()?;
         }
 
         Ok(attrs)
@@ -71,8 +115,40 @@ impl Parse for StructAttrs {
                 "deny_unknown_fields" => {
                     attrs.deny_unknown_fields = true;
                 }
-                _ => todo!(),
+                "transparent" => {
+                    attrs.transparent = true;
+                }
+                "lenient" => {
+                    attrs.lenient = true;
+                }
+                _ => return Err(syn::Error::new(attr.span(), "unknown simdnbt attribute")),
+            }
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+
+        Ok(attrs)
+    }
+}
+
+impl Parse for EnumAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        while !input.is_empty() {
+            let attr = input.parse::<proc_macro2::Ident>()?;
+            match attr.to_string().as_str() {
+                "case_insensitive" => {
+                    attrs.case_insensitive = true;
+                }
+                "tag" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let tag = input.parse::<syn::LitStr>()?;
+
+                    attrs.tag = Some(tag.value());
+                }
+                _ => return Err(syn::Error::new(attr.span(), "unknown simdnbt attribute")),
             }
+            input.parse::<Option<syn::Token![,]>>()?;
         }
 
         Ok(attrs)
@@ -89,9 +165,16 @@ pub fn parse_field_attrs(attrs: &[syn::Attribute]) -> FieldAttrs {
         if let Some(rename) = new_attr.rename {
             field_attrs.rename = Some(rename);
         }
+        field_attrs.aliases.extend(new_attr.aliases);
         if new_attr.flatten {
             field_attrs.flatten = true;
         }
+        if let Some(with) = new_attr.with {
+            field_attrs.with = Some(with);
+        }
+        if new_attr.lenient {
+            field_attrs.lenient = true;
+        }
     }
 
     field_attrs
@@ -107,6 +190,10 @@ pub fn parse_unit_attrs(attrs: &[syn::Attribute]) -> UnitAttrs {
         if let Some(rename) = new_attr.rename {
             unit_attrs.rename = Some(rename);
         }
+        unit_attrs.aliases.extend(new_attr.aliases);
+        if new_attr.other {
+            unit_attrs.other = true;
+        }
     }
 
     unit_attrs
@@ -122,7 +209,31 @@ pub fn parse_struct_attrs(attrs: &[syn::Attribute]) -> StructAttrs {
         if new_attr.deny_unknown_fields {
             struct_attrs.deny_unknown_fields = true;
         }
+        if new_attr.transparent {
+            struct_attrs.transparent = true;
+        }
+        if new_attr.lenient {
+            struct_attrs.lenient = true;
+        }
     }
 
     struct_attrs
 }
+
+pub fn parse_enum_attrs(attrs: &[syn::Attribute]) -> EnumAttrs {
+    let mut enum_attrs = EnumAttrs::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("simdnbt")) {
+        let new_attr = attr
+            .parse_args::<EnumAttrs>()
+            .expect("invalid simdnbt attr");
+        if new_attr.case_insensitive {
+            enum_attrs.case_insensitive = true;
+        }
+        if let Some(tag) = new_attr.tag {
+            enum_attrs.tag = Some(tag);
+        }
+    }
+
+    enum_attrs
+}
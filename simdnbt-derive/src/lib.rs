@@ -1,8 +1,9 @@
 mod attrs;
 
-use attrs::{parse_field_attrs, parse_unit_attrs};
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use attrs::{parse_enum_attrs, parse_field_attrs, parse_unit_attrs, EnumAttrs};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_macro_input, punctuated::Punctuated, DeriveInput, Token};
 
 #[proc_macro_derive(Deserialize, attributes(simdnbt))]
 pub fn deserialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -10,107 +11,400 @@ pub fn deserialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenSt
 
     let ident = input.ident;
 
-    let mut field_deserializers = Vec::<proc_macro2::TokenStream>::new();
-
-    match input.data {
+    // enums use the default `has_any_field`
+    let mut has_any_field = None;
+    let body = match input.data {
         syn::Data::Struct(syn::DataStruct { fields, .. }) => match fields {
             syn::Fields::Named(syn::FieldsNamed { named, .. }) => {
-                for field in named {
-                    let struct_field_name = field.ident.unwrap();
-
-                    let mut field_attrs = parse_field_attrs(&field.attrs);
-
-                    let field_name = field_attrs
-                        .rename
-                        .take()
-                        .unwrap_or_else(|| struct_field_name.to_string());
-
-                    if field_attrs.flatten {
-                        field_deserializers.push(quote! {
-                            #struct_field_name: simdnbt::Deserialize::from_compound(nbt)?,
-                        })
-                    } else {
-                        let debug_ident = format!("{ident}::{struct_field_name}");
-
-                        field_deserializers.push(quote! {
-                            #struct_field_name: simdnbt::FromNbtTag::from_optional_nbt_tag(
-                                nbt.get(#field_name)
-                            )?.ok_or(simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned()))?
-                        });
+                let struct_attrs = attrs::parse_struct_attrs(&input.attrs);
+
+                let field_checks = named_field_checks(&named);
+                has_any_field = Some(quote! { false #(|| #field_checks)* });
+                let field_deserializers = named_field_deserializers(
+                    &ident.to_string(),
+                    named,
+                    struct_attrs.lenient,
+                    None,
+                );
+
+                let extra_checks = if struct_attrs.deny_unknown_fields {
+                    quote! {
+                        if !nbt.is_empty() {
+                            return Err(simdnbt::DeserializeError::UnknownField(nbt.keys().next().unwrap().to_string()));
+                        }
                     }
+                } else {
+                    quote! {}
+                };
+
+                quote! {
+                    let value = Self {
+                        #(#field_deserializers),*
+                    };
+                    #extra_checks
+                    Ok(value)
+                }
+            }
+            syn::Fields::Unnamed(fields) => {
+                if fields.unnamed.len() != 1 {
+                    panic!("Tuple structs are lists, use #[derive(FromNbtTag)] instead")
                 }
+                let ty = &fields.unnamed[0].ty;
+                has_any_field = Some(quote! { <#ty as simdnbt::Deserialize>::has_any_field(nbt) });
+                quote! { Ok(Self(simdnbt::Deserialize::from_compound(nbt)?)) }
             }
-            syn::Fields::Unnamed(_) => todo!(),
             syn::Fields::Unit => todo!(),
         },
-        syn::Data::Enum(_) => todo!(),
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => {
+            deserialize_enum(&ident, parse_enum_attrs(&input.attrs), variants)
+        }
         syn::Data::Union(_) => todo!(),
-    }
+    };
 
     let generics = input.generics;
     let where_clause = &generics.where_clause;
 
-    let struct_attrs = attrs::parse_struct_attrs(&input.attrs);
-
-    let extra_checks = if struct_attrs.deny_unknown_fields {
+    let has_any_field = has_any_field.map(|has_any_field| {
         quote! {
-            if !nbt.is_empty() {
-                return Err(simdnbt::DeserializeError::UnknownField(nbt.keys().next().unwrap().to_string()));
+            fn has_any_field(nbt: simdnbt::borrow::NbtCompound) -> bool {
+                #has_any_field
             }
         }
-    } else {
-        quote! {}
-    };
+    });
 
     let output = quote! {
         impl #generics simdnbt::Deserialize for #ident #generics #where_clause {
             fn from_compound(mut nbt: simdnbt::borrow::NbtCompound) -> Result<Self, simdnbt::DeserializeError> {
-                let value = Self {
-                    #(#field_deserializers),*
-                };
-                #extra_checks
-                Ok(value)
+                #body
             }
+            #has_any_field
         }
     };
 
     output.into()
 }
 
+/// The `field: value` initializers for reading named fields from a compound
+/// called `nbt`. If `lenient` is true, every field is read like it has
+/// `#[simdnbt(lenient)]`.
+///
+/// Errors are marked as being in the field's key, and then in `parent_key` if
+/// the compound is itself in a key (like the variants of externally tagged
+/// enums).
+fn named_field_deserializers(
+    type_name: &str,
+    fields: Punctuated<syn::Field, Token![,]>,
+    lenient: bool,
+    parent_key: Option<&str>,
+) -> Vec<TokenStream> {
+    let mut field_deserializers = Vec::new();
+
+    for field in fields {
+        let struct_field_name = field.ident.unwrap();
+
+        let mut field_attrs = parse_field_attrs(&field.attrs);
+
+        let field_name = field_attrs
+            .rename
+            .take()
+            .unwrap_or_else(|| struct_field_name.to_string());
+
+        let debug_ident = format!("{type_name}::{struct_field_name}");
+        let aliases = &field_attrs.aliases;
+        let with = field_attrs.with.as_deref().map(parse_with_path);
+        let mismatched = quote! {
+            || simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned())
+        };
+        let in_parent_key = parent_key.map(|key| quote! { .in_key(#key) });
+
+        // each of these is a `Result` with the field's value
+        let value = if field_attrs.flatten {
+            match option_inner_type(&field.ty) {
+                Some(inner) => quote! {
+                    if <#inner as simdnbt::Deserialize>::has_any_field(nbt) {
+                        simdnbt::Deserialize::from_compound(nbt).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                },
+                None => quote! { simdnbt::Deserialize::from_compound(nbt) },
+            }
+        } else if let Some(with) = with {
+            quote! {
+                nbt.get(#field_name)#(.or_else(|| nbt.get(#aliases)))*
+                    .ok_or(simdnbt::DeserializeError::MissingField)
+                    .and_then(|tag| #with::from_nbt_tag(tag).ok_or_else(#mismatched))
+            }
+        } else if lenient || field_attrs.lenient {
+            quote! {
+                match nbt.get(#field_name)#(.or_else(|| nbt.get(#aliases)))* {
+                    Some(tag) => Ok(simdnbt::FromNbtTag::from_nbt_tag_lenient(tag)),
+                    None => simdnbt::FromNbtTag::from_optional_nbt_tag(None),
+                }.and_then(|value| value.ok_or_else(#mismatched))
+            }
+        } else {
+            quote! {
+                simdnbt::FromNbtTag::from_optional_nbt_tag(
+                    nbt.get(#field_name)#(.or_else(|| nbt.get(#aliases)))*
+                ).and_then(|value| value.ok_or_else(#mismatched))
+            }
+        };
+
+        // flattened fields are in the same compound, so they don't add a key
+        let in_key = if field_attrs.flatten {
+            quote! {}
+        } else {
+            quote! { .in_key(#field_name) }
+        };
+        if in_key.is_empty() && in_parent_key.is_none() {
+            field_deserializers.push(quote! { #struct_field_name: #value? });
+        } else {
+            field_deserializers.push(quote! {
+                #struct_field_name: #value.map_err(|err| err #in_key #in_parent_key)?
+            });
+        }
+    }
+
+    field_deserializers
+}
+
+/// The expressions that check whether a compound called `nbt` has any of the
+/// named fields, for `Deserialize::has_any_field`.
+fn named_field_checks(fields: &Punctuated<syn::Field, Token![,]>) -> Vec<TokenStream> {
+    let mut field_checks = Vec::new();
+
+    for field in fields {
+        let field_attrs = parse_field_attrs(&field.attrs);
+
+        if field_attrs.flatten {
+            let ty = option_inner_type(&field.ty).unwrap_or(&field.ty);
+            field_checks.push(quote! { <#ty as simdnbt::Deserialize>::has_any_field(nbt) });
+            continue;
+        }
+
+        let field_name = field_attrs
+            .rename
+            .unwrap_or_else(|| field.ident.as_ref().unwrap().to_string());
+        let names = [&field_name].into_iter().chain(&field_attrs.aliases);
+        field_checks.push(quote! { #(nbt.contains(#names))||* });
+    }
+
+    field_checks
+}
+
+/// Returns `T` if the type is `Option<T>`. Flattened fields with an `Option`
+/// are `None` when none of their fields are in the compound.
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+/// The error for enum variants with more than one unnamed field, which can't
+/// be read or written since there's nothing to name the fields with.
+fn multiple_unnamed_fields_error(fields: &syn::FieldsUnnamed) -> TokenStream {
+    syn::Error::new_spanned(
+        fields,
+        "Enum variants with data must have exactly one unnamed field, use named fields instead",
+    )
+    .to_compile_error()
+}
+
+fn other_variant_error(fields: &syn::FieldsUnnamed) -> TokenStream {
+    syn::Error::new_spanned(
+        fields,
+        "Enum variants with data must be #[simdnbt(other)] and have exactly one unnamed field",
+    )
+    .to_compile_error()
+}
+
+fn parse_with_path(with: &str) -> syn::Path {
+    syn::parse_str(with).expect("#[simdnbt(with)] should be the path to a module")
+}
+
+/// Read an enum with data in its variants.
+///
+/// With `#[simdnbt(tag = "...")]` the enum is internally tagged, so the variant
+/// name is a string in the compound alongside the variant's fields. Otherwise
+/// it's externally tagged, so the compound has the variant name as its only
+/// key.
+fn deserialize_enum(
+    ident: &syn::Ident,
+    enum_attrs: EnumAttrs,
+    variants: Punctuated<syn::Variant, Token![,]>,
+) -> TokenStream {
+    let mut matchers = Vec::<TokenStream>::new();
+    let mut other_matcher = None;
+
+    for variant in variants {
+        let enum_variant_name = variant.ident;
+        let mut unit_attrs = parse_unit_attrs(&variant.attrs);
+        let debug_ident = format!("{ident}::{enum_variant_name}");
+
+        let variant_name = unit_attrs
+            .rename
+            .take()
+            .unwrap_or_else(|| enum_variant_name.to_string());
+
+        let construct = match variant.fields {
+            syn::Fields::Named(syn::FieldsNamed { named, .. }) => {
+                // externally tagged variants are in a compound with the
+                // variant name as the key
+                let parent_key = enum_attrs.tag.is_none().then_some(variant_name.as_str());
+                let field_deserializers =
+                    named_field_deserializers(&debug_ident, named, false, parent_key);
+                if enum_attrs.tag.is_some() {
+                    quote! { Ok(Self::#enum_variant_name { #(#field_deserializers),* }) }
+                } else {
+                    quote! {{
+                        let nbt = value.compound().ok_or_else(|| simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned()))?;
+                        Ok(Self::#enum_variant_name { #(#field_deserializers),* })
+                    }}
+                }
+            }
+            syn::Fields::Unnamed(fields) => {
+                if fields.unnamed.len() != 1 {
+                    return multiple_unnamed_fields_error(&fields);
+                }
+                if enum_attrs.tag.is_some() {
+                    quote! { Ok(Self::#enum_variant_name(simdnbt::Deserialize::from_compound(nbt)?)) }
+                } else {
+                    quote! {
+                        Ok(Self::#enum_variant_name(
+                            simdnbt::FromNbtTag::try_from_nbt_tag(value)
+                                .map_err(|err| err.in_key(#variant_name))?
+                                .ok_or_else(|| simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned()))?
+                        ))
+                    }
+                }
+            }
+            syn::Fields::Unit => {
+                if unit_attrs.other {
+                    if other_matcher.is_some() {
+                        panic!("Only one variant can be #[simdnbt(other)]");
+                    }
+                    other_matcher = Some(quote! {
+                        _ => Ok(Self::#enum_variant_name),
+                    });
+                }
+                quote! { Ok(Self::#enum_variant_name) }
+            }
+        };
+
+        let names = [&variant_name].into_iter().chain(&unit_attrs.aliases);
+        if enum_attrs.case_insensitive {
+            matchers.push(quote! {
+                variant if #(variant.eq_ignore_ascii_case(#names))||* => #construct,
+            });
+        } else {
+            matchers.push(quote! {
+                #(#names)|* => #construct,
+            });
+        }
+    }
+
+    let other_matcher = other_matcher.unwrap_or_else(|| {
+        quote! {
+            variant => Err(simdnbt::DeserializeError::UnknownVariant(variant.to_owned())),
+        }
+    });
+
+    let read_variant = match &enum_attrs.tag {
+        Some(tag) => {
+            let debug_ident = format!("{ident}::{tag}");
+            quote! {
+                let variant = nbt
+                    .get(#tag)
+                    .ok_or(simdnbt::DeserializeError::MissingField)?
+                    .string()
+                    .ok_or_else(|| simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned()))?
+                    .to_str();
+            }
+        }
+        None => {
+            let debug_ident = ident.to_string();
+            quote! {
+                let mut entries = nbt.iter();
+                let (Some((variant, value)), None) = (entries.next(), entries.next()) else {
+                    return Err(simdnbt::DeserializeError::MismatchedFieldType(#debug_ident.to_owned()));
+                };
+                let variant = variant.to_str();
+            }
+        }
+    };
+
+    quote! {
+        #read_variant
+        match variant.as_ref() {
+            #(#matchers)*
+            #other_matcher
+        }
+    }
+}
+
 #[proc_macro_derive(Serialize, attributes(simdnbt))]
 pub fn serialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let ident = input.ident;
 
-    let mut field_serializers = Vec::<proc_macro2::TokenStream>::new();
-
-    match input.data {
+    // enums are written with the default `write_compound`, which goes through
+    // `to_compound`
+    let (body, write_body) = match input.data {
         syn::Data::Struct(syn::DataStruct { fields, .. }) => match fields {
             syn::Fields::Named(syn::FieldsNamed { named, .. }) => {
-                for field in named {
-                    let struct_field_name = field.ident.unwrap();
-
-                    let mut field_attrs = parse_field_attrs(&field.attrs);
-
-                    let field_name = field_attrs
-                        .rename
-                        .take()
-                        .unwrap_or_else(|| struct_field_name.to_string());
-
-                    field_serializers.push(quote! {
-                        if let Some(item) = simdnbt::ToNbtTag::to_optional_nbt_tag(self.#struct_field_name) {
-                            nbt.insert(#field_name, item);
-                        }
-                    });
+                let field_writers = named_field_writers(named.clone());
+                let field_serializers =
+                    named_field_serializers(named, |field| quote! { self.#field });
+                (
+                    quote! {
+                        let mut nbt = simdnbt::owned::NbtCompound::new();
+                        #(#field_serializers)*
+                        nbt
+                    },
+                    Some(quote! {
+                        #(#field_writers)*
+                        // TAG_End
+                        data.push(0);
+                    }),
+                )
+            }
+            syn::Fields::Unnamed(fields) => {
+                if fields.unnamed.len() != 1 {
+                    panic!("Tuple structs are lists, use #[derive(ToNbtTag)] instead")
                 }
+                (
+                    quote! { simdnbt::Serialize::to_compound(self.0) },
+                    Some(quote! { simdnbt::Serialize::write_compound(self.0, data) }),
+                )
             }
-            syn::Fields::Unnamed(_) => todo!(),
             syn::Fields::Unit => todo!(),
         },
-        syn::Data::Enum(_) => todo!(),
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => (
+            serialize_enum(parse_enum_attrs(&input.attrs), variants),
+            None,
+        ),
         syn::Data::Union(_) => todo!(),
-    }
+    };
+
+    let write_compound = write_body.map(|write_body| {
+        quote! {
+            fn write_compound(self, data: &mut Vec<u8>) {
+                #write_body
+            }
+        }
+    });
 
     let generics = input.generics;
     let where_clause = &generics.where_clause;
@@ -118,44 +412,290 @@ pub fn serialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
     let output = quote! {
         impl #generics simdnbt::Serialize for #ident #generics #where_clause {
             fn to_compound(self) -> simdnbt::owned::NbtCompound {
-                let mut nbt = simdnbt::owned::NbtCompound::new();
-                #(#field_serializers)*
-                nbt
+                #body
             }
+            #write_compound
         }
     };
 
     output.into()
 }
 
+/// The statements that insert named fields into a compound called `nbt`.
+/// `access` is called with the name of each field to get its value.
+fn named_field_serializers(
+    fields: Punctuated<syn::Field, Token![,]>,
+    access: impl Fn(&syn::Ident) -> TokenStream,
+) -> Vec<TokenStream> {
+    let mut field_serializers = Vec::new();
+
+    for field in fields {
+        let struct_field_name = field.ident.unwrap();
+
+        let mut field_attrs = parse_field_attrs(&field.attrs);
+
+        let field_name = field_attrs
+            .rename
+            .take()
+            .unwrap_or_else(|| struct_field_name.to_string());
+
+        let value = access(&struct_field_name);
+        if field_attrs.flatten {
+            // the fields are inserted into the same compound
+            if option_inner_type(&field.ty).is_some() {
+                field_serializers.push(quote! {
+                    if let Some(value) = #value {
+                        nbt.extend(simdnbt::Serialize::to_compound(value));
+                    }
+                });
+            } else {
+                field_serializers.push(quote! {
+                    nbt.extend(simdnbt::Serialize::to_compound(#value));
+                });
+            }
+        } else if let Some(with) = field_attrs.with.as_deref().map(parse_with_path) {
+            field_serializers.push(quote! {
+                nbt.insert(#field_name, #with::to_nbt_tag(#value));
+            });
+        } else {
+            field_serializers.push(quote! {
+                if let Some(item) = simdnbt::ToNbtTag::to_optional_nbt_tag(#value) {
+                    nbt.insert(#field_name, item);
+                }
+            });
+        }
+    }
+
+    field_serializers
+}
+
+/// The statements that write the named fields of `self` straight to a buffer
+/// called `data`, in the same format as [`named_field_serializers`].
+fn named_field_writers(fields: Punctuated<syn::Field, Token![,]>) -> Vec<TokenStream> {
+    let mut field_writers = Vec::new();
+
+    for field in fields {
+        let struct_field_name = field.ident.unwrap();
+
+        let mut field_attrs = parse_field_attrs(&field.attrs);
+
+        let field_name = field_attrs
+            .rename
+            .take()
+            .unwrap_or_else(|| struct_field_name.to_string());
+
+        if field_attrs.flatten {
+            // the fields are written into the same compound, so the end tag
+            // that `write_compound` adds is removed
+            let write = quote! {
+                simdnbt::Serialize::write_compound(value, data);
+                data.pop();
+            };
+            if option_inner_type(&field.ty).is_some() {
+                field_writers.push(quote! {
+                    if let Some(value) = self.#struct_field_name {
+                        #write
+                    }
+                });
+            } else {
+                field_writers.push(quote! {{
+                    let value = self.#struct_field_name;
+                    #write
+                }});
+            }
+            continue;
+        }
+
+        let value = if let Some(with) = field_attrs.with.as_deref().map(parse_with_path) {
+            quote! { #with::to_nbt_tag(self.#struct_field_name) }
+        } else {
+            quote! { self.#struct_field_name }
+        };
+        field_writers.push(quote! {
+            simdnbt::ToNbtTag::write_named_nbt_tag(#value, #field_name, data);
+        });
+    }
+
+    field_writers
+}
+
+/// Write an enum with data in its variants, in the same format that
+/// [`deserialize_enum`] reads.
+fn serialize_enum(
+    enum_attrs: EnumAttrs,
+    variants: Punctuated<syn::Variant, Token![,]>,
+) -> TokenStream {
+    let mut matchers = Vec::<TokenStream>::new();
+
+    for variant in variants {
+        let enum_variant_name = variant.ident;
+        let mut unit_attrs = parse_unit_attrs(&variant.attrs);
+
+        let variant_name = unit_attrs
+            .rename
+            .take()
+            .unwrap_or_else(|| enum_variant_name.to_string());
+
+        // the fields are bound with a prefix so they can't shadow `nbt`
+        let (pattern, contents) = match variant.fields {
+            syn::Fields::Named(syn::FieldsNamed { named, .. }) => {
+                let bindings = named
+                    .iter()
+                    .map(|field| {
+                        let field = field.ident.as_ref().unwrap();
+                        let binding = format_ident!("field_{field}");
+                        quote! { #field: #binding }
+                    })
+                    .collect::<Vec<_>>();
+                let field_serializers = named_field_serializers(named, |field| {
+                    let binding = format_ident!("field_{field}");
+                    quote! { #binding }
+                });
+                (
+                    quote! { Self::#enum_variant_name { #(#bindings),* } },
+                    quote! {{
+                        let mut nbt = simdnbt::owned::NbtCompound::new();
+                        #(#field_serializers)*
+                        nbt
+                    }},
+                )
+            }
+            syn::Fields::Unnamed(fields) => {
+                if fields.unnamed.len() != 1 {
+                    return multiple_unnamed_fields_error(&fields);
+                }
+                let contents = if enum_attrs.tag.is_some() {
+                    quote! { simdnbt::Serialize::to_compound(value) }
+                } else {
+                    quote! { simdnbt::ToNbtTag::to_nbt_tag(value) }
+                };
+                (quote! { Self::#enum_variant_name(value) }, contents)
+            }
+            syn::Fields::Unit => (
+                quote! { Self::#enum_variant_name },
+                quote! { simdnbt::owned::NbtCompound::new() },
+            ),
+        };
+
+        matchers.push(match &enum_attrs.tag {
+            Some(tag) => quote! {
+                #pattern => {
+                    let mut nbt = simdnbt::owned::NbtCompound::new();
+                    nbt.insert(#tag, #variant_name);
+                    nbt.extend(#contents);
+                    nbt
+                }
+            },
+            None => quote! {
+                #pattern => {
+                    let mut nbt = simdnbt::owned::NbtCompound::new();
+                    nbt.insert(#variant_name, #contents);
+                    nbt
+                }
+            },
+        });
+    }
+
+    quote! {
+        match self {
+            #(#matchers)*
+        }
+    }
+}
+
 #[proc_macro_derive(FromNbtTag, attributes(simdnbt))]
 pub fn from_nbt_tag_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let ident = input.ident;
 
-    let mut matchers = Vec::<proc_macro2::TokenStream>::new();
+    let mut matchers = Vec::<TokenStream>::new();
+    let mut other_matcher = None;
 
     match input.data {
-        syn::Data::Struct(_) => panic!("Use #[derive(Deserialize)] instead"),
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+            let syn::Fields::Unnamed(fields) = fields else {
+                panic!("Use #[derive(Deserialize)] instead")
+            };
+            let struct_attrs = attrs::parse_struct_attrs(&input.attrs);
+            let field_count = fields.unnamed.len();
+
+            let body = if struct_attrs.transparent {
+                if field_count != 1 {
+                    panic!("#[simdnbt(transparent)] structs must have exactly one field");
+                }
+                quote! { simdnbt::FromNbtTag::from_nbt_tag(tag).map(Self) }
+            } else {
+                let field_deserializers = (0..field_count).map(|_| {
+                    quote! { simdnbt::FromNbtTag::from_nbt_tag(items.next()?)? }
+                });
+                quote! {
+                    let list = tag.list()?;
+                    let mut items = list.iter();
+                    let value = Self(#(#field_deserializers),*);
+                    if items.next().is_some() {
+                        return None;
+                    }
+                    Some(value)
+                }
+            };
+
+            let generics = input.generics;
+            let where_clause = &generics.where_clause;
+
+            return quote! {
+                impl #generics simdnbt::FromNbtTag for #ident #generics #where_clause {
+                    fn from_nbt_tag(tag: simdnbt::borrow::NbtTag) -> Option<Self> {
+                        #body
+                    }
+                }
+            }
+            .into();
+        }
         syn::Data::Enum(syn::DataEnum { variants, .. }) => {
+            let enum_attrs = parse_enum_attrs(&input.attrs);
             for variant in variants {
+                let enum_variant_name = variant.ident;
+                let mut unit_attrs = parse_unit_attrs(&variant.attrs);
+
                 match variant.fields {
                     syn::Fields::Named(_) => todo!(),
-                    syn::Fields::Unnamed(_) => todo!(),
+                    syn::Fields::Unnamed(fields) => {
+                        if !unit_attrs.other || fields.unnamed.len() != 1 {
+                            return other_variant_error(&fields).into();
+                        }
+                        if other_matcher.is_some() {
+                            panic!("Only one variant can be #[simdnbt(other)]");
+                        }
+                        other_matcher = Some(quote! {
+                            value => Some(Self::#enum_variant_name(value.into())),
+                        });
+                    }
                     syn::Fields::Unit => {
-                        let enum_variant_name = variant.ident;
-
-                        let mut unit_attrs = parse_unit_attrs(&variant.attrs);
+                        if unit_attrs.other {
+                            if other_matcher.is_some() {
+                                panic!("Only one variant can be #[simdnbt(other)]");
+                            }
+                            other_matcher = Some(quote! {
+                                _ => Some(Self::#enum_variant_name),
+                            });
+                        }
 
                         let variant_name = unit_attrs
                             .rename
                             .take()
                             .unwrap_or_else(|| enum_variant_name.to_string());
 
-                        matchers.push(quote! {
-                            #variant_name => Some(Self::#enum_variant_name),
-                        });
+                        let names = [&variant_name].into_iter().chain(&unit_attrs.aliases);
+                        if enum_attrs.case_insensitive {
+                            matchers.push(quote! {
+                                value if #(value.eq_ignore_ascii_case(#names))||* => Some(Self::#enum_variant_name),
+                            });
+                        } else {
+                            matchers.push(quote! {
+                                #(#names)|* => Some(Self::#enum_variant_name),
+                            });
+                        }
                     }
                 }
             }
@@ -163,6 +703,8 @@ pub fn from_nbt_tag_derive(input: proc_macro::TokenStream) -> proc_macro::TokenS
         syn::Data::Union(_) => todo!(),
     }
 
+    let other_matcher = other_matcher.unwrap_or_else(|| quote! { _ => None, });
+
     let generics = input.generics;
     let where_clause = &generics.where_clause;
 
@@ -171,7 +713,7 @@ pub fn from_nbt_tag_derive(input: proc_macro::TokenStream) -> proc_macro::TokenS
             fn from_nbt_tag(tag: simdnbt::borrow::NbtTag) -> Option<Self> {
                 match tag.string()?.to_str().as_ref() {
                     #(#matchers)*
-                    _ => None,
+                    #other_matcher
                 }
             }
         }
@@ -186,15 +728,76 @@ pub fn to_nbt_tag_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 
     let ident = input.ident;
 
-    let mut field_matchers = Vec::<proc_macro2::TokenStream>::new();
+    let mut field_matchers = Vec::<TokenStream>::new();
 
     match input.data {
-        syn::Data::Struct(_) => panic!("Use #[derive(Serialize)] instead"),
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+            let syn::Fields::Unnamed(fields) = fields else {
+                panic!("Use #[derive(Serialize)] instead")
+            };
+            let struct_attrs = attrs::parse_struct_attrs(&input.attrs);
+            let field_count = fields.unnamed.len();
+
+            let body = if struct_attrs.transparent {
+                if field_count != 1 {
+                    panic!("#[simdnbt(transparent)] structs must have exactly one field");
+                }
+                quote! { simdnbt::ToNbtTag::to_nbt_tag(self.0) }
+            } else {
+                // lists can only have one type of element, so the fields have
+                // to be the same type
+                let first_ty = fields.unnamed[0].ty.to_token_stream().to_string();
+                if let Some(field) = fields
+                    .unnamed
+                    .iter()
+                    .find(|field| field.ty.to_token_stream().to_string() != first_ty)
+                {
+                    return syn::Error::new_spanned(
+                        &field.ty,
+                        "All of the fields in a tuple struct must be the same type, since it's written as a list",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                let indexes = (0..field_count).map(syn::Index::from);
+                quote! {
+                    simdnbt::owned::NbtTag::List(
+                        simdnbt::owned::NbtList::from_tags(vec![
+                            #(simdnbt::ToNbtTag::to_nbt_tag(self.#indexes)),*
+                        ])
+                        .expect("All of the elements in a list must be the same type of tag")
+                    )
+                }
+            };
+
+            let generics = input.generics;
+            let where_clause = &generics.where_clause;
+
+            return quote! {
+                impl #generics simdnbt::ToNbtTag for #ident #generics #where_clause {
+                    fn to_nbt_tag(self) -> simdnbt::owned::NbtTag {
+                        #body
+                    }
+                }
+            }
+            .into();
+        }
         syn::Data::Enum(syn::DataEnum { variants, .. }) => {
             for variant in variants {
                 match variant.fields {
                     syn::Fields::Named(_) => todo!(),
-                    syn::Fields::Unnamed(_) => todo!(),
+                    syn::Fields::Unnamed(fields) => {
+                        let enum_variant_name = variant.ident;
+
+                        let unit_attrs = parse_unit_attrs(&variant.attrs);
+                        if !unit_attrs.other || fields.unnamed.len() != 1 {
+                            return other_variant_error(&fields).into();
+                        }
+
+                        field_matchers.push(quote! {
+                            Self::#enum_variant_name(value) => simdnbt::owned::NbtTag::String(value.into()),
+                        });
+                    }
                     syn::Fields::Unit => {
                         let enum_variant_name = variant.ident;
 
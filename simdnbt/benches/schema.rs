@@ -0,0 +1,123 @@
+use std::{
+    fs::File,
+    io::{Cursor, Read},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use flate2::read::GzDecoder;
+use simdnbt::{
+    owned::{BaseNbt, NbtCompound},
+    packed::PackedBits,
+    schema::{Biomes, BlockState, BlockStates, Chunk, Heightmaps, LevelDat, Section},
+    Deserialize, Serialize,
+};
+
+/// A chunk with every section filled, so it's about as big as a real one.
+fn chunk() -> Chunk {
+    let palette = (0..12)
+        .map(|i| BlockState {
+            name: format!("minecraft:block_{i}"),
+            properties: (i % 3 == 0).then(|| {
+                [("facing".to_owned(), "north".to_owned())]
+                    .into_iter()
+                    .collect()
+            }),
+        })
+        .collect::<Vec<_>>();
+    let indices = (0..4096).map(|i| (i * 7 % 12) as u16).collect::<Vec<_>>();
+    let heightmap = Some(vec![0x0102_0304_0506_0708; 37]);
+    Chunk {
+        data_version: 3953,
+        x_pos: 0,
+        y_pos: Some(-4),
+        z_pos: 0,
+        status: "minecraft:full".to_owned(),
+        last_update: 100,
+        inhabited_time: 0,
+        sections: (-4..20)
+            .map(|y| Section {
+                y,
+                block_states: Some(BlockStates {
+                    palette: palette.clone(),
+                    data: PackedBits::pack(&indices, 4),
+                }),
+                biomes: Some(Biomes {
+                    palette: vec!["minecraft:plains".to_owned()],
+                    data: None,
+                }),
+                block_light: Some(vec![0; 2048]),
+                sky_light: Some(vec![0xff; 2048]),
+            })
+            .collect(),
+        block_entities: vec![NbtCompound::from_values(vec![(
+            "id".into(),
+            "minecraft:chest".into(),
+        )])],
+        heightmaps: Heightmaps {
+            motion_blocking: heightmap.clone(),
+            motion_blocking_no_leaves: heightmap.clone(),
+            ocean_floor: heightmap.clone(),
+            world_surface: heightmap,
+        },
+    }
+}
+
+fn bench_chunk(c: &mut Criterion) {
+    let chunk = chunk();
+    let input = BaseNbt::new("", chunk.clone().to_compound()).to_bytes();
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&input[..]))
+        .unwrap()
+        .unwrap();
+
+    let mut group = c.benchmark_group("schema/chunk");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("Deserialize", |b| {
+        b.iter(|| black_box(Chunk::from_nbt(black_box(&nbt)).unwrap()))
+    });
+    group.bench_function("Serialize", |b| {
+        b.iter(|| {
+            let mut data = Vec::with_capacity(input.len());
+            black_box(chunk.clone()).write_nbt(&mut data);
+            black_box(data);
+        })
+    });
+    group.bench_function("Unpack block states", |b| {
+        b.iter(|| {
+            for section in &chunk.sections {
+                black_box(section.block_states.as_ref().unwrap().indices());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_level_dat(c: &mut Criterion) {
+    let mut file = File::open("tests/level.dat").unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    let mut input = Vec::new();
+    GzDecoder::new(&contents[..])
+        .read_to_end(&mut input)
+        .unwrap();
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&input[..]))
+        .unwrap()
+        .unwrap();
+
+    let mut group = c.benchmark_group("schema/level.dat");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("Deserialize", |b| {
+        b.iter(|| black_box(LevelDat::from_nbt(black_box(&nbt)).unwrap()))
+    });
+    group.finish();
+}
+
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+fn bench(c: &mut Criterion) {
+    bench_chunk(c);
+    bench_level_dat(c);
+}
+
+criterion_group!(schema, bench);
+criterion_main!(schema);
@@ -0,0 +1,92 @@
+//! The parts of the allocator API that the tapes use.
+//!
+//! With the `nightly` feature these are the ones from [`std::alloc`], so tapes
+//! can be made with any allocator. Otherwise they're a copy of the API that
+//! only supports the global allocator, so the tapes work on stable Rust too.
+
+// `AllocError` is only used by the tests
+#[cfg(feature = "nightly")]
+#[allow(unused_imports)]
+pub use std::alloc::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "nightly"))]
+#[allow(unused_imports)]
+pub use self::stable::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "nightly"))]
+mod stable {
+    use std::{
+        alloc::{self, Layout},
+        ptr::{self, NonNull},
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AllocError;
+
+    /// A copy of the parts of [`std::alloc::Allocator`] that we use.
+    ///
+    /// # Safety
+    ///
+    /// See [`std::alloc::Allocator`].
+    pub unsafe trait Allocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+        /// # Safety
+        ///
+        /// The pointer must have been allocated by this allocator with the
+        /// given layout.
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+        /// # Safety
+        ///
+        /// The pointer must have been allocated by this allocator with
+        /// `old_layout`, and `new_layout` must have the same alignment and be
+        /// at least as big.
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError>;
+    }
+
+    /// The global memory allocator.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Global;
+
+    unsafe impl Allocator for Global {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            if layout.size() == 0 {
+                let dangling = ptr::without_provenance_mut(layout.align());
+                return Ok(NonNull::slice_from_raw_parts(
+                    unsafe { NonNull::new_unchecked(dangling) },
+                    0,
+                ));
+            }
+            let ptr = NonNull::new(unsafe { alloc::alloc(layout) }).ok_or(AllocError)?;
+            Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+            }
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            debug_assert_eq!(old_layout.align(), new_layout.align());
+            debug_assert!(new_layout.size() >= old_layout.size());
+            if old_layout.size() == 0 {
+                return self.allocate(new_layout);
+            }
+            let new_ptr = unsafe { alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) };
+            let new_ptr = NonNull::new(new_ptr).ok_or(AllocError)?;
+            Ok(NonNull::slice_from_raw_parts(new_ptr, new_layout.size()))
+        }
+    }
+}
@@ -0,0 +1,635 @@
+//! Owned NBT that's stored in a custom [`Allocator`], like a bump arena.
+//!
+//! Converting borrowed NBT with
+//! [`to_owned`](crate::borrow::NbtCompound::to_owned) allocates a separate
+//! `Vec` for every compound, list, array, and string. If you're converting a
+//! lot of data that's thrown away all at once, like the chunks that are sent to
+//! a player, it's usually faster to put all of those allocations in an arena
+//! with [`to_owned_in`](crate::borrow::NbtCompound::to_owned_in) and then reset
+//! the arena when you're done.
+//!
+//! The types in this module work like the ones in [`owned`](crate::owned),
+//! except that strings are stored as raw MUTF-8 bytes. Use
+//! [`NbtCompound::to_owned`] to move them out of the allocator.
+//!
+//! Any [`Allocator`] works, like a `&bumpalo::Bump` with bumpalo's
+//! `allocator_api` feature. This example uses the global allocator:
+//!
+//! ```
+//! #![feature(allocator_api)]
+//! # use std::io::Cursor;
+//! # let data = simdnbt::owned::BaseNbt::new("", simdnbt::compound! {
+//! #     "DataVersion": 3953,
+//! #     "sections": [{ "Y": -4i8 }],
+//! # })
+//! # .to_bytes();
+//! use std::alloc::Global;
+//!
+//! let nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+//! let chunk = nbt.as_compound().to_owned_in(Global);
+//! assert_eq!(chunk.int("DataVersion"), Some(3953));
+//! assert_eq!(chunk.to_owned(), nbt.as_compound().to_owned());
+//! ```
+
+use std::alloc::Allocator;
+
+use crate::{
+    common::{
+        slice_i8_into_u8, write_big_endian_with_u32_length, write_string, write_u32,
+        write_with_u32_length, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID,
+        INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    owned, EmptyLists, Mutf8Str, WriteOptions,
+};
+
+/// A complete NBT container in an allocator. This contains a name and a
+/// compound tag.
+#[derive(Debug, Clone)]
+pub struct BaseNbt<A: Allocator> {
+    name: Vec<u8, A>,
+    tag: NbtCompound<A>,
+}
+
+impl<A: Allocator> BaseNbt<A> {
+    pub fn new(name: Vec<u8, A>, tag: NbtCompound<A>) -> Self {
+        Self { name, tag }
+    }
+
+    /// Get the name of the NBT compound. This is often an empty string.
+    pub fn name(&self) -> &Mutf8Str {
+        Mutf8Str::from_slice(&self.name)
+    }
+
+    pub fn as_compound(&self) -> &NbtCompound<A> {
+        &self.tag
+    }
+
+    pub fn as_compound_mut(&mut self) -> &mut NbtCompound<A> {
+        &mut self.tag
+    }
+
+    pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        data.push(COMPOUND_ID);
+        write_string(data, self.name());
+        self.tag.write_with_opts(data, opts);
+    }
+
+    /// Copy the NBT out of the allocator into normal owned NBT.
+    pub fn to_owned(&self) -> owned::BaseNbt {
+        owned::BaseNbt::new(self.name(), self.tag.to_owned())
+    }
+}
+
+/// A list of named tags, stored in an allocator.
+#[derive(Debug, Clone)]
+pub struct NbtCompound<A: Allocator> {
+    values: Vec<(Vec<u8, A>, NbtTag<A>), A>,
+}
+
+impl<A: Allocator> NbtCompound<A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            values: Vec::new_in(alloc),
+        }
+    }
+
+    pub fn from_values(values: Vec<(Vec<u8, A>, NbtTag<A>), A>) -> Self {
+        Self { values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NbtTag<A>> {
+        let name = Mutf8Str::from_str(name);
+        self.values
+            .iter()
+            .find(|(k, _)| k.as_slice() == name.as_bytes())
+            .map(|(_, v)| v)
+    }
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut NbtTag<A>> {
+        let name = Mutf8Str::from_str(name);
+        self.values
+            .iter_mut()
+            .find(|(k, _)| k.as_slice() == name.as_bytes())
+            .map(|(_, v)| v)
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Add a tag to the end of the compound. The name is the MUTF-8 bytes of
+    /// the key. This doesn't check whether the key already exists.
+    pub fn insert(&mut self, name: Vec<u8, A>, tag: NbtTag<A>) {
+        self.values.push((name, tag));
+    }
+    /// Remove the first tag with the name and return it.
+    pub fn remove(&mut self, name: &str) -> Option<NbtTag<A>> {
+        let name = Mutf8Str::from_str(name);
+        let index = self
+            .values
+            .iter()
+            .position(|(k, _)| k.as_slice() == name.as_bytes())?;
+        Some(self.values.remove(index).1)
+    }
+
+    pub fn byte(&self, name: &str) -> Option<i8> {
+        self.get(name).and_then(|tag| tag.byte())
+    }
+    pub fn short(&self, name: &str) -> Option<i16> {
+        self.get(name).and_then(|tag| tag.short())
+    }
+    pub fn int(&self, name: &str) -> Option<i32> {
+        self.get(name).and_then(|tag| tag.int())
+    }
+    pub fn long(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(|tag| tag.long())
+    }
+    pub fn float(&self, name: &str) -> Option<f32> {
+        self.get(name).and_then(|tag| tag.float())
+    }
+    pub fn double(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|tag| tag.double())
+    }
+    pub fn byte_array(&self, name: &str) -> Option<&[u8]> {
+        self.get(name).and_then(|tag| tag.byte_array())
+    }
+    pub fn string(&self, name: &str) -> Option<&Mutf8Str> {
+        self.get(name).and_then(|tag| tag.string())
+    }
+    pub fn list(&self, name: &str) -> Option<&NbtList<A>> {
+        self.get(name).and_then(|tag| tag.list())
+    }
+    pub fn compound(&self, name: &str) -> Option<&NbtCompound<A>> {
+        self.get(name).and_then(|tag| tag.compound())
+    }
+    pub fn int_array(&self, name: &str) -> Option<&[i32]> {
+        self.get(name).and_then(|tag| tag.int_array())
+    }
+    pub fn long_array(&self, name: &str) -> Option<&[i64]> {
+        self.get(name).and_then(|tag| tag.long_array())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Mutf8Str, &NbtTag<A>)> {
+        self.values
+            .iter()
+            .map(|(k, v)| (Mutf8Str::from_slice(k), v))
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Mutf8Str, &mut NbtTag<A>)> {
+        self.values
+            .iter_mut()
+            .map(|(k, v)| (Mutf8Str::from_slice(k), v))
+    }
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the compound like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        for (name, tag) in self.iter() {
+            data.push(tag.id());
+            write_string(data, name);
+            tag.write_without_tag_type(data, opts);
+        }
+        data.push(END_ID);
+    }
+
+    /// Copy the compound out of the allocator into a normal owned compound.
+    pub fn to_owned(&self) -> owned::NbtCompound {
+        owned::NbtCompound::from_values(
+            self.iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+        )
+    }
+}
+
+/// A single NBT tag, stored in an allocator.
+#[repr(u8)]
+#[derive(Debug, Clone)]
+pub enum NbtTag<A: Allocator> {
+    Byte(i8) = BYTE_ID,
+    Short(i16) = SHORT_ID,
+    Int(i32) = INT_ID,
+    Long(i64) = LONG_ID,
+    Float(f32) = FLOAT_ID,
+    Double(f64) = DOUBLE_ID,
+    ByteArray(Vec<u8, A>) = BYTE_ARRAY_ID,
+    /// The MUTF-8 bytes of the string.
+    String(Vec<u8, A>) = STRING_ID,
+    List(NbtList<A>) = LIST_ID,
+    Compound(NbtCompound<A>) = COMPOUND_ID,
+    IntArray(Vec<i32, A>) = INT_ARRAY_ID,
+    LongArray(Vec<i64, A>) = LONG_ARRAY_ID,
+}
+
+impl<A: Allocator> NbtTag<A> {
+    /// Get the numerical ID of the tag type.
+    #[inline]
+    pub fn id(&self) -> u8 {
+        // SAFETY: Because `Self` is marked `repr(u8)`, its layout is a `repr(C)`
+        // `union` between `repr(C)` structs, each of which has the `u8`
+        // discriminant as its first field, so we can read the discriminant
+        // without offsetting the pointer.
+        unsafe { *<*const _>::from(self).cast::<u8>() }
+    }
+
+    pub fn byte(&self) -> Option<i8> {
+        match self {
+            NbtTag::Byte(byte) => Some(*byte),
+            _ => None,
+        }
+    }
+    pub fn short(&self) -> Option<i16> {
+        match self {
+            NbtTag::Short(short) => Some(*short),
+            _ => None,
+        }
+    }
+    pub fn int(&self) -> Option<i32> {
+        match self {
+            NbtTag::Int(int) => Some(*int),
+            _ => None,
+        }
+    }
+    pub fn long(&self) -> Option<i64> {
+        match self {
+            NbtTag::Long(long) => Some(*long),
+            _ => None,
+        }
+    }
+    pub fn float(&self) -> Option<f32> {
+        match self {
+            NbtTag::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+    pub fn double(&self) -> Option<f64> {
+        match self {
+            NbtTag::Double(double) => Some(*double),
+            _ => None,
+        }
+    }
+    pub fn byte_array(&self) -> Option<&[u8]> {
+        match self {
+            NbtTag::ByteArray(byte_array) => Some(byte_array),
+            _ => None,
+        }
+    }
+    pub fn string(&self) -> Option<&Mutf8Str> {
+        match self {
+            NbtTag::String(string) => Some(Mutf8Str::from_slice(string)),
+            _ => None,
+        }
+    }
+    pub fn list(&self) -> Option<&NbtList<A>> {
+        match self {
+            NbtTag::List(list) => Some(list),
+            _ => None,
+        }
+    }
+    pub fn list_mut(&mut self) -> Option<&mut NbtList<A>> {
+        match self {
+            NbtTag::List(list) => Some(list),
+            _ => None,
+        }
+    }
+    pub fn compound(&self) -> Option<&NbtCompound<A>> {
+        match self {
+            NbtTag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+    pub fn compound_mut(&mut self) -> Option<&mut NbtCompound<A>> {
+        match self {
+            NbtTag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+    pub fn int_array(&self) -> Option<&[i32]> {
+        match self {
+            NbtTag::IntArray(int_array) => Some(int_array),
+            _ => None,
+        }
+    }
+    pub fn long_array(&self) -> Option<&[i64]> {
+        match self {
+            NbtTag::LongArray(long_array) => Some(long_array),
+            _ => None,
+        }
+    }
+
+    fn write_without_tag_type(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        match self {
+            NbtTag::Byte(byte) => data.push(*byte as u8),
+            NbtTag::Short(short) => data.extend_from_slice(&short.to_be_bytes()),
+            NbtTag::Int(int) => data.extend_from_slice(&int.to_be_bytes()),
+            NbtTag::Long(long) => data.extend_from_slice(&long.to_be_bytes()),
+            NbtTag::Float(float) => data.extend_from_slice(&float.to_be_bytes()),
+            NbtTag::Double(double) => data.extend_from_slice(&double.to_be_bytes()),
+            NbtTag::ByteArray(byte_array) => write_with_u32_length(data, 1, byte_array),
+            NbtTag::String(string) => write_string(data, Mutf8Str::from_slice(string)),
+            NbtTag::List(list) => list.write_with_opts(data, opts),
+            NbtTag::Compound(compound) => compound.write_with_opts(data, opts),
+            NbtTag::IntArray(int_array) => write_big_endian_with_u32_length(data, int_array),
+            NbtTag::LongArray(long_array) => write_big_endian_with_u32_length(data, long_array),
+        }
+    }
+
+    /// Copy the tag out of the allocator into a normal owned tag.
+    pub fn to_owned(&self) -> owned::NbtTag {
+        match self {
+            NbtTag::Byte(byte) => owned::NbtTag::Byte(*byte),
+            NbtTag::Short(short) => owned::NbtTag::Short(*short),
+            NbtTag::Int(int) => owned::NbtTag::Int(*int),
+            NbtTag::Long(long) => owned::NbtTag::Long(*long),
+            NbtTag::Float(float) => owned::NbtTag::Float(*float),
+            NbtTag::Double(double) => owned::NbtTag::Double(*double),
+            NbtTag::ByteArray(byte_array) => owned::NbtTag::ByteArray(byte_array.to_vec()),
+            NbtTag::String(string) => {
+                owned::NbtTag::String(Mutf8Str::from_slice(string).to_owned())
+            }
+            NbtTag::List(list) => owned::NbtTag::List(list.to_owned()),
+            NbtTag::Compound(compound) => owned::NbtTag::Compound(compound.to_owned()),
+            NbtTag::IntArray(int_array) => owned::NbtTag::IntArray(int_array.to_vec()),
+            NbtTag::LongArray(long_array) => owned::NbtTag::LongArray(long_array.to_vec()),
+        }
+    }
+}
+
+/// A list of NBT tags of a single type, stored in an allocator.
+#[repr(u8)]
+#[derive(Debug, Clone)]
+pub enum NbtList<A: Allocator> {
+    Empty = END_ID,
+    Byte(Vec<i8, A>) = BYTE_ID,
+    Short(Vec<i16, A>) = SHORT_ID,
+    Int(Vec<i32, A>) = INT_ID,
+    Long(Vec<i64, A>) = LONG_ID,
+    Float(Vec<f32, A>) = FLOAT_ID,
+    Double(Vec<f64, A>) = DOUBLE_ID,
+    ByteArray(Vec<Vec<u8, A>, A>) = BYTE_ARRAY_ID,
+    /// The MUTF-8 bytes of the strings.
+    String(Vec<Vec<u8, A>, A>) = STRING_ID,
+    List(Vec<NbtList<A>, A>) = LIST_ID,
+    Compound(Vec<NbtCompound<A>, A>) = COMPOUND_ID,
+    IntArray(Vec<Vec<i32, A>, A>) = INT_ARRAY_ID,
+    LongArray(Vec<Vec<i64, A>, A>) = LONG_ARRAY_ID,
+}
+
+impl<A: Allocator> NbtList<A> {
+    /// Get the numerical ID of the tag type.
+    #[inline]
+    pub fn id(&self) -> u8 {
+        // SAFETY: See `NbtTag::id`.
+        unsafe { *<*const _>::from(self).cast::<u8>() }
+    }
+
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        match self {
+            NbtList::Empty => 0,
+            NbtList::Byte(list) => list.len(),
+            NbtList::Short(list) => list.len(),
+            NbtList::Int(list) => list.len(),
+            NbtList::Long(list) => list.len(),
+            NbtList::Float(list) => list.len(),
+            NbtList::Double(list) => list.len(),
+            NbtList::ByteArray(list) => list.len(),
+            NbtList::String(list) => list.len(),
+            NbtList::List(list) => list.len(),
+            NbtList::Compound(list) => list.len(),
+            NbtList::IntArray(list) => list.len(),
+            NbtList::LongArray(list) => list.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn lists(&self) -> Option<&[NbtList<A>]> {
+        match self {
+            NbtList::List(lists) => Some(lists),
+            _ => None,
+        }
+    }
+    pub fn compounds(&self) -> Option<&[NbtCompound<A>]> {
+        match self {
+            NbtList::Compound(compounds) => Some(compounds),
+            _ => None,
+        }
+    }
+    pub fn compounds_mut(&mut self) -> Option<&mut [NbtCompound<A>]> {
+        match self {
+            NbtList::Compound(compounds) => Some(compounds),
+            _ => None,
+        }
+    }
+
+    pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the list like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        if opts.empty_lists == EmptyLists::End && self.is_empty() {
+            data.push(END_ID);
+            write_u32(data, 0);
+            return;
+        }
+
+        data.push(self.id());
+        match self {
+            NbtList::Empty => write_u32(data, 0),
+            NbtList::Byte(bytes) => write_with_u32_length(data, 1, slice_i8_into_u8(bytes)),
+            NbtList::Short(shorts) => write_big_endian_with_u32_length(data, shorts),
+            NbtList::Int(ints) => write_big_endian_with_u32_length(data, ints),
+            NbtList::Long(longs) => write_big_endian_with_u32_length(data, longs),
+            NbtList::Float(floats) => write_big_endian_with_u32_length(data, floats),
+            NbtList::Double(doubles) => write_big_endian_with_u32_length(data, doubles),
+            NbtList::ByteArray(byte_arrays) => {
+                write_u32(data, byte_arrays.len() as u32);
+                for array in byte_arrays {
+                    write_with_u32_length(data, 1, array);
+                }
+            }
+            NbtList::String(strings) => {
+                write_u32(data, strings.len() as u32);
+                for string in strings {
+                    write_string(data, Mutf8Str::from_slice(string));
+                }
+            }
+            NbtList::List(lists) => {
+                write_u32(data, lists.len() as u32);
+                for list in lists {
+                    list.write_with_opts(data, opts);
+                }
+            }
+            NbtList::Compound(compounds) => {
+                write_u32(data, compounds.len() as u32);
+                for compound in compounds {
+                    compound.write_with_opts(data, opts);
+                }
+            }
+            NbtList::IntArray(int_arrays) => {
+                write_u32(data, int_arrays.len() as u32);
+                for array in int_arrays {
+                    write_big_endian_with_u32_length(data, array);
+                }
+            }
+            NbtList::LongArray(long_arrays) => {
+                write_u32(data, long_arrays.len() as u32);
+                for array in long_arrays {
+                    write_big_endian_with_u32_length(data, array);
+                }
+            }
+        }
+    }
+
+    /// Copy the list out of the allocator into a normal owned list.
+    pub fn to_owned(&self) -> owned::NbtList {
+        match self {
+            NbtList::Empty => owned::NbtList::Empty,
+            NbtList::Byte(bytes) => owned::NbtList::Byte(bytes.to_vec()),
+            NbtList::Short(shorts) => owned::NbtList::Short(shorts.to_vec()),
+            NbtList::Int(ints) => owned::NbtList::Int(ints.to_vec()),
+            NbtList::Long(longs) => owned::NbtList::Long(longs.to_vec()),
+            NbtList::Float(floats) => owned::NbtList::Float(floats.to_vec()),
+            NbtList::Double(doubles) => owned::NbtList::Double(doubles.to_vec()),
+            NbtList::ByteArray(byte_arrays) => {
+                owned::NbtList::ByteArray(byte_arrays.iter().map(|a| a.to_vec()).collect())
+            }
+            NbtList::String(strings) => owned::NbtList::String(
+                strings
+                    .iter()
+                    .map(|s| Mutf8Str::from_slice(s).to_owned())
+                    .collect(),
+            ),
+            NbtList::List(lists) => {
+                owned::NbtList::List(lists.iter().map(|l| l.to_owned()).collect())
+            }
+            NbtList::Compound(compounds) => {
+                owned::NbtList::Compound(compounds.iter().map(|c| c.to_owned()).collect())
+            }
+            NbtList::IntArray(int_arrays) => {
+                owned::NbtList::IntArray(int_arrays.iter().map(|a| a.to_vec()).collect())
+            }
+            NbtList::LongArray(long_arrays) => {
+                owned::NbtList::LongArray(long_arrays.iter().map(|a| a.to_vec()).collect())
+            }
+        }
+    }
+}
+
+/// Copy the slice into a `Vec` in the allocator.
+pub(crate) fn copy_in<T: Copy, A: Allocator>(slice: &[T], alloc: A) -> Vec<T, A> {
+    let mut vec = Vec::with_capacity_in(slice.len(), alloc);
+    vec.extend_from_slice(slice);
+    vec
+}
+
+/// Collect the items into a `Vec` in the allocator.
+pub(crate) fn collect_in<T, A: Allocator>(items: impl Iterator<Item = T>, alloc: A) -> Vec<T, A> {
+    let mut vec = Vec::with_capacity_in(items.size_hint().0, alloc);
+    vec.extend(items);
+    vec
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{AllocError, Allocator, Global, Layout},
+        cell::Cell,
+        io::Cursor,
+        ptr::NonNull,
+    };
+
+    use crate::{borrow, test_fixtures::complex_player, EmptyLists, WriteOptions};
+
+    /// An allocator that counts how many bytes were allocated with it.
+    #[derive(Default)]
+    struct Counting {
+        allocated: Cell<usize>,
+    }
+    unsafe impl Allocator for Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.allocated.set(self.allocated.get() + layout.size());
+            Global.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn to_owned_in() {
+        let src = complex_player();
+        let nbt = borrow::read(&mut Cursor::new(&src)).unwrap().unwrap();
+
+        let alloc = Counting::default();
+        let arena = nbt.to_owned_in(&alloc);
+        assert_eq!(arena.to_owned(), nbt);
+        let mut data = Vec::new();
+        arena.write(&mut data);
+        assert_eq!(data, nbt.to_bytes());
+        assert!(alloc.allocated.get() > 0);
+
+        let list = nbt.list("Inventory").unwrap();
+        assert_eq!(list.to_owned_in(&alloc).to_owned(), list.to_owned());
+        let tag = nbt.get("Pos").unwrap();
+        assert_eq!(tag.to_owned_in(&alloc).to_owned(), tag.to_owned());
+
+        let opts = WriteOptions {
+            empty_lists: EmptyLists::End,
+        };
+        let mut arena_data = Vec::new();
+        arena.write_with_opts(&mut arena_data, &opts);
+        let mut owned_data = Vec::new();
+        nbt.write_with_opts(&mut owned_data, &opts);
+        assert_eq!(arena_data, owned_data);
+    }
+
+    #[test]
+    fn edit_in_arena() {
+        let src = crate::owned::BaseNbt::new(
+            "",
+            crate::compound! { "a": 1, "b": { "c": "d" }, "e": [[I; 1, 2]] },
+        )
+        .to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&src)).unwrap().unwrap();
+
+        let alloc = Counting::default();
+        let mut compound = nbt.as_compound().to_owned_in(&alloc);
+        assert_eq!(compound.int("a"), Some(1));
+        assert_eq!(
+            compound
+                .compound("b")
+                .unwrap()
+                .string("c")
+                .unwrap()
+                .to_str(),
+            "d"
+        );
+        assert_eq!(compound.list("e").unwrap().len(), 1);
+
+        assert!(compound.remove("a").is_some());
+        compound.insert(
+            super::copy_in(b"f", &alloc),
+            super::NbtTag::LongArray(super::copy_in(&[3], &alloc)),
+        );
+        assert_eq!(compound.long_array("f"), Some(&[3][..]));
+        assert_eq!(
+            compound.to_owned(),
+            crate::compound! { "b": { "c": "d" }, "e": [[I; 1, 2]], "f": [L; 3] }
+        );
+    }
+}
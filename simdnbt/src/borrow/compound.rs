@@ -1,24 +1,35 @@
-use std::mem::MaybeUninit;
+#[cfg(feature = "nightly")]
+use std::{alloc::Allocator, ops::Range, simd::prelude::*};
+use std::{io, mem::MaybeUninit};
 
 use super::{
+    edit::NbtCompoundEdit,
     extra_tapes::ExtraTapes,
-    list::{self, NbtList},
+    key_index,
+    list::{self, NbtCompoundList, NbtList, NbtListList},
+    profile::{self, KeySize},
     tape::{TapeElement, TapeTagKind, UnalignedU16},
     NbtTag, Tapes,
 };
+#[cfg(feature = "nightly")]
+use crate::arena;
 use crate::{
     common::{
-        extend_unchecked, push_unchecked, read_int_array, read_long_array, read_string,
-        read_with_u32_length, write_string, write_string_unchecked, BYTE_ARRAY_ID, BYTE_ID,
-        COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID,
-        LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
+        canonical_f32, canonical_f64, extend_unchecked, push_unchecked, read_int_array,
+        read_long_array, read_string, read_with_u32_length, write_string, write_string_unchecked,
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
+        LIST_ID, LONG_ARRAY_ID, LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
     },
     error::NonRootError,
+    io_write::ChunkedWriter,
+    options::{DuplicateKeyPolicy, Limits, WriteOptions},
+    packed::PackedBits,
+    raw_list::RawList,
     reader::Reader,
-    Mutf8Str,
+    Deserialize, DeserializeError, FromNbtTag, Mutf8Str,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone, Copy)]
 pub struct NbtCompound<'a: 'tape, 'tape> {
     pub(crate) element: *const TapeElement, // includes the initial compound element
     pub(crate) extra_tapes: &'tape ExtraTapes<'a>,
@@ -36,28 +47,76 @@ impl<'a: 'tape, 'tape> NbtCompound<'a, 'tape> {
         stack.push(ParsingStackElement::compound(
             index_of_compound_element as u32,
         ))?;
-        tapes.main.push(TapeElement::new_with_approx_len_and_offset(
-            TapeTagKind::Compound,
-            // these get overwritten later
-            0,
-            0,
-        ));
+        tapes
+            .main
+            .push(TapeElement::new_with_approx_len_and_offset(
+                TapeTagKind::Compound,
+                // these get overwritten later
+                0,
+                0,
+            ))?;
 
         Ok(())
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
-        for (name, tag) in self.iter() {
-            // reserve 4 bytes extra so we can avoid reallocating for small tags
-            data.reserve(1 + 2 + name.len() + 4);
-            // SAFETY: We just reserved enough space for the tag ID, the name length, the
-            // name, and 4 bytes of tag data.
-            unsafe {
-                push_unchecked(data, tag.id());
-                write_string_unchecked(data, name);
-            }
+        self.write_with(data, &WriteOptions::default(), false);
+    }
+
+    /// Write the compound to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// Write the compound like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        self.write_with(data, opts, false);
+    }
 
-            write_tag(tag, data);
+    /// Write the compound to an [`io::Write`] like [`Self::write`].
+    ///
+    /// The data is written in chunks as it's serialized, so the whole
+    /// compound never has to be in memory at once. The writer doesn't need to
+    /// be buffered.
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the compound like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        let mut writer = ChunkedWriter::new(out, opts);
+        writer.borrowed_compound(self)?;
+        writer.finish()
+    }
+
+    /// Write the compound with its keys sorted by their bytes, including the
+    /// keys of every compound inside of it. This is done directly from the
+    /// tape, and the output only depends on the contents of the compound
+    /// (rather than the order it was originally written in), which makes it
+    /// useful for hashing and diffing.
+    ///
+    /// Every NaN float and double is also written as the same NaN (the one
+    /// that Java uses), so data that's equal always has the same bytes.
+    ///
+    /// Duplicate keys are kept in their original order.
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        self.write_with(data, &WriteOptions::default(), true);
+    }
+
+    pub(crate) fn write_with(&self, data: &mut Vec<u8>, opts: &WriteOptions, canonical: bool) {
+        if canonical {
+            let mut entries = self.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| name.as_bytes());
+            for (name, tag) in entries {
+                write_entry(name, tag, data, opts, true);
+            }
+        } else {
+            for (name, tag) in self.iter() {
+                write_entry(name, tag, data, opts, false);
+            }
         }
         data.push(END_ID);
     }
@@ -66,29 +125,39 @@ impl<'a: 'tape, 'tape> NbtCompound<'a, 'tape> {
     pub fn get(&self, name: &str) -> Option<NbtTag<'a, 'tape>> {
         let name = Mutf8Str::from_str(name);
         let name = name.as_ref();
-        for (key, value) in self.iter() {
-            if key == name {
-                return Some(value);
-            }
+        let mut iter = self.iter();
+        if let Some(keys) = self.indexed_keys() {
+            iter.current_tape_offset = key_index::find(keys, name)?;
+            return iter.next().map(|(_, value)| value);
         }
-        None
+        iter.find_key(name)
     }
 
     /// Returns whether there is a tag with the given name.
     pub fn contains(&self, name: &str) -> bool {
         let name = Mutf8Str::from_str(name);
         let name = name.as_ref();
-        for key in self.keys() {
-            if key == name {
-                return true;
-            }
+        if let Some(keys) = self.indexed_keys() {
+            return key_index::find(keys, name).is_some();
         }
-        false
+        self.iter().find_key(name).is_some()
+    }
+
+    /// The sorted keys of this compound, if
+    /// [`BaseNbt::build_key_index`](super::BaseNbt::build_key_index) was
+    /// called and the compound is big enough to be indexed.
+    fn indexed_keys(&self) -> Option<&'tape [(&'a Mutf8Str, u32)]> {
+        self.extra_tapes.key_index.as_ref()?.keys(self.element)
     }
 
     pub fn byte(&self, name: &str) -> Option<i8> {
         self.get(name).and_then(|tag| tag.byte())
     }
+    /// Get a byte tag as a `u8`, for values like light levels that are
+    /// meant to be unsigned.
+    pub fn ubyte(&self, name: &str) -> Option<u8> {
+        self.get(name).and_then(|tag| tag.ubyte())
+    }
     pub fn short(&self, name: &str) -> Option<i16> {
         self.get(name).and_then(|tag| tag.short())
     }
@@ -122,6 +191,158 @@ impl<'a: 'tape, 'tape> NbtCompound<'a, 'tape> {
     pub fn long_array(&self, name: &str) -> Option<Vec<i64>> {
         self.get(name).and_then(|tag| tag.long_array())
     }
+    pub fn int_array_raw(&self, name: &str) -> Option<RawList<'a, i32>> {
+        self.get(name).and_then(|tag| tag.int_array_raw())
+    }
+    pub fn long_array_raw(&self, name: &str) -> Option<RawList<'a, i64>> {
+        self.get(name).and_then(|tag| tag.long_array_raw())
+    }
+    /// Returns a view of the numbers that are bit-packed into the long array,
+    /// without copying it. See [`PackedBits`].
+    ///
+    /// Returns `None` if there's no long array with the name or
+    /// `bits_per_entry` is out of range.
+    pub fn long_array_packed(&self, name: &str, bits_per_entry: u32) -> Option<PackedBits<'a>> {
+        self.long_array_raw(name)
+            .and_then(|longs| PackedBits::from_raw(longs, bits_per_entry))
+    }
+
+    pub fn byte_list(&self, name: &str) -> Option<&'a [i8]> {
+        self.list(name).and_then(|list| list.bytes())
+    }
+    pub fn short_list(&self, name: &str) -> Option<Vec<i16>> {
+        self.list(name).and_then(|list| list.shorts())
+    }
+    pub fn int_list(&self, name: &str) -> Option<Vec<i32>> {
+        self.list(name).and_then(|list| list.ints())
+    }
+    pub fn long_list(&self, name: &str) -> Option<Vec<i64>> {
+        self.list(name).and_then(|list| list.longs())
+    }
+    pub fn float_list(&self, name: &str) -> Option<Vec<f32>> {
+        self.list(name).and_then(|list| list.floats())
+    }
+    pub fn double_list(&self, name: &str) -> Option<Vec<f64>> {
+        self.list(name).and_then(|list| list.doubles())
+    }
+    pub fn byte_array_list(&self, name: &str) -> Option<&'a [&'a [u8]]> {
+        self.list(name).and_then(|list| list.byte_arrays())
+    }
+    pub fn string_list(&self, name: &str) -> Option<&'a [&'a Mutf8Str]> {
+        self.list(name).and_then(|list| list.strings())
+    }
+    pub fn list_list(&self, name: &str) -> Option<NbtListList<'a, 'tape>> {
+        self.list(name).and_then(|list| list.lists())
+    }
+    pub fn compound_list(&self, name: &str) -> Option<NbtCompoundList<'a, 'tape>> {
+        self.list(name).and_then(|list| list.compounds())
+    }
+    pub fn int_array_list(&self, name: &str) -> Option<&'tape [RawList<'a, i32>]> {
+        self.list(name).and_then(|list| list.int_arrays())
+    }
+    pub fn long_array_list(&self, name: &str) -> Option<&'tape [RawList<'a, i64>]> {
+        self.list(name).and_then(|list| list.long_arrays())
+    }
+
+    /// Deserialize this compound into `T`.
+    ///
+    /// This is the same as calling [`Deserialize::from_compound`], and it's
+    /// useful for turning part of a document into a typed struct after it's
+    /// already been read.
+    pub fn deserialize<T: Deserialize>(&self) -> Result<T, DeserializeError> {
+        T::from_compound(*self)
+    }
+
+    /// Deserialize the compound with the given name into `T`.
+    ///
+    /// Unlike going through [`FromNbtTag`](crate::FromNbtTag), this keeps the
+    /// error from deserializing the inner compound. Errors are
+    /// [`DeserializeError::At`] the name.
+    pub fn compound_as<T: Deserialize>(&self, name: &str) -> Result<T, DeserializeError> {
+        let tag = self
+            .get(name)
+            .ok_or_else(|| DeserializeError::MissingField.in_key(name))?;
+        let compound = tag
+            .compound()
+            .ok_or_else(|| DeserializeError::MismatchedFieldType(name.to_owned()).in_key(name))?;
+        T::from_compound(compound).map_err(|err| err.in_key(name))
+    }
+
+    /// Get the tag with the given name and convert it with [`FromNbtTag`].
+    ///
+    /// Returns `None` if there's no tag with the name or it can't be converted
+    /// into `T`. Use [`Self::compound_as`] if you need to know why a compound
+    /// couldn't be deserialized.
+    pub fn get_as<T: FromNbtTag>(&self, name: &str) -> Option<T> {
+        self.get(name).and_then(T::from_nbt_tag)
+    }
+
+    /// Get a byte without checking the tag's type, for data that's already
+    /// been validated. This still returns `None` if there's no tag with the
+    /// name.
+    ///
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a byte.
+    pub unsafe fn byte_unchecked(&self, name: &str) -> Option<i8> {
+        self.get(name).map(|tag| unsafe { tag.byte_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a short.
+    pub unsafe fn short_unchecked(&self, name: &str) -> Option<i16> {
+        self.get(name).map(|tag| unsafe { tag.short_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be an int.
+    pub unsafe fn int_unchecked(&self, name: &str) -> Option<i32> {
+        self.get(name).map(|tag| unsafe { tag.int_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a long.
+    pub unsafe fn long_unchecked(&self, name: &str) -> Option<i64> {
+        self.get(name).map(|tag| unsafe { tag.long_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a float.
+    pub unsafe fn float_unchecked(&self, name: &str) -> Option<f32> {
+        self.get(name).map(|tag| unsafe { tag.float_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a double.
+    pub unsafe fn double_unchecked(&self, name: &str) -> Option<f64> {
+        self.get(name).map(|tag| unsafe { tag.double_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a byte array.
+    pub unsafe fn byte_array_unchecked(&self, name: &str) -> Option<&'a [u8]> {
+        self.get(name)
+            .map(|tag| unsafe { tag.byte_array_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a string.
+    pub unsafe fn string_unchecked(&self, name: &str) -> Option<&'a Mutf8Str> {
+        self.get(name).map(|tag| unsafe { tag.string_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a list.
+    pub unsafe fn list_unchecked(&self, name: &str) -> Option<NbtList<'a, 'tape>> {
+        self.get(name).map(|tag| unsafe { tag.list_unchecked() })
+    }
+    /// # Safety
+    ///
+    /// If the tag exists, it must be a compound.
+    pub unsafe fn compound_unchecked(&self, name: &str) -> Option<NbtCompound<'a, 'tape>> {
+        self.get(name)
+            .map(|tag| unsafe { tag.compound_unchecked() })
+    }
 
     /// Get the tape element kind and value for this compound.
     fn element(&self) -> TapeElement {
@@ -179,13 +400,42 @@ impl<'a: 'tape, 'tape> NbtCompound<'a, 'tape> {
         self.iter().map(|(k, _)| k)
     }
 
+    /// Returns the serialized size of every key in this compound, sorted from
+    /// largest to smallest.
+    ///
+    /// `max_depth` is how many levels of nested compounds (and lists of
+    /// compounds) should be reported, so `1` only reports the keys that are
+    /// directly in this compound.
+    pub fn key_sizes(&self, max_depth: usize) -> Vec<KeySize> {
+        profile::key_sizes(*self, max_depth)
+    }
+
+    /// Start editing the compound without converting it to an owned compound.
+    /// See [`NbtCompoundEdit`].
+    pub fn edit(&self) -> NbtCompoundEdit<'a, 'tape> {
+        NbtCompoundEdit::new(*self)
+    }
+
     pub fn to_owned(&self) -> crate::owned::NbtCompound {
-        crate::owned::NbtCompound {
-            values: self
-                .iter()
+        crate::owned::NbtCompound::from_values(
+            self.iter()
                 .map(|(k, v)| ((*k).to_owned(), v.to_owned()))
                 .collect(),
+        )
+    }
+
+    /// Copy the compound into an allocator, like an arena. See
+    /// [`crate::arena`].
+    #[cfg(feature = "nightly")]
+    pub fn to_owned_in<A: Allocator + Clone>(&self, alloc: A) -> arena::NbtCompound<A> {
+        let mut values = Vec::with_capacity_in(self.len(), alloc.clone());
+        for (name, tag) in self.iter() {
+            values.push((
+                arena::copy_in(name.as_bytes(), alloc.clone()),
+                tag.to_owned_in(alloc.clone()),
+            ));
         }
+        arena::NbtCompound::from_values(values)
     }
 }
 
@@ -196,7 +446,7 @@ impl PartialEq for NbtCompound<'_, '_> {
 }
 
 pub struct NbtCompoundIter<'a: 'tape, 'tape> {
-    current_tape_offset: usize,
+    pub(crate) current_tape_offset: usize,
     max_tape_offset: usize,
     tape: &'tape [TapeElement],
     extra_tapes: &'tape ExtraTapes<'a>,
@@ -220,16 +470,135 @@ impl<'a: 'tape, 'tape> Iterator for NbtCompoundIter<'a, 'tape> {
         self.current_tape_offset += 1;
 
         let element = unsafe { self.tape.as_ptr().add(self.current_tape_offset) };
-        let tag = NbtTag {
-            element,
-            extra_tapes: self.extra_tapes,
-        };
+        let tag = NbtTag::from_tape(element, self.extra_tapes, unsafe {
+            name_ptr.add(name_length as usize)
+        });
 
         self.current_tape_offset += unsafe { (*element).skip_offset() };
 
         Some((name, tag))
     }
 }
+impl<'a: 'tape, 'tape> NbtCompoundIter<'a, 'tape> {
+    /// Skip to the tag with the given name and return it.
+    ///
+    /// This is faster than calling `next` until the name matches, since it
+    /// doesn't make a tag for every entry that it skips, and it compares the
+    /// names with SIMD.
+    fn find_key(&mut self, name: &Mutf8Str) -> Option<NbtTag<'a, 'tape>> {
+        // names in nbt can't be this long, and the needle stores the length
+        // as a u16
+        if name.len() > u16::MAX as usize {
+            return None;
+        }
+        let needle = KeyNeedle::new(name, self.extra_tapes.input);
+        while self.current_tape_offset + 1 < self.max_tape_offset {
+            let name_length_ptr = self.tape[self.current_tape_offset].u64() as *const u8;
+            if unsafe { needle.matches(name_length_ptr) } {
+                return self.next().map(|(_, tag)| tag);
+            }
+            let element = unsafe { self.tape.as_ptr().add(self.current_tape_offset + 1) };
+            self.current_tape_offset += 1 + unsafe { (*element).skip_offset() };
+        }
+        None
+    }
+}
+
+/// A key that's being looked up in a compound, encoded the same way as the
+/// names in the original data (with a big-endian `u16` length before it) so
+/// the length and the start of the name can be compared at once.
+#[cfg(feature = "nightly")]
+struct KeyNeedle<'n> {
+    /// The first 16 bytes of the encoded key, padded with zeroes.
+    head: u8x16,
+    /// Which bytes in `head` are actually part of the encoded key.
+    head_mask: Mask<i8, 16>,
+    /// The rest of the name, if it didn't fit in `head`.
+    tail: &'n [u8],
+    /// The encoded key, for when `head` can't be used.
+    name: &'n Mutf8Str,
+    /// Names that start in this range of addresses have at least 16 bytes of
+    /// input after them, so it's safe to load 16 bytes at once.
+    simd_range: Range<usize>,
+}
+#[cfg(feature = "nightly")]
+impl<'n> KeyNeedle<'n> {
+    fn new(name: &'n Mutf8Str, input: &[u8]) -> Self {
+        let bytes = name.as_bytes();
+        let mut head = [0; 16];
+        head[..2].copy_from_slice(&(bytes.len() as u16).to_be_bytes());
+        let head_len = (bytes.len() + 2).min(16);
+        head[2..head_len].copy_from_slice(&bytes[..head_len - 2]);
+        let head_mask = u8x16::from_array(std::array::from_fn(|i| i as u8))
+            .simd_lt(u8x16::splat(head_len as u8));
+
+        let start = input.as_ptr() as usize;
+        let simd_range = start..(start + input.len()).saturating_sub(15).max(start);
+
+        Self {
+            head: u8x16::from_array(head),
+            head_mask,
+            tail: &bytes[head_len - 2..],
+            name,
+            simd_range,
+        }
+    }
+
+    /// Returns whether the length-prefixed name at the pointer is this key.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be to the length of a name in the input.
+    #[inline]
+    unsafe fn matches(&self, name_length_ptr: *const u8) -> bool {
+        if !self.simd_range.contains(&(name_length_ptr as usize)) {
+            return unsafe { name_at(name_length_ptr) == self.name.as_bytes() };
+        }
+
+        let chunk =
+            u8x16::from_array(unsafe { (name_length_ptr as *const [u8; 16]).read_unaligned() });
+        if !(chunk.simd_eq(self.head) | !self.head_mask).all() {
+            return false;
+        }
+        // the lengths are equal, so the rest of the name must be in the input
+        self.tail.is_empty()
+            || unsafe { std::slice::from_raw_parts(name_length_ptr.add(16), self.tail.len()) }
+                == self.tail
+    }
+}
+
+/// Without SIMD, every name is compared with the key directly.
+#[cfg(not(feature = "nightly"))]
+struct KeyNeedle<'n> {
+    name: &'n Mutf8Str,
+}
+#[cfg(not(feature = "nightly"))]
+impl<'n> KeyNeedle<'n> {
+    fn new(name: &'n Mutf8Str, _input: &[u8]) -> Self {
+        Self { name }
+    }
+
+    /// Returns whether the length-prefixed name at the pointer is this key.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be to the length of a name in the input.
+    #[inline]
+    unsafe fn matches(&self, name_length_ptr: *const u8) -> bool {
+        unsafe { name_at(name_length_ptr) == self.name.as_bytes() }
+    }
+}
+
+/// Get the name that comes after the length at the pointer.
+///
+/// # Safety
+///
+/// The pointer must be to the length of a name in the input.
+#[inline]
+unsafe fn name_at<'a>(name_length_ptr: *const u8) -> &'a [u8] {
+    let name_length = u16::from_be_bytes(unsafe { *(name_length_ptr as *const [u8; 2]) });
+    unsafe { std::slice::from_raw_parts(name_length_ptr.add(2), name_length as usize) }
+}
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct ParsingStackElement {
@@ -268,23 +637,56 @@ pub struct ParsingStack {
     stack: [MaybeUninit<ParsingStackElement>; MAX_DEPTH],
     remaining_elements_in_lists: [u32; MAX_DEPTH],
     depth: usize,
+    max_stack_depth: usize,
+    /// What to do with duplicate keys when a compound ends.
+    pub(crate) duplicate_keys: DuplicateKeyPolicy,
 }
 
 impl ParsingStack {
     pub fn new() -> Self {
+        Self::with_max_depth(MAX_DEPTH)
+    }
+
+    /// Make a stack that returns an error when there are more than
+    /// `max_depth` compounds and lists inside of the root compound.
+    pub fn with_max_depth(max_depth: usize) -> Self {
         Self {
             stack: unsafe { MaybeUninit::uninit().assume_init() },
             remaining_elements_in_lists: [0; MAX_DEPTH],
             depth: 0,
+            // the root compound is one element, and we have to be able to
+            // push one more before finding out that it's too deep
+            max_stack_depth: max_depth.saturating_add(2).min(MAX_DEPTH),
+            duplicate_keys: DuplicateKeyPolicy::KeepAll,
         }
     }
 
+    /// Make a stack that follows the depth limit and duplicate key policy in
+    /// the given limits.
+    pub(crate) fn with_limits(limits: &Limits) -> Self {
+        let mut stack = Self::with_max_depth(limits.max_depth);
+        stack.duplicate_keys = limits.duplicate_keys;
+        stack
+    }
+
     #[inline]
     pub fn push(&mut self, state: ParsingStackElement) -> Result<(), NonRootError> {
         unsafe { self.stack.get_unchecked_mut(self.depth).write(state) };
         self.depth += 1;
 
-        if self.depth >= MAX_DEPTH {
+        if self.depth >= self.max_stack_depth {
+            Err(NonRootError::max_depth_exceeded())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return an error if a list at the current position would be too deep.
+    /// Lists of lists and compounds are checked when they're pushed, but
+    /// other lists are never pushed to the stack.
+    #[inline]
+    pub fn check_list_depth(&self) -> Result<(), NonRootError> {
+        if self.depth + 1 >= self.max_stack_depth {
             Err(NonRootError::max_depth_exceeded())
         } else {
             Ok(())
@@ -408,7 +810,7 @@ pub(crate) fn read_tag<'a>(
         _ => return Err(NonRootError::unknown_tag_id(tag_type)),
     };
 
-    tapes.main.push(pushing_element);
+    tapes.main.push(pushing_element)?;
     Ok(())
 }
 
@@ -420,8 +822,7 @@ pub(crate) fn read_tag_in_compound<'a>(
 ) -> Result<(), NonRootError> {
     let tag_type = data.read_u8()?;
     if tag_type == END_ID {
-        handle_compound_end(tapes, stack);
-        return Ok(());
+        return handle_compound_end(tapes, stack);
     }
 
     let tag_name_ptr = data.cur;
@@ -447,14 +848,21 @@ pub(crate) fn read_tag_in_compound<'a>(
 
     // finished reading the tag name
 
-    tapes.main.push(TapeElement::new(tag_name_ptr as u64));
+    tapes.main.push(TapeElement::new(tag_name_ptr as u64))?;
 
     read_tag(data, tapes, stack, tag_type)
 }
 
 #[inline(always)]
-fn handle_compound_end(tapes: &mut Tapes, stack: &mut ParsingStack) {
+fn handle_compound_end(tapes: &mut Tapes, stack: &mut ParsingStack) -> Result<(), NonRootError> {
     let index_of_compound_element = stack.pop().index;
+    if stack.duplicate_keys != DuplicateKeyPolicy::KeepAll {
+        remove_duplicate_keys(
+            tapes,
+            index_of_compound_element as usize,
+            stack.duplicate_keys,
+        )?;
+    }
     let index_after_end_element = tapes.main.len();
 
     unsafe {
@@ -465,9 +873,79 @@ fn handle_compound_end(tapes: &mut Tapes, stack: &mut ParsingStack) {
             // is too expensive
             .set_offset(index_after_end_element as u32 - index_of_compound_element);
     };
+    Ok(())
 }
 
-pub(crate) fn write_tag(tag: NbtTag, data: &mut Vec<u8>) {
+/// Remove the entries that the policy doesn't keep from the compound that
+/// starts at the given index, which must be the last thing on the tape.
+///
+/// The offsets in the tape are relative, so the entries after a removed one
+/// can be moved back without changing them.
+#[cold]
+fn remove_duplicate_keys(
+    tapes: &mut Tapes,
+    index_of_compound_element: usize,
+    policy: DuplicateKeyPolicy,
+) -> Result<(), NonRootError> {
+    let tape = tapes.main.as_ptr();
+    let end = tapes.main.len();
+
+    // the index of the name element and the length of every entry
+    let mut entries = Vec::new();
+    let mut names = Vec::new();
+    let mut index = index_of_compound_element + 1;
+    while index < end {
+        let name_length_ptr = unsafe { (*tape.add(index)).u64() } as *const UnalignedU16;
+        let name_length = u16::from_be(u16::from(unsafe { *name_length_ptr }));
+        let name_ptr = unsafe { name_length_ptr.add(1) as *const u8 };
+        names.push(unsafe { std::slice::from_raw_parts(name_ptr, name_length as usize) });
+
+        let len = 1 + unsafe { (*tape.add(index + 1)).skip_offset() };
+        entries.push((index, len));
+        index += len;
+    }
+
+    let Some(keep) = policy.entries_to_keep(&names)? else {
+        return Ok(());
+    };
+
+    let mut new_end = index_of_compound_element + 1;
+    for ((index, len), keep) in entries.into_iter().zip(keep) {
+        if !keep {
+            continue;
+        }
+        if index != new_end {
+            unsafe {
+                let tape = tapes.main.get_unchecked_mut(0) as *mut TapeElement;
+                std::ptr::copy(tape.add(index), tape.add(new_end), len);
+            }
+        }
+        new_end += len;
+    }
+    tapes.main.truncate(new_end);
+    Ok(())
+}
+
+pub(crate) fn write_entry(
+    name: &Mutf8Str,
+    tag: NbtTag,
+    data: &mut Vec<u8>,
+    opts: &WriteOptions,
+    canonical: bool,
+) {
+    // reserve 4 bytes extra so we can avoid reallocating for small tags
+    data.reserve(1 + 2 + name.len() + 4);
+    // SAFETY: We just reserved enough space for the tag ID, the name length, the
+    // name, and 4 bytes of tag data.
+    unsafe {
+        push_unchecked(data, tag.id());
+        write_string_unchecked(data, name);
+    }
+
+    write_tag(tag, data, opts, canonical);
+}
+
+pub(crate) fn write_tag(tag: NbtTag, data: &mut Vec<u8>, opts: &WriteOptions, canonical: bool) {
     let el = tag.element();
     match el.kind() {
         TapeTagKind::Byte => unsafe {
@@ -482,11 +960,19 @@ pub(crate) fn write_tag(tag: NbtTag, data: &mut Vec<u8>) {
         TapeTagKind::Long => {
             data.extend_from_slice(&tag.long().unwrap().to_be_bytes());
         }
-        TapeTagKind::Float => unsafe {
-            extend_unchecked(data, &tag.float().unwrap().to_be_bytes());
-        },
+        TapeTagKind::Float => {
+            let mut float = tag.float().unwrap();
+            if canonical {
+                float = canonical_f32(float);
+            }
+            unsafe { extend_unchecked(data, &float.to_be_bytes()) };
+        }
         TapeTagKind::Double => {
-            data.extend_from_slice(&tag.double().unwrap().to_be_bytes());
+            let mut double = tag.double().unwrap();
+            if canonical {
+                double = canonical_f64(double);
+            }
+            data.extend_from_slice(&double.to_be_bytes());
         }
         TapeTagKind::ByteArray => {
             let byte_array = tag.byte_array().unwrap();
@@ -500,10 +986,10 @@ pub(crate) fn write_tag(tag: NbtTag, data: &mut Vec<u8>) {
             write_string(data, string);
         }
         kind if kind.is_list() => {
-            tag.list().unwrap().write(data);
+            tag.list().unwrap().write_with(data, opts, canonical);
         }
         TapeTagKind::Compound => {
-            tag.compound().unwrap().write(data);
+            tag.compound().unwrap().write_with(data, opts, canonical);
         }
         TapeTagKind::IntArray => {
             let int_array =
@@ -0,0 +1,326 @@
+use std::ops::{Deref, DerefMut};
+
+use super::{compound::write_entry, BaseNbt, NbtCompound, NbtTag};
+use crate::{
+    common::{push_unchecked, write_string, write_string_unchecked, COMPOUND_ID, END_ID},
+    mutf8::Mutf8String,
+    owned, Mutf8Str, ToNbtTag, WriteOptions,
+};
+
+/// A copy-on-write view of a borrowed compound that keeps track of changes.
+///
+/// Tags that weren't changed are written directly from the tape, so editing a
+/// single field of a large compound is much cheaper than converting the whole
+/// thing with [`NbtCompound::to_owned`]. Get one with [`NbtCompound::edit`] or
+/// [`BaseNbt::edit`].
+///
+/// If the compound has duplicate keys, changes apply to the first one and the
+/// rest are removed.
+///
+/// ```
+/// # use std::io::Cursor;
+/// # let mut compound = simdnbt::owned::NbtCompound::new();
+/// # compound.insert("DataVersion", 3953);
+/// # compound.insert("Level", simdnbt::owned::NbtCompound::new());
+/// # let data = simdnbt::owned::BaseNbt::new("", compound).to_bytes();
+/// let nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+///
+/// let mut edit = nbt.edit();
+/// edit.insert("DataVersion", 3955);
+/// edit.compound_mut("Level").unwrap().insert("Status", "full");
+///
+/// let data = edit.to_bytes();
+/// let nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+/// assert_eq!(nbt.int("DataVersion"), Some(3955));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NbtCompoundEdit<'a, 'tape> {
+    base: NbtCompound<'a, 'tape>,
+    changes: Vec<(Mutf8String, Change<'a, 'tape>)>,
+}
+
+#[derive(Debug, Clone)]
+enum Change<'a, 'tape> {
+    Insert(owned::NbtTag),
+    Remove,
+    Edit(NbtCompoundEdit<'a, 'tape>),
+}
+
+/// An entry in an edited compound.
+enum Entry<'a, 'tape, 'edit> {
+    Unchanged(NbtTag<'a, 'tape>),
+    Inserted(&'edit owned::NbtTag),
+    Edited(&'edit NbtCompoundEdit<'a, 'tape>),
+}
+
+impl<'a: 'tape, 'tape> NbtCompoundEdit<'a, 'tape> {
+    pub fn new(base: NbtCompound<'a, 'tape>) -> Self {
+        Self {
+            base,
+            changes: Vec::new(),
+        }
+    }
+
+    fn change_index(&self, name: &Mutf8Str) -> Option<usize> {
+        self.changes
+            .iter()
+            .position(|(key, _)| key.as_str() == name)
+    }
+
+    fn set_change(&mut self, name: Mutf8String, change: Change<'a, 'tape>) -> usize {
+        match self.change_index(&name) {
+            Some(index) => {
+                self.changes[index].1 = change;
+                index
+            }
+            None => {
+                self.changes.push((name, change));
+                self.changes.len() - 1
+            }
+        }
+    }
+
+    /// Insert a tag, replacing the existing one with the same name if there is
+    /// one.
+    pub fn insert(&mut self, name: impl Into<Mutf8String>, tag: impl ToNbtTag) {
+        self.set_change(name.into(), Change::Insert(tag.to_nbt_tag()));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.set_change(Mutf8Str::from_str(name).into_owned(), Change::Remove);
+    }
+
+    /// Get a compound to edit without converting it to an owned compound.
+    ///
+    /// Returns `None` if there's no compound with the name. This is also the
+    /// case if the tag was replaced with [`Self::insert`] or [`Self::get_mut`],
+    /// since then it's already owned.
+    pub fn compound_mut(&mut self, name: &str) -> Option<&mut NbtCompoundEdit<'a, 'tape>> {
+        let key = Mutf8Str::from_str(name);
+        let index = match self.change_index(&key) {
+            Some(index) => index,
+            None => {
+                let compound = self.base.compound(name)?;
+                self.set_change(key.into_owned(), Change::Edit(compound.edit()))
+            }
+        };
+        match &mut self.changes[index].1 {
+            Change::Edit(edit) => Some(edit),
+            _ => None,
+        }
+    }
+
+    /// Get a tag as an owned tag that can be modified. Only this tag is
+    /// converted to an owned tag, the rest of the compound stays borrowed.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut owned::NbtTag> {
+        let key = Mutf8Str::from_str(name);
+        let tag = match self.change_index(&key) {
+            Some(index) => match &self.changes[index].1 {
+                Change::Insert(_) => None,
+                Change::Remove => return None,
+                Change::Edit(edit) => Some(owned::NbtTag::Compound(edit.to_owned())),
+            },
+            None => Some(self.base.get(name)?.to_owned()),
+        };
+        let index = match tag {
+            Some(tag) => self.set_change(key.into_owned(), Change::Insert(tag)),
+            None => self.change_index(&key).unwrap(),
+        };
+        match &mut self.changes[index].1 {
+            Change::Insert(tag) => Some(tag),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns whether any changes were made to the compound.
+    pub fn is_modified(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Call the function for every entry in the compound after the changes are
+    /// applied.
+    fn for_each_entry(&self, mut f: impl FnMut(&Mutf8Str, Entry<'a, 'tape, '_>)) {
+        let mut applied = vec![false; self.changes.len()];
+        for (name, tag) in self.base.iter() {
+            let Some(index) = self.change_index(name) else {
+                f(name, Entry::Unchanged(tag));
+                continue;
+            };
+            if applied[index] {
+                continue;
+            }
+            applied[index] = true;
+            match &self.changes[index].1 {
+                Change::Insert(tag) => f(name, Entry::Inserted(tag)),
+                Change::Remove => {}
+                Change::Edit(edit) => f(name, Entry::Edited(edit)),
+            }
+        }
+        // new tags go at the end
+        for ((name, change), applied) in self.changes.iter().zip(applied) {
+            if let (Change::Insert(tag), false) = (change, applied) {
+                f(name, Entry::Inserted(tag));
+            }
+        }
+    }
+
+    pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the compound like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        self.for_each_entry(|name, entry| match entry {
+            Entry::Unchanged(tag) => write_entry(name, tag, data, opts, false),
+            Entry::Inserted(tag) => {
+                data.reserve(1 + 2 + name.len() + 4);
+                // SAFETY: We just reserved enough space for the tag ID, the name length,
+                // the name, and 4 bytes of tag data.
+                unsafe {
+                    push_unchecked(data, tag.id());
+                    write_string_unchecked(data, name);
+                    tag.write_without_tag_type_unchecked(data, opts, false);
+                }
+            }
+            Entry::Edited(edit) => {
+                data.push(COMPOUND_ID);
+                write_string(data, name);
+                edit.write_with_opts(data, opts);
+            }
+        });
+        data.push(END_ID);
+    }
+
+    /// Write the compound to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    pub fn to_owned(&self) -> owned::NbtCompound {
+        let mut values = Vec::new();
+        self.for_each_entry(|name, entry| {
+            let tag = match entry {
+                Entry::Unchanged(tag) => tag.to_owned(),
+                Entry::Inserted(tag) => tag.clone(),
+                Entry::Edited(edit) => owned::NbtTag::Compound(edit.to_owned()),
+            };
+            values.push((name.to_owned(), tag));
+        });
+        owned::NbtCompound::from_values(values)
+    }
+}
+
+/// A copy-on-write view of some borrowed NBT. See [`NbtCompoundEdit`].
+#[derive(Debug, Clone)]
+pub struct BaseNbtEdit<'a> {
+    name: &'a Mutf8Str,
+    compound: NbtCompoundEdit<'a, 'a>,
+}
+
+impl<'a> BaseNbtEdit<'a> {
+    pub fn new(nbt: &'a BaseNbt<'a>) -> Self {
+        Self {
+            name: nbt.name(),
+            compound: nbt.as_compound().edit(),
+        }
+    }
+
+    pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        data.push(COMPOUND_ID);
+        write_string(data, self.name);
+        self.compound.write_with_opts(data, opts);
+    }
+
+    /// Write the NBT to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    pub fn to_owned(&self) -> owned::BaseNbt {
+        owned::BaseNbt::new(self.name, self.compound.to_owned())
+    }
+}
+
+impl<'a> Deref for BaseNbtEdit<'a> {
+    type Target = NbtCompoundEdit<'a, 'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.compound
+    }
+}
+impl DerefMut for BaseNbtEdit<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.compound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{borrow, owned, test_fixtures::bigtest};
+
+    #[test]
+    fn edit_without_converting() {
+        let src = bigtest();
+        let nbt = borrow::read(&mut Cursor::new(&src)).unwrap().unwrap();
+
+        let mut edit = nbt.edit();
+        assert!(!edit.is_modified());
+        assert_eq!(edit.to_bytes(), src);
+
+        edit.insert("intTest", 5);
+        edit.insert("stringTest", "replaced");
+        edit.remove("byteTest");
+        edit.insert("newTag", 1.5f32);
+        let nested = edit
+            .compound_mut("nested compound test")
+            .unwrap()
+            .compound_mut("egg")
+            .unwrap();
+        nested.insert("value", 0.25f32);
+        nested.remove("name");
+        let Some(owned::NbtList::Long(longs)) = edit.get_mut("listTest (long)").unwrap().list_mut()
+        else {
+            panic!()
+        };
+        longs.push(16);
+        assert!(edit.compound_mut("intTest").is_none());
+        assert!(edit.get_mut("byteTest").is_none());
+
+        let mut expected = nbt.as_compound().to_owned();
+        *expected.get_mut("intTest").unwrap() = owned::NbtTag::Int(5);
+        *expected.get_mut("stringTest").unwrap() = owned::NbtTag::String("replaced".into());
+        expected.remove("byteTest");
+        expected.insert("newTag", 1.5f32);
+        let egg = expected
+            .compound_mut("nested compound test")
+            .unwrap()
+            .compound_mut("egg")
+            .unwrap();
+        *egg.get_mut("value").unwrap() = owned::NbtTag::Float(0.25);
+        egg.remove("name");
+        let Some(owned::NbtList::Long(longs)) = expected.list_mut("listTest (long)") else {
+            panic!()
+        };
+        longs.push(16);
+
+        assert_eq!(edit.to_owned().as_compound(), expected);
+        let written = edit.to_bytes();
+        assert_eq!(
+            owned::read(&mut Cursor::new(&written)).unwrap().unwrap(),
+            owned::BaseNbt::new(nbt.name(), expected)
+        );
+    }
+}
@@ -1,10 +1,42 @@
 use std::fmt::{self, Debug};
 
-use crate::{raw_list::RawList, Mutf8Str};
+use super::key_index::KeyIndex;
+use crate::{error::NonRootError, raw_list::RawList, Mutf8Str};
 
 #[derive(Default)]
 pub struct ExtraTapes<'a> {
     pub elements: Vec<ExtraTapeElement<'a>>,
+    /// The data that the tapes were parsed from. This is used for getting the
+    /// raw bytes of tags.
+    pub input: &'a [u8],
+    /// Sorted keys for large compounds, if
+    /// [`BaseNbt::build_key_index`](super::BaseNbt::build_key_index) was
+    /// called.
+    pub key_index: Option<KeyIndex<'a>>,
+}
+
+impl<'a> ExtraTapes<'a> {
+    /// Push an element, returning an error instead of aborting if we couldn't
+    /// allocate enough space for it.
+    #[inline]
+    pub fn push(&mut self, element: ExtraTapeElement<'a>) -> Result<(), NonRootError> {
+        if self.elements.len() == self.elements.capacity() {
+            self.grow()?;
+        }
+        self.elements.push(element);
+        Ok(())
+    }
+
+    #[cold]
+    fn grow(&mut self) -> Result<(), NonRootError> {
+        // indexes into the extra tape are stored as u32s
+        if self.elements.len() >= u32::MAX as usize {
+            return Err(NonRootError::too_many_tags());
+        }
+        self.elements
+            .try_reserve(1)
+            .map_err(|_| NonRootError::out_of_memory())
+    }
 }
 
 impl Debug for ExtraTapes<'_> {
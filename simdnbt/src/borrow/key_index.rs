@@ -0,0 +1,80 @@
+//! Sorted tables of the keys in compounds, so tags in large compounds can be
+//! found without scanning the whole compound. See
+//! [`BaseNbt::build_key_index`](super::BaseNbt::build_key_index).
+
+use std::collections::HashMap;
+
+use super::{list::NbtList, tape::TapeElement, NbtCompound};
+use crate::Mutf8Str;
+
+/// Compounds with fewer tags than this aren't indexed, since scanning them is
+/// about as fast as a binary search.
+const MIN_INDEXED_LEN: usize = 8;
+
+pub struct KeyIndex<'a> {
+    /// The keys of each indexed compound sorted by their bytes, along with
+    /// the tape offset of the tag in the compound. The compounds are keyed by
+    /// the address of their tape element.
+    compounds: HashMap<usize, Box<[(&'a Mutf8Str, u32)]>>,
+}
+
+impl<'a> KeyIndex<'a> {
+    pub fn build(compound: NbtCompound<'a, '_>) -> Self {
+        let mut index = Self {
+            compounds: HashMap::new(),
+        };
+        index.add_compound(compound);
+        index
+    }
+
+    fn add_compound(&mut self, compound: NbtCompound<'a, '_>) {
+        let mut keys = Vec::new();
+        let mut iter = compound.iter();
+        loop {
+            let offset = iter.current_tape_offset as u32;
+            let Some((name, tag)) = iter.next() else {
+                break;
+            };
+            keys.push((name, offset));
+
+            if let Some(child) = tag.compound() {
+                self.add_compound(child);
+            } else if let Some(list) = tag.list() {
+                self.add_list(list);
+            }
+        }
+
+        if keys.len() >= MIN_INDEXED_LEN {
+            // the sort is stable so the first tag with a name stays first,
+            // which is the one that `get` returns
+            keys.sort_by_key(|(name, _)| name.as_bytes());
+            self.compounds
+                .insert(compound.element as usize, keys.into_boxed_slice());
+        }
+    }
+
+    fn add_list(&mut self, list: NbtList<'a, '_>) {
+        if let Some(compounds) = list.compounds() {
+            for child in compounds {
+                self.add_compound(child);
+            }
+        } else if let Some(lists) = list.lists() {
+            for child in lists {
+                self.add_list(child);
+            }
+        }
+    }
+
+    /// Get the sorted keys of the compound, or `None` if it wasn't indexed.
+    pub fn keys(&self, compound: *const TapeElement) -> Option<&[(&'a Mutf8Str, u32)]> {
+        self.compounds.get(&(compound as usize)).map(|keys| &**keys)
+    }
+}
+
+/// Find the tape offset of the first tag with the given name in the sorted
+/// keys.
+pub fn find(keys: &[(&Mutf8Str, u32)], name: &Mutf8Str) -> Option<usize> {
+    let i = keys.partition_point(|(key, _)| key.as_bytes() < name.as_bytes());
+    let (key, offset) = keys.get(i)?;
+    (*key == name).then_some(*offset as usize)
+}
@@ -1,27 +1,33 @@
+#[cfg(feature = "nightly")]
+use std::alloc::Allocator;
 use std::{marker::PhantomData, mem};
 
 use super::{
     compound::{ParsingStack, ParsingStackElement},
     extra_tapes::{ExtraTapeElement, ExtraTapes},
     tape::{TapeElement, TapeTagKind, UnalignedU32},
-    NbtCompound, Tapes,
+    NbtCompound, NbtTag, Tapes,
 };
+#[cfg(feature = "nightly")]
+use crate::arena;
 use crate::{
     common::{
-        read_i8_array, read_int_array, read_long_array, read_string, read_u8_array,
-        read_with_u32_length, slice_i8_into_u8, write_string, write_u32, write_with_u32_length,
-        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
-        LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+        canonicalize_f32s, canonicalize_f64s, read_i8_array, read_int_array, read_length,
+        read_long_array, read_string, read_u8_array, read_with_u32_length, slice_i8_into_u8,
+        write_string, write_u32, write_with_u32_length, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID,
+        DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID,
+        SHORT_ID, STRING_ID,
     },
     error::NonRootError,
-    raw_list::RawList,
+    options::{EmptyLists, WriteOptions},
+    raw_list::{RawList, RawListIter},
     reader::Reader,
     swap_endianness::SwappableNumber,
     Mutf8Str,
 };
 
 /// A list of NBT tags of a single type.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct NbtList<'a: 'tape, 'tape> {
     pub(crate) element: *const TapeElement, // the initial list element
     pub(crate) extra_tapes: &'tape ExtraTapes<'a>,
@@ -32,6 +38,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
         tapes: &mut Tapes<'a>,
         stack: &mut ParsingStack,
     ) -> Result<(), NonRootError> {
+        stack.check_list_depth()?;
         let tag_type = data.read_u8()?;
 
         let pushing_element = match tag_type {
@@ -73,11 +80,11 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             BYTE_ARRAY_ID => {
                 let index_of_element = tapes.extra.elements.len() as u32;
 
-                let length = data.read_u32()?;
-                tapes.extra.elements.push(ExtraTapeElement { length });
+                let length = read_length(data)?;
+                tapes.extra.push(ExtraTapeElement { length })?;
                 for _ in 0..length {
                     let byte_array = read_u8_array(data)?;
-                    tapes.extra.elements.push(ExtraTapeElement { byte_array });
+                    tapes.extra.push(ExtraTapeElement { byte_array })?;
                 }
 
                 TapeElement::new_with_u32(TapeTagKind::ByteArrayList, index_of_element)
@@ -85,17 +92,17 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             STRING_ID => {
                 let index_of_element = tapes.extra.elements.len() as u32;
 
-                let length = data.read_u32()?;
-                tapes.extra.elements.push(ExtraTapeElement { length });
+                let length = read_length(data)?;
+                tapes.extra.push(ExtraTapeElement { length })?;
                 for _ in 0..length {
                     let string = read_string(data)?;
-                    tapes.extra.elements.push(ExtraTapeElement { string });
+                    tapes.extra.push(ExtraTapeElement { string })?;
                 }
 
                 TapeElement::new_with_u32(TapeTagKind::StringList, index_of_element)
             }
             LIST_ID => {
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
                 // length estimate + tape index offset to the end of the list
                 let index_of_list_element = tapes.main.len();
 
@@ -111,7 +118,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
                 )
             }
             COMPOUND_ID => {
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
                 // length estimate + tape index offset to the end of the compound
                 let index_of_list_element = tapes.main.len();
 
@@ -128,22 +135,22 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             }
             INT_ARRAY_ID => {
                 let index_of_element = tapes.extra.elements.len() as u32;
-                let length = data.read_u32()?;
-                tapes.extra.elements.push(ExtraTapeElement { length });
+                let length = read_length(data)?;
+                tapes.extra.push(ExtraTapeElement { length })?;
                 for _ in 0..length {
                     let int_array = read_int_array(data)?;
-                    tapes.extra.elements.push(ExtraTapeElement { int_array });
+                    tapes.extra.push(ExtraTapeElement { int_array })?;
                 }
 
                 TapeElement::new_with_u32(TapeTagKind::IntArrayList, index_of_element)
             }
             LONG_ARRAY_ID => {
                 let index_of_element = tapes.extra.elements.len() as u32;
-                let length = data.read_u32()?;
-                tapes.extra.elements.push(ExtraTapeElement { length });
+                let length = read_length(data)?;
+                tapes.extra.push(ExtraTapeElement { length })?;
                 for _ in 0..length {
                     let long_array = read_long_array(data)?;
-                    tapes.extra.elements.push(ExtraTapeElement { long_array });
+                    tapes.extra.push(ExtraTapeElement { long_array })?;
                 }
 
                 TapeElement::new_with_u32(TapeTagKind::LongArrayList, index_of_element)
@@ -151,14 +158,43 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             _ => return Err(NonRootError::unknown_tag_id(tag_type)),
         };
 
-        tapes.main.push(pushing_element);
+        tapes.main.push(pushing_element)?;
 
         Ok(())
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with(data, &WriteOptions::default(), false);
+    }
+
+    /// Write the list to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// Write the list like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        self.write_with(data, opts, false);
+    }
+
+    /// Write the list, sorting the keys of every compound inside of it and
+    /// writing every NaN the same way. See [`NbtCompound::write_canonical`].
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        self.write_with(data, &WriteOptions::default(), true);
+    }
+
+    pub(crate) fn write_with(&self, data: &mut Vec<u8>, opts: &WriteOptions, canonical: bool) {
         let el = self.element();
 
+        if opts.empty_lists == EmptyLists::End && self.iter().next().is_none() {
+            data.push(END_ID);
+            data.extend(&0u32.to_be_bytes());
+            return;
+        }
+
         data.push(self.id());
 
         match el.kind() {
@@ -196,6 +232,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
                 );
             }
             TapeTagKind::FloatList => {
+                let start = data.len();
                 write_with_u32_length(
                     data,
                     4,
@@ -203,8 +240,12 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
                         .unwrap()
                         .as_big_endian(),
                 );
+                if canonical {
+                    canonicalize_f32s(&mut data[start + 4..]);
+                }
             }
             TapeTagKind::DoubleList => {
+                let start = data.len();
                 write_with_u32_length(
                     data,
                     8,
@@ -212,40 +253,48 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
                         .unwrap()
                         .as_big_endian(),
                 );
+                if canonical {
+                    canonicalize_f64s(&mut data[start + 4..]);
+                }
             }
             TapeTagKind::ByteArrayList => {
                 let byte_arrays = self.byte_arrays().unwrap();
+                write_u32(data, byte_arrays.len() as u32);
                 for array in byte_arrays.iter() {
                     write_with_u32_length(data, 1, array);
                 }
             }
             TapeTagKind::StringList => {
                 let strings = self.strings().unwrap();
+                write_u32(data, strings.len() as u32);
                 for string in strings.iter() {
                     write_string(data, string);
                 }
             }
             TapeTagKind::ListList => {
                 let lists = self.lists().unwrap();
+                write_u32(data, lists.clone().len() as u32);
                 for list in lists {
-                    list.write(data);
+                    list.write_with(data, opts, canonical);
                 }
             }
             TapeTagKind::CompoundList => {
                 let compounds = self.compounds().unwrap();
                 write_u32(data, compounds.clone().len() as u32);
                 for compound in compounds {
-                    compound.write(data);
+                    compound.write_with(data, opts, canonical);
                 }
             }
             TapeTagKind::IntArrayList => {
                 let int_arrays = self.int_arrays().unwrap();
+                write_u32(data, int_arrays.len() as u32);
                 for array in int_arrays.iter() {
                     write_with_u32_length(data, 4, array.as_big_endian());
                 }
             }
             TapeTagKind::LongArrayList => {
                 let long_arrays = self.long_arrays().unwrap();
+                write_u32(data, long_arrays.len() as u32);
                 for array in long_arrays.iter() {
                     write_with_u32_length(data, 8, array.as_big_endian());
                 }
@@ -255,7 +304,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
     }
 
     /// Get the tape element kind and value for this list.
-    fn element(&self) -> TapeElement {
+    pub(crate) fn element(&self) -> TapeElement {
         unsafe { *self.element }
     }
 
@@ -287,7 +336,12 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
         self.element().kind() == TapeTagKind::EmptyList
     }
 
-    pub fn bytes(&self) -> Option<&[i8]> {
+    /// Get a list of bytes as `u8`s, for values that are meant to be
+    /// unsigned.
+    pub fn ubytes(&self) -> Option<&'a [u8]> {
+        self.bytes().map(slice_i8_into_u8)
+    }
+    pub fn bytes(&self) -> Option<&'a [i8]> {
         let el = self.element();
         if el.kind() != TapeTagKind::ByteList {
             return None;
@@ -315,6 +369,33 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
     pub fn doubles(&self) -> Option<Vec<f64>> {
         u32_prefixed_list_to_vec(TapeTagKind::DoubleList, self.element)
     }
+
+    /// Returns an iterator over a list of shorts that doesn't allocate, unlike
+    /// [`Self::shorts`].
+    pub fn shorts_iter(&self) -> Option<RawListIter<'a, i16>> {
+        u32_prefixed_list_to_rawlist(TapeTagKind::ShortList, self.element).map(|l| l.iter())
+    }
+    /// Returns an iterator over a list of ints that doesn't allocate, unlike
+    /// [`Self::ints`].
+    pub fn ints_iter(&self) -> Option<RawListIter<'a, i32>> {
+        u32_prefixed_list_to_rawlist(TapeTagKind::IntList, self.element).map(|l| l.iter())
+    }
+    /// Returns an iterator over a list of longs that doesn't allocate, unlike
+    /// [`Self::longs`].
+    pub fn longs_iter(&self) -> Option<RawListIter<'a, i64>> {
+        u32_prefixed_list_to_rawlist(TapeTagKind::LongList, self.element).map(|l| l.iter())
+    }
+    /// Returns an iterator over a list of floats that doesn't allocate, unlike
+    /// [`Self::floats`].
+    pub fn floats_iter(&self) -> Option<RawListIter<'a, f32>> {
+        u32_prefixed_list_to_rawlist(TapeTagKind::FloatList, self.element).map(|l| l.iter())
+    }
+    /// Returns an iterator over a list of doubles that doesn't allocate,
+    /// unlike [`Self::doubles`].
+    pub fn doubles_iter(&self) -> Option<RawListIter<'a, f64>> {
+        u32_prefixed_list_to_rawlist(TapeTagKind::DoubleList, self.element).map(|l| l.iter())
+    }
+
     pub fn byte_arrays(&self) -> Option<&'a [&'a [u8]]> {
         let el = self.element();
         if el.kind() != TapeTagKind::ByteArrayList {
@@ -399,7 +480,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             },
         })
     }
-    pub fn int_arrays(&self) -> Option<&[RawList<i32>]> {
+    pub fn int_arrays(&self) -> Option<&'tape [RawList<'a, i32>]> {
         let el = self.element();
         if el.kind() != TapeTagKind::IntArrayList {
             return None;
@@ -419,7 +500,7 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
         };
         Some(slice)
     }
-    pub fn long_arrays(&self) -> Option<&[RawList<i64>]> {
+    pub fn long_arrays(&self) -> Option<&'tape [RawList<'a, i64>]> {
         let el = self.element();
         if el.kind() != TapeTagKind::LongArrayList {
             return None;
@@ -440,6 +521,73 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
         Some(slice)
     }
 
+    /// Returns an iterator over the elements of the list as [`NbtTag`]s,
+    /// regardless of what type of list it is.
+    ///
+    /// This is useful for code that handles every type of tag generically,
+    /// but if you know the type of the list then the typed accessors like
+    /// [`Self::ints`] or [`Self::compounds`] are faster.
+    pub fn iter(&self) -> NbtListIter<'a, 'tape> {
+        let el = self.element();
+        let end = match el.kind() {
+            TapeTagKind::EmptyList => 0,
+            TapeTagKind::ListList | TapeTagKind::CompoundList => {
+                el.approx_len_and_offset().1 as usize
+            }
+            TapeTagKind::ByteArrayList
+            | TapeTagKind::StringList
+            | TapeTagKind::IntArrayList
+            | TapeTagKind::LongArrayList => {
+                let index_to_extra_tapes = el.u32() as usize;
+                unsafe { self.extra_tapes.elements[index_to_extra_tapes].length as usize }
+            }
+            _ => {
+                let length = unsafe { u32::from(*el.ptr::<UnalignedU32>()) };
+                #[cfg(target_endian = "little")]
+                let length = length.swap_bytes();
+                length as usize
+            }
+        };
+        let index = match el.kind() {
+            // the first element is the list element so we skip it
+            TapeTagKind::ListList | TapeTagKind::CompoundList => 1,
+            _ => 0,
+        };
+
+        NbtListIter {
+            list: *self,
+            index,
+            end,
+        }
+    }
+
+    /// Get the element at the given index as an [`NbtTag`], regardless of
+    /// what type of list it is.
+    ///
+    /// This is `O(1)` for most lists, but lists of lists and lists of
+    /// compounds have to be iterated over to find the element.
+    pub fn get(&self, index: usize) -> Option<NbtTag<'a, 'tape>> {
+        self.iter().nth(index)
+    }
+
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        match self.element().kind() {
+            TapeTagKind::ListList => self.lists().unwrap().len(),
+            TapeTagKind::CompoundList => self.compounds().unwrap().len(),
+            _ => self.iter().end,
+        }
+    }
+
+    /// Returns whether the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        match self.element().kind() {
+            TapeTagKind::ListList => self.lists().unwrap().is_empty(),
+            TapeTagKind::CompoundList => self.compounds().unwrap().is_empty(),
+            _ => self.iter().end == 0,
+        }
+    }
+
     pub fn to_owned(&self) -> crate::owned::NbtList {
         let el = self.element();
 
@@ -498,6 +646,93 @@ impl<'a, 'tape> NbtList<'a, 'tape> {
             _ => unreachable!("this is an NbtList, no other kinds should be possible"),
         }
     }
+
+    /// Copy the list into an allocator, like an arena. See [`crate::arena`].
+    #[cfg(feature = "nightly")]
+    pub fn to_owned_in<A: Allocator + Clone>(&self, alloc: A) -> arena::NbtList<A> {
+        let el = self.element();
+
+        match el.kind() {
+            TapeTagKind::EmptyList => arena::NbtList::Empty,
+            TapeTagKind::ByteList => {
+                arena::NbtList::Byte(arena::copy_in(self.bytes().unwrap(), alloc))
+            }
+            TapeTagKind::ShortList => {
+                arena::NbtList::Short(arena::collect_in(self.shorts_iter().unwrap(), alloc))
+            }
+            TapeTagKind::IntList => {
+                arena::NbtList::Int(arena::collect_in(self.ints_iter().unwrap(), alloc))
+            }
+            TapeTagKind::LongList => {
+                arena::NbtList::Long(arena::collect_in(self.longs_iter().unwrap(), alloc))
+            }
+            TapeTagKind::FloatList => {
+                arena::NbtList::Float(arena::collect_in(self.floats_iter().unwrap(), alloc))
+            }
+            TapeTagKind::DoubleList => {
+                arena::NbtList::Double(arena::collect_in(self.doubles_iter().unwrap(), alloc))
+            }
+            TapeTagKind::ByteArrayList => arena::NbtList::ByteArray(arena::collect_in(
+                self.byte_arrays()
+                    .unwrap()
+                    .iter()
+                    .map(|array| arena::copy_in(array, alloc.clone())),
+                alloc.clone(),
+            )),
+            TapeTagKind::StringList => arena::NbtList::String(arena::collect_in(
+                self.strings()
+                    .unwrap()
+                    .iter()
+                    .map(|string| arena::copy_in(string.as_bytes(), alloc.clone())),
+                alloc.clone(),
+            )),
+            TapeTagKind::ListList => {
+                let lists = self.lists().unwrap();
+                let mut values = Vec::with_capacity_in(lists.approx_len() as usize, alloc.clone());
+                values.extend(
+                    lists
+                        .into_iter()
+                        .map(|list| list.to_owned_in(alloc.clone())),
+                );
+                arena::NbtList::List(values)
+            }
+            TapeTagKind::CompoundList => {
+                let compounds = self.compounds().unwrap();
+                let mut values =
+                    Vec::with_capacity_in(compounds.approx_len() as usize, alloc.clone());
+                values.extend(
+                    compounds
+                        .into_iter()
+                        .map(|compound| compound.to_owned_in(alloc.clone())),
+                );
+                arena::NbtList::Compound(values)
+            }
+            TapeTagKind::IntArrayList => arena::NbtList::IntArray(arena::collect_in(
+                self.int_arrays()
+                    .unwrap()
+                    .iter()
+                    .map(|array| arena::collect_in(array.iter(), alloc.clone())),
+                alloc.clone(),
+            )),
+            TapeTagKind::LongArrayList => arena::NbtList::LongArray(arena::collect_in(
+                self.long_arrays()
+                    .unwrap()
+                    .iter()
+                    .map(|array| arena::collect_in(array.iter(), alloc.clone())),
+                alloc.clone(),
+            )),
+            _ => unreachable!("this is an NbtList, no other kinds should be possible"),
+        }
+    }
+}
+
+impl<'a: 'tape, 'tape> IntoIterator for NbtList<'a, 'tape> {
+    type Item = NbtTag<'a, 'tape>;
+    type IntoIter = NbtListIter<'a, 'tape>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl PartialEq for NbtList<'_, '_> {
@@ -530,6 +765,127 @@ impl PartialEq for NbtList<'_, '_> {
     }
 }
 
+/// An iterator over the elements of any type of list as [`NbtTag`]s. This is
+/// returned by [`NbtList::iter`].
+#[derive(Clone)]
+pub struct NbtListIter<'a, 'tape> {
+    list: NbtList<'a, 'tape>,
+    /// The index of the next element, or the offset of the next element on the
+    /// tape for lists of lists and lists of compounds.
+    index: usize,
+    end: usize,
+}
+impl<'a: 'tape, 'tape> Iterator for NbtListIter<'a, 'tape> {
+    type Item = NbtTag<'a, 'tape>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            return None;
+        }
+        let list = &self.list;
+        let kind = list.element().kind();
+
+        if matches!(kind, TapeTagKind::ListList | TapeTagKind::CompoundList) {
+            let el_ptr = unsafe { list.element.add(self.index) };
+            self.index += unsafe { (*el_ptr).skip_offset() };
+            // we don't know where compounds and lists in lists start
+            return Some(NbtTag::from_tape(
+                el_ptr,
+                list.extra_tapes,
+                std::ptr::null(),
+            ));
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        let (element, start) = match kind {
+            TapeTagKind::ByteArrayList => {
+                let array = list.byte_arrays().unwrap()[index];
+                // the element points to the length, which comes right before the data
+                let ptr = unsafe { array.as_ptr().sub(4) };
+                (TapeElement::new_with_ptr(TapeTagKind::ByteArray, ptr), ptr)
+            }
+            TapeTagKind::StringList => {
+                let string = list.strings().unwrap()[index];
+                let ptr = unsafe { string.as_bytes().as_ptr().sub(2) };
+                (TapeElement::new_with_ptr(TapeTagKind::String, ptr), ptr)
+            }
+            TapeTagKind::IntArrayList => {
+                let array = &list.int_arrays().unwrap()[index];
+                let ptr = unsafe { array.as_big_endian().as_ptr().sub(4) };
+                (TapeElement::new_with_ptr(TapeTagKind::IntArray, ptr), ptr)
+            }
+            TapeTagKind::LongArrayList => {
+                let array = &list.long_arrays().unwrap()[index];
+                let ptr = unsafe { array.as_big_endian().as_ptr().sub(4) };
+                (TapeElement::new_with_ptr(TapeTagKind::LongArray, ptr), ptr)
+            }
+            _ => {
+                // lists of numbers store a pointer to the length, and the numbers come after it
+                let data_ptr = unsafe { list.element().ptr::<UnalignedU32>().add(1) as *const u8 };
+                match kind {
+                    TapeTagKind::ByteList => {
+                        let ptr = unsafe { data_ptr.add(index) };
+                        (
+                            TapeElement::new_with_u8(TapeTagKind::Byte, unsafe { *ptr }),
+                            ptr,
+                        )
+                    }
+                    TapeTagKind::ShortList => {
+                        let ptr = unsafe { data_ptr.add(index * 2) };
+                        let value = u16::from_be_bytes(unsafe { *(ptr as *const [u8; 2]) });
+                        (TapeElement::new_with_u16(TapeTagKind::Short, value), ptr)
+                    }
+                    TapeTagKind::IntList | TapeTagKind::FloatList => {
+                        let ptr = unsafe { data_ptr.add(index * 4) };
+                        let value = u32::from_be_bytes(unsafe { *(ptr as *const [u8; 4]) });
+                        let kind = if kind == TapeTagKind::IntList {
+                            TapeTagKind::Int
+                        } else {
+                            TapeTagKind::Float
+                        };
+                        (TapeElement::new_with_u32(kind, value), ptr)
+                    }
+                    TapeTagKind::LongList | TapeTagKind::DoubleList => {
+                        let ptr = unsafe { data_ptr.add(index * 8) };
+                        let kind = if kind == TapeTagKind::LongList {
+                            TapeTagKind::Long
+                        } else {
+                            TapeTagKind::Double
+                        };
+                        (TapeElement::new_with_ptr(kind, ptr), ptr)
+                    }
+                    _ => unreachable!("this is an NbtList, no other kinds should be possible"),
+                }
+            }
+        };
+
+        Some(NbtTag {
+            element,
+            tape: std::ptr::null(),
+            extra_tapes: list.extra_tapes,
+            start,
+        })
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if matches!(
+            self.list.element().kind(),
+            TapeTagKind::ListList | TapeTagKind::CompoundList
+        ) {
+            // the elements take up different amounts of space on the tape, so
+            // we have to skip them one at a time
+            for _ in 0..n {
+                self.next()?;
+            }
+        } else {
+            self.index = self.index.saturating_add(n);
+        }
+        self.next()
+    }
+}
+
 /// A wrapper over [`NbtListListIter`] that acts more like a Vec.
 #[derive(Clone, Default)]
 pub struct NbtListList<'a, 'tape> {
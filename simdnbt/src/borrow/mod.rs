@@ -2,36 +2,68 @@
 //! you can keep a reference to the original buffer.
 
 mod compound;
+mod edit;
 mod extra_tapes;
+mod key_index;
 mod list;
+mod owned_eq;
+mod parser;
+mod profile;
+mod raw_tag;
+mod selective;
 mod tape;
+mod walk;
 
+#[cfg(feature = "nightly")]
+use std::alloc::Allocator;
 use std::{
     fmt::{self, Debug},
-    io::Cursor,
+    io::{self, Cursor},
+    mem,
+    ops::Range,
 };
 
 use byteorder::ReadBytesExt;
 use compound::ParsingStackElementKind;
 use tape::{UnalignedU16, UnalignedU32, UnalignedU64};
 
+pub(crate) use self::compound::write_tag;
 use self::{
     compound::{read_tag_in_compound, ParsingStack, ParsingStackElement},
-    extra_tapes::ExtraTapes,
+    extra_tapes::{ExtraTapeElement, ExtraTapes},
+    key_index::KeyIndex,
     list::{read_compound_in_list, read_list_in_list},
     tape::{MainTape, TapeElement, TapeTagKind},
 };
 pub use self::{
     compound::{NbtCompound, NbtCompoundIter},
-    list::{NbtCompoundList, NbtCompoundListIter, NbtList, NbtListList, NbtListListIter},
+    edit::{BaseNbtEdit, NbtCompoundEdit},
+    list::{
+        NbtCompoundList, NbtCompoundListIter, NbtList, NbtListIter, NbtListList, NbtListListIter,
+    },
+    parser::{ParsedNbt, Parser},
+    profile::KeySize,
+    raw_tag::RawTag,
+    selective::read_selective,
+    walk::{walk, Visit, VisitFlow},
 };
+#[cfg(feature = "nightly")]
+use crate::arena;
+#[cfg(feature = "compression")]
+use crate::Compression;
 use crate::{
     common::{
         read_string, write_string, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID,
         FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
     },
+    error::NonRootError,
+    io_write::ChunkedWriter,
+    little_endian::{convert_nbt, Endian},
+    options::Limits,
+    packed::PackedBits,
+    raw_list::{RawList, RawListIter},
     reader::{Reader, ReaderFromCursor},
-    Error, Mutf8Str,
+    Error, Mutf8Str, ReadOptions, TagType, WriteOptions,
 };
 
 /// Read a normal root NBT compound. This is either empty or has a name and
@@ -39,30 +71,99 @@ use crate::{
 ///
 /// Returns `Ok(Nbt::None)` if there is no data.
 pub fn read<'a>(data: &mut Cursor<&'a [u8]>) -> Result<Nbt<'a>, Error> {
+    read_with_limits(data, &Limits::default())
+}
+/// Read a normal root NBT compound like [`read`], but with the given options.
+/// This is useful for setting limits when reading untrusted data.
+pub fn read_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    options: &ReadOptions,
+) -> Result<Nbt<'a>, Error> {
+    read_with_limits(data, &Limits::new(options))
+}
+/// Read a normal root NBT compound like [`read`], but from a slice instead of
+/// a cursor. This also returns how many bytes the NBT took up, so data after
+/// it can be read.
+pub fn read_slice(data: &[u8]) -> Result<(Nbt<'_>, usize), Error> {
+    read_slice_with_opts(data, &ReadOptions::default())
+}
+/// Read a normal root NBT compound from a slice like [`read_slice`], but with
+/// the given options.
+pub fn read_slice_with_opts<'a>(
+    data: &'a [u8],
+    options: &ReadOptions,
+) -> Result<(Nbt<'a>, usize), Error> {
+    let mut cursor = Cursor::new(data);
+    let nbt = read_with_opts(&mut cursor, options)?;
+    Ok((nbt, cursor.position() as usize))
+}
+fn read_with_limits<'a>(data: &mut Cursor<&'a [u8]>, limits: &Limits) -> Result<Nbt<'a>, Error> {
+    let input = *data.get_ref();
+    let mut data = ReaderFromCursor::with_limits(data, limits);
+    let Some(name) = read_root_name(&mut data).map_err(|err| data.map_err(err))? else {
+        return Ok(Nbt::None);
+    };
+
+    let mut tapes = Tapes::new(input)?;
+    read_root_compound(&mut data, &mut tapes, limits).map_err(|err| data.map_err(err))?;
+
+    Ok(Nbt::Some(BaseNbt { name, tapes }))
+}
+/// Read the type and name of the root tag, or return `None` if it's empty.
+fn read_root_name<'a>(data: &mut Reader<'a>) -> Result<Option<&'a Mutf8Str>, Error> {
     let root_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
     if root_type == END_ID {
-        return Ok(Nbt::None);
+        return Ok(None);
     }
     if root_type != COMPOUND_ID {
         return Err(Error::InvalidRootType(root_type));
     }
-    let mut data = ReaderFromCursor::new(data);
-    let name = read_string(&mut data)?;
-
-    let mut tapes = Tapes::new();
-    tapes.main.push(TapeElement::new_with_approx_len_and_offset(
-        TapeTagKind::Compound,
-        // these get overwritten later
-        0,
-        0,
-    ));
-
-    let mut stack = ParsingStack::new();
+    Ok(Some(read_string(data)?))
+}
+/// Read the contents of the root compound into the tapes, which must be empty.
+fn read_root_compound<'a>(
+    data: &mut Reader<'a>,
+    tapes: &mut Tapes<'a>,
+    limits: &Limits,
+) -> Result<(), Error> {
+    tapes
+        .main
+        .push(TapeElement::new_with_approx_len_and_offset(
+            TapeTagKind::Compound,
+            // these get overwritten later
+            0,
+            0,
+        ))?;
+
+    let mut stack = ParsingStack::with_limits(limits);
     stack.push(ParsingStackElement::compound(0))?;
 
-    read_with_stack(&mut data, &mut tapes, &mut stack)?;
-
-    Ok(Nbt::Some(BaseNbt { name, tapes }))
+    read_with_stack(data, tapes, &mut stack, limits)
+}
+/// Read a normal root NBT compound like [`read`], but little-endian. This is
+/// what Bedrock Edition uses for files like `level.dat`.
+///
+/// To avoid copying, the data is converted to big-endian in place before it's
+/// read, so it'll be normal NBT afterwards. Any data after the NBT is ignored.
+pub fn read_le(data: &mut [u8]) -> Result<Nbt<'_>, Error> {
+    let len = convert_nbt(data, Endian::Little)?;
+    read(&mut Cursor::new(&data[..len]))
+}
+/// Read a normal root NBT compound like [`read`], but decompress it first if
+/// it's gzipped or zlib compressed.
+///
+/// The compressed data is decompressed into `buffer` (which the returned NBT
+/// borrows from), and uncompressed data is read directly.
+#[cfg(feature = "compression")]
+pub fn read_compressed<'a>(data: &'a [u8], buffer: &'a mut Vec<u8>) -> Result<Nbt<'a>, Error> {
+    let data = match Compression::detect(data) {
+        Compression::None => data,
+        compression => {
+            compression.decompress(data, buffer)?;
+            buffer
+        }
+    };
+    read(&mut Cursor::new(data))
 }
 /// Read a root NBT compound, but without reading the name. This is used in
 /// Minecraft when reading NBT over the network.
@@ -70,6 +171,14 @@ pub fn read<'a>(data: &mut Cursor<&'a [u8]>) -> Result<Nbt<'a>, Error> {
 /// This is similar to [`read_tag`], but returns an [`Nbt`] instead
 /// (guaranteeing it'll be either empty or a compound).
 pub fn read_unnamed<'a>(data: &mut Cursor<&'a [u8]>) -> Result<Nbt<'a>, Error> {
+    read_unnamed_with_opts(data, &ReadOptions::default())
+}
+/// Read a root NBT compound without a name like [`read_unnamed`], but with the
+/// given options.
+pub fn read_unnamed_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    options: &ReadOptions,
+) -> Result<Nbt<'a>, Error> {
     let root_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
     if root_type == END_ID {
         return Ok(Nbt::None);
@@ -78,26 +187,22 @@ pub fn read_unnamed<'a>(data: &mut Cursor<&'a [u8]>) -> Result<Nbt<'a>, Error> {
         return Err(Error::InvalidRootType(root_type));
     }
     let name = Mutf8Str::from_slice(&[]);
-    let BaseNbtCompound { tapes } = read_compound(data)?;
+    let BaseNbtCompound { tapes } = read_compound_with_opts(data, options)?;
     Ok(Nbt::Some(BaseNbt { name, tapes }))
 }
 /// Read a compound tag. This may have any number of items.
 pub fn read_compound<'a>(data: &mut Cursor<&'a [u8]>) -> Result<BaseNbtCompound<'a>, Error> {
-    let mut tapes = Tapes::new();
-    let mut stack = ParsingStack::new();
-
-    let mut data = ReaderFromCursor::new(data);
-
-    stack.push(ParsingStackElement::compound(0))?;
-
-    tapes.main.push(TapeElement::new_with_approx_len_and_offset(
-        TapeTagKind::Compound,
-        // these get overwritten later
-        0,
-        0,
-    ));
-
-    read_with_stack(&mut data, &mut tapes, &mut stack)?;
+    read_compound_with_opts(data, &ReadOptions::default())
+}
+/// Read a compound tag like [`read_compound`], but with the given options.
+pub fn read_compound_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    options: &ReadOptions,
+) -> Result<BaseNbtCompound<'a>, Error> {
+    let limits = Limits::new(options);
+    let mut tapes = Tapes::new(data.get_ref())?;
+    let mut data = ReaderFromCursor::with_limits(data, &limits);
+    read_root_compound(&mut data, &mut tapes, &limits).map_err(|err| data.map_err(err))?;
 
     Ok(BaseNbtCompound { tapes })
 }
@@ -105,46 +210,92 @@ pub fn read_compound<'a>(data: &mut Cursor<&'a [u8]>) -> Result<BaseNbtCompound<
 /// except for an end tag. If you need to be able to handle end tags, use
 /// [`read_optional_tag`].
 pub fn read_tag<'a>(data: &mut Cursor<&'a [u8]>) -> Result<BaseNbtTag<'a>, Error> {
-    let mut tapes = Tapes::new();
-    let mut stack = ParsingStack::new();
-
-    let mut data = ReaderFromCursor::new(data);
-
-    let tag_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
-    if tag_type == END_ID {
-        return Err(Error::InvalidRootType(0));
-    }
-    compound::read_tag(&mut data, &mut tapes, &mut stack, tag_type)?;
-    read_with_stack(&mut data, &mut tapes, &mut stack)?;
-
-    Ok(BaseNbtTag { tapes })
+    read_tag_with_opts(data, &ReadOptions::default())
+}
+/// Read an NBT tag like [`read_tag`], but with the given options.
+pub fn read_tag_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    options: &ReadOptions,
+) -> Result<BaseNbtTag<'a>, Error> {
+    read_optional_tag_with_opts(data, options)?.ok_or(Error::InvalidRootType(0))
 }
 /// Read any NBT tag, without reading its name. This may be any type of tag,
 /// including an end tag.
 ///
 /// Returns `Ok(None)` if there is no data.
 pub fn read_optional_tag<'a>(data: &mut Cursor<&'a [u8]>) -> Result<Option<BaseNbtTag<'a>>, Error> {
-    let mut tapes = Tapes::new();
-    let mut stack = ParsingStack::new();
-
-    let mut data = ReaderFromCursor::new(data);
+    read_optional_tag_with_opts(data, &ReadOptions::default())
+}
+/// Read any NBT tag like [`read_optional_tag`], but with the given options.
+pub fn read_optional_tag_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    options: &ReadOptions,
+) -> Result<Option<BaseNbtTag<'a>>, Error> {
+    let limits = Limits::new(options);
+    let mut tapes = Tapes::new(data.get_ref())?;
+    let mut stack = ParsingStack::with_limits(&limits);
+
+    // the payload starts right after the tag id
+    let start = data.position() as usize + 1;
+    let mut data = ReaderFromCursor::with_limits(data, &limits);
 
     let tag_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
     if tag_type == END_ID {
         return Ok(None);
     }
-    compound::read_tag(&mut data, &mut tapes, &mut stack, tag_type)?;
-    read_with_stack(&mut data, &mut tapes, &mut stack)?;
+    compound::read_tag(&mut data, &mut tapes, &mut stack, tag_type)
+        .map_err(Error::from)
+        .and_then(|()| read_with_stack(&mut data, &mut tapes, &mut stack, &limits))
+        .map_err(|err| data.map_err(err))?;
 
-    Ok(Some(BaseNbtTag { tapes }))
+    Ok(Some(BaseNbtTag { tapes, start }))
+}
+
+/// Read the payload of a tag whose ID is already known, like one that was
+/// captured with [`RawTag`]. The data has to be exactly the payload.
+pub(crate) fn read_tag_payload(data: &[u8], tag_type: u8) -> Result<BaseNbtTag<'_>, Error> {
+    let mut reader = Reader::new(data);
+    let tag = read_tag_payload_from(data, &mut reader, tag_type)?;
+    if reader.remaining() != 0 {
+        return Err(Error::InvalidLength);
+    }
+    Ok(tag)
+}
+
+/// Read the payload of a tag whose ID is already known from a reader over
+/// `input`, leaving the reader right after the tag.
+pub(crate) fn read_tag_payload_from<'a>(
+    input: &'a [u8],
+    reader: &mut Reader<'a>,
+    tag_type: u8,
+) -> Result<BaseNbtTag<'a>, Error> {
+    if tag_type == END_ID {
+        return Err(Error::InvalidRootType(0));
+    }
+    let start = reader.cur as usize - input.as_ptr() as usize;
+    let mut tapes = Tapes::new(input)?;
+    let mut stack = ParsingStack::new();
+    compound::read_tag(reader, &mut tapes, &mut stack, tag_type)?;
+    read_with_stack(reader, &mut tapes, &mut stack, &Limits::default())?;
+    Ok(BaseNbtTag { tapes, start })
 }
 
 fn read_with_stack<'a>(
     data: &mut Reader<'a>,
     tapes: &mut Tapes<'a>,
     stack: &mut ParsingStack,
+    limits: &Limits,
 ) -> Result<(), Error> {
     while !stack.is_empty() {
+        // every iteration reads at most one tag (or the elements of one list),
+        // so checking here is enough to stop it from getting much further
+        if tapes.main.len() + tapes.extra.elements.len() > limits.max_tags {
+            return Err(Error::TooManyTags);
+        }
+        if tapes.allocated() > limits.max_allocation {
+            return Err(Error::OutOfMemory);
+        }
+
         let top = stack.peek_mut();
         match top.kind {
             ParsingStackElementKind::Compound => read_tag_in_compound(data, tapes, stack)?,
@@ -152,18 +303,62 @@ fn read_with_stack<'a>(
             ParsingStackElementKind::ListOfLists => read_list_in_list(data, tapes, stack)?,
         }
     }
+    // the last tag could've been a long list
+    if tapes.allocated() > limits.max_allocation {
+        return Err(Error::OutOfMemory);
+    }
 
     Ok(())
 }
 
-#[derive(Default)]
 pub(crate) struct Tapes<'a> {
     main: MainTape,
     extra: ExtraTapes<'a>,
 }
-impl Tapes<'_> {
-    fn new() -> Self {
-        Self::default()
+impl<'a> Tapes<'a> {
+    fn new(input: &'a [u8]) -> Result<Self, NonRootError> {
+        Ok(Self {
+            main: MainTape::new()?,
+            extra: ExtraTapes {
+                elements: Vec::new(),
+                input,
+                key_index: None,
+            },
+        })
+    }
+
+    /// Empty the tapes so they can be used for reading different data,
+    /// without freeing their memory.
+    fn recycle<'b>(self, input: &'b [u8]) -> Tapes<'b> {
+        let Tapes { mut main, extra } = self;
+        main.clear();
+        let mut elements = mem::ManuallyDrop::new(extra.elements);
+        elements.clear();
+        // SAFETY: the vec is empty, so there's nothing left that borrows from
+        // the old input, and the layout of the elements doesn't depend on the
+        // lifetime.
+        let elements = unsafe {
+            Vec::from_raw_parts(
+                elements.as_mut_ptr().cast::<ExtraTapeElement<'b>>(),
+                0,
+                elements.capacity(),
+            )
+        };
+        Tapes {
+            main,
+            extra: ExtraTapes {
+                elements,
+                input,
+                key_index: None,
+            },
+        }
+    }
+
+    /// How many bytes the tapes take up so far.
+    #[inline]
+    fn allocated(&self) -> usize {
+        self.main.len() * mem::size_of::<TapeElement>()
+            + self.extra.elements.len() * mem::size_of::<ExtraTapeElement>()
     }
 }
 impl Debug for Tapes<'_> {
@@ -189,6 +384,12 @@ impl<'a> BaseNbt<'a> {
         }
     }
 
+    /// Start editing the NBT without converting it to owned NBT. See
+    /// [`NbtCompoundEdit`].
+    pub fn edit(&'a self) -> BaseNbtEdit<'a> {
+        BaseNbtEdit::new(self)
+    }
+
     /// Get the name of the NBT compound. This is often an empty string.
     pub fn name(&self) -> &'a Mutf8Str {
         self.name
@@ -197,6 +398,38 @@ impl<'a> BaseNbt<'a> {
     pub fn get<'tape>(&'a self, key: &str) -> Option<NbtTag<'a, 'tape>> {
         self.as_compound().get(key)
     }
+    /// Build a sorted table of the keys in every large compound, so
+    /// [`NbtCompound::get`] and [`NbtCompound::contains`] can do a binary
+    /// search instead of scanning the compound.
+    ///
+    /// This is worth it if you're going to look up tags in the same compounds
+    /// many times. Small compounds aren't indexed, since scanning them is
+    /// already fast.
+    ///
+    /// ```
+    /// # use std::io::Cursor;
+    /// # let mut root = simdnbt::owned::NbtCompound::new();
+    /// # for i in 0..100 {
+    /// #     root.insert(i.to_string(), i);
+    /// # }
+    /// # let data = simdnbt::owned::BaseNbt::new("", root).to_bytes();
+    /// let mut nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+    /// nbt.build_key_index();
+    /// assert_eq!(nbt.int("42"), Some(42));
+    /// ```
+    pub fn build_key_index(&mut self) {
+        let compound = NbtCompound {
+            element: self.tapes.main.as_ptr(),
+            extra_tapes: &self.tapes.extra,
+        };
+        let index = KeyIndex::build(compound);
+        self.tapes.extra.key_index = Some(index);
+    }
+    /// Get a nested tag with a path like `Level.Sections[3].Palette[0].Name`.
+    /// See [`NbtCompound::get_path`].
+    pub fn get_path<'tape>(&'a self, path: &str) -> Option<NbtTag<'a, 'tape>> {
+        self.as_compound().get_path(path)
+    }
     /// Returns whether there is a tag with the given name.
     pub fn contains(&'a self, key: &str) -> bool {
         self.as_compound().contains(key)
@@ -204,6 +437,9 @@ impl<'a> BaseNbt<'a> {
     pub fn byte(&self, name: &str) -> Option<i8> {
         self.as_compound().byte(name)
     }
+    pub fn ubyte(&self, name: &str) -> Option<u8> {
+        self.as_compound().ubyte(name)
+    }
     pub fn short(&self, name: &str) -> Option<i16> {
         self.as_compound().short(name)
     }
@@ -237,11 +473,71 @@ impl<'a> BaseNbt<'a> {
     pub fn long_array(&self, name: &str) -> Option<Vec<i64>> {
         self.as_compound().long_array(name)
     }
+    pub fn int_array_raw(&'a self, name: &str) -> Option<RawList<'a, i32>> {
+        self.as_compound().int_array_raw(name)
+    }
+    pub fn long_array_raw(&'a self, name: &str) -> Option<RawList<'a, i64>> {
+        self.as_compound().long_array_raw(name)
+    }
+    pub fn long_array_packed(&'a self, name: &str, bits_per_entry: u32) -> Option<PackedBits<'a>> {
+        self.as_compound().long_array_packed(name, bits_per_entry)
+    }
+
+    pub fn byte_list(&'a self, name: &str) -> Option<&'a [i8]> {
+        self.as_compound().byte_list(name)
+    }
+    pub fn short_list(&self, name: &str) -> Option<Vec<i16>> {
+        self.as_compound().short_list(name)
+    }
+    pub fn int_list(&self, name: &str) -> Option<Vec<i32>> {
+        self.as_compound().int_list(name)
+    }
+    pub fn long_list(&self, name: &str) -> Option<Vec<i64>> {
+        self.as_compound().long_list(name)
+    }
+    pub fn float_list(&self, name: &str) -> Option<Vec<f32>> {
+        self.as_compound().float_list(name)
+    }
+    pub fn double_list(&self, name: &str) -> Option<Vec<f64>> {
+        self.as_compound().double_list(name)
+    }
+    pub fn byte_array_list(&'a self, name: &str) -> Option<&'a [&'a [u8]]> {
+        self.as_compound().byte_array_list(name)
+    }
+    pub fn string_list(&'a self, name: &str) -> Option<&'a [&'a Mutf8Str]> {
+        self.as_compound().string_list(name)
+    }
+    pub fn list_list(&'a self, name: &str) -> Option<NbtListList<'a, 'a>> {
+        self.as_compound().list_list(name)
+    }
+    pub fn compound_list(&'a self, name: &str) -> Option<NbtCompoundList<'a, 'a>> {
+        self.as_compound().compound_list(name)
+    }
+    pub fn int_array_list(&'a self, name: &str) -> Option<&'a [RawList<'a, i32>]> {
+        self.as_compound().int_array_list(name)
+    }
+    pub fn long_array_list(&'a self, name: &str) -> Option<&'a [RawList<'a, i64>]> {
+        self.as_compound().long_array_list(name)
+    }
+
+    /// Returns the serialized size of every key in the root compound, sorted
+    /// from largest to smallest. See [`NbtCompound::key_sizes`] for more
+    /// details.
+    pub fn key_sizes(&self, max_depth: usize) -> Vec<KeySize> {
+        self.as_compound().key_sizes(max_depth)
+    }
 }
 
 impl Debug for BaseNbt<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("BaseNbt").finish()
+        let compound = NbtCompound {
+            element: self.tapes.main.as_ptr(),
+            extra_tapes: &self.tapes.extra,
+        };
+        f.debug_struct("BaseNbt")
+            .field("name", &self.name)
+            .field("tag", &compound)
+            .finish()
     }
 }
 
@@ -265,16 +561,17 @@ impl<'a, 'tape> From<&'a BaseNbtCompound<'a>> for NbtCompound<'a, 'tape> {
 /// A nameless NBT tag.
 pub struct BaseNbtTag<'a> {
     tapes: Tapes<'a>,
+    /// The offset of the tag's payload in the original data.
+    start: usize,
 }
 impl<'a> BaseNbtTag<'a> {
     pub fn as_tag<'tape>(&'a self) -> NbtTag<'a, 'tape>
     where
         'a: 'tape,
     {
-        NbtTag {
-            element: self.tapes.main.as_ptr(),
-            extra_tapes: &self.tapes.extra,
-        }
+        NbtTag::from_tape(self.tapes.main.as_ptr(), &self.tapes.extra, unsafe {
+            self.tapes.extra.input.as_ptr().add(self.start)
+        })
     }
 }
 impl<'a> From<&'a BaseNbtTag<'a>> for NbtTag<'a, '_> {
@@ -292,14 +589,60 @@ pub enum Nbt<'a> {
 
 impl<'a> Nbt<'a> {
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
         match self {
-            Nbt::Some(nbt) => nbt.write(data),
+            Nbt::Some(nbt) => nbt.write_with_opts(data, opts),
             Nbt::None => {
                 data.push(END_ID);
             }
         }
     }
 
+    /// Write the NBT to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// Write the NBT to an [`io::Write`] like [`Self::write`]. See
+    /// [`BaseNbt::write_to`].
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the NBT like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        match self {
+            Nbt::Some(nbt) => nbt.write_to_with_opts(out, opts),
+            Nbt::None => {
+                let mut writer = ChunkedWriter::new(out, opts);
+                writer.push(END_ID);
+                writer.finish()
+            }
+        }
+    }
+
+    /// Write the NBT to a new buffer and compress it. Use this for files like
+    /// `level.dat`, which are gzipped.
+    #[cfg(feature = "compression")]
+    pub fn to_compressed_bytes(&self, compression: Compression) -> Vec<u8> {
+        compression.compress(self.to_bytes())
+    }
+
+    /// Write the NBT like [`Self::write`], but little-endian. See
+    /// [`read_le`].
+    pub fn write_le(&self, data: &mut Vec<u8>) {
+        let start = data.len();
+        self.write(data);
+        convert_nbt(&mut data[start..], Endian::Big).expect("we just wrote valid NBT");
+    }
+
     pub fn unwrap(self) -> BaseNbt<'a> {
         match self {
             Nbt::Some(nbt) => nbt,
@@ -328,21 +671,105 @@ impl PartialEq for BaseNbt<'_> {
 }
 
 impl BaseNbt<'_> {
+    /// Copy the NBT into an allocator, like an arena. See [`crate::arena`].
+    #[cfg(feature = "nightly")]
+    pub fn to_owned_in<A: Allocator + Clone>(&self, alloc: A) -> arena::BaseNbt<A> {
+        arena::BaseNbt::new(
+            arena::copy_in(self.name.as_bytes(), alloc.clone()),
+            self.as_compound().to_owned_in(alloc),
+        )
+    }
+
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
         data.push(COMPOUND_ID);
         write_string(data, self.name);
-        self.as_compound().write(data);
-        data.push(END_ID);
+        self.as_compound().write_with_opts(data, opts);
+    }
+
+    /// Write the NBT to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// Write the NBT to an [`io::Write`] like [`Self::write`].
+    ///
+    /// The data is written in chunks as it's serialized, so the whole
+    /// document never has to be in memory at once. The writer doesn't need to
+    /// be buffered.
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the NBT like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        let mut writer = ChunkedWriter::new(out, opts);
+        writer.base(self.name);
+        writer.borrowed_compound(&self.as_compound())?;
+        writer.finish()
+    }
+
+    /// Write the NBT to a new buffer and compress it. Use this for files like
+    /// `level.dat`, which are gzipped.
+    #[cfg(feature = "compression")]
+    pub fn to_compressed_bytes(&self, compression: Compression) -> Vec<u8> {
+        compression.compress(self.to_bytes())
+    }
+
+    /// Write the NBT like [`Self::write`], but little-endian. See
+    /// [`read_le`].
+    pub fn write_le(&self, data: &mut Vec<u8>) {
+        let start = data.len();
+        self.write(data);
+        convert_nbt(&mut data[start..], Endian::Big).expect("we just wrote valid NBT");
+    }
+
+    /// Write the NBT with the keys of every compound sorted. See
+    /// [`NbtCompound::write_canonical`].
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        data.push(COMPOUND_ID);
+        write_string(data, self.name);
+        self.as_compound().write_canonical(data);
     }
 }
 
-#[derive(Debug)]
 pub struct NbtTag<'a: 'tape, 'tape> {
-    element: *const TapeElement,
+    /// The tape element for this tag. Tags inside of lists that aren't lists
+    /// of lists or compounds don't have an element on the tape, so their
+    /// element is made up when they're iterated over.
+    element: TapeElement,
+    /// A pointer to the element on the tape, or null if the element was made
+    /// up. This is only used for compounds and lists, since their contents
+    /// come after them on the tape.
+    tape: *const TapeElement,
     extra_tapes: &'tape ExtraTapes<'a>,
+    /// A pointer to the first byte of the tag's payload in the original data,
+    /// or null if it's unknown.
+    start: *const u8,
 }
 
 impl<'a: 'tape, 'tape> NbtTag<'a, 'tape> {
+    /// Create a tag for an element that's on the tape.
+    pub(crate) fn from_tape(
+        element: *const TapeElement,
+        extra_tapes: &'tape ExtraTapes<'a>,
+        start: *const u8,
+    ) -> Self {
+        NbtTag {
+            element: unsafe { *element },
+            tape: element,
+            extra_tapes,
+            start,
+        }
+    }
+
     /// Get the numerical ID of the tag type.
     #[inline]
     pub fn id(&self) -> u8 {
@@ -363,91 +790,250 @@ impl<'a: 'tape, 'tape> NbtTag<'a, 'tape> {
         }
     }
 
+    /// Get the type of the tag.
+    pub fn tag_type(&self) -> TagType {
+        match self.element().kind() {
+            TapeTagKind::Byte => TagType::Byte,
+            TapeTagKind::Short => TagType::Short,
+            TapeTagKind::Int => TagType::Int,
+            TapeTagKind::Long => TagType::Long,
+            TapeTagKind::Float => TagType::Float,
+            TapeTagKind::Double => TagType::Double,
+            TapeTagKind::ByteArray => TagType::ByteArray,
+            TapeTagKind::String => TagType::String,
+            TapeTagKind::Compound => TagType::Compound,
+            TapeTagKind::IntArray => TagType::IntArray,
+            TapeTagKind::LongArray => TagType::LongArray,
+            t if t.is_list() => TagType::List,
+            _ => unreachable!(),
+        }
+    }
+
     pub fn byte(&self) -> Option<i8> {
-        let el = self.element();
-        ensure_kind(el, TapeTagKind::Byte)?;
-        Some(el.u8() as i8)
+        ensure_kind(self.element(), TapeTagKind::Byte)?;
+        Some(unsafe { self.byte_unchecked() })
+    }
+    /// Get the byte as a `u8`, for values that are meant to be unsigned.
+    pub fn ubyte(&self) -> Option<u8> {
+        self.byte().map(|b| b as u8)
     }
     pub fn short(&self) -> Option<i16> {
-        let el = self.element();
-        ensure_kind(el, TapeTagKind::Short)?;
-        Some(el.u16() as i16)
+        ensure_kind(self.element(), TapeTagKind::Short)?;
+        Some(unsafe { self.short_unchecked() })
     }
     pub fn int(&self) -> Option<i32> {
-        let el = self.element();
-        ensure_kind(el, TapeTagKind::Int)?;
-        Some(el.u32() as i32)
+        ensure_kind(self.element(), TapeTagKind::Int)?;
+        Some(unsafe { self.int_unchecked() })
     }
     pub fn long(&self) -> Option<i64> {
+        ensure_kind(self.element(), TapeTagKind::Long)?;
+        Some(unsafe { self.long_unchecked() })
+    }
+    pub fn float(&self) -> Option<f32> {
+        ensure_kind(self.element(), TapeTagKind::Float)?;
+        Some(unsafe { self.float_unchecked() })
+    }
+    pub fn double(&self) -> Option<f64> {
+        ensure_kind(self.element(), TapeTagKind::Double)?;
+        Some(unsafe { self.double_unchecked() })
+    }
+    pub fn byte_array(&self) -> Option<&'a [u8]> {
+        ensure_kind(self.element(), TapeTagKind::ByteArray)?;
+        Some(unsafe { self.byte_array_unchecked() })
+    }
+    pub fn string(&self) -> Option<&'a Mutf8Str> {
+        ensure_kind(self.element(), TapeTagKind::String)?;
+        Some(unsafe { self.string_unchecked() })
+    }
+    pub fn list(&self) -> Option<NbtList<'a, 'tape>> {
+        if !self.element().kind().is_list() {
+            return None;
+        }
+        Some(unsafe { self.list_unchecked() })
+    }
+    pub fn compound(&self) -> Option<NbtCompound<'a, 'tape>> {
+        ensure_kind(self.element(), TapeTagKind::Compound)?;
+        Some(unsafe { self.compound_unchecked() })
+    }
+
+    /// Get the byte without checking the tag's type, for data that's already
+    /// been validated.
+    ///
+    /// # Safety
+    ///
+    /// The tag must be a byte.
+    #[inline]
+    pub unsafe fn byte_unchecked(&self) -> i8 {
+        let el = self.element();
+        debug_assert_eq!(el.kind(), TapeTagKind::Byte);
+        el.u8() as i8
+    }
+    /// # Safety
+    ///
+    /// The tag must be a short.
+    #[inline]
+    pub unsafe fn short_unchecked(&self) -> i16 {
+        let el = self.element();
+        debug_assert_eq!(el.kind(), TapeTagKind::Short);
+        el.u16() as i16
+    }
+    /// # Safety
+    ///
+    /// The tag must be an int.
+    #[inline]
+    pub unsafe fn int_unchecked(&self) -> i32 {
+        let el = self.element();
+        debug_assert_eq!(el.kind(), TapeTagKind::Int);
+        el.u32() as i32
+    }
+    /// # Safety
+    ///
+    /// The tag must be a long.
+    #[inline]
+    pub unsafe fn long_unchecked(&self) -> i64 {
         let el = self.element();
-        ensure_kind(el, TapeTagKind::Long)?;
+        debug_assert_eq!(el.kind(), TapeTagKind::Long);
         // longs are 64 bits so since we can't fit the kind and value in a
         // single element, we store a pointer to them instead
         let long_ptr = el.ptr::<UnalignedU64>();
-        Some(u64::from(unsafe { *long_ptr }).to_be() as i64)
+        u64::from(unsafe { *long_ptr }).to_be() as i64
     }
-    pub fn float(&self) -> Option<f32> {
+    /// # Safety
+    ///
+    /// The tag must be a float.
+    #[inline]
+    pub unsafe fn float_unchecked(&self) -> f32 {
         let el = self.element();
-        ensure_kind(el, TapeTagKind::Float)?;
-        Some(f32::from_bits(el.u32()))
+        debug_assert_eq!(el.kind(), TapeTagKind::Float);
+        f32::from_bits(el.u32())
     }
-    pub fn double(&self) -> Option<f64> {
+    /// # Safety
+    ///
+    /// The tag must be a double.
+    #[inline]
+    pub unsafe fn double_unchecked(&self) -> f64 {
         let el = self.element();
-        ensure_kind(el, TapeTagKind::Double)?;
+        debug_assert_eq!(el.kind(), TapeTagKind::Double);
         // see the comment above for longs, doubles are also 64 bits so the same applies
         let double_ptr = el.ptr::<UnalignedU64>();
-        Some(f64::from_bits(u64::from(unsafe { *double_ptr }).to_be()))
+        f64::from_bits(u64::from(unsafe { *double_ptr }).to_be())
     }
-    pub fn byte_array(&self) -> Option<&'a [u8]> {
+    /// # Safety
+    ///
+    /// The tag must be a byte array.
+    #[inline]
+    pub unsafe fn byte_array_unchecked(&self) -> &'a [u8] {
         let el = self.element();
-        ensure_kind(el, TapeTagKind::ByteArray)?;
+        debug_assert_eq!(el.kind(), TapeTagKind::ByteArray);
         let length_ptr = el.ptr::<UnalignedU32>();
         let length = u32::from(unsafe { *length_ptr });
         #[cfg(target_endian = "little")]
         let length = length.swap_bytes();
         let data_ptr = unsafe { length_ptr.add(1) as *const u8 };
-        Some(unsafe { std::slice::from_raw_parts(data_ptr, length as usize) })
+        unsafe { std::slice::from_raw_parts(data_ptr, length as usize) }
     }
-    pub fn string(&self) -> Option<&'a Mutf8Str> {
+    /// # Safety
+    ///
+    /// The tag must be a string.
+    #[inline]
+    pub unsafe fn string_unchecked(&self) -> &'a Mutf8Str {
         let el = self.element();
-        ensure_kind(el, TapeTagKind::String)?;
+        debug_assert_eq!(el.kind(), TapeTagKind::String);
         let length_ptr = el.ptr::<UnalignedU16>();
         let length = u16::from(unsafe { *length_ptr });
         #[cfg(target_endian = "little")]
         let length = length.swap_bytes();
         let data_ptr = unsafe { length_ptr.add(1) as *const u8 };
-        Some(unsafe { Mutf8Str::from_slice(std::slice::from_raw_parts(data_ptr, length as usize)) })
+        unsafe { Mutf8Str::from_slice(std::slice::from_raw_parts(data_ptr, length as usize)) }
     }
-    pub fn list(&self) -> Option<NbtList<'a, 'tape>> {
-        let el = self.element();
-        if !el.kind().is_list() {
-            return None;
-        }
-
-        Some(NbtList {
-            element: self.element,
+    /// # Safety
+    ///
+    /// The tag must be a list.
+    #[inline]
+    pub unsafe fn list_unchecked(&self) -> NbtList<'a, 'tape> {
+        debug_assert!(self.element().kind().is_list());
+        NbtList {
+            element: self.tape,
             extra_tapes: self.extra_tapes,
-        })
+        }
     }
-    pub fn compound(&self) -> Option<NbtCompound<'a, 'tape>> {
-        let el = self.element();
-        ensure_kind(el, TapeTagKind::Compound)?;
-
-        Some(NbtCompound {
-            element: self.element,
+    /// # Safety
+    ///
+    /// The tag must be a compound.
+    #[inline]
+    pub unsafe fn compound_unchecked(&self) -> NbtCompound<'a, 'tape> {
+        debug_assert_eq!(self.element().kind(), TapeTagKind::Compound);
+        NbtCompound {
+            element: self.tape,
             extra_tapes: self.extra_tapes,
-        })
+        }
     }
+
     pub fn int_array(&self) -> Option<Vec<i32>> {
-        list::u32_prefixed_list_to_vec(TapeTagKind::IntArray, self.element)
+        list::u32_prefixed_list_to_vec(TapeTagKind::IntArray, &self.element)
     }
     pub fn long_array(&self) -> Option<Vec<i64>> {
-        list::u32_prefixed_list_to_vec(TapeTagKind::LongArray, self.element)
+        list::u32_prefixed_list_to_vec(TapeTagKind::LongArray, &self.element)
+    }
+
+    /// Returns the int array without copying it or converting it from
+    /// big-endian, unlike [`Self::int_array`].
+    pub fn int_array_raw(&self) -> Option<RawList<'a, i32>> {
+        list::u32_prefixed_list_to_rawlist(TapeTagKind::IntArray, &self.element)
+    }
+    /// Returns the long array without copying it or converting it from
+    /// big-endian, unlike [`Self::long_array`].
+    pub fn long_array_raw(&self) -> Option<RawList<'a, i64>> {
+        list::u32_prefixed_list_to_rawlist(TapeTagKind::LongArray, &self.element)
+    }
+    /// Returns an iterator over an int array that doesn't allocate, unlike
+    /// [`Self::int_array`].
+    pub fn int_array_iter(&self) -> Option<RawListIter<'a, i32>> {
+        self.int_array_raw().map(|l| l.iter())
+    }
+    /// Returns an iterator over a long array that doesn't allocate, unlike
+    /// [`Self::long_array`].
+    pub fn long_array_iter(&self) -> Option<RawListIter<'a, i64>> {
+        self.long_array_raw().map(|l| l.iter())
+    }
+
+    /// Returns the bytes in the original data that encode this tag's payload.
+    /// This doesn't include the tag ID or the name.
+    ///
+    /// This is useful for copying a tag into another document or hashing it
+    /// without having to serialize it again.
+    ///
+    /// Returns `None` for compounds and lists that were gotten by iterating
+    /// over a list with [`NbtList::iter`], since where they start in the data
+    /// isn't recorded.
+    pub fn raw_bytes(&self) -> Option<&'a [u8]> {
+        Some(&self.extra_tapes.input[self.byte_range()?])
+    }
+
+    /// Returns the position of the tag's payload in the original data. The
+    /// range is relative to the start of the whole slice that was read from,
+    /// not the position that the cursor was at.
+    ///
+    /// See [`Self::raw_bytes`].
+    pub fn byte_range(&self) -> Option<Range<usize>> {
+        if self.start.is_null() {
+            return None;
+        }
+        let start = self.start as usize - self.extra_tapes.input.as_ptr() as usize;
+        Some(start..start + profile::tag_size(self))
+    }
+
+    /// Get the tag's ID and payload without parsing them again, like
+    /// [`Self::raw_bytes`].
+    ///
+    /// Returns `None` in the same cases as [`Self::raw_bytes`].
+    pub fn raw(&self) -> Option<RawTag<'a>> {
+        Some(RawTag::new(self.id(), self.raw_bytes()?))
     }
 
     /// Get the tape element for this tag.
     fn element(&self) -> TapeElement {
-        unsafe { *self.element }
+        self.element
     }
 
     pub fn to_owned(&self) -> crate::owned::NbtTag {
@@ -473,6 +1059,38 @@ impl<'a: 'tape, 'tape> NbtTag<'a, 'tape> {
             _ => unreachable!(),
         }
     }
+
+    /// Copy the tag into an allocator, like an arena. See [`crate::arena`].
+    #[cfg(feature = "nightly")]
+    pub fn to_owned_in<A: Allocator + Clone>(&self, alloc: A) -> arena::NbtTag<A> {
+        let el = self.element();
+
+        match el.kind() {
+            TapeTagKind::Byte => arena::NbtTag::Byte(self.byte().unwrap()),
+            TapeTagKind::Short => arena::NbtTag::Short(self.short().unwrap()),
+            TapeTagKind::Int => arena::NbtTag::Int(self.int().unwrap()),
+            TapeTagKind::Long => arena::NbtTag::Long(self.long().unwrap()),
+            TapeTagKind::Float => arena::NbtTag::Float(self.float().unwrap()),
+            TapeTagKind::Double => arena::NbtTag::Double(self.double().unwrap()),
+            TapeTagKind::ByteArray => {
+                arena::NbtTag::ByteArray(arena::copy_in(self.byte_array().unwrap(), alloc))
+            }
+            TapeTagKind::String => {
+                arena::NbtTag::String(arena::copy_in(self.string().unwrap().as_bytes(), alloc))
+            }
+            TapeTagKind::Compound => {
+                arena::NbtTag::Compound(self.compound().unwrap().to_owned_in(alloc))
+            }
+            kind if kind.is_list() => arena::NbtTag::List(self.list().unwrap().to_owned_in(alloc)),
+            TapeTagKind::IntArray => {
+                arena::NbtTag::IntArray(arena::collect_in(self.int_array_iter().unwrap(), alloc))
+            }
+            TapeTagKind::LongArray => {
+                arena::NbtTag::LongArray(arena::collect_in(self.long_array_iter().unwrap(), alloc))
+            }
+            _ => unreachable!(),
+        }
+    }
 }
 
 fn ensure_kind(el: TapeElement, other: TapeTagKind) -> Option<()> {
@@ -500,8 +1118,8 @@ impl PartialEq for NbtTag<'_, '_> {
             TapeTagKind::ByteArray => self.byte_array().unwrap() == other.byte_array().unwrap(),
             TapeTagKind::String => self.string().unwrap() == other.string().unwrap(),
             TapeTagKind::Compound => self.compound().unwrap() == other.compound().unwrap(),
-            TapeTagKind::IntArray => self.int_array().unwrap() == other.int_array().unwrap(),
-            TapeTagKind::LongArray => self.long_array().unwrap() == other.long_array().unwrap(),
+            TapeTagKind::IntArray => self.int_array_raw() == other.int_array_raw(),
+            TapeTagKind::LongArray => self.long_array_raw() == other.long_array_raw(),
             t if t.is_list() => self.list().unwrap() == other.list().unwrap(),
             _ => unreachable!(),
         }
@@ -516,6 +1134,7 @@ mod tests {
     use flate2::read::GzDecoder;
 
     use super::*;
+    use crate::{owned, test_fixtures};
 
     #[test]
     fn hello_world() {
@@ -562,6 +1181,30 @@ mod tests {
         assert_eq!(nbt.list("Rotation").unwrap().floats().unwrap().len(), 2);
     }
 
+    #[test]
+    fn get_keys_of_every_length() {
+        let keys = (0..40)
+            .map(|len| "abcdefghijklmnopqrstuvwxyz0123456789_-+:"[..len].to_owned())
+            .collect::<Vec<_>>();
+        let mut compound = owned::NbtCompound::new();
+        for (i, key) in keys.iter().enumerate() {
+            compound.insert(key.as_str(), i as i32);
+        }
+        // the last keys are close enough to the end that they can't be loaded
+        // 16 bytes at a time
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(nbt.int(key), Some(i as i32), "{key}");
+        }
+        assert!(!nbt.contains("abcdefghijklmnopqrstuvwxyz0123456789_-+:!"));
+        assert!(!nbt.contains("abcdefghijklmnopqrstuvwxyz0123456789_-+;"));
+        assert!(!nbt.contains("abcdefghijklmnoq"));
+        assert!(!nbt.contains("b"));
+        assert_eq!(nbt.int("\0"), None);
+    }
+
     #[test]
     fn read_hypixel() {
         let src = include_bytes!("../../tests/hypixel.nbt").to_vec();
@@ -711,6 +1354,30 @@ mod tests {
         nbt.as_compound().to_owned();
     }
     #[test]
+    fn get_int_and_long_arrays_raw() {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("ints", owned::NbtTag::IntArray(vec![1, -2, 3]));
+        compound.insert("longs", owned::NbtTag::LongArray(vec![i64::MIN, 5]));
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        let ints = nbt.int_array_raw("ints").unwrap();
+        assert_eq!(ints.len(), 3);
+        assert_eq!(ints.to_vec(), [1, -2, 3]);
+        assert_eq!(
+            nbt.get("ints")
+                .unwrap()
+                .int_array_iter()
+                .unwrap()
+                .rev()
+                .collect::<Vec<_>>(),
+            [3, -2, 1]
+        );
+        assert_eq!(nbt.long_array_raw("longs").unwrap().to_vec(), [i64::MIN, 5]);
+        assert!(nbt.int_array_raw("longs").is_none());
+        assert!(nbt.get("ints").unwrap().long_array_iter().is_none());
+    }
+    #[test]
     fn list_of_empty_lists() {
         // found from fuzzing
         // BaseNbt { name: m"", tag: NbtTag::NbtCompound { m"":
@@ -741,4 +1408,510 @@ mod tests {
 
         assert_eq!(nbt.as_compound().len(), nbt.as_compound().iter().count());
     }
+
+    #[test]
+    fn typed_list_getters() {
+        let decoded_src = test_fixtures::complex_player();
+        let nbt = super::read(&mut Cursor::new(&decoded_src))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(nbt.float_list("Rotation").unwrap().len(), 2);
+        assert_eq!(nbt.double_list("Pos").unwrap().len(), 3);
+        assert_eq!(
+            nbt.compound_list("Inventory").unwrap().len(),
+            nbt.list("Inventory").unwrap().compounds().unwrap().len()
+        );
+        assert_eq!(nbt.as_compound().double_list("Rotation"), None);
+        assert_eq!(nbt.string_list("Pos"), None);
+        assert_eq!(nbt.int_list("missing"), None);
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let decoded_src = test_fixtures::complex_player();
+        let nbt = super::read(&mut Cursor::new(&decoded_src))
+            .unwrap()
+            .unwrap();
+
+        // re-encoding every key with its raw bytes should give us the original data
+        let mut data = Vec::new();
+        data.push(COMPOUND_ID);
+        write_string(&mut data, nbt.name());
+        for (name, tag) in nbt.as_compound().iter() {
+            data.push(tag.id());
+            write_string(&mut data, name);
+            data.extend_from_slice(tag.raw_bytes().unwrap());
+        }
+        data.push(END_ID);
+        assert_eq!(data, decoded_src);
+
+        let fly_speed = nbt.compound("abilities").unwrap().get("flySpeed").unwrap();
+        assert_eq!(fly_speed.raw_bytes().unwrap(), 0.05f32.to_be_bytes());
+    }
+
+    #[test]
+    fn raw_bytes_of_tag_with_offset() {
+        let mut data = vec![0xff, 0xff];
+        data.write_u8(LIST_ID).unwrap();
+        data.write_u8(INT_ID).unwrap();
+        data.write_u32::<BE>(2).unwrap();
+        data.write_i32::<BE>(1).unwrap();
+        data.write_i32::<BE>(2).unwrap();
+        data.push(0xff);
+
+        let mut cursor = Cursor::new(&data[..]);
+        cursor.set_position(2);
+        let tag = super::read_tag(&mut cursor).unwrap();
+        let tag = tag.as_tag();
+        assert_eq!(tag.byte_range(), Some(3..3 + 1 + 4 + 4 * 2));
+        assert_eq!(tag.raw_bytes(), Some(&data[3..data.len() - 1]));
+    }
+
+    #[test]
+    fn write_every_list_type() {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert(
+            "byte_arrays",
+            owned::NbtList::ByteArray(vec![vec![1, 2], vec![]]),
+        );
+        compound.insert(
+            "strings",
+            owned::NbtList::String(vec!["a".into(), "bc".into()]),
+        );
+        compound.insert(
+            "lists",
+            owned::NbtList::List(vec![owned::NbtList::Int(vec![1]), owned::NbtList::Empty]),
+        );
+        compound.insert(
+            "int_arrays",
+            owned::NbtList::IntArray(vec![vec![1, 2], vec![3]]),
+        );
+        compound.insert(
+            "long_arrays",
+            owned::NbtList::LongArray(vec![vec![1], vec![]]),
+        );
+        let mut data = Vec::new();
+        owned::BaseNbt::new("root", compound).write(&mut data);
+
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let mut out = Vec::new();
+        nbt.write(&mut out);
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn write_canonical() {
+        let item = |count: i8| {
+            let mut item = owned::NbtCompound::new();
+            item.insert("id", "minecraft:stone");
+            item.insert("Count", count);
+            item
+        };
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("b", 1);
+        compound.insert("a", owned::NbtList::Compound(vec![item(1), item(2)]));
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", compound).write(&mut data);
+
+        let sorted_item = |count: i8| {
+            let mut item = owned::NbtCompound::new();
+            item.insert("Count", count);
+            item.insert("id", "minecraft:stone");
+            item
+        };
+        let mut sorted = owned::NbtCompound::new();
+        sorted.insert(
+            "a",
+            owned::NbtList::Compound(vec![sorted_item(1), sorted_item(2)]),
+        );
+        sorted.insert("b", 1);
+        let mut expected = Vec::new();
+        owned::BaseNbt::new("", sorted).write(&mut expected);
+
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let mut out = Vec::new();
+        nbt.write_canonical(&mut out);
+        assert_eq!(out, expected);
+
+        // writing it again shouldn't change anything
+        let nbt = super::read(&mut Cursor::new(&out)).unwrap().unwrap();
+        let mut out_again = Vec::new();
+        nbt.write_canonical(&mut out_again);
+        assert_eq!(out_again, expected);
+    }
+
+    #[test]
+    fn iter_every_list_type() {
+        let lists = vec![
+            owned::NbtList::Empty,
+            owned::NbtList::Byte(vec![1, -2]),
+            owned::NbtList::Short(vec![1, -2]),
+            owned::NbtList::Int(vec![1, -2]),
+            owned::NbtList::Long(vec![1, -2]),
+            owned::NbtList::Float(vec![1., -2.5]),
+            owned::NbtList::Double(vec![1., -2.5]),
+            owned::NbtList::ByteArray(vec![vec![1, 2], vec![]]),
+            owned::NbtList::String(vec!["a".into(), "bc".into()]),
+            owned::NbtList::List(vec![owned::NbtList::Int(vec![1]), owned::NbtList::Empty]),
+            owned::NbtList::Compound(vec![owned::NbtCompound::new(), {
+                let mut compound = owned::NbtCompound::new();
+                compound.insert("a", 1);
+                compound
+            }]),
+            owned::NbtList::IntArray(vec![vec![1, 2], vec![3]]),
+            owned::NbtList::LongArray(vec![vec![1], vec![]]),
+        ];
+        let mut compound = owned::NbtCompound::new();
+        for (i, list) in lists.iter().enumerate() {
+            compound.insert(i.to_string(), list.clone());
+        }
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", compound).write(&mut data);
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        for (i, list) in lists.iter().enumerate() {
+            let borrowed = nbt.list(&i.to_string()).unwrap();
+            let tags = borrowed
+                .iter()
+                .map(|tag| tag.to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(tags, list.as_nbt_tags());
+
+            assert_eq!(borrowed.len(), tags.len());
+            assert_eq!(borrowed.is_empty(), tags.is_empty());
+            for (index, tag) in tags.iter().enumerate() {
+                assert_eq!(borrowed.get(index).unwrap().to_owned(), *tag);
+            }
+            assert!(borrowed.get(tags.len()).is_none());
+            assert!(borrowed.get(usize::MAX).is_none());
+        }
+
+        let ints = nbt.list("3").unwrap();
+        let second = ints.iter().nth(1).unwrap();
+        assert_eq!(second.int(), Some(-2));
+        assert_eq!(second.raw_bytes(), Some(&(-2i32).to_be_bytes()[..]));
+        let strings = nbt.list("8").unwrap();
+        assert_eq!(
+            strings.iter().last().unwrap().raw_bytes(),
+            Some(&b"\0\x02bc"[..])
+        );
+        let compounds = nbt.list("10").unwrap();
+        assert_eq!(compounds.iter().last().unwrap().raw_bytes(), None);
+    }
+
+    #[test]
+    fn unsigned_bytes() {
+        let mut nbt = owned::NbtCompound::new();
+        nbt.insert("SkyLight", -1i8);
+        nbt.insert("Biomes", owned::NbtList::Byte(vec![1, -128]));
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", nbt).write(&mut data);
+
+        let nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(nbt.ubyte("SkyLight"), Some(255));
+        assert_eq!(nbt.get("SkyLight").unwrap().ubyte(), Some(255));
+        assert_eq!(nbt.list("Biomes").unwrap().ubytes(), Some(&[1, 128][..]));
+        assert_eq!(nbt.ubyte("Biomes"), None);
+    }
+
+    #[test]
+    fn unchecked_getters() {
+        let mut nbt = owned::NbtCompound::new();
+        nbt.insert("id", "minecraft:stone");
+        nbt.insert("Count", 3i8);
+        nbt.insert("Time", 1i64 << 40);
+        nbt.insert("Pos", owned::NbtList::Double(vec![1., 2.]));
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", nbt).write(&mut data);
+
+        let nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let nbt = nbt.as_compound();
+        unsafe {
+            assert_eq!(nbt.string_unchecked("id"), nbt.string("id"));
+            assert_eq!(nbt.byte_unchecked("Count"), Some(3));
+            assert_eq!(nbt.long_unchecked("Time"), Some(1 << 40));
+            assert_eq!(
+                nbt.list_unchecked("Pos").unwrap().doubles(),
+                Some(vec![1., 2.])
+            );
+            assert_eq!(nbt.int_unchecked("Missing"), None);
+            assert_eq!(nbt.get("Count").unwrap().byte_unchecked(), 3);
+        }
+    }
+
+    #[test]
+    fn numeric_list_iterators() {
+        let mut nbt = owned::NbtCompound::new();
+        nbt.insert("Heights", owned::NbtList::Float(vec![62., 70.5, 64.]));
+        nbt.insert("Pos", owned::NbtList::Double(vec![1., 2.]));
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", nbt).write(&mut data);
+
+        let nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let heights = nbt.list("Heights").unwrap();
+        let max = heights.floats_iter().unwrap().fold(f32::MIN, f32::max);
+        assert_eq!(max, 70.5);
+        assert_eq!(heights.floats_iter().unwrap().len(), 3);
+        assert!(heights.doubles_iter().is_none());
+        assert_eq!(
+            nbt.list("Pos")
+                .unwrap()
+                .doubles_iter()
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [1., 2.]
+        );
+    }
+
+    #[test]
+    fn write_empty_lists() {
+        let mut nbt = owned::NbtCompound::new();
+        nbt.insert("Pos", owned::NbtList::Double(vec![]));
+        nbt.insert(
+            "Nested",
+            owned::NbtList::List(vec![owned::NbtList::Int(vec![])]),
+        );
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", nbt).write(&mut data);
+        let nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        let mut preserved = Vec::new();
+        nbt.write(&mut preserved);
+        assert_eq!(preserved, data);
+
+        let mut normalized = Vec::new();
+        let opts = WriteOptions {
+            empty_lists: crate::EmptyLists::End,
+        };
+        nbt.write_with_opts(&mut normalized, &opts);
+        let normalized = crate::owned::read(&mut Cursor::new(&normalized))
+            .unwrap()
+            .unwrap();
+        assert_eq!(normalized.list("Pos"), Some(&owned::NbtList::Empty));
+        assert_eq!(
+            normalized.list("Nested"),
+            Some(&owned::NbtList::List(vec![owned::NbtList::Empty]))
+        );
+    }
+
+    #[test]
+    fn key_index() {
+        let mut big = owned::NbtCompound::new();
+        for i in (0..50).rev() {
+            big.insert(format!("key{i}"), i);
+        }
+        // `get` returns the first tag with a name
+        big.insert("key7", -1);
+        let mut root = owned::NbtCompound::new();
+        root.insert("small", 1);
+        root.insert("big", big.clone());
+        root.insert(
+            "lists",
+            owned::NbtList::List(vec![owned::NbtList::Compound(vec![big])]),
+        );
+        let data = owned::BaseNbt::new("", root).to_bytes();
+
+        let mut nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let before = nbt.as_compound().to_owned();
+        nbt.build_key_index();
+        assert_eq!(nbt.as_compound().to_owned(), before);
+
+        assert_eq!(nbt.int("small"), Some(1));
+        let nested = nbt
+            .list("lists")
+            .and_then(|list| list.lists())
+            .and_then(|lists| lists.get(0))
+            .and_then(|list| list.compounds())
+            .and_then(|compounds| compounds.get(0))
+            .unwrap();
+        for compound in [nbt.compound("big").unwrap(), nested] {
+            for i in 0..50 {
+                assert_eq!(compound.int(&format!("key{i}")), Some(i));
+            }
+            assert_eq!(compound.get("key50"), None);
+            assert!(compound.contains("key0"));
+            assert!(!compound.contains("key"));
+        }
+    }
+
+    #[test]
+    fn eq_owned() {
+        let mut inner = owned::NbtCompound::new();
+        inner.insert("a", owned::NbtTag::IntArray(vec![1, 2]));
+        let mut root = owned::NbtCompound::new();
+        root.insert("byte", 1i8);
+        root.insert("longs", owned::NbtList::Long(vec![1, 2, 3]));
+        root.insert("strings", owned::NbtList::String(vec!["x".into()]));
+        root.insert(
+            "lists",
+            owned::NbtList::List(vec![owned::NbtList::Empty, owned::NbtList::Byte(vec![1])]),
+        );
+        root.insert("compounds", owned::NbtList::Compound(vec![inner]));
+        root.insert("arrays", owned::NbtList::LongArray(vec![vec![], vec![4]]));
+        let base = owned::BaseNbt::new("root", root.clone());
+        let data = base.to_bytes();
+        let nbt = read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        assert!(nbt == base);
+        assert!(base == nbt);
+        assert!(nbt.as_compound() == root);
+        assert!(root == nbt.as_compound());
+        assert!(nbt.get("longs").unwrap() == *root.get("longs").unwrap());
+        assert!(nbt.get("byte").unwrap() != *root.get("longs").unwrap());
+        assert!(nbt.as_compound() != owned::NbtCompound::new());
+        assert!(nbt != owned::BaseNbt::new("", root.clone()));
+
+        let mut changed = [
+            ("byte", owned::NbtTag::Short(1)),
+            (
+                "longs",
+                owned::NbtTag::List(owned::NbtList::Long(vec![1, 2])),
+            ),
+            (
+                "lists",
+                owned::NbtTag::List(owned::NbtList::List(vec![owned::NbtList::Empty])),
+            ),
+            (
+                "arrays",
+                owned::NbtTag::List(owned::NbtList::LongArray(vec![vec![], vec![5]])),
+            ),
+        ];
+        for (name, tag) in changed.iter_mut() {
+            let mut other = root.clone();
+            std::mem::swap(other.get_mut(name).unwrap(), tag);
+            assert!(nbt.as_compound() != other, "{name}");
+        }
+        let mut extra = root.clone();
+        extra.insert("extra", 0);
+        assert!(nbt.as_compound() != extra && extra != nbt.as_compound());
+    }
+
+    #[test]
+    fn read_slice() {
+        let mut data = owned::BaseNbt::new("", crate::compound! { "a": [1, 2] }).to_bytes();
+        let len = data.len();
+        data.extend_from_slice(&[1, 2, 3]);
+
+        let (nbt, read_len) = super::read_slice(&data).unwrap();
+        assert_eq!(read_len, len);
+        assert_eq!(nbt.unwrap().list("a").unwrap().ints(), Some(vec![1, 2]));
+
+        let (nbt, read_len) = super::read_slice(&[END_ID, 1]).unwrap();
+        assert!(nbt.is_none());
+        assert_eq!(read_len, 1);
+
+        assert_eq!(
+            super::read_slice(&data[..len - 1]).unwrap_err(),
+            Error::UnexpectedEof
+        );
+        let options = ReadOptions {
+            max_tags: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            read_slice_with_opts(&data, &options).unwrap_err(),
+            Error::TooManyTags
+        );
+    }
+
+    #[test]
+    fn get_as() {
+        let data = owned::BaseNbt::new(
+            "",
+            crate::compound! { "Rotation": [1.5f32, -2f32], "Name": "a", "Count": 3i8 },
+        )
+        .to_bytes();
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let nbt = nbt.as_compound();
+
+        assert_eq!(nbt.get_as::<Vec<f32>>("Rotation"), Some(vec![1.5, -2.]));
+        assert_eq!(nbt.get_as::<String>("Name"), Some("a".to_owned()));
+        assert_eq!(nbt.get_as::<i8>("Count"), Some(3));
+        assert_eq!(nbt.get_as::<i32>("Name"), None);
+        assert_eq!(nbt.get_as::<i8>("Missing"), None);
+    }
+
+    #[test]
+    fn key_longer_than_u16() {
+        // 70000 as a u16 is 4464, so a needle with a truncated length would
+        // match this key and then read past the end of the input
+        let short = "a".repeat(70000 - 65536);
+        let mut compound = owned::NbtCompound::new();
+        compound.insert(short.as_str(), 1);
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = super::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        let long = "a".repeat(70000);
+        assert_eq!(nbt.int(&short), Some(1));
+        assert!(nbt.get(&long).is_none());
+        assert!(!nbt.contains(&long));
+    }
+
+    #[test]
+    fn cursor_past_the_end() {
+        let data = owned::BaseNbt::new("", crate::compound! { "a": 1 }).to_bytes();
+        let mut cursor = Cursor::new(&data[..]);
+        cursor.set_position(data.len() as u64 + 10);
+        assert_eq!(super::read(&mut cursor).unwrap_err(), Error::UnexpectedEof);
+        assert_eq!(
+            super::read_unnamed(&mut cursor).unwrap_err(),
+            Error::UnexpectedEof
+        );
+        let mut reader = ReaderFromCursor::new(&mut cursor);
+        assert!(reader.read_u8().is_err());
+    }
+
+    #[test]
+    fn unnamed_reads_with_opts() {
+        // a compound with `"a": 1` and then `"a": 2`, and a list inside of it
+        let compound = [
+            3, 0, 1, b'a', 0, 0, 0, 1, 3, 0, 1, b'a', 0, 0, 0, 2, 9, 0, 1, b'l', 1, 0, 0, 0, 0, 0,
+        ];
+        let mut tag = vec![COMPOUND_ID];
+        tag.extend(compound);
+
+        let duplicate_keys = ReadOptions {
+            duplicate_keys: crate::DuplicateKeyPolicy::Error,
+            ..Default::default()
+        };
+        assert_eq!(
+            read_compound_with_opts(&mut Cursor::new(&compound), &duplicate_keys).err(),
+            Some(Error::DuplicateKey)
+        );
+        assert_eq!(
+            read_tag_with_opts(&mut Cursor::new(&tag), &duplicate_keys).err(),
+            Some(Error::DuplicateKey)
+        );
+        assert_eq!(
+            read_unnamed_with_opts(&mut Cursor::new(&tag), &duplicate_keys).unwrap_err(),
+            Error::DuplicateKey
+        );
+
+        let last = ReadOptions {
+            duplicate_keys: crate::DuplicateKeyPolicy::Last,
+            ..Default::default()
+        };
+        let read = read_optional_tag_with_opts(&mut Cursor::new(&tag), &last)
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.as_tag().compound().unwrap().int("a"), Some(2));
+
+        let max_depth = ReadOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            read_tag_with_opts(&mut Cursor::new(&tag), &max_depth).err(),
+            Some(Error::MaxDepthExceeded)
+        );
+        let max_bytes = ReadOptions {
+            max_bytes: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            read_unnamed_with_opts(&mut Cursor::new(&tag), &max_bytes).unwrap_err(),
+            Error::TooManyBytes
+        );
+    }
 }
@@ -0,0 +1,138 @@
+//! Comparing borrowed NBT with owned NBT without converting either of them.
+//!
+//! Two values are equal if [`to_owned`](NbtCompound::to_owned) on the borrowed
+//! one would be equal to the owned one.
+
+use super::{list, tape::TapeTagKind, BaseNbt, NbtCompound, NbtList, NbtTag};
+use crate::{owned, raw_list::RawList, swap_endianness::SwappableNumber};
+
+impl PartialEq<owned::NbtCompound> for NbtCompound<'_, '_> {
+    fn eq(&self, other: &owned::NbtCompound) -> bool {
+        let mut other_iter = other.iter();
+        for (name, tag) in self.iter() {
+            match other_iter.next() {
+                Some((other_name, other_tag)) if name == other_name && tag == *other_tag => {}
+                _ => return false,
+            }
+        }
+        other_iter.next().is_none()
+    }
+}
+
+impl PartialEq<owned::NbtList> for NbtList<'_, '_> {
+    fn eq(&self, other: &owned::NbtList) -> bool {
+        match (self.element().kind(), other) {
+            (TapeTagKind::EmptyList, owned::NbtList::Empty) => true,
+            (TapeTagKind::ByteList, owned::NbtList::Byte(bytes)) => self.bytes().unwrap() == bytes,
+            (TapeTagKind::ShortList, owned::NbtList::Short(shorts)) => {
+                self.shorts_iter().unwrap().eq(shorts.iter().copied())
+            }
+            (TapeTagKind::IntList, owned::NbtList::Int(ints)) => {
+                self.ints_iter().unwrap().eq(ints.iter().copied())
+            }
+            (TapeTagKind::LongList, owned::NbtList::Long(longs)) => {
+                self.longs_iter().unwrap().eq(longs.iter().copied())
+            }
+            (TapeTagKind::FloatList, owned::NbtList::Float(floats)) => {
+                self.floats_iter().unwrap().eq(floats.iter().copied())
+            }
+            (TapeTagKind::DoubleList, owned::NbtList::Double(doubles)) => {
+                self.doubles_iter().unwrap().eq(doubles.iter().copied())
+            }
+            (TapeTagKind::ByteArrayList, owned::NbtList::ByteArray(arrays)) => {
+                slice_eq(self.byte_arrays().unwrap().iter().copied(), arrays)
+            }
+            (TapeTagKind::StringList, owned::NbtList::String(strings)) => self
+                .strings()
+                .unwrap()
+                .iter()
+                .copied()
+                .eq(strings.iter().map(|s| s.as_str())),
+            (TapeTagKind::ListList, owned::NbtList::List(lists)) => {
+                slice_eq(self.lists().unwrap(), lists)
+            }
+            (TapeTagKind::CompoundList, owned::NbtList::Compound(compounds)) => {
+                slice_eq(self.compounds().unwrap(), compounds)
+            }
+            (TapeTagKind::IntArrayList, owned::NbtList::IntArray(arrays)) => {
+                raw_lists_eq(self.int_arrays().unwrap(), arrays)
+            }
+            (TapeTagKind::LongArrayList, owned::NbtList::LongArray(arrays)) => {
+                raw_lists_eq(self.long_arrays().unwrap(), arrays)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<owned::NbtTag> for NbtTag<'_, '_> {
+    fn eq(&self, other: &owned::NbtTag) -> bool {
+        match other {
+            owned::NbtTag::Byte(byte) => self.byte() == Some(*byte),
+            owned::NbtTag::Short(short) => self.short() == Some(*short),
+            owned::NbtTag::Int(int) => self.int() == Some(*int),
+            owned::NbtTag::Long(long) => self.long() == Some(*long),
+            owned::NbtTag::Float(float) => self.float() == Some(*float),
+            owned::NbtTag::Double(double) => self.double() == Some(*double),
+            owned::NbtTag::ByteArray(bytes) => self.byte_array() == Some(bytes.as_slice()),
+            owned::NbtTag::String(string) => self.string() == Some(string.as_str()),
+            owned::NbtTag::List(list) => self.list().is_some_and(|l| l == *list),
+            owned::NbtTag::Compound(compound) => self.compound().is_some_and(|c| c == *compound),
+            owned::NbtTag::IntArray(ints) => {
+                list::u32_prefixed_list_to_rawlist(TapeTagKind::IntArray, &self.element)
+                    .is_some_and(|raw: RawList<i32>| raw.iter().eq(ints.iter().copied()))
+            }
+            owned::NbtTag::LongArray(longs) => {
+                list::u32_prefixed_list_to_rawlist(TapeTagKind::LongArray, &self.element)
+                    .is_some_and(|raw: RawList<i64>| raw.iter().eq(longs.iter().copied()))
+            }
+        }
+    }
+}
+
+impl PartialEq<owned::BaseNbt> for BaseNbt<'_> {
+    fn eq(&self, other: &owned::BaseNbt) -> bool {
+        self.name == other.name() && self.as_compound() == **other
+    }
+}
+
+/// Compare the items of an iterator with the items of a slice, without
+/// needing to know the length of the iterator beforehand.
+fn slice_eq<A: PartialEq<B>, B>(a: impl IntoIterator<Item = A>, b: &[B]) -> bool {
+    let mut b = b.iter();
+    a.into_iter().all(|a| b.next().is_some_and(|b| a == *b)) && b.next().is_none()
+}
+
+fn raw_lists_eq<T: Copy + SwappableNumber + PartialEq>(
+    raw: &[RawList<T>],
+    owned: &[Vec<T>],
+) -> bool {
+    raw.len() == owned.len()
+        && raw
+            .iter()
+            .zip(owned)
+            .all(|(raw, owned)| raw.iter().eq(owned.iter().copied()))
+}
+
+// and the same thing in the other direction
+
+impl PartialEq<NbtCompound<'_, '_>> for owned::NbtCompound {
+    fn eq(&self, other: &NbtCompound) -> bool {
+        other == self
+    }
+}
+impl PartialEq<NbtList<'_, '_>> for owned::NbtList {
+    fn eq(&self, other: &NbtList) -> bool {
+        other == self
+    }
+}
+impl PartialEq<NbtTag<'_, '_>> for owned::NbtTag {
+    fn eq(&self, other: &NbtTag) -> bool {
+        other == self
+    }
+}
+impl PartialEq<BaseNbt<'_>> for owned::BaseNbt {
+    fn eq(&self, other: &BaseNbt) -> bool {
+        other == self
+    }
+}
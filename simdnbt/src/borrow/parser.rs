@@ -0,0 +1,154 @@
+//! Reading many documents without allocating new tapes for each one.
+
+use std::{io::Cursor, mem, ops::Deref};
+
+use super::{read_root_compound, read_root_name, BaseNbt, Nbt, Tapes};
+use crate::{options::Limits, reader::ReaderFromCursor, Error, ReadOptions};
+
+/// Reads NBT like [`read_with_opts`](super::read_with_opts), but keeps the
+/// tapes around after the NBT is dropped so their memory can be reused by the
+/// next read.
+///
+/// This is useful when you're reading a lot of small documents in a loop, like
+/// packets, since otherwise most of the time can end up being spent on
+/// allocating and freeing the tapes. The tapes never shrink, so a parser that
+/// read one huge document will keep that memory until it's dropped.
+///
+/// ```
+/// # use std::io::Cursor;
+/// use simdnbt::{borrow::{Nbt, Parser}, compound, owned::BaseNbt};
+///
+/// let packets = (0..10)
+///     .map(|i| BaseNbt::new("", compound! { "id": i }).to_bytes())
+///     .collect::<Vec<_>>();
+///
+/// let mut parser = Parser::new();
+/// for (i, packet) in packets.iter().enumerate() {
+///     let nbt = parser.read(&mut Cursor::new(packet)).unwrap();
+///     let Nbt::Some(nbt) = &*nbt else { panic!() };
+///     assert_eq!(nbt.int("id"), Some(i as i32));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    /// The tapes from the last read, or `None` if nothing has been read yet.
+    tapes: Option<Tapes<'static>>,
+    options: ReadOptions,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a parser that reads with the given options. See
+    /// [`read_with_opts`](super::read_with_opts).
+    pub fn with_opts(options: ReadOptions) -> Self {
+        Self {
+            tapes: None,
+            options,
+        }
+    }
+
+    /// Read a normal root NBT compound like [`read`](super::read).
+    ///
+    /// The parser can't be used again until the returned NBT is dropped, and
+    /// then its tapes go back to the parser.
+    pub fn read<'p, 'a>(
+        &'p mut self,
+        data: &mut Cursor<&'a [u8]>,
+    ) -> Result<ParsedNbt<'p, 'a>, Error> {
+        let limits = Limits::new(&self.options);
+        let input = *data.get_ref();
+        let mut data = ReaderFromCursor::with_limits(data, &limits);
+        let Some(name) = read_root_name(&mut data).map_err(|err| data.map_err(err))? else {
+            return Ok(ParsedNbt {
+                parser: self,
+                nbt: Nbt::None,
+            });
+        };
+
+        let mut tapes = match self.tapes.take() {
+            Some(tapes) => tapes.recycle(input),
+            None => Tapes::new(input)?,
+        };
+        if let Err(err) = read_root_compound(&mut data, &mut tapes, &limits) {
+            self.tapes = Some(tapes.recycle(&[]));
+            return Err(data.map_err(err));
+        }
+
+        Ok(ParsedNbt {
+            parser: self,
+            nbt: Nbt::Some(BaseNbt { name, tapes }),
+        })
+    }
+}
+
+/// NBT that was read by a [`Parser`]. This derefs to [`Nbt`], and gives the
+/// tapes back to the parser when it's dropped.
+#[derive(Debug)]
+pub struct ParsedNbt<'p, 'a> {
+    parser: &'p mut Parser,
+    nbt: Nbt<'a>,
+}
+
+impl<'a> Deref for ParsedNbt<'_, 'a> {
+    type Target = Nbt<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.nbt
+    }
+}
+
+impl Drop for ParsedNbt<'_, '_> {
+    fn drop(&mut self) {
+        if let Nbt::Some(nbt) = mem::take(&mut self.nbt) {
+            self.parser.tapes = Some(nbt.tapes.recycle(&[]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{borrow, compound, owned, test_fixtures::complex_player};
+
+    #[test]
+    fn reuses_tapes() {
+        let complex = complex_player();
+        let small = owned::BaseNbt::new("a", compound! { "b": [1, 2], "c": "d" }).to_bytes();
+
+        let mut parser = Parser::new();
+        for data in [&complex, &small, &vec![0], &small, &complex] {
+            let expected = borrow::read(&mut Cursor::new(data)).unwrap();
+            let mut cursor = Cursor::new(&data[..]);
+            let nbt = parser.read(&mut cursor).unwrap();
+            assert_eq!(*nbt, expected);
+            assert_eq!(cursor.position(), data.len() as u64);
+        }
+        let capacity = parser.tapes.as_ref().unwrap().main.capacity();
+
+        // errors give the tapes back too
+        assert_eq!(
+            parser
+                .read(&mut Cursor::new(&small[..small.len() - 1]))
+                .unwrap_err(),
+            Error::UnexpectedEof
+        );
+        assert_eq!(parser.tapes.as_ref().unwrap().main.capacity(), capacity);
+        assert_eq!(*parser.read(&mut Cursor::new(&small)).unwrap(), {
+            borrow::read(&mut Cursor::new(&small)).unwrap()
+        });
+
+        let mut parser = Parser::with_opts(ReadOptions {
+            max_bytes: Some(small.len() - 1),
+            ..Default::default()
+        });
+        assert_eq!(
+            parser.read(&mut Cursor::new(&small)).unwrap_err(),
+            Error::TooManyBytes
+        );
+    }
+}
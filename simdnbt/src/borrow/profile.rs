@@ -0,0 +1,253 @@
+//! Tools for figuring out which parts of an NBT document are taking up the most
+//! space.
+
+use std::collections::HashMap;
+
+use super::{
+    list::{self, NbtList},
+    tape::TapeTagKind,
+    NbtCompound, NbtTag,
+};
+use crate::path;
+
+/// The serialized size of a key in a compound. This is returned by
+/// [`NbtCompound::key_sizes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySize {
+    /// The path to the key, separated by dots. Keys that are inside of a list
+    /// of compounds have `[]` appended to the name of the list (like
+    /// `Inventory[].tag`), and their sizes are summed up across every element
+    /// of the list. Keys are quoted like in an [`NbtPath`](crate::NbtPath) if
+    /// they have dots or other special characters in them.
+    pub path: String,
+    /// The number of bytes that the key takes up when serialized, including
+    /// the tag ID and the name.
+    pub size: usize,
+}
+
+pub(crate) fn key_sizes(compound: NbtCompound, max_depth: usize) -> Vec<KeySize> {
+    let mut sizes = HashMap::new();
+    if max_depth > 0 {
+        profile_compound(compound, "", max_depth, &mut sizes);
+    }
+
+    let mut sizes = sizes
+        .into_iter()
+        .map(|(path, size)| KeySize { path, size })
+        .collect::<Vec<_>>();
+    sizes.sort_unstable_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    sizes
+}
+
+/// Record the sizes of the keys in the compound and return the size of the
+/// compound's payload.
+fn profile_compound(
+    compound: NbtCompound,
+    prefix: &str,
+    depth_left: usize,
+    sizes: &mut HashMap<String, usize>,
+) -> usize {
+    let mut total = 0;
+    for (name, tag) in compound.iter() {
+        let mut path = prefix.to_owned();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path::write_key(&mut path, &name.to_string_lossy()).unwrap();
+
+        let payload_size = if depth_left > 1 {
+            if let Some(child) = tag.compound() {
+                profile_compound(child, &path, depth_left - 1, sizes)
+            } else if let Some(compounds) = tag.list().and_then(|list| list.compounds()) {
+                let element_prefix = format!("{path}[]");
+                // the tag id and length of the list
+                let mut list_size = 1 + 4;
+                for child in compounds {
+                    list_size += profile_compound(child, &element_prefix, depth_left - 1, sizes);
+                }
+                list_size
+            } else {
+                tag_size(&tag)
+            }
+        } else {
+            tag_size(&tag)
+        };
+
+        // the tag id, the name length, and the name
+        let entry_size = 1 + 2 + name.len() + payload_size;
+        *sizes.entry(path).or_default() += entry_size;
+        total += entry_size;
+    }
+    // the end tag
+    total + 1
+}
+
+/// Returns the number of bytes that the tag's payload takes up when
+/// serialized. This doesn't include the tag ID or name.
+pub(crate) fn tag_size(tag: &NbtTag) -> usize {
+    let el = tag.element();
+    match el.kind() {
+        TapeTagKind::Byte => 1,
+        TapeTagKind::Short => 2,
+        TapeTagKind::Int | TapeTagKind::Float => 4,
+        TapeTagKind::Long | TapeTagKind::Double => 8,
+        TapeTagKind::ByteArray => 4 + tag.byte_array().unwrap().len(),
+        TapeTagKind::String => 2 + tag.string().unwrap().len(),
+        TapeTagKind::Compound => compound_size(tag.compound().unwrap()),
+        TapeTagKind::IntArray => {
+            4 + list::u32_prefixed_list_to_rawlist::<i32>(TapeTagKind::IntArray, &tag.element)
+                .unwrap()
+                .as_big_endian()
+                .len()
+        }
+        TapeTagKind::LongArray => {
+            4 + list::u32_prefixed_list_to_rawlist::<i64>(TapeTagKind::LongArray, &tag.element)
+                .unwrap()
+                .as_big_endian()
+                .len()
+        }
+        kind if kind.is_list() => list_size(tag.list().unwrap()),
+        _ => unreachable!(),
+    }
+}
+
+fn compound_size(compound: NbtCompound) -> usize {
+    let mut size = 0;
+    for (name, tag) in compound.iter() {
+        size += 1 + 2 + name.len() + tag_size(&tag);
+    }
+    size + 1
+}
+
+fn list_size(list: NbtList) -> usize {
+    // the tag id of the elements and the length
+    let header_size = 1 + 4;
+    let el = list.element();
+    let elements_size = match el.kind() {
+        TapeTagKind::EmptyList => 0,
+        TapeTagKind::ByteList => list.bytes().unwrap().len(),
+        TapeTagKind::ShortList => raw_list_size::<i16>(list),
+        TapeTagKind::IntList => raw_list_size::<i32>(list),
+        TapeTagKind::LongList => raw_list_size::<i64>(list),
+        TapeTagKind::FloatList => raw_list_size::<f32>(list),
+        TapeTagKind::DoubleList => raw_list_size::<f64>(list),
+        TapeTagKind::ByteArrayList => list
+            .byte_arrays()
+            .unwrap()
+            .iter()
+            .map(|array| 4 + array.len())
+            .sum(),
+        TapeTagKind::StringList => list
+            .strings()
+            .unwrap()
+            .iter()
+            .map(|string| 2 + string.len())
+            .sum(),
+        TapeTagKind::ListList => list.lists().unwrap().into_iter().map(list_size).sum(),
+        TapeTagKind::CompoundList => list
+            .compounds()
+            .unwrap()
+            .into_iter()
+            .map(compound_size)
+            .sum(),
+        TapeTagKind::IntArrayList => list
+            .int_arrays()
+            .unwrap()
+            .iter()
+            .map(|array| 4 + array.as_big_endian().len())
+            .sum(),
+        TapeTagKind::LongArrayList => list
+            .long_arrays()
+            .unwrap()
+            .iter()
+            .map(|array| 4 + array.as_big_endian().len())
+            .sum(),
+        _ => unreachable!("this is an NbtList, no other kinds should be possible"),
+    };
+    header_size + elements_size
+}
+
+fn raw_list_size<T: Copy + crate::swap_endianness::SwappableNumber>(list: NbtList) -> usize {
+    list::u32_prefixed_list_to_rawlist::<T>(list.element().kind(), list.element)
+        .unwrap()
+        .as_big_endian()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        owned::{self, NbtCompound, NbtList, NbtTag},
+        test_fixtures::complex_player,
+    };
+
+    #[test]
+    fn top_level_sizes_add_up() {
+        let decoded_src = complex_player();
+        let nbt = super::super::read(&mut Cursor::new(&decoded_src))
+            .unwrap()
+            .unwrap();
+
+        let sizes = nbt.key_sizes(1);
+        assert_eq!(sizes.len(), nbt.as_compound().len());
+        assert!(sizes.windows(2).all(|w| w[0].size >= w[1].size));
+
+        // root tag id + root name + every key + end tag
+        let root_header_size = 1 + 2 + nbt.name().len();
+        let total: usize = sizes.iter().map(|s| s.size).sum();
+        assert_eq!(root_header_size + total + 1, decoded_src.len());
+    }
+
+    #[test]
+    fn nested_sizes() {
+        let mut item = NbtCompound::new();
+        item.insert("id", "minecraft:stone");
+        item.insert("Count", 1i8);
+        let mut root = NbtCompound::new();
+        root.insert(
+            "Inventory",
+            NbtList::Compound(vec![item.clone(), item.clone()]),
+        );
+        root.insert("Pos", NbtTag::List(NbtList::Double(vec![0., 0., 0.])));
+
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", root).write(&mut data);
+        let nbt = super::super::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+
+        let sizes = nbt.key_sizes(2);
+        let size_of = |path: &str| sizes.iter().find(|s| s.path == path).unwrap().size;
+
+        // tag id + name length + "id" + string length + "minecraft:stone"
+        assert_eq!(size_of("Inventory[].id"), (1 + 2 + 2 + 2 + 15) * 2);
+        assert_eq!(size_of("Inventory[].Count"), (1 + 2 + 5 + 1) * 2);
+        assert_eq!(size_of("Pos"), 1 + 2 + 3 + 1 + 4 + 8 * 3);
+        assert_eq!(
+            size_of("Inventory"),
+            1 + 2 + 9 + 1 + 4 + size_of("Inventory[].id") + size_of("Inventory[].Count") + 2
+        );
+        assert_eq!(sizes[0].path, "Inventory");
+    }
+
+    #[test]
+    fn keys_with_dots_are_quoted() {
+        let mut inner = NbtCompound::new();
+        inner.insert("b", 1i8);
+        let mut root = NbtCompound::new();
+        root.insert("a.b", 1i8);
+        root.insert("a", inner);
+
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", root).write(&mut data);
+        let nbt = super::super::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+
+        let sizes = nbt.key_sizes(2);
+        let paths = sizes.iter().map(|s| s.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, ["a", "\"a.b\"", "a.b"]);
+    }
+}
@@ -0,0 +1,138 @@
+use super::{read_tag_payload, BaseNbtTag};
+use crate::{owned, reader::Reader, validate, Error, RawNbtValue};
+
+/// The ID and unparsed payload of a tag, borrowed from the original data.
+///
+/// This is the borrowed version of [`RawNbtValue`]. It can be parsed later,
+/// or copied into another buffer exactly as it was, which is useful for
+/// passing data through without decoding it.
+///
+/// ```
+/// # use std::io::Cursor;
+/// use simdnbt::borrow;
+///
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("items", simdnbt::owned::NbtList::Int(vec![1, 2, 3]));
+/// let data = simdnbt::owned::BaseNbt::new("", compound).to_bytes();
+///
+/// let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+/// let raw = nbt.get("items").unwrap().raw().unwrap();
+///
+/// let mut out = Vec::new();
+/// raw.write(&mut out);
+/// let tag = simdnbt::owned::read_tag(&mut Cursor::new(&out)).unwrap();
+/// assert_eq!(tag.list().unwrap().ints(), Some(vec![1, 2, 3]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawTag<'a> {
+    id: u8,
+    data: &'a [u8],
+}
+
+impl<'a> RawTag<'a> {
+    /// Make a raw tag from its ID and payload. The payload isn't checked to
+    /// be valid until it's parsed.
+    pub fn new(id: u8, data: &'a [u8]) -> Self {
+        Self { id, data }
+    }
+
+    /// Capture the payload of a tag with the given ID, and move the reader
+    /// past it. The tag ID and name must've already been read.
+    pub fn read(reader: &mut Reader<'a>, id: u8) -> Result<Self, Error> {
+        let len = validate::measure_tag(reader, id)?;
+        let data = reader.read_slice(len)?;
+        Ok(Self { id, data })
+    }
+
+    /// Get the numerical ID of the tag type.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Returns the encoded payload of the tag, without the tag ID.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Parse the tag into a borrowed tag.
+    pub fn parse(&self) -> Result<BaseNbtTag<'a>, Error> {
+        read_tag_payload(self.data, self.id)
+    }
+
+    /// Parse the tag into an owned tag.
+    pub fn parse_owned(&self) -> Result<owned::NbtTag, Error> {
+        Ok(self.parse()?.as_tag().to_owned())
+    }
+
+    /// Write the tag ID followed by the payload, which is the same format that
+    /// [`owned::NbtTag::write`] uses.
+    pub fn write(&self, data: &mut Vec<u8>) {
+        data.reserve(1 + self.data.len());
+        data.push(self.id);
+        data.extend_from_slice(self.data);
+    }
+
+    /// Copy the tag into a [`RawNbtValue`], which doesn't borrow from the
+    /// data.
+    pub fn to_owned(&self) -> RawNbtValue {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        RawNbtValue::from_bytes(data)
+    }
+}
+
+impl<'a> From<&'a RawNbtValue> for RawTag<'a> {
+    fn from(value: &'a RawNbtValue) -> Self {
+        Self::new(value.id(), value.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        borrow,
+        common::{COMPOUND_ID, INT_ID},
+    };
+
+    #[test]
+    fn raw_tag() {
+        let mut inner = owned::NbtCompound::new();
+        inner.insert("a", owned::NbtList::String(vec!["b".into()]));
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("inner", inner.clone());
+        compound.insert("int", 5);
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let raw = nbt.get("inner").unwrap().raw().unwrap();
+        assert_eq!(raw.id(), COMPOUND_ID);
+        assert_eq!(raw.as_bytes(), inner.to_bytes());
+        assert_eq!(
+            raw.parse().unwrap().as_tag(),
+            owned::NbtTag::Compound(inner.clone())
+        );
+        assert_eq!(
+            raw.parse_owned().unwrap(),
+            owned::NbtTag::Compound(inner.clone())
+        );
+        assert_eq!(
+            raw.to_owned(),
+            RawNbtValue::from_tag(&owned::NbtTag::Compound(inner))
+        );
+        assert_eq!(RawTag::from(&raw.to_owned()), raw);
+
+        // reading it by hand
+        let mut reader = Reader::new(&data[1 + 2 + 1 + 2 + 5..]);
+        assert_eq!(RawTag::read(&mut reader, COMPOUND_ID).unwrap(), raw);
+        assert_eq!(reader.read_u8().unwrap(), INT_ID);
+
+        // trailing data isn't allowed
+        let mut payload = raw.as_bytes().to_vec();
+        payload.push(0);
+        assert!(RawTag::new(COMPOUND_ID, &payload).parse().is_err());
+        assert!(RawTag::new(INT_ID, &[0, 0]).parse().is_err());
+    }
+}
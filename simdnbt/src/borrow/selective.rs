@@ -0,0 +1,223 @@
+use super::{read_tag_payload_from, BaseNbtTag};
+use crate::{
+    common::{read_length, read_string, skip_tag, COMPOUND_ID, END_ID, LIST_ID},
+    reader::Reader,
+    Error, NbtPath, PathSegment,
+};
+
+/// Read only the tags at the given paths, skipping over everything else and
+/// stopping as soon as all of them were found.
+///
+/// The paths use the same syntax as [`NbtPath`], like `Level.Status` or
+/// `Level.Sections[3].Y`. The result has one entry for each path, which is
+/// `None` if the tag wasn't found or the path is invalid. Only the selected
+/// tags are parsed into tapes, so peeking at a few fields of a large structure
+/// is much faster than [`super::read`].
+///
+/// Since reading stops early, data after the selected tags isn't checked to
+/// be valid.
+///
+/// ```
+/// # let mut level = simdnbt::owned::NbtCompound::new();
+/// # level.insert("Status", "full");
+/// # let mut root = simdnbt::owned::NbtCompound::new();
+/// # root.insert("DataVersion", 3953);
+/// # root.insert("Level", level);
+/// # let data = simdnbt::owned::BaseNbt::new("", root).to_bytes();
+/// let tags = simdnbt::borrow::read_selective(&data, &["DataVersion", "Level.Status"]).unwrap();
+/// assert_eq!(tags[0].as_ref().unwrap().as_tag().int(), Some(3953));
+/// assert_eq!(
+///     tags[1].as_ref().unwrap().as_tag().string().unwrap().to_str(),
+///     "full"
+/// );
+/// ```
+pub fn read_selective<'a>(
+    data: &'a [u8],
+    paths: &[&str],
+) -> Result<Vec<Option<BaseNbtTag<'a>>>, Error> {
+    let paths = paths
+        .iter()
+        .map(|path| path.parse::<NbtPath>().ok())
+        .collect::<Vec<_>>();
+    let mut selector = Selector {
+        input: data,
+        results: paths.iter().map(|_| None).collect(),
+        remaining: paths.iter().flatten().count(),
+    };
+    let targets = paths
+        .iter()
+        .enumerate()
+        .filter_map(|(index, path)| Some((index, path.as_ref()?.segments())))
+        .collect::<Vec<_>>();
+
+    let mut reader = Reader::new(data);
+    let root_type = reader.read_u8()?;
+    if root_type == END_ID {
+        return Ok(selector.results);
+    }
+    if root_type != COMPOUND_ID {
+        return Err(Error::InvalidRootType(root_type));
+    }
+    read_string(&mut reader)?;
+    selector.select(&mut reader, COMPOUND_ID, 0, &targets)?;
+
+    Ok(selector.results)
+}
+
+/// The paths that are being looked for in a tag. The first segment of each
+/// path is for the tag's children.
+type Targets<'p> = [(usize, &'p [PathSegment])];
+
+struct Selector<'a> {
+    input: &'a [u8],
+    results: Vec<Option<BaseNbtTag<'a>>>,
+    /// How many paths haven't been found yet.
+    remaining: usize,
+}
+
+impl<'a> Selector<'a> {
+    /// Look for the targets in the payload of a tag, leaving the reader after
+    /// the tag.
+    fn select(
+        &mut self,
+        data: &mut Reader<'a>,
+        tag_type: u8,
+        depth: usize,
+        targets: &Targets,
+    ) -> Result<(), Error> {
+        match tag_type {
+            COMPOUND_ID if !targets.is_empty() => loop {
+                let tag_type = data.read_u8()?;
+                if tag_type == END_ID {
+                    return Ok(());
+                }
+                let name = read_string(data)?;
+                let name = name.to_str();
+                let children = children(
+                    targets,
+                    |segment| matches!(segment, PathSegment::Key(key) if *key == name),
+                );
+                self.select_child(data, tag_type, depth, &children)?;
+                if self.remaining == 0 {
+                    return Ok(());
+                }
+            },
+            LIST_ID if !targets.is_empty() => {
+                let element_type = data.read_u8()?;
+                let length = read_length(data)? as usize;
+                if element_type == END_ID {
+                    return Ok(());
+                }
+                for index in 0..length {
+                    let children = children(
+                        targets,
+                        |segment| matches!(segment, PathSegment::Index(i) if *i == index),
+                    );
+                    self.select_child(data, element_type, depth, &children)?;
+                    if self.remaining == 0 {
+                        return Ok(());
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(skip_tag(data, tag_type, depth)?),
+        }
+    }
+
+    fn select_child(
+        &mut self,
+        data: &mut Reader<'a>,
+        tag_type: u8,
+        depth: usize,
+        targets: &Targets,
+    ) -> Result<(), Error> {
+        let (found, deeper): (Vec<_>, Vec<_>) = targets
+            .iter()
+            .copied()
+            .partition(|(_, path)| path.is_empty());
+
+        let mut end = None;
+        for &(index, _) in &found {
+            let mut tag_data = data.clone();
+            self.results[index] = Some(read_tag_payload_from(self.input, &mut tag_data, tag_type)?);
+            self.remaining -= 1;
+            end = Some(tag_data.cur);
+        }
+        if self.remaining == 0 {
+            return Ok(());
+        }
+        match end {
+            // the tag was already read so we can skip past it, but we might
+            // still have to look inside of it
+            Some(end) if deeper.is_empty() => data.cur = end,
+            Some(end) => {
+                self.select(data, tag_type, depth + 1, &deeper)?;
+                data.cur = end;
+            }
+            None => self.select(data, tag_type, depth + 1, &deeper)?,
+        }
+        Ok(())
+    }
+}
+
+/// Get the targets that continue into a child whose name or index matches.
+fn children<'p>(
+    targets: &Targets<'p>,
+    matches: impl Fn(&PathSegment) -> bool,
+) -> Vec<(usize, &'p [PathSegment])> {
+    targets
+        .iter()
+        .filter_map(|&(index, path)| {
+            let (first, rest) = path.split_first()?;
+            matches(first).then_some((index, rest))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{borrow, owned, test_fixtures::bigtest};
+
+    #[test]
+    fn read_selective() {
+        let src = bigtest();
+        let nbt = borrow::read(&mut Cursor::new(&src)).unwrap().unwrap();
+
+        let paths = [
+            "intTest",
+            r#""nested compound test".egg.name"#,
+            r#""nested compound test""#,
+            r#""listTest (compound)"[1].name"#,
+            r#""listTest (long)"[2]"#,
+            "missing",
+            "not a [valid path",
+        ];
+        let tags = super::read_selective(&src, &paths).unwrap();
+        assert_eq!(tags.len(), paths.len());
+        for (path, tag) in paths.iter().zip(&tags) {
+            assert_eq!(
+                tag.as_ref().map(|tag| tag.as_tag().to_owned()),
+                nbt.get_path(path).map(|tag| tag.to_owned()),
+                "{path}"
+            );
+        }
+        assert!(tags[..5].iter().all(Option::is_some));
+        assert!(tags[5..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn stops_after_finding_everything() {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("a", 1);
+        let mut data = owned::BaseNbt::new("", compound).to_bytes();
+        // garbage instead of the end tag, which would be an error if we read it
+        *data.last_mut().unwrap() = 0xff;
+
+        assert!(borrow::read(&mut Cursor::new(&data)).is_err());
+        let tags = super::read_selective(&data, &["a"]).unwrap();
+        assert_eq!(tags[0].as_ref().unwrap().as_tag().int(), Some(1));
+        assert!(super::read_selective(&data, &["b"]).is_err());
+    }
+}
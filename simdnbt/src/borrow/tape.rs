@@ -1,13 +1,17 @@
 use std::{
-    alloc::{self, Allocator, Layout},
+    alloc::Layout,
     fmt::{self, Debug},
     mem,
     ptr::NonNull,
 };
 
-use crate::common::{
-    BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LONG_ARRAY_ID,
-    LONG_ID, SHORT_ID, STRING_ID,
+use crate::{
+    allocator::{Allocator, Global},
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
+        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    error::NonRootError,
 };
 
 const DEFAULT_CAPACITY: usize = 1024;
@@ -15,7 +19,7 @@ const DEFAULT_CAPACITY: usize = 1024;
 // this is faster than a Vec mainly because we store a `cur` pointer which
 // allows us to avoid having to add the start+length when pushing
 #[derive(Debug)]
-pub struct MainTape<A: Allocator = alloc::Global> {
+pub struct MainTape<A: Allocator = Global> {
     /// The next address we'll be writing to.
     cur: NonNull<TapeElement>,
     /// The last (probably uninitialized) element in the tape.
@@ -25,35 +29,76 @@ pub struct MainTape<A: Allocator = alloc::Global> {
 
     alloc: A,
 }
+// the tape owns its allocation like a `Vec` does, so it can be sent and shared
+// between threads
+unsafe impl<A: Allocator + Send> Send for MainTape<A> {}
+unsafe impl<A: Allocator + Sync> Sync for MainTape<A> {}
+impl MainTape {
+    pub fn new() -> Result<Self, NonRootError> {
+        Self::new_in(Global)
+    }
+}
 impl<A: Allocator> MainTape<A> {
+    pub fn new_in(alloc: A) -> Result<Self, NonRootError> {
+        let element_size = mem::size_of::<TapeElement>();
+        // this is faster than Layout::array
+        let layout = unsafe {
+            Layout::from_size_align_unchecked(DEFAULT_CAPACITY * element_size, element_size)
+        };
+        let ptr = alloc
+            .allocate(layout)
+            .map_err(|_| NonRootError::out_of_memory())?
+            .cast::<TapeElement>();
+        let end = unsafe { ptr.add(DEFAULT_CAPACITY) };
+        Ok(Self {
+            cur: ptr,
+            end,
+            ptr: ptr.cast(),
+            alloc,
+        })
+    }
+
+    /// Push an element to the tape, returning an error if we couldn't
+    /// allocate enough space for it.
     #[inline]
-    pub fn push(&mut self, element: TapeElement) {
+    pub fn push(&mut self, element: TapeElement) -> Result<(), NonRootError> {
         if self.cur == self.end {
-            let old_cap = self.capacity();
-            let extending_by = old_cap;
-            let new_cap = old_cap + extending_by;
-
-            let element_size = mem::size_of::<TapeElement>();
-            let old_cap_bytes = old_cap * element_size;
-            let new_cap_bytes = new_cap * element_size;
-            let new_ptr = unsafe {
-                self.alloc.grow(
-                    self.ptr,
-                    Layout::from_size_align_unchecked(old_cap_bytes, element_size),
-                    Layout::from_size_align_unchecked(new_cap_bytes, element_size),
-                )
-            };
-            let new_ptr = new_ptr.expect("allocation failed");
-
-            self.ptr = new_ptr.cast();
-            // update cur in case the ptr changed
-            self.cur = NonNull::new(self.ptr.as_ptr() as *mut TapeElement).unwrap();
-            self.cur = unsafe { self.cur.add(old_cap) };
-            // and end has to be updated anyways since we're updating the capacity
-            self.end = unsafe { self.cur.add(extending_by) };
+            self.grow()?;
         }
 
         unsafe { self.push_unchecked(element) };
+        Ok(())
+    }
+
+    #[cold]
+    fn grow(&mut self) -> Result<(), NonRootError> {
+        let old_cap = self.capacity();
+        let extending_by = old_cap;
+        let new_cap = old_cap + extending_by;
+        // indexes into the tape are stored as u32s, so it can't get any bigger
+        // than this
+        if new_cap > u32::MAX as usize {
+            return Err(NonRootError::too_many_tags());
+        }
+
+        let element_size = mem::size_of::<TapeElement>();
+        let old_cap_bytes = old_cap * element_size;
+        let new_cap_bytes = new_cap * element_size;
+        let new_ptr = unsafe {
+            self.alloc.grow(
+                self.ptr,
+                Layout::from_size_align_unchecked(old_cap_bytes, element_size),
+                Layout::from_size_align_unchecked(new_cap_bytes, element_size),
+            )
+        };
+        let new_ptr = new_ptr.map_err(|_| NonRootError::out_of_memory())?;
+
+        self.ptr = new_ptr.cast();
+        // update cur in case the ptr changed
+        self.cur = unsafe { self.ptr.cast::<TapeElement>().add(old_cap) };
+        // and end has to be updated anyways since we're updating the capacity
+        self.end = unsafe { self.cur.add(extending_by) };
+        Ok(())
     }
 
     #[inline]
@@ -62,12 +107,25 @@ impl<A: Allocator> MainTape<A> {
         self.cur = unsafe { self.cur.add(1) };
     }
 
+    /// Remove every element from the tape, without changing its capacity.
+    pub fn clear(&mut self) {
+        self.cur = self.ptr.cast();
+    }
+
+    /// Remove the elements after the first `len`. This does nothing if the
+    /// tape isn't longer than that.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.cur = unsafe { self.ptr.cast::<TapeElement>().add(len) };
+        }
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         unsafe { self.cur.offset_from(self.ptr.cast()) as usize }
     }
     #[inline]
-    fn capacity(&self) -> usize {
+    pub fn capacity(&self) -> usize {
         unsafe { self.end.offset_from(self.ptr.cast()) as usize }
     }
 
@@ -82,32 +140,13 @@ impl<A: Allocator> MainTape<A> {
         self.ptr.cast().as_ptr()
     }
 }
-impl Default for MainTape {
-    fn default() -> Self {
-        let element_size = mem::size_of::<TapeElement>();
-        let ptr = unsafe {
-            // this is faster than Layout::array
-            alloc::alloc(Layout::from_size_align_unchecked(
-                DEFAULT_CAPACITY * element_size,
-                element_size,
-            ))
-        };
-        let ptr = NonNull::new(ptr as *mut TapeElement).expect("allocation failed");
-        let end = unsafe { ptr.add(DEFAULT_CAPACITY) };
-        Self {
-            cur: ptr,
-            end,
-            ptr: ptr.cast(),
-            alloc: alloc::Global,
-        }
-    }
-}
 impl<A: Allocator> Drop for MainTape<A> {
     fn drop(&mut self) {
+        let element_size = mem::size_of::<TapeElement>();
         unsafe {
             self.alloc.deallocate(
-                self.ptr.cast(),
-                Layout::array::<TapeElement>(self.capacity()).unwrap(),
+                self.ptr,
+                Layout::from_size_align_unchecked(self.capacity() * element_size, element_size),
             )
         };
     }
@@ -355,6 +394,7 @@ impl TapeTagKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::allocator::AllocError;
 
     #[test]
     fn test_u56() {
@@ -368,4 +408,34 @@ mod tests {
         let value: u64 = u56 { a, b, c }.into();
         assert_eq!(value, 0x34_5678_9abc_def0);
     }
+
+    /// An allocator that can make the initial tape but never grow it.
+    struct CantGrow;
+    unsafe impl Allocator for CantGrow {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Global.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+        unsafe fn grow(
+            &self,
+            _ptr: NonNull<u8>,
+            _old_layout: Layout,
+            _new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            Err(AllocError)
+        }
+    }
+
+    #[test]
+    fn failed_allocation_is_an_error() {
+        let mut tape = MainTape::new_in(CantGrow).unwrap();
+        for _ in 0..DEFAULT_CAPACITY {
+            tape.push(TapeElement::new(0)).unwrap();
+        }
+        let err = tape.push(TapeElement::new(0)).unwrap_err();
+        assert_eq!(crate::Error::from(err), crate::Error::OutOfMemory);
+        assert_eq!(tape.len(), DEFAULT_CAPACITY);
+    }
 }
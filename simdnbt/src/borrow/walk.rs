@@ -0,0 +1,195 @@
+//! Visiting every tag in borrowed NBT without writing the recursion yourself.
+
+use super::{BaseNbt, NbtCompound, NbtTag};
+use crate::{NbtPath, PathSegment};
+
+/// What [`walk`] should do after a tag was visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisitFlow {
+    /// Keep going, including into the tag's children if it's a compound or a
+    /// list.
+    #[default]
+    Continue,
+    /// Don't visit the children of this tag, but keep going with its siblings.
+    SkipChildren,
+    /// Stop walking immediately.
+    Stop,
+}
+
+/// Callbacks for [`walk`].
+pub trait Visit {
+    /// Called for every tag, before its children are visited.
+    ///
+    /// The path is where the tag is relative to the root compound. Elements of
+    /// lists are visited too, so a list of ints will call this once for the
+    /// list and once for each int.
+    fn visit_tag(&mut self, path: &NbtPath, tag: &NbtTag) -> VisitFlow;
+}
+
+/// Go through every tag in the NBT depth-first, in the order that they're
+/// stored, and call [`Visit::visit_tag`] for each of them. The root compound
+/// itself isn't visited.
+///
+/// ```
+/// # use std::io::Cursor;
+/// use simdnbt::{
+///     borrow::{self, NbtTag, Visit, VisitFlow},
+///     compound, owned, NbtPath,
+/// };
+///
+/// /// Find every tag named `Pos`, no matter how deeply it's nested.
+/// struct FindPositions(Vec<String>);
+/// impl Visit for FindPositions {
+///     fn visit_tag(&mut self, path: &NbtPath, _tag: &NbtTag) -> VisitFlow {
+///         if let Some(simdnbt::PathSegment::Key(key)) = path.segments().last() {
+///             if key == "Pos" {
+///                 self.0.push(path.to_string());
+///                 return VisitFlow::SkipChildren;
+///             }
+///         }
+///         VisitFlow::Continue
+///     }
+/// }
+///
+/// let data = owned::BaseNbt::new("", compound! {
+///     "Pos": [1., 2., 3.],
+///     "Passengers": [{ "Pos": [4., 5., 6.] }],
+/// })
+/// .to_bytes();
+/// let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+///
+/// let mut visitor = FindPositions(Vec::new());
+/// borrow::walk(&nbt, &mut visitor);
+/// assert_eq!(visitor.0, ["Pos", "Passengers[0].Pos"]);
+/// ```
+pub fn walk(nbt: &BaseNbt, visitor: &mut impl Visit) {
+    walk_compound(nbt.as_compound(), &mut NbtPath::new(), visitor);
+}
+
+/// Returns `true` if the visitor asked to stop.
+fn walk_compound(compound: NbtCompound, path: &mut NbtPath, visitor: &mut impl Visit) -> bool {
+    for (name, tag) in compound.iter() {
+        path.push(PathSegment::Key(name.to_string_lossy().into_owned()));
+        let stopped = walk_tag(&tag, path, visitor);
+        path.pop();
+        if stopped {
+            return true;
+        }
+    }
+    false
+}
+
+fn walk_tag(tag: &NbtTag, path: &mut NbtPath, visitor: &mut impl Visit) -> bool {
+    match visitor.visit_tag(path, tag) {
+        VisitFlow::Continue => {}
+        VisitFlow::SkipChildren => return false,
+        VisitFlow::Stop => return true,
+    }
+
+    if let Some(compound) = tag.compound() {
+        return walk_compound(compound, path, visitor);
+    }
+    if let Some(list) = tag.list() {
+        for (i, element) in list.iter().enumerate() {
+            path.push(PathSegment::Index(i));
+            let stopped = walk_tag(&element, path, visitor);
+            path.pop();
+            if stopped {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{borrow, compound, owned};
+
+    struct Record {
+        visited: Vec<(String, u8)>,
+        skip: &'static str,
+        stop: &'static str,
+    }
+    impl Visit for Record {
+        fn visit_tag(&mut self, path: &NbtPath, tag: &NbtTag) -> VisitFlow {
+            let path = path.to_string();
+            self.visited.push((path.clone(), tag.id()));
+            if path == self.skip {
+                VisitFlow::SkipChildren
+            } else if path == self.stop {
+                VisitFlow::Stop
+            } else {
+                VisitFlow::Continue
+            }
+        }
+    }
+
+    fn visit(data: &[u8], skip: &'static str, stop: &'static str) -> Vec<String> {
+        let nbt = borrow::read(&mut Cursor::new(data)).unwrap().unwrap();
+        let mut visitor = Record {
+            visited: Vec::new(),
+            skip,
+            stop,
+        };
+        walk(&nbt, &mut visitor);
+        visitor.visited.into_iter().map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn walk_order() {
+        let data = owned::BaseNbt::new(
+            "",
+            compound! {
+                "a": 1,
+                "lists": [[1i8, 2i8], [], [{ "b": "c" }]],
+                "d": { "e": [L; 1, 2] },
+                "f": [],
+            },
+        )
+        .to_bytes();
+
+        let all = [
+            "a",
+            "lists",
+            "lists[0]",
+            "lists[0][0]",
+            "lists[0][1]",
+            "lists[1]",
+            "lists[2]",
+            "lists[2][0]",
+            "lists[2][0].b",
+            "d",
+            "d.e",
+            "f",
+        ];
+        assert_eq!(visit(&data, "", ""), all);
+
+        let skipped = visit(&data, "lists", "");
+        assert_eq!(skipped, ["a", "lists", "d", "d.e", "f"]);
+
+        let stopped = visit(&data, "", "lists[0][1]");
+        assert_eq!(stopped, all[..5]);
+    }
+
+    #[test]
+    fn walk_ids() {
+        let data = owned::BaseNbt::new("", compound! { "x": [{}, {}], "y": 1i8 }).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let mut visitor = Record {
+            visited: Vec::new(),
+            skip: "",
+            stop: "",
+        };
+        walk(&nbt, &mut visitor);
+        let ids = visitor
+            .visited
+            .iter()
+            .map(|(_, id)| *id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [9, 10, 10, 1]);
+    }
+}
@@ -1,10 +1,10 @@
 use std::{mem, slice};
 
 use crate::{
-    error::UnexpectedEofError,
+    error::{NonRootError, UnexpectedEofError},
     raw_list::RawList,
     reader::Reader,
-    swap_endianness::{swap_endianness_as_u8, SwappableNumber},
+    swap_endianness::{swap_endianness_in_place, SwappableNumber},
     Mutf8Str,
 };
 
@@ -34,14 +34,30 @@ pub fn read_with_u16_length<'a>(
     data.read_slice(length_in_bytes)
 }
 
+/// Read a length that's prefixed to a list or array.
+///
+/// Lengths are signed in Java, so a length that would be negative is invalid.
+/// Rejecting them here also means that hostile inputs can't make us do
+/// anything with lengths that are close to `u32::MAX`.
+#[inline(always)]
+pub fn read_length(data: &mut Reader<'_>) -> Result<u32, NonRootError> {
+    let length = data.read_u32()?;
+    if length > data.max_list_len {
+        return Err(NonRootError::invalid_length());
+    }
+    Ok(length)
+}
+
 #[inline(never)]
 pub fn read_with_u32_length<'a>(
     data: &mut Reader<'a>,
     width: usize,
-) -> Result<&'a [u8], UnexpectedEofError> {
-    let length = data.read_u32()?;
-    let length_in_bytes = length as usize * width;
-    data.read_slice(length_in_bytes)
+) -> Result<&'a [u8], NonRootError> {
+    let length = read_length(data)?;
+    let length_in_bytes = (length as usize)
+        .checked_mul(width)
+        .ok_or(NonRootError::invalid_length())?;
+    Ok(data.read_slice(length_in_bytes)?)
 }
 
 pub fn read_string<'a>(data: &mut Reader<'a>) -> Result<&'a Mutf8Str, UnexpectedEofError> {
@@ -49,24 +65,88 @@ pub fn read_string<'a>(data: &mut Reader<'a>) -> Result<&'a Mutf8Str, Unexpected
     Ok(Mutf8Str::from_slice(data))
 }
 
-pub fn read_u8_array<'a>(data: &mut Reader<'a>) -> Result<&'a [u8], UnexpectedEofError> {
+pub fn read_u8_array<'a>(data: &mut Reader<'a>) -> Result<&'a [u8], NonRootError> {
     read_with_u32_length(data, 1)
 }
-pub fn read_i8_array<'a>(data: &mut Reader<'a>) -> Result<&'a [i8], UnexpectedEofError> {
+pub fn read_i8_array<'a>(data: &mut Reader<'a>) -> Result<&'a [i8], NonRootError> {
     Ok(slice_u8_into_i8(read_u8_array(data)?))
 }
 
-pub fn read_int_array<'a>(data: &mut Reader<'a>) -> Result<RawList<'a, i32>, UnexpectedEofError> {
+pub fn read_int_array<'a>(data: &mut Reader<'a>) -> Result<RawList<'a, i32>, NonRootError> {
     let array_bytes = read_with_u32_length(data, 4)?;
     Ok(RawList::new(array_bytes))
 }
 
-pub fn read_long_array<'a>(data: &mut Reader<'a>) -> Result<RawList<'a, i64>, UnexpectedEofError> {
+pub fn read_long_array<'a>(data: &mut Reader<'a>) -> Result<RawList<'a, i64>, NonRootError> {
     let array_bytes = read_with_u32_length(data, 8)?;
     Ok(RawList::new(array_bytes))
 }
 
-fn slice_u8_into_i8(s: &[u8]) -> &[i8] {
+/// Move past the payload of a tag without reading it into anything. `depth` is
+/// how many compounds and lists the tag is inside of.
+pub fn skip_tag(data: &mut Reader<'_>, tag_type: u8, depth: usize) -> Result<(), NonRootError> {
+    match tag_type {
+        BYTE_ID => data.skip(1)?,
+        SHORT_ID => data.skip(2)?,
+        INT_ID | FLOAT_ID => data.skip(4)?,
+        LONG_ID | DOUBLE_ID => data.skip(8)?,
+        BYTE_ARRAY_ID => {
+            read_with_u32_length(data, 1)?;
+        }
+        STRING_ID => {
+            read_with_u16_length(data, 1)?;
+        }
+        INT_ARRAY_ID => {
+            read_with_u32_length(data, 4)?;
+        }
+        LONG_ARRAY_ID => {
+            read_with_u32_length(data, 8)?;
+        }
+        LIST_ID => {
+            if depth >= MAX_DEPTH {
+                return Err(NonRootError::max_depth_exceeded());
+            }
+            let element_type = data.read_u8()?;
+            // this has to be checked before the length, since empty lists of
+            // unknown types would be skipped otherwise
+            if element_type > LONG_ARRAY_ID {
+                return Err(NonRootError::unknown_tag_id(element_type));
+            }
+            let length = read_length(data)? as usize;
+            match element_type {
+                // the length is unused for lists of end tags
+                END_ID => {}
+                // lists of numbers can be skipped all at once
+                BYTE_ID => data.skip(length)?,
+                SHORT_ID => data.skip(length * 2)?,
+                INT_ID | FLOAT_ID => data.skip(length * 4)?,
+                LONG_ID | DOUBLE_ID => data.skip(length * 8)?,
+                _ => {
+                    for _ in 0..length {
+                        skip_tag(data, element_type, depth + 1)?;
+                    }
+                }
+            }
+        }
+        COMPOUND_ID => {
+            if depth >= MAX_DEPTH {
+                return Err(NonRootError::max_depth_exceeded());
+            }
+            loop {
+                let tag_type = data.read_u8()?;
+                if tag_type == END_ID {
+                    break;
+                }
+                read_with_u16_length(data, 1)?;
+                skip_tag(data, tag_type, depth + 1)?;
+            }
+        }
+        _ => return Err(NonRootError::unknown_tag_id(tag_type)),
+    }
+    Ok(())
+}
+
+pub fn slice_u8_into_i8(s: &[u8]) -> &[i8] {
     unsafe { slice::from_raw_parts(s.as_ptr() as *const i8, s.len()) }
 }
 
@@ -74,6 +154,18 @@ pub fn slice_i8_into_u8(s: &[i8]) -> &[u8] {
     unsafe { slice::from_raw_parts(s.as_ptr() as *const u8, s.len()) }
 }
 
+pub fn vec_u8_into_i8(v: Vec<u8>) -> Vec<i8> {
+    let mut v = mem::ManuallyDrop::new(v);
+    // SAFETY: u8 and i8 have the same size and alignment
+    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as *mut i8, v.len(), v.capacity()) }
+}
+
+pub fn vec_i8_into_u8(v: Vec<i8>) -> Vec<u8> {
+    let mut v = mem::ManuallyDrop::new(v);
+    // SAFETY: u8 and i8 have the same size and alignment
+    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as *mut u8, v.len(), v.capacity()) }
+}
+
 #[inline(always)]
 pub fn write_with_u32_length(data: &mut Vec<u8>, width: usize, value: &[u8]) {
     let length = value.len() / width;
@@ -133,23 +225,77 @@ pub unsafe fn push_unchecked(data: &mut Vec<u8>, value: u8) {
 }
 
 /// Convert a slice of any type into a slice of u8. This will probably return
-/// the data as little endian! Use [`slice_into_u8_big_endian`] to get big
+/// the data as little endian! Use [`extend_big_endian`] to write big
 /// endian (the endianness that's used in NBT).
 #[inline]
 pub fn slice_into_u8_native_endian<T>(s: &[T]) -> &[u8] {
     unsafe { slice::from_raw_parts(s.as_ptr() as *const u8, mem::size_of_val(s)) }
 }
 
-/// Convert a slice of any type into a Vec<u8>. This will return the data as big
-/// endian (the endianness that's used in NBT).
+/// Write a slice of numbers to the end of the Vec as big endian (the
+/// endianness that's used in NBT). The numbers are swapped in the Vec, so no
+/// temporary buffer is allocated.
+#[inline]
+pub fn extend_big_endian<T: SwappableNumber>(data: &mut Vec<u8>, s: &[T]) {
+    let start = data.len();
+    data.extend_from_slice(slice_into_u8_native_endian(s));
+    swap_endianness_in_place::<T>(&mut data[start..]);
+}
+
+/// Write the length of the slice as a u32 followed by its numbers as big
+/// endian.
+#[inline]
+pub fn write_big_endian_with_u32_length<T: SwappableNumber>(data: &mut Vec<u8>, s: &[T]) {
+    data.reserve(4 + mem::size_of_val(s));
+    // SAFETY: We just reserved enough space for the length
+    unsafe {
+        extend_unchecked(data, &(s.len() as u32).to_be_bytes());
+    }
+    extend_big_endian(data, s);
+}
+
+/// Replace NaN with the NaN that Java's `Float.NaN` is, so every NaN is
+/// written the same way when writing canonically.
 #[inline]
-pub fn slice_into_u8_big_endian<T: SwappableNumber>(s: &[T]) -> Vec<u8> {
-    swap_endianness_as_u8::<T>(slice_into_u8_native_endian(s))
+pub fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::from_bits(0x7fc0_0000)
+    } else {
+        value
+    }
+}
+
+/// Replace NaN with the NaN that Java's `Double.NaN` is. See
+/// [`canonical_f32`].
+#[inline]
+pub fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::from_bits(0x7ff8_0000_0000_0000)
+    } else {
+        value
+    }
+}
+
+/// Replace every NaN in big-endian floats that were already written with the
+/// canonical one. See [`canonical_f32`].
+pub fn canonicalize_f32s(data: &mut [u8]) {
+    for float in data.as_chunks_mut::<4>().0 {
+        *float = canonical_f32(f32::from_be_bytes(*float)).to_be_bytes();
+    }
+}
+
+/// Replace every NaN in big-endian doubles that were already written with the
+/// canonical one. See [`canonical_f64`].
+pub fn canonicalize_f64s(data: &mut [u8]) {
+    for double in data.as_chunks_mut::<8>().0 {
+        *double = canonical_f64(f64::from_be_bytes(*double)).to_be_bytes();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
 
     // this test specifically checks with little-endian
     #[cfg(target_endian = "little")]
@@ -163,11 +309,26 @@ mod tests {
     }
 
     #[test]
-    fn test_slice_into_u8_big_endian() {
-        assert_eq!(slice_into_u8_big_endian(&[1u16, 2u16]), [0, 1, 0, 2]);
-        assert_eq!(
-            slice_into_u8_big_endian(&[1u32, 2u32]),
-            [0, 0, 0, 1, 0, 0, 0, 2]
-        );
+    fn test_extend_big_endian() {
+        let mut data = vec![9];
+        extend_big_endian(&mut data, &[1u16, 2u16]);
+        assert_eq!(data, [9, 0, 1, 0, 2]);
+
+        let mut data = Vec::new();
+        write_big_endian_with_u32_length(&mut data, &[1u32, 2u32]);
+        assert_eq!(data, [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn skip_list_of_unknown_type() {
+        for length in [0, 1] {
+            let data = [13, 0, 0, 0, length];
+            assert_eq!(
+                Error::from(skip_tag(&mut Reader::new(&data), LIST_ID, 0).unwrap_err()),
+                Error::UnknownTagId(13)
+            );
+        }
+        let data = [LONG_ARRAY_ID, 0, 0, 0, 0];
+        assert!(skip_tag(&mut Reader::new(&data), LIST_ID, 0).is_ok());
     }
 }
@@ -0,0 +1,252 @@
+//! Compare two NBT documents without parsing either of them into a tree.
+
+use std::fmt::Write;
+
+use crate::{
+    common::{
+        read_length, read_string, read_with_u32_length, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID,
+        DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID,
+        MAX_DEPTH, SHORT_ID, STRING_ID,
+    },
+    error::NonRootError,
+    reader::Reader,
+    Error, Mutf8Str,
+};
+
+/// The result of [`compare`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    /// The documents are different. The path is separated by dots and indexes
+    /// into lists look like `[0]`. It's empty if the root compounds have
+    /// different names, or if one of the documents is empty.
+    Different {
+        path: String,
+    },
+}
+
+impl Comparison {
+    pub fn is_equal(&self) -> bool {
+        matches!(self, Comparison::Equal)
+    }
+}
+
+enum Segment<'a> {
+    Key(&'a Mutf8Str),
+    Index(u32),
+}
+
+/// The segments of the path to a difference, from the innermost to the
+/// outermost. This is only built after a difference is found, so comparing
+/// equal documents doesn't have to allocate.
+type Difference<'a> = Option<Vec<Segment<'a>>>;
+
+/// Compare two normal root NBT compounds, returning the path of the first
+/// difference between them.
+///
+/// The documents are read at the same time and neither of them gets parsed
+/// into a tree, which makes this much faster than reading both and comparing
+/// them. The order of keys in compounds matters and floats are compared by
+/// their bits.
+///
+/// An error is only returned if one of the documents is invalid before the
+/// first difference.
+pub fn compare(a: &[u8], b: &[u8]) -> Result<Comparison, Error> {
+    let mut a = Reader::new(a);
+    let mut b = Reader::new(b);
+
+    let a_type = a.read_u8().map_err(|_| Error::UnexpectedEof)?;
+    let b_type = b.read_u8().map_err(|_| Error::UnexpectedEof)?;
+    for root_type in [a_type, b_type] {
+        if root_type != END_ID && root_type != COMPOUND_ID {
+            return Err(Error::InvalidRootType(root_type));
+        }
+    }
+    if a_type != b_type {
+        return Ok(different(Vec::new()));
+    }
+    if a_type == END_ID {
+        return Ok(Comparison::Equal);
+    }
+
+    if read_string(&mut a)? != read_string(&mut b)? {
+        return Ok(different(Vec::new()));
+    }
+
+    Ok(match compare_compound(&mut a, &mut b, 0)? {
+        Some(segments) => different(segments),
+        None => Comparison::Equal,
+    })
+}
+
+fn different(segments: Vec<Segment>) -> Comparison {
+    let mut path = String::new();
+    for segment in segments.iter().rev() {
+        match segment {
+            Segment::Key(key) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&key.to_string_lossy());
+            }
+            Segment::Index(index) => write!(path, "[{index}]").unwrap(),
+        }
+    }
+    Comparison::Different { path }
+}
+
+fn compare_compound<'a>(
+    a: &mut Reader<'a>,
+    b: &mut Reader<'a>,
+    depth: usize,
+) -> Result<Difference<'a>, NonRootError> {
+    if depth > MAX_DEPTH {
+        return Err(NonRootError::max_depth_exceeded());
+    }
+    loop {
+        let a_type = a.read_u8()?;
+        let b_type = b.read_u8()?;
+        if a_type == END_ID && b_type == END_ID {
+            return Ok(None);
+        }
+        if a_type == END_ID || b_type == END_ID {
+            // one of the compounds has an extra key
+            let reader = if a_type == END_ID { b } else { a };
+            return Ok(Some(vec![Segment::Key(read_string(reader)?)]));
+        }
+
+        let a_name = read_string(a)?;
+        let b_name = read_string(b)?;
+        if a_type != b_type || a_name != b_name {
+            return Ok(Some(vec![Segment::Key(a_name)]));
+        }
+
+        if let Some(mut segments) = compare_tag(a, b, a_type, depth)? {
+            segments.push(Segment::Key(a_name));
+            return Ok(Some(segments));
+        }
+    }
+}
+
+fn compare_tag<'a>(
+    a: &mut Reader<'a>,
+    b: &mut Reader<'a>,
+    tag_type: u8,
+    depth: usize,
+) -> Result<Difference<'a>, NonRootError> {
+    let equal = match tag_type {
+        BYTE_ID => a.read_slice(1)? == b.read_slice(1)?,
+        SHORT_ID => a.read_slice(2)? == b.read_slice(2)?,
+        INT_ID | FLOAT_ID => a.read_slice(4)? == b.read_slice(4)?,
+        LONG_ID | DOUBLE_ID => a.read_slice(8)? == b.read_slice(8)?,
+        BYTE_ARRAY_ID => read_with_u32_length(a, 1)? == read_with_u32_length(b, 1)?,
+        STRING_ID => read_string(a)? == read_string(b)?,
+        LIST_ID => return compare_list(a, b, depth + 1),
+        COMPOUND_ID => return compare_compound(a, b, depth + 1),
+        INT_ARRAY_ID => read_with_u32_length(a, 4)? == read_with_u32_length(b, 4)?,
+        LONG_ARRAY_ID => read_with_u32_length(a, 8)? == read_with_u32_length(b, 8)?,
+        _ => return Err(NonRootError::unknown_tag_id(tag_type)),
+    };
+    Ok(if equal { None } else { Some(Vec::new()) })
+}
+
+fn compare_list<'a>(
+    a: &mut Reader<'a>,
+    b: &mut Reader<'a>,
+    depth: usize,
+) -> Result<Difference<'a>, NonRootError> {
+    if depth > MAX_DEPTH {
+        return Err(NonRootError::max_depth_exceeded());
+    }
+    let a_type = a.read_u8()?;
+    let b_type = b.read_u8()?;
+    if a_type == END_ID && b_type == END_ID {
+        // the length of empty lists is ignored
+        a.skip(4)?;
+        b.skip(4)?;
+        return Ok(None);
+    }
+    let a_length = read_length(a)?;
+    let b_length = read_length(b)?;
+    if a_type != b_type || a_length != b_length {
+        return Ok(Some(Vec::new()));
+    }
+
+    let width = match a_type {
+        BYTE_ID => 1,
+        SHORT_ID => 2,
+        INT_ID | FLOAT_ID => 4,
+        LONG_ID | DOUBLE_ID => 8,
+        _ => 0,
+    };
+    if width != 0 {
+        let length_in_bytes = a_length as usize * width;
+        let equal = a.read_slice(length_in_bytes)? == b.read_slice(length_in_bytes)?;
+        return Ok(if equal { None } else { Some(Vec::new()) });
+    }
+
+    for i in 0..a_length {
+        if let Some(mut segments) = compare_tag(a, b, a_type, depth)? {
+            segments.push(Segment::Index(i));
+            return Ok(Some(segments));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        owned::{NbtCompound, NbtList, NbtTag},
+        test_fixtures::{item, write},
+    };
+
+    #[test]
+    fn equal() {
+        let src = include_bytes!("../tests/hypixel.nbt");
+        assert_eq!(compare(src, src), Ok(Comparison::Equal));
+        assert_eq!(compare(&[0], &[0]), Ok(Comparison::Equal));
+    }
+
+    #[test]
+    fn differences() {
+        let mut a = NbtCompound::new();
+        a.insert("Inventory", NbtList::Compound(vec![item(1), item(2)]));
+        a.insert("Pos", NbtList::Double(vec![0., 1., 2.]));
+        let a_data = write(a.clone());
+
+        let different_path = |b: NbtCompound| match compare(&a_data, &write(b)).unwrap() {
+            Comparison::Different { path } => path,
+            Comparison::Equal => panic!("expected a difference"),
+        };
+
+        let mut b = a.clone();
+        *b.list_mut("Inventory").unwrap() = NbtList::Compound(vec![item(1), item(3)]);
+        assert_eq!(different_path(b), "Inventory[1].Count");
+
+        let mut b = a.clone();
+        *b.list_mut("Pos").unwrap() = NbtList::Double(vec![0., 1., 3.]);
+        assert_eq!(different_path(b), "Pos");
+
+        let mut b = a.clone();
+        b.insert("extra", 1);
+        assert_eq!(different_path(b), "extra");
+
+        let mut b = a.clone();
+        b.remove("Pos");
+        assert_eq!(different_path(b), "Pos");
+
+        assert_eq!(different_path(NbtCompound::new()), "Inventory");
+    }
+
+    #[test]
+    fn errors() {
+        let data = write(NbtCompound::from_values(vec![("a".into(), NbtTag::Int(1))]));
+        assert_eq!(
+            compare(&data, &data[..data.len() - 1]),
+            Err(Error::UnexpectedEof)
+        );
+        assert_eq!(compare(&data, &[1]), Err(Error::InvalidRootType(1)));
+    }
+}
@@ -0,0 +1,132 @@
+//! The compression formats that NBT is usually stored in.
+
+use std::io::{Read, Write};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+
+use crate::Error;
+
+/// How NBT data is compressed. Files like `level.dat` and player data are
+/// gzipped, chunks in region files are usually zlib, and NBT sent over the
+/// network isn't compressed at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl Compression {
+    /// Guess how the data is compressed by looking at its header.
+    ///
+    /// Uncompressed NBT always starts with a tag id, which can't be confused
+    /// with the magic bytes of gzip or zlib.
+    pub fn detect(data: &[u8]) -> Self {
+        match data {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // the first byte is the compression method (8 is deflate) and window
+            // size, and the two bytes together are a multiple of 31
+            &[cmf, flg, ..] if cmf & 0x0f == 8 && u16::from_be_bytes([cmf, flg]) % 31 == 0 => {
+                Compression::Zlib
+            }
+            _ => Compression::None,
+        }
+    }
+
+    /// Decompress the data into the buffer, replacing anything that was in it.
+    pub(crate) fn decompress(self, data: &[u8], buffer: &mut Vec<u8>) -> Result<(), Error> {
+        buffer.clear();
+        let result = match self {
+            Compression::None => {
+                buffer.extend_from_slice(data);
+                return Ok(());
+            }
+            Compression::Gzip => GzDecoder::new(data).read_to_end(buffer),
+            Compression::Zlib => ZlibDecoder::new(data).read_to_end(buffer),
+        };
+        result.map_err(|_| Error::InvalidCompressedData)?;
+        Ok(())
+    }
+
+    /// Compress the data with the default compression level.
+    pub(crate) fn compress(self, data: Vec<u8>) -> Vec<u8> {
+        let level = flate2::Compression::default();
+        // writing to a vec can't fail
+        match self {
+            Compression::None => data,
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(&data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(&data).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::owned::{BaseNbt, Nbt, NbtCompound};
+
+    #[test]
+    fn to_compressed_bytes() {
+        let mut compound = NbtCompound::new();
+        compound.insert("DataVersion", 3953);
+        let nbt = BaseNbt::new("", compound.clone());
+        let data = nbt.to_bytes();
+        assert_eq!(data[0], crate::common::COMPOUND_ID);
+        assert_eq!(data[3..], compound.to_bytes());
+        assert_eq!(nbt.to_compressed_bytes(Compression::None), data);
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&nbt.to_compressed_bytes(Compression::Gzip)[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+
+        let borrowed = crate::borrow::read(&mut Cursor::new(&data)).unwrap();
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(&borrowed.to_compressed_bytes(Compression::Zlib)[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+
+        assert_eq!(Nbt::None.to_bytes(), [0]);
+    }
+
+    #[test]
+    fn read_compressed() {
+        let src = include_bytes!("../tests/bigtest.nbt");
+        assert_eq!(Compression::detect(src), Compression::Gzip);
+        let nbt = Nbt::read_compressed(src).unwrap();
+
+        let uncompressed = nbt.to_bytes();
+        let zlib = nbt.to_compressed_bytes(Compression::Zlib);
+        assert_eq!(Compression::detect(&uncompressed), Compression::None);
+        assert_eq!(Compression::detect(&zlib), Compression::Zlib);
+
+        for data in [&src[..], &uncompressed, &zlib] {
+            assert_eq!(Nbt::read_compressed(data).unwrap(), nbt);
+
+            let mut buffer = Vec::new();
+            let borrowed = crate::borrow::read_compressed(data, &mut buffer).unwrap();
+            assert_eq!(borrowed.to_bytes(), uncompressed);
+        }
+
+        assert_eq!(
+            Nbt::read_compressed(&[0x1f, 0x8b, 1, 2, 3]).unwrap_err(),
+            Error::InvalidCompressedData
+        );
+    }
+}
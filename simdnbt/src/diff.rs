@@ -0,0 +1,402 @@
+//! Find every difference between two compounds.
+
+use std::collections::HashMap;
+
+use crate::{
+    borrow,
+    common::{COMPOUND_ID, DOUBLE_ID, FLOAT_ID, LIST_ID},
+    owned::{self, NbtTag},
+    Mutf8Str, NbtPath, PathSegment,
+};
+
+/// A difference between two compounds, returned by [`diff()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// A tag that's only in the second compound.
+    Added { path: NbtPath, value: NbtTag },
+    /// A tag that's only in the first compound.
+    Removed { path: NbtPath, value: NbtTag },
+    /// A tag that has a different value in each compound.
+    Changed {
+        path: NbtPath,
+        old: NbtTag,
+        new: NbtTag,
+    },
+}
+
+impl Difference {
+    pub fn path(&self) -> &NbtPath {
+        match self {
+            Difference::Added { path, .. }
+            | Difference::Removed { path, .. }
+            | Difference::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// A compound that can be passed to [`diff()`]. You usually don't have to
+/// make this yourself, since owned and borrowed compounds and [`BaseNbt`]s
+/// can be converted into it.
+///
+/// [`BaseNbt`]: owned::BaseNbt
+#[derive(Clone, Copy)]
+pub enum CompoundRef<'a: 'tape, 'tape> {
+    Owned(&'a owned::NbtCompound),
+    Borrowed(borrow::NbtCompound<'a, 'tape>),
+}
+
+impl<'a: 'tape, 'tape> From<&'a owned::NbtCompound> for CompoundRef<'a, 'tape> {
+    fn from(compound: &'a owned::NbtCompound) -> Self {
+        CompoundRef::Owned(compound)
+    }
+}
+impl<'a: 'tape, 'tape> From<&'a owned::BaseNbt> for CompoundRef<'a, 'tape> {
+    fn from(nbt: &'a owned::BaseNbt) -> Self {
+        CompoundRef::Owned(nbt)
+    }
+}
+impl<'a: 'tape, 'tape> From<borrow::NbtCompound<'a, 'tape>> for CompoundRef<'a, 'tape> {
+    fn from(compound: borrow::NbtCompound<'a, 'tape>) -> Self {
+        CompoundRef::Borrowed(compound)
+    }
+}
+impl<'a> From<&'a borrow::BaseNbt<'a>> for CompoundRef<'a, 'a> {
+    fn from(nbt: &'a borrow::BaseNbt<'a>) -> Self {
+        CompoundRef::Borrowed(nbt.as_compound())
+    }
+}
+
+impl<'a: 'tape, 'tape> CompoundRef<'a, 'tape> {
+    fn entries(self) -> Vec<(&'a Mutf8Str, Value<'a, 'tape>)> {
+        match self {
+            CompoundRef::Owned(compound) => compound
+                .iter()
+                .map(|(name, tag)| (name, Value::Tag(tag).normalize()))
+                .collect(),
+            CompoundRef::Borrowed(compound) => compound
+                .iter()
+                .map(|(name, tag)| (name, Value::Borrowed(tag)))
+                .collect(),
+        }
+    }
+}
+
+/// Find the differences between two compounds, in the order that the tags
+/// appear in them.
+///
+/// Tags in compounds are matched up by their names, so the order of keys
+/// doesn't matter. Lists of compounds and lists of lists are compared element
+/// by element, and any other list is reported as a single change. Owned and
+/// borrowed compounds can be compared with each other without converting
+/// either of them.
+///
+/// ```
+/// use simdnbt::{diff, owned::NbtCompound, Difference};
+///
+/// let mut a = NbtCompound::new();
+/// a.insert("health", 20f32);
+/// a.insert("xp", 5);
+/// let mut b = NbtCompound::new();
+/// b.insert("health", 15f32);
+/// b.insert("name", "steve");
+///
+/// let differences = diff(&a, &b);
+/// assert_eq!(differences.len(), 3);
+/// assert_eq!(differences[0].path().to_string(), "health");
+/// assert!(matches!(differences[1], Difference::Removed { .. }));
+/// assert!(matches!(differences[2], Difference::Added { .. }));
+/// ```
+pub fn diff<'a: 'tape, 'tape>(
+    a: impl Into<CompoundRef<'a, 'tape>>,
+    b: impl Into<CompoundRef<'a, 'tape>>,
+) -> Vec<Difference> {
+    let mut differ = Differ {
+        path: Vec::new(),
+        differences: Vec::new(),
+    };
+    differ.compound(a.into(), b.into());
+    differ.differences
+}
+
+struct Differ {
+    path: Vec<PathSegment>,
+    differences: Vec<Difference>,
+}
+
+impl Differ {
+    fn path(&self) -> NbtPath {
+        self.path
+            .iter()
+            .fold(NbtPath::new(), |path, segment| match segment {
+                PathSegment::Key(key) => path.key(key.clone()),
+                PathSegment::Index(index) => path.index(*index),
+            })
+    }
+
+    fn compound(&mut self, a: CompoundRef, b: CompoundRef) {
+        let b_entries = b.entries();
+        let mut b_positions = HashMap::with_capacity(b_entries.len());
+        for (i, (name, _)) in b_entries.iter().enumerate() {
+            // like `get`, only the first tag with a name counts
+            b_positions.entry(*name).or_insert(i);
+        }
+        let mut b_entries = b_entries.into_iter().map(Some).collect::<Vec<_>>();
+
+        for (name, a_tag) in a.entries() {
+            self.path
+                .push(PathSegment::Key(name.to_string_lossy().into_owned()));
+            match b_positions.get(name).and_then(|&i| b_entries[i].take()) {
+                Some((_, b_tag)) => self.tag(a_tag, b_tag),
+                None => self.differences.push(Difference::Removed {
+                    path: self.path(),
+                    value: a_tag.to_owned(),
+                }),
+            }
+            self.path.pop();
+        }
+        for (name, b_tag) in b_entries.into_iter().flatten() {
+            self.path
+                .push(PathSegment::Key(name.to_string_lossy().into_owned()));
+            self.differences.push(Difference::Added {
+                path: self.path(),
+                value: b_tag.to_owned(),
+            });
+            self.path.pop();
+        }
+    }
+
+    fn tag(&mut self, a: Value, b: Value) {
+        if let (Some(a), Some(b)) = (a.compound(), b.compound()) {
+            return self.compound(a, b);
+        }
+        if let (Some((a_id, a_items)), Some((b_id, b_items))) = (a.containers(), b.containers()) {
+            if a_id == b_id {
+                return self.list(a_items, b_items);
+            }
+        }
+        if !a.eq(&b) {
+            self.differences.push(Difference::Changed {
+                path: self.path(),
+                old: a.to_owned(),
+                new: b.to_owned(),
+            });
+        }
+    }
+
+    fn list(&mut self, a: Vec<Value>, b: Vec<Value>) {
+        let mut a = a.into_iter();
+        let mut b = b.into_iter();
+        for index in 0.. {
+            self.path.push(PathSegment::Index(index));
+            match (a.next(), b.next()) {
+                (Some(a), Some(b)) => self.tag(a, b),
+                (Some(a), None) => self.differences.push(Difference::Removed {
+                    path: self.path(),
+                    value: a.to_owned(),
+                }),
+                (None, Some(b)) => self.differences.push(Difference::Added {
+                    path: self.path(),
+                    value: b.to_owned(),
+                }),
+                (None, None) => {
+                    self.path.pop();
+                    return;
+                }
+            }
+            self.path.pop();
+        }
+    }
+}
+
+/// A tag from either an owned or a borrowed compound. Owned lists and
+/// compounds that are tags are always in the `List` and `Compound` variants,
+/// so they can be compared with lists and compounds that are elements of
+/// lists.
+enum Value<'a: 'tape, 'tape> {
+    Tag(&'a NbtTag),
+    List(&'a owned::NbtList),
+    Compound(&'a owned::NbtCompound),
+    Borrowed(borrow::NbtTag<'a, 'tape>),
+}
+
+impl<'a: 'tape, 'tape> Value<'a, 'tape> {
+    fn normalize(self) -> Self {
+        match self {
+            Value::Tag(NbtTag::List(list)) => Value::List(list),
+            Value::Tag(NbtTag::Compound(compound)) => Value::Compound(compound),
+            value => value,
+        }
+    }
+
+    fn compound(&self) -> Option<CompoundRef<'a, 'tape>> {
+        match self {
+            Value::Compound(compound) => Some(CompoundRef::Owned(compound)),
+            Value::Borrowed(tag) => tag.compound().map(CompoundRef::Borrowed),
+            _ => None,
+        }
+    }
+
+    /// The element ID and elements of a list of compounds or a list of lists.
+    fn containers(&self) -> Option<(u8, Vec<Self>)> {
+        match self {
+            Value::List(owned::NbtList::Compound(compounds)) => {
+                Some((COMPOUND_ID, compounds.iter().map(Value::Compound).collect()))
+            }
+            Value::List(owned::NbtList::List(lists)) => {
+                Some((LIST_ID, lists.iter().map(Value::List).collect()))
+            }
+            Value::Borrowed(tag) => {
+                let list = tag.list()?;
+                matches!(list.id(), COMPOUND_ID | LIST_ID)
+                    .then(|| (list.id(), list.iter().map(Value::Borrowed).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Floats are compared with [`NbtTag::total_eq`], so NaNs that didn't
+    /// change aren't reported.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Borrowed(a), b) | (b, Value::Borrowed(a)) if has_floats(a) => {
+                a.to_owned().total_eq(&b.to_owned())
+            }
+            (Value::Borrowed(a), Value::Borrowed(b)) => a == b,
+            (Value::Borrowed(a), b) | (b, Value::Borrowed(a)) => match b {
+                Value::Tag(tag) => a == *tag,
+                Value::List(list) => a.list().is_some_and(|a| a == **list),
+                Value::Compound(compound) => a.compound().is_some_and(|a| a == **compound),
+                Value::Borrowed(_) => unreachable!(),
+            },
+            (Value::Tag(a), Value::Tag(b)) => a.total_eq(b),
+            (Value::List(a), Value::List(b)) => a.total_eq(b),
+            (Value::Compound(a), Value::Compound(b)) => a.total_eq(b),
+            _ => false,
+        }
+    }
+
+    fn to_owned(&self) -> NbtTag {
+        match self {
+            Value::Tag(tag) => (*tag).clone(),
+            Value::List(list) => NbtTag::List((*list).clone()),
+            Value::Compound(compound) => NbtTag::Compound((*compound).clone()),
+            Value::Borrowed(tag) => tag.to_owned(),
+        }
+    }
+}
+
+fn has_floats(tag: &borrow::NbtTag) -> bool {
+    let id = tag.list().map_or(tag.id(), |list| list.id());
+    matches!(id, FLOAT_ID | DOUBLE_ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        owned::{NbtCompound, NbtList},
+        test_fixtures::{item, write},
+    };
+
+    #[test]
+    fn differences() {
+        let mut a = NbtCompound::new();
+        a.insert("Inventory", NbtList::Compound(vec![item(1), item(2)]));
+        a.insert("Pos", NbtList::Double(vec![0., 1., 2.]));
+        a.insert("Health", 20f32);
+        a.insert("Old", 1);
+
+        let mut b = NbtCompound::new();
+        b.insert("Health", 15f32);
+        b.insert("Pos", NbtList::Double(vec![0., 1., 3.]));
+        b.insert(
+            "Inventory",
+            NbtList::Compound(vec![item(1), item(3), item(4)]),
+        );
+        b.insert("New", "x");
+
+        let expected = vec![
+            Difference::Changed {
+                path: "Inventory[1].Count".parse().unwrap(),
+                old: NbtTag::Byte(2),
+                new: NbtTag::Byte(3),
+            },
+            Difference::Added {
+                path: "Inventory[2]".parse().unwrap(),
+                value: NbtTag::Compound(item(4)),
+            },
+            Difference::Changed {
+                path: "Pos".parse().unwrap(),
+                old: NbtTag::List(NbtList::Double(vec![0., 1., 2.])),
+                new: NbtTag::List(NbtList::Double(vec![0., 1., 3.])),
+            },
+            Difference::Changed {
+                path: "Health".parse().unwrap(),
+                old: NbtTag::Float(20.),
+                new: NbtTag::Float(15.),
+            },
+            Difference::Removed {
+                path: "Old".parse().unwrap(),
+                value: NbtTag::Int(1),
+            },
+            Difference::Added {
+                path: "New".parse().unwrap(),
+                value: NbtTag::String("x".into()),
+            },
+        ];
+        assert_eq!(diff(&a, &b), expected);
+
+        // the same thing with borrowed compounds on either side
+        let a_data = write(a.clone());
+        let b_data = write(b.clone());
+        let a_nbt = borrow::read(&mut Cursor::new(&a_data)).unwrap().unwrap();
+        let b_nbt = borrow::read(&mut Cursor::new(&b_data)).unwrap().unwrap();
+        assert_eq!(diff(&a_nbt, &b_nbt), expected);
+        assert_eq!(diff(&a_nbt, &b), expected);
+        assert_eq!(diff(&a, b_nbt.as_compound()), expected);
+
+        assert_eq!(diff(&a, &a), []);
+        assert_eq!(diff(&a_nbt, &a), []);
+    }
+
+    #[test]
+    fn nested_lists() {
+        let mut a = NbtCompound::new();
+        a.insert(
+            "lists",
+            NbtList::List(vec![NbtList::Int(vec![1]), NbtList::Empty]),
+        );
+        let mut b = NbtCompound::new();
+        b.insert("lists", NbtList::List(vec![NbtList::Int(vec![2])]));
+        let b_data = write(b.clone());
+        let b_nbt = borrow::read(&mut Cursor::new(&b_data)).unwrap().unwrap();
+
+        let expected = vec![
+            Difference::Changed {
+                path: "lists[0]".parse().unwrap(),
+                old: NbtTag::List(NbtList::Int(vec![1])),
+                new: NbtTag::List(NbtList::Int(vec![2])),
+            },
+            Difference::Removed {
+                path: "lists[1]".parse().unwrap(),
+                value: NbtTag::List(NbtList::Empty),
+            },
+        ];
+        assert_eq!(diff(&a, &b), expected);
+        assert_eq!(diff(&a, &b_nbt), expected);
+    }
+    #[test]
+    fn unchanged_nan() {
+        let mut a = NbtCompound::new();
+        a.insert("nan", f32::NAN);
+        a.insert("nans", NbtList::Double(vec![f64::NAN]));
+        let data = write(a.clone());
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        assert_eq!(diff(&a, &a), []);
+        assert_eq!(diff(&a, &nbt), []);
+        assert_eq!(diff(&nbt, &nbt), []);
+    }
+}
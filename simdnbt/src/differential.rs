@@ -0,0 +1,361 @@
+//! Decode the same data with every decoder in simdnbt and make sure that they
+//! all agree with each other.
+//!
+//! This is mostly useful for fuzzing and for testing tools that produce NBT,
+//! since a disagreement between the decoders means there's a bug in one of
+//! them.
+//!
+//! The SIMD code that's used with the `nightly` feature and the fallback code
+//! that's used without it are picked at compile time, so they can't both be
+//! checked by the same build. To check the fallback code, run the checks (or
+//! the fuzz targets, which have a `nightly` feature too) with
+//! `--no-default-features`.
+//!
+//! ```
+//! let mut data = Vec::new();
+//! simdnbt::owned::BaseNbt::new("", simdnbt::owned::NbtCompound::new()).write(&mut data);
+//!
+//! simdnbt::differential::check(&data).unwrap();
+//! ```
+
+use std::{fmt::Write, io::Cursor};
+
+use thiserror::Error;
+
+use crate::{
+    borrow,
+    owned::{self, NbtCompound, NbtList, NbtTag},
+    Error,
+};
+
+/// A disagreement between the decoders, returned by [`check`].
+#[derive(Error, Debug, PartialEq)]
+pub enum Mismatch {
+    /// One of the decoders returned an error and the other didn't, or they
+    /// returned different errors.
+    #[error(
+        "decoders returned different results: borrow returned {borrow:?}, owned returned {owned:?}"
+    )]
+    Error {
+        borrow: Option<Error>,
+        owned: Option<Error>,
+    },
+    /// The decoders stopped reading at different positions in the data.
+    #[error("decoders read a different number of bytes: borrow read {borrow}, owned read {owned}")]
+    BytesRead { borrow: u64, owned: u64 },
+    /// The decoders read a different name for the root compound.
+    #[error("decoders read different root names: borrow read {borrow:?}, owned read {owned:?}")]
+    Name { borrow: String, owned: String },
+    /// The decoders decoded different values. The path is separated by dots
+    /// and indexes into lists look like `[0]`, and it's empty for the root
+    /// compound.
+    ///
+    /// A value is `None` if that decoder didn't have anything at the path.
+    #[error("decoders disagree at {path:?}: borrow decoded {borrow:?}, owned decoded {owned:?}")]
+    Value {
+        path: String,
+        borrow: Option<NbtTag>,
+        owned: Option<NbtTag>,
+    },
+    /// Reading the written data gave a different value than the one that was
+    /// written, returned by [`check_roundtrip`].
+    #[error(
+        "value changed when written and read at {path:?}: wrote {original:?}, read {decoded:?}"
+    )]
+    Roundtrip {
+        path: String,
+        original: Option<NbtTag>,
+        decoded: Option<NbtTag>,
+    },
+}
+
+/// Decode the data as a normal root NBT compound with both the
+/// [borrow](crate::borrow) and [owned](crate::owned) decoders, and return the
+/// first difference between them.
+///
+/// Unlike `PartialEq`, floats are compared by their bits, so NaNs are
+/// considered equal to themselves.
+pub fn check(data: &[u8]) -> Result<(), Mismatch> {
+    let mut borrow_cursor = Cursor::new(data);
+    let borrow_result = borrow::read(&mut borrow_cursor);
+    let mut owned_cursor = Cursor::new(data);
+    let owned_result = owned::read(&mut owned_cursor);
+
+    let (borrow_nbt, owned_nbt) = match (borrow_result, owned_result) {
+        (Ok(borrow_nbt), Ok(owned_nbt)) => (borrow_nbt, owned_nbt),
+        (Err(borrow_err), Err(owned_err)) if borrow_err == owned_err => return Ok(()),
+        (borrow_result, owned_result) => {
+            return Err(Mismatch::Error {
+                borrow: borrow_result.err(),
+                owned: owned_result.err(),
+            })
+        }
+    };
+
+    if borrow_cursor.position() != owned_cursor.position() {
+        return Err(Mismatch::BytesRead {
+            borrow: borrow_cursor.position(),
+            owned: owned_cursor.position(),
+        });
+    }
+
+    let (borrow_nbt, owned_nbt) = match (borrow_nbt, owned_nbt) {
+        (borrow::Nbt::Some(borrow_nbt), owned::Nbt::Some(owned_nbt)) => (borrow_nbt, owned_nbt),
+        (borrow::Nbt::None, owned::Nbt::None) => return Ok(()),
+        (borrow_nbt, owned_nbt) => {
+            return Err(Mismatch::Value {
+                path: String::new(),
+                borrow: match borrow_nbt {
+                    borrow::Nbt::Some(nbt) => Some(NbtTag::Compound(nbt.as_compound().to_owned())),
+                    borrow::Nbt::None => None,
+                },
+                owned: match owned_nbt {
+                    owned::Nbt::Some(nbt) => Some(NbtTag::Compound(nbt.as_compound())),
+                    owned::Nbt::None => None,
+                },
+            })
+        }
+    };
+
+    if borrow_nbt.name() != owned_nbt.name() {
+        return Err(Mismatch::Name {
+            borrow: borrow_nbt.name().to_string_lossy().into_owned(),
+            owned: owned_nbt.name().to_string_lossy().into_owned(),
+        });
+    }
+
+    let borrow_compound = borrow_nbt.as_compound().to_owned();
+    compare_compounds(&mut String::new(), &borrow_compound, &owned_nbt)
+}
+
+/// Write the NBT, make sure that the decoders agree on the written data with
+/// [`check`], and then make sure that reading it gives back the same NBT.
+///
+/// This is useful for fuzzing with NBT generated by the `arbitrary` feature.
+///
+/// ```
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// let nbt = simdnbt::owned::BaseNbt::new("", compound);
+///
+/// simdnbt::differential::check_roundtrip(&nbt).unwrap();
+/// ```
+pub fn check_roundtrip(nbt: &owned::BaseNbt) -> Result<(), Mismatch> {
+    let mut data = Vec::new();
+    nbt.write(&mut data);
+    check(&data)?;
+
+    let decoded = match owned::read(&mut Cursor::new(&data)) {
+        Ok(owned::Nbt::Some(decoded)) => decoded,
+        Ok(owned::Nbt::None) => unreachable!("a root compound was written"),
+        Err(err) => {
+            return Err(Mismatch::Error {
+                borrow: None,
+                owned: Some(err),
+            })
+        }
+    };
+    compare_compounds(&mut String::new(), nbt, &decoded).map_err(|mismatch| match mismatch {
+        Mismatch::Value {
+            path,
+            borrow,
+            owned,
+        } => Mismatch::Roundtrip {
+            path,
+            original: borrow,
+            decoded: owned,
+        },
+        mismatch => mismatch,
+    })
+}
+
+fn compare_tags(path: &mut String, borrow: &NbtTag, owned: &NbtTag) -> Result<(), Mismatch> {
+    let equal = match (borrow, owned) {
+        (NbtTag::Compound(borrow), NbtTag::Compound(owned)) => {
+            return compare_compounds(path, borrow, owned)
+        }
+        (NbtTag::List(borrow), NbtTag::List(owned)) => return compare_lists(path, borrow, owned),
+        (borrow, owned) => borrow.total_eq(owned),
+    };
+    if equal {
+        Ok(())
+    } else {
+        Err(value_mismatch(path, Some(borrow), Some(owned)))
+    }
+}
+
+fn compare_compounds(
+    path: &mut String,
+    borrow: &NbtCompound,
+    owned: &NbtCompound,
+) -> Result<(), Mismatch> {
+    let mut borrow_iter = borrow.iter();
+    let mut owned_iter = owned.iter();
+    loop {
+        let path_len = path.len();
+        let result = match (borrow_iter.next(), owned_iter.next()) {
+            (None, None) => return Ok(()),
+            (Some((borrow_key, borrow_tag)), Some((owned_key, owned_tag))) => {
+                push_key(path, &borrow_key.to_string_lossy());
+                if borrow_key == owned_key {
+                    compare_tags(path, borrow_tag, owned_tag)
+                } else {
+                    Err(value_mismatch(
+                        path,
+                        Some(borrow_tag),
+                        owned.get(&borrow_key.to_str()),
+                    ))
+                }
+            }
+            (Some((borrow_key, borrow_tag)), None) => {
+                push_key(path, &borrow_key.to_string_lossy());
+                Err(value_mismatch(path, Some(borrow_tag), None))
+            }
+            (None, Some((owned_key, owned_tag))) => {
+                push_key(path, &owned_key.to_string_lossy());
+                Err(value_mismatch(path, None, Some(owned_tag)))
+            }
+        };
+        result?;
+        path.truncate(path_len);
+    }
+}
+
+fn compare_lists(path: &mut String, borrow: &NbtList, owned: &NbtList) -> Result<(), Mismatch> {
+    let equal = match (borrow, owned) {
+        (NbtList::Compound(borrow), NbtList::Compound(owned)) if borrow.len() == owned.len() => {
+            for (i, (borrow, owned)) in borrow.iter().zip(owned).enumerate() {
+                let path_len = path.len();
+                write!(path, "[{i}]").unwrap();
+                compare_compounds(path, borrow, owned)?;
+                path.truncate(path_len);
+            }
+            true
+        }
+        (NbtList::List(borrow), NbtList::List(owned)) if borrow.len() == owned.len() => {
+            for (i, (borrow, owned)) in borrow.iter().zip(owned).enumerate() {
+                let path_len = path.len();
+                write!(path, "[{i}]").unwrap();
+                compare_lists(path, borrow, owned)?;
+                path.truncate(path_len);
+            }
+            true
+        }
+        (borrow, owned) => borrow.total_eq(owned),
+    };
+    if equal {
+        Ok(())
+    } else {
+        Err(value_mismatch(
+            path,
+            Some(&NbtTag::List(borrow.clone())),
+            Some(&NbtTag::List(owned.clone())),
+        ))
+    }
+}
+
+fn push_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+}
+
+fn value_mismatch(path: &str, borrow: Option<&NbtTag>, owned: Option<&NbtTag>) -> Mismatch {
+    Mismatch::Value {
+        path: path.to_owned(),
+        borrow: borrow.cloned(),
+        owned: owned.cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        owned::BaseNbt,
+        test_fixtures::{bigtest, complex_player, level_dat},
+    };
+
+    #[test]
+    fn test_files_agree() {
+        for decoded_src in [complex_player(), level_dat(), bigtest()] {
+            check(&decoded_src).unwrap();
+        }
+        check(include_bytes!("../tests/hypixel.nbt")).unwrap();
+    }
+
+    #[test]
+    fn errors_agree() {
+        let mut data = Vec::new();
+        let mut compound = NbtCompound::new();
+        compound.insert("a", NbtTag::List(NbtList::Int(vec![1, 2, 3])));
+        BaseNbt::new("", compound).write(&mut data);
+
+        for len in 0..data.len() {
+            check(&data[..len]).unwrap();
+        }
+        check(&[8]).unwrap();
+        check(&[0]).unwrap();
+    }
+
+    #[test]
+    fn nan_is_equal_to_itself() {
+        let mut data = Vec::new();
+        let mut compound = NbtCompound::new();
+        compound.insert("nan", f64::NAN);
+        compound.insert("nans", NbtTag::List(NbtList::Float(vec![f32::NAN])));
+        BaseNbt::new("", compound).write(&mut data);
+
+        check(&data).unwrap();
+    }
+
+    #[test]
+    fn mismatch_path() {
+        let mut item = NbtCompound::new();
+        item.insert("Count", 1i8);
+        let mut borrow = NbtCompound::new();
+        borrow.insert("Inventory", NbtList::Compound(vec![item.clone(), item]));
+        let mut owned = borrow.clone();
+        *owned.list_mut("Inventory").unwrap() = NbtList::Compound(vec![
+            {
+                let mut item = NbtCompound::new();
+                item.insert("Count", 1i8);
+                item
+            },
+            {
+                let mut item = NbtCompound::new();
+                item.insert("Count", 2i8);
+                item
+            },
+        ]);
+
+        assert_eq!(
+            compare_compounds(&mut String::new(), &borrow, &owned),
+            Err(Mismatch::Value {
+                path: "Inventory[1].Count".to_owned(),
+                borrow: Some(NbtTag::Byte(1)),
+                owned: Some(NbtTag::Byte(2)),
+            })
+        );
+
+        owned.insert("extra", 0);
+        assert_eq!(
+            compare_compounds(&mut String::new(), &borrow, &owned),
+            Err(Mismatch::Value {
+                path: "Inventory[1].Count".to_owned(),
+                borrow: Some(NbtTag::Byte(1)),
+                owned: Some(NbtTag::Byte(2)),
+            })
+        );
+        *owned.list_mut("Inventory").unwrap() = borrow.list("Inventory").unwrap().clone();
+        assert_eq!(
+            compare_compounds(&mut String::new(), &borrow, &owned),
+            Err(Mismatch::Value {
+                path: "extra".to_owned(),
+                borrow: None,
+                owned: Some(NbtTag::Int(0)),
+            })
+        );
+    }
+}
@@ -2,8 +2,10 @@ use std::fmt::Debug;
 
 use thiserror::Error;
 
-use crate::common::MAX_DEPTH;
+use crate::{common::MAX_DEPTH, NbtPath, PathSegment};
 
+/// An error from reading NBT. This doesn't say where in the data the error
+/// happened, but [`locate_error`](crate::locate_error) can find that.
 #[derive(Error, Debug, PartialEq)]
 pub enum Error {
     #[error("Invalid root type {0}")]
@@ -14,11 +16,28 @@ pub enum Error {
     UnexpectedEof,
     #[error("Tried to read NBT tag with too high complexity, depth > {MAX_DEPTH}")]
     MaxDepthExceeded,
+    #[error("Tried to read a length that's too large")]
+    InvalidLength,
+    #[error("Tried to read more tags than allowed by the read options")]
+    TooManyTags,
+    #[error("Tried to read more bytes than allowed by the read options")]
+    TooManyBytes,
+    #[error("Ran out of memory while reading")]
+    OutOfMemory,
+    #[error("The data looked compressed but couldn't be decompressed")]
+    InvalidCompressedData,
+    #[error("Found a compound with a duplicate key")]
+    DuplicateKey,
 }
 
 // these two structs exist to optimize errors, since Error is an entire 2 bytes
 // which are often unnecessary
-pub(crate) struct UnexpectedEofError;
+/// An error for when there wasn't enough data, which is returned by the
+/// methods of [`Reader`](crate::reader::Reader) that can't fail in any other
+/// way. This converts into [`Error::UnexpectedEof`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Unexpected end of data")]
+pub struct UnexpectedEofError;
 impl From<UnexpectedEofError> for Error {
     fn from(_: UnexpectedEofError) -> Self {
         Error::UnexpectedEof
@@ -27,6 +46,10 @@ impl From<UnexpectedEofError> for Error {
 pub struct NonRootError {
     // 0 = unexpected eof
     // 1 = max depth exceeded
+    // 2 = invalid length
+    // 3 = too many tags
+    // 4 = out of memory
+    // 5 = duplicate key
     // anything else = unknown tag id, the id is value-1
     value: u8,
 }
@@ -36,6 +59,10 @@ impl From<NonRootError> for Error {
         match e.value {
             0 => Error::UnexpectedEof,
             1 => Error::MaxDepthExceeded,
+            2 => Error::InvalidLength,
+            3 => Error::TooManyTags,
+            4 => Error::OutOfMemory,
+            5 => Error::DuplicateKey,
             _ => Error::UnknownTagId(e.value.wrapping_add(1)),
         }
     }
@@ -51,9 +78,25 @@ impl NonRootError {
         NonRootError { value: 1 }
     }
     #[inline]
+    pub fn invalid_length() -> Self {
+        NonRootError { value: 2 }
+    }
+    #[inline]
+    pub fn too_many_tags() -> Self {
+        NonRootError { value: 3 }
+    }
+    #[inline]
+    pub fn out_of_memory() -> Self {
+        NonRootError { value: 4 }
+    }
+    #[inline]
+    pub fn duplicate_key() -> Self {
+        NonRootError { value: 5 }
+    }
+    #[inline]
     pub fn unknown_tag_id(id: u8) -> Self {
-        // the value can't be 1 or 2 (because those are always valid tag ids),
-        // so we take advantage of that in our encoding
+        // the value can't be 0 to 11 (because ids 1 to 12 are always valid
+        // tag ids), so we take advantage of that in our encoding
         NonRootError {
             value: id.wrapping_sub(1),
         }
@@ -71,12 +114,16 @@ impl Debug for NonRootError {
         match self.value {
             0 => write!(f, "UnexpectedEofError"),
             1 => write!(f, "MaxDepthExceededError"),
+            2 => write!(f, "InvalidLengthError"),
+            3 => write!(f, "TooManyTagsError"),
+            4 => write!(f, "OutOfMemoryError"),
+            5 => write!(f, "DuplicateKeyError"),
             _ => write!(f, "UnknownTagId({})", self.value.wrapping_add(1)),
         }
     }
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum DeserializeError {
     #[error("Missing field")]
     MissingField,
@@ -84,4 +131,58 @@ pub enum DeserializeError {
     MismatchedFieldType(String),
     #[error("Unknown field {0:?}")]
     UnknownField(String),
+    #[error("Unknown variant {0:?}")]
+    UnknownVariant(String),
+    /// An error that happened in a nested tag, like a field of a struct or an
+    /// element of a list.
+    #[error("{error} at {path}")]
+    At {
+        path: NbtPath,
+        error: Box<DeserializeError>,
+    },
+}
+
+impl DeserializeError {
+    /// Mark the error as having happened in the tag with the given key. This
+    /// is called on the way back up from a nested error, so the key is added
+    /// to the start of the path.
+    pub fn in_key(self, key: &str) -> Self {
+        self.in_segment(PathSegment::Key(key.to_owned()))
+    }
+
+    /// Mark the error as having happened in the list element with the given
+    /// index, like [`Self::in_key`].
+    pub fn in_index(self, index: usize) -> Self {
+        self.in_segment(PathSegment::Index(index))
+    }
+
+    fn in_segment(self, segment: PathSegment) -> Self {
+        match self {
+            DeserializeError::At { mut path, error } => {
+                path.push_front(segment);
+                DeserializeError::At { path, error }
+            }
+            error => DeserializeError::At {
+                path: NbtPath::new().segment(segment),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// The path to where the error happened, or `None` if it happened at the
+    /// top level.
+    pub fn path(&self) -> Option<&NbtPath> {
+        match self {
+            DeserializeError::At { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The error without the path to where it happened.
+    pub fn inner(&self) -> &DeserializeError {
+        match self {
+            DeserializeError::At { error, .. } => error,
+            error => error,
+        }
+    }
 }
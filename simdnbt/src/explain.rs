@@ -0,0 +1,712 @@
+//! Print the tree of an NBT document along with where each tag is in the data.
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Write},
+    mem,
+};
+
+use crate::{
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
+        LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    error::{NonRootError, UnexpectedEofError},
+    options::{DuplicateKeyPolicy, Limits},
+    owned::{NbtCompound, NbtList, NbtTag},
+    reader::Reader,
+    Error, Mutf8Str, Mutf8String, NbtPath, PathSegment, ReadOptions,
+};
+
+/// How the root NBT that's passed to [`dump_annotated_with`] and
+/// [`locate_error_with`] is laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RootLayout {
+    /// A normal root compound with a name, like in files.
+    #[default]
+    Named,
+    /// A root compound without a name, like in Java Edition packets since
+    /// 1.20.2.
+    Unnamed,
+    /// A root compound with a name where every number is little-endian, like
+    /// in Bedrock Edition's files.
+    LittleEndian,
+}
+
+/// Returns the tree of a normal root NBT compound as a string, annotated with
+/// the offset and encoded size of every tag. See [`dump_annotated`].
+pub fn explain(data: &[u8]) -> String {
+    let mut out = String::new();
+    dump_annotated(data, &mut out).expect("writing to a string can't fail");
+    out
+}
+
+/// Write the tree of a normal root NBT compound, with one line for every tag
+/// that includes the offset of the tag in the data (in hex), the number of
+/// bytes it takes up, its name, its type, and its value if it's a number or
+/// string.
+///
+/// The offset and size of a tag in a compound include its tag ID and name.
+/// Lists of numbers and arrays aren't expanded.
+///
+/// Invalid data is dumped up until the point where it became invalid, and then
+/// a final line is written with the error and the path where it happened, like
+/// `error at offset 0x3f21 inside Level.Entities[7]: Unexpected end of data`.
+/// The size of tags that were never finished is shown as `?`.
+///
+/// ```
+/// let mut data = Vec::new();
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// simdnbt::owned::BaseNbt::new("", compound).write(&mut data);
+///
+/// assert_eq!(
+///     simdnbt::explain(&data),
+///     "0x00000000       13  \"\": compound\n\
+///      0x00000003        9    Count: byte = 1\n"
+/// );
+/// ```
+pub fn dump_annotated(data: &[u8], out: &mut impl Write) -> fmt::Result {
+    dump_annotated_with(data, RootLayout::Named, out)
+}
+
+/// Write the tree of some root NBT like [`dump_annotated`], but with the given
+/// layout.
+pub fn dump_annotated_with(data: &[u8], layout: RootLayout, out: &mut impl Write) -> fmt::Result {
+    let mut dumper = Dumper::new(data, layout, &ReadOptions::default());
+    let result = dumper.run();
+
+    for line in &dumper.lines {
+        write!(out, "{:#010x} ", line.offset)?;
+        match line.size {
+            Some(size) => write!(out, "{size:>8}")?,
+            None => write!(out, "{:>8}", "?")?,
+        }
+        write!(
+            out,
+            "  {:indent$}{}: {}",
+            "",
+            line.label,
+            line.kind,
+            indent = line.depth * 2
+        )?;
+        if let Some(value) = &line.value {
+            write!(out, " = {value}")?;
+        }
+        writeln!(out)?;
+    }
+
+    if let Err(error) = result {
+        writeln!(out, "{}", dumper.input.locate(error))?;
+    }
+    Ok(())
+}
+
+/// Where the data became invalid in a normal root NBT compound that couldn't
+/// be read. See [`locate_error`].
+#[derive(Debug, PartialEq)]
+pub struct ErrorLocation {
+    /// The byte offset in the data where the error happened.
+    pub offset: usize,
+    /// The path to the tag that was being read. This is empty if the error
+    /// happened in the root tag.
+    pub path: NbtPath,
+    pub error: Error,
+}
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error at offset {:#x}", self.offset)?;
+        if !self.path.segments().is_empty() {
+            write!(f, " inside {}", self.path)?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+/// Find where a normal root NBT compound became invalid, or return `None` if
+/// the data is valid.
+///
+/// The [`Error`]s returned while reading don't include where they happened,
+/// since keeping track of that would make reading valid data slower. Call
+/// this after reading fails to read the data again, more slowly, and get the
+/// offset and path of the damage.
+///
+/// ```
+/// let mut data = Vec::new();
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// simdnbt::owned::BaseNbt::new("", compound).write(&mut data);
+/// data.truncate(data.len() - 2);
+///
+/// let location = simdnbt::locate_error(&data).unwrap();
+/// assert_eq!(location.offset, 11);
+/// assert_eq!(location.path.to_string(), "Count");
+/// assert_eq!(location.error, simdnbt::Error::UnexpectedEof);
+/// ```
+pub fn locate_error(data: &[u8]) -> Option<ErrorLocation> {
+    locate_error_with(data, RootLayout::Named)
+}
+
+/// Find where some root NBT became invalid like [`locate_error`], but with the
+/// given layout. This is useful for NBT from packets or from Bedrock Edition.
+///
+/// The data is read with the default [`ReadOptions`], so errors that only
+/// happen because of a limit or [`DuplicateKeyPolicy::Error`] aren't found.
+/// Use [`locate_error_with_opts`] for those.
+///
+/// ```
+/// # use simdnbt::RootLayout;
+/// // an unnamed compound with a byte whose name is cut off
+/// let data = [10, 1, 0, 5, b'C', b'o'];
+///
+/// let location = simdnbt::locate_error_with(&data, RootLayout::Unnamed).unwrap();
+/// assert_eq!(location.offset, 4);
+/// assert_eq!(location.error, simdnbt::Error::UnexpectedEof);
+/// // the data is read differently if it's named
+/// assert_ne!(simdnbt::locate_error(&data).unwrap().offset, 4);
+/// ```
+pub fn locate_error_with(data: &[u8], layout: RootLayout) -> Option<ErrorLocation> {
+    locate_error_with_opts(data, layout, &ReadOptions::default())
+}
+
+/// Find where some root NBT became invalid like [`locate_error_with`], but
+/// with the limits and duplicate key policy from the given options. Use the
+/// same options that reading failed with.
+///
+/// [`ReadOptions::max_tags`] and [`ReadOptions::max_allocation`] are counted
+/// the same way as the owned reader counts them, so the borrow reader may run
+/// out a little earlier or later than where the error is located. Duplicate
+/// keys are located at the second tag with the name.
+///
+/// ```
+/// # use simdnbt::{ReadOptions, RootLayout};
+/// let mut data = Vec::new();
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// simdnbt::owned::BaseNbt::new("", compound).write(&mut data);
+///
+/// let options = ReadOptions {
+///     max_bytes: Some(8),
+///     ..Default::default()
+/// };
+/// let location = simdnbt::locate_error_with_opts(&data, RootLayout::Named, &options).unwrap();
+/// // the name of Count starts at 6 and goes past the limit
+/// assert_eq!(location.offset, 6);
+/// assert_eq!(location.error, simdnbt::Error::TooManyBytes);
+/// ```
+pub fn locate_error_with_opts(
+    data: &[u8],
+    layout: RootLayout,
+    options: &ReadOptions,
+) -> Option<ErrorLocation> {
+    let mut dumper = Dumper::new(data, layout, options);
+    let error = dumper.run().err()?;
+    Some(dumper.input.locate(error))
+}
+
+/// A reader that keeps track of the offset and path of the tag that's being
+/// read, so errors can be located.
+pub(crate) struct PathReader<'a> {
+    data_len: usize,
+    pub reader: Reader<'a>,
+    /// The path to the tag that's currently being read.
+    path: NbtPath,
+}
+
+impl<'a> PathReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data_len: data.len(),
+            reader: Reader::new(data),
+            path: NbtPath::new(),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.data_len - self.reader.remaining()
+    }
+
+    /// Start reading the tag with the given name in a compound.
+    pub fn push_key(&mut self, name: &Mutf8Str) {
+        self.path
+            .push(PathSegment::Key(name.to_string_lossy().into_owned()));
+    }
+    /// Start reading the element at the given index in a list.
+    pub fn push_index(&mut self, index: usize) {
+        self.path.push(PathSegment::Index(index));
+    }
+    /// Finish reading the tag from the last [`Self::push_key`] or
+    /// [`Self::push_index`].
+    pub fn pop(&mut self) {
+        self.path.pop();
+    }
+
+    /// Where the error happened, if it happened at the current offset.
+    pub fn locate(&self, error: impl Into<Error>) -> ErrorLocation {
+        ErrorLocation {
+            offset: self.offset(),
+            path: self.path.clone(),
+            error: error.into(),
+        }
+    }
+}
+
+struct Line {
+    offset: usize,
+    /// This is `None` if the tag couldn't be read completely.
+    size: Option<usize>,
+    depth: usize,
+    label: String,
+    kind: String,
+    value: Option<String>,
+}
+
+struct Dumper<'a> {
+    input: PathReader<'a>,
+    layout: RootLayout,
+    lines: Vec<Line>,
+    limits: Limits,
+    /// Whether the data was cut off at [`Limits::max_bytes`].
+    truncated: bool,
+}
+
+impl<'a> Dumper<'a> {
+    fn new(data: &'a [u8], layout: RootLayout, options: &ReadOptions) -> Self {
+        let limits = Limits::new(options);
+        let truncated = data.len() > limits.max_bytes;
+        let mut input = PathReader::new(&data[..data.len().min(limits.max_bytes)]);
+        input.reader.max_list_len = limits.max_list_len;
+        Self {
+            input,
+            layout,
+            lines: Vec::new(),
+            limits,
+            truncated,
+        }
+    }
+
+    /// Dump the root tag, and turn running out of data into
+    /// [`Error::TooManyBytes`] like the readers do if it was cut off early.
+    fn run(&mut self) -> Result<(), Error> {
+        match self.dump_root() {
+            Err(Error::UnexpectedEof) if self.truncated => Err(Error::TooManyBytes),
+            result => result,
+        }
+    }
+
+    fn dump_root(&mut self) -> Result<(), Error> {
+        let root_type = self
+            .input
+            .reader
+            .read_u8()
+            .map_err(|_| Error::UnexpectedEof)?;
+        if root_type == END_ID {
+            self.lines.push(Line {
+                offset: 0,
+                size: Some(1),
+                depth: 0,
+                label: "(root)".to_owned(),
+                kind: "empty".to_owned(),
+                value: None,
+            });
+            return Ok(());
+        }
+        if root_type != COMPOUND_ID {
+            return Err(Error::InvalidRootType(root_type));
+        }
+        let label = if self.layout == RootLayout::Unnamed {
+            "(root)".to_owned()
+        } else {
+            format!("{:?}", self.read_string()?.to_string_lossy())
+        };
+        self.limits.count_tags(1)?;
+        let line = self.push_line(0, 0, label, root_type);
+        self.dump_payload(line, root_type, 0)?;
+        Ok(())
+    }
+
+    fn read_u16(&mut self) -> Result<u16, UnexpectedEofError> {
+        let value = self.input.reader.read_u16()?;
+        Ok(self.to_native(value, u16::swap_bytes))
+    }
+    fn read_u32(&mut self) -> Result<u32, UnexpectedEofError> {
+        let value = self.input.reader.read_u32()?;
+        Ok(self.to_native(value, u32::swap_bytes))
+    }
+    fn read_u64(&mut self) -> Result<u64, UnexpectedEofError> {
+        let value = self.input.reader.read_u64()?;
+        Ok(self.to_native(value, u64::swap_bytes))
+    }
+    /// The reader reads numbers as big-endian, so they have to be swapped if
+    /// the data is little-endian.
+    fn to_native<T>(&self, value: T, swap_bytes: fn(T) -> T) -> T {
+        if self.layout == RootLayout::LittleEndian {
+            swap_bytes(value)
+        } else {
+            value
+        }
+    }
+
+    fn read_string(&mut self) -> Result<&'a Mutf8Str, UnexpectedEofError> {
+        let length = self.read_u16()?;
+        Ok(Mutf8Str::from_slice(
+            self.input.reader.read_slice(length as usize)?,
+        ))
+    }
+    fn read_length(&mut self) -> Result<u32, NonRootError> {
+        let length = self.read_u32()?;
+        if length > self.input.reader.max_list_len {
+            return Err(NonRootError::invalid_length());
+        }
+        Ok(length)
+    }
+    /// Read an array and return how many elements it has.
+    fn read_array(&mut self, width: usize) -> Result<usize, NonRootError> {
+        let length = self.read_length()? as usize;
+        let length_in_bytes = length
+            .checked_mul(width)
+            .ok_or(NonRootError::invalid_length())?;
+        self.input.reader.skip(length_in_bytes)?;
+        self.limits.allocate(length_in_bytes)?;
+        Ok(length)
+    }
+    /// Read a string and record the memory it'd take up when it's owned.
+    fn read_owned_string(&mut self) -> Result<&'a Mutf8Str, NonRootError> {
+        let string = self.read_string()?;
+        self.limits.allocate(string.len())?;
+        Ok(string)
+    }
+
+    /// Add a line for a tag and return its index.
+    fn push_line(&mut self, offset: usize, depth: usize, label: String, tag_type: u8) -> usize {
+        self.lines.push(Line {
+            offset,
+            size: None,
+            depth,
+            label,
+            kind: type_name(tag_type).to_owned(),
+            value: None,
+        });
+        self.lines.len() - 1
+    }
+
+    /// The deepest that a compound or list can be. The root compound's
+    /// contents are at a depth of 1 here, but the limit doesn't count it.
+    fn max_depth(&self) -> usize {
+        self.limits.max_depth + 1
+    }
+
+    fn dump_payload(
+        &mut self,
+        line: usize,
+        tag_type: u8,
+        depth: usize,
+    ) -> Result<(), NonRootError> {
+        let value = match tag_type {
+            BYTE_ID => Some(self.input.reader.read_i8()?.to_string()),
+            SHORT_ID => Some((self.read_u16()? as i16).to_string()),
+            INT_ID => Some((self.read_u32()? as i32).to_string()),
+            LONG_ID => Some((self.read_u64()? as i64).to_string()),
+            FLOAT_ID => Some(f32::from_bits(self.read_u32()?).to_string()),
+            DOUBLE_ID => Some(f64::from_bits(self.read_u64()?).to_string()),
+            BYTE_ARRAY_ID => Some(elements(self.read_array(1)?)),
+            STRING_ID => Some(format!("{:?}", self.read_owned_string()?.to_string_lossy())),
+            INT_ARRAY_ID => Some(elements(self.read_array(4)?)),
+            LONG_ARRAY_ID => Some(elements(self.read_array(8)?)),
+            LIST_ID => self.dump_list(line, depth + 1)?,
+            COMPOUND_ID => {
+                self.dump_compound(depth + 1)?;
+                None
+            }
+            _ => return Err(NonRootError::unknown_tag_id(tag_type)),
+        };
+
+        let end = self.input.offset();
+        let line = &mut self.lines[line];
+        line.value = value;
+        line.size = Some(end - line.offset);
+        Ok(())
+    }
+
+    fn dump_compound(&mut self, depth: usize) -> Result<(), NonRootError> {
+        if depth > self.max_depth() {
+            return Err(NonRootError::max_depth_exceeded());
+        }
+        let mut names = HashSet::new();
+        loop {
+            let offset = self.input.offset();
+            let tag_type = self.input.reader.read_u8()?;
+            if tag_type == END_ID {
+                return Ok(());
+            }
+            // the key and the value
+            self.limits.count_tags(2)?;
+            let name = self.read_string()?;
+            self.limits
+                .allocate(mem::size_of::<(Mutf8String, NbtTag)>() + name.len())?;
+            self.input.push_key(name);
+            if self.limits.duplicate_keys == DuplicateKeyPolicy::Error && !names.insert(name) {
+                return Err(NonRootError::duplicate_key());
+            }
+
+            let line = self.push_line(offset, depth, name.to_string_lossy().into_owned(), tag_type);
+            self.dump_payload(line, tag_type, depth)?;
+
+            self.input.pop();
+        }
+    }
+
+    /// Dump the elements of a list and return the value that should be shown
+    /// for the list itself.
+    fn dump_list(&mut self, line: usize, depth: usize) -> Result<Option<String>, NonRootError> {
+        if depth > self.max_depth() {
+            return Err(NonRootError::max_depth_exceeded());
+        }
+        let element_type = self.input.reader.read_u8()?;
+        let length = self.read_length()?;
+        self.lines[line].kind = format!("list of {}", type_name(element_type));
+
+        let width = match element_type {
+            END_ID => 0,
+            BYTE_ID => 1,
+            SHORT_ID => 2,
+            INT_ID | FLOAT_ID => 4,
+            LONG_ID | DOUBLE_ID => 8,
+            _ => {
+                let element_size = match element_type {
+                    BYTE_ARRAY_ID => mem::size_of::<Vec<u8>>(),
+                    STRING_ID => mem::size_of::<Mutf8String>(),
+                    LIST_ID => mem::size_of::<NbtList>(),
+                    COMPOUND_ID => mem::size_of::<NbtCompound>(),
+                    INT_ARRAY_ID => mem::size_of::<Vec<i32>>(),
+                    LONG_ARRAY_ID => mem::size_of::<Vec<i64>>(),
+                    _ => 0,
+                };
+                self.limits.count_tags(length as usize)?;
+                self.limits
+                    .allocate((length as usize).saturating_mul(element_size))?;
+                for i in 0..length {
+                    self.input.push_index(i as usize);
+
+                    let offset = self.input.offset();
+                    let line = self.push_line(offset, depth, format!("[{i}]"), element_type);
+                    self.dump_payload(line, element_type, depth)?;
+
+                    self.input.pop();
+                }
+                return Ok(Some(elements(length as usize)));
+            }
+        };
+        // empty lists can have any length, so we don't try to skip anything
+        if element_type != END_ID {
+            self.input.reader.skip(length as usize * width)?;
+            self.limits.allocate(length as usize * width)?;
+        }
+        Ok(Some(elements(length as usize)))
+    }
+}
+
+fn elements(length: usize) -> String {
+    if length == 1 {
+        "1 element".to_owned()
+    } else {
+        format!("{length} elements")
+    }
+}
+
+fn type_name(tag_type: u8) -> &'static str {
+    match tag_type {
+        END_ID => "end",
+        BYTE_ID => "byte",
+        SHORT_ID => "short",
+        INT_ID => "int",
+        LONG_ID => "long",
+        FLOAT_ID => "float",
+        DOUBLE_ID => "double",
+        BYTE_ARRAY_ID => "byte array",
+        STRING_ID => "string",
+        LIST_ID => "list",
+        COMPOUND_ID => "compound",
+        INT_ARRAY_ID => "int array",
+        LONG_ARRAY_ID => "long array",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        owned::{BaseNbt, NbtCompound, NbtList},
+        test_fixtures::{item, write},
+    };
+
+    #[test]
+    fn dump() {
+        let mut compound = NbtCompound::new();
+        compound.insert("Inventory", NbtList::Compound(vec![item(1), item(2)]));
+        compound.insert("Pos", NbtList::Double(vec![0., 1., 2.]));
+        compound.insert("id", "minecraft:pig");
+
+        assert_eq!(
+            explain(&write(compound)),
+            "\
+0x00000000      140  \"\": compound
+0x00000003       81    Inventory: list of compound = 2 elements
+0x00000014       32      [0]: compound
+0x00000014       22        id: string = \"minecraft:stone\"
+0x0000002a        9        Count: byte = 1
+0x00000034       32      [1]: compound
+0x00000034       22        id: string = \"minecraft:stone\"
+0x0000004a        9        Count: byte = 2
+0x00000054       35    Pos: list of double = 3 elements
+0x00000077       20    id: string = \"minecraft:pig\"
+"
+        );
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut level = NbtCompound::new();
+        level.insert("Entities", NbtList::Compound(vec![item(1), item(2)]));
+        let mut compound = NbtCompound::new();
+        compound.insert("Level", level);
+        let data = write(compound);
+
+        let dump = explain(&data[..data.len() - 5]);
+        assert!(
+            dump.ends_with(
+                "error at offset 0x54 inside Level.Entities[1]: Unexpected end of data\n"
+            ),
+            "{dump}"
+        );
+        assert!(dump.starts_with("0x00000000        ?  \"\": compound\n"));
+        assert!(dump.contains("0x0000001b       32        [0]: compound\n"));
+
+        assert_eq!(explain(&[1]), "error at offset 0x1: Invalid root type 1\n");
+    }
+
+    #[test]
+    fn locate() {
+        let mut level = NbtCompound::new();
+        level.insert("Entities", NbtList::Compound(vec![item(1), item(2)]));
+        let mut compound = NbtCompound::new();
+        compound.insert("Level", level);
+        let mut data = write(compound);
+
+        assert_eq!(locate_error(&data), None);
+
+        // make the second item's Count an unknown tag type
+        let count_offset = data.len() - 12;
+        assert_eq!(data[count_offset], BYTE_ID);
+        data[count_offset] = 13;
+        let location = locate_error(&data).unwrap();
+        assert_eq!(location.offset, count_offset + 8);
+        assert_eq!(location.path, "Level.Entities[1].Count".parse().unwrap());
+        assert_eq!(location.error, Error::UnknownTagId(13));
+    }
+
+    #[test]
+    fn other_layouts() {
+        let mut level = NbtCompound::new();
+        level.insert("Entities", NbtList::Compound(vec![item(1), item(2)]));
+        level.insert("Pos", NbtList::Int(vec![1, 2, 3]));
+        let mut compound = NbtCompound::new();
+        compound.insert("Level", level);
+        let nbt = BaseNbt::new("", compound);
+
+        let mut unnamed = Vec::new();
+        nbt.write_unnamed(&mut unnamed);
+        assert_eq!(locate_error_with(&unnamed, RootLayout::Unnamed), None);
+        let location = locate_error_with(&unnamed[..29], RootLayout::Unnamed).unwrap();
+        // the name of id in the first item is cut off
+        assert_eq!(location.offset, 28);
+        assert_eq!(location.path, "Level.Entities[0]".parse().unwrap());
+
+        let mut little_endian = Vec::new();
+        nbt.write_le(&mut little_endian);
+        assert_eq!(
+            locate_error_with(&little_endian, RootLayout::LittleEndian),
+            None
+        );
+        let mut out = String::new();
+        dump_annotated_with(&little_endian, RootLayout::LittleEndian, &mut out).unwrap();
+        let mut big_endian = String::new();
+        dump_annotated(&nbt.to_bytes(), &mut big_endian).unwrap();
+        assert_eq!(out, big_endian);
+
+        // make the length of Pos negative
+        let pos_length = little_endian.len() - 18;
+        little_endian[pos_length + 3] = 0x80;
+        let location = locate_error_with(&little_endian, RootLayout::LittleEndian).unwrap();
+        assert_eq!(location.offset, pos_length + 4);
+        assert_eq!(location.path, "Level.Pos".parse().unwrap());
+        assert_eq!(location.error, Error::InvalidLength);
+    }
+
+    #[test]
+    fn locate_with_opts() {
+        let mut level = NbtCompound::new();
+        level.insert("Entities", NbtList::Compound(vec![item(1), item(2)]));
+        let mut compound = NbtCompound::new();
+        compound.insert("Level", level);
+        let data = write(compound);
+        let locate = |options: &ReadOptions| {
+            let error = crate::owned::read_slice_with_opts(&data, options).unwrap_err();
+            let location = locate_error_with_opts(&data, RootLayout::Named, options).unwrap();
+            assert_eq!(location.error, error);
+            location
+        };
+
+        assert_eq!(
+            locate_error_with_opts(&data, RootLayout::Named, &ReadOptions::default()),
+            None
+        );
+        assert_eq!(locate_error(&data), None);
+
+        let location = locate(&ReadOptions {
+            max_bytes: Some(0x40),
+            ..Default::default()
+        });
+        assert_eq!(location.offset, 0x40);
+        assert_eq!(location.path, "Level.Entities[1].id".parse().unwrap());
+
+        let location = locate(&ReadOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(location.path, "Level.Entities".parse().unwrap());
+
+        let location = locate(&ReadOptions {
+            max_tags: Some(8),
+            ..Default::default()
+        });
+        assert_eq!(location.path, "Level.Entities[0]".parse().unwrap());
+
+        locate(&ReadOptions {
+            max_list_len: Some(1),
+            ..Default::default()
+        });
+        locate(&ReadOptions {
+            max_allocation: Some(100),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn locate_duplicate_key() {
+        // a compound with `"a": 1` and then `"a": 2`
+        let data = [
+            10, 0, 0, 3, 0, 1, b'a', 0, 0, 0, 1, 3, 0, 1, b'a', 0, 0, 0, 2, 0,
+        ];
+        let options = ReadOptions {
+            duplicate_keys: DuplicateKeyPolicy::Error,
+            ..Default::default()
+        };
+        assert_eq!(locate_error(&data), None);
+        let location = locate_error_with_opts(&data, RootLayout::Named, &options).unwrap();
+        assert_eq!(location.offset, 15);
+        assert_eq!(location.path, "a".parse().unwrap());
+        assert_eq!(location.error, Error::DuplicateKey);
+    }
+}
@@ -0,0 +1,401 @@
+//! Hashing NBT by its structure, without writing it to bytes first.
+//!
+//! Borrowed and owned NBT that are equal have the same hash, so they can be
+//! mixed in the same hash set. Floats and doubles are hashed by their bits,
+//! except that every NaN is hashed the same way, which matches what
+//! [`write_canonical`](owned::NbtCompound::write_canonical) writes.
+
+use std::hash::Hasher;
+
+use crate::{
+    borrow,
+    common::{
+        canonical_f32, canonical_f64, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID,
+        FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    owned,
+};
+
+impl owned::NbtCompound {
+    /// Feed the contents of the compound into the hasher. The order of the
+    /// keys matters, so compounds that are equal always have the same hash.
+    ///
+    /// The hash is the same as the one from
+    /// [`borrow::NbtCompound::structural_hash`] for the same data.
+    ///
+    /// ```
+    /// # use std::hash::{DefaultHasher, Hasher};
+    /// # use simdnbt::compound;
+    /// let hash = |compound: simdnbt::owned::NbtCompound| {
+    ///     let mut hasher = DefaultHasher::new();
+    ///     compound.structural_hash(&mut hasher);
+    ///     hasher.finish()
+    /// };
+    /// let item = || compound! { "id": "minecraft:stone", "count": 64 };
+    /// assert_eq!(hash(item()), hash(item()));
+    /// ```
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        StructuralHasher::new(state, false).owned_compound(self);
+    }
+
+    /// Feed the contents of the compound into the hasher like
+    /// [`Self::structural_hash`], but ignoring the order of the keys in every
+    /// compound (including the ones inside of it).
+    ///
+    /// This has to sort the keys of every compound, so it's slower than
+    /// [`Self::structural_hash`].
+    pub fn structural_hash_unordered<H: Hasher>(&self, state: &mut H) {
+        StructuralHasher::new(state, true).owned_compound(self);
+    }
+}
+
+impl owned::NbtTag {
+    /// Feed the tag into the hasher, including its type. See
+    /// [`owned::NbtCompound::structural_hash`].
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(self.id());
+        StructuralHasher::new(state, false).owned_tag(self);
+    }
+}
+
+impl owned::NbtList {
+    /// Feed the list into the hasher, including the type of its elements. See
+    /// [`owned::NbtCompound::structural_hash`].
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        StructuralHasher::new(state, false).owned_list(self);
+    }
+}
+
+impl borrow::NbtCompound<'_, '_> {
+    /// Feed the contents of the compound into the hasher. See
+    /// [`owned::NbtCompound::structural_hash`].
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        StructuralHasher::new(state, false).borrowed_compound(self);
+    }
+
+    /// Feed the contents of the compound into the hasher, ignoring the order
+    /// of the keys. See [`owned::NbtCompound::structural_hash_unordered`].
+    pub fn structural_hash_unordered<H: Hasher>(&self, state: &mut H) {
+        StructuralHasher::new(state, true).borrowed_compound(self);
+    }
+}
+
+/// Feeds tags into a hasher in the same way for borrowed and owned NBT.
+///
+/// Every tag starts with its ID, compounds end with [`END_ID`] (which can't be
+/// the ID of a tag in them), and lists and arrays start with their length, so
+/// different structures can't be hashed the same way.
+struct StructuralHasher<'h, H: Hasher> {
+    state: &'h mut H,
+    unordered: bool,
+}
+
+impl<'h, H: Hasher> StructuralHasher<'h, H> {
+    fn new(state: &'h mut H, unordered: bool) -> Self {
+        Self { state, unordered }
+    }
+
+    fn entry(&mut self, id: u8, name: &[u8]) {
+        self.state.write_u8(id);
+        self.bytes(name);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.state.write_usize(bytes.len());
+        self.state.write(bytes);
+    }
+
+    fn ints(&mut self, ints: impl ExactSizeIterator<Item = i32>) {
+        self.state.write_usize(ints.len());
+        ints.for_each(|int| self.state.write_i32(int));
+    }
+
+    fn longs(&mut self, longs: impl ExactSizeIterator<Item = i64>) {
+        self.state.write_usize(longs.len());
+        longs.for_each(|long| self.state.write_i64(long));
+    }
+
+    fn float(&mut self, float: f32) {
+        self.state.write_u32(canonical_f32(float).to_bits());
+    }
+
+    fn double(&mut self, double: f64) {
+        self.state.write_u64(canonical_f64(double).to_bits());
+    }
+
+    fn owned_compound(&mut self, compound: &owned::NbtCompound) {
+        if self.unordered {
+            let mut entries = compound.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| name.as_bytes());
+            for (name, tag) in entries {
+                self.entry(tag.id(), name.as_bytes());
+                self.owned_tag(tag);
+            }
+        } else {
+            for (name, tag) in compound.iter() {
+                self.entry(tag.id(), name.as_bytes());
+                self.owned_tag(tag);
+            }
+        }
+        self.state.write_u8(END_ID);
+    }
+
+    fn owned_tag(&mut self, tag: &owned::NbtTag) {
+        match tag {
+            owned::NbtTag::Byte(byte) => self.state.write_i8(*byte),
+            owned::NbtTag::Short(short) => self.state.write_i16(*short),
+            owned::NbtTag::Int(int) => self.state.write_i32(*int),
+            owned::NbtTag::Long(long) => self.state.write_i64(*long),
+            owned::NbtTag::Float(float) => self.float(*float),
+            owned::NbtTag::Double(double) => self.double(*double),
+            owned::NbtTag::ByteArray(bytes) => self.bytes(bytes),
+            owned::NbtTag::String(string) => self.bytes(string.as_bytes()),
+            owned::NbtTag::List(list) => self.owned_list(list),
+            owned::NbtTag::Compound(compound) => self.owned_compound(compound),
+            owned::NbtTag::IntArray(ints) => self.ints(ints.iter().copied()),
+            owned::NbtTag::LongArray(longs) => self.longs(longs.iter().copied()),
+        }
+    }
+
+    fn owned_list(&mut self, list: &owned::NbtList) {
+        self.state.write_u8(list.id());
+        self.state.write_usize(list.len());
+        match list {
+            owned::NbtList::Empty => {}
+            owned::NbtList::Byte(bytes) => {
+                for byte in bytes {
+                    self.state.write_i8(*byte);
+                }
+            }
+            owned::NbtList::Short(shorts) => {
+                for short in shorts {
+                    self.state.write_i16(*short);
+                }
+            }
+            owned::NbtList::Int(ints) => {
+                for int in ints {
+                    self.state.write_i32(*int);
+                }
+            }
+            owned::NbtList::Long(longs) => {
+                for long in longs {
+                    self.state.write_i64(*long);
+                }
+            }
+            owned::NbtList::Float(floats) => {
+                for float in floats {
+                    self.float(*float);
+                }
+            }
+            owned::NbtList::Double(doubles) => {
+                for double in doubles {
+                    self.double(*double);
+                }
+            }
+            owned::NbtList::ByteArray(arrays) => {
+                for array in arrays {
+                    self.bytes(array);
+                }
+            }
+            owned::NbtList::String(strings) => {
+                for string in strings {
+                    self.bytes(string.as_bytes());
+                }
+            }
+            owned::NbtList::List(lists) => {
+                for list in lists {
+                    self.owned_list(list);
+                }
+            }
+            owned::NbtList::Compound(compounds) => {
+                for compound in compounds {
+                    self.owned_compound(compound);
+                }
+            }
+            owned::NbtList::IntArray(arrays) => {
+                for array in arrays {
+                    self.ints(array.iter().copied());
+                }
+            }
+            owned::NbtList::LongArray(arrays) => {
+                for array in arrays {
+                    self.longs(array.iter().copied());
+                }
+            }
+        }
+    }
+
+    fn borrowed_compound(&mut self, compound: &borrow::NbtCompound) {
+        if self.unordered {
+            let mut entries = compound.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| name.as_bytes());
+            for (name, tag) in entries {
+                self.entry(tag.id(), name.as_bytes());
+                self.borrowed_tag(&tag);
+            }
+        } else {
+            for (name, tag) in compound.iter() {
+                self.entry(tag.id(), name.as_bytes());
+                self.borrowed_tag(&tag);
+            }
+        }
+        self.state.write_u8(END_ID);
+    }
+
+    fn borrowed_tag(&mut self, tag: &borrow::NbtTag) {
+        match tag.id() {
+            BYTE_ID => self.state.write_i8(tag.byte().unwrap()),
+            SHORT_ID => self.state.write_i16(tag.short().unwrap()),
+            INT_ID => self.state.write_i32(tag.int().unwrap()),
+            LONG_ID => self.state.write_i64(tag.long().unwrap()),
+            FLOAT_ID => self.float(tag.float().unwrap()),
+            DOUBLE_ID => self.double(tag.double().unwrap()),
+            BYTE_ARRAY_ID => self.bytes(tag.byte_array().unwrap()),
+            STRING_ID => self.bytes(tag.string().unwrap().as_bytes()),
+            LIST_ID => self.borrowed_list(&tag.list().unwrap()),
+            COMPOUND_ID => self.borrowed_compound(&tag.compound().unwrap()),
+            INT_ARRAY_ID => self.ints(tag.int_array_raw().unwrap().iter()),
+            LONG_ARRAY_ID => self.longs(tag.long_array_raw().unwrap().iter()),
+            id => unreachable!("Invalid tag id {id}"),
+        }
+    }
+
+    fn borrowed_list(&mut self, list: &borrow::NbtList) {
+        self.state.write_u8(list.id());
+        self.state.write_usize(list.len());
+        match list.id() {
+            END_ID => {}
+            BYTE_ID => {
+                for byte in list.bytes().unwrap() {
+                    self.state.write_i8(*byte);
+                }
+            }
+            SHORT_ID => {
+                for short in list.shorts_iter().unwrap() {
+                    self.state.write_i16(short);
+                }
+            }
+            INT_ID => {
+                for int in list.ints_iter().unwrap() {
+                    self.state.write_i32(int);
+                }
+            }
+            LONG_ID => {
+                for long in list.longs_iter().unwrap() {
+                    self.state.write_i64(long);
+                }
+            }
+            FLOAT_ID => {
+                for float in list.floats_iter().unwrap() {
+                    self.float(float);
+                }
+            }
+            DOUBLE_ID => {
+                for double in list.doubles_iter().unwrap() {
+                    self.double(double);
+                }
+            }
+            BYTE_ARRAY_ID => {
+                for array in list.byte_arrays().unwrap() {
+                    self.bytes(array);
+                }
+            }
+            STRING_ID => {
+                for string in list.strings().unwrap() {
+                    self.bytes(string.as_bytes());
+                }
+            }
+            LIST_ID => {
+                for list in list.lists().unwrap() {
+                    self.borrowed_list(&list);
+                }
+            }
+            COMPOUND_ID => {
+                for compound in list.compounds().unwrap() {
+                    self.borrowed_compound(&compound);
+                }
+            }
+            INT_ARRAY_ID => {
+                for array in list.int_arrays().unwrap() {
+                    self.ints(array.iter());
+                }
+            }
+            LONG_ARRAY_ID => {
+                for array in list.long_arrays().unwrap() {
+                    self.longs(array.iter());
+                }
+            }
+            id => unreachable!("Invalid list element id {id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{hash::DefaultHasher, io::Cursor};
+
+    use super::*;
+    use crate::{compound, test_fixtures::complex_player};
+
+    fn hash(f: impl FnOnce(&mut DefaultHasher)) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        f(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn borrowed_and_owned_are_the_same() {
+        let data = complex_player();
+        let mut compound = owned::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap()
+            .as_compound();
+        compound.insert(
+            "extra",
+            compound! {
+                "empty": [],
+                "floats": [1f32, f32::NAN],
+                "arrays": [[B; 1, 2], [B; 3]],
+                "ints": [[I; 1, 2], [I;]],
+                "longs": [[L; 1], [L; 2, 3]],
+                "strings": ["a", "b"],
+                "nested": [[1, 2], [3]],
+            },
+        );
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+
+        let owned = owned::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let borrowed = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let borrowed = borrowed.as_compound();
+        assert_eq!(
+            hash(|h| owned.structural_hash(h)),
+            hash(|h| borrowed.structural_hash(h))
+        );
+        assert_eq!(
+            hash(|h| owned.structural_hash_unordered(h)),
+            hash(|h| borrowed.structural_hash_unordered(h))
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let a = compound! { "a": 1, "b": { "c": 2, "d": [f64::NAN] } };
+        let b = compound! { "b": { "d": [-f64::NAN], "c": 2 }, "a": 1 };
+        assert_ne!(
+            hash(|h| a.structural_hash(h)),
+            hash(|h| b.structural_hash(h))
+        );
+        assert_eq!(
+            hash(|h| a.structural_hash_unordered(h)),
+            hash(|h| b.structural_hash_unordered(h))
+        );
+
+        // the same values in a different structure
+        let a = compound! { "a": [[1], [2, 3]] };
+        let b = compound! { "a": [[1, 2], [3]] };
+        assert_ne!(
+            hash(|h| a.structural_hash(h)),
+            hash(|h| b.structural_hash(h))
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use ::fastnbt::{ByteArray, IntArray, LongArray, Value};
+use thiserror::Error;
+
+use crate::{
+    common::{vec_i8_into_u8, vec_u8_into_i8},
+    owned::{NbtCompound, NbtList, NbtTag},
+};
+
+/// The error for converting a `fastnbt::Value` into an [`NbtTag`] when it has
+/// a list with elements of different types, which NBT can't represent.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("A list has elements with different types")]
+pub struct MixedListError;
+
+impl From<NbtTag> for Value {
+    fn from(tag: NbtTag) -> Self {
+        match tag {
+            NbtTag::Byte(byte) => Value::Byte(byte),
+            NbtTag::Short(short) => Value::Short(short),
+            NbtTag::Int(int) => Value::Int(int),
+            NbtTag::Long(long) => Value::Long(long),
+            NbtTag::Float(float) => Value::Float(float),
+            NbtTag::Double(double) => Value::Double(double),
+            NbtTag::ByteArray(bytes) => Value::ByteArray(ByteArray::new(vec_u8_into_i8(bytes))),
+            NbtTag::String(string) => Value::String(string.into_string()),
+            NbtTag::List(list) => list.into(),
+            NbtTag::Compound(compound) => compound.into(),
+            NbtTag::IntArray(ints) => Value::IntArray(IntArray::new(ints)),
+            NbtTag::LongArray(longs) => Value::LongArray(LongArray::new(longs)),
+        }
+    }
+}
+
+impl From<NbtList> for Value {
+    fn from(list: NbtList) -> Self {
+        fn values<T>(items: Vec<T>, into: impl FnMut(T) -> Value) -> Value {
+            Value::List(items.into_iter().map(into).collect())
+        }
+        match list {
+            NbtList::Empty => Value::List(Vec::new()),
+            NbtList::Byte(bytes) => values(bytes, Value::Byte),
+            NbtList::Short(shorts) => values(shorts, Value::Short),
+            NbtList::Int(ints) => values(ints, Value::Int),
+            NbtList::Long(longs) => values(longs, Value::Long),
+            NbtList::Float(floats) => values(floats, Value::Float),
+            NbtList::Double(doubles) => values(doubles, Value::Double),
+            NbtList::ByteArray(arrays) => values(arrays, |bytes| {
+                Value::ByteArray(ByteArray::new(vec_u8_into_i8(bytes)))
+            }),
+            NbtList::String(strings) => {
+                values(strings, |string| Value::String(string.into_string()))
+            }
+            NbtList::List(lists) => values(lists, Value::from),
+            NbtList::Compound(compounds) => values(compounds, Value::from),
+            NbtList::IntArray(arrays) => {
+                values(arrays, |ints| Value::IntArray(IntArray::new(ints)))
+            }
+            NbtList::LongArray(arrays) => {
+                values(arrays, |longs| Value::LongArray(LongArray::new(longs)))
+            }
+        }
+    }
+}
+
+impl From<NbtCompound> for Value {
+    /// Convert the compound into a `fastnbt` compound. Since it's a
+    /// `HashMap`, the order of the keys is lost, and only the last tag with
+    /// each name is kept.
+    fn from(compound: NbtCompound) -> Self {
+        Value::Compound(
+            compound
+                .into_iter()
+                .map(|(name, tag)| (name.into_string(), tag.into()))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+}
+
+impl TryFrom<Value> for NbtTag {
+    type Error = MixedListError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Byte(byte) => NbtTag::Byte(byte),
+            Value::Short(short) => NbtTag::Short(short),
+            Value::Int(int) => NbtTag::Int(int),
+            Value::Long(long) => NbtTag::Long(long),
+            Value::Float(float) => NbtTag::Float(float),
+            Value::Double(double) => NbtTag::Double(double),
+            Value::ByteArray(bytes) => NbtTag::ByteArray(vec_i8_into_u8(bytes.into_inner())),
+            Value::String(string) => NbtTag::String(string.into()),
+            Value::List(values) => NbtTag::List(values.try_into()?),
+            Value::Compound(values) => NbtTag::Compound(values.try_into()?),
+            Value::IntArray(ints) => NbtTag::IntArray(ints.into_inner()),
+            Value::LongArray(longs) => NbtTag::LongArray(longs.into_inner()),
+        })
+    }
+}
+
+impl TryFrom<Vec<Value>> for NbtList {
+    type Error = MixedListError;
+
+    fn try_from(values: Vec<Value>) -> Result<Self, Self::Error> {
+        let tags = values
+            .into_iter()
+            .map(NbtTag::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        NbtList::from_tags(tags).ok_or(MixedListError)
+    }
+}
+
+impl TryFrom<HashMap<String, Value>> for NbtCompound {
+    type Error = MixedListError;
+
+    /// Convert a `fastnbt` compound into an [`NbtCompound`]. The keys are
+    /// sorted, since the order of a `HashMap` isn't meaningful.
+    fn try_from(values: HashMap<String, Value>) -> Result<Self, Self::Error> {
+        let mut values = values
+            .into_iter()
+            .map(|(name, value)| Ok((name.into(), NbtTag::try_from(value)?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        values.sort_by(|(a, _), (b, _): &(crate::Mutf8String, _)| a.as_bytes().cmp(b.as_bytes()));
+        Ok(NbtCompound::from_values(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compound;
+
+    #[test]
+    fn roundtrip() {
+        let compound = compound! {
+            "a": 1,
+            "b": [B; 1, 2],
+            "c": [L; 3],
+            "d": [{ "e": "caf\u{e9}" }, {}],
+            "f": [],
+            "g": [[1f32], [2f32, 3f32]],
+        };
+        let value = Value::from(compound.clone());
+        let Value::Compound(values) = &value else {
+            panic!("expected a compound");
+        };
+        assert_eq!(
+            values["d"],
+            Value::List(vec![
+                Value::Compound(HashMap::from([(
+                    "e".to_owned(),
+                    Value::String("caf\u{e9}".to_owned())
+                )])),
+                Value::Compound(HashMap::new()),
+            ])
+        );
+
+        // the keys are already sorted, so the order doesn't change
+        assert_eq!(NbtTag::try_from(value), Ok(NbtTag::Compound(compound)));
+
+        let mixed = Value::List(vec![Value::Int(1), Value::Long(2)]);
+        assert_eq!(NbtTag::try_from(mixed), Err(MixedListError));
+    }
+}
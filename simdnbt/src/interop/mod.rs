@@ -0,0 +1,23 @@
+//! Conversions between [`owned`](crate::owned) NBT and the value types of
+//! other NBT crates, so simdnbt can be used for reading and writing while the
+//! rest of a project still works with the other crate's values.
+//!
+//! - With the `fastnbt` feature, [`owned::NbtTag`](crate::owned::NbtTag) can be
+//!   converted into a `fastnbt::Value`, and a `fastnbt::Value` can be converted
+//!   back with `TryFrom` (since its lists can mix types).
+//! - With the `valence_nbt` feature, tags, lists, and compounds can be
+//!   converted to and from `valence_nbt::Value`, `valence_nbt::List`, and
+//!   `valence_nbt::Compound`.
+//!
+//! Strings are converted to UTF-8 when they leave simdnbt, so a string that
+//! isn't valid MUTF-8 becomes empty. Both crates store compounds in maps, so
+//! only the last tag with each name is kept, and `fastnbt` doesn't keep the
+//! order of the keys.
+
+#[cfg(feature = "fastnbt")]
+mod fastnbt;
+#[cfg(feature = "valence_nbt")]
+mod valence_nbt;
+
+#[cfg(feature = "fastnbt")]
+pub use self::fastnbt::MixedListError;
@@ -0,0 +1,144 @@
+use ::valence_nbt::{Compound, List, Value};
+
+use crate::{
+    common::{vec_i8_into_u8, vec_u8_into_i8},
+    owned::{NbtCompound, NbtList, NbtTag},
+};
+
+impl From<NbtTag> for Value {
+    fn from(tag: NbtTag) -> Self {
+        match tag {
+            NbtTag::Byte(byte) => Value::Byte(byte),
+            NbtTag::Short(short) => Value::Short(short),
+            NbtTag::Int(int) => Value::Int(int),
+            NbtTag::Long(long) => Value::Long(long),
+            NbtTag::Float(float) => Value::Float(float),
+            NbtTag::Double(double) => Value::Double(double),
+            NbtTag::ByteArray(bytes) => Value::ByteArray(vec_u8_into_i8(bytes)),
+            NbtTag::String(string) => Value::String(string.into_string()),
+            NbtTag::List(list) => Value::List(list.into()),
+            NbtTag::Compound(compound) => Value::Compound(compound.into()),
+            NbtTag::IntArray(ints) => Value::IntArray(ints),
+            NbtTag::LongArray(longs) => Value::LongArray(longs),
+        }
+    }
+}
+
+impl From<NbtList> for List {
+    fn from(list: NbtList) -> Self {
+        match list {
+            NbtList::Empty => List::End,
+            NbtList::Byte(bytes) => List::Byte(bytes),
+            NbtList::Short(shorts) => List::Short(shorts),
+            NbtList::Int(ints) => List::Int(ints),
+            NbtList::Long(longs) => List::Long(longs),
+            NbtList::Float(floats) => List::Float(floats),
+            NbtList::Double(doubles) => List::Double(doubles),
+            NbtList::ByteArray(arrays) => {
+                List::ByteArray(arrays.into_iter().map(vec_u8_into_i8).collect())
+            }
+            NbtList::String(strings) => {
+                List::String(strings.into_iter().map(|s| s.into_string()).collect())
+            }
+            NbtList::List(lists) => List::List(lists.into_iter().map(List::from).collect()),
+            NbtList::Compound(compounds) => {
+                List::Compound(compounds.into_iter().map(Compound::from).collect())
+            }
+            NbtList::IntArray(arrays) => List::IntArray(arrays),
+            NbtList::LongArray(arrays) => List::LongArray(arrays),
+        }
+    }
+}
+
+impl From<NbtCompound> for Compound {
+    /// Convert the compound into a `valence_nbt` compound. Only the last tag
+    /// with each name is kept.
+    fn from(compound: NbtCompound) -> Self {
+        compound
+            .into_iter()
+            .map(|(name, tag)| (name.into_string(), Value::from(tag)))
+            .collect()
+    }
+}
+
+impl From<Value> for NbtTag {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Byte(byte) => NbtTag::Byte(byte),
+            Value::Short(short) => NbtTag::Short(short),
+            Value::Int(int) => NbtTag::Int(int),
+            Value::Long(long) => NbtTag::Long(long),
+            Value::Float(float) => NbtTag::Float(float),
+            Value::Double(double) => NbtTag::Double(double),
+            Value::ByteArray(bytes) => NbtTag::ByteArray(vec_i8_into_u8(bytes)),
+            Value::String(string) => NbtTag::String(string.into()),
+            Value::List(list) => NbtTag::List(list.into()),
+            Value::Compound(compound) => NbtTag::Compound(compound.into()),
+            Value::IntArray(ints) => NbtTag::IntArray(ints),
+            Value::LongArray(longs) => NbtTag::LongArray(longs),
+        }
+    }
+}
+
+impl From<List> for NbtList {
+    fn from(list: List) -> Self {
+        match list {
+            List::End => NbtList::Empty,
+            List::Byte(bytes) => NbtList::Byte(bytes),
+            List::Short(shorts) => NbtList::Short(shorts),
+            List::Int(ints) => NbtList::Int(ints),
+            List::Long(longs) => NbtList::Long(longs),
+            List::Float(floats) => NbtList::Float(floats),
+            List::Double(doubles) => NbtList::Double(doubles),
+            List::ByteArray(arrays) => {
+                NbtList::ByteArray(arrays.into_iter().map(vec_i8_into_u8).collect())
+            }
+            List::String(strings) => NbtList::String(strings.into_iter().map(Into::into).collect()),
+            List::List(lists) => NbtList::List(lists.into_iter().map(NbtList::from).collect()),
+            List::Compound(compounds) => {
+                NbtList::Compound(compounds.into_iter().map(NbtCompound::from).collect())
+            }
+            List::IntArray(arrays) => NbtList::IntArray(arrays),
+            List::LongArray(arrays) => NbtList::LongArray(arrays),
+        }
+    }
+}
+
+impl From<Compound> for NbtCompound {
+    fn from(compound: Compound) -> Self {
+        NbtCompound::from_values(
+            compound
+                .into_iter()
+                .map(|(name, value)| (name.into(), NbtTag::from(value)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compound;
+
+    #[test]
+    fn roundtrip() {
+        let compound = compound! {
+            "a": 1,
+            "b": [B; 1, 2],
+            "c": [L; 3],
+            "d": [{ "e": "caf\u{e9}" }, {}],
+            "f": [],
+            "g": [[1f32], [2f32, 3f32]],
+        };
+        let converted = Compound::from(compound.clone());
+        assert_eq!(converted.get("a"), Some(&Value::Int(1)));
+        assert_eq!(converted.get("f"), Some(&Value::List(List::End)));
+
+        // valence_nbt doesn't keep the order of keys by default
+        let back = NbtCompound::from(converted);
+        assert_eq!(back.len(), compound.len());
+        for (name, tag) in compound.iter() {
+            assert_eq!(back.get(&name.to_str()), Some(tag));
+        }
+    }
+}
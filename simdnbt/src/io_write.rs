@@ -0,0 +1,205 @@
+//! Writing NBT to an [`io::Write`] in chunks, so the whole document never has
+//! to be in memory at once.
+
+use std::io;
+
+use crate::{
+    borrow,
+    common::{write_string, write_u32, COMPOUND_ID, END_ID, LIST_ID},
+    owned, Mutf8Str, WriteOptions,
+};
+
+/// How big the buffer can get before it's written.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes tags to a buffer and moves the buffer into the writer whenever it's
+/// full. Compounds and lists of compounds or lists are written one element at
+/// a time, and every other tag is written all at once.
+pub(crate) struct ChunkedWriter<'o, W: io::Write> {
+    out: W,
+    buffer: Vec<u8>,
+    opts: &'o WriteOptions,
+}
+
+impl<'o, W: io::Write> ChunkedWriter<'o, W> {
+    pub fn new(out: W, opts: &'o WriteOptions) -> Self {
+        Self {
+            out,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            opts,
+        }
+    }
+
+    /// Write whatever is left in the buffer.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&self.buffer)?;
+        self.out.flush()
+    }
+
+    fn write_if_full(&mut self) -> io::Result<()> {
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.out.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.buffer.push(byte);
+    }
+
+    /// Write the ID and name of a tag in a compound.
+    fn tag_header(&mut self, id: u8, name: &Mutf8Str) {
+        self.buffer.push(id);
+        write_string(&mut self.buffer, name);
+    }
+
+    fn list_header(&mut self, id: u8, len: usize) {
+        self.buffer.push(id);
+        write_u32(&mut self.buffer, len as u32);
+    }
+
+    pub fn base(&mut self, name: &Mutf8Str) {
+        self.tag_header(COMPOUND_ID, name);
+    }
+
+    pub fn owned_compound(&mut self, compound: &owned::NbtCompound) -> io::Result<()> {
+        for (name, tag) in compound.iter() {
+            self.tag_header(tag.id(), name);
+            match tag {
+                owned::NbtTag::Compound(compound) => self.owned_compound(compound)?,
+                owned::NbtTag::List(list) => self.owned_list(list)?,
+                tag => {
+                    self.buffer.reserve(4);
+                    // SAFETY: we just reserved the 4 bytes that it needs
+                    unsafe {
+                        tag.write_without_tag_type_unchecked(&mut self.buffer, self.opts, false)
+                    };
+                }
+            }
+            self.write_if_full()?;
+        }
+        self.buffer.push(END_ID);
+        Ok(())
+    }
+
+    fn owned_list(&mut self, list: &owned::NbtList) -> io::Result<()> {
+        match list {
+            owned::NbtList::Compound(compounds) if !compounds.is_empty() => {
+                self.list_header(COMPOUND_ID, compounds.len());
+                for compound in compounds {
+                    self.owned_compound(compound)?;
+                }
+            }
+            owned::NbtList::List(lists) if !lists.is_empty() => {
+                self.list_header(LIST_ID, lists.len());
+                for list in lists {
+                    self.owned_list(list)?;
+                    self.write_if_full()?;
+                }
+            }
+            list => list.write_with_opts(&mut self.buffer, self.opts),
+        }
+        Ok(())
+    }
+
+    pub fn borrowed_compound(&mut self, compound: &borrow::NbtCompound) -> io::Result<()> {
+        for (name, tag) in compound.iter() {
+            self.tag_header(tag.id(), name);
+            if let Some(compound) = tag.compound() {
+                self.borrowed_compound(&compound)?;
+            } else if let Some(list) = tag.list() {
+                self.borrowed_list(&list)?;
+            } else {
+                borrow::write_tag(tag, &mut self.buffer, self.opts, false);
+            }
+            self.write_if_full()?;
+        }
+        self.buffer.push(END_ID);
+        Ok(())
+    }
+
+    fn borrowed_list(&mut self, list: &borrow::NbtList) -> io::Result<()> {
+        if let Some(compounds) = list.compounds().filter(|c| !c.clone().is_empty()) {
+            self.list_header(COMPOUND_ID, compounds.clone().len());
+            for compound in compounds {
+                self.borrowed_compound(&compound)?;
+            }
+        } else if let Some(lists) = list.lists().filter(|l| !l.clone().is_empty()) {
+            self.list_header(LIST_ID, lists.clone().len());
+            for list in lists {
+                self.borrowed_list(&list)?;
+                self.write_if_full()?;
+            }
+        } else {
+            list.write_with_opts(&mut self.buffer, self.opts);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{borrow, owned, test_fixtures::complex_player};
+
+    /// A writer that counts how many times it was written to.
+    struct CountingWriter {
+        data: Vec<u8>,
+        writes: usize,
+    }
+    impl std::io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_to() {
+        let src = complex_player();
+
+        let borrowed = borrow::read(&mut Cursor::new(&src)).unwrap();
+        let mut out = Vec::new();
+        borrowed.write_to(&mut out).unwrap();
+        assert_eq!(out, borrowed.to_bytes());
+
+        let owned = owned::read(&mut Cursor::new(&src)).unwrap();
+        let mut out = Vec::new();
+        owned.write_to(&mut out).unwrap();
+        assert_eq!(out, owned.to_bytes());
+
+        let mut out = Vec::new();
+        owned::Nbt::None.write_to(&mut out).unwrap();
+        assert_eq!(out, [0]);
+    }
+
+    #[test]
+    fn writes_in_chunks() {
+        let mut item = owned::NbtCompound::new();
+        item.insert("id", "minecraft:stone");
+        item.insert("tag", owned::NbtTag::ByteArray(vec![0; 1000]));
+        let mut root = owned::NbtCompound::new();
+        root.insert("items", owned::NbtList::Compound(vec![item; 1000]));
+        root.insert("empty", owned::NbtList::Compound(vec![]));
+        let nbt = owned::BaseNbt::new("", root);
+
+        let mut out = CountingWriter {
+            data: Vec::new(),
+            writes: 0,
+        };
+        nbt.write_to(&mut out).unwrap();
+        assert_eq!(out.data, nbt.to_bytes());
+        assert!(out.writes > 10);
+
+        let borrowed = borrow::read(&mut Cursor::new(&out.data)).unwrap().unwrap();
+        let mut out = Vec::new();
+        borrowed.write_to(&mut out).unwrap();
+        assert_eq!(out, nbt.to_bytes());
+    }
+}
@@ -0,0 +1,445 @@
+//! Convert NBT to JSON.
+//!
+//! [`to_json`] and [`write_json`] convert NBT data straight into JSON text
+//! without parsing it into a tree. With the `serde_json` feature, owned and
+//! borrowed NBT can also be converted to and from `serde_json::Value` with
+//! `ToJsonValue` and `from_json_value`.
+
+#[cfg(feature = "serde_json")]
+mod value;
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    mem,
+};
+
+use simd_cesu8::mutf8;
+use thiserror::Error;
+
+#[cfg(feature = "serde_json")]
+pub use self::value::{compound_from_json_value, from_json_value, ToJsonValue};
+use crate::{
+    common::{BYTE_ID, INT_ID, LONG_ID},
+    stream::{Event, Parser, Value},
+    Error, Mutf8Str,
+};
+
+/// Options for how NBT is converted to JSON.
+///
+/// JSON text and `serde_json::Value`s are made the same way, and
+/// `from_json_value` uses the same options to convert them back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    pub arrays: ArrayStyle,
+    pub invalid_strings: InvalidStrings,
+}
+
+/// How byte, int, and long arrays are written as JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// Write arrays as plain JSON arrays of numbers, like lists. This is the
+    /// easiest to use, but arrays can't be told apart from lists when
+    /// converting back to NBT.
+    #[default]
+    Plain,
+    /// Write arrays as objects with one key that says the type of the array,
+    /// like `{"int_array":[1,2,3]}`. The keys are `byte_array`, `int_array`,
+    /// and `long_array`.
+    ///
+    /// When converting back to NBT, compounds that look like this become
+    /// arrays.
+    Tagged,
+}
+
+/// What to do with strings (and names) that aren't valid MUTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidStrings {
+    /// Replace the invalid parts with U+FFFD.
+    #[default]
+    Lossy,
+    /// Return [`JsonError::InvalidString`].
+    Error,
+}
+
+/// An error from converting between NBT and JSON.
+#[derive(Error, Debug, PartialEq)]
+pub enum JsonError {
+    #[error(transparent)]
+    Nbt(#[from] Error),
+    #[error("A string isn't valid MUTF-8")]
+    InvalidString,
+    #[error("JSON null can't be converted to NBT")]
+    Null,
+    #[error("The number {0} can't be converted to NBT")]
+    InvalidNumber(String),
+    #[error("Lists can only contain one type of tag")]
+    MixedList,
+    #[error("Expected a JSON object")]
+    ExpectedObject,
+}
+
+/// Convert a normal root NBT compound into JSON text. See [`write_json`].
+pub fn to_json(data: &[u8]) -> Result<String, Error> {
+    to_json_with_opts(data, &JsonOptions::default()).map_err(|err| match err {
+        JsonError::Nbt(err) => err,
+        _ => unreachable!("lossy strings can't fail"),
+    })
+}
+
+/// Convert a normal root NBT compound into JSON text with the given options.
+/// See [`write_json`].
+///
+/// ```
+/// use simdnbt::json::{ArrayStyle, JsonOptions};
+///
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("heights", simdnbt::owned::NbtTag::LongArray(vec![1, 2]));
+/// let data = simdnbt::owned::BaseNbt::new("", compound).to_bytes();
+///
+/// let opts = JsonOptions {
+///     arrays: ArrayStyle::Tagged,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     simdnbt::json::to_json_with_opts(&data, &opts).unwrap(),
+///     r#"{"heights":{"long_array":[1,2]}}"#
+/// );
+/// ```
+pub fn to_json_with_opts(data: &[u8], opts: &JsonOptions) -> Result<String, JsonError> {
+    let mut out = Vec::new();
+    write_json_with_opts(data, &mut out, opts).map_err(|err| {
+        // writing to a vec can't fail, so this must be an nbt or json error
+        let err = err.into_inner().expect("error should have an inner error");
+        match err.downcast::<Error>() {
+            Ok(err) => JsonError::Nbt(*err),
+            Err(err) => *err
+                .downcast::<JsonError>()
+                .expect("error should be an nbt or json error"),
+        }
+    })?;
+    // the transcoder only writes valid utf-8
+    Ok(String::from_utf8(out).expect("json should be valid utf-8"))
+}
+
+/// Convert a normal root NBT compound into JSON text, writing it to `out` as
+/// the data is read. The NBT is never parsed into a tree, so this works well
+/// for exporting lots of documents.
+///
+/// Compounds become objects (the name of the root is dropped), lists and
+/// arrays become arrays, and numbers become JSON numbers. Floats and doubles
+/// that aren't finite become `null`, and strings that aren't valid MUTF-8 are
+/// decoded lossily. An empty root becomes `null`.
+///
+/// Since nothing is buffered, the output will be incomplete if the data is
+/// invalid. NBT errors are returned as [`io::ErrorKind::InvalidData`] with an
+/// [`Error`] inside.
+///
+/// ```
+/// let mut data = Vec::new();
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("id", "minecraft:stone");
+/// compound.insert("Count", 1i8);
+/// simdnbt::owned::BaseNbt::new("", compound).write(&mut data);
+///
+/// assert_eq!(
+///     simdnbt::json::to_json(&data).unwrap(),
+///     r#"{"id":"minecraft:stone","Count":1}"#
+/// );
+/// ```
+pub fn write_json(data: &[u8], out: &mut impl Write) -> io::Result<()> {
+    write_json_with_opts(data, out, &JsonOptions::default())
+}
+
+/// Convert NBT into JSON text like [`write_json`], but with the given options.
+///
+/// If [`InvalidStrings::Error`] is used, invalid strings are returned as
+/// [`io::ErrorKind::InvalidData`] with a [`JsonError`] inside.
+pub fn write_json_with_opts(
+    data: &[u8],
+    out: &mut impl Write,
+    opts: &JsonOptions,
+) -> io::Result<()> {
+    let mut transcoder = Transcoder {
+        parser: Parser::new(data),
+        out,
+        opts,
+    };
+    transcoder.write_root().map_err(|err| match err {
+        TranscodeError::Json(JsonError::Nbt(err)) => {
+            io::Error::new(io::ErrorKind::InvalidData, err)
+        }
+        TranscodeError::Json(err) => io::Error::new(io::ErrorKind::InvalidData, err),
+        TranscodeError::Io(err) => err,
+    })
+}
+
+enum TranscodeError {
+    Json(JsonError),
+    Io(io::Error),
+}
+impl From<Error> for TranscodeError {
+    fn from(err: Error) -> Self {
+        TranscodeError::Json(JsonError::Nbt(err))
+    }
+}
+impl From<JsonError> for TranscodeError {
+    fn from(err: JsonError) -> Self {
+        TranscodeError::Json(err)
+    }
+}
+impl From<io::Error> for TranscodeError {
+    fn from(err: io::Error) -> Self {
+        TranscodeError::Io(err)
+    }
+}
+
+struct Transcoder<'a, W> {
+    parser: Parser<'a>,
+    out: W,
+    opts: &'a JsonOptions,
+}
+
+impl<W: Write> Transcoder<'_, W> {
+    fn write_root(&mut self) -> Result<(), TranscodeError> {
+        // the closing bracket of each compound and list that we're in, and
+        // whether anything was written in it yet
+        let mut open: Vec<(u8, bool)> = Vec::new();
+        let mut is_empty = true;
+        while let Some(event) = self.parser.next_event()? {
+            is_empty = false;
+            let name = match &event {
+                Event::CompoundStart(name) | Event::ListStart(name, ..) | Event::Tag(name, _) => {
+                    *name
+                }
+                Event::End => {
+                    let (close, _) = open.pop().expect("containers are ended after they start");
+                    self.out.write_all(&[close])?;
+                    continue;
+                }
+            };
+            // the root isn't in anything, so its name is dropped
+            if let Some((_, written)) = open.last_mut() {
+                if mem::replace(written, true) {
+                    self.out.write_all(b",")?;
+                }
+                if let Some(name) = name {
+                    write_string(&mut self.out, &decode(name, self.opts)?)?;
+                    self.out.write_all(b":")?;
+                }
+            }
+
+            match event {
+                Event::CompoundStart(_) => {
+                    self.out.write_all(b"{")?;
+                    open.push((b'}', false));
+                }
+                Event::ListStart(..) => {
+                    self.out.write_all(b"[")?;
+                    open.push((b']', false));
+                }
+                Event::Tag(_, value) => self.write_value(value)?,
+                Event::End => unreachable!(),
+            }
+        }
+        if is_empty {
+            self.out.write_all(b"null")?;
+        }
+        Ok(())
+    }
+
+    fn write_value(&mut self, value: Value) -> Result<(), TranscodeError> {
+        match value {
+            Value::Byte(value) => write!(self.out, "{value}")?,
+            Value::Short(value) => write!(self.out, "{value}")?,
+            Value::Int(value) => write!(self.out, "{value}")?,
+            Value::Long(value) => write!(self.out, "{value}")?,
+            Value::Float(value) => write_f32(&mut self.out, value)?,
+            Value::Double(value) => write_f64(&mut self.out, value)?,
+            Value::ByteArray(array) => self.write_array(array, BYTE_ID)?,
+            Value::String(string) => write_string(&mut self.out, &decode(string, self.opts)?)?,
+            Value::IntArray(array) => self.write_array(array.as_big_endian(), INT_ID)?,
+            Value::LongArray(array) => self.write_array(array.as_big_endian(), LONG_ID)?,
+        }
+        Ok(())
+    }
+
+    /// Write a byte, int, or long array with the configured [`ArrayStyle`].
+    fn write_array(&mut self, data: &[u8], number_type: u8) -> Result<(), TranscodeError> {
+        match self.opts.arrays {
+            ArrayStyle::Plain => self.write_numbers(data, number_type),
+            ArrayStyle::Tagged => {
+                write!(self.out, "{{\"{}\":", array_key(number_type))?;
+                self.write_numbers(data, number_type)?;
+                self.out.write_all(b"}")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the big-endian elements of an array as a JSON array.
+    fn write_numbers(&mut self, data: &[u8], number_type: u8) -> Result<(), TranscodeError> {
+        let out = &mut self.out;
+        out.write_all(b"[")?;
+        let width = match number_type {
+            BYTE_ID => 1,
+            INT_ID => 4,
+            _ => 8,
+        };
+        for (i, bytes) in data.chunks_exact(width).enumerate() {
+            if i != 0 {
+                out.write_all(b",")?;
+            }
+            match number_type {
+                BYTE_ID => write!(out, "{}", bytes[0] as i8)?,
+                INT_ID => write!(out, "{}", i32::from_be_bytes(bytes.try_into().unwrap()))?,
+                _ => write!(out, "{}", i64::from_be_bytes(bytes.try_into().unwrap()))?,
+            }
+        }
+        out.write_all(b"]")?;
+        Ok(())
+    }
+}
+
+fn write_f32(out: &mut impl Write, value: f32) -> io::Result<()> {
+    if value.is_finite() {
+        write!(out, "{value}")
+    } else {
+        out.write_all(b"null")
+    }
+}
+fn write_f64(out: &mut impl Write, value: f64) -> io::Result<()> {
+    if value.is_finite() {
+        write!(out, "{value}")
+    } else {
+        out.write_all(b"null")
+    }
+}
+
+/// The key that's used for arrays with [`ArrayStyle::Tagged`], from the id of
+/// the array's element type.
+fn array_key(number_type: u8) -> &'static str {
+    match number_type {
+        BYTE_ID => "byte_array",
+        INT_ID => "int_array",
+        _ => "long_array",
+    }
+}
+
+fn decode<'a>(string: &'a Mutf8Str, opts: &JsonOptions) -> Result<Cow<'a, str>, JsonError> {
+    match opts.invalid_strings {
+        InvalidStrings::Lossy => Ok(string.to_string_lossy()),
+        InvalidStrings::Error => {
+            mutf8::decode(string.as_bytes()).map_err(|_| JsonError::InvalidString)
+        }
+    }
+}
+
+fn write_string(out: &mut impl Write, string: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut start = 0;
+    for (i, byte) in string.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0..0x20 => "",
+            _ => continue,
+        };
+        out.write_all(&string.as_bytes()[start..i])?;
+        if escaped.is_empty() {
+            write!(out, "\\u{byte:04x}")?;
+        } else {
+            out.write_all(escaped.as_bytes())?;
+        }
+        start = i + 1;
+    }
+    out.write_all(&string.as_bytes()[start..])?;
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        owned::{NbtCompound, NbtList, NbtTag},
+        test_fixtures::write,
+        Mutf8String,
+    };
+
+    #[test]
+    fn every_type() {
+        let mut compound = NbtCompound::new();
+        compound.insert("byte", -1i8);
+        compound.insert("long", i64::MAX);
+        compound.insert("float", 0.5f32);
+        compound.insert("nan", f64::NAN);
+        compound.insert("string", "quote \" newline \n tab \t bell \x07");
+        compound.insert("bytes", NbtTag::ByteArray(vec![1, 255]));
+        compound.insert("ints", NbtTag::IntArray(vec![1, -2]));
+        compound.insert("longs", NbtTag::LongArray(vec![]));
+        compound.insert("shorts", NbtList::Short(vec![1, 2]));
+        compound.insert("doubles", NbtList::Double(vec![1.5]));
+        compound.insert("empty", NbtList::Empty);
+        compound.insert("nested", NbtList::List(vec![NbtList::Int(vec![3])]));
+        compound.insert("compounds", NbtList::Compound(vec![NbtCompound::new()]));
+
+        assert_eq!(
+            to_json(&write(compound)).unwrap(),
+            r#"{"byte":-1,"long":9223372036854775807,"float":0.5,"nan":null,"#.to_owned()
+                + r#""string":"quote \" newline \n tab \t bell \u0007","#
+                + r#""bytes":[1,-1],"ints":[1,-2],"longs":[],"shorts":[1,2],"doubles":[1.5],"#
+                + r#""empty":[],"nested":[[3]],"compounds":[{}]}"#
+        );
+        assert_eq!(to_json(&[0]).unwrap(), "null");
+    }
+
+    #[test]
+    fn errors() {
+        let mut compound = NbtCompound::new();
+        compound.insert("a", 1);
+        let data = write(compound);
+        assert_eq!(to_json(&data[..data.len() - 2]), Err(Error::UnexpectedEof));
+        assert_eq!(to_json(&[1]), Err(Error::InvalidRootType(1)));
+
+        let err = write_json(&[1], &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn options() {
+        let mut compound = NbtCompound::new();
+        compound.insert("bytes", NbtTag::ByteArray(vec![1, 255]));
+        compound.insert("ints", NbtList::IntArray(vec![vec![2]]));
+        // an unpaired surrogate
+        compound.insert(
+            "string",
+            NbtTag::String(Mutf8String::from_vec(vec![b'a', 0xed, 0xa0, 0x80])),
+        );
+        let data = write(compound);
+
+        let tagged = JsonOptions {
+            arrays: ArrayStyle::Tagged,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_json_with_opts(&data, &tagged).unwrap(),
+            r#"{"bytes":{"byte_array":[1,-1]},"ints":[{"int_array":[2]}],"string":"a���"}"#
+        );
+
+        let strict = JsonOptions {
+            invalid_strings: InvalidStrings::Error,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_json_with_opts(&data, &strict),
+            Err(JsonError::InvalidString)
+        );
+        assert_eq!(
+            to_json_with_opts(&[1], &strict),
+            Err(JsonError::Nbt(Error::InvalidRootType(1)))
+        );
+    }
+}
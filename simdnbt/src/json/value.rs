@@ -0,0 +1,421 @@
+//! Conversions between NBT and [`serde_json::Value`].
+
+use serde_json::{Map, Number, Value};
+
+use super::{array_key, decode, ArrayStyle, JsonError, JsonOptions};
+use crate::{
+    borrow,
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    owned::{NbtCompound, NbtList, NbtTag},
+    Mutf8Str,
+};
+
+/// A type that can be converted into a [`serde_json::Value`]. This is
+/// implemented for the owned and borrowed tags, lists, and compounds.
+///
+/// Values are made the same way as [`super::to_json`] writes them, except
+/// that compounds become [`serde_json::Map`]s, so their keys might be
+/// reordered and only the first tag with each name is kept.
+///
+/// ```
+/// use simdnbt::json::{JsonOptions, ToJsonValue};
+///
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// let value = compound.to_json_value(&JsonOptions::default()).unwrap();
+/// assert_eq!(value, serde_json::json!({ "Count": 1 }));
+/// ```
+pub trait ToJsonValue {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError>;
+}
+
+impl ToJsonValue for NbtTag {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        Ok(match self {
+            NbtTag::Byte(byte) => Value::from(*byte),
+            NbtTag::Short(short) => Value::from(*short),
+            NbtTag::Int(int) => Value::from(*int),
+            NbtTag::Long(long) => Value::from(*long),
+            NbtTag::Float(float) => f32_to_value(*float),
+            NbtTag::Double(double) => f64_to_value(*double),
+            NbtTag::ByteArray(bytes) => byte_array(bytes, opts),
+            NbtTag::String(string) => string_to_value(string, opts)?,
+            NbtTag::List(list) => list.to_json_value(opts)?,
+            NbtTag::Compound(compound) => compound.to_json_value(opts)?,
+            NbtTag::IntArray(ints) => array(numbers(ints), INT_ID, opts),
+            NbtTag::LongArray(longs) => array(numbers(longs), LONG_ID, opts),
+        })
+    }
+}
+impl ToJsonValue for NbtList {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        Ok(match self {
+            NbtList::Empty => Value::Array(Vec::new()),
+            NbtList::Byte(bytes) => numbers(bytes),
+            NbtList::Short(shorts) => numbers(shorts),
+            NbtList::Int(ints) => numbers(ints),
+            NbtList::Long(longs) => numbers(longs),
+            NbtList::Float(floats) => floats.iter().map(|f| f32_to_value(*f)).collect(),
+            NbtList::Double(doubles) => doubles.iter().map(|d| f64_to_value(*d)).collect(),
+            NbtList::ByteArray(arrays) => arrays.iter().map(|a| byte_array(a, opts)).collect(),
+            NbtList::String(strings) => strings
+                .iter()
+                .map(|s| string_to_value(s, opts))
+                .collect::<Result<_, _>>()?,
+            NbtList::List(lists) => lists
+                .iter()
+                .map(|l| l.to_json_value(opts))
+                .collect::<Result<_, _>>()?,
+            NbtList::Compound(compounds) => compounds
+                .iter()
+                .map(|c| c.to_json_value(opts))
+                .collect::<Result<_, _>>()?,
+            NbtList::IntArray(arrays) => arrays
+                .iter()
+                .map(|a| array(numbers(a), INT_ID, opts))
+                .collect(),
+            NbtList::LongArray(arrays) => arrays
+                .iter()
+                .map(|a| array(numbers(a), LONG_ID, opts))
+                .collect(),
+        })
+    }
+}
+impl ToJsonValue for NbtCompound {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        compound_to_value(self.iter(), |tag| tag.to_json_value(opts), opts)
+    }
+}
+
+impl ToJsonValue for borrow::NbtTag<'_, '_> {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        Ok(match self.id() {
+            BYTE_ID => Value::from(self.byte().unwrap()),
+            SHORT_ID => Value::from(self.short().unwrap()),
+            INT_ID => Value::from(self.int().unwrap()),
+            LONG_ID => Value::from(self.long().unwrap()),
+            FLOAT_ID => f32_to_value(self.float().unwrap()),
+            DOUBLE_ID => f64_to_value(self.double().unwrap()),
+            BYTE_ARRAY_ID => byte_array(self.byte_array().unwrap(), opts),
+            STRING_ID => string_to_value(self.string().unwrap(), opts)?,
+            LIST_ID => self.list().unwrap().to_json_value(opts)?,
+            COMPOUND_ID => self.compound().unwrap().to_json_value(opts)?,
+            INT_ARRAY_ID => array(numbers(&self.int_array().unwrap()), INT_ID, opts),
+            LONG_ARRAY_ID => array(numbers(&self.long_array().unwrap()), LONG_ID, opts),
+            _ => unreachable!(),
+        })
+    }
+}
+impl ToJsonValue for borrow::NbtList<'_, '_> {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        self.iter().map(|tag| tag.to_json_value(opts)).collect()
+    }
+}
+impl ToJsonValue for borrow::NbtCompound<'_, '_> {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        compound_to_value(self.iter(), |tag| tag.to_json_value(opts), opts)
+    }
+}
+/// The name of the root is dropped, since JSON doesn't have a way to write it.
+impl ToJsonValue for borrow::BaseNbt<'_> {
+    fn to_json_value(&self, opts: &JsonOptions) -> Result<Value, JsonError> {
+        self.as_compound().to_json_value(opts)
+    }
+}
+
+fn compound_to_value<'a, T>(
+    tags: impl Iterator<Item = (&'a Mutf8Str, T)>,
+    to_value: impl Fn(T) -> Result<Value, JsonError>,
+    opts: &JsonOptions,
+) -> Result<Value, JsonError> {
+    let mut map = Map::new();
+    for (name, tag) in tags {
+        let name = decode(name, opts)?.into_owned();
+        // the first tag with a name is the one that `get` returns
+        if !map.contains_key(&name) {
+            map.insert(name, to_value(tag)?);
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+fn numbers<T: Copy + Into<Value>>(numbers: &[T]) -> Value {
+    numbers.iter().map(|n| (*n).into()).collect()
+}
+
+fn byte_array(bytes: &[u8], opts: &JsonOptions) -> Value {
+    let bytes = bytes.iter().map(|b| Value::from(*b as i8)).collect();
+    array(bytes, BYTE_ID, opts)
+}
+
+/// Wrap the numbers of a byte, int, or long array in an object if
+/// [`ArrayStyle::Tagged`] is used.
+fn array(numbers: Value, number_type: u8, opts: &JsonOptions) -> Value {
+    match opts.arrays {
+        ArrayStyle::Plain => numbers,
+        ArrayStyle::Tagged => {
+            let mut map = Map::new();
+            map.insert(array_key(number_type).to_owned(), numbers);
+            Value::Object(map)
+        }
+    }
+}
+
+fn string_to_value(string: &Mutf8Str, opts: &JsonOptions) -> Result<Value, JsonError> {
+    Ok(Value::String(decode(string, opts)?.into_owned()))
+}
+
+fn f32_to_value(value: f32) -> Value {
+    // go through the shortest representation of the float, so 0.1 is written
+    // as 0.1 instead of 0.10000000149011612
+    f64_to_value(value.to_string().parse().unwrap_or(f64::NAN))
+}
+fn f64_to_value(value: f64) -> Value {
+    // json doesn't have infinity or nan
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Convert a JSON value into an NBT tag, in the same way that the
+/// [`ToJsonValue`] conversions would have made it.
+///
+/// Since JSON doesn't have as many types as NBT, some types have to be
+/// guessed:
+/// - Integers become ints, or longs if they're too big to be an int.
+/// - Other numbers become doubles.
+/// - `true` and `false` become bytes.
+/// - Lists of numbers are widened to the biggest type of number in them.
+/// - With [`ArrayStyle::Tagged`], objects like `{"int_array":[1,2,3]}` become
+///   arrays.
+///
+/// `null` isn't allowed, and neither are lists with different types in them.
+///
+/// ```
+/// use simdnbt::json::{from_json_value, JsonOptions};
+///
+/// let tag = from_json_value(
+///     &serde_json::json!({ "id": "minecraft:stone", "pos": [1, 2, 3_000_000_000i64] }),
+///     &JsonOptions::default(),
+/// )
+/// .unwrap();
+/// let compound = tag.compound().unwrap();
+/// assert_eq!(compound.string("id").unwrap().to_str(), "minecraft:stone");
+/// assert_eq!(compound.list("pos").unwrap().longs(), Some(vec![1, 2, 3_000_000_000]));
+/// ```
+pub fn from_json_value(value: &Value, opts: &JsonOptions) -> Result<NbtTag, JsonError> {
+    Ok(match value {
+        Value::Null => return Err(JsonError::Null),
+        Value::Bool(value) => NbtTag::Byte(*value as i8),
+        Value::Number(number) => {
+            if let Some(int) = number.as_i64() {
+                match i32::try_from(int) {
+                    Ok(int) => NbtTag::Int(int),
+                    Err(_) => NbtTag::Long(int),
+                }
+            } else if number.is_u64() {
+                return Err(JsonError::InvalidNumber(number.to_string()));
+            } else {
+                NbtTag::Double(number.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(string) => NbtTag::String(string.as_str().into()),
+        Value::Array(values) => NbtTag::List(list_from_values(values, opts)?),
+        Value::Object(map) => {
+            if opts.arrays == ArrayStyle::Tagged {
+                if let Some(array) = tagged_array(map)? {
+                    return Ok(array);
+                }
+            }
+            NbtTag::Compound(compound_from_map(map, opts)?)
+        }
+    })
+}
+
+/// Convert a JSON object into an NBT compound. See [`from_json_value`].
+pub fn compound_from_json_value(
+    value: &Value,
+    opts: &JsonOptions,
+) -> Result<NbtCompound, JsonError> {
+    match value {
+        Value::Object(map) => compound_from_map(map, opts),
+        _ => Err(JsonError::ExpectedObject),
+    }
+}
+
+fn compound_from_map(
+    map: &Map<String, Value>,
+    opts: &JsonOptions,
+) -> Result<NbtCompound, JsonError> {
+    let mut compound = NbtCompound::new();
+    for (name, value) in map {
+        compound.insert(name.as_str(), from_json_value(value, opts)?);
+    }
+    Ok(compound)
+}
+
+fn list_from_values(values: &[Value], opts: &JsonOptions) -> Result<NbtList, JsonError> {
+    let mut tags = values
+        .iter()
+        .map(|value| from_json_value(value, opts))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // numbers that came from json can be ints, longs, or doubles, so make them
+    // all the same type
+    if tags
+        .iter()
+        .all(|tag| matches!(tag, NbtTag::Int(_) | NbtTag::Long(_) | NbtTag::Double(_)))
+    {
+        if tags.iter().any(|tag| matches!(tag, NbtTag::Double(_))) {
+            for tag in &mut tags {
+                *tag = NbtTag::Double(match *tag {
+                    NbtTag::Int(int) => int as f64,
+                    NbtTag::Long(long) => long as f64,
+                    NbtTag::Double(double) => double,
+                    _ => unreachable!(),
+                });
+            }
+        } else if tags.iter().any(|tag| matches!(tag, NbtTag::Long(_))) {
+            for tag in &mut tags {
+                if let NbtTag::Int(int) = *tag {
+                    *tag = NbtTag::Long(int as i64);
+                }
+            }
+        }
+    }
+
+    NbtList::from_tags(tags).ok_or(JsonError::MixedList)
+}
+
+/// Read an object like `{"int_array":[1,2,3]}` as an array, or return `None`
+/// if it doesn't look like one.
+fn tagged_array(map: &Map<String, Value>) -> Result<Option<NbtTag>, JsonError> {
+    if map.len() != 1 {
+        return Ok(None);
+    }
+    let (key, Value::Array(values)) = map.iter().next().unwrap() else {
+        return Ok(None);
+    };
+    fn collect<T: TryFrom<i64>>(values: &[Value]) -> Result<Vec<T>, JsonError> {
+        values
+            .iter()
+            .map(|value| {
+                value
+                    .as_i64()
+                    .and_then(|n| T::try_from(n).ok())
+                    .ok_or_else(|| JsonError::InvalidNumber(value.to_string()))
+            })
+            .collect()
+    }
+    Ok(Some(match key.as_str() {
+        "byte_array" => NbtTag::ByteArray(
+            collect::<i16>(values)?
+                .into_iter()
+                .map(|n| match i8::try_from(n) {
+                    // unsigned bytes are allowed too
+                    Ok(n) => Ok(n as u8),
+                    Err(_) => u8::try_from(n).map_err(|_| JsonError::InvalidNumber(n.to_string())),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        "int_array" => NbtTag::IntArray(collect(values)?),
+        "long_array" => NbtTag::LongArray(collect(values)?),
+        _ => return Ok(None),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::owned::BaseNbt;
+
+    fn compound() -> NbtCompound {
+        let mut inner = NbtCompound::new();
+        inner.insert("name", "stone");
+        let mut compound = NbtCompound::new();
+        compound.insert("byte", 1i8);
+        compound.insert("long", i64::MAX);
+        compound.insert("float", 0.1f32);
+        compound.insert("bytes", NbtTag::ByteArray(vec![1, 255]));
+        compound.insert("ints", NbtTag::IntArray(vec![1, 2]));
+        compound.insert("longs", NbtList::LongArray(vec![vec![3]]));
+        compound.insert("doubles", NbtList::Double(vec![1.5, 2.5]));
+        compound.insert("compounds", NbtList::Compound(vec![inner]));
+        compound.insert("empty", NbtList::Empty);
+        compound
+    }
+
+    #[test]
+    fn owned_and_borrowed() {
+        let compound = compound();
+        let data = BaseNbt::new("", compound.clone()).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        let opts = JsonOptions::default();
+        let expected = json!({
+            "byte": 1,
+            "long": i64::MAX,
+            "float": 0.1,
+            "bytes": [1, -1],
+            "ints": [1, 2],
+            "longs": [[3]],
+            "doubles": [1.5, 2.5],
+            "compounds": [{ "name": "stone" }],
+            "empty": [],
+        });
+        assert_eq!(compound.to_json_value(&opts).unwrap(), expected);
+        assert_eq!(nbt.to_json_value(&opts).unwrap(), expected);
+        // the streaming converter makes the same json
+        assert_eq!(
+            serde_json::from_str::<Value>(&super::super::to_json(&data).unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn round_trip_with_tagged_arrays() {
+        let compound = compound();
+        let opts = JsonOptions {
+            arrays: ArrayStyle::Tagged,
+            ..Default::default()
+        };
+        let value = compound.to_json_value(&opts).unwrap();
+        assert_eq!(value["bytes"], json!({ "byte_array": [1, -1] }));
+
+        let round_tripped = compound_from_json_value(&value, &opts).unwrap();
+        // the json map sorts the keys, and bytes and floats become ints and
+        // doubles
+        assert_eq!(round_tripped.int("byte"), Some(1));
+        assert_eq!(round_tripped.double("float"), Some(0.1));
+        for name in ["long", "bytes", "ints", "longs", "doubles", "compounds"] {
+            assert_eq!(round_tripped.get(name), compound.get(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn from_json_errors() {
+        let opts = JsonOptions::default();
+        assert_eq!(from_json_value(&json!(null), &opts), Err(JsonError::Null));
+        assert_eq!(
+            from_json_value(&json!([1, "a"]), &opts),
+            Err(JsonError::MixedList)
+        );
+        assert_eq!(
+            from_json_value(&json!(u64::MAX), &opts),
+            Err(JsonError::InvalidNumber(u64::MAX.to_string()))
+        );
+        assert_eq!(
+            compound_from_json_value(&json!([]), &opts),
+            Err(JsonError::ExpectedObject)
+        );
+        assert_eq!(
+            from_json_value(&json!([1, 2.5]), &opts),
+            Ok(NbtTag::List(NbtList::Double(vec![1.0, 2.5])))
+        );
+    }
+}
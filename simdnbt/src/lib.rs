@@ -1,26 +1,82 @@
 #![doc = include_str!("../README.md")]
-#![feature(portable_simd)]
-#![feature(array_chunks)]
+#![cfg_attr(feature = "nightly", feature(portable_simd))]
+#![cfg_attr(feature = "nightly", feature(array_chunks))]
 #![allow(internal_features)]
-#![feature(core_intrinsics)]
-#![feature(allocator_api)]
+#![cfg_attr(feature = "nightly", feature(core_intrinsics))]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
 
-#[cfg(not(target_pointer_width = "64"))]
-compile_error!("simdnbt only supports 64-bit platforms");
+#[cfg(not(any(target_pointer_width = "64", target_arch = "wasm32")))]
+compile_error!("simdnbt only supports 64-bit platforms and wasm32");
 
+// so the derive macros can be used inside the crate
+extern crate self as simdnbt;
+
+mod allocator;
+#[cfg(feature = "nightly")]
+pub mod arena;
 pub mod borrow;
 mod common;
+mod compare;
+#[cfg(feature = "compression")]
+mod compression;
+mod diff;
+pub mod differential;
 mod error;
+mod explain;
+mod hash;
+#[cfg(any(feature = "fastnbt", feature = "valence_nbt"))]
+pub mod interop;
+mod io_write;
+pub mod json;
+mod little_endian;
+pub mod migrate;
 mod mutf8;
+pub mod net;
+mod options;
 pub mod owned;
+pub mod packed;
+#[cfg(feature = "rayon")]
+pub mod par;
+mod path;
+#[cfg(feature = "proptest")]
+pub mod proptest;
 pub mod raw_list;
-mod reader;
+mod raw_value;
+pub mod reader;
+#[cfg(feature = "mc-schema")]
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod snbt;
+pub mod stream;
 pub mod swap_endianness;
+mod tag_type;
+#[cfg(test)]
+mod test_fixtures;
+pub mod testing;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 mod traits;
+pub mod validate;
+#[cfg(feature = "world")]
+pub mod world;
 
+pub use compare::{compare, Comparison};
+#[cfg(feature = "compression")]
+pub use compression::Compression;
+pub use diff::{diff, CompoundRef, Difference};
 pub use error::{DeserializeError, Error};
-pub use mutf8::Mutf8Str;
+pub use explain::{
+    dump_annotated, dump_annotated_with, explain, locate_error, locate_error_with,
+    locate_error_with_opts, ErrorLocation, RootLayout,
+};
+pub use mutf8::{Mutf8Str, Mutf8String};
+pub use options::{DuplicateKeyPolicy, EmptyLists, ReadOptions, WriteOptions};
+pub use path::{NbtPath, NbtPathError, PathSegment};
+pub use raw_value::RawNbtValue;
+#[cfg(feature = "derive")]
 pub use simdnbt_derive::*;
+pub use tag_type::TagType;
 pub use traits::{Deserialize, FromNbtTag, Serialize, ToNbtTag};
 
 #[cfg(test)]
@@ -38,17 +94,12 @@ mod tests {
     }
 
     fn test_decodes_equally(src: &[u8]) {
-        let nbt_borrow = crate::borrow::read(&mut Cursor::new(src))
-            .unwrap()
-            .unwrap()
-            .as_compound()
-            .to_owned();
-        let nbt_owned = crate::owned::read(&mut Cursor::new(src))
-            .unwrap()
-            .unwrap()
-            .as_compound();
+        let nbt_borrow = crate::borrow::read(&mut Cursor::new(src)).unwrap().unwrap();
+        let nbt_owned = crate::owned::read(&mut Cursor::new(src)).unwrap().unwrap();
         // (there's another test in owned to make sure that PartialEq actually works)
-        assert_eq!(nbt_borrow, nbt_owned);
+        assert_eq!(nbt_borrow.as_compound().to_owned(), *nbt_owned);
+        assert!(nbt_borrow == nbt_owned);
+        assert!(nbt_owned == nbt_borrow);
     }
 
     #[test]
@@ -66,9 +117,271 @@ mod tests {
         let src = include_bytes!("../tests/hypixel.nbt").to_vec();
         test_decodes_equally(&src);
     }
+    #[test]
+    fn huge_lengths_are_invalid() {
+        use crate::common::{BYTE_ARRAY_ID, COMPOUND_ID, INT_ID, LIST_ID, STRING_ID};
+
+        for prefix in [
+            &[BYTE_ARRAY_ID][..],
+            &[LIST_ID, INT_ID],
+            &[LIST_ID, STRING_ID],
+            &[LIST_ID, COMPOUND_ID],
+            &[LIST_ID, LIST_ID],
+        ] {
+            for length in [0x8000_0000u32, u32::MAX] {
+                let mut data = vec![COMPOUND_ID, 0, 0, prefix[0], 0, 0];
+                data.extend_from_slice(&prefix[1..]);
+                data.extend_from_slice(&length.to_be_bytes());
+
+                assert_eq!(
+                    crate::borrow::read(&mut Cursor::new(&data)).unwrap_err(),
+                    crate::Error::InvalidLength
+                );
+                assert_eq!(
+                    crate::owned::read(&mut Cursor::new(&data)).unwrap_err(),
+                    crate::Error::InvalidLength
+                );
+            }
+        }
+    }
+
     #[test]
     fn bigtest_borrow_and_owned() {
         let src = include_bytes!("../tests/bigtest.nbt").to_vec();
         test_decodes_equally(&gzip_decode(&src));
     }
+
+    #[test]
+    fn max_tags() {
+        let mut compound = crate::owned::NbtCompound::new();
+        for i in 0..100 {
+            compound.insert(i.to_string(), i);
+        }
+        compound.insert(
+            "strings",
+            crate::owned::NbtList::String(vec!["a".into(); 100]),
+        );
+        let mut data = Vec::new();
+        crate::owned::BaseNbt::new("", compound).write(&mut data);
+
+        let options = crate::ReadOptions {
+            max_tags: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            crate::borrow::read_with_opts(&mut Cursor::new(&data), &options).unwrap_err(),
+            crate::Error::TooManyTags
+        );
+        assert_eq!(
+            crate::owned::read_with_opts(&mut Cursor::new(&data), &options).unwrap_err(),
+            crate::Error::TooManyTags
+        );
+
+        let options = crate::ReadOptions {
+            max_tags: Some(1000),
+            ..Default::default()
+        };
+        assert!(crate::borrow::read_with_opts(&mut Cursor::new(&data), &options).is_ok());
+        assert!(crate::owned::read_with_opts(&mut Cursor::new(&data), &options).is_ok());
+    }
+
+    /// Read the data with both readers and check that they have the same
+    /// result.
+    fn read_both(data: &[u8], options: &crate::ReadOptions) -> Result<(), crate::Error> {
+        let borrow = crate::borrow::read_with_opts(&mut Cursor::new(data), options).map(|_| ());
+        let owned = crate::owned::read_with_opts(&mut Cursor::new(data), options).map(|_| ());
+        assert_eq!(borrow, owned);
+        borrow
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::owned::{BaseNbt, NbtCompound, NbtList};
+
+        let options = crate::ReadOptions {
+            max_depth: Some(3),
+            ..Default::default()
+        };
+        for depth in 0..6 {
+            let mut compound = NbtCompound::new();
+            let mut list = NbtList::Int(vec![1]);
+            for _ in 0..depth {
+                let mut outer = NbtCompound::new();
+                outer.insert("a", compound);
+                compound = outer;
+                list = NbtList::List(vec![list]);
+            }
+            let expected = if depth <= 3 {
+                Ok(())
+            } else {
+                Err(crate::Error::MaxDepthExceeded)
+            };
+            assert_eq!(
+                read_both(&BaseNbt::new("", compound).to_bytes(), &options),
+                expected
+            );
+
+            // the list of ints is one level deeper than the lists around it
+            let mut root = NbtCompound::new();
+            root.insert("a", list);
+            let expected = if depth < 3 {
+                Ok(())
+            } else {
+                Err(crate::Error::MaxDepthExceeded)
+            };
+            assert_eq!(
+                read_both(&BaseNbt::new("", root).to_bytes(), &options),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn max_bytes_and_list_len() {
+        let mut compound = crate::owned::NbtCompound::new();
+        compound.insert("ints", crate::owned::NbtList::Int(vec![1; 10]));
+        compound.insert("array", crate::owned::NbtTag::LongArray(vec![2; 5]));
+        let mut data = crate::owned::BaseNbt::new("", compound).to_bytes();
+        let len = data.len();
+
+        let options = |max_bytes, max_list_len| crate::ReadOptions {
+            max_bytes: Some(max_bytes),
+            max_list_len: Some(max_list_len),
+            ..Default::default()
+        };
+        assert_eq!(read_both(&data, &options(len, 10)), Ok(()));
+        assert_eq!(
+            read_both(&data, &options(len - 1, 10)),
+            Err(crate::Error::TooManyBytes)
+        );
+        assert_eq!(
+            read_both(&data, &options(len, 9)),
+            Err(crate::Error::InvalidLength)
+        );
+        assert_eq!(
+            read_both(&data, &options(len, 4)),
+            Err(crate::Error::InvalidLength)
+        );
+
+        // data after the nbt doesn't count
+        data.extend_from_slice(&[0; 100]);
+        assert_eq!(read_both(&data, &options(len, 10)), Ok(()));
+        let mut cursor = Cursor::new(&data[..]);
+        crate::borrow::read_with_opts(&mut cursor, &options(len, 10)).unwrap();
+        assert_eq!(cursor.position(), len as u64);
+        // and neither does data before the cursor
+        let mut cursor = Cursor::new(&data[..]);
+        cursor.set_position(len as u64 - 1);
+        crate::owned::read_with_opts(&mut cursor, &options(1, 10)).unwrap();
+    }
+
+    #[test]
+    fn max_allocation() {
+        let mut compound = crate::owned::NbtCompound::new();
+        compound.insert("bytes", crate::owned::NbtTag::ByteArray(vec![0; 10_000]));
+        for i in 0..100 {
+            compound.insert(i.to_string(), i);
+        }
+        let data = crate::owned::BaseNbt::new("", compound).to_bytes();
+
+        let options = crate::ReadOptions {
+            max_allocation: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(
+            crate::borrow::read_with_opts(&mut Cursor::new(&data), &options).unwrap_err(),
+            crate::Error::OutOfMemory
+        );
+        assert_eq!(
+            crate::owned::read_with_opts(&mut Cursor::new(&data), &options).unwrap_err(),
+            crate::Error::OutOfMemory
+        );
+
+        // the borrowed reader doesn't copy the byte array
+        let options = crate::ReadOptions {
+            max_allocation: Some(5000),
+            ..Default::default()
+        };
+        assert!(crate::borrow::read_with_opts(&mut Cursor::new(&data), &options).is_ok());
+        assert_eq!(
+            crate::owned::read_with_opts(&mut Cursor::new(&data), &options).unwrap_err(),
+            crate::Error::OutOfMemory
+        );
+
+        let options = crate::ReadOptions {
+            max_allocation: Some(100_000),
+            ..Default::default()
+        };
+        assert_eq!(read_both(&data, &options), Ok(()));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        use crate::{
+            compound,
+            owned::{BaseNbt, NbtCompound, NbtList, NbtTag},
+            DuplicateKeyPolicy,
+        };
+
+        let with_duplicates = |values: Vec<(&str, NbtTag)>| {
+            NbtCompound::from_values(
+                values
+                    .into_iter()
+                    .map(|(name, tag)| (name.into(), tag))
+                    .collect(),
+            )
+        };
+        let inner = with_duplicates(vec![("z", 1.into()), ("z", 2.into())]);
+        let first_b = with_duplicates(vec![
+            ("x", 1.into()),
+            ("x", 2.into()),
+            ("y", NbtList::Compound(vec![inner]).into()),
+        ]);
+        let compound = with_duplicates(vec![
+            ("a", 1.into()),
+            ("b", first_b.into()),
+            ("a", 2.into()),
+            ("c", "after".into()),
+            ("b", compound! { "x": 3 }.into()),
+        ]);
+        let data = BaseNbt::new("", compound.clone()).to_bytes();
+
+        let read = |duplicate_keys| {
+            let options = crate::ReadOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            let borrow = crate::borrow::read_with_opts(&mut Cursor::new(&data), &options)
+                .map(|nbt| nbt.unwrap().as_compound().to_owned());
+            let owned = crate::owned::read_with_opts(&mut Cursor::new(&data), &options)
+                .map(|nbt| (*nbt.unwrap()).clone());
+            assert_eq!(borrow, owned);
+            borrow
+        };
+
+        assert_eq!(read(DuplicateKeyPolicy::KeepAll), Ok(compound));
+        assert_eq!(
+            read(DuplicateKeyPolicy::First),
+            Ok(compound! {
+                "a": 1,
+                "b": { "x": 1, "y": [{ "z": 1 }] },
+                "c": "after",
+            })
+        );
+        assert_eq!(
+            read(DuplicateKeyPolicy::Last),
+            Ok(compound! { "a": 2, "c": "after", "b": { "x": 3 } })
+        );
+        assert_eq!(
+            read(DuplicateKeyPolicy::Error),
+            Err(crate::Error::DuplicateKey)
+        );
+
+        let data = BaseNbt::new("", compound! { "a": 1, "b": 2 }).to_bytes();
+        let options = crate::ReadOptions {
+            duplicate_keys: DuplicateKeyPolicy::Error,
+            ..Default::default()
+        };
+        assert_eq!(read_both(&data, &options), Ok(()));
+    }
 }
@@ -0,0 +1,369 @@
+//! Conversion between the big-endian NBT that Java Edition uses and the
+//! little-endian NBT that Bedrock Edition uses for files like `level.dat`.
+//!
+//! Rather than having a second copy of every reader, we convert the data in
+//! place and then read it like normal. Owned NBT is written little-endian
+//! directly, since it can be nested deeper than the converter allows.
+
+use crate::{
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
+        LIST_ID, LONG_ARRAY_ID, LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
+    },
+    owned::{NbtCompound, NbtList, NbtTag},
+    swap_endianness::{swap_endianness_in_place, SwappableNumber},
+    Error, Mutf8Str,
+};
+
+/// The endianness that the data is being converted from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Endian {
+    Big,
+    Little,
+}
+
+/// Swap the endianness of every number in some root NBT (a tag id, a name,
+/// and a compound), returning how many bytes the NBT took up.
+///
+/// If this returns an error, the data may have been partially converted.
+pub(crate) fn convert_nbt(data: &mut [u8], from: Endian) -> Result<usize, Error> {
+    let mut converter = Converter { data, pos: 0, from };
+    let root_type = converter.u8()?;
+    if root_type == END_ID {
+        return Ok(converter.pos);
+    }
+    if root_type != COMPOUND_ID {
+        return Err(Error::InvalidRootType(root_type));
+    }
+    converter.string()?;
+    converter.compound(0)?;
+    Ok(converter.pos)
+}
+
+/// Write the name and payload of a root compound little-endian. The tag ID
+/// has to be written before this.
+pub(crate) fn write_root(name: &Mutf8Str, compound: &NbtCompound, data: &mut Vec<u8>) {
+    write_string(name, data);
+    write_compound(compound, data);
+}
+
+fn write_string(value: &Mutf8Str, data: &mut Vec<u8>) {
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+fn write_length(length: usize, data: &mut Vec<u8>) {
+    data.extend_from_slice(&(length as u32).to_le_bytes());
+}
+
+fn write_compound(compound: &NbtCompound, data: &mut Vec<u8>) {
+    for (name, tag) in compound.iter() {
+        data.push(tag.id());
+        write_string(name, data);
+        write_tag(tag, data);
+    }
+    data.push(END_ID);
+}
+
+/// Write the payload of a tag.
+fn write_tag(tag: &NbtTag, data: &mut Vec<u8>) {
+    match tag {
+        NbtTag::Byte(byte) => data.push(*byte as u8),
+        NbtTag::Short(short) => data.extend_from_slice(&short.to_le_bytes()),
+        NbtTag::Int(int) => data.extend_from_slice(&int.to_le_bytes()),
+        NbtTag::Long(long) => data.extend_from_slice(&long.to_le_bytes()),
+        NbtTag::Float(float) => data.extend_from_slice(&float.to_le_bytes()),
+        NbtTag::Double(double) => data.extend_from_slice(&double.to_le_bytes()),
+        NbtTag::ByteArray(bytes) => {
+            write_length(bytes.len(), data);
+            data.extend_from_slice(bytes);
+        }
+        NbtTag::String(string) => write_string(string, data),
+        NbtTag::List(list) => write_list(list, data),
+        NbtTag::Compound(compound) => write_compound(compound, data),
+        NbtTag::IntArray(ints) => write_numbers(ints, i32::to_le_bytes, data),
+        NbtTag::LongArray(longs) => write_numbers(longs, i64::to_le_bytes, data),
+    }
+}
+
+fn write_numbers<T: Copy, const N: usize>(
+    numbers: &[T],
+    to_le_bytes: fn(T) -> [u8; N],
+    data: &mut Vec<u8>,
+) {
+    write_length(numbers.len(), data);
+    data.reserve(numbers.len() * N);
+    for &number in numbers {
+        data.extend_from_slice(&to_le_bytes(number));
+    }
+}
+
+fn write_list(list: &NbtList, data: &mut Vec<u8>) {
+    data.push(list.id());
+    match list {
+        NbtList::Empty => write_length(0, data),
+        NbtList::Byte(bytes) => {
+            write_length(bytes.len(), data);
+            data.extend(bytes.iter().map(|&byte| byte as u8));
+        }
+        NbtList::Short(shorts) => write_numbers(shorts, i16::to_le_bytes, data),
+        NbtList::Int(ints) => write_numbers(ints, i32::to_le_bytes, data),
+        NbtList::Long(longs) => write_numbers(longs, i64::to_le_bytes, data),
+        NbtList::Float(floats) => write_numbers(floats, f32::to_le_bytes, data),
+        NbtList::Double(doubles) => write_numbers(doubles, f64::to_le_bytes, data),
+        NbtList::ByteArray(arrays) => {
+            write_length(arrays.len(), data);
+            for array in arrays {
+                write_length(array.len(), data);
+                data.extend_from_slice(array);
+            }
+        }
+        NbtList::String(strings) => {
+            write_length(strings.len(), data);
+            for string in strings {
+                write_string(string, data);
+            }
+        }
+        NbtList::List(lists) => {
+            write_length(lists.len(), data);
+            for list in lists {
+                write_list(list, data);
+            }
+        }
+        NbtList::Compound(compounds) => {
+            write_length(compounds.len(), data);
+            for compound in compounds {
+                write_compound(compound, data);
+            }
+        }
+        NbtList::IntArray(arrays) => {
+            write_length(arrays.len(), data);
+            for array in arrays {
+                write_numbers(array, i32::to_le_bytes, data);
+            }
+        }
+        NbtList::LongArray(arrays) => {
+            write_length(arrays.len(), data);
+            for array in arrays {
+                write_numbers(array, i64::to_le_bytes, data);
+            }
+        }
+    }
+}
+
+struct Converter<'a> {
+    data: &'a mut [u8],
+    pos: usize,
+    from: Endian,
+}
+
+impl Converter<'_> {
+    /// Get the next `len` bytes and move past them.
+    fn take(&mut self, len: usize) -> Result<&mut [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(Error::UnexpectedEof)?;
+        let bytes = &mut self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read a number in the endianness we're converting from and then swap
+    /// it.
+    fn swapped<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let from = self.from;
+        let bytes = self.take(N)?;
+        bytes.reverse();
+        // the bytes are now in the opposite order from what we're converting
+        // from
+        let mut value: [u8; N] = (&*bytes).try_into().unwrap();
+        if from == Endian::Big {
+            value.reverse();
+        }
+        Ok(value)
+    }
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.swapped()?))
+    }
+    fn length(&mut self) -> Result<usize, Error> {
+        let length = u32::from_be_bytes(self.swapped()?);
+        if length > i32::MAX as u32 {
+            return Err(Error::InvalidLength);
+        }
+        Ok(length as usize)
+    }
+
+    fn string(&mut self) -> Result<(), Error> {
+        let length = self.u16()? as usize;
+        self.take(length)?;
+        Ok(())
+    }
+
+    /// Swap `count` numbers of type `T`.
+    fn numbers<T: SwappableNumber>(&mut self, count: usize) -> Result<(), Error> {
+        let bytes = self.take(count * size_of::<T>())?;
+        if cfg!(target_endian = "little") {
+            swap_endianness_in_place::<T>(bytes);
+        } else {
+            // swap_endianness only swaps on little-endian systems, since it's
+            // meant for converting to native endianness
+            for number in bytes.chunks_exact_mut(size_of::<T>()) {
+                number.reverse();
+            }
+        }
+        Ok(())
+    }
+
+    fn compound(&mut self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::MaxDepthExceeded);
+        }
+        loop {
+            let tag_type = self.u8()?;
+            if tag_type == END_ID {
+                return Ok(());
+            }
+            self.string()?;
+            self.tag(tag_type, depth)?;
+        }
+    }
+
+    fn list(&mut self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::MaxDepthExceeded);
+        }
+        let tag_type = self.u8()?;
+        let length = self.length()?;
+        match tag_type {
+            // numbers can all be swapped at once
+            END_ID | BYTE_ID => {
+                self.take(length)?;
+            }
+            SHORT_ID => self.numbers::<u16>(length)?,
+            INT_ID | FLOAT_ID => self.numbers::<u32>(length)?,
+            LONG_ID | DOUBLE_ID => self.numbers::<u64>(length)?,
+            _ => {
+                for _ in 0..length {
+                    self.tag(tag_type, depth)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the payload of a tag with the given type.
+    fn tag(&mut self, tag_type: u8, depth: usize) -> Result<(), Error> {
+        match tag_type {
+            BYTE_ID => {
+                self.take(1)?;
+            }
+            SHORT_ID => self.numbers::<u16>(1)?,
+            INT_ID | FLOAT_ID => self.numbers::<u32>(1)?,
+            LONG_ID | DOUBLE_ID => self.numbers::<u64>(1)?,
+            BYTE_ARRAY_ID => {
+                let length = self.length()?;
+                self.take(length)?;
+            }
+            STRING_ID => self.string()?,
+            LIST_ID => self.list(depth + 1)?,
+            COMPOUND_ID => self.compound(depth + 1)?,
+            INT_ARRAY_ID => {
+                let length = self.length()?;
+                self.numbers::<u32>(length)?;
+            }
+            LONG_ARRAY_ID => {
+                let length = self.length()?;
+                self.numbers::<u64>(length)?;
+            }
+            _ => return Err(Error::UnknownTagId(tag_type)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{borrow, owned, test_fixtures::bigtest};
+
+    #[test]
+    fn read_little_endian() {
+        #[rustfmt::skip]
+        let data = [
+            10, 0, 0,
+            3, 3, 0, b'i', b'n', b't', 1, 0, 0, 0,
+            9, 4, 0, b'l', b'i', b's', b't', 2, 2, 0, 0, 0, 1, 0, 0, 1,
+            0,
+            // trailing data that isn't part of the nbt
+            0xff,
+        ];
+
+        let mut cursor = Cursor::new(&data[..]);
+        let nbt = owned::read_le(&mut cursor).unwrap().unwrap();
+        assert_eq!(cursor.position(), data.len() as u64 - 1);
+        assert_eq!(nbt.int("int"), Some(1));
+        assert_eq!(nbt.list("list"), Some(&owned::NbtList::Short(vec![1, 256])));
+
+        let mut borrowed_data = data;
+        let borrowed = borrow::read_le(&mut borrowed_data).unwrap().unwrap();
+        assert_eq!(borrowed.as_compound().to_owned(), *nbt);
+
+        let mut written = Vec::new();
+        nbt.write_le(&mut written);
+        assert_eq!(written, data[..data.len() - 1]);
+    }
+
+    #[test]
+    fn bigtest_round_trip() {
+        let src = bigtest();
+        let nbt = owned::read(&mut Cursor::new(&src)).unwrap();
+
+        let mut data = Vec::new();
+        nbt.write_le(&mut data);
+        assert_ne!(data, src);
+        assert_eq!(owned::read_le(&mut Cursor::new(&data)).unwrap(), nbt);
+
+        let mut borrowed_data = data.clone();
+        let borrowed = borrow::read_le(&mut borrowed_data).unwrap();
+        let mut written = Vec::new();
+        borrowed.write_le(&mut written);
+        assert_eq!(written, data);
+    }
+
+    #[test]
+    fn write_deeper_than_max_depth() {
+        let mut list = owned::NbtList::Empty;
+        for _ in 0..crate::common::MAX_DEPTH + 10 {
+            list = owned::NbtList::List(vec![list]);
+        }
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("list", list);
+        let nbt = owned::Nbt::Some(owned::BaseNbt::new("", compound));
+
+        let mut expected = vec![10, 0, 0, 9, 4, 0, b'l', b'i', b's', b't'];
+        for _ in 0..crate::common::MAX_DEPTH + 10 {
+            expected.extend_from_slice(&[9, 1, 0, 0, 0]);
+        }
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+        let mut data = Vec::new();
+        nbt.write_le(&mut data);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let mut data = [10, 0, 0, 11, 0, 0, 2, 0, 0, 0, 1, 0, 0];
+        assert_eq!(
+            borrow::read_le(&mut data).unwrap_err(),
+            crate::Error::UnexpectedEof
+        );
+    }
+}
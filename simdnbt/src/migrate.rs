@@ -0,0 +1,855 @@
+//! Upgrading NBT from older versions of Minecraft, based on its
+//! `DataVersion`.
+//!
+//! A [`Migrator`] has a list of [`Migration`]s, each of which upgrades data
+//! from a range of `DataVersion`s to the version at the end of the range.
+//! Migrations are made of steps that rename, move, remove, or convert the tags
+//! that match a pattern, or run arbitrary code on the whole compound. This
+//! crate doesn't come with any of the vanilla migrations, so you have to add
+//! the ones that you need.
+//!
+//! Patterns are [`NbtPath`]s that can also have wildcards: `*` matches every
+//! key in a compound and `[]` matches every element in a list. They always end
+//! with a key (or `*`), since they're for tags in compounds.
+//!
+//! ```
+//! use simdnbt::{
+//!     migrate::{Migration, Migrator},
+//!     owned::{NbtCompound, NbtList, NbtTag},
+//! };
+//!
+//! let migrator = Migrator::new()
+//!     .migration(
+//!         Migration::new("flatten chunks", 0..2844)
+//!             .move_tag("Level.Sections", "sections")
+//!             .move_tag("Level.xPos", "xPos"),
+//!     )
+//!     .migration(
+//!         Migration::new("lowercase section y", 2844..3000)
+//!             .rename("sections[].Y", "y")
+//!             .convert("sections[].y", |tag| tag.byte().map(|y| NbtTag::Int(y as i32))),
+//!     );
+//!
+//! let mut section = NbtCompound::new();
+//! section.insert("Y", 3i8);
+//! let mut level = NbtCompound::new();
+//! level.insert("Sections", NbtList::Compound(vec![section]));
+//! level.insert("xPos", 5);
+//! let mut chunk = NbtCompound::new();
+//! chunk.insert("DataVersion", 2586);
+//! chunk.insert("Level", level);
+//!
+//! let report = migrator.migrate(&mut chunk, 3000).unwrap();
+//! assert_eq!(report.applied, ["flatten chunks", "lowercase section y"]);
+//! assert_eq!(chunk.int("DataVersion"), Some(3000));
+//! assert_eq!(chunk.int("xPos"), Some(5));
+//! assert_eq!(chunk.list("sections").unwrap().compounds().unwrap()[0].int("y"), Some(3));
+//! ```
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::{
+    owned::{NbtCompound, NbtList, NbtTag},
+    path::{parse_pattern, PatternSegment},
+    NbtPath, PathSegment,
+};
+
+/// Applies [`Migration`]s to compounds based on their `DataVersion`. See the
+/// [module-level documentation](self).
+pub struct Migrator {
+    /// Sorted by the version that they migrate to.
+    migrations: Vec<Migration>,
+    version_path: Vec<PathSegment>,
+    default_version: Option<i32>,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Migrator {
+    /// Create a migrator with no migrations that reads the `DataVersion` tag
+    /// from the root compound.
+    pub fn new() -> Self {
+        Self {
+            migrations: Vec::new(),
+            version_path: vec![PathSegment::Key("DataVersion".to_owned())],
+            default_version: None,
+        }
+    }
+
+    /// Add a migration. Migrations are applied in order of the version that
+    /// they migrate to, and migrations to the same version are applied in the
+    /// order that they were added.
+    pub fn migration(mut self, migration: Migration) -> Self {
+        let position = self
+            .migrations
+            .partition_point(|other| other.versions.end <= migration.versions.end);
+        self.migrations.insert(position, migration);
+        self
+    }
+
+    /// Set the path to the `DataVersion` tag, like `Data.DataVersion` for
+    /// `level.dat`. It's `DataVersion` by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is invalid or doesn't end with a key.
+    pub fn version_path(mut self, path: &str) -> Self {
+        let path = path.parse::<NbtPath>().expect("invalid version path");
+        assert!(
+            matches!(path.segments().last(), Some(PathSegment::Key(_))),
+            "the version path must end with a key"
+        );
+        self.version_path = path.segments().to_vec();
+        self
+    }
+
+    /// Set the `DataVersion` that's assumed for data that doesn't have one,
+    /// like worlds from before 1.9. Without this, migrating data with no
+    /// `DataVersion` returns [`MigrateError::MissingDataVersion`].
+    pub fn default_version(mut self, version: i32) -> Self {
+        self.default_version = Some(version);
+        self
+    }
+
+    /// Apply every migration that's needed to bring the compound up to the
+    /// target `DataVersion`, and then set its `DataVersion` to the target.
+    ///
+    /// Migrations that go past the target aren't applied.
+    pub fn migrate(
+        &self,
+        nbt: &mut NbtCompound,
+        target: i32,
+    ) -> Result<MigrationReport, MigrateError> {
+        let original = get_mut(nbt, &self.version_path).and_then(|tag| tag.int());
+        let mut version = original
+            .or(self.default_version)
+            .ok_or(MigrateError::MissingDataVersion)?;
+        if version > target {
+            return Err(MigrateError::TooNew { version, target });
+        }
+
+        let mut report = MigrationReport {
+            from_version: original,
+            to_version: target,
+            applied: Vec::new(),
+            changes: Vec::new(),
+        };
+        for migration in &self.migrations {
+            if migration.versions.end > target {
+                break;
+            }
+            if !migration.versions.contains(&version) {
+                continue;
+            }
+            migration.apply(nbt, &mut report.changes);
+            report.applied.push(migration.name.clone());
+            version = migration.versions.end;
+        }
+
+        let (key, parent) = self.version_path.split_last().unwrap();
+        if let (PathSegment::Key(key), Some(parent)) = (key, compound_mut_or_insert(nbt, parent)) {
+            set(parent, key, NbtTag::Int(target));
+        }
+        Ok(report)
+    }
+
+    /// Migrate every compound to the target `DataVersion`, like the chunks in
+    /// a region. See [`Self::migrate`].
+    pub fn migrate_all<'a>(
+        &self,
+        nbts: impl IntoIterator<Item = &'a mut NbtCompound>,
+        target: i32,
+    ) -> Vec<Result<MigrationReport, MigrateError>> {
+        nbts.into_iter()
+            .map(|nbt| self.migrate(nbt, target))
+            .collect()
+    }
+}
+
+/// An error from [`Migrator::migrate`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MigrateError {
+    #[error("The data doesn't have a DataVersion")]
+    MissingDataVersion,
+    #[error("The data is from DataVersion {version}, which is newer than the target {target}")]
+    TooNew { version: i32, target: i32 },
+}
+
+/// What a call to [`Migrator::migrate`] did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// The `DataVersion` that the data had, or `None` if it didn't have one
+    /// and the [default version](Migrator::default_version) was used.
+    pub from_version: Option<i32>,
+    pub to_version: i32,
+    /// The names of the migrations that were applied, in order.
+    pub applied: Vec<String>,
+    /// Every tag that was changed by a migration step, except for the ones
+    /// changed by [custom](Migration::custom) steps.
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// The name of the migration that made the change.
+    pub migration: String,
+    /// The path to the tag before it was changed.
+    pub path: NbtPath,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Renamed {
+        to: NbtPath,
+    },
+    Moved {
+        to: NbtPath,
+    },
+    Removed,
+    Converted,
+    /// The tag matched the pattern but couldn't be changed, so it was left
+    /// alone.
+    Skipped {
+        reason: &'static str,
+    },
+}
+
+/// A set of steps that upgrade data from a range of `DataVersion`s to the
+/// version at the end of the range. The steps are applied in the order that
+/// they were added.
+pub struct Migration {
+    name: String,
+    versions: Range<i32>,
+    steps: Vec<Step>,
+}
+
+type Convert = Box<dyn Fn(&NbtTag) -> Option<NbtTag> + Send + Sync>;
+type Custom = Box<dyn Fn(&mut NbtCompound) + Send + Sync>;
+
+enum Step {
+    Move {
+        from: Vec<PatternSegment>,
+        to: Vec<PatternSegment>,
+        /// Whether this is from [`Migration::rename`], which is only used for
+        /// the report.
+        rename: bool,
+    },
+    Remove(Vec<PatternSegment>),
+    Convert(Vec<PatternSegment>, Convert),
+    Custom(Custom),
+}
+
+impl Migration {
+    /// Create a migration for data with a `DataVersion` in the range, which
+    /// upgrades it to `versions.end`.
+    pub fn new(name: impl Into<String>, versions: Range<i32>) -> Self {
+        Self {
+            name: name.into(),
+            versions,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn versions(&self) -> Range<i32> {
+        self.versions.clone()
+    }
+
+    /// Rename every tag that matches the pattern, keeping it in the same
+    /// compound. A tag that already has the new name is replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid or doesn't end with a key.
+    pub fn rename(mut self, pattern: &str, name: impl Into<String>) -> Self {
+        let from = pattern_for_tag(pattern);
+        let mut to = from.clone();
+        *to.last_mut().unwrap() = PatternSegment::Key(name.into());
+        // the pattern can't end with a wildcard, since the renamed tags
+        // wouldn't be able to fill it
+        assert!(
+            !matches!(from.last(), Some(PatternSegment::AnyKey)),
+            "can't rename every tag in a compound to the same name"
+        );
+        self.steps.push(Step::Move {
+            from,
+            to,
+            rename: true,
+        });
+        self
+    }
+
+    /// Move every tag that matches the `from` pattern to the `to` path,
+    /// creating compounds along the way if they don't exist. A tag that's
+    /// already at the destination is replaced.
+    ///
+    /// Wildcards in `to` are filled in with what the wildcards in `from`
+    /// matched, in order, so `Level.Sections[].Palette` can be moved to
+    /// `sections[].block_states.palette`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either pattern is invalid or doesn't end with a key, or if
+    /// the wildcards in `to` aren't the same as the first ones in `from`.
+    pub fn move_tag(mut self, from: &str, to: &str) -> Self {
+        let from = pattern_for_tag(from);
+        let to = pattern_for_tag(to);
+        let wildcards = |pattern: &[PatternSegment]| {
+            pattern
+                .iter()
+                .filter(|segment| {
+                    matches!(segment, PatternSegment::AnyKey | PatternSegment::AnyIndex)
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert!(
+            wildcards(&from).starts_with(&wildcards(&to)),
+            "the wildcards in the destination must match the ones in the source"
+        );
+        self.steps.push(Step::Move {
+            from,
+            to,
+            rename: false,
+        });
+        self
+    }
+
+    /// Remove every tag that matches the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid or doesn't end with a key.
+    pub fn remove(mut self, pattern: &str) -> Self {
+        self.steps.push(Step::Remove(pattern_for_tag(pattern)));
+        self
+    }
+
+    /// Replace every tag that matches the pattern with the result of the
+    /// function, like to change its type. If the function returns `None` then
+    /// the tag is left alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid or doesn't end with a key.
+    pub fn convert(
+        mut self,
+        pattern: &str,
+        convert: impl Fn(&NbtTag) -> Option<NbtTag> + Send + Sync + 'static,
+    ) -> Self {
+        self.steps
+            .push(Step::Convert(pattern_for_tag(pattern), Box::new(convert)));
+        self
+    }
+
+    /// Run the function on the whole compound, for changes that the other
+    /// steps can't do.
+    pub fn custom(mut self, f: impl Fn(&mut NbtCompound) + Send + Sync + 'static) -> Self {
+        self.steps.push(Step::Custom(Box::new(f)));
+        self
+    }
+
+    fn apply(&self, nbt: &mut NbtCompound, changes: &mut Vec<Change>) {
+        for step in &self.steps {
+            let pattern = match step {
+                Step::Move { from, .. } => from,
+                Step::Remove(pattern) | Step::Convert(pattern, _) => pattern,
+                Step::Custom(f) => {
+                    f(nbt);
+                    continue;
+                }
+            };
+
+            let mut matches = Vec::new();
+            find_matches(
+                Node::Compound(nbt),
+                pattern,
+                &mut Vec::new(),
+                &mut Vec::new(),
+                &mut matches,
+            );
+            // the matches are in depth-first order, so going backwards changes
+            // nested tags before the compounds that they're in
+            for Match { path, captures } in matches.into_iter().rev() {
+                let kind = match step {
+                    Step::Move { to, rename, .. } => {
+                        let to = fill_wildcards(to, &captures);
+                        move_tag(nbt, &path, &to).map(|()| {
+                            let to = to_nbt_path(to);
+                            if *rename {
+                                ChangeKind::Renamed { to }
+                            } else {
+                                ChangeKind::Moved { to }
+                            }
+                        })
+                    }
+                    Step::Remove(_) => {
+                        let (key, parent) = split_path(&path);
+                        compound_mut(nbt, parent).and_then(|parent| parent.remove(key));
+                        Ok(ChangeKind::Removed)
+                    }
+                    Step::Convert(_, convert) => {
+                        let tag = get_mut(nbt, &path).expect("the tag was matched");
+                        match convert(tag) {
+                            Some(converted) => {
+                                *tag = converted;
+                                Ok(ChangeKind::Converted)
+                            }
+                            None => Err("the tag couldn't be converted"),
+                        }
+                    }
+                    Step::Custom(_) => unreachable!(),
+                };
+                changes.push(Change {
+                    migration: self.name.clone(),
+                    path: to_nbt_path(path),
+                    kind: kind.unwrap_or_else(|reason| ChangeKind::Skipped { reason }),
+                });
+            }
+        }
+    }
+}
+
+fn pattern_for_tag(pattern: &str) -> Vec<PatternSegment> {
+    let pattern = parse_pattern(pattern).expect("invalid pattern");
+    assert!(
+        matches!(
+            pattern.last(),
+            Some(PatternSegment::Key(_) | PatternSegment::AnyKey)
+        ),
+        "patterns must end with a key"
+    );
+    pattern
+}
+
+fn fill_wildcards(pattern: &[PatternSegment], captures: &[PathSegment]) -> Vec<PathSegment> {
+    let mut captures = captures.iter();
+    pattern
+        .iter()
+        .map(|segment| match segment {
+            PatternSegment::Key(key) => PathSegment::Key(key.clone()),
+            PatternSegment::Index(index) => PathSegment::Index(*index),
+            PatternSegment::AnyKey | PatternSegment::AnyIndex => {
+                captures.next().expect("checked in move_tag").clone()
+            }
+        })
+        .collect()
+}
+
+fn to_nbt_path(segments: Vec<PathSegment>) -> NbtPath {
+    segments.into_iter().fold(NbtPath::new(), NbtPath::segment)
+}
+
+/// Split a path to a tag in a compound into the tag's name and the path to
+/// the compound.
+fn split_path(path: &[PathSegment]) -> (&str, &[PathSegment]) {
+    match path.split_last() {
+        Some((PathSegment::Key(key), parent)) => (key, parent),
+        _ => unreachable!("patterns end with a key"),
+    }
+}
+
+/// Move the tag at `from` to `to`, or put it back if there's something other
+/// than a compound at `to`.
+fn move_tag(
+    nbt: &mut NbtCompound,
+    from: &[PathSegment],
+    to: &[PathSegment],
+) -> Result<(), &'static str> {
+    if from == to {
+        return Ok(());
+    }
+    let (from_key, from_parent) = split_path(from);
+    let (to_key, to_parent) = split_path(to);
+
+    let tag = compound_mut(nbt, from_parent)
+        .and_then(|parent| parent.remove(from_key))
+        .expect("the tag was matched");
+    match compound_mut_or_insert(nbt, to_parent) {
+        Some(parent) => {
+            set(parent, to_key, tag);
+            Ok(())
+        }
+        None => {
+            // the path to the tag still exists, since only the tag was removed
+            compound_mut(nbt, from_parent)
+                .unwrap()
+                .insert(from_key, tag);
+            Err("the destination isn't in a compound")
+        }
+    }
+}
+
+/// Replace the tag with the name, or add it if there isn't one.
+fn set(compound: &mut NbtCompound, key: &str, tag: NbtTag) {
+    match compound.get_mut(key) {
+        Some(existing) => *existing = tag,
+        None => compound.insert(key, tag),
+    }
+}
+
+struct Match {
+    path: Vec<PathSegment>,
+    /// What each wildcard in the pattern matched.
+    captures: Vec<PathSegment>,
+}
+
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Compound(&'a NbtCompound),
+    List(&'a NbtList),
+    Other,
+}
+impl<'a> Node<'a> {
+    fn of(tag: &'a NbtTag) -> Self {
+        match tag {
+            NbtTag::Compound(compound) => Node::Compound(compound),
+            NbtTag::List(list) => Node::List(list),
+            _ => Node::Other,
+        }
+    }
+
+    fn element(list: &'a NbtList, index: usize) -> Option<Self> {
+        match list {
+            NbtList::Compound(compounds) => compounds.get(index).map(Node::Compound),
+            NbtList::List(lists) => lists.get(index).map(Node::List),
+            _ if index < list.len() => Some(Node::Other),
+            _ => None,
+        }
+    }
+}
+
+fn find_matches(
+    node: Node,
+    pattern: &[PatternSegment],
+    path: &mut Vec<PathSegment>,
+    captures: &mut Vec<PathSegment>,
+    matches: &mut Vec<Match>,
+) {
+    let Some((segment, rest)) = pattern.split_first() else {
+        matches.push(Match {
+            path: path.clone(),
+            captures: captures.clone(),
+        });
+        return;
+    };
+
+    let mut visit = |segment: PathSegment, child: Node, capture: bool| {
+        if capture {
+            captures.push(segment.clone());
+        }
+        path.push(segment);
+        find_matches(child, rest, path, captures, matches);
+        path.pop();
+        if capture {
+            captures.pop();
+        }
+    };
+    match (segment, node) {
+        (PatternSegment::Key(key), Node::Compound(compound)) => {
+            if let Some(tag) = compound.get(key) {
+                visit(PathSegment::Key(key.clone()), Node::of(tag), false);
+            }
+        }
+        (PatternSegment::AnyKey, Node::Compound(compound)) => {
+            for (key, tag) in compound.iter() {
+                visit(
+                    PathSegment::Key(key.to_str().into_owned()),
+                    Node::of(tag),
+                    true,
+                );
+            }
+        }
+        (PatternSegment::Index(index), Node::List(list)) => {
+            if let Some(element) = Node::element(list, *index) {
+                visit(PathSegment::Index(*index), element, false);
+            }
+        }
+        (PatternSegment::AnyIndex, Node::List(list)) => {
+            for index in 0..list.len() {
+                let element = Node::element(list, index).unwrap();
+                visit(PathSegment::Index(index), element, true);
+            }
+        }
+        _ => {}
+    }
+}
+
+enum NodeMut<'a> {
+    Compound(&'a mut NbtCompound),
+    List(&'a mut NbtList),
+    Other,
+}
+impl<'a> NodeMut<'a> {
+    fn of(tag: &'a mut NbtTag) -> Self {
+        match tag {
+            NbtTag::Compound(compound) => NodeMut::Compound(compound),
+            NbtTag::List(list) => NodeMut::List(list),
+            _ => NodeMut::Other,
+        }
+    }
+
+    /// Go to the child at the segment. If `insert` is true then missing keys
+    /// are added as empty compounds.
+    fn child(self, segment: &PathSegment, insert: bool) -> Option<Self> {
+        Some(match (segment, self) {
+            (PathSegment::Key(key), NodeMut::Compound(compound)) => {
+                if insert && !compound.contains(key) {
+                    compound.insert(key.as_str(), NbtCompound::new());
+                }
+                NodeMut::of(compound.get_mut(key)?)
+            }
+            (PathSegment::Index(index), NodeMut::List(list)) => match list {
+                NbtList::Compound(compounds) => NodeMut::Compound(compounds.get_mut(*index)?),
+                NbtList::List(lists) => NodeMut::List(lists.get_mut(*index)?),
+                _ => return None,
+            },
+            _ => return None,
+        })
+    }
+}
+
+fn get_mut<'a>(nbt: &'a mut NbtCompound, path: &[PathSegment]) -> Option<&'a mut NbtTag> {
+    let (key, parent) = match path.split_last()? {
+        (PathSegment::Key(key), parent) => (key, parent),
+        _ => return None,
+    };
+    compound_mut(nbt, parent)?.get_mut(key)
+}
+
+fn compound_mut<'a>(nbt: &'a mut NbtCompound, path: &[PathSegment]) -> Option<&'a mut NbtCompound> {
+    walk_mut(nbt, path, false)
+}
+
+fn compound_mut_or_insert<'a>(
+    nbt: &'a mut NbtCompound,
+    path: &[PathSegment],
+) -> Option<&'a mut NbtCompound> {
+    walk_mut(nbt, path, true)
+}
+
+fn walk_mut<'a>(
+    nbt: &'a mut NbtCompound,
+    path: &[PathSegment],
+    insert: bool,
+) -> Option<&'a mut NbtCompound> {
+    let mut node = NodeMut::Compound(nbt);
+    for segment in path {
+        node = node.child(segment, insert)?;
+    }
+    match node {
+        NodeMut::Compound(compound) => Some(compound),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(version: Option<i32>) -> NbtCompound {
+        let sections = (0..3)
+            .map(|y| {
+                let mut palette = NbtCompound::new();
+                palette.insert("Name", "minecraft:stone");
+                let mut section = NbtCompound::new();
+                section.insert("Y", y as i8);
+                section.insert("Palette", NbtList::Compound(vec![palette]));
+                section
+            })
+            .collect();
+        let mut level = NbtCompound::new();
+        level.insert("Sections", NbtList::Compound(sections));
+        level.insert("Status", "full");
+        let mut chunk = NbtCompound::new();
+        if let Some(version) = version {
+            chunk.insert("DataVersion", version);
+        }
+        chunk.insert("Level", level);
+        chunk
+    }
+
+    fn migrator() -> Migrator {
+        Migrator::new()
+            .migration(
+                Migration::new("status", 2000..2500)
+                    .convert("Level.Status", |tag| {
+                        let status = tag.string()?.to_str();
+                        Some(NbtTag::from(format!("minecraft:{status}").as_str()))
+                    })
+                    .convert("Level.Missing", |tag| Some(tag.clone())),
+            )
+            // added out of order on purpose
+            .migration(Migration::new("rename y", 2844..3000).rename("sections[].Y", "y"))
+            .migration(
+                Migration::new("flatten", 2500..2844)
+                    .move_tag(
+                        "Level.Sections[].Palette",
+                        "Level.Sections[].block_states.palette",
+                    )
+                    .move_tag("Level.Sections", "sections")
+                    .move_tag("Level.Status", "Status")
+                    .remove("Level"),
+            )
+    }
+
+    #[test]
+    fn migrate_chunk() {
+        let mut nbt = chunk(Some(2100));
+        let report = migrator().migrate(&mut nbt, 3000).unwrap();
+        assert_eq!(report.from_version, Some(2100));
+        assert_eq!(report.applied, ["status", "flatten", "rename y"]);
+
+        assert_eq!(nbt.int("DataVersion"), Some(3000));
+        assert_eq!(nbt.string("Status").unwrap().to_str(), "minecraft:full");
+        assert!(nbt.get("Level").is_none());
+        let sections = nbt.list("sections").unwrap().compounds().unwrap();
+        assert_eq!(sections.len(), 3);
+        for (y, section) in sections.iter().enumerate() {
+            assert_eq!(section.byte("y"), Some(y as i8));
+            assert!(section.get("Y").is_none());
+            assert!(section.get("Palette").is_none());
+            let palette = section
+                .compound("block_states")
+                .unwrap()
+                .list("palette")
+                .unwrap();
+            assert_eq!(palette.len(), 1);
+        }
+
+        let renamed = report
+            .changes
+            .iter()
+            .filter(|change| matches!(change.kind, ChangeKind::Renamed { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(renamed.len(), 3);
+        assert_eq!(renamed[2].path.to_string(), "sections[0].Y");
+        assert_eq!(
+            renamed[2].kind,
+            ChangeKind::Renamed {
+                to: "sections[0].y".parse().unwrap()
+            }
+        );
+        let removed = report
+            .changes
+            .iter()
+            .filter(|change| change.kind == ChangeKind::Removed)
+            .collect::<Vec<_>>();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].path.to_string(), "Level");
+    }
+
+    #[test]
+    fn wildcard_keys() {
+        let migrator = Migrator::new().migration(
+            Migration::new("game rules", 0..1)
+                .convert("GameRules.*", |tag| {
+                    Some(NbtTag::Byte((tag.string()?.to_str() == "true") as i8))
+                })
+                .move_tag("GameRules.*", "rules.*.value"),
+        );
+        let mut rules = NbtCompound::new();
+        rules.insert("doFireTick", "true");
+        rules.insert("keepInventory", "false");
+        let mut nbt = NbtCompound::new();
+        nbt.insert("GameRules", rules);
+
+        migrator.migrate(&mut nbt, 0).unwrap_err();
+        let report = migrator.default_version(0).migrate(&mut nbt, 1).unwrap();
+        assert_eq!(report.changes.len(), 4);
+        let rules = nbt.compound("rules").unwrap();
+        assert_eq!(rules.compound("doFireTick").unwrap().byte("value"), Some(1));
+        assert_eq!(
+            rules.compound("keepInventory").unwrap().byte("value"),
+            Some(0)
+        );
+        assert!(nbt.compound("GameRules").unwrap().is_empty());
+    }
+
+    #[test]
+    fn versions() {
+        // only the migrations after the data's version are applied
+        let mut nbt = chunk(Some(2600));
+        let report = migrator().migrate(&mut nbt, 2844).unwrap();
+        assert_eq!(report.applied, ["flatten"]);
+        assert_eq!(nbt.string("Status").unwrap().to_str(), "full");
+        assert_eq!(nbt.int("DataVersion"), Some(2844));
+
+        // and migrations past the target aren't
+        let mut nbt = chunk(Some(2600));
+        let report = migrator().migrate(&mut nbt, 2900).unwrap();
+        assert_eq!(report.applied, ["flatten"]);
+        assert_eq!(nbt.int("DataVersion"), Some(2900));
+
+        assert_eq!(
+            migrator().migrate(&mut chunk(Some(3001)), 3000),
+            Err(MigrateError::TooNew {
+                version: 3001,
+                target: 3000
+            })
+        );
+        assert_eq!(
+            migrator().migrate(&mut chunk(None), 3000),
+            Err(MigrateError::MissingDataVersion)
+        );
+
+        let mut nbt = chunk(None);
+        let report = migrator()
+            .default_version(2500)
+            .migrate(&mut nbt, 3000)
+            .unwrap();
+        assert_eq!(report.from_version, None);
+        assert_eq!(report.applied, ["flatten", "rename y"]);
+        assert_eq!(nbt.int("DataVersion"), Some(3000));
+    }
+
+    #[test]
+    fn skipped_and_custom() {
+        let migrator = Migrator::new()
+            .version_path("Data.DataVersion")
+            .default_version(0)
+            .migration(
+                Migration::new("test", 0..1)
+                    .move_tag("Data.Name", "Data.Number.Name")
+                    .convert("Data.Number", |tag| tag.string().map(|_| NbtTag::Int(0)))
+                    .custom(|nbt| nbt.insert("Custom", true)),
+            );
+        let mut data = NbtCompound::new();
+        data.insert("Name", "a");
+        data.insert("Number", 1);
+        let mut nbt = NbtCompound::new();
+        nbt.insert("Data", data);
+
+        let report = migrator.migrate(&mut nbt, 1).unwrap();
+        assert_eq!(
+            report
+                .changes
+                .iter()
+                .map(|change| &change.kind)
+                .collect::<Vec<_>>(),
+            [
+                &ChangeKind::Skipped {
+                    reason: "the destination isn't in a compound"
+                },
+                &ChangeKind::Skipped {
+                    reason: "the tag couldn't be converted"
+                }
+            ]
+        );
+        let data = nbt.compound("Data").unwrap();
+        assert_eq!(data.string("Name").unwrap().to_str(), "a");
+        assert_eq!(data.int("Number"), Some(1));
+        assert_eq!(data.int("DataVersion"), Some(1));
+        assert_eq!(nbt.byte("Custom"), Some(1));
+    }
+}
@@ -1,26 +1,52 @@
 //! The string representation used in NBT.
 
+#[cfg(feature = "nightly")]
+use std::simd::prelude::*;
 use std::{
     borrow::{Borrow, Cow},
-    fmt, mem,
-    ops::Deref,
-    simd::prelude::*,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::{AddAssign, Deref},
+    sync::Arc,
 };
 
 use simd_cesu8::mutf8;
 
 /// A MUTF-8 string slice. This is how strings are represented internally in
 /// NBT.
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Hash)]
 pub struct Mutf8Str {
     pub(crate) slice: [u8],
 }
 /// An owned MUTF-8 string.
-#[derive(Eq, PartialEq, Clone, Default)]
+///
+/// The bytes are kept exactly as they were read, even if they aren't valid
+/// MUTF-8. Use this instead of `String` for fields in structs with
+/// `#[derive(Deserialize, Serialize)]` if strings have to be written back
+/// byte-for-byte.
+///
+/// Strings made by an [`Interner`](crate::owned::Interner) share their bytes
+/// with every other string that has the same contents. They're copied the
+/// first time they're modified.
+#[derive(Clone, Default)]
 pub struct Mutf8String {
-    pub(crate) vec: Vec<u8>,
+    pub(crate) buffer: Buffer,
 }
 
+#[derive(Clone)]
+pub(crate) enum Buffer {
+    Owned(Vec<u8>),
+    Shared(Arc<[u8]>),
+}
+impl Default for Buffer {
+    #[inline]
+    fn default() -> Self {
+        Buffer::Owned(Vec::new())
+    }
+}
+
+#[cfg(feature = "nightly")]
 #[inline]
 fn is_plain_ascii(slice: &[u8]) -> bool {
     let mut is_plain_ascii = true;
@@ -78,6 +104,32 @@ fn is_plain_ascii(slice: &[u8]) -> bool {
     is_plain_ascii
 }
 
+#[cfg(not(feature = "nightly"))]
+#[inline]
+fn is_plain_ascii(slice: &[u8]) -> bool {
+    slice.is_ascii()
+}
+
+/// How null characters are encoded in MUTF-8.
+const MUTF8_NULL: [u8; 2] = [0xc0, 0x80];
+
+/// Replace the plain null bytes in valid CESU-8 data with the MUTF-8 encoding
+/// of a null character.
+fn replace_nulls(bytes: &[u8]) -> Cow<Mutf8Str> {
+    if !bytes.contains(&0) {
+        return Cow::Borrowed(Mutf8Str::from_slice(bytes));
+    }
+    let mut vec = Vec::with_capacity(bytes.len() + 1);
+    for &byte in bytes {
+        if byte == 0 {
+            vec.extend_from_slice(&MUTF8_NULL);
+        } else {
+            vec.push(byte);
+        }
+    }
+    Cow::Owned(Mutf8String::from_vec(vec))
+}
+
 impl Mutf8Str {
     // we can't implement FromStr on Cow<Mutf8Str>
     #[allow(clippy::should_implement_trait)]
@@ -85,12 +137,14 @@ impl Mutf8Str {
     pub fn from_str(s: &str) -> Cow<Mutf8Str> {
         match mutf8::encode(s) {
             Cow::Borrowed(slice) => Cow::Borrowed(Mutf8Str::from_slice(slice)),
-            Cow::Owned(vec) => Cow::Owned(Mutf8String { vec }),
+            Cow::Owned(vec) => Cow::Owned(Mutf8String::from_vec(vec)),
         }
     }
 
     /// Try to convert this MUTF-8 string into a UTF-8 string. If the data isn't
-    /// valid MUTF-8, it'll return an empty string without erroring.
+    /// valid MUTF-8, it'll return an empty string without erroring. Use
+    /// [`Self::try_to_str`] to get the error or [`Self::to_string_lossy`] to
+    /// keep the valid parts.
     #[inline]
     pub fn to_str(&self) -> Cow<str> {
         // fast check to skip if none of the bytes have the top bit set.
@@ -107,11 +161,75 @@ impl Mutf8Str {
         }
     }
 
+    /// Convert this MUTF-8 string into a UTF-8 string, or return an error if
+    /// the data isn't valid MUTF-8.
+    ///
+    /// Unlike [`Self::to_str`], this doesn't allow plain null bytes or 4-byte
+    /// UTF-8 characters, and supplementary characters must be encoded as
+    /// six-byte surrogate pairs. Unpaired surrogates are always an error.
+    #[inline]
+    pub fn try_to_str(&self) -> Result<Cow<str>, simd_cesu8::DecodingError> {
+        mutf8::decode_strict(&self.slice)
+    }
+
+    /// Convert this MUTF-8 string into a UTF-8 string, replacing invalid data
+    /// (like unpaired surrogates) with `U+FFFD`.
     #[inline]
     pub fn to_string_lossy(&self) -> Cow<str> {
         mutf8::decode_lossy(&self.slice)
     }
 
+    /// Convert CESU-8 data into a MUTF-8 string, or return an error if it
+    /// isn't valid CESU-8.
+    ///
+    /// CESU-8 is the same as MUTF-8 except that null characters are encoded as
+    /// a single zero byte, so the data is only copied if it has null
+    /// characters.
+    ///
+    /// ```
+    /// # use simdnbt::Mutf8Str;
+    /// let cesu8 = [b'a', 0, 0xed, 0xa0, 0x81, 0xed, 0xb0, 0x81];
+    /// let string = Mutf8Str::from_cesu8(&cesu8).unwrap();
+    /// assert_eq!(string.to_str(), "a\0\u{10401}");
+    /// assert_eq!(string.to_cesu8(), &cesu8[..]);
+    /// ```
+    pub fn from_cesu8(bytes: &[u8]) -> Result<Cow<Mutf8Str>, simd_cesu8::DecodingError> {
+        simd_cesu8::decode_strict(bytes)?;
+        Ok(replace_nulls(bytes))
+    }
+
+    /// Convert CESU-8 data into a MUTF-8 string like [`Self::from_cesu8`], but
+    /// replace invalid data with `U+FFFD` instead of returning an error.
+    pub fn from_cesu8_lossy(bytes: &[u8]) -> Cow<Mutf8Str> {
+        match simd_cesu8::decode_lossy(bytes) {
+            // the data was valid, so it can be kept as-is
+            Cow::Borrowed(_) => replace_nulls(bytes),
+            Cow::Owned(string) => Cow::Owned(Mutf8String::from_string(string)),
+        }
+    }
+
+    /// Convert this string to CESU-8, which is the same as MUTF-8 except that
+    /// null characters are encoded as a single zero byte. The data is only
+    /// copied if it has null characters.
+    pub fn to_cesu8(&self) -> Cow<[u8]> {
+        let bytes = &self.slice;
+        if !bytes.windows(2).any(|pair| pair == MUTF8_NULL) {
+            return Cow::Borrowed(bytes);
+        }
+        let mut cesu8 = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i..].starts_with(&MUTF8_NULL) {
+                cesu8.push(0);
+                i += 2;
+            } else {
+                cesu8.push(bytes[i]);
+                i += 1;
+            }
+        }
+        Cow::Owned(cesu8)
+    }
+
     /// Convert a slice of bytes into a Mutf8Str. This is safe because it's only
     /// checked to be valid MUTF-8 while being converted to UTF-8.
     #[inline]
@@ -160,9 +278,7 @@ impl ToOwned for Mutf8Str {
 
     #[inline]
     fn to_owned(&self) -> Self::Owned {
-        Mutf8String {
-            vec: self.slice.to_vec(),
-        }
+        Mutf8String::from_vec(self.slice.to_vec())
     }
 }
 impl Borrow<Mutf8Str> for Mutf8String {
@@ -174,34 +290,38 @@ impl Borrow<Mutf8Str> for Mutf8String {
 
 impl Mutf8String {
     pub fn new() -> Self {
-        Self { vec: Vec::new() }
+        Self::from_vec(Vec::new())
     }
 
     #[inline]
     pub fn as_str(&self) -> &Mutf8Str {
-        Mutf8Str::from_slice(self.vec.as_slice())
+        let bytes = match &self.buffer {
+            Buffer::Owned(vec) => vec.as_slice(),
+            Buffer::Shared(bytes) => bytes,
+        };
+        Mutf8Str::from_slice(bytes)
     }
 
     /// Convert this MUTF-8 string into a UTF-8 string. If the data isn't
     /// valid MUTF-8, it'll return an empty string without erroring.
     #[inline]
     pub fn into_string(self) -> String {
-        if is_plain_ascii(&self.vec) {
-            // SAFETY: &[u8] and &str are the same layout.
-            unsafe { String::from_utf8_unchecked(self.vec) }
+        if is_plain_ascii(&self.slice) {
+            // SAFETY: Plain ASCII is always valid UTF-8.
+            unsafe { String::from_utf8_unchecked(self.into_vec()) }
         } else {
-            mutf8::decode(&self.vec).unwrap_or_default().to_string()
+            mutf8::decode(&self.slice).unwrap_or_default().to_string()
         }
     }
 
     /// Try to convert this MUTF-8 string into a UTF-8 string.
     #[inline]
     pub fn try_into_string(self) -> Result<String, simd_cesu8::DecodingError> {
-        if is_plain_ascii(&self.vec) {
-            // SAFETY: &[u8] and &str are the same layout.
-            Ok(unsafe { String::from_utf8_unchecked(self.vec) })
+        if is_plain_ascii(&self.slice) {
+            // SAFETY: Plain ASCII is always valid UTF-8.
+            Ok(unsafe { String::from_utf8_unchecked(self.into_vec()) })
         } else {
-            mutf8::decode(&self.vec).map(|cow| cow.into_owned())
+            mutf8::decode(&self.slice).map(|cow| cow.into_owned())
         }
     }
 
@@ -212,9 +332,133 @@ impl Mutf8String {
 
     #[inline]
     pub fn from_vec(vec: Vec<u8>) -> Mutf8String {
-        Self { vec }
+        Self {
+            buffer: Buffer::Owned(vec),
+        }
+    }
+
+    /// Get the bytes of the string, copying them if they're shared.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.buffer {
+            Buffer::Owned(vec) => vec,
+            Buffer::Shared(bytes) => bytes.to_vec(),
+        }
+    }
+
+    /// Whether the bytes of this string are shared with other strings, which
+    /// is the case for strings made by an [`Interner`](crate::owned::Interner).
+    #[inline]
+    pub fn is_shared(&self) -> bool {
+        matches!(self.buffer, Buffer::Shared(_))
+    }
+
+    /// Make a string that shares its bytes with the other strings that are
+    /// made from the same `Arc`.
+    #[inline]
+    pub(crate) fn from_shared(bytes: Arc<[u8]>) -> Mutf8String {
+        Self {
+            buffer: Buffer::Shared(bytes),
+        }
+    }
+
+    /// Get the bytes so they can be modified, copying them first if they're
+    /// shared.
+    #[inline]
+    fn vec_mut(&mut self) -> &mut Vec<u8> {
+        if let Buffer::Shared(bytes) = &self.buffer {
+            self.buffer = Buffer::Owned(bytes.to_vec());
+        }
+        match &mut self.buffer {
+            Buffer::Owned(vec) => vec,
+            Buffer::Shared(_) => unreachable!(),
+        }
+    }
+
+    /// Convert bytes that are supposed to be UTF-8 into a MUTF-8 string,
+    /// replacing invalid data with `U+FFFD` like [`String::from_utf8_lossy`].
+    #[inline]
+    pub fn from_utf8_lossy(bytes: &[u8]) -> Mutf8String {
+        Self::from(String::from_utf8_lossy(bytes))
+    }
+
+    /// Append a UTF-8 string to the end of this string.
+    ///
+    /// ```
+    /// # use simdnbt::Mutf8String;
+    /// let mut lore = Mutf8String::from("Damage: ");
+    /// lore.push_str("7");
+    /// lore.push_char('\u{2694}');
+    /// lore += " total";
+    /// assert_eq!(lore.to_str(), "Damage: 7\u{2694} total");
+    /// ```
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.vec_mut().extend_from_slice(&mutf8::encode(s));
+    }
+
+    /// Append a MUTF-8 string to the end of this string without converting it.
+    #[inline]
+    pub fn push_mutf8_str(&mut self, s: &Mutf8Str) {
+        self.vec_mut().extend_from_slice(&s.slice);
+    }
+
+    #[inline]
+    pub fn push_char(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Remove everything from the string, keeping its allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        match &mut self.buffer {
+            Buffer::Owned(vec) => vec.clear(),
+            Buffer::Shared(_) => self.buffer = Buffer::Owned(Vec::new()),
+        }
+    }
+}
+impl AddAssign<&str> for Mutf8String {
+    #[inline]
+    fn add_assign(&mut self, s: &str) {
+        self.push_str(s);
+    }
+}
+impl AddAssign<&Mutf8Str> for Mutf8String {
+    #[inline]
+    fn add_assign(&mut self, s: &Mutf8Str) {
+        self.push_mutf8_str(s);
+    }
+}
+/// Lets strings be built with `write!`, which is useful for formatting numbers
+/// into them.
+impl fmt::Write for Mutf8String {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push_char(c);
+        Ok(())
+    }
+}
+impl PartialEq for Mutf8String {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl Eq for Mutf8String {}
+// this has to hash the same way as `Mutf8Str` for `Borrow` to work
+impl Hash for Mutf8String {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
     }
 }
+
 impl Deref for Mutf8String {
     type Target = Mutf8Str;
 
@@ -244,6 +488,52 @@ impl Default for &Mutf8Str {
     }
 }
 
+impl From<Cow<'_, str>> for Mutf8String {
+    #[inline]
+    fn from(s: Cow<'_, str>) -> Self {
+        match s {
+            Cow::Borrowed(s) => Self::from(s),
+            Cow::Owned(s) => Self::from_string(s),
+        }
+    }
+}
+impl From<char> for Mutf8String {
+    #[inline]
+    fn from(c: char) -> Self {
+        Self::from(&*c.encode_utf8(&mut [0; 4]))
+    }
+}
+impl FromIterator<char> for Mutf8String {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Self::from_string(String::from_iter(iter))
+    }
+}
+/// Convert bytes into a `Mutf8String`, returning an error if they aren't
+/// valid MUTF-8. Use [`Mutf8String::from_vec`] if you don't need validation.
+impl TryFrom<Vec<u8>> for Mutf8String {
+    type Error = simd_cesu8::DecodingError;
+
+    #[inline]
+    fn try_from(vec: Vec<u8>) -> Result<Self, Self::Error> {
+        mutf8::decode_strict(&vec)?;
+        Ok(Self::from_vec(vec))
+    }
+}
+
+impl AsRef<Mutf8Str> for Mutf8String {
+    #[inline]
+    fn as_ref(&self) -> &Mutf8Str {
+        self.as_str()
+    }
+}
+impl AsRef<Mutf8Str> for Mutf8Str {
+    #[inline]
+    fn as_ref(&self) -> &Mutf8Str {
+        self
+    }
+}
+
 impl From<&Mutf8Str> for Mutf8String {
     #[inline]
     fn from(s: &Mutf8Str) -> Self {
@@ -262,7 +552,7 @@ impl From<&Mutf8Str> for String {
 mod tests {
     use std::borrow::Cow;
 
-    use crate::mutf8::Mutf8Str;
+    use crate::mutf8::{Mutf8Str, Mutf8String};
 
     #[test]
     fn same_as_utf8() {
@@ -287,6 +577,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn conversions() {
+        let expected = Mutf8String::from("h\0é");
+        assert_eq!(Mutf8String::from(Cow::Borrowed("h\0é")), expected);
+        assert_eq!(Mutf8String::from(Cow::Owned("h\0é".to_owned())), expected);
+        assert_eq!("h\0é".chars().collect::<Mutf8String>(), expected);
+        assert_eq!(Mutf8String::from('\0').as_bytes(), &[0xC0, 0x80]);
+        assert_eq!(
+            Mutf8String::try_from(expected.as_bytes().to_vec()),
+            Ok(expected.clone())
+        );
+        // plain null bytes aren't allowed in mutf-8
+        assert!(Mutf8String::try_from(vec![b'h', 0]).is_err());
+        assert!(Mutf8String::try_from(vec![0xff]).is_err());
+
+        let as_ref: &Mutf8Str = expected.as_ref();
+        assert_eq!(as_ref, expected.as_str());
+    }
+
+    #[test]
+    fn strict_and_lossy() {
+        // an unpaired high surrogate followed by a valid surrogate pair
+        let mutf8_data = &[0xed, 0xa0, 0xbd, 0xed, 0xa0, 0xbd, 0xed, 0xb2, 0x96];
+        let str = Mutf8Str::from_slice(mutf8_data);
+        assert!(str.try_to_str().is_err());
+        assert_eq!(str.to_string_lossy(), "\u{FFFD}\u{FFFD}\u{FFFD}\u{1F496}");
+
+        let valid = Mutf8Str::from_slice(&[0xED, 0xA0, 0x81, 0xED, 0xB0, 0x81]);
+        assert_eq!(valid.try_to_str().unwrap(), "\u{10401}");
+        // plain null bytes and 4-byte characters aren't allowed in mutf-8
+        assert!(Mutf8Str::from_slice(b"\0").try_to_str().is_err());
+        assert!(Mutf8Str::from_slice("\u{10401}".as_bytes())
+            .try_to_str()
+            .is_err());
+    }
+
+    #[test]
+    fn cesu8() {
+        let cesu8 = [0, b'h', 0xED, 0xA0, 0x81, 0xED, 0xB0, 0x81, 0];
+        let str = Mutf8Str::from_cesu8(&cesu8).unwrap();
+        assert_eq!(str.to_str(), "\0h\u{10401}\0");
+        assert_eq!(
+            str.as_bytes(),
+            Mutf8String::from("\0h\u{10401}\0").as_bytes()
+        );
+        assert_eq!(str.to_cesu8(), &cesu8[..]);
+
+        let without_nulls = [b'h', 0xED, 0xA0, 0x81, 0xED, 0xB0, 0x81];
+        assert!(matches!(
+            Mutf8Str::from_cesu8(&without_nulls),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert!(matches!(
+            Mutf8Str::from_slice(&without_nulls).to_cesu8(),
+            Cow::Borrowed(_)
+        ));
+
+        // mutf-8 nulls aren't allowed in cesu-8
+        assert!(Mutf8Str::from_cesu8(&[0xC0, 0x80]).is_err());
+        assert_eq!(
+            Mutf8Str::from_cesu8_lossy(&[0, 0xED, 0xA0, 0x81]).to_str(),
+            "\0\u{FFFD}\u{FFFD}\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn mutation() {
+        use std::fmt::Write;
+
+        let mut string = Mutf8String::new();
+        string.push_str("h\0");
+        string.push_char('\u{10401}');
+        string += Mutf8String::from("é").as_str();
+        write!(string, "{}", 12).unwrap();
+        assert_eq!(string, Mutf8String::from("h\0\u{10401}é12"));
+
+        string.clear();
+        assert!(string.is_empty());
+        string += "\0";
+        assert_eq!(string.as_bytes(), &[0xC0, 0x80]);
+
+        assert_eq!(
+            Mutf8String::from_utf8_lossy(&[b'h', 0xff, 0]),
+            Mutf8String::from("h\u{FFFD}\0")
+        );
+    }
+
     #[test]
     fn null_bytes() {
         let str = "\0";
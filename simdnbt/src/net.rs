@@ -0,0 +1,309 @@
+//! Reading and writing NBT in the layouts that Minecraft: Java Edition uses in
+//! packets.
+//!
+//! Before 1.20.2, NBT in packets was a normal root compound with a name (which
+//! was always empty). Since 1.20.2 the name is left out, so the compound's
+//! type is followed directly by its contents. [`NetworkFormat`] picks between
+//! the two, so code that supports several versions only has to look at the
+//! protocol version once.
+//!
+//! ```
+//! # use std::io::Cursor;
+//! use simdnbt::{
+//!     compound,
+//!     net::{self, NetworkFormat},
+//!     owned::{BaseNbt, Nbt},
+//! };
+//!
+//! let nbt = Nbt::Some(BaseNbt::new("", compound! { "id": 1 }));
+//! for protocol_version in [763, 767] {
+//!     let format = NetworkFormat::for_protocol_version(protocol_version);
+//!     let mut packet = Vec::new();
+//!     net::write_java_network(&nbt, &mut packet, format);
+//!
+//!     let read = net::read_java_network(&mut Cursor::new(&packet), format).unwrap();
+//!     assert_eq!(read.unwrap().int("id"), Some(1));
+//! }
+//! ```
+
+use std::io::Cursor;
+
+use crate::{borrow, owned, Error, ReadOptions};
+
+/// How a root compound is laid out in a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkFormat {
+    /// A root compound with a name, like in files. This was used before
+    /// 1.20.2.
+    Named,
+    /// A root compound without a name. This is used since 1.20.2.
+    Unnamed,
+}
+
+impl NetworkFormat {
+    /// The protocol version of 1.20.2, which is the first version that uses
+    /// [`NetworkFormat::Unnamed`].
+    pub const UNNAMED_SINCE_PROTOCOL_VERSION: i32 = 764;
+
+    /// Get the format that's used by the given protocol version.
+    pub fn for_protocol_version(protocol_version: i32) -> Self {
+        if protocol_version >= Self::UNNAMED_SINCE_PROTOCOL_VERSION {
+            NetworkFormat::Unnamed
+        } else {
+            NetworkFormat::Named
+        }
+    }
+}
+
+/// Read a root compound from a packet with [`borrow`]. An empty root (a single
+/// `TAG_End`) is returned as [`Nbt::None`](borrow::Nbt::None).
+pub fn read_java_network<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    format: NetworkFormat,
+) -> Result<borrow::Nbt<'a>, Error> {
+    read_java_network_with_opts(data, format, &ReadOptions::default())
+}
+
+/// Read a root compound from a packet like [`read_java_network`], but with the
+/// given options. Packets usually come from untrusted clients or servers, so
+/// this should be used to set limits on them.
+pub fn read_java_network_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    format: NetworkFormat,
+    options: &ReadOptions,
+) -> Result<borrow::Nbt<'a>, Error> {
+    match format {
+        NetworkFormat::Named => borrow::read_with_opts(data, options),
+        NetworkFormat::Unnamed => borrow::read_unnamed_with_opts(data, options),
+    }
+}
+
+/// Read a root compound from a packet with [`owned`]. See
+/// [`read_java_network`].
+pub fn read_java_network_owned(
+    data: &mut Cursor<&[u8]>,
+    format: NetworkFormat,
+) -> Result<owned::Nbt, Error> {
+    read_java_network_owned_with_opts(data, format, &ReadOptions::default())
+}
+
+/// Read a root compound from a packet with [`owned`], but with the given
+/// options. See [`read_java_network_with_opts`].
+pub fn read_java_network_owned_with_opts(
+    data: &mut Cursor<&[u8]>,
+    format: NetworkFormat,
+    options: &ReadOptions,
+) -> Result<owned::Nbt, Error> {
+    match format {
+        NetworkFormat::Named => owned::read_with_opts(data, options),
+        NetworkFormat::Unnamed => owned::read_unnamed_with_opts(data, options),
+    }
+}
+
+/// Write a root compound to a packet. With [`NetworkFormat::Named`] the name
+/// of the NBT is written too, which should be empty for vanilla clients.
+pub fn write_java_network(nbt: &owned::Nbt, data: &mut Vec<u8>, format: NetworkFormat) {
+    match format {
+        NetworkFormat::Named => nbt.write(data),
+        NetworkFormat::Unnamed => nbt.write_unnamed(data),
+    }
+}
+
+/// The number of bytes that [`write_java_network`] would write.
+pub fn java_network_size(nbt: &owned::Nbt, format: NetworkFormat) -> usize {
+    match (nbt, format) {
+        (owned::Nbt::Some(nbt), NetworkFormat::Unnamed) => 1 + (**nbt).serialized_size(),
+        _ => nbt.serialized_size(),
+    }
+}
+
+/// Read a root compound that comes after its length as a VarInt, like NBT
+/// that's embedded in some custom payloads.
+///
+/// Returns [`Error::InvalidLength`] if the VarInt is invalid or the NBT
+/// doesn't take up exactly that many bytes.
+pub fn read_length_prefixed<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    format: NetworkFormat,
+) -> Result<borrow::Nbt<'a>, Error> {
+    read_length_prefixed_with_opts(data, format, &ReadOptions::default())
+}
+
+/// Read a length-prefixed root compound like [`read_length_prefixed`], but
+/// with the given options.
+pub fn read_length_prefixed_with_opts<'a>(
+    data: &mut Cursor<&'a [u8]>,
+    format: NetworkFormat,
+    options: &ReadOptions,
+) -> Result<borrow::Nbt<'a>, Error> {
+    let len = read_var_int(data)?;
+    let start = data.position() as usize;
+    let frame = data
+        .get_ref()
+        .get(start..)
+        .and_then(|rest| rest.get(..len))
+        .ok_or(Error::UnexpectedEof)?;
+
+    let mut frame_cursor = Cursor::new(frame);
+    let nbt = read_java_network_with_opts(&mut frame_cursor, format, options)?;
+    if frame_cursor.position() as usize != len {
+        return Err(Error::InvalidLength);
+    }
+    data.set_position((start + len) as u64);
+    Ok(nbt)
+}
+
+/// Write a root compound after its length as a VarInt. See
+/// [`read_length_prefixed`].
+///
+/// The length is calculated before anything is written, so the NBT is written
+/// directly into `data`.
+pub fn write_length_prefixed(nbt: &owned::Nbt, data: &mut Vec<u8>, format: NetworkFormat) {
+    let len = java_network_size(nbt, format);
+    data.reserve(5 + len);
+    write_var_int(data, len as u32);
+    write_java_network(nbt, data, format);
+}
+
+fn read_var_int(data: &mut Cursor<&[u8]>) -> Result<usize, Error> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let position = data.position() as usize;
+        let byte = *data.get_ref().get(position).ok_or(Error::UnexpectedEof)?;
+        data.set_position(position as u64 + 1);
+        value |= ((byte & 0x7f) as u32) << (i * 7);
+        if byte & 0x80 == 0 {
+            // lengths can't be negative
+            return i32::try_from(value)
+                .map(|value| value as usize)
+                .map_err(|_| Error::InvalidLength);
+        }
+    }
+    Err(Error::InvalidLength)
+}
+
+fn write_var_int(data: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compound, owned::BaseNbt};
+
+    fn nbt() -> owned::Nbt {
+        owned::Nbt::Some(BaseNbt::new(
+            "",
+            compound! { "text": "a".repeat(200), "list": [1, 2, 3] },
+        ))
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            NetworkFormat::for_protocol_version(763),
+            NetworkFormat::Named
+        );
+        assert_eq!(
+            NetworkFormat::for_protocol_version(764),
+            NetworkFormat::Unnamed
+        );
+
+        let nbt = nbt();
+        let mut named = Vec::new();
+        write_java_network(&nbt, &mut named, NetworkFormat::Named);
+        let mut unnamed = Vec::new();
+        write_java_network(&nbt, &mut unnamed, NetworkFormat::Unnamed);
+        // the empty name is two bytes for its length
+        assert_eq!(named.len(), unnamed.len() + 2);
+        assert_eq!(named[3..], unnamed[1..]);
+
+        for (format, data) in [
+            (NetworkFormat::Named, &named),
+            (NetworkFormat::Unnamed, &unnamed),
+        ] {
+            assert_eq!(java_network_size(&nbt, format), data.len());
+            let borrowed = read_java_network(&mut Cursor::new(data), format).unwrap();
+            assert!(borrowed.unwrap() == nbt.clone().unwrap());
+            let owned = read_java_network_owned(&mut Cursor::new(data), format).unwrap();
+            assert_eq!(owned, nbt);
+        }
+
+        let mut empty = Vec::new();
+        write_java_network(&owned::Nbt::None, &mut empty, NetworkFormat::Unnamed);
+        assert_eq!(empty, [0]);
+        assert!(
+            read_java_network(&mut Cursor::new(&empty), NetworkFormat::Unnamed)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn length_prefixed() {
+        let nbt = nbt();
+        let mut data = Vec::new();
+        write_length_prefixed(&nbt, &mut data, NetworkFormat::Unnamed);
+        let len = java_network_size(&nbt, NetworkFormat::Unnamed);
+        // the length is more than 127 so the VarInt is 2 bytes
+        assert!(len > 127);
+        assert_eq!(data.len(), len + 2);
+        data.push(0xff);
+
+        let mut cursor = Cursor::new(&data[..]);
+        let read = read_length_prefixed(&mut cursor, NetworkFormat::Unnamed).unwrap();
+        assert!(read.unwrap() == nbt.unwrap());
+        assert_eq!(cursor.position() as usize, data.len() - 1);
+
+        // the length doesn't match the nbt
+        let mut wrong = data.clone();
+        wrong[0] += 1;
+        assert_eq!(
+            read_length_prefixed(&mut Cursor::new(&wrong), NetworkFormat::Unnamed).unwrap_err(),
+            Error::InvalidLength
+        );
+        assert_eq!(
+            read_length_prefixed(&mut Cursor::new(&data[..10]), NetworkFormat::Unnamed)
+                .unwrap_err(),
+            Error::UnexpectedEof
+        );
+        assert_eq!(
+            read_length_prefixed(&mut Cursor::new(&[0xff; 6]), NetworkFormat::Unnamed).unwrap_err(),
+            Error::InvalidLength
+        );
+    }
+
+    #[test]
+    fn with_opts() {
+        let nbt = nbt();
+        let options = ReadOptions {
+            max_bytes: Some(100),
+            ..Default::default()
+        };
+        for format in [NetworkFormat::Named, NetworkFormat::Unnamed] {
+            let mut data = Vec::new();
+            write_java_network(&nbt, &mut data, format);
+            assert_eq!(
+                read_java_network_with_opts(&mut Cursor::new(&data), format, &options).unwrap_err(),
+                Error::TooManyBytes
+            );
+            assert_eq!(
+                read_java_network_owned_with_opts(&mut Cursor::new(&data), format, &options)
+                    .unwrap_err(),
+                Error::TooManyBytes
+            );
+
+            let mut data = Vec::new();
+            write_length_prefixed(&nbt, &mut data, format);
+            assert_eq!(
+                read_length_prefixed_with_opts(&mut Cursor::new(&data), format, &options)
+                    .unwrap_err(),
+                Error::TooManyBytes
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Options for controlling how NBT is read and written.
+
+use std::mem;
+
+use crate::{common::MAX_DEPTH, error::NonRootError};
+
+/// Options for [`borrow::read_with_opts`](crate::borrow::read_with_opts),
+/// [`owned::read_with_opts`](crate::owned::read_with_opts), and the other
+/// `_with_opts` functions.
+///
+/// The default options are the same as what the normal `read` functions use.
+///
+/// ```
+/// # use simdnbt::ReadOptions;
+/// let options = ReadOptions {
+///     max_tags: Some(10_000),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// The maximum number of tags that can be read before giving up and
+    /// returning [`Error::TooManyTags`](crate::Error::TooManyTags). This is
+    /// useful for untrusted data, since a document with millions of tiny tags
+    /// can take a lot of time and memory to read even if it's small.
+    ///
+    /// Compound keys count as tags too, but numbers inside of lists and arrays
+    /// don't, since they don't take any extra memory to read. The count is
+    /// approximate and may differ slightly between the borrow and owned
+    /// readers.
+    pub max_tags: Option<usize>,
+    /// The maximum number of compounds and lists that can be inside of each
+    /// other, not counting the root compound. Going deeper than this returns
+    /// [`Error::MaxDepthExceeded`](crate::Error::MaxDepthExceeded).
+    ///
+    /// There's always a hard limit of 512, so this can only make it lower.
+    pub max_depth: Option<usize>,
+    /// The maximum number of bytes that can be read, starting from the
+    /// cursor's position. Data that's longer returns
+    /// [`Error::TooManyBytes`](crate::Error::TooManyBytes), but anything after
+    /// the end of the NBT is allowed to be longer.
+    pub max_bytes: Option<usize>,
+    /// The maximum length of every list and array. Longer ones return
+    /// [`Error::InvalidLength`](crate::Error::InvalidLength) before anything
+    /// is allocated for them.
+    pub max_list_len: Option<usize>,
+    /// Roughly how many bytes of memory the result can take up, not counting
+    /// the input for the borrow reader (since it doesn't copy it). Using more
+    /// returns [`Error::OutOfMemory`](crate::Error::OutOfMemory).
+    pub max_allocation: Option<usize>,
+    /// What to do with compounds that have more than one tag with the same
+    /// name.
+    pub duplicate_keys: DuplicateKeyPolicy,
+}
+
+/// What to do when a compound has more than one tag with the same name. See
+/// [`ReadOptions::duplicate_keys`].
+///
+/// The NBT format doesn't forbid duplicate keys, but Minecraft reads compounds
+/// into a map, so only the last tag with each name is kept. Use
+/// [`DuplicateKeyPolicy::Last`] to match that.
+///
+/// ```
+/// # use std::io::Cursor;
+/// # use simdnbt::{DuplicateKeyPolicy, ReadOptions};
+/// // a compound with `"a": 1` and then `"a": 2`
+/// let data = [10, 0, 0, 3, 0, 1, b'a', 0, 0, 0, 1, 3, 0, 1, b'a', 0, 0, 0, 2, 0];
+/// let options = ReadOptions {
+///     duplicate_keys: DuplicateKeyPolicy::Last,
+///     ..Default::default()
+/// };
+/// let nbt = simdnbt::borrow::read_with_opts(&mut Cursor::new(&data), &options)
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(nbt.int("a"), Some(2));
+/// assert_eq!(nbt.as_compound().len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Only keep the first tag with each name.
+    First,
+    /// Only keep the last tag with each name, like Minecraft does.
+    Last,
+    /// Return [`Error::DuplicateKey`](crate::Error::DuplicateKey).
+    Error,
+    /// Keep every tag, so iterating over the compound returns all of them and
+    /// looking up a name returns the first one. This is the fastest since the
+    /// names don't have to be compared.
+    #[default]
+    KeepAll,
+}
+
+impl DuplicateKeyPolicy {
+    /// Decide which entries of a compound with the given names are kept.
+    /// Returns `None` if all of them are, which is always the case when there
+    /// are no duplicates.
+    ///
+    /// Removed entries don't move the ones that are kept, so with
+    /// [`DuplicateKeyPolicy::Last`] a tag ends up where its last duplicate
+    /// was.
+    pub(crate) fn entries_to_keep(
+        self,
+        names: &[&[u8]],
+    ) -> Result<Option<Vec<bool>>, NonRootError> {
+        if self == DuplicateKeyPolicy::KeepAll || names.len() < 2 {
+            return Ok(None);
+        }
+
+        // the sort is stable, so equal names stay in their original order
+        let mut order = (0..names.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| names[i]);
+
+        let mut keep = None;
+        for group in order.chunk_by(|&a, &b| names[a] == names[b]) {
+            if group.len() < 2 {
+                continue;
+            }
+            let kept = match self {
+                DuplicateKeyPolicy::First => group[0],
+                DuplicateKeyPolicy::Last => group[group.len() - 1],
+                _ => return Err(NonRootError::duplicate_key()),
+            };
+            let keep = keep.get_or_insert_with(|| vec![true; names.len()]);
+            for &i in group {
+                keep[i] = i == kept;
+            }
+        }
+        Ok(keep)
+    }
+}
+
+/// Options for the `write_with_opts` functions in [`borrow`](crate::borrow)
+/// and [`owned`](crate::owned).
+///
+/// The default options are the same as what the normal `write` functions use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// How empty lists that have an element type are written.
+    pub empty_lists: EmptyLists,
+}
+
+/// How empty lists are written. See [`WriteOptions::empty_lists`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyLists {
+    /// Keep the element type that the list had, which means that reading and
+    /// writing a document won't change it.
+    #[default]
+    Preserve,
+    /// Write every empty list as a list of `TAG_End`, which is what vanilla
+    /// Minecraft does.
+    End,
+}
+
+/// The state for enforcing the limits in [`ReadOptions`] while reading.
+pub(crate) struct Limits {
+    pub max_tags: usize,
+    pub max_depth: usize,
+    pub max_bytes: usize,
+    pub max_list_len: u32,
+    pub max_allocation: usize,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    tags: usize,
+    allocated: usize,
+}
+impl Limits {
+    pub fn new(options: &ReadOptions) -> Self {
+        Self {
+            max_tags: options.max_tags.unwrap_or(usize::MAX),
+            max_depth: options
+                .max_depth
+                .map_or(MAX_DEPTH, |max| max.min(MAX_DEPTH)),
+            max_bytes: options.max_bytes.unwrap_or(usize::MAX),
+            // lengths are never allowed to be negative
+            max_list_len: options
+                .max_list_len
+                .map_or(i32::MAX as u32, |max| max.min(i32::MAX as usize) as u32),
+            max_allocation: options.max_allocation.unwrap_or(usize::MAX),
+            duplicate_keys: options.duplicate_keys,
+            tags: 0,
+            allocated: 0,
+        }
+    }
+
+    /// Record that some tags were read, and return an error if there were too
+    /// many.
+    #[inline]
+    pub fn count_tags(&mut self, count: usize) -> Result<(), NonRootError> {
+        self.tags = self.tags.saturating_add(count);
+        if self.tags > self.max_tags {
+            return Err(NonRootError::too_many_tags());
+        }
+        Ok(())
+    }
+
+    /// Record that some memory is about to be allocated, and return an error
+    /// if it would be too much.
+    #[inline]
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), NonRootError> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        if self.allocated > self.max_allocation {
+            return Err(NonRootError::out_of_memory());
+        }
+        Ok(())
+    }
+
+    /// Record that a list with `len` elements of type `T` is about to be
+    /// allocated.
+    #[inline]
+    pub fn allocate_list<T>(&mut self, len: usize) -> Result<(), NonRootError> {
+        self.allocate(len.saturating_mul(mem::size_of::<T>()))
+    }
+}
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(&ReadOptions::default())
+    }
+}
@@ -0,0 +1,288 @@
+//! Generating random NBT with the `arbitrary` crate, for fuzzing and
+//! property tests.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{BaseNbt, NbtCompound, NbtList, NbtTag};
+use crate::{
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    mutf8::Mutf8String,
+};
+
+/// The most characters that a generated string can have. Every character
+/// takes up at most 6 bytes in MUTF-8, and strings can't be longer than
+/// `u16::MAX` bytes.
+const MAX_STRING_CHARS: usize = u16::MAX as usize / 6;
+
+const TAG_IDS: &[u8] = &[
+    BYTE_ID,
+    SHORT_ID,
+    INT_ID,
+    LONG_ID,
+    FLOAT_ID,
+    DOUBLE_ID,
+    BYTE_ARRAY_ID,
+    STRING_ID,
+    LIST_ID,
+    COMPOUND_ID,
+    INT_ARRAY_ID,
+    LONG_ARRAY_ID,
+];
+/// The tag IDs that can't contain other tags.
+const LEAF_TAG_IDS: &[u8] = &[
+    BYTE_ID,
+    SHORT_ID,
+    INT_ID,
+    LONG_ID,
+    FLOAT_ID,
+    DOUBLE_ID,
+    BYTE_ARRAY_ID,
+    STRING_ID,
+    INT_ARRAY_ID,
+    LONG_ARRAY_ID,
+];
+
+/// How big the NBT made by [`NbtCompound::arbitrary_with_limits`] and
+/// [`NbtTag::arbitrary_with_limits`] can get.
+///
+/// The [`Arbitrary`] impls use the default limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArbitraryLimits {
+    /// How deeply compounds and lists can be nested. Tags at the maximum depth
+    /// are never compounds or lists.
+    pub max_depth: usize,
+    /// The most tags, list elements, array elements, and string characters
+    /// that can be generated in total.
+    pub max_size: usize,
+}
+impl Default for ArbitraryLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            max_size: 1024,
+        }
+    }
+}
+
+impl NbtCompound {
+    /// Generate a random compound that stays within the limits.
+    ///
+    /// ```
+    /// use simdnbt::owned::{ArbitraryLimits, NbtCompound};
+    ///
+    /// let data = [0xab; 64];
+    /// let mut u = arbitrary::Unstructured::new(&data);
+    /// let limits = ArbitraryLimits {
+    ///     max_depth: 2,
+    ///     max_size: 16,
+    /// };
+    /// let compound = NbtCompound::arbitrary_with_limits(&mut u, limits).unwrap();
+    /// assert!(compound.len() <= 16);
+    /// ```
+    pub fn arbitrary_with_limits(
+        u: &mut Unstructured<'_>,
+        limits: ArbitraryLimits,
+    ) -> Result<Self> {
+        Generator::new(u, limits).compound(0)
+    }
+}
+
+impl NbtTag {
+    /// Generate a random tag that stays within the limits. See
+    /// [`NbtCompound::arbitrary_with_limits`].
+    pub fn arbitrary_with_limits(
+        u: &mut Unstructured<'_>,
+        limits: ArbitraryLimits,
+    ) -> Result<Self> {
+        Generator::new(u, limits).tag(0)
+    }
+}
+
+impl<'a> Arbitrary<'a> for NbtCompound {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Self::arbitrary_with_limits(u, ArbitraryLimits::default())
+    }
+}
+impl<'a> Arbitrary<'a> for NbtTag {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Self::arbitrary_with_limits(u, ArbitraryLimits::default())
+    }
+}
+impl<'a> Arbitrary<'a> for NbtList {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Generator::new(u, ArbitraryLimits::default()).list(0)
+    }
+}
+impl<'a> Arbitrary<'a> for BaseNbt {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut generator = Generator::new(u, ArbitraryLimits::default());
+        let name = generator.string()?;
+        let tag = generator.compound(0)?;
+        Ok(BaseNbt { name, tag })
+    }
+}
+
+struct Generator<'u, 'a> {
+    u: &'u mut Unstructured<'a>,
+    max_depth: usize,
+    /// How much more can be generated, in the units of
+    /// [`ArbitraryLimits::max_size`].
+    remaining: usize,
+}
+
+impl<'u, 'a> Generator<'u, 'a> {
+    fn new(u: &'u mut Unstructured<'a>, limits: ArbitraryLimits) -> Self {
+        Self {
+            u,
+            max_depth: limits.max_depth,
+            remaining: limits.max_size,
+        }
+    }
+
+    /// Pick how many things to generate and take them out of the remaining
+    /// size.
+    fn len(&mut self, max: usize) -> Result<usize> {
+        let len = self.u.int_in_range(0..=self.remaining.min(max))?;
+        self.remaining -= len;
+        Ok(len)
+    }
+
+    fn vec<T>(&mut self, mut generate: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.len(usize::MAX)?;
+        (0..len).map(|_| generate(self)).collect()
+    }
+
+    fn numbers<T: Arbitrary<'a>>(&mut self) -> Result<Vec<T>> {
+        self.vec(|generator| generator.u.arbitrary())
+    }
+
+    fn string(&mut self) -> Result<Mutf8String> {
+        let len = self.len(MAX_STRING_CHARS)?;
+        let string = (0..len)
+            .map(|_| self.u.arbitrary::<char>())
+            .collect::<Result<String>>()?;
+        Ok(Mutf8String::from(string))
+    }
+
+    fn compound(&mut self, depth: usize) -> Result<NbtCompound> {
+        let values = self.vec(|generator| Ok((generator.string()?, generator.tag(depth)?)))?;
+        Ok(NbtCompound::from_values(values))
+    }
+
+    /// Pick a tag ID that can be used at the depth.
+    fn tag_id(&mut self, depth: usize) -> Result<u8> {
+        if depth >= self.max_depth {
+            self.u.choose(LEAF_TAG_IDS).copied()
+        } else {
+            self.u.choose(TAG_IDS).copied()
+        }
+    }
+
+    fn tag(&mut self, depth: usize) -> Result<NbtTag> {
+        Ok(match self.tag_id(depth)? {
+            BYTE_ID => NbtTag::Byte(self.u.arbitrary()?),
+            SHORT_ID => NbtTag::Short(self.u.arbitrary()?),
+            INT_ID => NbtTag::Int(self.u.arbitrary()?),
+            LONG_ID => NbtTag::Long(self.u.arbitrary()?),
+            FLOAT_ID => NbtTag::Float(self.u.arbitrary()?),
+            DOUBLE_ID => NbtTag::Double(self.u.arbitrary()?),
+            BYTE_ARRAY_ID => NbtTag::ByteArray(self.numbers()?),
+            STRING_ID => NbtTag::String(self.string()?),
+            LIST_ID => NbtTag::List(self.list(depth + 1)?),
+            COMPOUND_ID => NbtTag::Compound(self.compound(depth + 1)?),
+            INT_ARRAY_ID => NbtTag::IntArray(self.numbers()?),
+            LONG_ARRAY_ID => NbtTag::LongArray(self.numbers()?),
+            _ => unreachable!(),
+        })
+    }
+
+    fn list(&mut self, depth: usize) -> Result<NbtList> {
+        // empty lists can have any element type, so pick an element type
+        // separately from the length
+        if self.u.ratio(1, 8)? {
+            return Ok(NbtList::Empty);
+        }
+        Ok(match self.tag_id(depth)? {
+            BYTE_ID => NbtList::Byte(self.numbers()?),
+            SHORT_ID => NbtList::Short(self.numbers()?),
+            INT_ID => NbtList::Int(self.numbers()?),
+            LONG_ID => NbtList::Long(self.numbers()?),
+            FLOAT_ID => NbtList::Float(self.numbers()?),
+            DOUBLE_ID => NbtList::Double(self.numbers()?),
+            BYTE_ARRAY_ID => NbtList::ByteArray(self.vec(Self::numbers)?),
+            STRING_ID => NbtList::String(self.vec(Self::string)?),
+            LIST_ID => NbtList::List(self.vec(|generator| generator.list(depth + 1))?),
+            COMPOUND_ID => NbtList::Compound(self.vec(|generator| generator.compound(depth + 1))?),
+            INT_ARRAY_ID => NbtList::IntArray(self.vec(Self::numbers)?),
+            LONG_ARRAY_ID => NbtList::LongArray(self.vec(Self::numbers)?),
+            _ => unreachable!(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differential;
+
+    /// Bytes that look random enough for the generator to make interesting
+    /// NBT.
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                // xorshift
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn depth(tag: &NbtTag) -> usize {
+        match tag {
+            NbtTag::Compound(compound) => compound_depth(compound),
+            NbtTag::List(list) => 1 + list_depth(list),
+            _ => 0,
+        }
+    }
+    fn compound_depth(compound: &NbtCompound) -> usize {
+        1 + compound.values().map(depth).max().unwrap_or(0)
+    }
+    fn list_depth(list: &NbtList) -> usize {
+        match list {
+            NbtList::List(lists) => 1 + lists.iter().map(list_depth).max().unwrap_or(0),
+            NbtList::Compound(compounds) => compounds.iter().map(compound_depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for seed in 1..200 {
+            let data = noise(seed, 4096);
+            let nbt = BaseNbt::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            differential::check_roundtrip(&nbt).unwrap();
+        }
+    }
+
+    #[test]
+    fn stays_within_limits() {
+        let limits = ArbitraryLimits {
+            max_depth: 3,
+            max_size: 64,
+        };
+        for seed in 1..200 {
+            let data = noise(seed, 4096);
+            let compound =
+                NbtCompound::arbitrary_with_limits(&mut Unstructured::new(&data), limits).unwrap();
+            // the compound itself is at depth 0
+            assert!(compound_depth(&compound) <= limits.max_depth + 1);
+            assert!(compound.to_bytes().len() < limits.max_size * 16);
+        }
+    }
+}
@@ -1,18 +1,36 @@
-use std::mem::{self, MaybeUninit};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    mem::{self, MaybeUninit},
+};
 
 use super::{list::NbtList, NbtTag};
 use crate::{
-    common::{push_unchecked, read_string, write_string_unchecked, END_ID, MAX_DEPTH},
+    common::{push_unchecked, read_string, write_string_unchecked, END_ID},
     error::NonRootError,
+    io_write::ChunkedWriter,
     mutf8::Mutf8String,
+    options::{DuplicateKeyPolicy, Limits, WriteOptions},
+    packed::PackedBits,
     reader::Reader,
-    Mutf8Str, ToNbtTag,
+    FromNbtTag, Mutf8Str, ToNbtTag,
 };
 
 /// A list of named tags. The order of the tags is preserved.
-#[derive(Debug, Default, Clone, PartialEq)]
+///
+/// Looking up a tag by name scans all of the tags, which is fast for the small
+/// compounds that are common in NBT. For compounds with lots of tags that are
+/// looked up often, call [`Self::build_index`] (or use [`Self::with_index`])
+/// to keep a hash map of the names alongside the tags. If you don't care about
+/// the order, [`NbtMap`](super::NbtMap) is also an option.
+#[derive(Default, Clone)]
 pub struct NbtCompound {
     pub(crate) values: Vec<(Mutf8String, NbtTag)>,
+    /// The position of the first tag with each name, if the index was built.
+    /// It's boxed so compounds without an index (and so `NbtTag`) don't get
+    /// bigger.
+    #[allow(clippy::box_collection)]
+    pub(crate) index: Option<Box<HashMap<Mutf8String, usize>>>,
 }
 
 impl NbtCompound {
@@ -21,19 +39,82 @@ impl NbtCompound {
     }
 
     pub fn from_values(values: Vec<(Mutf8String, NbtTag)>) -> Self {
-        Self { values }
+        Self {
+            values,
+            index: None,
+        }
+    }
+
+    /// Create a compound from the tags and build its index. See
+    /// [`Self::build_index`].
+    ///
+    /// ```
+    /// # use simdnbt::owned::{NbtCompound, NbtTag};
+    /// let compound = NbtCompound::with_index(
+    ///     (0..1000).map(|i| (i.to_string().into(), NbtTag::Int(i))).collect(),
+    /// );
+    /// assert!(compound.has_index());
+    /// assert_eq!(compound.int("999"), Some(999));
+    /// ```
+    pub fn with_index(values: Vec<(Mutf8String, NbtTag)>) -> Self {
+        let mut compound = Self::from_values(values);
+        compound.build_index();
+        compound
+    }
+
+    /// Build a hash map from names to tags, so looking up tags is O(1) instead
+    /// of scanning the whole compound.
+    ///
+    /// The index is kept up to date when the compound is modified, and the
+    /// order of the tags is still preserved. It's only worth it for large
+    /// compounds, since building it has to clone every name.
+    pub fn build_index(&mut self) {
+        let mut index = HashMap::with_capacity(self.values.len());
+        for (i, (key, _)) in self.values.iter().enumerate() {
+            // the first tag with a name is the one that `get` returns
+            index.entry(key.clone()).or_insert(i);
+        }
+        self.index = Some(Box::new(index));
+    }
+
+    /// Remove the index that was created by [`Self::build_index`].
+    pub fn drop_index(&mut self) {
+        self.index = None;
+    }
+
+    /// Whether the compound has an index. See [`Self::build_index`].
+    pub fn has_index(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Find the position of the first tag with the given name.
+    fn position(&self, name: &Mutf8Str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(name).copied(),
+            None => self.values.iter().position(|(key, _)| key.as_str() == name),
+        }
+    }
+
+    /// Add a tag to the end of the compound, updating the index if there is
+    /// one.
+    fn push(&mut self, name: Mutf8String, tag: NbtTag) {
+        if let Some(index) = &mut self.index {
+            index.entry(name.clone()).or_insert(self.values.len());
+        }
+        self.values.push((name, tag));
     }
 
-    pub(crate) fn read(data: &mut Reader<'_>) -> Result<Self, NonRootError> {
-        Self::read_with_depth(data, 0)
+    pub(crate) fn read(data: &mut Reader<'_>, limits: &mut Limits) -> Result<Self, NonRootError> {
+        Self::read_with_depth(data, 0, limits)
     }
 
     pub(crate) fn read_with_depth_and_capacity(
         data: &mut Reader<'_>,
         depth: usize,
         capacity: usize,
+        limits: &mut Limits,
     ) -> Result<Self, NonRootError> {
-        if depth > MAX_DEPTH {
+        if depth > limits.max_depth {
             return Err(NonRootError::max_depth_exceeded());
         }
 
@@ -48,8 +129,12 @@ impl NbtCompound {
             if tag_type == END_ID {
                 break;
             }
-            let tag_name = read_string(data)?.to_owned();
-            let tag = NbtTag::read_with_type(data, tag_type, depth)?;
+            // the key and the value
+            limits.count_tags(2)?;
+            let tag_name = read_string(data)?;
+            limits.allocate(mem::size_of::<(Mutf8String, NbtTag)>() + tag_name.len())?;
+            let tag_name = tag_name.to_owned();
+            let tag = NbtTag::read_with_type(data, tag_type, depth, limits)?;
 
             tags_buffer[tags_buffer_len] = MaybeUninit::new((tag_name, tag));
             tags_buffer_len += 1;
@@ -66,79 +151,132 @@ impl NbtCompound {
             values.push(unsafe { tags_buffer.get_unchecked(i).assume_init_read() });
         }
 
-        Ok(Self { values })
+        if limits.duplicate_keys != DuplicateKeyPolicy::KeepAll {
+            remove_duplicate_keys(&mut values, limits.duplicate_keys)?;
+        }
+
+        Ok(Self::from_values(values))
     }
 
     pub(crate) fn read_with_depth(
         data: &mut Reader<'_>,
         depth: usize,
+        limits: &mut Limits,
     ) -> Result<Self, NonRootError> {
-        Self::read_with_depth_and_capacity(data, depth, 8)
+        Self::read_with_depth_and_capacity(data, depth, 8, limits)
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
-        for (name, tag) in &self.values {
-            // reserve 4 bytes extra so we can avoid reallocating for small tags
-            data.reserve(1 + 2 + name.len() + 4);
-            // SAFETY: We just reserved enough space for the tag ID, the name length, the
-            // name, and 4 bytes of tag data.
-            unsafe {
-                push_unchecked(data, tag.id());
-                write_string_unchecked(data, name);
-                tag.write_without_tag_type_unchecked(data);
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the compound like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        self.write_with(data, opts, false);
+    }
+
+    /// Write the compound with its keys sorted by their bytes, including the
+    /// keys of every compound inside of it, and with every NaN float and
+    /// double written as the same NaN (the one that Java uses). The output
+    /// only depends on the contents of the compound, so compounds that are
+    /// equal (ignoring the order of their keys) always have the same bytes.
+    ///
+    /// This is the same as what the borrowed
+    /// [`NbtCompound::write_canonical`](crate::borrow::NbtCompound::write_canonical)
+    /// writes. Duplicate keys are kept in their original order.
+    ///
+    /// ```
+    /// # use simdnbt::compound;
+    /// let mut a = Vec::new();
+    /// compound! { "x": 1, "y": f32::NAN }.write_canonical(&mut a);
+    /// let mut b = Vec::new();
+    /// compound! { "y": -f32::NAN, "x": 1 }.write_canonical(&mut b);
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        self.write_with(data, &WriteOptions::default(), true);
+    }
+
+    pub(crate) fn write_with(&self, data: &mut Vec<u8>, opts: &WriteOptions, canonical: bool) {
+        if canonical {
+            let mut entries = self.values.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| name.as_str().as_bytes());
+            for (name, tag) in entries {
+                write_entry(name, tag, data, opts, true);
+            }
+        } else {
+            for (name, tag) in &self.values {
+                write_entry(name, tag, data, opts, false);
             }
         }
         data.push(END_ID);
     }
 
+    /// Write the compound to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// The number of bytes that [`Self::write`] would write. See
+    /// [`BaseNbt::serialized_size`](super::BaseNbt::serialized_size).
+    pub fn serialized_size(&self) -> usize {
+        self.values
+            .iter()
+            .map(|(name, tag)| 1 + 2 + name.len() + tag.payload_size())
+            .sum::<usize>()
+            + 1
+    }
+
+    /// Write the compound to an [`io::Write`] like [`Self::write`].
+    ///
+    /// The data is written in chunks as it's serialized, so the whole
+    /// compound never has to be in memory at once. The writer doesn't need to
+    /// be buffered.
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the compound like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        let mut writer = ChunkedWriter::new(out, opts);
+        writer.owned_compound(self)?;
+        writer.finish()
+    }
+
     #[inline]
     pub fn get(&self, name: &str) -> Option<&NbtTag> {
-        let name = Mutf8Str::from_str(name);
-        let name = name.as_ref();
-        for (key, value) in &self.values {
-            if key.as_str() == name {
-                return Some(value);
-            }
-        }
-        None
+        let index = self.position(&Mutf8Str::from_str(name))?;
+        Some(&self.values[index].1)
+    }
+
+    /// Get the tag with the given name and convert it with [`FromNbtTag`].
+    ///
+    /// Returns `None` if there's no tag with the name or it can't be converted
+    /// into `T`. This uses [`FromNbtTag::from_owned_nbt_tag`], which converts
+    /// numbers, strings, and [`NbtTag`]s directly, but writes and reads the tag
+    /// again for types like the ones from `#[derive(Deserialize)]`.
+    pub fn get_as<T: FromNbtTag>(&self, name: &str) -> Option<T> {
+        T::from_owned_nbt_tag(self.get(name)?)
     }
 
     #[inline]
     pub fn get_mut(&mut self, name: &str) -> Option<&mut NbtTag> {
-        let name = Mutf8Str::from_str(name);
-        let name = name.as_ref();
-        for (key, value) in &mut self.values {
-            if key.as_str() == name {
-                return Some(value);
-            }
-        }
-        None
+        let index = self.position(&Mutf8Str::from_str(name))?;
+        Some(&mut self.values[index].1)
     }
 
     /// Get an owned tag from the compound by swapping it with a dummy tag.
     pub fn take(&mut self, name: &str) -> Option<NbtTag> {
-        let name = Mutf8Str::from_str(name);
-        let name = name.as_ref();
-        for i in 0..self.values.len() {
-            if self.values[i].0.as_str() == name {
-                let mut value = NbtTag::Byte(0);
-                mem::swap(&mut self.values[i].1, &mut value);
-                return Some(value);
-            }
-        }
-        None
+        let index = self.position(&Mutf8Str::from_str(name))?;
+        Some(mem::replace(&mut self.values[index].1, NbtTag::Byte(0)))
     }
 
     /// Returns whether there is a tag with the given name.
     pub fn contains(&self, name: &str) -> bool {
-        let name = Mutf8Str::from_str(name);
-        let name = name.as_ref();
-        for (key, _) in &self.values {
-            if key.as_str() == name {
-                return true;
-            }
-        }
-        false
+        self.position(&Mutf8Str::from_str(name)).is_some()
     }
 
     pub fn byte(&self, name: &str) -> Option<i8> {
@@ -147,6 +285,11 @@ impl NbtCompound {
     pub fn byte_mut(&mut self, name: &str) -> Option<&mut i8> {
         self.get_mut(name).and_then(|tag| tag.byte_mut())
     }
+    /// Get a byte tag as a `u8`, for values like light levels that are
+    /// meant to be unsigned.
+    pub fn ubyte(&self, name: &str) -> Option<u8> {
+        self.get(name).and_then(|tag| tag.ubyte())
+    }
     pub fn short(&self, name: &str) -> Option<i16> {
         self.get(name).and_then(|tag| tag.short())
     }
@@ -183,6 +326,9 @@ impl NbtCompound {
     pub fn byte_array_mut(&mut self, name: &str) -> Option<&mut Vec<u8>> {
         self.get_mut(name).and_then(|tag| tag.byte_array_mut())
     }
+    pub fn byte_array_i8(&self, name: &str) -> Option<&[i8]> {
+        self.get(name).and_then(|tag| tag.byte_array_i8())
+    }
     pub fn string(&self, name: &str) -> Option<&Mutf8Str> {
         self.get(name).and_then(|tag| tag.string())
     }
@@ -213,6 +359,67 @@ impl NbtCompound {
     pub fn long_array_mut(&mut self, name: &str) -> Option<&mut Vec<i64>> {
         self.get_mut(name).and_then(|tag| tag.long_array_mut())
     }
+    /// Returns a view of the numbers that are bit-packed into the long array.
+    /// See [`PackedBits`].
+    ///
+    /// Returns `None` if there's no long array with the name or
+    /// `bits_per_entry` is out of range.
+    pub fn long_array_packed(&self, name: &str, bits_per_entry: u32) -> Option<PackedBits<'_>> {
+        self.long_array(name)
+            .and_then(|longs| PackedBits::new(longs, bits_per_entry))
+    }
+
+    pub fn byte_list(&self, name: &str) -> Option<&[i8]> {
+        self.list(name).and_then(|list| list.bytes())
+    }
+    pub fn short_list(&self, name: &str) -> Option<&[i16]> {
+        match self.list(name)? {
+            NbtList::Short(shorts) => Some(shorts),
+            _ => None,
+        }
+    }
+    pub fn int_list(&self, name: &str) -> Option<&[i32]> {
+        match self.list(name)? {
+            NbtList::Int(ints) => Some(ints),
+            _ => None,
+        }
+    }
+    pub fn long_list(&self, name: &str) -> Option<&[i64]> {
+        match self.list(name)? {
+            NbtList::Long(longs) => Some(longs),
+            _ => None,
+        }
+    }
+    pub fn float_list(&self, name: &str) -> Option<&[f32]> {
+        match self.list(name)? {
+            NbtList::Float(floats) => Some(floats),
+            _ => None,
+        }
+    }
+    pub fn double_list(&self, name: &str) -> Option<&[f64]> {
+        match self.list(name)? {
+            NbtList::Double(doubles) => Some(doubles),
+            _ => None,
+        }
+    }
+    pub fn byte_array_list(&self, name: &str) -> Option<&[Vec<u8>]> {
+        self.list(name).and_then(|list| list.byte_arrays())
+    }
+    pub fn string_list(&self, name: &str) -> Option<&[Mutf8String]> {
+        self.list(name).and_then(|list| list.strings())
+    }
+    pub fn list_list(&self, name: &str) -> Option<&[NbtList]> {
+        self.list(name).and_then(|list| list.lists())
+    }
+    pub fn compound_list(&self, name: &str) -> Option<&[NbtCompound]> {
+        self.list(name).and_then(|list| list.compounds())
+    }
+    pub fn int_array_list(&self, name: &str) -> Option<&[Vec<i32>]> {
+        self.list(name).and_then(|list| list.int_arrays())
+    }
+    pub fn long_array_list(&self, name: &str) -> Option<&[Vec<i64>]> {
+        self.list(name).and_then(|list| list.long_arrays())
+    }
 
     pub fn iter(&self) -> impl Iterator<Item = (&Mutf8Str, &NbtTag)> {
         self.values.iter().map(|(k, v)| (k.as_str(), v))
@@ -229,7 +436,10 @@ impl NbtCompound {
     pub fn keys(&self) -> impl Iterator<Item = &Mutf8Str> {
         self.values.iter().map(|(k, _)| k.as_str())
     }
+    /// Iterate over the names of the tags mutably. This removes the index if
+    /// the compound had one, since it can't be kept up to date.
     pub fn keys_mut(&mut self) -> impl Iterator<Item = &mut Mutf8String> {
+        self.index = None;
         self.values.iter_mut().map(|(k, _)| k)
     }
     pub fn values(&self) -> impl Iterator<Item = &NbtTag> {
@@ -240,31 +450,297 @@ impl NbtCompound {
     }
     pub fn clear(&mut self) {
         self.values.clear();
+        if let Some(index) = &mut self.index {
+            index.clear();
+        }
     }
     pub fn insert(&mut self, name: impl Into<Mutf8String>, tag: impl ToNbtTag) {
         let name = name.into();
         let tag = tag.to_nbt_tag();
-        self.values.push((name, tag));
+        self.push(name, tag);
+    }
+    /// Insert a byte array of signed bytes. See [`NbtTag::byte_array_from_i8`].
+    pub fn insert_byte_array_i8(&mut self, name: impl Into<Mutf8String>, byte_array: Vec<i8>) {
+        self.insert(name, NbtTag::byte_array_from_i8(byte_array));
     }
     pub fn extend(
         &mut self,
         other: impl IntoIterator<Item = (impl Into<Mutf8String>, impl ToNbtTag)>,
     ) {
-        self.values.extend(
-            other
-                .into_iter()
-                .map(|(name, tag)| (name.into(), tag.to_nbt_tag())),
-        );
+        let other = other.into_iter();
+        self.values.reserve(other.size_hint().0);
+        for (name, tag) in other {
+            self.push(name.into(), tag.to_nbt_tag());
+        }
+    }
+    /// Move all of the tags from `other` into this compound, deciding what to
+    /// do with names that are already in this compound with `policy`.
+    ///
+    /// This is faster than inserting the tags one at a time, since the names
+    /// are looked up with a hash map and nothing has to be cloned or
+    /// re-encoded. Duplicate names that are already within `other` are kept.
+    ///
+    /// ```
+    /// # use simdnbt::owned::{AppendPolicy, NbtCompound};
+    /// let mut a = NbtCompound::new();
+    /// a.insert("x", 1);
+    /// let mut b = NbtCompound::new();
+    /// b.insert("x", 2);
+    /// b.insert("y", 3);
+    ///
+    /// a.append(b, AppendPolicy::Replace);
+    /// assert_eq!(a.int("x"), Some(2));
+    /// assert_eq!(a.int("y"), Some(3));
+    /// ```
+    pub fn append(&mut self, other: NbtCompound, policy: AppendPolicy) {
+        if policy == AppendPolicy::KeepAll || self.values.is_empty() {
+            let mut other = other;
+            self.values.append(&mut other.values);
+            if self.index.is_some() {
+                self.build_index();
+            }
+            return;
+        }
+
+        let mut indices = HashMap::with_capacity(self.values.len());
+        for (i, (key, _)) in self.values.iter().enumerate() {
+            // the first tag with a name is the one that `get` returns
+            indices.entry(key.as_str()).or_insert(i);
+        }
+
+        let mut replaced = Vec::new();
+        let mut added = Vec::with_capacity(other.values.len());
+        for (key, tag) in other.values {
+            match indices.get(key.as_str()) {
+                Some(&index) => {
+                    if policy == AppendPolicy::Replace {
+                        replaced.push((index, tag));
+                    }
+                }
+                None => added.push((key, tag)),
+            }
+        }
+        drop(indices);
+
+        for (index, tag) in replaced {
+            self.values[index].1 = tag;
+        }
+        for (key, tag) in added {
+            self.push(key, tag);
+        }
+    }
+
+    /// Deep-merge another compound into this one, like Minecraft's
+    /// `/data merge` command.
+    ///
+    /// Tags that are only in `other` are added to the end, and compounds that
+    /// are in both are merged recursively. `strategy` decides what happens to
+    /// any other tag that's in both.
+    ///
+    /// ```
+    /// # use simdnbt::owned::{MergeStrategy, NbtCompound, NbtList};
+    /// let mut a = NbtCompound::new();
+    /// a.ensure_path("display").insert("Name", "a");
+    /// a.insert("Tags", NbtList::String(vec!["x".into()]));
+    /// let mut b = NbtCompound::new();
+    /// b.ensure_path("display").insert("Lore", "b");
+    /// b.insert("Tags", NbtList::String(vec!["y".into()]));
+    ///
+    /// a.merge(b, MergeStrategy::ConcatLists);
+    /// let display = a.compound("display").unwrap();
+    /// assert_eq!(display.string("Name").unwrap().to_str(), "a");
+    /// assert_eq!(display.string("Lore").unwrap().to_str(), "b");
+    /// assert_eq!(a.list("Tags").unwrap().len(), 2);
+    /// ```
+    pub fn merge(&mut self, other: NbtCompound, strategy: MergeStrategy) {
+        for (key, tag) in other.values {
+            match self.position(&key) {
+                Some(index) => merge_tag(&mut self.values[index].1, tag, strategy),
+                None => self.push(key, tag),
+            }
+        }
     }
+
+    /// Remove the first tag with the given name and return it.
+    ///
+    /// The tags after it have to be shifted over, so if the compound has an
+    /// index then it's rebuilt.
     pub fn remove(&mut self, name: &str) -> Option<NbtTag> {
-        let name = Mutf8Str::from_str(name);
-        let name = name.as_ref();
-        for i in 0..self.values.len() {
-            if self.values[i].0.as_str() == name {
-                return Some(self.values.remove(i).1);
+        let index = self.position(&Mutf8Str::from_str(name))?;
+        let (_, tag) = self.values.remove(index);
+        if self.index.is_some() {
+            self.build_index();
+        }
+        Some(tag)
+    }
+
+    /// Get a compound at the given dot-separated path (like `"a.b.c"`),
+    /// creating any compounds that don't exist yet.
+    ///
+    /// If a tag along the path exists but isn't a compound, it's replaced with
+    /// an empty compound. An empty path returns this compound.
+    pub fn ensure_path(&mut self, path: &str) -> &mut NbtCompound {
+        if path.is_empty() {
+            return self;
+        }
+        path.split('.')
+            .fold(self, |compound, name| compound.ensure_compound(name))
+    }
+
+    /// Get a list at the given dot-separated path (like `"a.b.items"`),
+    /// creating it and any compounds leading up to it if they don't exist
+    /// yet.
+    ///
+    /// Like [`Self::ensure_path`], tags along the path that have the wrong
+    /// type are replaced. A new list is [`NbtList::Empty`].
+    pub fn ensure_list(&mut self, path: &str) -> &mut NbtList {
+        let (parent, name) = match path.rsplit_once('.') {
+            Some((parent, name)) => (self.ensure_path(parent), name),
+            None => (self, path),
+        };
+        let tag = parent.ensure_tag(
+            name,
+            || NbtTag::List(NbtList::Empty),
+            |tag| matches!(tag, NbtTag::List(_)),
+        );
+        let NbtTag::List(list) = tag else {
+            unreachable!()
+        };
+        list
+    }
+
+    fn ensure_compound(&mut self, name: &str) -> &mut NbtCompound {
+        let tag = self.ensure_tag(
+            name,
+            || NbtTag::Compound(NbtCompound::new()),
+            |tag| matches!(tag, NbtTag::Compound(_)),
+        );
+        let NbtTag::Compound(compound) = tag else {
+            unreachable!()
+        };
+        compound
+    }
+
+    /// Get the tag with the given name, inserting or replacing it with
+    /// `default()` if it doesn't exist or `is_valid` returns false.
+    fn ensure_tag(
+        &mut self,
+        name: &str,
+        default: impl FnOnce() -> NbtTag,
+        is_valid: impl FnOnce(&NbtTag) -> bool,
+    ) -> &mut NbtTag {
+        let mutf8_name = Mutf8Str::from_str(name);
+        let index = match self.position(&mutf8_name) {
+            Some(index) => {
+                if !is_valid(&self.values[index].1) {
+                    self.values[index].1 = default();
+                }
+                index
+            }
+            None => {
+                self.push(mutf8_name.into_owned(), default());
+                self.values.len() - 1
+            }
+        };
+        &mut self.values[index].1
+    }
+}
+
+fn write_entry(
+    name: &Mutf8String,
+    tag: &NbtTag,
+    data: &mut Vec<u8>,
+    opts: &WriteOptions,
+    canonical: bool,
+) {
+    // reserve 4 bytes extra so we can avoid reallocating for small tags
+    data.reserve(1 + 2 + name.len() + 4);
+    // SAFETY: We just reserved enough space for the tag ID, the name length, the
+    // name, and 4 bytes of tag data.
+    unsafe {
+        push_unchecked(data, tag.id());
+        write_string_unchecked(data, name);
+        tag.write_without_tag_type_unchecked(data, opts, canonical);
+    }
+}
+
+/// Remove the tags from a compound that was just read that the policy doesn't
+/// keep, or return an error if it doesn't allow duplicates.
+pub(super) fn remove_duplicate_keys(
+    values: &mut Vec<(Mutf8String, NbtTag)>,
+    policy: DuplicateKeyPolicy,
+) -> Result<(), NonRootError> {
+    let names = values
+        .iter()
+        .map(|(name, _)| name.as_str().as_bytes())
+        .collect::<Vec<_>>();
+    let Some(keep) = policy.entries_to_keep(&names)? else {
+        return Ok(());
+    };
+    let mut keep = keep.into_iter();
+    values.retain(|_| keep.next().unwrap_or(true));
+    Ok(())
+}
+
+/// What [`NbtCompound::append`] does with tags that have the same name as a
+/// tag that's already in the compound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppendPolicy {
+    /// Add the tag anyways, so the compound has both. This is the fastest
+    /// option, and it's the same as what [`NbtCompound::insert`] does.
+    #[default]
+    KeepAll,
+    /// Ignore the new tag.
+    KeepExisting,
+    /// Replace the value of the existing tag with the new one, keeping its
+    /// position in the compound.
+    Replace,
+}
+
+fn merge_tag(existing: &mut NbtTag, new: NbtTag, strategy: MergeStrategy) {
+    match (existing, new) {
+        (NbtTag::Compound(existing), NbtTag::Compound(new)) => existing.merge(new, strategy),
+        (NbtTag::List(existing), NbtTag::List(new)) if strategy == MergeStrategy::ConcatLists => {
+            if let Err(new) = existing.append(new) {
+                *existing = new;
+            }
+        }
+        (existing, new) => {
+            if strategy != MergeStrategy::KeepExisting {
+                *existing = new;
             }
         }
-        None
+    }
+}
+
+/// What [`NbtCompound::merge`] does with tags that are in both compounds and
+/// aren't both compounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Replace the existing tag with the new one. This is what `/data merge`
+    /// does.
+    #[default]
+    Replace,
+    /// Keep the existing tag. Compounds are still merged, so only tags that
+    /// are missing get added.
+    KeepExisting,
+    /// Add the elements of the new list to the end of the existing list if
+    /// they have the same type, and otherwise replace the existing tag.
+    ConcatLists,
+}
+
+impl fmt::Debug for NbtCompound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NbtCompound")
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+impl PartialEq for NbtCompound {
+    fn eq(&self, other: &Self) -> bool {
+        // the index doesn't matter for equality
+        self.values == other.values
     }
 }
 
@@ -0,0 +1,329 @@
+//! Comparisons that handle floats more usefully than `PartialEq`.
+
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use super::{NbtCompound, NbtList, NbtTag};
+
+/// A wrapper for [`NbtTag`], [`NbtList`], or [`NbtCompound`] that implements
+/// `Eq`, `Ord`, and `Hash`, so NBT can be used as a key in a `HashMap` or
+/// `BTreeMap`.
+///
+/// Floats and doubles are compared by their bits like in
+/// [`NbtTag::total_eq`], and the order is the one from
+/// [`NbtTag::total_cmp`].
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use simdnbt::{compound, owned::FullEq};
+/// let mut seen = HashSet::new();
+/// assert!(seen.insert(FullEq(compound! { "id": "minecraft:stone", "x": f32::NAN })));
+/// assert!(!seen.insert(FullEq(compound! { "id": "minecraft:stone", "x": f32::NAN })));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FullEq<T>(pub T);
+
+macro_rules! impl_full_eq {
+    ($($ty:ty),*) => {
+        $(
+            impl PartialEq for FullEq<$ty> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0.total_eq(&other.0)
+                }
+            }
+            impl Eq for FullEq<$ty> {}
+            impl PartialOrd for FullEq<$ty> {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+            impl Ord for FullEq<$ty> {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    self.0.total_cmp(&other.0)
+                }
+            }
+            impl Hash for FullEq<$ty> {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    self.0.structural_hash(state);
+                }
+            }
+        )*
+    };
+}
+impl_full_eq!(NbtTag, NbtList, NbtCompound);
+
+/// Compare slices lexicographically with the given function for their
+/// elements, like `Ord` does for slices.
+fn cmp_slices<T>(a: &[T], b: &[T], cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        match cmp(a, b) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[derive(Clone, Copy)]
+enum FloatEq {
+    /// Floats are equal if they have the same bits.
+    Total,
+    /// Floats are equal if they're within epsilon of each other, or if
+    /// they're both NaN.
+    Epsilon(f64),
+}
+impl FloatEq {
+    fn f32(self, a: f32, b: f32) -> bool {
+        match self {
+            FloatEq::Total => a.to_bits() == b.to_bits(),
+            FloatEq::Epsilon(_) => self.f64(a as f64, b as f64),
+        }
+    }
+    fn f64(self, a: f64, b: f64) -> bool {
+        match self {
+            FloatEq::Total => a.to_bits() == b.to_bits(),
+            FloatEq::Epsilon(epsilon) => {
+                a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= epsilon
+            }
+        }
+    }
+}
+
+impl NbtTag {
+    /// Returns whether the tags are equal, treating floats and doubles that
+    /// are within `epsilon` of each other as equal. Unlike `PartialEq`, NaNs
+    /// are considered equal to each other.
+    ///
+    /// Like `PartialEq`, the order of keys in compounds matters.
+    pub fn eq_with_epsilon(&self, other: &Self, epsilon: f64) -> bool {
+        self.eq_by(other, FloatEq::Epsilon(epsilon))
+    }
+
+    /// Returns whether the tags are equal, comparing floats and doubles by
+    /// their bits. This means NaNs with the same bits are equal and `0.0` is
+    /// not equal to `-0.0`, which makes this behave like the tags were
+    /// compared as serialized bytes.
+    pub fn total_eq(&self, other: &Self) -> bool {
+        self.eq_by(other, FloatEq::Total)
+    }
+
+    /// Returns whether the tags have the same bits when they're serialized.
+    /// This is the same as [`Self::total_eq`].
+    pub fn eq_bitwise(&self, other: &Self) -> bool {
+        self.total_eq(other)
+    }
+
+    /// Compare the tags with a total order that's consistent with
+    /// [`Self::total_eq`]. Floats and doubles are ordered with
+    /// [`f32::total_cmp`], tags with different types are ordered by their IDs,
+    /// and strings are ordered by their bytes.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (NbtTag::Byte(a), NbtTag::Byte(b)) => a.cmp(b),
+            (NbtTag::Short(a), NbtTag::Short(b)) => a.cmp(b),
+            (NbtTag::Int(a), NbtTag::Int(b)) => a.cmp(b),
+            (NbtTag::Long(a), NbtTag::Long(b)) => a.cmp(b),
+            (NbtTag::Float(a), NbtTag::Float(b)) => a.total_cmp(b),
+            (NbtTag::Double(a), NbtTag::Double(b)) => a.total_cmp(b),
+            (NbtTag::ByteArray(a), NbtTag::ByteArray(b)) => a.cmp(b),
+            (NbtTag::String(a), NbtTag::String(b)) => a.as_bytes().cmp(b.as_bytes()),
+            (NbtTag::List(a), NbtTag::List(b)) => a.total_cmp(b),
+            (NbtTag::Compound(a), NbtTag::Compound(b)) => a.total_cmp(b),
+            (NbtTag::IntArray(a), NbtTag::IntArray(b)) => a.cmp(b),
+            (NbtTag::LongArray(a), NbtTag::LongArray(b)) => a.cmp(b),
+            (a, b) => a.id().cmp(&b.id()),
+        }
+    }
+
+    fn eq_by(&self, other: &Self, float_eq: FloatEq) -> bool {
+        match (self, other) {
+            (NbtTag::Float(a), NbtTag::Float(b)) => float_eq.f32(*a, *b),
+            (NbtTag::Double(a), NbtTag::Double(b)) => float_eq.f64(*a, *b),
+            (NbtTag::List(a), NbtTag::List(b)) => a.eq_by(b, float_eq),
+            (NbtTag::Compound(a), NbtTag::Compound(b)) => a.eq_by(b, float_eq),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl NbtList {
+    /// Returns whether the lists are equal, treating floats and doubles that
+    /// are within `epsilon` of each other as equal. See
+    /// [`NbtTag::eq_with_epsilon`].
+    pub fn eq_with_epsilon(&self, other: &Self, epsilon: f64) -> bool {
+        self.eq_by(other, FloatEq::Epsilon(epsilon))
+    }
+
+    /// Returns whether the lists are equal, comparing floats and doubles by
+    /// their bits. See [`NbtTag::total_eq`].
+    pub fn total_eq(&self, other: &Self) -> bool {
+        self.eq_by(other, FloatEq::Total)
+    }
+
+    /// Returns whether the lists have the same bits when they're serialized.
+    /// This is the same as [`Self::total_eq`].
+    pub fn eq_bitwise(&self, other: &Self) -> bool {
+        self.total_eq(other)
+    }
+
+    /// Compare the lists with a total order. Lists with different types are
+    /// ordered by the IDs of their elements, and lists with the same type are
+    /// compared element by element. See [`NbtTag::total_cmp`].
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (NbtList::Byte(a), NbtList::Byte(b)) => a.cmp(b),
+            (NbtList::Short(a), NbtList::Short(b)) => a.cmp(b),
+            (NbtList::Int(a), NbtList::Int(b)) => a.cmp(b),
+            (NbtList::Long(a), NbtList::Long(b)) => a.cmp(b),
+            (NbtList::Float(a), NbtList::Float(b)) => cmp_slices(a, b, f32::total_cmp),
+            (NbtList::Double(a), NbtList::Double(b)) => cmp_slices(a, b, f64::total_cmp),
+            (NbtList::ByteArray(a), NbtList::ByteArray(b)) => a.cmp(b),
+            (NbtList::String(a), NbtList::String(b)) => {
+                cmp_slices(a, b, |a, b| a.as_bytes().cmp(b.as_bytes()))
+            }
+            (NbtList::List(a), NbtList::List(b)) => cmp_slices(a, b, NbtList::total_cmp),
+            (NbtList::Compound(a), NbtList::Compound(b)) => {
+                cmp_slices(a, b, NbtCompound::total_cmp)
+            }
+            (NbtList::IntArray(a), NbtList::IntArray(b)) => a.cmp(b),
+            (NbtList::LongArray(a), NbtList::LongArray(b)) => a.cmp(b),
+            (a, b) => a.id().cmp(&b.id()),
+        }
+    }
+
+    fn eq_by(&self, other: &Self, float_eq: FloatEq) -> bool {
+        match (self, other) {
+            (NbtList::Float(a), NbtList::Float(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| float_eq.f32(*a, *b))
+            }
+            (NbtList::Double(a), NbtList::Double(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| float_eq.f64(*a, *b))
+            }
+            (NbtList::List(a), NbtList::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_by(b, float_eq))
+            }
+            (NbtList::Compound(a), NbtList::Compound(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_by(b, float_eq))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl NbtCompound {
+    /// Returns whether the compounds are equal, treating floats and doubles
+    /// that are within `epsilon` of each other as equal. See
+    /// [`NbtTag::eq_with_epsilon`].
+    pub fn eq_with_epsilon(&self, other: &Self, epsilon: f64) -> bool {
+        self.eq_by(other, FloatEq::Epsilon(epsilon))
+    }
+
+    /// Returns whether the compounds are equal, comparing floats and doubles
+    /// by their bits. See [`NbtTag::total_eq`].
+    pub fn total_eq(&self, other: &Self) -> bool {
+        self.eq_by(other, FloatEq::Total)
+    }
+
+    /// Returns whether the compounds have the same bits when they're
+    /// serialized. This is the same as [`Self::total_eq`].
+    pub fn eq_bitwise(&self, other: &Self) -> bool {
+        self.total_eq(other)
+    }
+
+    /// Compare the compounds with a total order. The tags are compared in
+    /// order by their names and then their values, so the order of the keys
+    /// matters. See [`NbtTag::total_cmp`].
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        cmp_slices(&self.values, &other.values, |(a_key, a), (b_key, b)| {
+            a_key
+                .as_bytes()
+                .cmp(b_key.as_bytes())
+                .then_with(|| a.total_cmp(b))
+        })
+    }
+
+    fn eq_by(&self, other: &Self, float_eq: FloatEq) -> bool {
+        self.values.len() == other.values.len()
+            && self
+                .values
+                .iter()
+                .zip(&other.values)
+                .all(|((a_key, a), (b_key, b))| a_key == b_key && a.eq_by(b, float_eq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epsilon() {
+        let a = NbtTag::Double(0.1 + 0.2);
+        let b = NbtTag::Double(0.3);
+        assert_ne!(a, b);
+        assert!(a.eq_with_epsilon(&b, 1e-9));
+        assert!(!a.eq_with_epsilon(&b, 0.));
+        assert!(!NbtTag::Double(1.).eq_with_epsilon(&NbtTag::Float(1.), 1.));
+
+        let a = NbtList::Float(vec![1., f32::NAN]);
+        let b = NbtList::Float(vec![1.000001, f32::NAN]);
+        assert!(a.eq_with_epsilon(&b, 1e-5));
+        assert!(!a.eq_with_epsilon(&NbtList::Float(vec![1.]), 1e-5));
+    }
+
+    #[test]
+    fn total() {
+        assert!(NbtTag::Float(f32::NAN).total_eq(&NbtTag::Float(f32::NAN)));
+        assert!(!NbtTag::Float(0.).total_eq(&NbtTag::Float(-0.)));
+        assert_eq!(NbtTag::Float(0.), NbtTag::Float(-0.));
+        assert!(NbtTag::Double(f64::NAN).eq_bitwise(&NbtTag::Double(f64::NAN)));
+        assert!(!NbtTag::Double(0.).eq_bitwise(&NbtTag::Double(-0.)));
+        assert!(NbtList::Float(vec![f32::NAN]).eq_bitwise(&NbtList::Float(vec![f32::NAN])));
+
+        let mut a = NbtCompound::new();
+        a.insert("nan", f64::NAN);
+        a.insert("list", NbtList::List(vec![NbtList::Double(vec![f64::NAN])]));
+        let b = a.clone();
+        assert_ne!(a, b);
+        assert!(a.total_eq(&b));
+        assert!(a.eq_bitwise(&b));
+        assert!(a.eq_with_epsilon(&b, 0.));
+
+        let mut c = a.clone();
+        c.insert("extra", 0);
+        assert!(!a.total_eq(&c));
+    }
+
+    #[test]
+    fn total_order() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let tags = [
+            NbtTag::Float(f32::NAN),
+            NbtTag::Float(-0.),
+            NbtTag::Float(0.),
+            NbtTag::Int(2),
+            NbtTag::String("b".into()),
+            NbtTag::List(NbtList::Float(vec![1., f32::NAN])),
+            NbtTag::List(NbtList::Float(vec![1.])),
+            NbtTag::List(NbtList::Empty),
+            NbtTag::Compound(NbtCompound::from_values(vec![("a".into(), 1.into())])),
+        ];
+        for a in &tags {
+            for b in &tags {
+                assert_eq!(a.total_eq(b), a.total_cmp(b).is_eq(), "{a:?} {b:?}");
+                assert_eq!(a.total_cmp(b), b.total_cmp(a).reverse());
+            }
+        }
+        assert!(NbtTag::Float(-0.).total_cmp(&NbtTag::Float(0.)).is_lt());
+        assert!(NbtTag::Byte(5).total_cmp(&NbtTag::Int(1)).is_lt());
+
+        let doubled = || tags.iter().chain(&tags).cloned().map(FullEq);
+        assert_eq!(doubled().collect::<HashSet<_>>().len(), tags.len());
+        let sorted = doubled().collect::<BTreeSet<_>>();
+        assert_eq!(sorted.len(), tags.len());
+        assert_eq!(sorted.first().unwrap().0, NbtTag::Int(2));
+    }
+}
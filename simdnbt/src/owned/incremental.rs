@@ -0,0 +1,578 @@
+use std::{io::IoSlice, mem};
+
+use super::{compound::remove_duplicate_keys, BaseNbt, Nbt, NbtCompound, NbtList, NbtTag};
+use crate::{
+    common::{
+        slice_u8_into_i8, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID,
+        INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    error::NonRootError,
+    mutf8::Mutf8String,
+    options::{DuplicateKeyPolicy, Limits},
+    swap_endianness::swap_endianness,
+    Error, ReadOptions,
+};
+
+/// Reads owned NBT from data that arrives in pieces, like packets that are
+/// split between the segments of a ring buffer.
+///
+/// Every piece is parsed as soon as it's fed to the reader, so the pieces
+/// don't have to be copied into one contiguous buffer first. The only data
+/// that's buffered is a number, name, or array that's split between two
+/// pieces.
+///
+/// ```
+/// use simdnbt::{
+///     compound,
+///     owned::{BaseNbt, IncrementalReader},
+/// };
+///
+/// let data = BaseNbt::new("", compound! { "id": 1, "name": "stone" }).to_bytes();
+/// let (first, second) = data.split_at(10);
+///
+/// let mut reader = IncrementalReader::new();
+/// assert_eq!(reader.feed(first).unwrap(), None);
+/// let (nbt, used) = reader.feed(second).unwrap().unwrap();
+/// assert_eq!(nbt.unwrap().int("id"), Some(1));
+/// assert_eq!(used, second.len());
+/// ```
+pub struct IncrementalReader {
+    options: ReadOptions,
+    named: bool,
+    limits: Limits,
+    /// How many bytes of the current document have been read.
+    read: usize,
+    step: Step,
+    /// The start of the data for the current step, if it was split between
+    /// pieces.
+    pending: Vec<u8>,
+    root_name: Mutf8String,
+    /// The compounds and lists that are being read, starting with the root.
+    frames: Vec<Frame>,
+}
+
+/// What the reader is waiting for.
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    RootType,
+    RootNameLength,
+    RootName(usize),
+    /// The type of the next tag in the current compound, or the end of it.
+    TagType,
+    NameLength(u8),
+    Name(u8, usize),
+    /// A tag with the given type, or its length if it has one.
+    Payload(u8),
+    /// The elements of a byte array, string, int array, or long array.
+    Array(u8, usize),
+    /// The elements of a list of numbers with the given type.
+    NumberList(u8, usize),
+}
+
+enum Frame {
+    Compound {
+        values: Vec<(Mutf8String, NbtTag)>,
+        /// The name of the tag that's being read.
+        name: Mutf8String,
+    },
+    /// A list whose elements are read one at a time, which is every list
+    /// except for lists of numbers.
+    List {
+        list: NbtList,
+        element_type: u8,
+        remaining: usize,
+    },
+}
+
+impl IncrementalReader {
+    /// Make a reader for normal root NBT compounds, like
+    /// [`read`](super::read).
+    pub fn new() -> Self {
+        Self::with_opts(ReadOptions::default())
+    }
+
+    /// Make a reader that reads with the given options. See
+    /// [`read_with_opts`](super::read_with_opts).
+    pub fn with_opts(options: ReadOptions) -> Self {
+        Self {
+            limits: Limits::new(&options),
+            options,
+            named: true,
+            read: 0,
+            step: Step::RootType,
+            pending: Vec::new(),
+            root_name: Mutf8String::default(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Read root compounds without a name, like
+    /// [`read_unnamed`](super::read_unnamed).
+    pub fn unnamed(mut self) -> Self {
+        self.named = false;
+        self
+    }
+
+    /// Whether part of a document has been read, but not all of it.
+    pub fn is_reading(&self) -> bool {
+        self.read > 0
+    }
+
+    /// Read the next piece of data.
+    ///
+    /// If the piece finishes a document, it's returned with how many bytes of
+    /// the piece it used, and anything after that is the start of the next
+    /// document. Otherwise the whole piece was used and `None` is returned.
+    ///
+    /// After an error, the reader starts over from the beginning of a
+    /// document.
+    pub fn feed(&mut self, data: &[u8]) -> Result<Option<(Nbt, usize)>, Error> {
+        let result = self.feed_inner(data);
+        if result.is_err() {
+            self.reset();
+        }
+        result
+    }
+
+    /// Read several pieces of data in order, like [`Self::feed`]. The number
+    /// of bytes that were used is counted across all of the pieces.
+    pub fn feed_vectored(&mut self, data: &[IoSlice<'_>]) -> Result<Option<(Nbt, usize)>, Error> {
+        let mut used = 0;
+        for piece in data {
+            if let Some((nbt, piece_used)) = self.feed(piece)? {
+                return Ok(Some((nbt, used + piece_used)));
+            }
+            used += piece.len();
+        }
+        Ok(None)
+    }
+
+    fn feed_inner(&mut self, mut data: &[u8]) -> Result<Option<(Nbt, usize)>, Error> {
+        let len = data.len();
+        loop {
+            let needed = self.needed();
+            let nbt = if self.pending.is_empty() && data.len() >= needed {
+                // this is the usual case, so nothing has to be copied
+                let bytes;
+                (bytes, data) = data.split_at(needed);
+                self.consume(needed)?;
+                self.advance(bytes)?
+            } else {
+                let taken = (needed - self.pending.len()).min(data.len());
+                self.consume(taken)?;
+                self.pending.extend_from_slice(&data[..taken]);
+                data = &data[taken..];
+                if self.pending.len() < needed {
+                    return Ok(None);
+                }
+                let mut pending = mem::take(&mut self.pending);
+                let nbt = self.advance(&pending)?;
+                // keep the allocation for the next split value
+                pending.clear();
+                self.pending = pending;
+                nbt
+            };
+            if let Some(nbt) = nbt {
+                return Ok(Some((nbt, len - data.len())));
+            }
+        }
+    }
+
+    /// How many bytes the current step needs.
+    fn needed(&self) -> usize {
+        match self.step {
+            Step::RootType | Step::TagType => 1,
+            Step::RootNameLength | Step::NameLength(_) => 2,
+            Step::RootName(len) | Step::Name(_, len) => len,
+            Step::Payload(tag_type) => match tag_type {
+                BYTE_ID => 1,
+                SHORT_ID | STRING_ID => 2,
+                INT_ID | FLOAT_ID | BYTE_ARRAY_ID | INT_ARRAY_ID | LONG_ARRAY_ID => 4,
+                LONG_ID | DOUBLE_ID => 8,
+                // the type of the elements and the length
+                LIST_ID => 5,
+                _ => 0,
+            },
+            Step::Array(tag_type, len) => len.saturating_mul(match tag_type {
+                INT_ARRAY_ID => 4,
+                LONG_ARRAY_ID => 8,
+                _ => 1,
+            }),
+            Step::NumberList(element_type, len) => len.saturating_mul(match element_type {
+                BYTE_ID => 1,
+                SHORT_ID => 2,
+                INT_ID | FLOAT_ID => 4,
+                _ => 8,
+            }),
+        }
+    }
+
+    fn consume(&mut self, bytes: usize) -> Result<(), Error> {
+        self.read += bytes;
+        if self.read > self.limits.max_bytes {
+            return Err(Error::TooManyBytes);
+        }
+        Ok(())
+    }
+
+    /// Handle the data for the current step, which is exactly as long as
+    /// [`Self::needed`].
+    fn advance(&mut self, bytes: &[u8]) -> Result<Option<Nbt>, Error> {
+        match self.step {
+            Step::RootType => {
+                let root_type = bytes[0];
+                if root_type == END_ID {
+                    return Ok(Some(self.finish(Nbt::None)));
+                }
+                if root_type != COMPOUND_ID {
+                    return Err(Error::InvalidRootType(root_type));
+                }
+                if self.named {
+                    self.step = Step::RootNameLength;
+                } else {
+                    self.start_compound()?;
+                }
+            }
+            Step::RootNameLength => self.step = Step::RootName(read_u16(bytes) as usize),
+            Step::RootName(_) => {
+                self.root_name = Mutf8String::from_vec(bytes.to_vec());
+                self.limits.count_tags(1)?;
+                self.start_compound()?;
+            }
+            Step::TagType => {
+                let tag_type = bytes[0];
+                if tag_type == END_ID {
+                    return self.end_compound();
+                }
+                if !(BYTE_ID..=LONG_ARRAY_ID).contains(&tag_type) {
+                    return Err(Error::UnknownTagId(tag_type));
+                }
+                // the key and the value
+                self.limits.count_tags(2)?;
+                self.step = Step::NameLength(tag_type);
+            }
+            Step::NameLength(tag_type) => {
+                self.step = Step::Name(tag_type, read_u16(bytes) as usize)
+            }
+            Step::Name(tag_type, _) => {
+                self.limits
+                    .allocate(mem::size_of::<(Mutf8String, NbtTag)>() + bytes.len())?;
+                if let Some(Frame::Compound { name, .. }) = self.frames.last_mut() {
+                    *name = Mutf8String::from_vec(bytes.to_vec());
+                }
+                self.step = Step::Payload(tag_type);
+            }
+            Step::Payload(tag_type) => return self.read_payload(tag_type, bytes),
+            Step::Array(tag_type, _) => {
+                self.limits.allocate(bytes.len())?;
+                let tag = match tag_type {
+                    BYTE_ARRAY_ID => NbtTag::ByteArray(bytes.to_vec()),
+                    STRING_ID => NbtTag::String(Mutf8String::from_vec(bytes.to_vec())),
+                    INT_ARRAY_ID => NbtTag::IntArray(swap_endianness(bytes)),
+                    _ => NbtTag::LongArray(swap_endianness(bytes)),
+                };
+                return self.push(tag);
+            }
+            Step::NumberList(element_type, _) => {
+                self.limits.allocate(bytes.len())?;
+                let list = match element_type {
+                    BYTE_ID => NbtList::Byte(slice_u8_into_i8(bytes).to_owned()),
+                    SHORT_ID => NbtList::Short(swap_endianness(bytes)),
+                    INT_ID => NbtList::Int(swap_endianness(bytes)),
+                    LONG_ID => NbtList::Long(swap_endianness(bytes)),
+                    FLOAT_ID => NbtList::Float(swap_endianness(bytes)),
+                    _ => NbtList::Double(swap_endianness(bytes)),
+                };
+                return self.push(NbtTag::List(list));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_payload(&mut self, tag_type: u8, bytes: &[u8]) -> Result<Option<Nbt>, Error> {
+        let tag = match tag_type {
+            BYTE_ID => NbtTag::Byte(bytes[0] as i8),
+            SHORT_ID => NbtTag::Short(read_u16(bytes) as i16),
+            INT_ID => NbtTag::Int(read_u32(bytes) as i32),
+            LONG_ID => NbtTag::Long(i64::from_be_bytes(bytes.try_into().unwrap())),
+            FLOAT_ID => NbtTag::Float(f32::from_bits(read_u32(bytes))),
+            DOUBLE_ID => NbtTag::Double(f64::from_bits(u64::from_be_bytes(
+                bytes.try_into().unwrap(),
+            ))),
+            STRING_ID => {
+                self.step = Step::Array(STRING_ID, read_u16(bytes) as usize);
+                return Ok(None);
+            }
+            BYTE_ARRAY_ID | INT_ARRAY_ID | LONG_ARRAY_ID => {
+                self.step = Step::Array(tag_type, self.read_length(bytes)?);
+                return Ok(None);
+            }
+            COMPOUND_ID => {
+                self.start_compound()?;
+                return Ok(None);
+            }
+            _ => return self.start_list(bytes[0], self.read_length(&bytes[1..])?),
+        };
+        self.push(tag)
+    }
+
+    fn read_length(&self, bytes: &[u8]) -> Result<usize, NonRootError> {
+        let length = read_u32(bytes);
+        if length > self.limits.max_list_len {
+            return Err(NonRootError::invalid_length());
+        }
+        Ok(length as usize)
+    }
+
+    /// Return an error if starting another compound or list would go past the
+    /// maximum depth.
+    fn check_depth(&self) -> Result<(), NonRootError> {
+        // the root compound is at a depth of 0
+        if self.frames.len() > self.limits.max_depth {
+            return Err(NonRootError::max_depth_exceeded());
+        }
+        Ok(())
+    }
+
+    fn start_compound(&mut self) -> Result<(), NonRootError> {
+        self.check_depth()?;
+        self.frames.push(Frame::Compound {
+            values: Vec::new(),
+            name: Mutf8String::default(),
+        });
+        self.step = Step::TagType;
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> Result<Option<Nbt>, Error> {
+        let Some(Frame::Compound { mut values, .. }) = self.frames.pop() else {
+            unreachable!("tag types are only read in compounds");
+        };
+        if self.limits.duplicate_keys != DuplicateKeyPolicy::KeepAll {
+            remove_duplicate_keys(&mut values, self.limits.duplicate_keys)?;
+        }
+        let compound = NbtCompound::from_values(values);
+        if self.frames.is_empty() {
+            let name = mem::take(&mut self.root_name);
+            return Ok(Some(self.finish(Nbt::Some(BaseNbt::new(name, compound)))));
+        }
+        self.push(NbtTag::Compound(compound))
+    }
+
+    fn start_list(&mut self, element_type: u8, len: usize) -> Result<Option<Nbt>, Error> {
+        if element_type == END_ID {
+            return self.push(NbtTag::List(NbtList::Empty));
+        }
+        self.check_depth()?;
+        let list = match element_type {
+            BYTE_ID..=DOUBLE_ID => {
+                self.step = Step::NumberList(element_type, len);
+                return Ok(None);
+            }
+            BYTE_ARRAY_ID => {
+                self.limits.allocate_list::<Vec<u8>>(len)?;
+                NbtList::ByteArray(Vec::new())
+            }
+            STRING_ID => {
+                self.limits.allocate_list::<Mutf8String>(len)?;
+                NbtList::String(Vec::new())
+            }
+            LIST_ID => {
+                self.limits.allocate_list::<NbtList>(len)?;
+                NbtList::List(Vec::new())
+            }
+            COMPOUND_ID => {
+                self.limits.allocate_list::<NbtCompound>(len)?;
+                NbtList::Compound(Vec::new())
+            }
+            INT_ARRAY_ID => {
+                self.limits.allocate_list::<Vec<i32>>(len)?;
+                NbtList::IntArray(Vec::new())
+            }
+            LONG_ARRAY_ID => {
+                self.limits.allocate_list::<Vec<i64>>(len)?;
+                NbtList::LongArray(Vec::new())
+            }
+            _ => return Err(Error::UnknownTagId(element_type)),
+        };
+        self.limits.count_tags(len)?;
+        if len == 0 {
+            return self.push(NbtTag::List(list));
+        }
+        self.frames.push(Frame::List {
+            list,
+            element_type,
+            remaining: len,
+        });
+        self.step = Step::Payload(element_type);
+        Ok(None)
+    }
+
+    /// Add a tag that was just read to the compound or list that it's in, and
+    /// finish the list if that was its last element.
+    fn push(&mut self, mut tag: NbtTag) -> Result<Option<Nbt>, Error> {
+        loop {
+            match self.frames.last_mut() {
+                Some(Frame::Compound { values, name }) => {
+                    values.push((mem::take(name), tag));
+                    self.step = Step::TagType;
+                    return Ok(None);
+                }
+                Some(Frame::List {
+                    list,
+                    element_type,
+                    remaining,
+                }) => {
+                    push_element(list, tag);
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        self.step = Step::Payload(*element_type);
+                        return Ok(None);
+                    }
+                    let Some(Frame::List { list, .. }) = self.frames.pop() else {
+                        unreachable!();
+                    };
+                    tag = NbtTag::List(list);
+                }
+                None => unreachable!("the root is always a compound"),
+            }
+        }
+    }
+
+    /// Get ready to read the next document and return the one that was read.
+    fn finish(&mut self, nbt: Nbt) -> Nbt {
+        self.reset();
+        nbt
+    }
+
+    fn reset(&mut self) {
+        self.limits = Limits::new(&self.options);
+        self.read = 0;
+        self.step = Step::RootType;
+        self.pending.clear();
+        self.root_name = Mutf8String::default();
+        self.frames.clear();
+    }
+}
+impl Default for IncrementalReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push_element(list: &mut NbtList, tag: NbtTag) {
+    match (list, tag) {
+        (NbtList::ByteArray(list), NbtTag::ByteArray(tag)) => list.push(tag),
+        (NbtList::String(list), NbtTag::String(tag)) => list.push(tag),
+        (NbtList::List(list), NbtTag::List(tag)) => list.push(tag),
+        (NbtList::Compound(list), NbtTag::Compound(tag)) => list.push(tag),
+        (NbtList::IntArray(list), NbtTag::IntArray(tag)) => list.push(tag),
+        (NbtList::LongArray(list), NbtTag::LongArray(tag)) => list.push(tag),
+        _ => unreachable!("the elements of a list are always read with its type"),
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{compound, test_fixtures::complex_player};
+
+    #[test]
+    fn every_split() {
+        let data = complex_player();
+        let expected = super::super::read(&mut Cursor::new(&data)).unwrap();
+
+        let mut reader = IncrementalReader::new();
+        for split in (0..=data.len()).step_by(97) {
+            let (first, second) = data.split_at(split);
+            let result = match reader.feed(first).unwrap() {
+                Some(result) => result,
+                None => reader.feed(second).unwrap().unwrap(),
+            };
+            assert_eq!(result, (expected.clone(), data.len() - split));
+            assert!(!reader.is_reading());
+        }
+
+        // one byte at a time
+        for &byte in &data[..data.len() - 1] {
+            assert_eq!(reader.feed(&[byte]).unwrap(), None);
+        }
+        assert_eq!(
+            reader.feed(&data[data.len() - 1..]).unwrap(),
+            Some((expected, 1))
+        );
+    }
+
+    #[test]
+    fn vectored() {
+        let nbt = BaseNbt::new(
+            "",
+            compound! {
+                "empty": [],
+                "lists": [[1i8, 2i8], [3i8]],
+                "compounds": [{ "a": [L; 1, 2] }, {}],
+                "strings": ["a", "bc"],
+            },
+        );
+        let mut data = nbt.to_bytes();
+        let len = data.len();
+        // the start of the next document
+        data.push(COMPOUND_ID);
+
+        let (a, rest) = data.split_at(7);
+        let (b, c) = rest.split_at(20);
+        let mut reader = IncrementalReader::new();
+        let (read, used) = reader
+            .feed_vectored(&[IoSlice::new(a), IoSlice::new(b), IoSlice::new(c)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(read, Nbt::Some(nbt.clone()));
+        assert_eq!(used, len);
+
+        let mut unnamed = vec![COMPOUND_ID];
+        NbtCompound::write(&nbt, &mut unnamed);
+        let mut reader = IncrementalReader::new().unnamed();
+        assert_eq!(reader.feed(&unnamed[..5]).unwrap(), None);
+        assert!(reader.is_reading());
+        let (read, _) = reader.feed(&unnamed[5..]).unwrap().unwrap();
+        assert_eq!(read, Nbt::Some(nbt));
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = IncrementalReader::new();
+        assert_eq!(reader.feed(&[INT_ID]), Err(Error::InvalidRootType(INT_ID)));
+        // the reader starts over after an error
+        assert_eq!(reader.feed(&[END_ID]), Ok(Some((Nbt::None, 1))));
+        assert_eq!(
+            reader.feed(&[COMPOUND_ID, 0, 0, 13]),
+            Err(Error::UnknownTagId(13))
+        );
+
+        let options = ReadOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let data = BaseNbt::new("", compound! { "a": [[1]] }).to_bytes();
+        assert_eq!(
+            IncrementalReader::with_opts(options).feed(&data),
+            Err(Error::MaxDepthExceeded)
+        );
+        let options = ReadOptions {
+            max_bytes: Some(data.len() - 1),
+            ..Default::default()
+        };
+        let mut reader = IncrementalReader::with_opts(options);
+        assert_eq!(reader.feed(&data[..4]), Ok(None));
+        assert_eq!(reader.feed(&data[4..]), Err(Error::TooManyBytes));
+    }
+}
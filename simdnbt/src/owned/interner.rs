@@ -0,0 +1,165 @@
+use std::{collections::HashSet, io::Cursor, sync::Arc};
+
+use super::{read, Nbt, NbtCompound, NbtList, NbtTag};
+use crate::{mutf8::Mutf8String, Error, Mutf8Str};
+
+/// Makes strings with the same contents share one allocation.
+///
+/// Chunks and other big documents repeat the same few keys (like `Name`,
+/// `Properties`, and `id`) thousands of times, so sharing them can save a lot
+/// of memory when many documents are kept around. Use the same interner for
+/// every document to share keys between them too.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use simdnbt::owned::{BaseNbt, Interner, NbtCompound, NbtList};
+///
+/// let mut block = NbtCompound::new();
+/// block.insert("Name", "minecraft:stone");
+/// let mut compound = NbtCompound::new();
+/// compound.insert("palette", NbtList::Compound(vec![block.clone(), block]));
+/// let data = BaseNbt::new("", compound).to_bytes();
+///
+/// let mut interner = Interner::new();
+/// let nbt = simdnbt::owned::read_with_interner(&mut Cursor::new(&data), &mut interner)
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(nbt.list("palette").unwrap().compounds().unwrap().len(), 2);
+/// // "palette" and "Name"
+/// assert_eq!(interner.len(), 2);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    strings: HashSet<Arc<[u8]>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a string with the same contents that shares its bytes with every
+    /// other string that this interner made from the same contents.
+    pub fn intern(&mut self, string: &Mutf8Str) -> Mutf8String {
+        let bytes = string.as_bytes();
+        let shared = match self.strings.get(bytes) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared = Arc::<[u8]>::from(bytes);
+                self.strings.insert(shared.clone());
+                shared
+            }
+        };
+        Mutf8String::from_shared(shared)
+    }
+
+    /// The number of different strings that have been interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Forget the strings that aren't used anymore outside of the interner.
+    pub fn shrink(&mut self) {
+        self.strings.retain(|shared| Arc::strong_count(shared) > 1);
+    }
+}
+
+/// Read a normal root NBT compound like [`read`], but make its keys share
+/// their allocations with the interner. See [`Interner`].
+pub fn read_with_interner(data: &mut Cursor<&[u8]>, interner: &mut Interner) -> Result<Nbt, Error> {
+    let mut nbt = read(data)?;
+    if let Nbt::Some(nbt) = &mut nbt {
+        nbt.tag.intern_keys(interner);
+    }
+    Ok(nbt)
+}
+
+impl NbtCompound {
+    /// Replace the keys in this compound and every compound inside of it with
+    /// ones that share their allocations with the interner. See
+    /// [`Interner`].
+    pub fn intern_keys(&mut self, interner: &mut Interner) {
+        for (key, tag) in &mut self.values {
+            *key = interner.intern(key);
+            tag.intern_keys(interner);
+        }
+        // the index is keyed by copies of the keys
+        if self.has_index() {
+            self.build_index();
+        }
+    }
+}
+
+impl NbtTag {
+    fn intern_keys(&mut self, interner: &mut Interner) {
+        match self {
+            NbtTag::Compound(compound) => compound.intern_keys(interner),
+            NbtTag::List(list) => list.intern_keys(interner),
+            _ => {}
+        }
+    }
+}
+
+impl NbtList {
+    fn intern_keys(&mut self, interner: &mut Interner) {
+        match self {
+            NbtList::Compound(compounds) => {
+                for compound in compounds {
+                    compound.intern_keys(interner);
+                }
+            }
+            NbtList::List(lists) => {
+                for list in lists {
+                    list.intern_keys(interner);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_fixtures::complex_player;
+
+    #[test]
+    fn interned_chunk_is_unchanged() {
+        let data = complex_player();
+
+        let mut interner = Interner::new();
+        let interned = read_with_interner(&mut Cursor::new(&data), &mut interner).unwrap();
+        let normal = read(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(interned, normal);
+        assert!(interned.values.iter().all(|(key, _)| key.is_shared()));
+
+        let mut interned_again = normal.clone().unwrap();
+        interned_again.tag.intern_keys(&mut interner);
+        let len = interner.len();
+        assert_eq!(interned_again, normal.unwrap());
+
+        drop(interned);
+        drop(interned_again);
+        assert_eq!(interner.len(), len);
+        interner.shrink();
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn modifying_interned_key() {
+        let mut interner = Interner::new();
+        let mut key = interner.intern(Mutf8Str::from_str("Name").as_ref());
+        let other = interner.intern(Mutf8Str::from_str("Name").as_ref());
+        key.push_str("s");
+        assert!(!key.is_shared());
+        assert_eq!(key.to_str(), "Names");
+        assert_eq!(other.to_str(), "Name");
+    }
+}
@@ -1,18 +1,25 @@
-use super::{compound::NbtCompound, MAX_DEPTH};
+use super::compound::NbtCompound;
 use crate::{
     common::{
-        extend_unchecked, push_unchecked, read_i8_array, read_int_array, read_long_array,
-        read_string, read_u8_array, read_with_u32_length, slice_i8_into_u8,
-        slice_into_u8_big_endian, write_string, write_u32, write_with_u32_length, BYTE_ARRAY_ID,
-        BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
-        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+        canonicalize_f32s, canonicalize_f64s, extend_unchecked, push_unchecked, read_int_array,
+        read_length, read_long_array, read_string, read_u8_array, read_with_u32_length,
+        slice_i8_into_u8, slice_u8_into_i8, write_big_endian_with_u32_length, write_string,
+        write_u32, write_with_u32_length, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID,
+        FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
     },
     error::NonRootError,
     mutf8::Mutf8String,
+    options::{EmptyLists, Limits, WriteOptions},
     reader::Reader,
     swap_endianness::swap_endianness,
 };
 
+/// Lists of compounds with at least this many elements are written in
+/// parallel when the `rayon` feature is enabled. Smaller lists aren't worth
+/// the overhead.
+#[cfg(feature = "rayon")]
+const PARALLEL_WRITE_THRESHOLD: usize = 1024;
+
 /// A list of NBT tags of a single type.
 #[repr(u8)]
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -33,8 +40,12 @@ pub enum NbtList {
     LongArray(Vec<Vec<i64>>) = LONG_ARRAY_ID,
 }
 impl NbtList {
-    pub(crate) fn read(data: &mut Reader<'_>, depth: usize) -> Result<Self, NonRootError> {
-        if depth > MAX_DEPTH {
+    pub(crate) fn read(
+        data: &mut Reader<'_>,
+        depth: usize,
+        limits: &mut Limits,
+    ) -> Result<Self, NonRootError> {
+        if depth > limits.max_depth {
             return Err(NonRootError::max_depth_exceeded());
         }
         let tag_type = data.read_u8().map_err(|_| NonRootError::unexpected_eof())?;
@@ -43,66 +54,91 @@ impl NbtList {
                 data.skip(4)?;
                 NbtList::Empty
             }
-            BYTE_ID => NbtList::Byte(read_i8_array(data)?.to_owned()),
-            SHORT_ID => NbtList::Short(swap_endianness(read_with_u32_length(data, 2)?)),
-            INT_ID => NbtList::Int(swap_endianness(read_with_u32_length(data, 4)?)),
-            LONG_ID => NbtList::Long(swap_endianness(read_with_u32_length(data, 8)?)),
-            FLOAT_ID => NbtList::Float(swap_endianness(read_with_u32_length(data, 4)?)),
-            DOUBLE_ID => NbtList::Double(swap_endianness(read_with_u32_length(data, 8)?)),
+            BYTE_ID => NbtList::Byte(slice_u8_into_i8(read_numbers(data, 1, limits)?).to_owned()),
+            SHORT_ID => NbtList::Short(swap_endianness(read_numbers(data, 2, limits)?)),
+            INT_ID => NbtList::Int(swap_endianness(read_numbers(data, 4, limits)?)),
+            LONG_ID => NbtList::Long(swap_endianness(read_numbers(data, 8, limits)?)),
+            FLOAT_ID => NbtList::Float(swap_endianness(read_numbers(data, 4, limits)?)),
+            DOUBLE_ID => NbtList::Double(swap_endianness(read_numbers(data, 8, limits)?)),
             BYTE_ARRAY_ID => NbtList::ByteArray({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<Vec<u8>>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut arrays = Vec::with_capacity(length.min(128) as usize);
                 for _ in 0..length {
-                    arrays.push(read_u8_array(data)?.to_vec())
+                    let array = read_u8_array(data)?;
+                    limits.allocate(array.len())?;
+                    arrays.push(array.to_vec())
                 }
                 arrays
             }),
             STRING_ID => NbtList::String({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<Mutf8String>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut strings = Vec::with_capacity(length.min(128) as usize);
                 for _ in 0..length {
-                    strings.push(read_string(data)?.to_owned())
+                    let string = read_string(data)?;
+                    limits.allocate(string.len())?;
+                    strings.push(string.to_owned())
                 }
                 strings
             }),
             LIST_ID => NbtList::List({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<NbtList>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut lists = Vec::with_capacity(length.min(128) as usize);
                 for _ in 0..length {
-                    lists.push(NbtList::read(data, depth + 1)?)
+                    lists.push(NbtList::read(data, depth + 1, limits)?)
                 }
                 lists
             }),
             COMPOUND_ID => NbtList::Compound({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<NbtCompound>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut compounds = Vec::with_capacity(length.min(128) as usize);
                 let mut capacity: usize = 8;
                 for _ in 0..length {
-                    let tag = NbtCompound::read_with_depth_and_capacity(data, depth + 1, capacity)?;
+                    let tag = NbtCompound::read_with_depth_and_capacity(
+                        data,
+                        depth + 1,
+                        capacity,
+                        limits,
+                    )?;
                     capacity = tag.len();
                     compounds.push(tag);
                 }
                 compounds
             }),
             INT_ARRAY_ID => NbtList::IntArray({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<Vec<i32>>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut arrays = Vec::with_capacity(length.min(128) as usize);
                 for _ in 0..length {
-                    arrays.push(read_int_array(data)?.to_vec())
+                    let array = read_int_array(data)?;
+                    limits.allocate(array.as_big_endian().len())?;
+                    arrays.push(array.to_vec())
                 }
                 arrays
             }),
             LONG_ARRAY_ID => NbtList::LongArray({
-                let length = data.read_u32()?;
+                let length = read_length(data)?;
+                limits.count_tags(length as usize)?;
+                limits.allocate_list::<Vec<i64>>(length as usize)?;
                 // arbitrary number to prevent big allocations
                 let mut arrays = Vec::with_capacity(length.min(128) as usize);
                 for _ in 0..length {
-                    arrays.push(read_long_array(data)?.to_vec())
+                    let array = read_long_array(data)?;
+                    limits.allocate(array.as_big_endian().len())?;
+                    arrays.push(array.to_vec())
                 }
                 arrays
             }),
@@ -111,6 +147,64 @@ impl NbtList {
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the list to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// The number of bytes that [`Self::write`] would write, including the
+    /// element type and length. See
+    /// [`BaseNbt::serialized_size`](super::BaseNbt::serialized_size).
+    pub fn serialized_size(&self) -> usize {
+        let elements = match self {
+            NbtList::Empty => 0,
+            NbtList::Byte(bytes) => bytes.len(),
+            NbtList::Short(shorts) => shorts.len() * 2,
+            NbtList::Int(ints) => ints.len() * 4,
+            NbtList::Long(longs) => longs.len() * 8,
+            NbtList::Float(floats) => floats.len() * 4,
+            NbtList::Double(doubles) => doubles.len() * 8,
+            NbtList::ByteArray(byte_arrays) => {
+                byte_arrays.iter().map(|array| 4 + array.len()).sum()
+            }
+            NbtList::String(strings) => strings.iter().map(|string| 2 + string.len()).sum(),
+            NbtList::List(lists) => lists.iter().map(NbtList::serialized_size).sum(),
+            NbtList::Compound(compounds) => {
+                compounds.iter().map(NbtCompound::serialized_size).sum()
+            }
+            NbtList::IntArray(int_arrays) => {
+                int_arrays.iter().map(|array| 4 + array.len() * 4).sum()
+            }
+            NbtList::LongArray(long_arrays) => {
+                long_arrays.iter().map(|array| 4 + array.len() * 8).sum()
+            }
+        };
+        1 + 4 + elements
+    }
+
+    /// Write the list like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
+        self.write_with(data, opts, false);
+    }
+
+    /// Write the list, sorting the keys of every compound inside of it and
+    /// writing every NaN the same way. See [`NbtCompound::write_canonical`].
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        self.write_with(data, &WriteOptions::default(), true);
+    }
+
+    pub(crate) fn write_with(&self, data: &mut Vec<u8>, opts: &WriteOptions, canonical: bool) {
+        if opts.empty_lists == EmptyLists::End && self.is_empty() {
+            NbtList::Empty.write(data);
+            return;
+        }
+
         // fast path for compound since it's very common to have lists of compounds
         if let NbtList::Compound(compounds) = self {
             data.reserve(5);
@@ -119,8 +213,13 @@ impl NbtList {
                 push_unchecked(data, COMPOUND_ID);
                 extend_unchecked(data, &(compounds.len() as u32).to_be_bytes());
             }
+            #[cfg(feature = "rayon")]
+            if compounds.len() >= PARALLEL_WRITE_THRESHOLD {
+                write_compounds_in_parallel(data, compounds, opts, canonical);
+                return;
+            }
             for compound in compounds {
-                compound.write(data);
+                compound.write_with(data, opts, canonical);
             }
             return;
         }
@@ -134,19 +233,27 @@ impl NbtList {
                 write_with_u32_length(data, 1, slice_i8_into_u8(bytes));
             }
             NbtList::Short(shorts) => {
-                write_with_u32_length(data, 2, &slice_into_u8_big_endian(shorts));
+                write_big_endian_with_u32_length(data, shorts);
             }
             NbtList::Int(ints) => {
-                write_with_u32_length(data, 4, &slice_into_u8_big_endian(ints));
+                write_big_endian_with_u32_length(data, ints);
             }
             NbtList::Long(longs) => {
-                write_with_u32_length(data, 8, &slice_into_u8_big_endian(longs));
+                write_big_endian_with_u32_length(data, longs);
             }
             NbtList::Float(floats) => {
-                write_with_u32_length(data, 4, &slice_into_u8_big_endian(floats));
+                let start = data.len();
+                write_big_endian_with_u32_length(data, floats);
+                if canonical {
+                    canonicalize_f32s(&mut data[start + 4..]);
+                }
             }
             NbtList::Double(doubles) => {
-                write_with_u32_length(data, 8, &slice_into_u8_big_endian(doubles));
+                let start = data.len();
+                write_big_endian_with_u32_length(data, doubles);
+                if canonical {
+                    canonicalize_f64s(&mut data[start + 4..]);
+                }
             }
             NbtList::ByteArray(byte_arrays) => {
                 write_u32(data, byte_arrays.len() as u32);
@@ -163,7 +270,7 @@ impl NbtList {
             NbtList::List(lists) => {
                 write_u32(data, lists.len() as u32);
                 for list in lists {
-                    list.write(data);
+                    list.write_with(data, opts, canonical);
                 }
             }
             NbtList::Compound(_) => {
@@ -172,13 +279,13 @@ impl NbtList {
             NbtList::IntArray(int_arrays) => {
                 write_u32(data, int_arrays.len() as u32);
                 for array in int_arrays {
-                    write_with_u32_length(data, 4, &slice_into_u8_big_endian(array));
+                    write_big_endian_with_u32_length(data, array);
                 }
             }
             NbtList::LongArray(long_arrays) => {
                 write_u32(data, long_arrays.len() as u32);
                 for array in long_arrays {
-                    write_with_u32_length(data, 8, &slice_into_u8_big_endian(array));
+                    write_big_endian_with_u32_length(data, array);
                 }
             }
         }
@@ -194,6 +301,57 @@ impl NbtList {
         unsafe { *<*const _>::from(self).cast::<u8>() }
     }
 
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        match self {
+            NbtList::Empty => 0,
+            NbtList::Byte(bytes) => bytes.len(),
+            NbtList::Short(shorts) => shorts.len(),
+            NbtList::Int(ints) => ints.len(),
+            NbtList::Long(longs) => longs.len(),
+            NbtList::Float(floats) => floats.len(),
+            NbtList::Double(doubles) => doubles.len(),
+            NbtList::ByteArray(byte_arrays) => byte_arrays.len(),
+            NbtList::String(strings) => strings.len(),
+            NbtList::List(lists) => lists.len(),
+            NbtList::Compound(compounds) => compounds.len(),
+            NbtList::IntArray(int_arrays) => int_arrays.len(),
+            NbtList::LongArray(long_arrays) => long_arrays.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Move the elements of `other` to the end of this list. If the lists
+    /// have different types and neither of them is empty, `other` is returned
+    /// back.
+    pub fn append(&mut self, other: NbtList) -> Result<(), NbtList> {
+        match (self, other) {
+            (_, NbtList::Empty) => {}
+            (this @ NbtList::Empty, other) => *this = other,
+            (NbtList::Byte(a), NbtList::Byte(mut b)) => a.append(&mut b),
+            (NbtList::Short(a), NbtList::Short(mut b)) => a.append(&mut b),
+            (NbtList::Int(a), NbtList::Int(mut b)) => a.append(&mut b),
+            (NbtList::Long(a), NbtList::Long(mut b)) => a.append(&mut b),
+            (NbtList::Float(a), NbtList::Float(mut b)) => a.append(&mut b),
+            (NbtList::Double(a), NbtList::Double(mut b)) => a.append(&mut b),
+            (NbtList::ByteArray(a), NbtList::ByteArray(mut b)) => a.append(&mut b),
+            (NbtList::String(a), NbtList::String(mut b)) => a.append(&mut b),
+            (NbtList::List(a), NbtList::List(mut b)) => a.append(&mut b),
+            (NbtList::Compound(a), NbtList::Compound(mut b)) => a.append(&mut b),
+            (NbtList::IntArray(a), NbtList::IntArray(mut b)) => a.append(&mut b),
+            (NbtList::LongArray(a), NbtList::LongArray(mut b)) => a.append(&mut b),
+            (_, other) => return Err(other),
+        }
+        Ok(())
+    }
+
+    /// Get a list of bytes as `u8`s, for values that are meant to be
+    /// unsigned.
+    pub fn ubytes(&self) -> Option<&[u8]> {
+        self.bytes().map(slice_i8_into_u8)
+    }
     pub fn bytes(&self) -> Option<&[i8]> {
         match self {
             NbtList::Byte(bytes) => Some(bytes),
@@ -350,6 +508,42 @@ impl NbtList {
         }
     }
 
+    /// Make a list from tags that all have the same type, or return `None` if
+    /// they don't.
+    pub fn from_tags(tags: Vec<super::NbtTag>) -> Option<Self> {
+        let Some(first) = tags.first() else {
+            return Some(NbtList::Empty);
+        };
+        let id = first.id();
+        if tags.iter().any(|tag| tag.id() != id) {
+            return None;
+        }
+
+        fn collect<T>(
+            tags: Vec<super::NbtTag>,
+            into: impl FnMut(super::NbtTag) -> Result<T, super::NbtTag>,
+        ) -> Vec<T> {
+            tags.into_iter()
+                .map(into)
+                .collect::<Result<_, _>>()
+                .expect("all of the tags have the same type")
+        }
+        Some(match id {
+            BYTE_ID => NbtList::Byte(collect(tags, super::NbtTag::into_byte)),
+            SHORT_ID => NbtList::Short(collect(tags, super::NbtTag::into_short)),
+            INT_ID => NbtList::Int(collect(tags, super::NbtTag::into_int)),
+            LONG_ID => NbtList::Long(collect(tags, super::NbtTag::into_long)),
+            FLOAT_ID => NbtList::Float(collect(tags, super::NbtTag::into_float)),
+            DOUBLE_ID => NbtList::Double(collect(tags, super::NbtTag::into_double)),
+            BYTE_ARRAY_ID => NbtList::ByteArray(collect(tags, super::NbtTag::into_byte_array)),
+            STRING_ID => NbtList::String(collect(tags, super::NbtTag::into_string)),
+            LIST_ID => NbtList::List(collect(tags, super::NbtTag::into_list)),
+            COMPOUND_ID => NbtList::Compound(collect(tags, super::NbtTag::into_compound)),
+            INT_ARRAY_ID => NbtList::IntArray(collect(tags, super::NbtTag::into_int_array)),
+            _ => NbtList::LongArray(collect(tags, super::NbtTag::into_long_array)),
+        })
+    }
+
     pub fn as_nbt_tags(&self) -> Vec<super::NbtTag> {
         match self {
             NbtList::Empty => vec![],
@@ -389,6 +583,50 @@ impl NbtList {
     }
 }
 
+/// Read the bytes of a list of numbers, and count the memory that copying
+/// them will take.
+fn read_numbers<'a>(
+    data: &mut Reader<'a>,
+    width: usize,
+    limits: &mut Limits,
+) -> Result<&'a [u8], NonRootError> {
+    let bytes = read_with_u32_length(data, width)?;
+    limits.allocate(bytes.len())?;
+    Ok(bytes)
+}
+
+/// Write the compounds in chunks on the rayon thread pool, each into its own
+/// buffer, and then copy the buffers into `data` in order.
+#[cfg(feature = "rayon")]
+fn write_compounds_in_parallel(
+    data: &mut Vec<u8>,
+    compounds: &[NbtCompound],
+    opts: &WriteOptions,
+    canonical: bool,
+) {
+    use rayon::prelude::*;
+
+    let chunk_size = compounds
+        .len()
+        .div_ceil(rayon::current_num_threads() * 4)
+        .max(PARALLEL_WRITE_THRESHOLD / 4);
+    let buffers = compounds
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut buffer = Vec::new();
+            for compound in chunk {
+                compound.write_with(&mut buffer, opts, canonical);
+            }
+            buffer
+        })
+        .collect::<Vec<_>>();
+
+    data.reserve(buffers.iter().map(Vec::len).sum());
+    for buffer in buffers {
+        data.extend_from_slice(&buffer);
+    }
+}
+
 impl From<Vec<i8>> for NbtList {
     fn from(bytes: Vec<i8>) -> Self {
         NbtList::Byte(bytes)
@@ -0,0 +1,232 @@
+//! Macros for building owned NBT with a syntax that looks like SNBT.
+
+/// Build an owned [`NbtCompound`](crate::owned::NbtCompound).
+///
+/// Keys are string literals (or any expression in parentheses), and values
+/// can be:
+/// - `{ ... }` for a nested compound,
+/// - `[B; ...]`, `[I; ...]` and `[L; ...]` for byte, int and long arrays,
+/// - `[ ... ]` for a list, which works like [`list!`],
+/// - or any other expression that implements [`ToNbtTag`](crate::ToNbtTag).
+///
+/// ```
+/// use simdnbt::{compound, owned::NbtList};
+///
+/// let name = "Count";
+/// let item = compound! {
+///     "id": "minecraft:diamond_sword",
+///     (name): 1i8,
+///     "components": {
+///         "minecraft:damage": 10 * 2,
+///         "minecraft:lore": ["first line", "second line"],
+///     },
+///     "heights": [L; 1, 2, 3],
+///     "pos": [0.5, 64., -0.5],
+/// };
+/// assert_eq!(item.byte("Count"), Some(1));
+/// assert_eq!(item.compound("components").unwrap().int("minecraft:damage"), Some(20));
+/// assert_eq!(item.long_array("heights"), Some(&[1, 2, 3][..]));
+/// assert_eq!(item.list("pos"), Some(&NbtList::Double(vec![0.5, 64., -0.5])));
+/// ```
+#[macro_export]
+macro_rules! compound {
+    // most compounds only have values that are a single token, which can be
+    // matched all at once without hitting the recursion limit
+    ($($key:tt : $value:tt),* $(,)?) => {{
+        // keys in parentheses would cause warnings otherwise
+        #[allow(unused_mut, unused_parens)]
+        let compound = {
+            let mut compound = $crate::owned::NbtCompound::new();
+            $(compound.insert($key, $crate::__nbt_tag!($value));)*
+            compound
+        };
+        compound
+    }};
+    ($($tt:tt)*) => {{
+        #[allow(unused_parens)]
+        let compound = {
+            let mut compound = $crate::owned::NbtCompound::new();
+            $crate::__compound_entries!(compound; $($tt)*);
+            compound
+        };
+        compound
+    }};
+}
+
+/// Build an owned [`NbtList`](crate::owned::NbtList). The elements use the
+/// same syntax as the values in [`compound!`].
+///
+/// # Panics
+///
+/// If the elements don't all have the same type.
+///
+/// ```
+/// use simdnbt::{list, owned::NbtList};
+///
+/// assert_eq!(list![1, 2, -3], NbtList::Int(vec![1, 2, -3]));
+/// assert_eq!(list![], NbtList::Empty);
+/// assert_eq!(list![{ "a": 1 }, {}].compounds().unwrap().len(), 2);
+/// ```
+#[macro_export]
+macro_rules! list {
+    () => {
+        $crate::owned::NbtList::Empty
+    };
+    ($($value:tt),+ $(,)?) => {
+        $crate::__list_from_tags!(vec![$($crate::__nbt_tag!($value)),+])
+    };
+    ($($tt:tt)+) => {{
+        #[allow(clippy::vec_init_then_push)]
+        let tags = {
+            let mut tags = Vec::new();
+            $crate::__list_elements!(tags; (); $($tt)+);
+            tags
+        };
+        $crate::__list_from_tags!(tags)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __list_from_tags {
+    ($tags:expr) => {
+        $crate::owned::NbtList::from_tags($tags)
+            .expect("all of the elements in a list must have the same type")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nbt_tag {
+    ({ $($tt:tt)* }) => {
+        $crate::owned::NbtTag::Compound($crate::compound!($($tt)*))
+    };
+    ([B; $($value:expr),* $(,)?]) => {
+        $crate::owned::NbtTag::ByteArray(vec![$($value),*])
+    };
+    ([I; $($value:expr),* $(,)?]) => {
+        $crate::owned::NbtTag::IntArray(vec![$($value),*])
+    };
+    ([L; $($value:expr),* $(,)?]) => {
+        $crate::owned::NbtTag::LongArray(vec![$($value),*])
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::owned::NbtTag::List($crate::list!($($tt)*))
+    };
+    ($value:expr) => {
+        $crate::ToNbtTag::to_nbt_tag($value)
+    };
+}
+
+/// Insert the entries of a compound, collecting the tokens of each value
+/// until the next comma.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compound_entries {
+    ($compound:ident;) => {};
+    ($compound:ident; $key:tt : $($rest:tt)*) => {
+        $crate::__compound_value!($compound; $key; (); $($rest)*);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compound_value {
+    ($compound:ident; $key:tt; ($($value:tt)+); , $($rest:tt)*) => {
+        $compound.insert($key, $crate::__nbt_tag!($($value)+));
+        $crate::__compound_entries!($compound; $($rest)*);
+    };
+    ($compound:ident; $key:tt; ($($value:tt)+);) => {
+        $compound.insert($key, $crate::__nbt_tag!($($value)+));
+    };
+    ($compound:ident; $key:tt; ($($value:tt)*); $next:tt $($rest:tt)*) => {
+        $crate::__compound_value!($compound; $key; ($($value)* $next); $($rest)*);
+    };
+}
+
+/// Push the elements of a list, collecting the tokens of each element until
+/// the next comma.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __list_elements {
+    ($tags:ident; ($($value:tt)+); , $($rest:tt)*) => {
+        $tags.push($crate::__nbt_tag!($($value)+));
+        $crate::__list_elements!($tags; (); $($rest)*);
+    };
+    ($tags:ident; ();) => {};
+    ($tags:ident; ($($value:tt)+);) => {
+        $tags.push($crate::__nbt_tag!($($value)+));
+    };
+    ($tags:ident; ($($value:tt)*); $next:tt $($rest:tt)*) => {
+        $crate::__list_elements!($tags; ($($value)* $next); $($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::owned::{NbtCompound, NbtList, NbtTag};
+
+    #[test]
+    fn compound_macro() {
+        let offset = 5;
+        let built = compound! {
+            "byte": 1i8,
+            "sum": offset + 1,
+            "negative": -offset,
+            "string": "a",
+            "nested": { "empty": {}, "list": [] },
+            "bytes": [B; 1, 255],
+            "ints": [I; -1, offset],
+            "longs": [L;],
+            "compounds": [{ "a": 1 }, { "a": offset * 2 }],
+            "lists": [[1i8], [2i8, -3i8]],
+            "method": "abc".len() as i32
+        };
+
+        let mut nested = NbtCompound::new();
+        nested.insert("empty", NbtCompound::new());
+        nested.insert("list", NbtList::Empty);
+        let mut expected = NbtCompound::new();
+        expected.insert("byte", 1i8);
+        expected.insert("sum", 6);
+        expected.insert("negative", -5);
+        expected.insert("string", "a");
+        expected.insert("nested", nested);
+        expected.insert("bytes", NbtTag::ByteArray(vec![1, 255]));
+        expected.insert("ints", NbtTag::IntArray(vec![-1, 5]));
+        expected.insert("longs", NbtTag::LongArray(vec![]));
+        expected.insert(
+            "compounds",
+            NbtList::Compound(vec![
+                NbtCompound::from_values(vec![("a".into(), NbtTag::Int(1))]),
+                NbtCompound::from_values(vec![("a".into(), NbtTag::Int(10))]),
+            ]),
+        );
+        expected.insert(
+            "lists",
+            NbtList::List(vec![NbtList::Byte(vec![1]), NbtList::Byte(vec![2, -3])]),
+        );
+        expected.insert("method", 3);
+        assert_eq!(built, expected);
+
+        assert_eq!(compound! {}, NbtCompound::new());
+        let key = String::from("key");
+        assert_eq!(compound! { (key.as_str()): 1 }.int("key"), Some(1));
+    }
+
+    #[test]
+    fn list_macro() {
+        assert_eq!(list![1, -2, 3,], NbtList::Int(vec![1, -2, 3]));
+        assert_eq!(list!["a"], NbtList::String(vec!["a".into()]));
+        assert_eq!(
+            list![[I; 1], [I;]],
+            NbtList::IntArray(vec![vec![1], vec![]])
+        );
+    }
+
+    #[test]
+    #[should_panic = "same type"]
+    fn mixed_list() {
+        list![1, "a"];
+    }
+}
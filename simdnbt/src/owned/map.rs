@@ -0,0 +1,160 @@
+use std::collections::{hash_map, HashMap};
+
+use super::{NbtCompound, NbtTag};
+use crate::{mutf8::Mutf8String, Mutf8Str, ToNbtTag};
+
+/// A compound that's backed by a hash map instead of a list.
+///
+/// Getting, inserting, and removing tags is O(1), unlike [`NbtCompound`]
+/// which has to scan all of its tags. The order of the tags isn't kept, so
+/// convert it back into an [`NbtCompound`] to write it.
+///
+/// ```
+/// # use simdnbt::owned::{NbtCompound, NbtMap};
+/// let mut compound = NbtCompound::new();
+/// compound.insert("Count", 1i8);
+///
+/// let mut map = NbtMap::from(compound);
+/// map.insert("id", "minecraft:stone");
+/// assert_eq!(map.get("Count").and_then(|tag| tag.byte()), Some(1));
+///
+/// let compound = NbtCompound::from(map);
+/// assert_eq!(compound.len(), 2);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NbtMap {
+    values: HashMap<Mutf8String, NbtTag>,
+}
+
+impl NbtMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: HashMap::with_capacity(capacity),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&NbtTag> {
+        self.values.get(Mutf8Str::from_str(name).as_ref())
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut NbtTag> {
+        self.values.get_mut(Mutf8Str::from_str(name).as_ref())
+    }
+
+    /// Returns whether there is a tag with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(Mutf8Str::from_str(name).as_ref())
+    }
+
+    /// Insert a tag, returning the tag that had the same name before if there
+    /// was one.
+    pub fn insert(&mut self, name: impl Into<Mutf8String>, tag: impl ToNbtTag) -> Option<NbtTag> {
+        self.values.insert(name.into(), tag.to_nbt_tag())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<NbtTag> {
+        self.values.remove(Mutf8Str::from_str(name).as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Mutf8Str, &NbtTag)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Mutf8Str, &mut NbtTag)> {
+        self.values.iter_mut().map(|(k, v)| (k.as_str(), v))
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &Mutf8Str> {
+        self.values.keys().map(|k| k.as_str())
+    }
+    pub fn values(&self) -> impl Iterator<Item = &NbtTag> {
+        self.values.values()
+    }
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut NbtTag> {
+        self.values.values_mut()
+    }
+}
+
+/// If the compound has duplicate keys, the first one is kept, which matches
+/// what [`NbtCompound::get`] returns.
+impl From<NbtCompound> for NbtMap {
+    fn from(compound: NbtCompound) -> Self {
+        let mut values = HashMap::with_capacity(compound.len());
+        for (name, tag) in compound {
+            values.entry(name).or_insert(tag);
+        }
+        Self { values }
+    }
+}
+
+/// The tags in the compound will be in an arbitrary order.
+impl From<NbtMap> for NbtCompound {
+    fn from(map: NbtMap) -> Self {
+        NbtCompound::from_values(map.values.into_iter().collect())
+    }
+}
+
+impl IntoIterator for NbtMap {
+    type Item = (Mutf8String, NbtTag);
+    type IntoIter = hash_map::IntoIter<Mutf8String, NbtTag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl FromIterator<(Mutf8String, NbtTag)> for NbtMap {
+    fn from_iter<T: IntoIterator<Item = (Mutf8String, NbtTag)>>(iter: T) -> Self {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map() {
+        let mut compound = NbtCompound::new();
+        compound.insert("a", 1);
+        compound.insert("b", 2);
+        compound.insert("a", 3);
+        compound.insert("ü", 4);
+
+        let mut map = NbtMap::from(compound);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("a"), Some(&NbtTag::Int(1)));
+        assert_eq!(map.get("ü"), Some(&NbtTag::Int(4)));
+        assert_eq!(map.insert("b", 5), Some(NbtTag::Int(2)));
+        assert_eq!(map.remove("a"), Some(NbtTag::Int(1)));
+        assert!(!map.contains("a"));
+
+        let mut compound = NbtCompound::from(map);
+        compound
+            .values
+            .sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+        assert_eq!(
+            compound,
+            NbtCompound::from_values(vec![
+                ("b".into(), NbtTag::Int(5)),
+                ("ü".into(), NbtTag::Int(4)),
+            ])
+        );
+    }
+}
@@ -1,23 +1,50 @@
 //! The owned variant of NBT. This is useful if you're writing NBT or if you
 //! can't keep a reference to the original data.
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
 mod compound;
+mod float_eq;
+mod incremental;
+mod interner;
 mod list;
+mod macros;
+mod map;
+mod recover;
 
-use std::{io::Cursor, ops::Deref};
+use std::{
+    io::{self, Cursor},
+    ops::Deref,
+};
 
-pub use self::{compound::NbtCompound, list::NbtList};
+#[cfg(feature = "arbitrary")]
+pub use self::arbitrary::ArbitraryLimits;
+pub use self::{
+    compound::{AppendPolicy, MergeStrategy, NbtCompound},
+    float_eq::FullEq,
+    incremental::IncrementalReader,
+    interner::{read_with_interner, Interner},
+    list::NbtList,
+    map::NbtMap,
+    recover::read_best_effort,
+};
+#[cfg(feature = "compression")]
+use crate::Compression;
 use crate::{
     common::{
-        extend_unchecked, push_unchecked, read_int_array, read_long_array, read_string,
-        read_with_u32_length, slice_into_u8_big_endian, write_string, BYTE_ARRAY_ID, BYTE_ID,
-        COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID,
-        LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
+        canonical_f32, canonical_f64, extend_big_endian, extend_unchecked, push_unchecked,
+        read_int_array, read_long_array, read_string, read_with_u32_length, slice_u8_into_i8,
+        vec_i8_into_u8, vec_u8_into_i8, write_string, BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID,
+        DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID, LONG_ARRAY_ID, LONG_ID,
+        SHORT_ID, STRING_ID,
     },
     error::NonRootError,
-    mutf8::Mutf8String,
+    io_write::ChunkedWriter,
+    little_endian::{self, convert_nbt, Endian},
+    mutf8::{Buffer, Mutf8String},
+    options::Limits,
     reader::{Reader, ReaderFromCursor},
-    Error, Mutf8Str,
+    Error, Mutf8Str, ReadOptions, TagType, WriteOptions,
 };
 
 /// Read a normal root NBT compound. This is either empty or has a name and
@@ -26,7 +53,37 @@ use crate::{
 /// Returns `Ok(Nbt::None)` if there is no data.
 pub fn read(data: &mut Cursor<&[u8]>) -> Result<Nbt, Error> {
     let mut reader = ReaderFromCursor::new(data);
-    Nbt::read(&mut reader)
+    Nbt::read(&mut reader, &mut Limits::default())
+}
+/// Read a normal root NBT compound like [`read`], but with the given options.
+/// This is useful for setting limits when reading untrusted data.
+pub fn read_with_opts(data: &mut Cursor<&[u8]>, options: &ReadOptions) -> Result<Nbt, Error> {
+    let mut limits = Limits::new(options);
+    let mut reader = ReaderFromCursor::with_limits(data, &limits);
+    Nbt::read(&mut reader, &mut limits).map_err(|err| reader.map_err(err))
+}
+/// Read a normal root NBT compound like [`read`], but from a slice instead of
+/// a cursor. This also returns how many bytes the NBT took up, so data after
+/// it can be read.
+pub fn read_slice(data: &[u8]) -> Result<(Nbt, usize), Error> {
+    read_slice_with_opts(data, &ReadOptions::default())
+}
+/// Read a normal root NBT compound from a slice like [`read_slice`], but with
+/// the given options.
+pub fn read_slice_with_opts(data: &[u8], options: &ReadOptions) -> Result<(Nbt, usize), Error> {
+    let mut cursor = Cursor::new(data);
+    let nbt = read_with_opts(&mut cursor, options)?;
+    Ok((nbt, cursor.position() as usize))
+}
+/// Read a normal root NBT compound like [`read`], but little-endian. This is
+/// what Bedrock Edition uses for files like `level.dat`.
+pub fn read_le(data: &mut Cursor<&[u8]>) -> Result<Nbt, Error> {
+    let position = data.position() as usize;
+    let mut converted = data.get_ref().get(position..).unwrap_or_default().to_vec();
+    let len = convert_nbt(&mut converted, Endian::Little)?;
+    let nbt = read(&mut Cursor::new(&converted[..len]))?;
+    data.set_position((position + len) as u64);
+    Ok(nbt)
 }
 /// Read a root NBT compound, but without reading the name. This is used in
 /// Minecraft when reading NBT over the network.
@@ -35,12 +92,22 @@ pub fn read(data: &mut Cursor<&[u8]>) -> Result<Nbt, Error> {
 /// (guaranteeing it'll be either empty or a compound).
 pub fn read_unnamed(data: &mut Cursor<&[u8]>) -> Result<Nbt, Error> {
     let mut reader = ReaderFromCursor::new(data);
-    Nbt::read_unnamed(&mut reader)
+    Nbt::read_unnamed(&mut reader, &mut Limits::default())
+}
+/// Read a root NBT compound without a name like [`read_unnamed`], but with the
+/// given options.
+pub fn read_unnamed_with_opts(
+    data: &mut Cursor<&[u8]>,
+    options: &ReadOptions,
+) -> Result<Nbt, Error> {
+    let mut limits = Limits::new(options);
+    let mut reader = ReaderFromCursor::with_limits(data, &limits);
+    Nbt::read_unnamed(&mut reader, &mut limits).map_err(|err| reader.map_err(err))
 }
 /// Read a compound tag. This may have any number of items.
 pub fn read_compound(data: &mut Cursor<&[u8]>) -> Result<NbtCompound, NonRootError> {
     let mut reader = ReaderFromCursor::new(data);
-    NbtCompound::read(&mut reader)
+    NbtCompound::read(&mut reader, &mut Limits::default())
 }
 /// Read an NBT tag, without reading its name. This may be any type of tag
 /// except for an end tag. If you need to be able to handle end tags, use
@@ -77,9 +144,22 @@ impl Nbt {
         Self::Some(BaseNbt { name, tag })
     }
 
+    /// Read a normal root NBT compound like [`read`], but decompress it first
+    /// if it's gzipped or zlib compressed.
+    #[cfg(feature = "compression")]
+    pub fn read_compressed(data: &[u8]) -> Result<Nbt, Error> {
+        let compression = Compression::detect(data);
+        if compression == Compression::None {
+            return read(&mut Cursor::new(data));
+        }
+        let mut buffer = Vec::new();
+        compression.decompress(data, &mut buffer)?;
+        read(&mut Cursor::new(&buffer))
+    }
+
     /// Reads NBT from the given data. Returns `Ok(Nbt::None)` if there is no
     /// data.
-    fn read(data: &mut Reader<'_>) -> Result<Nbt, Error> {
+    fn read(data: &mut Reader<'_>, limits: &mut Limits) -> Result<Nbt, Error> {
         let root_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
         if root_type == END_ID {
             return Ok(Nbt::None);
@@ -88,12 +168,13 @@ impl Nbt {
             return Err(Error::InvalidRootType(root_type));
         }
         let name = read_string(data)?.to_owned();
-        let tag = NbtCompound::read(data)?;
+        limits.count_tags(1)?;
+        let tag = NbtCompound::read(data, limits)?;
 
         Ok(Nbt::Some(BaseNbt { name, tag }))
     }
 
-    fn read_unnamed(data: &mut Reader<'_>) -> Result<Nbt, Error> {
+    fn read_unnamed(data: &mut Reader<'_>, limits: &mut Limits) -> Result<Nbt, Error> {
         let root_type = data.read_u8().map_err(|_| Error::UnexpectedEof)?;
         if root_type == END_ID {
             return Ok(Nbt::None);
@@ -101,7 +182,8 @@ impl Nbt {
         if root_type != COMPOUND_ID {
             return Err(Error::InvalidRootType(root_type));
         }
-        let tag = NbtCompound::read(data)?;
+        limits.count_tags(1)?;
+        let tag = NbtCompound::read(data, limits)?;
 
         Ok(Nbt::Some(BaseNbt {
             name: Mutf8String::from(""),
@@ -110,14 +192,70 @@ impl Nbt {
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
         match self {
-            Nbt::Some(nbt) => nbt.write(data),
+            Nbt::Some(nbt) => nbt.write_with_opts(data, opts),
             Nbt::None => {
                 data.push(END_ID);
             }
         }
     }
 
+    /// Write the NBT to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// The number of bytes that [`Self::write`] would write, without writing
+    /// anything. See [`BaseNbt::serialized_size`].
+    pub fn serialized_size(&self) -> usize {
+        match self {
+            Nbt::Some(nbt) => nbt.serialized_size(),
+            Nbt::None => 1,
+        }
+    }
+
+    /// Write the NBT to an [`io::Write`] like [`Self::write`]. See
+    /// [`BaseNbt::write_to`].
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the NBT like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        match self {
+            Nbt::Some(nbt) => nbt.write_to_with_opts(out, opts),
+            Nbt::None => {
+                let mut writer = ChunkedWriter::new(out, opts);
+                writer.push(END_ID);
+                writer.finish()
+            }
+        }
+    }
+
+    /// Write the NBT to a new buffer and compress it. Use this for files like
+    /// `level.dat`, which are gzipped.
+    #[cfg(feature = "compression")]
+    pub fn to_compressed_bytes(&self, compression: Compression) -> Vec<u8> {
+        compression.compress(self.to_bytes())
+    }
+
+    /// Write the NBT like [`Self::write`], but little-endian. See
+    /// [`read_le`].
+    pub fn write_le(&self, data: &mut Vec<u8>) {
+        match self {
+            Nbt::Some(nbt) => nbt.write_le(data),
+            Nbt::None => data.push(END_ID),
+        }
+    }
+
     pub fn write_unnamed(&self, data: &mut Vec<u8>) {
         match self {
             Nbt::Some(nbt) => nbt.write_unnamed(data),
@@ -153,7 +291,10 @@ impl Nbt {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&Mutf8Str, &NbtTag)> {
-        const EMPTY: &NbtCompound = &NbtCompound { values: Vec::new() };
+        const EMPTY: &NbtCompound = &NbtCompound {
+            values: Vec::new(),
+            index: None,
+        };
 
         if let Nbt::Some(nbt) = self {
             nbt.iter()
@@ -167,8 +308,13 @@ impl Deref for Nbt {
 
     fn deref(&self) -> &Self::Target {
         const EMPTY: &BaseNbt = &BaseNbt {
-            name: Mutf8String { vec: Vec::new() },
-            tag: NbtCompound { values: Vec::new() },
+            name: Mutf8String {
+                buffer: Buffer::Owned(Vec::new()),
+            },
+            tag: NbtCompound {
+                values: Vec::new(),
+                index: None,
+            },
         };
 
         match self {
@@ -183,7 +329,10 @@ impl IntoIterator for Nbt {
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        const EMPTY: NbtCompound = NbtCompound { values: Vec::new() };
+        const EMPTY: NbtCompound = NbtCompound {
+            values: Vec::new(),
+            index: None,
+        };
 
         match self {
             Nbt::Some(nbt) => nbt.tag.into_iter(),
@@ -205,9 +354,77 @@ impl BaseNbt {
 
     /// Writes the NBT to the given buffer.
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the NBT like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
         data.push(COMPOUND_ID);
         write_string(data, &self.name);
-        self.tag.write(data);
+        self.tag.write_with_opts(data, opts);
+    }
+
+    /// Write the NBT with the keys of every compound sorted and every NaN
+    /// written the same way. See [`NbtCompound::write_canonical`].
+    pub fn write_canonical(&self, data: &mut Vec<u8>) {
+        data.push(COMPOUND_ID);
+        write_string(data, &self.name);
+        self.tag.write_canonical(data);
+    }
+
+    /// Write the NBT to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// The number of bytes that [`Self::write`] would write, without writing
+    /// anything. This is the same for every [`WriteOptions`].
+    ///
+    /// This is useful for allocating a buffer of the right size, writing a
+    /// length prefix before the NBT, or rejecting NBT that's too big before
+    /// writing it.
+    pub fn serialized_size(&self) -> usize {
+        1 + 2 + self.name.len() + self.tag.serialized_size()
+    }
+
+    /// Write the NBT to an [`io::Write`] like [`Self::write`].
+    ///
+    /// The data is written in chunks as it's serialized, so the whole
+    /// document never has to be in memory at once. The writer doesn't need to
+    /// be buffered.
+    ///
+    /// ```no_run
+    /// # let nbt = simdnbt::owned::BaseNbt::default();
+    /// let file = std::fs::File::create("export.nbt").unwrap();
+    /// nbt.write_to(file).unwrap();
+    /// ```
+    pub fn write_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.write_to_with_opts(out, &WriteOptions::default())
+    }
+
+    /// Write the NBT like [`Self::write_to`], but with the given options.
+    pub fn write_to_with_opts(&self, out: impl io::Write, opts: &WriteOptions) -> io::Result<()> {
+        let mut writer = ChunkedWriter::new(out, opts);
+        writer.base(&self.name);
+        writer.owned_compound(&self.tag)?;
+        writer.finish()
+    }
+
+    /// Write the NBT to a new buffer and compress it. Use this for files like
+    /// `level.dat`, which are gzipped.
+    #[cfg(feature = "compression")]
+    pub fn to_compressed_bytes(&self, compression: Compression) -> Vec<u8> {
+        compression.compress(self.to_bytes())
+    }
+
+    /// Write the NBT like [`Self::write`], but little-endian. See
+    /// [`read_le`].
+    pub fn write_le(&self, data: &mut Vec<u8>) {
+        data.push(COMPOUND_ID);
+        little_endian::write_root(&self.name, &self.tag, data);
     }
 
     pub fn write_unnamed(&self, data: &mut Vec<u8>) {
@@ -265,11 +482,30 @@ impl NbtTag {
         unsafe { *<*const _>::from(self).cast::<u8>() }
     }
 
+    /// Get the type of the tag.
+    pub fn tag_type(&self) -> TagType {
+        match self {
+            NbtTag::Byte(_) => TagType::Byte,
+            NbtTag::Short(_) => TagType::Short,
+            NbtTag::Int(_) => TagType::Int,
+            NbtTag::Long(_) => TagType::Long,
+            NbtTag::Float(_) => TagType::Float,
+            NbtTag::Double(_) => TagType::Double,
+            NbtTag::ByteArray(_) => TagType::ByteArray,
+            NbtTag::String(_) => TagType::String,
+            NbtTag::List(_) => TagType::List,
+            NbtTag::Compound(_) => TagType::Compound,
+            NbtTag::IntArray(_) => TagType::IntArray,
+            NbtTag::LongArray(_) => TagType::LongArray,
+        }
+    }
+
     #[inline(always)]
     fn read_with_type(
         data: &mut Reader<'_>,
         tag_type: u8,
         depth: usize,
+        limits: &mut Limits,
     ) -> Result<Self, NonRootError> {
         match tag_type {
             BYTE_ID => Ok(NbtTag::Byte(
@@ -295,22 +531,39 @@ impl NbtTag {
                 data.read_f64()
                     .map_err(|_| NonRootError::unexpected_eof())?,
             )),
-            BYTE_ARRAY_ID => Ok(NbtTag::ByteArray(read_with_u32_length(data, 1)?.to_owned())),
-            STRING_ID => Ok(NbtTag::String(read_string(data)?.to_owned())),
-            LIST_ID => Ok(NbtTag::List(NbtList::read(data, depth + 1)?)),
+            BYTE_ARRAY_ID => {
+                let bytes = read_with_u32_length(data, 1)?;
+                limits.allocate(bytes.len())?;
+                Ok(NbtTag::ByteArray(bytes.to_owned()))
+            }
+            STRING_ID => {
+                let string = read_string(data)?;
+                limits.allocate(string.len())?;
+                Ok(NbtTag::String(string.to_owned()))
+            }
+            LIST_ID => Ok(NbtTag::List(NbtList::read(data, depth + 1, limits)?)),
             COMPOUND_ID => Ok(NbtTag::Compound(NbtCompound::read_with_depth(
                 data,
                 depth + 1,
+                limits,
             )?)),
-            INT_ARRAY_ID => Ok(NbtTag::IntArray(read_int_array(data)?.to_vec())),
-            LONG_ARRAY_ID => Ok(NbtTag::LongArray(read_long_array(data)?.to_vec())),
+            INT_ARRAY_ID => {
+                let ints = read_int_array(data)?;
+                limits.allocate(ints.as_big_endian().len())?;
+                Ok(NbtTag::IntArray(ints.to_vec()))
+            }
+            LONG_ARRAY_ID => {
+                let longs = read_long_array(data)?;
+                limits.allocate(longs.as_big_endian().len())?;
+                Ok(NbtTag::LongArray(longs.to_vec()))
+            }
             _ => Err(NonRootError::unknown_tag_id(tag_type)),
         }
     }
 
     fn read(data: &mut Reader<'_>) -> Result<Self, NonRootError> {
         let tag_type = data.read_u8().map_err(|_| NonRootError::unexpected_eof())?;
-        Self::read_with_type(data, tag_type, 0)
+        Self::read_with_type(data, tag_type, 0, &mut Limits::default())
     }
 
     fn read_optional(data: &mut Reader<'_>) -> Result<Option<Self>, NonRootError> {
@@ -318,7 +571,12 @@ impl NbtTag {
         if tag_type == END_ID {
             return Ok(None);
         }
-        Ok(Some(Self::read_with_type(data, tag_type, 0)?))
+        Ok(Some(Self::read_with_type(
+            data,
+            tag_type,
+            0,
+            &mut Limits::default(),
+        )?))
     }
 
     /// Write to the data without checking that there's enough space in it.
@@ -329,7 +587,12 @@ impl NbtTag {
     /// space in the data. 4 bytes MUST be reserved before calling this
     /// function.
     #[inline]
-    unsafe fn write_without_tag_type_unchecked(&self, data: &mut Vec<u8>) {
+    pub(crate) unsafe fn write_without_tag_type_unchecked(
+        &self,
+        data: &mut Vec<u8>,
+        opts: &WriteOptions,
+        canonical: bool,
+    ) {
         match self {
             NbtTag::Byte(byte) => unsafe {
                 push_unchecked(data, *byte as u8);
@@ -343,10 +606,20 @@ impl NbtTag {
             NbtTag::Long(long) => {
                 data.extend_from_slice(&long.to_be_bytes());
             }
-            NbtTag::Float(float) => unsafe {
-                extend_unchecked(data, &float.to_be_bytes());
-            },
+            NbtTag::Float(float) => {
+                let float = if canonical {
+                    canonical_f32(*float)
+                } else {
+                    *float
+                };
+                unsafe { extend_unchecked(data, &float.to_be_bytes()) };
+            }
             NbtTag::Double(double) => {
+                let double = if canonical {
+                    canonical_f64(*double)
+                } else {
+                    *double
+                };
                 data.extend_from_slice(&double.to_be_bytes());
             }
             NbtTag::ByteArray(byte_array) => {
@@ -359,32 +632,67 @@ impl NbtTag {
                 write_string(data, string);
             }
             NbtTag::List(list) => {
-                list.write(data);
+                list.write_with(data, opts, canonical);
             }
             NbtTag::Compound(compound) => {
-                compound.write(data);
+                compound.write_with(data, opts, canonical);
             }
             NbtTag::IntArray(int_array) => {
                 unsafe {
                     extend_unchecked(data, &(int_array.len() as u32).to_be_bytes());
                 }
-                data.extend_from_slice(&slice_into_u8_big_endian(int_array));
+                extend_big_endian(data, int_array);
             }
             NbtTag::LongArray(long_array) => {
                 unsafe {
                     extend_unchecked(data, &(long_array.len() as u32).to_be_bytes());
                 }
-                data.extend_from_slice(&slice_into_u8_big_endian(long_array));
+                extend_big_endian(data, long_array);
             }
         }
     }
 
     pub fn write(&self, data: &mut Vec<u8>) {
+        self.write_with_opts(data, &WriteOptions::default());
+    }
+
+    /// Write the tag like [`Self::write`], but with the given options.
+    pub fn write_with_opts(&self, data: &mut Vec<u8>, opts: &WriteOptions) {
         data.reserve(1 + 4);
         // SAFETY: We just reserved enough space for the tag ID and 4 bytes of tag data.
         unsafe {
             push_unchecked(data, self.id());
-            self.write_without_tag_type_unchecked(data);
+            self.write_without_tag_type_unchecked(data, opts, false);
+        }
+    }
+
+    /// Write the tag to a new buffer. This is the same as calling
+    /// [`Self::write`] with an empty `Vec`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        data
+    }
+
+    /// The number of bytes that [`Self::write`] would write, including the tag
+    /// type. See [`BaseNbt::serialized_size`].
+    pub fn serialized_size(&self) -> usize {
+        1 + self.payload_size()
+    }
+
+    /// The number of bytes that are written for the tag without its type.
+    pub(crate) fn payload_size(&self) -> usize {
+        match self {
+            NbtTag::Byte(_) => 1,
+            NbtTag::Short(_) => 2,
+            NbtTag::Int(_) | NbtTag::Float(_) => 4,
+            NbtTag::Long(_) | NbtTag::Double(_) => 8,
+            NbtTag::ByteArray(byte_array) => 4 + byte_array.len(),
+            NbtTag::String(string) => 2 + string.len(),
+            NbtTag::List(list) => list.serialized_size(),
+            NbtTag::Compound(compound) => compound.serialized_size(),
+            NbtTag::IntArray(int_array) => 4 + int_array.len() * 4,
+            NbtTag::LongArray(long_array) => 4 + long_array.len() * 8,
         }
     }
 
@@ -394,16 +702,22 @@ impl NbtTag {
             _ => None,
         }
     }
+    /// Get the byte as a `u8`, for values that are meant to be unsigned.
+    pub fn ubyte(&self) -> Option<u8> {
+        self.byte().map(|b| b as u8)
+    }
     pub fn byte_mut(&mut self) -> Option<&mut i8> {
         match self {
             NbtTag::Byte(byte) => Some(byte),
             _ => None,
         }
     }
-    pub fn into_byte(self) -> Option<i8> {
+    /// Take the byte out of the tag, or give the tag back if it's a different
+    /// type. The other `into_*` methods work the same way.
+    pub fn into_byte(self) -> Result<i8, Self> {
         match self {
-            NbtTag::Byte(byte) => Some(byte),
-            _ => None,
+            NbtTag::Byte(byte) => Ok(byte),
+            tag => Err(tag),
         }
     }
 
@@ -419,10 +733,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_short(self) -> Option<i16> {
+    pub fn into_short(self) -> Result<i16, Self> {
         match self {
-            NbtTag::Short(short) => Some(short),
-            _ => None,
+            NbtTag::Short(short) => Ok(short),
+            tag => Err(tag),
         }
     }
 
@@ -438,10 +752,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_int(self) -> Option<i32> {
+    pub fn into_int(self) -> Result<i32, Self> {
         match self {
-            NbtTag::Int(int) => Some(int),
-            _ => None,
+            NbtTag::Int(int) => Ok(int),
+            tag => Err(tag),
         }
     }
 
@@ -457,10 +771,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_long(self) -> Option<i64> {
+    pub fn into_long(self) -> Result<i64, Self> {
         match self {
-            NbtTag::Long(long) => Some(long),
-            _ => None,
+            NbtTag::Long(long) => Ok(long),
+            tag => Err(tag),
         }
     }
 
@@ -476,10 +790,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_float(self) -> Option<f32> {
+    pub fn into_float(self) -> Result<f32, Self> {
         match self {
-            NbtTag::Float(float) => Some(float),
-            _ => None,
+            NbtTag::Float(float) => Ok(float),
+            tag => Err(tag),
         }
     }
 
@@ -495,10 +809,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_double(self) -> Option<f64> {
+    pub fn into_double(self) -> Result<f64, Self> {
         match self {
-            NbtTag::Double(double) => Some(double),
-            _ => None,
+            NbtTag::Double(double) => Ok(double),
+            tag => Err(tag),
         }
     }
 
@@ -514,12 +828,28 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_byte_array(self) -> Option<Vec<u8>> {
+    pub fn into_byte_array(self) -> Result<Vec<u8>, Self> {
         match self {
-            NbtTag::ByteArray(byte_array) => Some(byte_array),
-            _ => None,
+            NbtTag::ByteArray(byte_array) => Ok(byte_array),
+            tag => Err(tag),
         }
     }
+    /// Get the byte array as signed bytes, which is what they are in NBT.
+    /// This doesn't copy the data.
+    pub fn byte_array_i8(&self) -> Option<&[i8]> {
+        self.byte_array().map(slice_u8_into_i8)
+    }
+    /// Take the byte array out of the tag as signed bytes, without copying it.
+    pub fn into_i8_vec(self) -> Result<Vec<i8>, Self> {
+        self.into_byte_array().map(vec_u8_into_i8)
+    }
+    /// Create a byte array tag from signed bytes, without copying them.
+    ///
+    /// Note that `Vec<i8>` converts into a list of bytes with
+    /// [`ToNbtTag`](crate::ToNbtTag), not a byte array.
+    pub fn byte_array_from_i8(byte_array: Vec<i8>) -> Self {
+        NbtTag::ByteArray(vec_i8_into_u8(byte_array))
+    }
 
     pub fn string(&self) -> Option<&Mutf8Str> {
         match self {
@@ -533,10 +863,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_string(self) -> Option<Mutf8String> {
+    pub fn into_string(self) -> Result<Mutf8String, Self> {
         match self {
-            NbtTag::String(string) => Some(string),
-            _ => None,
+            NbtTag::String(string) => Ok(string),
+            tag => Err(tag),
         }
     }
 
@@ -552,10 +882,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_list(self) -> Option<NbtList> {
+    pub fn into_list(self) -> Result<NbtList, Self> {
         match self {
-            NbtTag::List(list) => Some(list),
-            _ => None,
+            NbtTag::List(list) => Ok(list),
+            tag => Err(tag),
         }
     }
 
@@ -571,10 +901,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_compound(self) -> Option<NbtCompound> {
+    pub fn into_compound(self) -> Result<NbtCompound, Self> {
         match self {
-            NbtTag::Compound(compound) => Some(compound),
-            _ => None,
+            NbtTag::Compound(compound) => Ok(compound),
+            tag => Err(tag),
         }
     }
 
@@ -590,10 +920,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_int_array(self) -> Option<Vec<i32>> {
+    pub fn into_int_array(self) -> Result<Vec<i32>, Self> {
         match self {
-            NbtTag::IntArray(int_array) => Some(int_array),
-            _ => None,
+            NbtTag::IntArray(int_array) => Ok(int_array),
+            tag => Err(tag),
         }
     }
 
@@ -609,10 +939,10 @@ impl NbtTag {
             _ => None,
         }
     }
-    pub fn into_long_array(self) -> Option<Vec<i64>> {
+    pub fn into_long_array(self) -> Result<Vec<i64>, Self> {
         match self {
-            NbtTag::LongArray(long_array) => Some(long_array),
-            _ => None,
+            NbtTag::LongArray(long_array) => Ok(long_array),
+            tag => Err(tag),
         }
     }
 }
@@ -635,6 +965,77 @@ impl From<Nbt> for NbtTag {
     }
 }
 
+// conversions between tags and the types that they contain, so a tag can be
+// made with `.into()` and taken apart with `.try_into()`
+macro_rules! impl_tag_conversions {
+    ($($variant:ident($t:ty) => $into:ident),* $(,)?) => {
+        $(
+            impl From<$t> for NbtTag {
+                fn from(value: $t) -> Self {
+                    NbtTag::$variant(value)
+                }
+            }
+            impl TryFrom<NbtTag> for $t {
+                /// The tag is given back if it's a different type.
+                type Error = NbtTag;
+
+                fn try_from(tag: NbtTag) -> Result<Self, Self::Error> {
+                    tag.$into()
+                }
+            }
+        )*
+    };
+}
+impl_tag_conversions! {
+    Byte(i8) => into_byte,
+    Short(i16) => into_short,
+    Int(i32) => into_int,
+    Long(i64) => into_long,
+    Float(f32) => into_float,
+    Double(f64) => into_double,
+    ByteArray(Vec<u8>) => into_byte_array,
+    String(Mutf8String) => into_string,
+    List(NbtList) => into_list,
+    Compound(NbtCompound) => into_compound,
+    IntArray(Vec<i32>) => into_int_array,
+    LongArray(Vec<i64>) => into_long_array,
+}
+
+impl From<bool> for NbtTag {
+    /// Booleans are stored as a byte that's either 0 or 1.
+    fn from(value: bool) -> Self {
+        NbtTag::Byte(value as i8)
+    }
+}
+impl TryFrom<NbtTag> for bool {
+    type Error = NbtTag;
+
+    fn try_from(tag: NbtTag) -> Result<Self, Self::Error> {
+        tag.into_byte().map(|byte| byte != 0)
+    }
+}
+impl From<String> for NbtTag {
+    fn from(value: String) -> Self {
+        NbtTag::String(value.into())
+    }
+}
+impl From<&str> for NbtTag {
+    fn from(value: &str) -> Self {
+        NbtTag::String(value.into())
+    }
+}
+impl TryFrom<NbtTag> for String {
+    type Error = NbtTag;
+
+    /// Returns the tag back if it isn't a string or isn't valid MUTF-8.
+    fn try_from(tag: NbtTag) -> Result<Self, Self::Error> {
+        match tag {
+            NbtTag::String(string) if string.try_to_str().is_ok() => Ok(string.into_string()),
+            tag => Err(tag),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Read;
@@ -643,6 +1044,7 @@ mod tests {
     use flate2::read::GzDecoder;
 
     use super::*;
+    use crate::test_fixtures;
 
     #[test]
     fn hello_world() {
@@ -806,4 +1208,397 @@ mod tests {
 
         assert_ne!(nbt, modified_nbt);
     }
+
+    #[test]
+    fn ensure_path() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("a", 1);
+        nbt.ensure_path("a.b.c").insert("d", 2);
+        nbt.ensure_path("a.b").insert("e", 3);
+        *nbt.ensure_list("a.items") = NbtList::from(vec![1i32, 2, 3]);
+        nbt.ensure_list("a.b.empty");
+
+        assert_eq!(nbt.len(), 1);
+        let a = nbt.compound("a").unwrap();
+        assert_eq!(
+            a.compound("b").unwrap().compound("c").unwrap().int("d"),
+            Some(2)
+        );
+        assert_eq!(a.compound("b").unwrap().int("e"), Some(3));
+        assert_eq!(a.list("items").unwrap().ints(), Some(vec![1, 2, 3]));
+        assert_eq!(
+            a.compound("b").unwrap().list("empty"),
+            Some(&NbtList::Empty)
+        );
+        assert_eq!(nbt.ensure_list("a.items").ints(), Some(vec![1, 2, 3]));
+        assert_eq!(nbt.ensure_path("").len(), 1);
+    }
+
+    #[test]
+    fn typed_list_getters() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("Pos", NbtList::Double(vec![1., 2., 3.]));
+        nbt.insert("Tags", NbtList::String(vec!["a".into()]));
+        nbt.insert("Items", NbtList::Compound(vec![NbtCompound::new()]));
+
+        assert_eq!(nbt.double_list("Pos"), Some(&[1., 2., 3.][..]));
+        assert_eq!(nbt.string_list("Tags").unwrap()[0].to_str(), "a");
+        assert_eq!(nbt.compound_list("Items").unwrap().len(), 1);
+        assert_eq!(nbt.float_list("Pos"), None);
+        assert_eq!(nbt.compound_list("Tags"), None);
+        assert_eq!(nbt.int_list("missing"), None);
+    }
+
+    #[test]
+    fn into_value() {
+        let mut compound = NbtCompound::new();
+        compound.insert("a", 1);
+        let tag = NbtTag::Compound(compound.clone());
+
+        let tag = tag.into_list().unwrap_err();
+        assert_eq!(tag.into_compound(), Ok(compound));
+        assert_eq!(NbtTag::Int(1).into_int(), Ok(1));
+        assert_eq!(NbtTag::Int(1).into_string(), Err(NbtTag::Int(1)));
+    }
+
+    #[test]
+    fn invalid_string_round_trip() {
+        let mut data = vec![COMPOUND_ID, 0, 0, STRING_ID, 0, 1, b'a', 0, 3];
+        // a lone surrogate and a null byte, neither of which is valid mutf-8
+        data.extend([0xed, 0xa0, 0x00]);
+        data.push(END_ID);
+
+        let nbt = read(&mut Cursor::new(&data)).unwrap();
+        let mut written = Vec::new();
+        nbt.write(&mut written);
+        assert_eq!(written, data);
+    }
+
+    #[test]
+    fn unsigned_bytes() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("SkyLight", -1i8);
+        nbt.insert("Biomes", NbtList::Byte(vec![1, -128]));
+        assert_eq!(nbt.ubyte("SkyLight"), Some(255));
+        assert_eq!(nbt.list("Biomes").unwrap().ubytes(), Some(&[1, 128][..]));
+        assert_eq!(nbt.ubyte("Biomes"), None);
+    }
+
+    #[test]
+    fn write_empty_lists() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("Items", NbtList::Compound(vec![]));
+        nbt.insert("Tags", NbtList::Empty);
+        let nbt = BaseNbt::new("", nbt);
+
+        let mut preserved = Vec::new();
+        nbt.write(&mut preserved);
+        let read_back = read(&mut Cursor::new(&preserved)).unwrap().unwrap();
+        assert_eq!(read_back.list("Items"), Some(&NbtList::Compound(vec![])));
+
+        let mut normalized = Vec::new();
+        let opts = WriteOptions {
+            empty_lists: crate::EmptyLists::End,
+        };
+        nbt.write_with_opts(&mut normalized, &opts);
+        let read_back = read(&mut Cursor::new(&normalized)).unwrap().unwrap();
+        assert_eq!(read_back.list("Items"), Some(&NbtList::Empty));
+        assert_eq!(preserved.len(), normalized.len());
+        assert_ne!(preserved, normalized);
+    }
+
+    #[test]
+    fn append() {
+        let compound = |values: &[(&str, i32)]| {
+            let mut compound = NbtCompound::new();
+            compound.extend(values.iter().copied());
+            compound
+        };
+        let base = compound(&[("a", 1), ("b", 2)]);
+        let other = compound(&[("b", 3), ("c", 4), ("c", 5)]);
+
+        let mut keep_all = base.clone();
+        keep_all.append(other.clone(), AppendPolicy::KeepAll);
+        assert_eq!(
+            keep_all,
+            compound(&[("a", 1), ("b", 2), ("b", 3), ("c", 4), ("c", 5)])
+        );
+
+        let mut keep_existing = base.clone();
+        keep_existing.append(other.clone(), AppendPolicy::KeepExisting);
+        assert_eq!(
+            keep_existing,
+            compound(&[("a", 1), ("b", 2), ("c", 4), ("c", 5)])
+        );
+
+        let mut replace = base.clone();
+        replace.append(other, AppendPolicy::Replace);
+        assert_eq!(replace, compound(&[("a", 1), ("b", 3), ("c", 4), ("c", 5)]));
+    }
+
+    #[test]
+    fn compound_index() {
+        let mut compound = NbtCompound::new();
+        compound.insert("a", 1);
+        compound.insert("b", 2);
+        compound.insert("a", 3);
+        let unindexed = compound.clone();
+        compound.build_index();
+        assert_eq!(compound, unindexed);
+
+        // the first tag with a name wins, like without an index
+        assert_eq!(compound.int("a"), Some(1));
+        compound.insert("c", 4);
+        assert_eq!(compound.int("c"), Some(4));
+        assert_eq!(compound.remove("a"), Some(NbtTag::Int(1)));
+        assert_eq!(compound.int("a"), Some(3));
+        assert_eq!(compound.int("b"), Some(2));
+        compound.ensure_path("d.e");
+        assert!(compound.compound("d").unwrap().contains("e"));
+        compound.append(
+            NbtCompound::from_values(vec![("b".into(), NbtTag::Int(5))]),
+            AppendPolicy::Replace,
+        );
+        assert_eq!(compound.int("b"), Some(5));
+        assert_eq!(
+            compound.keys().map(|k| k.to_str()).collect::<Vec<_>>(),
+            ["b", "a", "c", "d"]
+        );
+
+        compound.clear();
+        assert!(compound.has_index());
+        assert!(!compound.contains("b"));
+    }
+
+    #[test]
+    fn tag_conversions() {
+        assert_eq!(NbtTag::from(5), NbtTag::Int(5));
+        assert_eq!(NbtTag::from(true), NbtTag::Byte(1));
+        assert_eq!(NbtTag::from("a"), NbtTag::String("a".into()));
+        assert_eq!(NbtTag::from(vec![1i64]), NbtTag::LongArray(vec![1]));
+        assert_eq!(
+            NbtTag::from(NbtList::Int(vec![1])),
+            NbtTag::List(NbtList::Int(vec![1]))
+        );
+
+        assert_eq!(i32::try_from(NbtTag::Int(5)), Ok(5));
+        assert_eq!(i64::try_from(NbtTag::Int(5)), Err(NbtTag::Int(5)));
+        assert_eq!(bool::try_from(NbtTag::Byte(2)), Ok(true));
+        assert_eq!(String::try_from(NbtTag::from("a")), Ok("a".to_owned()));
+        let invalid = NbtTag::String(Mutf8String::from_vec(vec![b'a', 0xff]));
+        assert_eq!(String::try_from(invalid.clone()), Err(invalid));
+        let ints: Result<Vec<i32>, _> = NbtTag::IntArray(vec![1, 2]).try_into();
+        assert_eq!(ints, Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn merge() {
+        let nested = |name: &str, value: i32| {
+            NbtCompound::from_values(vec![(name.into(), NbtTag::Int(value))])
+        };
+        let mut a = NbtCompound::new();
+        a.insert("x", 1);
+        a.insert("nested", nested("a", 1));
+        a.insert("ints", NbtList::Int(vec![1]));
+        a.insert("empty", NbtList::Empty);
+        let mut b = NbtCompound::new();
+        b.insert("x", 2);
+        b.insert("nested", nested("a", 2));
+        b.insert("ints", NbtList::Int(vec![2]));
+        b.insert("empty", NbtList::Long(vec![3]));
+        b.insert("y", "new");
+
+        let merged = |strategy| {
+            let mut a = a.clone();
+            a.merge(b.clone(), strategy);
+            a
+        };
+
+        let replaced = merged(MergeStrategy::Replace);
+        assert_eq!(replaced.int("x"), Some(2));
+        assert_eq!(replaced.compound("nested"), Some(&nested("a", 2)));
+        assert_eq!(replaced.list("ints"), Some(&NbtList::Int(vec![2])));
+        assert_eq!(replaced.string("y").unwrap().to_str(), "new");
+
+        let kept = merged(MergeStrategy::KeepExisting);
+        assert_eq!(kept.int("x"), Some(1));
+        assert_eq!(kept.compound("nested"), Some(&nested("a", 1)));
+        assert_eq!(kept.list("empty"), Some(&NbtList::Empty));
+        assert_eq!(kept.string("y").unwrap().to_str(), "new");
+
+        let concatenated = merged(MergeStrategy::ConcatLists);
+        assert_eq!(concatenated.int("x"), Some(2));
+        assert_eq!(concatenated.list("ints"), Some(&NbtList::Int(vec![1, 2])));
+        assert_eq!(concatenated.list("empty"), Some(&NbtList::Long(vec![3])));
+
+        // lists with different types are replaced
+        let mut c = a.clone();
+        c.merge(
+            NbtCompound::from_values(vec![("ints".into(), NbtTag::List(NbtList::Byte(vec![1])))]),
+            MergeStrategy::ConcatLists,
+        );
+        assert_eq!(c.list("ints"), Some(&NbtList::Byte(vec![1])));
+        assert_eq!(c.len(), a.len());
+    }
+
+    #[test]
+    fn signed_byte_arrays() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert_byte_array_i8("Data", vec![-1, 0, 127]);
+        assert_eq!(nbt.byte_array("Data"), Some(&[255, 0, 127][..]));
+        assert_eq!(nbt.byte_array_i8("Data"), Some(&[-1, 0, 127][..]));
+        assert_eq!(
+            nbt.take("Data").unwrap().into_i8_vec(),
+            Ok(vec![-1, 0, 127])
+        );
+        assert_eq!(NbtTag::Int(1).into_i8_vec(), Err(NbtTag::Int(1)));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn write_compound_list_in_parallel() {
+        let compounds = (0..5000)
+            .map(|i| {
+                let mut compound = NbtCompound::new();
+                compound.insert("id", i);
+                compound.insert("name", i.to_string());
+                compound
+            })
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![COMPOUND_ID];
+        expected.extend_from_slice(&5000u32.to_be_bytes());
+        for compound in &compounds {
+            compound.write(&mut expected);
+        }
+        assert_eq!(NbtList::Compound(compounds).to_bytes(), expected);
+    }
+
+    #[test]
+    fn serialized_size() {
+        for data in [
+            test_fixtures::complex_player(),
+            test_fixtures::bigtest(),
+            test_fixtures::level_dat(),
+        ] {
+            let nbt = super::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(nbt.serialized_size(), data.len());
+            assert_eq!(nbt.serialized_size(), nbt.to_bytes().len());
+        }
+        assert_eq!(Nbt::None.serialized_size(), 1);
+
+        let tags = [
+            NbtTag::Byte(1),
+            NbtTag::Double(1.),
+            NbtTag::String("caf\u{e9}".into()),
+            NbtTag::ByteArray(vec![1; 3]),
+            NbtTag::LongArray(vec![1; 3]),
+            NbtTag::List(NbtList::Empty),
+            NbtTag::List(NbtList::Short(vec![1, 2])),
+            NbtTag::List(NbtList::String(vec!["a".into(), "".into()])),
+            NbtTag::List(NbtList::IntArray(vec![vec![1], vec![]])),
+            NbtTag::List(NbtList::List(vec![NbtList::Empty, NbtList::Int(vec![1])])),
+        ];
+        for tag in tags {
+            assert_eq!(tag.serialized_size(), tag.to_bytes().len(), "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn write_canonical() {
+        use crate::compound;
+
+        // NaN with a different payload than the one Java uses
+        let nan_f32 = f32::from_bits(0x7f80_0001);
+        let nan_f64 = -f64::NAN;
+        let a = BaseNbt::new(
+            "",
+            compound! {
+                "b": { "y": nan_f64, "x": [nan_f32, 1f32] },
+                "a": [{ "d": 1, "c": 2 }],
+                "c": [[nan_f64]],
+            },
+        );
+        let b = BaseNbt::new(
+            "",
+            compound! {
+                "a": [{ "c": 2, "d": 1 }],
+                "c": [[f64::NAN]],
+                "b": { "x": [f32::NAN, 1f32], "y": f64::NAN },
+            },
+        );
+
+        let mut a_canonical = Vec::new();
+        a.write_canonical(&mut a_canonical);
+        let mut b_canonical = Vec::new();
+        b.write_canonical(&mut b_canonical);
+        assert_eq!(a_canonical, b_canonical);
+        assert_ne!(a.to_bytes(), b.to_bytes());
+
+        // the borrowed writer writes the same thing
+        let data = a.to_bytes();
+        let borrowed = crate::borrow::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+        let mut borrowed_canonical = Vec::new();
+        borrowed.write_canonical(&mut borrowed_canonical);
+        assert_eq!(borrowed_canonical, a_canonical);
+
+        let canonical = super::read(&mut Cursor::new(&a_canonical))
+            .unwrap()
+            .unwrap();
+        let b = canonical.compound("b").unwrap();
+        assert_eq!(
+            b.keys().map(|key| key.to_str()).collect::<Vec<_>>(),
+            ["x", "y"]
+        );
+        assert_eq!(b.double("y").unwrap().to_bits(), 0x7ff8_0000_0000_0000);
+    }
+
+    #[test]
+    fn read_slice() {
+        let nbt = BaseNbt::new("hello", crate::compound! { "a": "b" });
+        let mut data = nbt.to_bytes();
+        let len = data.len();
+        data.push(0);
+
+        let (read, read_len) = super::read_slice(&data).unwrap();
+        assert_eq!(read, Nbt::Some(nbt));
+        assert_eq!(read_len, len);
+        // the rest of the data is another (empty) root
+        assert_eq!(super::read_slice(&data[len..]).unwrap(), (Nbt::None, 1));
+        assert_eq!(super::read_slice(&[]).unwrap_err(), Error::UnexpectedEof);
+    }
+
+    #[test]
+    fn get_as() {
+        let nbt = crate::compound! {
+            "Rotation": [1.5f32, -2f32],
+            "Tags": ["a", "b"],
+            "Item": { "id": "minecraft:stone" },
+        };
+
+        assert_eq!(nbt.get_as::<Vec<f32>>("Rotation"), Some(vec![1.5, -2.]));
+        assert_eq!(
+            nbt.get_as::<Vec<String>>("Tags"),
+            Some(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            nbt.get_as::<NbtTag>("Item"),
+            Some(NbtTag::Compound(
+                crate::compound! { "id": "minecraft:stone" }
+            ))
+        );
+        assert_eq!(nbt.get_as::<Vec<f32>>("Tags"), None);
+        assert_eq!(nbt.get_as::<i32>("Missing"), None);
+
+        // too deep for the borrowed reader, but it doesn't have to be read
+        let mut deep = NbtList::Empty;
+        for _ in 0..crate::common::MAX_DEPTH + 10 {
+            deep = NbtList::List(vec![deep]);
+        }
+        let mut nbt = NbtCompound::new();
+        nbt.insert("deep", deep.clone());
+        assert_eq!(nbt.get_as::<NbtTag>("deep"), Some(NbtTag::List(deep)));
+    }
 }
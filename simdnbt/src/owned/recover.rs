@@ -0,0 +1,334 @@
+//! Reading as much as possible from damaged NBT. See [`read_best_effort`].
+
+use super::{BaseNbt, Nbt, NbtCompound, NbtList, NbtTag};
+use crate::{
+    common::{
+        read_int_array, read_length, read_long_array, read_string, read_with_u32_length,
+        BYTE_ARRAY_ID, COMPOUND_ID, END_ID, INT_ARRAY_ID, LIST_ID, LONG_ARRAY_ID, STRING_ID,
+    },
+    error::NonRootError,
+    explain::PathReader,
+    options::Limits,
+    Error, ErrorLocation,
+};
+
+/// Read a normal root NBT compound like [`read`](super::read), but keep
+/// everything that was read before the data became invalid instead of
+/// failing.
+///
+/// When a tag has an unknown ID or the data ends too early, the compounds and
+/// lists that were being read are cut off at that point and the tag that was
+/// being read is left out. Nothing after the damage can be read, since there's
+/// no way to know where the next tag starts. The returned warnings say where
+/// the data became invalid, and are empty if all of it was read.
+///
+/// ```
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("DataVersion", 3953);
+/// compound.insert("Status", "minecraft:full");
+/// let mut data = Vec::new();
+/// simdnbt::owned::BaseNbt::new("", compound).write(&mut data);
+/// data.truncate(data.len() - 4);
+///
+/// let (nbt, warnings) = simdnbt::owned::read_best_effort(&data);
+/// assert_eq!(nbt.unwrap().int("DataVersion"), Some(3953));
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].path.to_string(), "Status");
+/// ```
+pub fn read_best_effort(data: &[u8]) -> (Nbt, Vec<ErrorLocation>) {
+    let mut recoverer = Recoverer {
+        input: PathReader::new(data),
+        limits: Limits::default(),
+        warnings: Vec::new(),
+    };
+    let nbt = recoverer.read_root();
+    (nbt, recoverer.warnings)
+}
+
+struct Recoverer<'a> {
+    input: PathReader<'a>,
+    limits: Limits,
+    warnings: Vec<ErrorLocation>,
+}
+
+impl Recoverer<'_> {
+    /// Record where the data became invalid. Since nothing after this can be
+    /// read, every caller stops reading once there's a warning.
+    fn warn(&mut self, error: impl Into<Error>) {
+        let location = self.input.locate(error);
+        self.warnings.push(location);
+    }
+
+    fn failed(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    fn read_root(&mut self) -> Nbt {
+        let root_type = match self.input.reader.read_u8() {
+            Ok(root_type) => root_type,
+            Err(_) => {
+                self.warn(Error::UnexpectedEof);
+                return Nbt::None;
+            }
+        };
+        if root_type == END_ID {
+            return Nbt::None;
+        }
+        if root_type != COMPOUND_ID {
+            self.warn(Error::InvalidRootType(root_type));
+            return Nbt::None;
+        }
+        let name = match read_string(&mut self.input.reader) {
+            Ok(name) => name.to_owned(),
+            Err(err) => {
+                self.warn(err);
+                return Nbt::None;
+            }
+        };
+        let tag = self.read_compound(0);
+        Nbt::Some(BaseNbt { name, tag })
+    }
+
+    fn read_compound(&mut self, depth: usize) -> NbtCompound {
+        let mut compound = NbtCompound::new();
+        if depth > self.limits.max_depth {
+            self.warn(NonRootError::max_depth_exceeded());
+            return compound;
+        }
+        loop {
+            let tag_type = match self.input.reader.read_u8() {
+                Ok(tag_type) => tag_type,
+                Err(err) => {
+                    self.warn(err);
+                    break;
+                }
+            };
+            if tag_type == END_ID {
+                break;
+            }
+            let name = match read_string(&mut self.input.reader) {
+                Ok(name) => name,
+                Err(err) => {
+                    self.warn(err);
+                    break;
+                }
+            };
+            self.input.push_key(name);
+            let tag = self.read_tag(tag_type, depth);
+            self.input.pop();
+
+            if let Some(tag) = tag {
+                compound.insert(name.to_owned(), tag);
+            }
+            if self.failed() {
+                break;
+            }
+        }
+        compound
+    }
+
+    /// Read the payload of a tag, or return `None` if not enough of it could
+    /// be read to keep it.
+    fn read_tag(&mut self, tag_type: u8, depth: usize) -> Option<NbtTag> {
+        match tag_type {
+            COMPOUND_ID => Some(NbtTag::Compound(self.read_compound(depth + 1))),
+            LIST_ID => self.read_list(depth + 1).map(NbtTag::List),
+            _ => {
+                match NbtTag::read_with_type(
+                    &mut self.input.reader,
+                    tag_type,
+                    depth,
+                    &mut self.limits,
+                ) {
+                    Ok(tag) => Some(tag),
+                    Err(err) => {
+                        self.warn(err);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_list(&mut self, depth: usize) -> Option<NbtList> {
+        if depth > self.limits.max_depth {
+            self.warn(NonRootError::max_depth_exceeded());
+            return None;
+        }
+
+        // lists of numbers are either read completely or not at all
+        let before = self.input.reader.clone();
+        let element_type = match self.input.reader.read_u8() {
+            Ok(element_type) => element_type,
+            Err(err) => {
+                self.warn(err);
+                return None;
+            }
+        };
+        let length = match read_length(&mut self.input.reader) {
+            Ok(length) => length as usize,
+            Err(err) => {
+                self.warn(err);
+                return None;
+            }
+        };
+
+        let list = match element_type {
+            COMPOUND_ID => NbtList::Compound(
+                self.read_elements(length, |recoverer| Some(recoverer.read_compound(depth + 1))),
+            ),
+            LIST_ID => NbtList::List(
+                self.read_elements(length, |recoverer| recoverer.read_list(depth + 1)),
+            ),
+            STRING_ID => NbtList::String(self.read_elements(length, |recoverer| {
+                let result =
+                    read_string(&mut recoverer.input.reader).map(|string| string.to_owned());
+                recoverer.ok(result)
+            })),
+            BYTE_ARRAY_ID => NbtList::ByteArray(self.read_elements(length, |recoverer| {
+                let result =
+                    read_with_u32_length(&mut recoverer.input.reader, 1).map(<[u8]>::to_vec);
+                recoverer.ok(result)
+            })),
+            INT_ARRAY_ID => NbtList::IntArray(self.read_elements(length, |recoverer| {
+                let result = read_int_array(&mut recoverer.input.reader).map(|ints| ints.to_vec());
+                recoverer.ok(result)
+            })),
+            LONG_ARRAY_ID => NbtList::LongArray(self.read_elements(length, |recoverer| {
+                let result =
+                    read_long_array(&mut recoverer.input.reader).map(|longs| longs.to_vec());
+                recoverer.ok(result)
+            })),
+            _ => {
+                self.input.reader = before;
+                match NbtList::read(&mut self.input.reader, depth, &mut self.limits) {
+                    Ok(list) => list,
+                    Err(err) => {
+                        self.warn(err);
+                        return None;
+                    }
+                }
+            }
+        };
+        Some(list)
+    }
+
+    /// Read up to `length` elements of a list, stopping at the first one that
+    /// couldn't be read.
+    fn read_elements<T>(
+        &mut self,
+        length: usize,
+        mut read_element: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Vec<T> {
+        // arbitrary number to prevent big allocations
+        let mut elements = Vec::with_capacity(length.min(128));
+        for i in 0..length {
+            self.input.push_index(i);
+            let element = read_element(self);
+            self.input.pop();
+
+            if let Some(element) = element {
+                elements.push(element);
+            }
+            if self.failed() {
+                break;
+            }
+        }
+        elements
+    }
+
+    fn ok<T, E: Into<Error>>(&mut self, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.warn(err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damaged_chunk() -> Vec<u8> {
+        let mut section = NbtCompound::new();
+        section.insert("Y", 4i8);
+        section.insert("BlockLight", NbtTag::ByteArray(vec![0; 16]));
+
+        let mut compound = NbtCompound::new();
+        compound.insert("DataVersion", 3953);
+        compound.insert(
+            "sections",
+            NbtList::from(vec![section.clone(), section.clone()]),
+        );
+        compound.insert("Status", "minecraft:full");
+
+        let mut data = Vec::new();
+        BaseNbt::new("", compound).write(&mut data);
+        data
+    }
+
+    #[test]
+    fn read_undamaged() {
+        let data = damaged_chunk();
+        let (nbt, warnings) = read_best_effort(&data);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            nbt,
+            super::super::read(&mut std::io::Cursor::new(&data)).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_list() {
+        let mut data = damaged_chunk();
+        // cut off the second section in the middle of its byte array
+        let position = data
+            .windows(10)
+            .rposition(|window| window == b"BlockLight")
+            .unwrap();
+        data.truncate(position + 10 + 4 + 3);
+
+        let (nbt, warnings) = read_best_effort(&data);
+        let nbt = nbt.unwrap();
+        assert_eq!(nbt.int("DataVersion"), Some(3953));
+        assert_eq!(nbt.string("Status"), None);
+        let sections = nbt.list("sections").unwrap().compounds().unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].byte_array("BlockLight").unwrap().len(), 16);
+        assert_eq!(sections[1].byte("Y"), Some(4));
+        assert_eq!(sections[1].byte_array("BlockLight"), None);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path.to_string(), "sections[1].BlockLight");
+        assert_eq!(warnings[0].error, Error::UnexpectedEof);
+    }
+
+    #[test]
+    fn unknown_tag_id() {
+        let mut data = damaged_chunk();
+        let position = data
+            .windows(6)
+            .position(|window| window == b"Status")
+            .unwrap();
+        // the ID of the tag comes before the length of its name
+        data[position - 3] = 42;
+
+        let (nbt, warnings) = read_best_effort(&data);
+        let nbt = nbt.unwrap();
+        assert_eq!(nbt.list("sections").unwrap().compounds().unwrap().len(), 2);
+        assert_eq!(nbt.string("Status"), None);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path.to_string(), "Status");
+        assert_eq!(warnings[0].error, Error::UnknownTagId(42));
+    }
+
+    #[test]
+    fn invalid_root() {
+        let (nbt, warnings) = read_best_effort(&[1, 0, 0, 5]);
+        assert_eq!(nbt, Nbt::None);
+        assert_eq!(warnings[0].error, Error::InvalidRootType(1));
+    }
+}
@@ -0,0 +1,302 @@
+//! Reading numbers that are bit-packed into long arrays, like block states and
+//! heightmaps in chunks.
+//!
+//! Since Minecraft 1.16, every long holds as many whole entries as fit in it,
+//! starting from the least significant bits, and the rest of its bits are
+//! unused. Entries never span two longs.
+//!
+//! ```
+//! use simdnbt::packed::PackedBits;
+//!
+//! // a heightmap for a world that's 384 blocks tall
+//! let heights = vec![64u16; 256];
+//! let bits = PackedBits::bits_for(384 + 1);
+//! let longs = PackedBits::pack(&heights, bits).unwrap();
+//! assert_eq!(longs.len(), 37);
+//!
+//! let packed = PackedBits::new(&longs, bits).unwrap();
+//! assert_eq!(packed.get(255), Some(64));
+//! assert_eq!(&packed.to_vec()[..256], &heights[..]);
+//! ```
+
+#[cfg(feature = "nightly")]
+use std::simd::prelude::*;
+
+use crate::raw_list::RawList;
+
+/// How many entries are unpacked at once.
+#[cfg(feature = "nightly")]
+const LANES: usize = 8;
+
+/// A view of numbers that are bit-packed into a long array. See the
+/// [module-level documentation](self) for the format.
+///
+/// This can be made from an owned long array with [`Self::new`], or from a
+/// borrowed one without copying it with [`Self::from_raw`]. The
+/// `long_array_packed` methods on compounds do this for you.
+#[derive(Debug, Clone, Copy)]
+pub struct PackedBits<'a> {
+    longs: Longs<'a>,
+    bits_per_entry: u32,
+    entries_per_long: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Longs<'a> {
+    Native(&'a [i64]),
+    /// The longs haven't been converted from big-endian yet.
+    BigEndian(&'a [u8]),
+}
+impl Longs<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Longs::Native(longs) => longs.len(),
+            Longs::BigEndian(bytes) => bytes.len() / 8,
+        }
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> u64 {
+        match self {
+            Longs::Native(longs) => longs[index] as u64,
+            Longs::BigEndian(bytes) => {
+                u64::from_be_bytes(bytes[index * 8..index * 8 + 8].try_into().unwrap())
+            }
+        }
+    }
+}
+
+impl<'a> PackedBits<'a> {
+    /// The most bits that an entry can have, since entries are `u16`s.
+    pub const MAX_BITS_PER_ENTRY: u32 = 16;
+
+    /// Make a view of the entries in the longs.
+    ///
+    /// Returns `None` if `bits_per_entry` isn't between 1 and
+    /// [`Self::MAX_BITS_PER_ENTRY`].
+    pub fn new(longs: &'a [i64], bits_per_entry: u32) -> Option<Self> {
+        Self::with_longs(Longs::Native(longs), bits_per_entry)
+    }
+
+    /// Make a view of the entries in a long array that was borrowed from the
+    /// original data, like the ones returned by
+    /// [`borrow::NbtTag::long_array_raw`](crate::borrow::NbtTag::long_array_raw).
+    ///
+    /// Returns `None` if `bits_per_entry` isn't between 1 and
+    /// [`Self::MAX_BITS_PER_ENTRY`].
+    pub fn from_raw(longs: RawList<'a, i64>, bits_per_entry: u32) -> Option<Self> {
+        Self::with_longs(Longs::BigEndian(longs.into_inner()), bits_per_entry)
+    }
+
+    fn with_longs(longs: Longs<'a>, bits_per_entry: u32) -> Option<Self> {
+        Some(Self {
+            longs,
+            bits_per_entry,
+            entries_per_long: entries_per_long(bits_per_entry)?,
+        })
+    }
+
+    /// The number of bits that are needed to store the numbers from 0 up to
+    /// (but not including) `count`, which is at least 1.
+    ///
+    /// Block states in chunks use at least 4 bits, so use
+    /// `PackedBits::bits_for(palette.len()).max(4)` for them.
+    pub fn bits_for(count: usize) -> u32 {
+        (usize::BITS - count.saturating_sub(1).leading_zeros()).max(1)
+    }
+
+    pub fn bits_per_entry(&self) -> u32 {
+        self.bits_per_entry
+    }
+
+    /// The number of entries that fit in the longs. This can be more than the
+    /// number of entries that were packed, since the last long may be
+    /// padded.
+    pub fn len(&self) -> usize {
+        self.longs.len() * self.entries_per_long
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.longs.len() == 0
+    }
+
+    /// Get the entry at the index, or `None` if it's out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<u16> {
+        if index >= self.len() {
+            return None;
+        }
+        let long = self.longs.get(index / self.entries_per_long);
+        let shift = (index % self.entries_per_long) as u32 * self.bits_per_entry;
+        Some(((long >> shift) & self.mask()) as u16)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..self.len()).map(|index| self.get(index).unwrap())
+    }
+
+    /// Unpack every entry into a `Vec`. See [`Self::unpack_into`].
+    pub fn to_vec(&self) -> Vec<u16> {
+        let mut entries = Vec::with_capacity(self.len());
+        self.unpack_into(&mut entries);
+        entries
+    }
+
+    /// Unpack every entry and append them to the `Vec`. This includes the
+    /// padding at the end of the last long, so truncate it if you know how
+    /// many entries there should be.
+    #[cfg(feature = "nightly")]
+    pub fn unpack_into(&self, entries: &mut Vec<u16>) {
+        entries.reserve(self.len());
+
+        let bits = self.bits_per_entry as u64;
+        let mask = u64x8::splat(self.mask());
+        // shifting by 64 or more isn't allowed, so the shifts for entries that
+        // don't exist are clamped and those entries are ignored
+        let shifts: [u64x8; 64 / LANES] = std::array::from_fn(|chunk| {
+            u64x8::from_array(std::array::from_fn(|lane| {
+                ((chunk * LANES + lane) as u64 * bits).min(63)
+            }))
+        });
+        let chunks = &shifts[..self.entries_per_long.div_ceil(LANES)];
+
+        for index in 0..self.longs.len() {
+            let long = u64x8::splat(self.longs.get(index));
+            let mut remaining = self.entries_per_long;
+            for shifts in chunks {
+                let unpacked: u16x8 = ((long >> shifts) & mask).cast();
+                let count = remaining.min(LANES);
+                entries.extend_from_slice(&unpacked.as_array()[..count]);
+                remaining -= count;
+            }
+        }
+    }
+
+    /// Unpack every entry and append them to the `Vec`. This includes the
+    /// padding at the end of the last long, so truncate it if you know how
+    /// many entries there should be.
+    #[cfg(not(feature = "nightly"))]
+    pub fn unpack_into(&self, entries: &mut Vec<u16>) {
+        entries.reserve(self.len());
+
+        let bits = self.bits_per_entry;
+        let mask = self.mask();
+        for index in 0..self.longs.len() {
+            let long = self.longs.get(index);
+            entries.extend(
+                (0..self.entries_per_long as u32).map(|i| ((long >> (i * bits)) & mask) as u16),
+            );
+        }
+    }
+
+    /// Pack the entries into longs in the same format that's read by this
+    /// type.
+    ///
+    /// Entries that don't fit in `bits_per_entry` bits are truncated. Returns
+    /// `None` if `bits_per_entry` isn't between 1 and
+    /// [`Self::MAX_BITS_PER_ENTRY`].
+    pub fn pack(entries: &[u16], bits_per_entry: u32) -> Option<Vec<i64>> {
+        let entries_per_long = entries_per_long(bits_per_entry)?;
+        let mask = (1u64 << bits_per_entry) - 1;
+        let longs = entries
+            .chunks(entries_per_long)
+            .map(|chunk| {
+                chunk.iter().enumerate().fold(0u64, |long, (i, &entry)| {
+                    long | ((entry as u64 & mask) << (i as u32 * bits_per_entry))
+                }) as i64
+            })
+            .collect();
+        Some(longs)
+    }
+
+    #[inline]
+    fn mask(&self) -> u64 {
+        (1 << self.bits_per_entry) - 1
+    }
+}
+
+fn entries_per_long(bits_per_entry: u32) -> Option<usize> {
+    if !(1..=PackedBits::MAX_BITS_PER_ENTRY).contains(&bits_per_entry) {
+        return None;
+    }
+    Some(64 / bits_per_entry as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_every_width() {
+        for bits in 1..=16 {
+            let entries = (0..4096u32)
+                .map(|i| (i.wrapping_mul(2654435761) >> 7) as u16 & ((1 << bits) - 1) as u16)
+                .collect::<Vec<_>>();
+            let longs = PackedBits::pack(&entries, bits).unwrap();
+            let packed = PackedBits::new(&longs, bits).unwrap();
+
+            let unpacked = packed.to_vec();
+            assert_eq!(unpacked.len(), packed.len());
+            assert_eq!(&unpacked[..entries.len()], &entries[..], "{bits} bits");
+            assert!(unpacked[entries.len()..].iter().all(|&entry| entry == 0));
+            for (i, &entry) in entries.iter().enumerate() {
+                assert_eq!(packed.get(i), Some(entry));
+            }
+            assert_eq!(packed.get(packed.len()), None);
+        }
+    }
+
+    #[test]
+    fn known_layout() {
+        // 5 bits per entry means 12 entries per long and 4 unused bits
+        let longs = [0b0_00010_00001_i64, -1];
+        let packed = PackedBits::new(&longs, 5).unwrap();
+        assert_eq!(packed.len(), 24);
+        assert_eq!(packed.get(0), Some(1));
+        assert_eq!(packed.get(1), Some(2));
+        assert_eq!(packed.get(2), Some(0));
+        assert_eq!(packed.get(12), Some(31));
+        assert_eq!(packed.iter().filter(|&entry| entry == 31).count(), 12);
+    }
+
+    #[test]
+    fn from_raw() {
+        let longs = PackedBits::pack(&[1, 2, 3, 4, 5], 4).unwrap();
+        let data = longs
+            .iter()
+            .flat_map(|long| long.to_be_bytes())
+            .collect::<Vec<_>>();
+        let packed = PackedBits::from_raw(RawList::new(&data), 4).unwrap();
+        assert_eq!(&packed.to_vec()[..5], &[1, 2, 3, 4, 5]);
+        assert_eq!(packed.get(4), Some(5));
+    }
+
+    #[test]
+    fn bits_for() {
+        assert_eq!(PackedBits::bits_for(0), 1);
+        assert_eq!(PackedBits::bits_for(1), 1);
+        assert_eq!(PackedBits::bits_for(2), 1);
+        assert_eq!(PackedBits::bits_for(3), 2);
+        assert_eq!(PackedBits::bits_for(16), 4);
+        assert_eq!(PackedBits::bits_for(17), 5);
+        assert_eq!(PackedBits::bits_for(385), 9);
+    }
+
+    #[test]
+    fn invalid_bits_per_entry() {
+        for bits in [0, 17, 64] {
+            assert!(PackedBits::new(&[0], bits).is_none());
+            assert!(PackedBits::pack(&[0], bits).is_none());
+        }
+
+        let mut compound = crate::owned::NbtCompound::new();
+        compound.insert("data", crate::owned::NbtTag::LongArray(vec![0; 4]));
+        assert!(compound.long_array_packed("data", 4).is_some());
+        assert!(compound.long_array_packed("data", 17).is_none());
+        let data = crate::owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = crate::borrow::read(&mut std::io::Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+        assert!(nbt.long_array_packed("data", 0).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+//! Reading many independent NBT documents in parallel on the rayon thread
+//! pool, like the chunks in a world.
+//!
+//! ```
+//! use simdnbt::{borrow::Nbt, compound, owned::BaseNbt};
+//!
+//! let documents = (0..100)
+//!     .map(|i| BaseNbt::new("", compound! { "id": i }).to_bytes())
+//!     .collect::<Vec<_>>();
+//! let documents = documents.iter().map(Vec::as_slice).collect::<Vec<_>>();
+//!
+//! let ids = simdnbt::par::map_many(&documents, |nbt| match nbt {
+//!     Nbt::Some(nbt) => nbt.int("id"),
+//!     Nbt::None => None,
+//! });
+//! assert_eq!(ids[42], Ok(Some(42)));
+//!
+//! let nbts = simdnbt::par::read_many(&documents);
+//! assert_eq!(nbts.len(), 100);
+//! ```
+
+use std::io::Cursor;
+
+use rayon::prelude::*;
+
+use crate::{
+    borrow::{self, Nbt, Parser},
+    Error, ReadOptions,
+};
+
+/// Read every document with [`borrow::read`] in parallel. The results are in
+/// the same order as the documents.
+///
+/// Every document gets its own tapes, since they're kept in the returned
+/// [`Nbt`]. If you only need to get some data out of each document, use
+/// [`map_many`] instead, which reuses the tapes.
+pub fn read_many<'a>(documents: &[&'a [u8]]) -> Vec<Result<Nbt<'a>, Error>> {
+    read_many_with_opts(documents, &ReadOptions::default())
+}
+
+/// Read every document in parallel like [`read_many`], but with the given
+/// options.
+pub fn read_many_with_opts<'a>(
+    documents: &[&'a [u8]],
+    options: &ReadOptions,
+) -> Vec<Result<Nbt<'a>, Error>> {
+    documents
+        .par_iter()
+        .map(|data| borrow::read_with_opts(&mut Cursor::new(data), options))
+        .collect()
+}
+
+/// Read every document in parallel and call the function with it, returning
+/// the results in the same order as the documents.
+///
+/// Each thread reads with a [`Parser`] that's reused for every document it
+/// reads, so this allocates much less than [`read_many`].
+pub fn map_many<T, F>(documents: &[&[u8]], f: F) -> Vec<Result<T, Error>>
+where
+    T: Send,
+    F: Fn(&Nbt) -> T + Sync,
+{
+    map_many_with_opts(documents, &ReadOptions::default(), f)
+}
+
+/// Read every document in parallel like [`map_many`], but with the given
+/// options.
+pub fn map_many_with_opts<T, F>(
+    documents: &[&[u8]],
+    options: &ReadOptions,
+    f: F,
+) -> Vec<Result<T, Error>>
+where
+    T: Send,
+    F: Fn(&Nbt) -> T + Sync,
+{
+    documents
+        .par_iter()
+        .map_init(
+            || Parser::with_opts(options.clone()),
+            |parser, data| {
+                let nbt = parser.read(&mut Cursor::new(data))?;
+                Ok(f(&nbt))
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compound, owned::BaseNbt};
+
+    #[test]
+    fn same_as_sequential() {
+        let mut documents = (0..1000)
+            .map(|i| {
+                BaseNbt::new(
+                    "",
+                    compound! { "id": i, "name": i.to_string(), "list": [i, i + 1] },
+                )
+                .to_bytes()
+            })
+            .collect::<Vec<_>>();
+        documents[10] = vec![0];
+        documents[20].pop();
+        let documents = documents.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let expected = documents
+            .iter()
+            .map(|data| borrow::read(&mut Cursor::new(data)))
+            .collect::<Vec<_>>();
+        assert_eq!(read_many(&documents), expected);
+        assert_eq!(read_many(&documents)[20], Err(Error::UnexpectedEof));
+
+        let ids = map_many(&documents, |nbt| match nbt {
+            Nbt::Some(nbt) => nbt.int("id"),
+            Nbt::None => None,
+        });
+        for (i, id) in ids.into_iter().enumerate() {
+            match i {
+                10 => assert_eq!(id, Ok(None)),
+                20 => assert_eq!(id, Err(Error::UnexpectedEof)),
+                _ => assert_eq!(id, Ok(Some(i as i32))),
+            }
+        }
+
+        let options = ReadOptions {
+            max_tags: Some(2),
+            ..Default::default()
+        };
+        let results = map_many_with_opts(&documents, &options, |_| ());
+        assert_eq!(results[0], Err(Error::TooManyTags));
+        assert_eq!(results[10], Ok(()));
+        assert_eq!(
+            read_many_with_opts(&documents, &options)[0],
+            Err(Error::TooManyTags)
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! Paths like `Level.Sections[3].Palette[0].Name` for getting deeply nested
+//! tags in one call.
+
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::borrow::{NbtCompound, NbtTag};
+
+/// A path to a tag that's nested in compounds and lists.
+///
+/// Keys are separated by dots and list indexes go in square brackets. Keys
+/// with characters other than letters, numbers, `_`, `-`, `+` and `:` have to
+/// be in double quotes, like `"my key"."with.dots"[0]`.
+///
+/// If you're only using a path once, [`NbtCompound::get_path`] can look it up
+/// without parsing it into an `NbtPath` first.
+///
+/// ```
+/// # use std::io::Cursor;
+/// # let mut section = simdnbt::owned::NbtCompound::new();
+/// # section.insert("Y", 3i8);
+/// # let mut level = simdnbt::owned::NbtCompound::new();
+/// # level.insert("Sections", simdnbt::owned::NbtList::Compound(vec![section]));
+/// # let mut root = simdnbt::owned::NbtCompound::new();
+/// # root.insert("Level", level);
+/// # let data = simdnbt::owned::BaseNbt::new("", root).to_bytes();
+/// use simdnbt::NbtPath;
+///
+/// let nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+///
+/// let path: NbtPath = "Level.Sections[0].Y".parse().unwrap();
+/// assert_eq!(path.get(nbt.as_compound()).and_then(|tag| tag.byte()), Some(3));
+/// assert_eq!(nbt.get_path("Level.Sections[0].Y").unwrap().byte(), Some(3));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NbtPath {
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The name of a tag in a compound.
+    Key(String),
+    /// The index of an element in a list.
+    Index(usize),
+}
+
+/// An error from parsing an invalid [`NbtPath`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at position {position}")]
+pub struct NbtPathError {
+    pub message: &'static str,
+    /// The byte offset in the path where the error happened.
+    pub position: usize,
+}
+
+impl NbtPath {
+    /// Create an empty path. Use [`Self::key`] and [`Self::index`] to add to
+    /// it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.segments.push(PathSegment::Key(key.into()));
+        self
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        self
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub(crate) fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub(crate) fn segment(mut self, segment: PathSegment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    pub(crate) fn push_front(&mut self, segment: PathSegment) {
+        self.segments.insert(0, segment);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.segments.pop();
+    }
+
+    /// Get the tag at this path in the compound, or `None` if it doesn't exist
+    /// or the path is empty.
+    pub fn get<'a, 'tape>(&self, compound: NbtCompound<'a, 'tape>) -> Option<NbtTag<'a, 'tape>> {
+        walk(
+            compound,
+            self.segments.iter().map(|segment| match segment {
+                PathSegment::Key(key) => Segment::Key(Cow::Borrowed(key)),
+                PathSegment::Index(index) => Segment::Index(*index),
+            }),
+        )
+    }
+}
+
+impl FromStr for NbtPath {
+    type Err = NbtPathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let segments = Segments::new(path)
+            .map(|segment| {
+                segment.map(|segment| match segment {
+                    Segment::Key(key) => PathSegment::Key(key.into_owned()),
+                    Segment::Index(index) => PathSegment::Index(index),
+                    Segment::AnyKey | Segment::AnyIndex => {
+                        unreachable!("wildcards are only parsed in patterns")
+                    }
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { segments })
+    }
+}
+
+/// A segment of a path that can have wildcards. `*` matches every key in a
+/// compound, and `[]` matches every element in a list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PatternSegment {
+    Key(String),
+    Index(usize),
+    AnyKey,
+    AnyIndex,
+}
+
+/// Parse a path that can have `*` and `[]` wildcards. See [`PatternSegment`].
+pub(crate) fn parse_pattern(pattern: &str) -> Result<Vec<PatternSegment>, NbtPathError> {
+    let mut segments = Segments::new(pattern);
+    segments.wildcards = true;
+    segments
+        .map(|segment| {
+            segment.map(|segment| match segment {
+                Segment::Key(key) => PatternSegment::Key(key.into_owned()),
+                Segment::Index(index) => PatternSegment::Index(index),
+                Segment::AnyKey => PatternSegment::AnyKey,
+                Segment::AnyIndex => PatternSegment::AnyIndex,
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for NbtPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) => {
+                    if i != 0 {
+                        f.write_str(".")?;
+                    }
+                    write_key(f, key)?;
+                }
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a key the way it's written in a path, which is in quotes if it has
+/// any characters that aren't allowed in unquoted keys.
+pub(crate) fn write_key(out: &mut impl fmt::Write, key: &str) -> fmt::Result {
+    if !key.is_empty() && key.bytes().all(is_unquoted_char) {
+        return out.write_str(key);
+    }
+    out.write_str("\"")?;
+    for c in key.chars() {
+        if c == '"' || c == '\\' {
+            out.write_str("\\")?;
+        }
+        out.write_char(c)?;
+    }
+    out.write_str("\"")
+}
+
+impl<'a, 'tape> NbtCompound<'a, 'tape> {
+    /// Get a nested tag with a path like `Level.Sections[3].Palette[0].Name`.
+    /// See [`NbtPath`] for the syntax.
+    ///
+    /// The path is read as it's being resolved, so this doesn't allocate
+    /// unless there are escaped quotes in a key. Returns `None` if the path is
+    /// invalid or there's no tag at it.
+    pub fn get_path(&self, path: &str) -> Option<NbtTag<'a, 'tape>> {
+        let mut error = false;
+        let tag = walk(
+            *self,
+            Segments::new(path).map_while(|segment| {
+                error = segment.is_err();
+                segment.ok()
+            }),
+        );
+        if error {
+            None
+        } else {
+            tag
+        }
+    }
+}
+
+/// A segment of a path that might borrow from the string it was parsed from.
+enum Segment<'p> {
+    Key(Cow<'p, str>),
+    Index(usize),
+    AnyKey,
+    AnyIndex,
+}
+
+fn walk<'a, 'tape, 'p>(
+    compound: NbtCompound<'a, 'tape>,
+    segments: impl Iterator<Item = Segment<'p>>,
+) -> Option<NbtTag<'a, 'tape>> {
+    // None means that we're still at the root compound
+    let mut tag: Option<NbtTag<'a, 'tape>> = None;
+    for segment in segments {
+        tag = Some(match segment {
+            Segment::Key(key) => {
+                let compound = match tag {
+                    Some(tag) => tag.compound()?,
+                    None => compound,
+                };
+                compound.get(&key)?
+            }
+            Segment::Index(index) => tag?.list()?.iter().nth(index)?,
+            Segment::AnyKey | Segment::AnyIndex => return None,
+        });
+    }
+    tag
+}
+
+fn is_unquoted_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'+' | b':')
+}
+
+/// An iterator over the segments in a path string.
+struct Segments<'p> {
+    path: &'p str,
+    pos: usize,
+    failed: bool,
+    /// Whether `*` and `[]` are allowed.
+    wildcards: bool,
+}
+
+impl<'p> Segments<'p> {
+    fn new(path: &'p str) -> Self {
+        Self {
+            path,
+            pos: 0,
+            failed: false,
+            wildcards: false,
+        }
+    }
+
+    fn error(&mut self, message: &'static str) -> NbtPathError {
+        self.failed = true;
+        NbtPathError {
+            message,
+            position: self.pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.path.as_bytes().get(self.pos).copied()
+    }
+
+    fn key(&mut self) -> Result<Segment<'p>, NbtPathError> {
+        if self.peek() == Some(b'"') {
+            return self.quoted_key();
+        }
+        if self.wildcards && self.peek() == Some(b'*') {
+            self.pos += 1;
+            return Ok(Segment::AnyKey);
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("Expected a key"));
+        }
+        Ok(Segment::Key(Cow::Borrowed(&self.path[start..self.pos])))
+    }
+
+    fn quoted_key(&mut self) -> Result<Segment<'p>, NbtPathError> {
+        // skip the opening quote
+        self.pos += 1;
+        let start = self.pos;
+        let mut unescaped: Option<String> = None;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated quoted key")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let unescaped =
+                        unescaped.get_or_insert_with(|| self.path[start..self.pos].to_owned());
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c @ (b'"' | b'\\')) => unescaped.push(c as char),
+                        _ => return Err(self.error("Invalid escape")),
+                    }
+                    self.pos += 1;
+                }
+                Some(_) => {
+                    let c = self.path[self.pos..].chars().next().unwrap();
+                    if let Some(unescaped) = &mut unescaped {
+                        unescaped.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        let key = match unescaped {
+            Some(unescaped) => Cow::Owned(unescaped),
+            None => Cow::Borrowed(&self.path[start..self.pos]),
+        };
+        // skip the closing quote
+        self.pos += 1;
+        Ok(Segment::Key(key))
+    }
+
+    fn index(&mut self) -> Result<Segment<'p>, NbtPathError> {
+        // skip the opening bracket
+        self.pos += 1;
+        if self.wildcards && self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Segment::AnyIndex);
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let index = self.path[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("Expected an index"))?;
+        if self.peek() != Some(b']') {
+            return Err(self.error("Expected ']'"));
+        }
+        self.pos += 1;
+        Ok(Segment::Index(index))
+    }
+}
+
+impl<'p> Iterator for Segments<'p> {
+    type Item = Result<Segment<'p>, NbtPathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.path.len() {
+            return None;
+        }
+        Some(match self.peek() {
+            Some(b'[') => self.index(),
+            Some(b'.') if self.pos != 0 => {
+                self.pos += 1;
+                self.key()
+            }
+            _ if self.pos == 0 => self.key(),
+            _ => Err(self.error("Expected '.' or '['")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{borrow, owned};
+
+    #[test]
+    fn get_path() {
+        let mut block = owned::NbtCompound::new();
+        block.insert("Name", "minecraft:stone");
+        let mut section = owned::NbtCompound::new();
+        section.insert(
+            "Palette",
+            owned::NbtList::Compound(vec![owned::NbtCompound::new(), block]),
+        );
+        let mut level = owned::NbtCompound::new();
+        level.insert("Sections", owned::NbtList::Compound(vec![section]));
+        level.insert("weird.key \"name\"", owned::NbtList::Int(vec![1, 2, 3]));
+        let mut root = owned::NbtCompound::new();
+        root.insert("Level", level);
+        let data = owned::BaseNbt::new("", root).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        let name = nbt.get_path("Level.Sections[0].Palette[1].Name").unwrap();
+        assert_eq!(name.string().unwrap().to_str(), "minecraft:stone");
+        let int = nbt.get_path(r#"Level."weird.key \"name\""[2]"#).unwrap();
+        assert_eq!(int.int(), Some(3));
+
+        for missing in [
+            "",
+            "Level.Sections[1]",
+            "Level.Sections[0].Palette[1].Name.Nope",
+            "Level[0]",
+            "Nope",
+            // invalid paths
+            "Level.",
+            "Level.Sections[0",
+            "Level.Sections[-1]",
+            "Level.\"Sections",
+        ] {
+            assert!(nbt.get_path(missing).is_none(), "{missing}");
+        }
+
+        let path = NbtPath::new()
+            .key("Level")
+            .key("weird.key \"name\"")
+            .index(2);
+        assert_eq!(path.to_string(), r#"Level."weird.key \"name\""[2]"#);
+        assert_eq!(path.to_string().parse::<NbtPath>().unwrap(), path);
+        assert_eq!(path.get(nbt.as_compound()).unwrap().int(), Some(3));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "a.[0]".parse::<NbtPath>(),
+            Err(NbtPathError {
+                message: "Expected a key",
+                position: 2
+            })
+        );
+        assert_eq!(
+            "a b".parse::<NbtPath>(),
+            Err(NbtPathError {
+                message: "Expected '.' or '['",
+                position: 1
+            })
+        );
+        assert_eq!("".parse::<NbtPath>(), Ok(NbtPath::new()));
+    }
+}
@@ -0,0 +1,123 @@
+//! [proptest](https://docs.rs/proptest) strategies for generating owned NBT.
+//!
+//! Every strategy takes the deepest that compounds and lists can be nested
+//! inside of the generated value, and the most tags that a single compound,
+//! list, or array can have.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use simdnbt::{owned::BaseNbt, proptest::any_nbt_compound};
+//!
+//! proptest! {
+//!     fn written_nbt_can_be_read(compound in any_nbt_compound(4, 8)) {
+//!         let nbt = BaseNbt::new("", compound);
+//!         prop_assert!(simdnbt::differential::check_roundtrip(&nbt).is_ok());
+//!     }
+//! }
+//! # written_nbt_can_be_read();
+//! ```
+
+use proptest::{collection::vec, prelude::*};
+
+use crate::{
+    mutf8::Mutf8String,
+    owned::{BaseNbt, NbtCompound, NbtList, NbtTag},
+};
+
+/// A strategy for any tag, including compounds and lists that are nested at
+/// most `depth` levels deep.
+pub fn any_nbt_tag(depth: u32, breadth: usize) -> impl Strategy<Value = NbtTag> {
+    let desired_size = breadth.saturating_pow(depth).min(u32::MAX as usize) as u32;
+    leaf_tag(breadth).prop_recursive(depth, desired_size, breadth as u32, move |inner| {
+        prop_oneof![
+            list_of(inner.clone(), breadth).prop_map(NbtTag::List),
+            compound_of(inner, breadth).prop_map(NbtTag::Compound),
+        ]
+    })
+}
+
+/// A strategy for compounds whose tags are nested at most `depth` levels deep
+/// and that have at most `breadth` tags.
+pub fn any_nbt_compound(depth: u32, breadth: usize) -> impl Strategy<Value = NbtCompound> {
+    compound_of(any_nbt_tag(depth, breadth), breadth)
+}
+
+/// A strategy for lists whose elements are nested at most `depth` levels deep
+/// and that have at most `breadth` elements.
+pub fn any_nbt_list(depth: u32, breadth: usize) -> impl Strategy<Value = NbtList> {
+    list_of(any_nbt_tag(depth, breadth), breadth)
+}
+
+/// A strategy for a root compound with any name. See [`any_nbt_compound`].
+pub fn any_base_nbt(depth: u32, breadth: usize) -> impl Strategy<Value = BaseNbt> {
+    (any_mutf8_string(), any_nbt_compound(depth, breadth))
+        .prop_map(|(name, compound)| BaseNbt::new(name, compound))
+}
+
+fn any_mutf8_string() -> impl Strategy<Value = Mutf8String> {
+    any::<String>().prop_map(Mutf8String::from)
+}
+
+fn leaf_tag(breadth: usize) -> impl Strategy<Value = NbtTag> {
+    prop_oneof![
+        any::<i8>().prop_map(NbtTag::Byte),
+        any::<i16>().prop_map(NbtTag::Short),
+        any::<i32>().prop_map(NbtTag::Int),
+        any::<i64>().prop_map(NbtTag::Long),
+        any::<f32>().prop_map(NbtTag::Float),
+        any::<f64>().prop_map(NbtTag::Double),
+        vec(any::<u8>(), 0..=breadth).prop_map(NbtTag::ByteArray),
+        any_mutf8_string().prop_map(NbtTag::String),
+        vec(any::<i32>(), 0..=breadth).prop_map(NbtTag::IntArray),
+        vec(any::<i64>(), 0..=breadth).prop_map(NbtTag::LongArray),
+    ]
+}
+
+fn compound_of(
+    tag: impl Strategy<Value = NbtTag>,
+    breadth: usize,
+) -> impl Strategy<Value = NbtCompound> {
+    vec((any_mutf8_string(), tag), 0..=breadth).prop_map(NbtCompound::from_values)
+}
+
+/// Lists can only have one type of tag, so only the tags with the same type as
+/// the first one are kept.
+fn list_of(tag: impl Strategy<Value = NbtTag>, breadth: usize) -> impl Strategy<Value = NbtList> {
+    vec(tag, 0..=breadth).prop_map(|mut tags| {
+        if let Some(id) = tags.first().map(NbtTag::id) {
+            tags.retain(|tag| tag.id() == id);
+        }
+        NbtList::from_tags(tags).expect("all of the tags have the same type")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differential;
+
+    fn depth(tag: &NbtTag) -> u32 {
+        match tag {
+            NbtTag::Compound(compound) => 1 + compound.values().map(depth).max().unwrap_or(0),
+            NbtTag::List(list) => 1 + list.as_nbt_tags().iter().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip(nbt in any_base_nbt(4, 6)) {
+            prop_assert_eq!(differential::check_roundtrip(&nbt), Ok(()));
+        }
+
+        #[test]
+        fn stays_within_bounds(tag in any_nbt_tag(3, 4)) {
+            prop_assert!(depth(&tag) <= 3);
+            match &tag {
+                NbtTag::Compound(compound) => prop_assert!(compound.len() <= 4),
+                NbtTag::List(list) => prop_assert!(list.len() <= 4),
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, mem};
+use std::{iter::FusedIterator, marker::PhantomData, mem, ptr, slice};
 
 use crate::swap_endianness::{swap_endianness, swap_endianness_as_u8, SwappableNumber};
 
@@ -30,6 +30,17 @@ impl<'a, T> RawList<'a, T> {
     }
 }
 
+impl<'a, T: Copy + SwappableNumber> RawList<'a, T> {
+    /// Returns an iterator that converts each number from big-endian as it's
+    /// read. Unlike [`Self::to_vec`], this doesn't allocate.
+    pub fn iter(&self) -> RawListIter<'a, T> {
+        RawListIter {
+            data: self.data,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<T: SwappableNumber> RawList<'_, T> {
     pub fn to_vec(&self) -> Vec<T>
     where
@@ -69,3 +80,74 @@ where
         self.to_vec().into_iter()
     }
 }
+
+/// A lazy iterator over the numbers in a [`RawList`]. This is returned by
+/// [`RawList::iter`].
+#[derive(Debug, Clone)]
+pub struct RawListIter<'a, T> {
+    data: &'a [u8],
+    _marker: PhantomData<T>,
+}
+
+/// Read a big-endian number from the start of the data, which must be at
+/// least as long as the number.
+#[inline]
+fn read_big_endian<T: Copy + SwappableNumber>(data: &[u8]) -> T {
+    let width = mem::size_of::<T>();
+    debug_assert!(data.len() >= width);
+    let mut value = mem::MaybeUninit::<T>::uninit();
+    // SAFETY: the data is long enough, and every bit pattern is valid for the
+    // number types that implement SwappableNumber
+    unsafe {
+        let value_ptr = value.as_mut_ptr() as *mut u8;
+        ptr::copy_nonoverlapping(data.as_ptr(), value_ptr, width);
+        #[cfg(target_endian = "little")]
+        slice::from_raw_parts_mut(value_ptr, width).reverse();
+        value.assume_init()
+    }
+}
+
+impl<T: Copy + SwappableNumber> Iterator for RawListIter<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (item, rest) = self.data.split_at_checked(mem::size_of::<T>())?;
+        self.data = rest;
+        Some(read_big_endian(item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.data.len() / mem::size_of::<T>();
+        (len, Some(len))
+    }
+}
+impl<T: Copy + SwappableNumber> DoubleEndedIterator for RawListIter<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        let index = self.data.len().checked_sub(mem::size_of::<T>())?;
+        let (rest, item) = self.data.split_at(index);
+        self.data = rest;
+        Some(read_big_endian(item))
+    }
+}
+impl<T: Copy + SwappableNumber> ExactSizeIterator for RawListIter<'_, T> {}
+impl<T: Copy + SwappableNumber> FusedIterator for RawListIter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter() {
+        let list = RawList::<i32>::new(&[0, 0, 0, 1, 0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(list.iter().collect::<Vec<_>>(), [1, -2]);
+        assert_eq!(list.iter().rev().collect::<Vec<_>>(), [-2, 1]);
+        assert_eq!(list.iter().len(), 2);
+
+        let list = RawList::<f64>::new(&[0x3f, 0xf8, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(list.iter().next(), Some(1.5));
+        assert_eq!(RawList::<u16>::new(&[]).iter().next(), None);
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::Cursor;
+
+use crate::{borrow, owned, Error, FromNbtTag, ToNbtTag};
+
+/// A tag that hasn't been parsed yet.
+///
+/// When it's used as a field in a struct with `#[derive(Deserialize)]`, the
+/// bytes for the field get copied without being parsed, so you can decide
+/// later whether they should be parsed at all. This is similar to
+/// `serde_json::value::RawValue`, and it's useful for skipping big subtrees
+/// that you usually don't need.
+///
+/// Since the derive doesn't keep a reference to the original data, the bytes
+/// are owned.
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// # use simdnbt::{Deserialize, RawNbtValue};
+/// #[derive(Deserialize)]
+/// struct Item {
+///     id: String,
+///     tag: RawNbtValue,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawNbtValue {
+    /// The tag ID followed by the payload.
+    data: Vec<u8>,
+}
+
+impl RawNbtValue {
+    /// Encode an owned tag into a raw value.
+    pub fn from_tag(tag: &owned::NbtTag) -> Self {
+        let mut data = Vec::new();
+        tag.write(&mut data);
+        Self { data }
+    }
+
+    /// Make a raw value from a tag ID followed by its payload.
+    pub(crate) fn from_bytes(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    /// Get the numerical ID of the tag type.
+    pub fn id(&self) -> u8 {
+        self.data[0]
+    }
+
+    /// Returns the encoded payload of the tag, without the tag ID.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[1..]
+    }
+
+    /// Parse the tag into an owned tag.
+    pub fn parse(&self) -> Result<owned::NbtTag, Error> {
+        Ok(owned::read_tag(&mut Cursor::new(&self.data))?)
+    }
+
+    /// Parse the tag into a borrowed tag, which is usually faster than
+    /// [`Self::parse`].
+    pub fn parse_borrowed(&self) -> Result<borrow::BaseNbtTag<'_>, Error> {
+        borrow::read_tag(&mut Cursor::new(&self.data))
+    }
+}
+
+impl FromNbtTag for RawNbtValue {
+    fn from_nbt_tag(tag: borrow::NbtTag) -> Option<Self> {
+        let mut data = vec![tag.id()];
+        match tag.raw_bytes() {
+            Some(bytes) => data.extend_from_slice(bytes),
+            // we don't know where the tag is in the original data, so we have
+            // to write it again
+            None => borrow::write_tag(tag, &mut data, &Default::default(), false),
+        }
+        Some(Self { data })
+    }
+}
+
+impl ToNbtTag for RawNbtValue {
+    fn to_nbt_tag(self) -> owned::NbtTag {
+        // raw values are always made from tags that were already valid
+        self.parse().expect("raw value should contain a valid tag")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compound_in_list() {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("id", "minecraft:stone");
+        let list = owned::NbtList::Compound(vec![compound.clone()]);
+        let mut data = Vec::new();
+        owned::NbtTag::List(list).write(&mut data);
+
+        let tag = borrow::read_tag(&mut Cursor::new(&data)).unwrap();
+        let element = tag.as_tag().list().unwrap().iter().next().unwrap();
+        let raw = RawNbtValue::from_nbt_tag(element).unwrap();
+        assert_eq!(
+            raw,
+            RawNbtValue::from_tag(&owned::NbtTag::Compound(compound))
+        );
+        assert_eq!(raw.id(), crate::common::COMPOUND_ID);
+        let parsed = raw.parse_borrowed().unwrap();
+        let parsed = parsed.as_tag().compound().unwrap();
+        assert_eq!(parsed.string("id").unwrap().to_str(), "minecraft:stone");
+    }
+}
@@ -1,3 +1,22 @@
+//! A fast reader for big-endian binary data, for writing parsers by hand.
+//!
+//! This is what simdnbt uses internally to read NBT. It has methods for
+//! reading numbers and the parts of NBT that have lengths, and never copies
+//! the data.
+//!
+//! ```
+//! use simdnbt::reader::Reader;
+//!
+//! // a string tag that's named "a" and contains "hi"
+//! let data = [8, 0, 1, b'a', 0, 2, b'h', b'i'];
+//! let mut reader = Reader::new(&data);
+//! assert_eq!(reader.read_u8()?, 8);
+//! assert_eq!(reader.read_string()?.to_str(), "a");
+//! assert_eq!(reader.read_string()?.to_str(), "hi");
+//! assert_eq!(reader.remaining(), 0);
+//! # Ok::<(), simdnbt::Error>(())
+//! ```
+
 use std::{
     io::Cursor,
     marker::PhantomData,
@@ -5,34 +24,57 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
-use crate::error::UnexpectedEofError;
+pub use crate::error::UnexpectedEofError;
+use crate::{
+    common::{read_int_array, read_long_array, read_string, read_u8_array, skip_tag},
+    options::Limits,
+    raw_list::RawList,
+    Error, Mutf8Str,
+};
 
+/// Reads big-endian data from a byte slice.
+///
+/// Reading past the end of the data returns an [`UnexpectedEofError`] and
+/// leaves the reader in an unspecified position, so it shouldn't be used
+/// again after that.
+#[derive(Clone)]
 pub struct Reader<'a> {
-    pub cur: *const u8,
+    pub(crate) cur: *const u8,
     /// pointer to after the last byte (so remaining=end-cur)
     end: *const u8,
+    /// The longest that lists and arrays are allowed to be. This is here
+    /// instead of in [`Limits`] so it can be checked while reading the length.
+    pub(crate) max_list_len: u32,
 
     _marker: PhantomData<&'a ()>,
 }
 
 impl<'a> Reader<'a> {
+    /// Make a reader that starts at the beginning of the data.
     pub fn new(data: &'a [u8]) -> Reader<'a> {
         Self {
             cur: data.as_ptr(),
             end: unsafe { data.as_ptr().add(data.len()) },
+            max_list_len: i32::MAX as u32,
             _marker: PhantomData,
         }
     }
 
     #[inline]
-    pub fn end_addr(&self) -> usize {
+    pub(crate) fn end_addr(&self) -> usize {
         self.end as usize
     }
 
+    /// The number of bytes that haven't been read yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        // a failed read can leave the pointer past the end
+        (self.end as usize).saturating_sub(self.cur as usize)
+    }
+
+    /// Return an error if there are fewer than `size` bytes left.
     pub fn ensure_can_read(&self, size: usize) -> Result<(), UnexpectedEofError> {
-        let data_addr = self.cur as usize;
-        let end_addr = self.end as usize;
-        if data_addr + size > end_addr {
+        if size > self.remaining() {
             Err(UnexpectedEofError)
         } else {
             Ok(())
@@ -40,13 +82,13 @@ impl<'a> Reader<'a> {
     }
 
     #[inline]
-    pub unsafe fn read_type_unchecked<T>(&mut self) -> T {
+    pub(crate) unsafe fn read_type_unchecked<T>(&mut self) -> T {
         let value = unsafe { self.cur.cast::<T>().read_unaligned() };
         self.cur = unsafe { self.cur.add(mem::size_of::<T>()) };
         value
     }
 
-    pub fn read_type<T: Copy>(&mut self) -> Result<T, UnexpectedEofError> {
+    pub(crate) fn read_type<T: Copy>(&mut self) -> Result<T, UnexpectedEofError> {
         let addr = self.cur;
 
         // it's faster to add and then check for eof. it does leave our pointer in a bad
@@ -61,67 +103,78 @@ impl<'a> Reader<'a> {
         Ok(value)
     }
 
+    /// Read a big-endian `u8`.
     #[inline]
     pub fn read_u8(&mut self) -> Result<u8, UnexpectedEofError> {
         self.read_type()
     }
+    /// Read a big-endian `i8`.
     #[inline]
     pub fn read_i8(&mut self) -> Result<i8, UnexpectedEofError> {
         self.read_u8().map(|x| x as i8)
     }
 
+    /// Read a big-endian `u16`.
     #[inline]
     pub fn read_u16(&mut self) -> Result<u16, UnexpectedEofError> {
         self.read_type::<u16>().map(u16::to_be)
     }
+    /// Read a big-endian `i16`.
     #[inline]
     pub fn read_i16(&mut self) -> Result<i16, UnexpectedEofError> {
         self.read_u16().map(|x| x as i16)
     }
 
+    /// Read a big-endian `u32`.
     #[inline]
     pub fn read_u32(&mut self) -> Result<u32, UnexpectedEofError> {
         self.read_type::<u32>().map(u32::to_be)
     }
+    /// Read a big-endian `i32`.
     #[inline]
     pub fn read_i32(&mut self) -> Result<i32, UnexpectedEofError> {
         self.read_u32().map(|x| x as i32)
     }
 
+    /// Read a big-endian `u64`.
     #[inline]
     pub fn read_u64(&mut self) -> Result<u64, UnexpectedEofError> {
         self.read_type::<u64>().map(u64::to_be)
     }
+    /// Read a big-endian `i64`.
     #[inline]
     pub fn read_i64(&mut self) -> Result<i64, UnexpectedEofError> {
         self.read_u64().map(|x| x as i64)
     }
 
+    /// Read a big-endian `f32`.
     #[inline]
     pub fn read_f32(&mut self) -> Result<f32, UnexpectedEofError> {
         self.read_u32().map(f32::from_bits)
     }
 
+    /// Read a big-endian `f64`.
     #[inline]
     pub fn read_f64(&mut self) -> Result<f64, UnexpectedEofError> {
         self.read_u64().map(f64::from_bits)
     }
 
+    /// Move forward by `size` bytes.
     #[inline]
     pub fn skip(&mut self, size: usize) -> Result<(), UnexpectedEofError> {
-        self.cur = unsafe { self.cur.add(size) };
-        if self.cur > self.end {
-            return Err(UnexpectedEofError);
-        }
-
+        // compare with the remaining length first so we never create a pointer
+        // that's past the end of the data
+        self.ensure_can_read(size)?;
+        unsafe { self.skip_unchecked(size) };
         Ok(())
     }
 
     #[inline]
-    pub unsafe fn skip_unchecked(&mut self, size: usize) {
+    pub(crate) unsafe fn skip_unchecked(&mut self, size: usize) {
         self.cur = unsafe { self.cur.add(size) };
     }
 
+    /// Read the next `size` bytes without copying them.
     #[inline]
     pub fn read_slice(&mut self, size: usize) -> Result<&'a [u8], UnexpectedEofError> {
         self.ensure_can_read(size)?;
@@ -129,6 +182,40 @@ impl<'a> Reader<'a> {
         unsafe { self.skip_unchecked(size) };
         Ok(slice)
     }
+
+    /// Read a string that's prefixed by its length as a `u16`, like the names
+    /// of tags and the payload of string tags.
+    pub fn read_string(&mut self) -> Result<&'a Mutf8Str, UnexpectedEofError> {
+        read_string(self)
+    }
+
+    /// Read the payload of a byte array tag.
+    pub fn read_byte_array(&mut self) -> Result<&'a [u8], Error> {
+        Ok(read_u8_array(self)?)
+    }
+
+    /// Read the payload of an int array tag.
+    pub fn read_int_array(&mut self) -> Result<RawList<'a, i32>, Error> {
+        Ok(read_int_array(self)?)
+    }
+
+    /// Read the payload of a long array tag.
+    pub fn read_long_array(&mut self) -> Result<RawList<'a, i64>, Error> {
+        Ok(read_long_array(self)?)
+    }
+
+    /// Move past the payload of a tag with the given ID, like one that you
+    /// don't care about. This is the same as
+    /// [`validate::skip_tag`](crate::validate::skip_tag).
+    pub fn skip_tag(&mut self, tag_type: u8) -> Result<(), Error> {
+        Ok(skip_tag(self, tag_type, 0)?)
+    }
+
+    /// The address of the next byte, for measuring how much was read.
+    #[inline]
+    pub(crate) fn addr(&self) -> usize {
+        self.cur as usize
+    }
 }
 
 impl<'a> From<&'a [u8]> for Reader<'a> {
@@ -137,19 +224,72 @@ impl<'a> From<&'a [u8]> for Reader<'a> {
     }
 }
 
+/// A [`Reader`] that starts at the position of a [`Cursor`], and moves the
+/// cursor to wherever it stopped reading when it's dropped.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use simdnbt::reader::ReaderFromCursor;
+///
+/// let mut cursor = Cursor::new(&[1, 2, 3][..]);
+/// cursor.set_position(1);
+/// let mut reader = ReaderFromCursor::new(&mut cursor);
+/// assert_eq!(reader.read_u8()?, 2);
+/// drop(reader);
+/// assert_eq!(cursor.position(), 2);
+/// # Ok::<(), simdnbt::Error>(())
+/// ```
 pub struct ReaderFromCursor<'a: 'cursor, 'cursor> {
     reader: Reader<'a>,
     original_cursor: &'cursor mut Cursor<&'a [u8]>,
+    /// Whether the data was cut off because of [`Limits::max_bytes`].
+    truncated: bool,
 }
 
 impl<'a, 'cursor> ReaderFromCursor<'a, 'cursor> {
+    /// Make a reader that starts at the cursor's position.
     pub fn new(cursor: &'cursor mut Cursor<&'a [u8]>) -> Self {
-        let data = cursor.get_ref();
         Self {
-            reader: Reader::new(data[cursor.position() as usize..].as_ref()),
+            reader: Reader::new(remaining(cursor)),
             original_cursor: cursor,
+            truncated: false,
         }
     }
+
+    /// Make a reader that enforces the limits that can be checked while
+    /// reading, which are [`Limits::max_bytes`] and [`Limits::max_list_len`].
+    pub(crate) fn with_limits(cursor: &'cursor mut Cursor<&'a [u8]>, limits: &Limits) -> Self {
+        let data = remaining(cursor);
+        let truncated = data.len() > limits.max_bytes;
+        let mut reader = Reader::new(&data[..data.len().min(limits.max_bytes)]);
+        reader.max_list_len = limits.max_list_len;
+        Self {
+            reader,
+            original_cursor: cursor,
+            truncated,
+        }
+    }
+
+    /// Running out of data is only an error in the NBT if it wasn't cut off
+    /// early, so this turns those errors into [`Error::TooManyBytes`].
+    pub(crate) fn map_err(&self, err: Error) -> Error {
+        if self.truncated && err == Error::UnexpectedEof {
+            Error::TooManyBytes
+        } else {
+            err
+        }
+    }
+}
+/// The data after the cursor's position. If the position is past the end of
+/// the data, this is an empty slice at the end so reading from it returns
+/// [`Error::UnexpectedEof`].
+fn remaining<'a>(cursor: &Cursor<&'a [u8]>) -> &'a [u8] {
+    let data = *cursor.get_ref();
+    // the empty slice has to be in the data, since the position that's set
+    // when the reader is dropped is calculated from the pointer
+    data.get(cursor.position() as usize..)
+        .unwrap_or(&data[data.len()..])
 }
 impl Drop for ReaderFromCursor<'_, '_> {
     fn drop(&mut self) {
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use crate::{owned::NbtCompound, packed::PackedBits, Deserialize, Serialize};
+
+/// The number of blocks in a chunk section.
+const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
+/// The number of biomes in a chunk section, which are stored in 4x4x4 cells.
+const BIOMES_PER_SECTION: usize = 4 * 4 * 4;
+
+/// A chunk from a region file, in the format used since 1.18. See
+/// [`data_version::FLAT_CHUNKS`](super::data_version::FLAT_CHUNKS).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Chunk {
+    #[simdnbt(rename = "DataVersion")]
+    pub data_version: i32,
+    /// The x coordinate of the chunk, which is the block x coordinate divided
+    /// by 16.
+    #[simdnbt(rename = "xPos")]
+    pub x_pos: i32,
+    /// The lowest section in the chunk, like -4 in the overworld.
+    #[simdnbt(rename = "yPos")]
+    pub y_pos: Option<i32>,
+    #[simdnbt(rename = "zPos")]
+    pub z_pos: i32,
+    /// How far the chunk has been generated, like `minecraft:full`.
+    #[simdnbt(rename = "Status")]
+    pub status: String,
+    /// The game tick when the chunk was last saved.
+    #[simdnbt(rename = "LastUpdate")]
+    pub last_update: i64,
+    /// The total number of ticks that players have spent in the chunk.
+    #[simdnbt(rename = "InhabitedTime")]
+    pub inhabited_time: i64,
+    pub sections: Vec<Section>,
+    /// Block entities like chests and signs. These have too many formats to
+    /// have a struct.
+    pub block_entities: Vec<NbtCompound>,
+    #[simdnbt(rename = "Heightmaps")]
+    pub heightmaps: Heightmaps,
+}
+
+impl Chunk {
+    /// Get the section with the given section y coordinate, which is the block
+    /// y coordinate divided by 16.
+    pub fn section(&self, y: i8) -> Option<&Section> {
+        self.sections.iter().find(|section| section.y == y)
+    }
+}
+
+/// The height of the highest block that matches a condition in each column of
+/// the chunk, packed into long arrays. Read them with
+/// [`PackedBits::bits_for`]`(world_height + 1)` bits per entry.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Heightmaps {
+    #[simdnbt(rename = "MOTION_BLOCKING")]
+    pub motion_blocking: Option<Vec<i64>>,
+    #[simdnbt(rename = "MOTION_BLOCKING_NO_LEAVES")]
+    pub motion_blocking_no_leaves: Option<Vec<i64>>,
+    #[simdnbt(rename = "OCEAN_FLOOR")]
+    pub ocean_floor: Option<Vec<i64>>,
+    #[simdnbt(rename = "WORLD_SURFACE")]
+    pub world_surface: Option<Vec<i64>>,
+}
+
+/// A 16x16x16 section of a chunk.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Section {
+    /// The section y coordinate, which is the block y coordinate divided by 16.
+    #[simdnbt(rename = "Y")]
+    pub y: i8,
+    pub block_states: Option<BlockStates>,
+    pub biomes: Option<Biomes>,
+    /// The block light level of every block, as 4 bits each.
+    #[simdnbt(rename = "BlockLight")]
+    pub block_light: Option<Vec<u8>>,
+    /// The sky light level of every block, as 4 bits each.
+    #[simdnbt(rename = "SkyLight")]
+    pub sky_light: Option<Vec<u8>>,
+}
+
+/// The blocks in a section, as indices into a palette that are packed into
+/// longs.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BlockStates {
+    pub palette: Vec<BlockState>,
+    /// Missing if the palette only has one block.
+    pub data: Option<Vec<i64>>,
+}
+
+impl BlockStates {
+    /// Get the palette index of every block in the section, in YZX order (so
+    /// the index of a block is `(y * 16 + z) * 16 + x`).
+    ///
+    /// Returns `None` if the palette is too big for the indices to be packed.
+    pub fn indices(&self) -> Option<Vec<u16>> {
+        unpack(
+            self.data.as_deref(),
+            palette_bits(self.palette.len(), 4)?,
+            BLOCKS_PER_SECTION,
+        )
+    }
+
+    /// Get the block at the coordinates relative to the section, or `None` if
+    /// the data doesn't match the palette.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&BlockState> {
+        let index = get(
+            self.data.as_deref(),
+            palette_bits(self.palette.len(), 4)?,
+            (y * 16 + z) * 16 + x,
+        )?;
+        self.palette.get(index as usize)
+    }
+}
+
+/// A block and its properties, like `minecraft:oak_stairs` with `facing` set
+/// to `north`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BlockState {
+    #[simdnbt(rename = "Name")]
+    pub name: String,
+    #[simdnbt(rename = "Properties")]
+    pub properties: Option<HashMap<String, String>>,
+}
+
+/// The biomes in a section, for each 4x4x4 cell of blocks.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Biomes {
+    /// Biome IDs, like `minecraft:plains`.
+    pub palette: Vec<String>,
+    /// Missing if the palette only has one biome.
+    pub data: Option<Vec<i64>>,
+}
+
+impl Biomes {
+    /// Get the palette index of every cell in the section, in YZX order (so
+    /// the index of a cell is `(y * 4 + z) * 4 + x`).
+    ///
+    /// Returns `None` if the palette is too big for the indices to be packed.
+    pub fn indices(&self) -> Option<Vec<u16>> {
+        unpack(
+            self.data.as_deref(),
+            palette_bits(self.palette.len(), 1)?,
+            BIOMES_PER_SECTION,
+        )
+    }
+
+    /// Get the biome at the cell coordinates (the block coordinates relative
+    /// to the section divided by 4), or `None` if the data doesn't match the
+    /// palette.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&str> {
+        let index = get(
+            self.data.as_deref(),
+            palette_bits(self.palette.len(), 1)?,
+            (y * 4 + z) * 4 + x,
+        )?;
+        self.palette.get(index as usize).map(String::as_str)
+    }
+}
+
+/// The number of bits that the indices into a palette are packed with, or
+/// `None` if the palette is too big for [`PackedBits`]. The palette comes from
+/// the chunk data, so it can be any length.
+fn palette_bits(palette_len: usize, min_bits: u32) -> Option<u32> {
+    let bits = PackedBits::bits_for(palette_len).max(min_bits);
+    (bits <= PackedBits::MAX_BITS_PER_ENTRY).then_some(bits)
+}
+
+fn unpack(data: Option<&[i64]>, bits_per_entry: u32, len: usize) -> Option<Vec<u16>> {
+    let Some(data) = data else {
+        // every entry is the first thing in the palette
+        return Some(vec![0; len]);
+    };
+    let mut entries = PackedBits::new(data, bits_per_entry)?.to_vec();
+    entries.resize(len, 0);
+    Some(entries)
+}
+
+fn get(data: Option<&[i64]>, bits_per_entry: u32, index: usize) -> Option<u16> {
+    match data {
+        Some(data) => PackedBits::new(data, bits_per_entry)?.get(index),
+        None => Some(0),
+    }
+}
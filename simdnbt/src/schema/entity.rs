@@ -0,0 +1,43 @@
+use crate::{Deserialize, Serialize};
+
+/// The tags that every entity has. Players and other entities have many more
+/// tags, which aren't included.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Entity {
+    /// The entity type, like `minecraft:zombie`. Players don't have this.
+    pub id: Option<String>,
+    /// The x, y, and z coordinates.
+    #[simdnbt(rename = "Pos")]
+    pub pos: Vec<f64>,
+    /// The velocity along the x, y, and z axes, in blocks per tick.
+    #[simdnbt(rename = "Motion")]
+    pub motion: Vec<f64>,
+    /// The yaw and pitch, in degrees.
+    #[simdnbt(rename = "Rotation")]
+    pub rotation: Vec<f32>,
+    /// The UUID as four ints, most significant first. Entities from before
+    /// 1.16 have `UUIDMost` and `UUIDLeast` instead.
+    #[simdnbt(rename = "UUID")]
+    pub uuid: Option<[i32; 4]>,
+    #[simdnbt(rename = "OnGround", lenient)]
+    pub on_ground: bool,
+    #[simdnbt(rename = "FallDistance", lenient)]
+    pub fall_distance: f32,
+    /// The number of ticks until the fire goes out, or a negative number of
+    /// ticks that the entity is immune to fire for.
+    #[simdnbt(rename = "Fire")]
+    pub fire: i16,
+    #[simdnbt(rename = "Air")]
+    pub air: i16,
+    #[simdnbt(rename = "Invulnerable")]
+    pub invulnerable: Option<bool>,
+    /// The custom name as a JSON text component.
+    #[simdnbt(rename = "CustomName")]
+    pub custom_name: Option<String>,
+    /// The scoreboard tags that were added with `/tag`.
+    #[simdnbt(rename = "Tags")]
+    pub tags: Option<Vec<String>>,
+    /// The entities riding this one.
+    #[simdnbt(rename = "Passengers")]
+    pub passengers: Option<Vec<Entity>>,
+}
@@ -0,0 +1,54 @@
+use crate::{owned::NbtCompound, Deserialize, Serialize};
+
+/// An item in an inventory or on the ground, in the format used since 1.20.5.
+/// See [`data_version::ITEM_COMPONENTS`](super::data_version::ITEM_COMPONENTS).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ItemStack {
+    pub id: String,
+    pub count: i32,
+    /// Data like enchantments and custom names, keyed by the component ID.
+    pub components: Option<NbtCompound>,
+    /// The slot that the item is in, for items in inventories.
+    #[simdnbt(rename = "Slot")]
+    pub slot: Option<i8>,
+}
+
+/// An item in the format used before 1.20.5. See [`ItemStack`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LegacyItemStack {
+    pub id: String,
+    #[simdnbt(rename = "Count")]
+    pub count: i8,
+    /// The damage of tools, or the variant of items before 1.13.
+    #[simdnbt(rename = "Damage")]
+    pub damage: Option<i16>,
+    /// Data like enchantments and custom names.
+    pub tag: Option<NbtCompound>,
+    #[simdnbt(rename = "Slot")]
+    pub slot: Option<i8>,
+}
+
+/// Components and tags can't be converted without knowing what every one of
+/// them means, so they're dropped. The count saturates at `i8::MAX`.
+impl From<ItemStack> for LegacyItemStack {
+    fn from(item: ItemStack) -> Self {
+        Self {
+            id: item.id,
+            count: item.count.clamp(i8::MIN as i32, i8::MAX as i32) as i8,
+            damage: None,
+            tag: None,
+            slot: item.slot,
+        }
+    }
+}
+/// See the `From<ItemStack>` impl for [`LegacyItemStack`].
+impl From<LegacyItemStack> for ItemStack {
+    fn from(item: LegacyItemStack) -> Self {
+        Self {
+            id: item.id,
+            count: item.count as i32,
+            components: None,
+            slot: item.slot,
+        }
+    }
+}
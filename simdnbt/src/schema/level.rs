@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::{owned::NbtCompound, Deserialize, Serialize};
+
+/// A `level.dat` file, which has everything about a world that isn't stored in
+/// its chunks.
+///
+/// The file is gzipped, so decompress it before reading it, or read it with
+/// `borrow::read_compressed`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LevelDat {
+    #[simdnbt(rename = "Data")]
+    pub data: LevelData,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LevelData {
+    #[simdnbt(rename = "LevelName")]
+    pub level_name: String,
+    /// The version of the data in the world. This is missing in worlds from
+    /// before 1.9.
+    #[simdnbt(rename = "DataVersion")]
+    pub data_version: Option<i32>,
+    /// The version of the game that last saved the world. This is missing in
+    /// worlds from before 1.9.
+    #[simdnbt(rename = "Version")]
+    pub version: Option<Version>,
+    /// 0 for survival, 1 for creative, 2 for adventure, and 3 for spectator.
+    #[simdnbt(rename = "GameType")]
+    pub game_type: i32,
+    #[simdnbt(rename = "Difficulty")]
+    pub difficulty: Option<i8>,
+    #[simdnbt(lenient)]
+    pub hardcore: bool,
+    #[simdnbt(rename = "allowCommands", lenient)]
+    pub allow_commands: bool,
+    #[simdnbt(rename = "SpawnX")]
+    pub spawn_x: i32,
+    #[simdnbt(rename = "SpawnY")]
+    pub spawn_y: i32,
+    #[simdnbt(rename = "SpawnZ")]
+    pub spawn_z: i32,
+    /// The number of ticks since the world was created.
+    #[simdnbt(rename = "Time")]
+    pub time: i64,
+    /// The time of day in ticks. This keeps going up after the first day, so
+    /// take it modulo 24000 to get the time of the current day.
+    #[simdnbt(rename = "DayTime")]
+    pub day_time: i64,
+    /// When the world was last played, in milliseconds since the Unix epoch.
+    #[simdnbt(rename = "LastPlayed")]
+    pub last_played: i64,
+    #[simdnbt(lenient)]
+    pub raining: bool,
+    #[simdnbt(lenient)]
+    pub thundering: bool,
+    /// Every game rule is stored as a string, even if it's a number or a
+    /// boolean.
+    #[simdnbt(rename = "GameRules")]
+    pub game_rules: Option<HashMap<String, String>>,
+    /// The seed and dimension generators, since 1.16.
+    #[simdnbt(rename = "WorldGenSettings")]
+    pub world_gen_settings: Option<NbtCompound>,
+    /// The seed before 1.16.
+    #[simdnbt(rename = "RandomSeed")]
+    pub random_seed: Option<i64>,
+    /// The player in singleplayer worlds.
+    #[simdnbt(rename = "Player")]
+    pub player: Option<NbtCompound>,
+}
+
+/// The version of the game that saved a world.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Version {
+    /// The same as the world's `DataVersion`.
+    #[simdnbt(rename = "Id")]
+    pub id: i32,
+    /// The name of the version, like `1.21.4`.
+    #[simdnbt(rename = "Name")]
+    pub name: String,
+    #[simdnbt(rename = "Snapshot", lenient)]
+    pub snapshot: bool,
+}
@@ -0,0 +1,173 @@
+//! Structs for common Minecraft NBT documents, like chunks, entities, items,
+//! and `level.dat`.
+//!
+//! These derive [`Deserialize`](crate::Deserialize) and
+//! [`Serialize`](crate::Serialize), so they can be read from and written to
+//! NBT like any other struct. They only have the fields that most tools
+//! need, so tags that aren't in the structs are ignored when reading and lost
+//! when writing. Formats that changed between versions have a struct for each
+//! version, and the [`data_version`] module has the `DataVersion`s where they
+//! changed.
+//!
+//! ```
+//! use simdnbt::{
+//!     schema::{ItemStack, LegacyItemStack},
+//!     Deserialize, Serialize,
+//! };
+//!
+//! let item = ItemStack {
+//!     id: "minecraft:diamond".to_owned(),
+//!     count: 3,
+//!     components: None,
+//!     slot: None,
+//! };
+//! let compound = item.clone().to_compound();
+//! assert_eq!(compound.int("count"), Some(3));
+//!
+//! // items from before 1.20.5 had a byte for the count instead
+//! let legacy = LegacyItemStack::from(item);
+//! assert_eq!(legacy.to_compound().byte("Count"), Some(3));
+//! ```
+
+mod chunk;
+mod entity;
+mod item;
+mod level;
+
+pub use self::{
+    chunk::{Biomes, BlockState, BlockStates, Chunk, Heightmaps, Section},
+    entity::Entity,
+    item::{ItemStack, LegacyItemStack},
+    level::{LevelDat, LevelData, Version},
+};
+
+/// The `DataVersion`s where the formats of the structs in this module changed.
+pub mod data_version {
+    /// 1.18 (21w43a), when chunks stopped having a `Level` compound and
+    /// sections got paletted `block_states` and `biomes`. [`Chunk`] is for
+    /// chunks from this version or later.
+    ///
+    /// [`Chunk`]: super::Chunk
+    pub const FLAT_CHUNKS: i32 = 2844;
+    /// 1.20.5 (24w09a), when items switched from a `tag` compound to
+    /// `components`. [`ItemStack`] is for items from this version or later, and
+    /// [`LegacyItemStack`] is for items from before it.
+    ///
+    /// [`ItemStack`]: super::ItemStack
+    /// [`LegacyItemStack`]: super::LegacyItemStack
+    pub const ITEM_COMPONENTS: i32 = 3819;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        owned::{BaseNbt, NbtCompound},
+        packed::PackedBits,
+        test_fixtures::level_dat,
+        Deserialize, Serialize,
+    };
+
+    #[test]
+    fn old_level_dat() {
+        let data = level_dat();
+        let nbt = crate::borrow::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+
+        let level = LevelDat::from_nbt(&nbt).unwrap();
+        assert_eq!(level.data.level_name, "1.0.0");
+        assert_eq!(level.data.data_version, None);
+        assert_eq!(level.data.version, None);
+    }
+
+    #[test]
+    fn chunk_roundtrip() {
+        let palette = (0..5)
+            .map(|i| BlockState {
+                name: format!("minecraft:block_{i}"),
+                properties: (i == 2).then(|| {
+                    [("facing".to_owned(), "north".to_owned())]
+                        .into_iter()
+                        .collect()
+                }),
+            })
+            .collect::<Vec<_>>();
+        let indices = (0..4096).map(|i| (i % 5) as u16).collect::<Vec<_>>();
+        let chunk = Chunk {
+            data_version: 3953,
+            x_pos: -2,
+            y_pos: Some(-4),
+            z_pos: 7,
+            status: "minecraft:full".to_owned(),
+            last_update: 100,
+            inhabited_time: 0,
+            sections: vec![Section {
+                y: -1,
+                block_states: Some(BlockStates {
+                    palette,
+                    // 5 entries need 3 bits, but block states use at least 4
+                    data: PackedBits::pack(&indices, 4),
+                }),
+                biomes: Some(Biomes {
+                    palette: vec!["minecraft:plains".to_owned()],
+                    data: None,
+                }),
+                block_light: None,
+                sky_light: Some(vec![0xff; 2048]),
+            }],
+            block_entities: vec![NbtCompound::from_values(vec![(
+                "id".into(),
+                "minecraft:chest".into(),
+            )])],
+            heightmaps: Heightmaps {
+                motion_blocking: Some(vec![1, 2, 3]),
+                motion_blocking_no_leaves: None,
+                ocean_floor: None,
+                world_surface: None,
+            },
+        };
+
+        let data = BaseNbt::new("", chunk.clone().to_compound()).to_bytes();
+        let nbt = crate::borrow::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+        let read = Chunk::from_nbt(&nbt).unwrap();
+        assert_eq!(read, chunk);
+
+        let section = read.section(-1).unwrap();
+        let block_states = section.block_states.as_ref().unwrap();
+        assert_eq!(block_states.indices(), Some(indices));
+        assert_eq!(block_states.get(2, 0, 0).unwrap().name, "minecraft:block_2");
+        let biomes = section.biomes.as_ref().unwrap();
+        assert_eq!(biomes.indices(), Some(vec![0; 64]));
+        assert_eq!(biomes.get(3, 3, 3), Some("minecraft:plains"));
+        assert!(read.section(0).is_none());
+    }
+
+    #[test]
+    fn oversized_palette() {
+        // too many entries for the indices to fit in 16 bits
+        let biomes = Biomes {
+            palette: vec!["minecraft:plains".to_owned(); 70000],
+            data: Some(vec![0; 64]),
+        };
+        assert_eq!(biomes.indices(), None);
+        assert_eq!(biomes.get(0, 0, 0), None);
+
+        let block_states = BlockStates {
+            palette: vec![
+                BlockState {
+                    name: "minecraft:stone".to_owned(),
+                    properties: None,
+                };
+                70000
+            ],
+            data: Some(vec![0; 256]),
+        };
+        assert_eq!(block_states.indices(), None);
+        assert_eq!(block_states.get(0, 0, 0), None);
+    }
+}
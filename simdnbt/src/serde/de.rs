@@ -0,0 +1,288 @@
+use std::borrow::Cow;
+
+use ::serde::{
+    de::{
+        self, value::SeqDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess,
+        Unexpected, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use super::Error;
+use crate::{
+    borrow::{NbtCompound, NbtCompoundIter, NbtListIter, NbtTag},
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    Mutf8Str,
+};
+
+/// A serde deserializer over a borrowed tag or compound.
+pub struct Deserializer<'a, 'tape> {
+    value: Value<'a, 'tape>,
+}
+
+enum Value<'a, 'tape> {
+    Tag(NbtTag<'a, 'tape>),
+    Compound(NbtCompound<'a, 'tape>),
+}
+
+impl<'a, 'tape> Deserializer<'a, 'tape> {
+    pub fn from_tag(tag: NbtTag<'a, 'tape>) -> Self {
+        Self {
+            value: Value::Tag(tag),
+        }
+    }
+
+    pub fn from_compound(compound: NbtCompound<'a, 'tape>) -> Self {
+        Self {
+            value: Value::Compound(compound),
+        }
+    }
+
+    fn tag(&self) -> Option<&NbtTag<'a, 'tape>> {
+        match &self.value {
+            Value::Tag(tag) => Some(tag),
+            Value::Compound(_) => None,
+        }
+    }
+}
+
+fn visit_string<'a, V: Visitor<'a>>(string: &'a Mutf8Str, visitor: V) -> Result<V::Value, Error> {
+    match string.to_str() {
+        Cow::Borrowed(string) => visitor.visit_borrowed_str(string),
+        Cow::Owned(string) => visitor.visit_string(string),
+    }
+}
+
+impl<'a> de::Deserializer<'a> for Deserializer<'a, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let tag = match self.value {
+            Value::Tag(tag) => tag,
+            Value::Compound(compound) => {
+                return visitor.visit_map(CompoundAccess {
+                    iter: compound.iter(),
+                    value: None,
+                })
+            }
+        };
+        match tag.id() {
+            BYTE_ID => visitor.visit_i8(tag.byte().unwrap()),
+            SHORT_ID => visitor.visit_i16(tag.short().unwrap()),
+            INT_ID => visitor.visit_i32(tag.int().unwrap()),
+            LONG_ID => visitor.visit_i64(tag.long().unwrap()),
+            FLOAT_ID => visitor.visit_f32(tag.float().unwrap()),
+            DOUBLE_ID => visitor.visit_f64(tag.double().unwrap()),
+            BYTE_ARRAY_ID => SeqDeserializer::new(tag.byte_array().unwrap().iter().copied())
+                .deserialize_any(visitor),
+            STRING_ID => visit_string(tag.string().unwrap(), visitor),
+            LIST_ID => visitor.visit_seq(ListAccess {
+                iter: tag.list().unwrap().iter(),
+            }),
+            COMPOUND_ID => visitor.visit_map(CompoundAccess {
+                iter: tag.compound().unwrap().iter(),
+                value: None,
+            }),
+            INT_ARRAY_ID => {
+                SeqDeserializer::new(tag.int_array().unwrap().into_iter()).deserialize_any(visitor)
+            }
+            LONG_ARRAY_ID => {
+                SeqDeserializer::new(tag.long_array().unwrap().into_iter()).deserialize_any(visitor)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::byte) {
+            Some(byte) => visitor.visit_bool(byte != 0),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    // unsigned numbers are stored as the signed type with the same size
+    fn deserialize_u8<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::byte) {
+            Some(byte) => visitor.visit_u8(byte as u8),
+            None => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u16<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::short) {
+            Some(short) => visitor.visit_u16(short as u16),
+            None => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u32<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::int) {
+            Some(int) => visitor.visit_u32(int as u32),
+            None => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u64<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::long) {
+            Some(long) => visitor.visit_u64(long as u64),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag().and_then(NbtTag::byte_array) {
+            Some(bytes) => visitor.visit_borrowed_bytes(bytes),
+            None => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_byte_buf<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    // missing fields are handled by serde, so if there's a tag then it's always
+    // Some
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+    fn deserialize_unit_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        // unit variants are strings, and other variants are a compound with the
+        // variant name as the only key
+        if let Some(string) = self.tag().and_then(NbtTag::string) {
+            return visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(
+                string.to_str(),
+            ));
+        }
+        let compound = match self.value {
+            Value::Tag(tag) => tag.compound(),
+            Value::Compound(compound) => Some(compound),
+        };
+        let mut iter = compound.iter().flat_map(NbtCompound::iter);
+        match (iter.next(), iter.next()) {
+            (Some((name, value)), None) => visitor.visit_enum(EnumAccess { name, value }),
+            _ => Err(de::Error::invalid_type(
+                Unexpected::Other("tag that isn't a string or a compound with one key"),
+                &visitor,
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        <V: Visitor<'a>>
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct ListAccess<'a, 'tape> {
+    iter: NbtListIter<'a, 'tape>,
+}
+impl<'a> SeqAccess<'a> for ListAccess<'a, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.iter
+            .next()
+            .map(|tag| seed.deserialize(Deserializer::from_tag(tag)))
+            .transpose()
+    }
+}
+
+struct CompoundAccess<'a, 'tape> {
+    iter: NbtCompoundIter<'a, 'tape>,
+    value: Option<NbtTag<'a, 'tape>>,
+}
+impl<'a> MapAccess<'a> for CompoundAccess<'a, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((name, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(name.to_str().into_deserializer())
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed is always called after next_key_seed");
+        seed.deserialize(Deserializer::from_tag(value))
+    }
+}
+
+struct EnumAccess<'a, 'tape> {
+    name: &'a Mutf8Str,
+    value: NbtTag<'a, 'tape>,
+}
+impl<'a, 'tape> de::EnumAccess<'a> for EnumAccess<'a, 'tape> {
+    type Error = Error;
+    type Variant = Deserializer<'a, 'tape>;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(self.name.to_str().into_deserializer())?;
+        Ok((variant, Deserializer::from_tag(self.value)))
+    }
+}
+impl<'a> de::VariantAccess<'a> for Deserializer<'a, '_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'a>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
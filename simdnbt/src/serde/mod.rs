@@ -0,0 +1,217 @@
+//! Compatibility with [serde](https://serde.rs), for types that already
+//! derive `serde::Serialize` and `serde::Deserialize`.
+//!
+//! Deserializing works on borrowed NBT, so strings can be borrowed from the
+//! input if they're valid UTF-8. Serializing produces an [`owned::NbtTag`],
+//! which can then be written like any other tag.
+//!
+//! Sequences are read from lists and arrays, and are written as lists. Enums
+//! are either a string for unit variants, or a compound with the variant name
+//! as its only key (so a list can't mix unit and non-unit variants). `None`
+//! and `()` fields are skipped when writing, and like the rest of simdnbt,
+//! unsigned integers are written as the signed type of the same size.
+//!
+//! ```
+//! use std::io::Cursor;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+//! struct Item<'a> {
+//!     id: &'a str,
+//!     #[serde(rename = "Count")]
+//!     count: i8,
+//! }
+//!
+//! let item = Item { id: "minecraft:stone", count: 1 };
+//! let compound = simdnbt::serde::to_compound(&item).unwrap();
+//! let data = simdnbt::owned::BaseNbt::new("", compound).to_bytes();
+//!
+//! let nbt = simdnbt::borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+//! let read: Item = simdnbt::serde::from_nbt(&nbt).unwrap();
+//! assert_eq!(read, item);
+//! ```
+
+mod de;
+mod ser;
+
+use ::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use self::{de::Deserializer, ser::Serializer};
+use crate::{borrow, owned};
+
+/// An error from serializing or deserializing with serde.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{0}")]
+pub struct Error(String);
+
+impl ::serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+impl ::serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Deserialize a value from the root compound of some borrowed NBT. The name
+/// of the root is ignored.
+pub fn from_nbt<'a, T: Deserialize<'a>>(nbt: &'a borrow::BaseNbt<'a>) -> Result<T, Error> {
+    from_compound(nbt.as_compound())
+}
+
+pub fn from_compound<'a, T: Deserialize<'a>>(
+    compound: borrow::NbtCompound<'a, '_>,
+) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_compound(compound))
+}
+
+pub fn from_tag<'a, T: Deserialize<'a>>(tag: borrow::NbtTag<'a, '_>) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_tag(tag))
+}
+
+/// Serialize a value into an owned tag.
+///
+/// This returns an error if the value is `None` or `()`, since those don't
+/// have an NBT representation.
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> Result<owned::NbtTag, Error> {
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error("Can't serialize None or () as a tag".to_owned()))
+}
+
+/// Serialize a value into an owned compound, which can then be written as the
+/// root of some NBT with [`owned::BaseNbt::new`].
+pub fn to_compound<T: Serialize + ?Sized>(value: &T) -> Result<owned::NbtCompound, Error> {
+    match to_tag(value)? {
+        owned::NbtTag::Compound(compound) => Ok(compound),
+        tag => Err(Error(format!(
+            "Expected the value to serialize to a compound, but it was tag id {}",
+            tag.id()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Cursor};
+
+    use ::serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::owned::{NbtCompound, NbtList, NbtTag};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f32),
+        Rect { width: i32, height: i32 },
+        Line(i16, i16),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Everything<'a> {
+        byte: i8,
+        unsigned: u8,
+        short: i16,
+        int: i32,
+        long: i64,
+        float: f32,
+        double: f64,
+        flag: bool,
+        name: &'a str,
+        owned_name: String,
+        nothing: Option<i32>,
+        something: Option<i32>,
+        numbers: Vec<i32>,
+        nested: Vec<Vec<String>>,
+        map: HashMap<String, i64>,
+        shape: Shape,
+        shapes: Vec<Shape>,
+        tuple: (i8, i8),
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = Everything {
+            byte: -1,
+            unsigned: 255,
+            short: 2,
+            int: 3,
+            long: 4,
+            float: 0.5,
+            double: 1.5,
+            flag: true,
+            name: "hello",
+            owned_name: "ünïcode".to_owned(),
+            nothing: None,
+            something: Some(6),
+            numbers: vec![1, 2, 3],
+            nested: vec![vec!["a".to_owned()], vec![]],
+            map: HashMap::from([("key".to_owned(), 7)]),
+            shape: Shape::Point,
+            shapes: vec![
+                Shape::Circle(1.),
+                Shape::Rect {
+                    width: 2,
+                    height: 3,
+                },
+                Shape::Line(4, 5),
+            ],
+            tuple: (8, 9),
+        };
+
+        let compound = to_compound(&value).unwrap();
+        assert_eq!(compound.byte("unsigned"), Some(-1));
+        assert_eq!(compound.byte("flag"), Some(1));
+        assert!(!compound.contains("nothing"));
+        assert_eq!(compound.list("numbers"), Some(&NbtList::Int(vec![1, 2, 3])));
+        assert_eq!(compound.string("shape").unwrap().to_str(), "Point");
+
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        let read: Everything = from_nbt(&nbt).unwrap();
+        assert_eq!(read, value);
+    }
+
+    #[test]
+    fn reads_arrays_as_sequences() {
+        let mut compound = NbtCompound::new();
+        compound.insert("bytes", NbtTag::ByteArray(vec![1, 2]));
+        compound.insert("ints", NbtTag::IntArray(vec![3, 4]));
+        compound.insert("longs", NbtTag::LongArray(vec![5]));
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+
+        #[derive(Deserialize)]
+        struct Arrays<'a> {
+            bytes: &'a [u8],
+            ints: Vec<i32>,
+            longs: [i64; 1],
+        }
+        let arrays: Arrays = from_nbt(&nbt).unwrap();
+        assert_eq!(arrays.bytes, [1, 2]);
+        assert_eq!(arrays.ints, [3, 4]);
+        assert_eq!(arrays.longs, [5]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(to_tag(&None::<i32>).is_err());
+        assert!(to_compound(&1).is_err());
+        assert!(to_tag(&vec![Some(1), None]).is_err());
+        assert!(to_tag(&HashMap::from([(1, 2)])).is_err());
+
+        let mut compound = NbtCompound::new();
+        compound.insert("a", 1);
+        let data = owned::BaseNbt::new("", compound).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Wrong {
+            a: String,
+        }
+        assert!(from_nbt::<Wrong>(&nbt).is_err());
+    }
+}
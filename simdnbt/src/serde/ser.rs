@@ -0,0 +1,317 @@
+use ::serde::ser::{self, Serialize};
+
+use super::Error;
+use crate::{
+    owned::{NbtCompound, NbtList, NbtTag},
+    Mutf8String,
+};
+
+/// A serde serializer that produces an owned tag.
+///
+/// The output is `None` for values that aren't written at all, like `None` and
+/// `()`. Use [`super::to_tag`] if you don't want to deal with that.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeCompound;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Byte(v as i8)))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Byte(v)))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Short(v)))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Int(v)))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Long(v)))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Byte(v as i8)))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Short(v as i16)))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Int(v as i32)))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Long(v as i64)))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Float(v)))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Double(v)))
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::String(v.to_string().into())))
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::String(v.into())))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::ByteArray(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Error> {
+        Ok(Some(NbtTag::Compound(NbtCompound::new())))
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        let mut compound = NbtCompound::new();
+        if let Some(tag) = value.serialize(self)? {
+            compound.insert(variant, tag);
+        }
+        Ok(Some(NbtTag::Compound(compound)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            tags: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            tags: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeCompound, Error> {
+        Ok(SerializeCompound {
+            compound: NbtCompound::from_values(Vec::with_capacity(len.unwrap_or_default())),
+            key: None,
+            variant: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeCompound, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeCompound, Error> {
+        Ok(SerializeCompound {
+            compound: NbtCompound::from_values(Vec::with_capacity(len)),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Wrap the tag in a compound with the variant name as the key, if this is
+/// for an enum variant.
+fn wrap_in_variant(tag: NbtTag, variant: Option<&'static str>) -> NbtTag {
+    match variant {
+        Some(variant) => NbtTag::Compound(NbtCompound::from_values(vec![(variant.into(), tag)])),
+        None => tag,
+    }
+}
+
+pub struct SerializeList {
+    tags: Vec<NbtTag>,
+    variant: Option<&'static str>,
+}
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let tag = value
+            .serialize(Serializer)?
+            .ok_or_else(|| Error("Lists can't contain None or ()".to_owned()))?;
+        self.tags.push(tag);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<NbtTag>, Error> {
+        let list = NbtList::from_tags(self.tags).ok_or_else(|| {
+            Error("All of the elements in a list must be the same type".to_owned())
+        })?;
+        Ok(Some(wrap_in_variant(NbtTag::List(list), self.variant)))
+    }
+}
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+pub struct SerializeCompound {
+    compound: NbtCompound,
+    key: Option<Mutf8String>,
+    variant: Option<&'static str>,
+}
+impl SerializeCompound {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        // None and () are skipped
+        if let Some(tag) = value.serialize(Serializer)? {
+            self.compound.insert(key, tag);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<NbtTag>, Error> {
+        Ok(Some(wrap_in_variant(
+            NbtTag::Compound(self.compound),
+            self.variant,
+        )))
+    }
+}
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(Serializer)? {
+            Some(NbtTag::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error("Compound keys must be strings".to_owned())),
+        }
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value is always called after serialize_key");
+        if let Some(tag) = value.serialize(Serializer)? {
+            self.compound.insert(key, tag);
+        }
+        Ok(())
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+impl ser::SerializeStructVariant for SerializeCompound {
+    type Ok = Option<NbtTag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
@@ -0,0 +1,773 @@
+//! Parse and write stringified NBT, the text format that's used in commands
+//! and data packs (like `{Count:1b,id:"minecraft:stone"}`).
+
+mod pretty;
+
+use std::fmt::{self, Write};
+
+use thiserror::Error;
+
+pub use self::pretty::PrettyOptions;
+use self::pretty::{Pretty, Printer};
+use crate::{
+    borrow,
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+        LONG_ARRAY_ID, LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
+    },
+    owned::{NbtCompound, NbtList, NbtTag},
+    Mutf8Str,
+};
+
+/// An error from parsing invalid SNBT.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at position {position}")]
+pub struct SnbtError {
+    pub message: String,
+    /// The byte offset in the input where the error happened.
+    pub position: usize,
+}
+
+/// Parse an SNBT compound, like `{Count:1b,id:"minecraft:stone"}`.
+///
+/// ```
+/// let compound = simdnbt::snbt::parse_compound(r#"{Count:1b,id:"minecraft:stone"}"#).unwrap();
+/// assert_eq!(compound.byte("Count"), Some(1));
+/// assert_eq!(compound.string("id").unwrap().to_str(), "minecraft:stone");
+/// ```
+pub fn parse_compound(input: &str) -> Result<NbtCompound, SnbtError> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_whitespace();
+    if parser.peek() != Some(b'{') {
+        return Err(parser.error("Expected a compound"));
+    }
+    let tag = parser.parse_value(0)?;
+    parser.expect_end()?;
+    match tag {
+        NbtTag::Compound(compound) => Ok(compound),
+        _ => unreachable!("we checked that the value starts with a brace"),
+    }
+}
+
+/// Parse any SNBT value, like `1b`, `"hello"`, `[I;1,2,3]`, or a compound.
+///
+/// Numbers without a suffix are ints (or doubles if they have a decimal
+/// point), `true` and `false` are bytes, and words that aren't numbers are
+/// strings. Like in Minecraft, numbers that are too big for their type are
+/// strings too.
+pub fn parse(input: &str) -> Result<NbtTag, SnbtError> {
+    let mut parser = Parser { input, pos: 0 };
+    let tag = parser.parse_value(0)?;
+    parser.expect_end()?;
+    Ok(tag)
+}
+
+/// A type that can be written as SNBT. This is implemented for the owned and
+/// borrowed tags, lists, and compounds.
+///
+/// The output is compact and can be parsed again with [`parse`]. Strings that
+/// aren't valid MUTF-8 are converted lossily, and NaN and infinite numbers are
+/// written like `NaNf`, `Infinityd`, and `-Infinityd`, which is how Minecraft
+/// writes them (although it reads them as strings).
+///
+/// ```
+/// use simdnbt::snbt::ToSnbt;
+///
+/// let mut compound = simdnbt::owned::NbtCompound::new();
+/// compound.insert("Count", 1i8);
+/// compound.insert("id", "minecraft:stone");
+/// assert_eq!(compound.to_snbt(), r#"{Count:1b,id:"minecraft:stone"}"#);
+/// ```
+pub trait ToSnbt {
+    fn write_snbt(&self, out: &mut String);
+
+    fn to_snbt(&self) -> String {
+        let mut out = String::new();
+        self.write_snbt(&mut out);
+        out
+    }
+
+    /// Write SNBT that's indented over multiple lines. See [`PrettyOptions`].
+    ///
+    /// The default implementation ignores the options and writes the same
+    /// thing as [`Self::write_snbt`].
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        let _ = opts;
+        self.write_snbt(out);
+    }
+
+    /// Make SNBT that's indented over multiple lines. This is also what the
+    /// alternate `Display` format (`{:#}`) writes with the default options.
+    ///
+    /// ```
+    /// use simdnbt::snbt::{PrettyOptions, ToSnbt};
+    ///
+    /// let mut compound = simdnbt::owned::NbtCompound::new();
+    /// compound.insert("Count", 1i8);
+    /// compound.insert("Pos", simdnbt::owned::NbtList::Double(vec![0.5, 64., 0.5]));
+    /// assert_eq!(
+    ///     compound.to_pretty_snbt(&PrettyOptions::default()),
+    ///     "{\n    Count: 1b,\n    Pos: [0.5d, 64d, 0.5d]\n}"
+    /// );
+    /// ```
+    fn to_pretty_snbt(&self, opts: &PrettyOptions) -> String {
+        let mut out = String::new();
+        self.write_pretty_snbt(&mut out, opts);
+        out
+    }
+}
+
+/// Implement `Display` as compact SNBT, or pretty SNBT with `{:#}`.
+macro_rules! impl_display {
+    ($($ty:ty),*) => {
+        $(
+            impl fmt::Display for $ty {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    if f.alternate() {
+                        f.write_str(&self.to_pretty_snbt(&PrettyOptions::default()))
+                    } else {
+                        f.write_str(&self.to_snbt())
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_display!(
+    NbtTag,
+    NbtList,
+    NbtCompound,
+    borrow::NbtTag<'_, '_>,
+    borrow::NbtList<'_, '_>,
+    borrow::NbtCompound<'_, '_>,
+    borrow::BaseNbt<'_>
+);
+
+/// The borrowed types are written as SNBT for debugging, since their fields
+/// are just pointers into the tape.
+macro_rules! impl_debug {
+    ($($ty:ty),*) => {
+        $(
+            impl fmt::Debug for $ty {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Display::fmt(self, f)
+                }
+            }
+        )*
+    };
+}
+impl_debug!(
+    borrow::NbtTag<'_, '_>,
+    borrow::NbtList<'_, '_>,
+    borrow::NbtCompound<'_, '_>
+);
+
+impl ToSnbt for NbtTag {
+    fn write_snbt(&self, out: &mut String) {
+        match self {
+            NbtTag::Byte(byte) => write_byte(out, *byte),
+            NbtTag::Short(short) => write_short(out, *short),
+            NbtTag::Int(int) => write_int(out, *int),
+            NbtTag::Long(long) => write_long(out, *long),
+            NbtTag::Float(float) => write_float(out, *float),
+            NbtTag::Double(double) => write_double(out, *double),
+            NbtTag::ByteArray(bytes) => {
+                write_array(out, "[B;", bytes, |out, byte| write_byte(out, *byte as i8))
+            }
+            NbtTag::String(string) => write_string(out, string),
+            NbtTag::List(list) => list.write_snbt(out),
+            NbtTag::Compound(compound) => compound.write_snbt(out),
+            NbtTag::IntArray(ints) => {
+                write_array(out, "[I;", ints, |out, int| write_int(out, *int))
+            }
+            NbtTag::LongArray(longs) => {
+                write_array(out, "[L;", longs, |out, long| write_long(out, *long))
+            }
+        }
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+impl ToSnbt for NbtList {
+    fn write_snbt(&self, out: &mut String) {
+        match self {
+            NbtList::Empty => out.push_str("[]"),
+            NbtList::Byte(bytes) => write_array(out, "[", bytes, |out, b| write_byte(out, *b)),
+            NbtList::Short(shorts) => write_array(out, "[", shorts, |out, s| write_short(out, *s)),
+            NbtList::Int(ints) => write_array(out, "[", ints, |out, i| write_int(out, *i)),
+            NbtList::Long(longs) => write_array(out, "[", longs, |out, l| write_long(out, *l)),
+            NbtList::Float(floats) => write_array(out, "[", floats, |out, f| write_float(out, *f)),
+            NbtList::Double(doubles) => {
+                write_array(out, "[", doubles, |out, d| write_double(out, *d))
+            }
+            NbtList::ByteArray(arrays) => write_array(out, "[", arrays, |out, array| {
+                write_array(out, "[B;", array, |out, byte| write_byte(out, *byte as i8))
+            }),
+            NbtList::String(strings) => {
+                write_array(out, "[", strings, |out, s| write_string(out, s))
+            }
+            NbtList::List(lists) => write_array(out, "[", lists, |out, list| list.write_snbt(out)),
+            NbtList::Compound(compounds) => write_array(out, "[", compounds, |out, compound| {
+                compound.write_snbt(out)
+            }),
+            NbtList::IntArray(arrays) => write_array(out, "[", arrays, |out, array| {
+                write_array(out, "[I;", array, |out, int| write_int(out, *int))
+            }),
+            NbtList::LongArray(arrays) => write_array(out, "[", arrays, |out, array| {
+                write_array(out, "[L;", array, |out, long| write_long(out, *long))
+            }),
+        }
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+impl ToSnbt for NbtCompound {
+    fn write_snbt(&self, out: &mut String) {
+        write_compound(out, self.iter(), |out, tag| tag.write_snbt(out));
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+
+impl ToSnbt for borrow::NbtTag<'_, '_> {
+    fn write_snbt(&self, out: &mut String) {
+        match self.id() {
+            BYTE_ID => write_byte(out, self.byte().unwrap()),
+            SHORT_ID => write_short(out, self.short().unwrap()),
+            INT_ID => write_int(out, self.int().unwrap()),
+            LONG_ID => write_long(out, self.long().unwrap()),
+            FLOAT_ID => write_float(out, self.float().unwrap()),
+            DOUBLE_ID => write_double(out, self.double().unwrap()),
+            BYTE_ARRAY_ID => write_array(out, "[B;", self.byte_array().unwrap(), |out, byte| {
+                write_byte(out, *byte as i8)
+            }),
+            STRING_ID => write_string(out, self.string().unwrap()),
+            LIST_ID => self.list().unwrap().write_snbt(out),
+            COMPOUND_ID => self.compound().unwrap().write_snbt(out),
+            INT_ARRAY_ID => write_array(out, "[I;", &self.int_array().unwrap(), |out, int| {
+                write_int(out, *int)
+            }),
+            LONG_ARRAY_ID => write_array(out, "[L;", &self.long_array().unwrap(), |out, long| {
+                write_long(out, *long)
+            }),
+            _ => unreachable!(),
+        }
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+impl ToSnbt for borrow::NbtList<'_, '_> {
+    fn write_snbt(&self, out: &mut String) {
+        out.push('[');
+        for (i, tag) in self.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            tag.write_snbt(out);
+        }
+        out.push(']');
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+impl ToSnbt for borrow::NbtCompound<'_, '_> {
+    fn write_snbt(&self, out: &mut String) {
+        write_compound(out, self.iter(), |out, tag| tag.write_snbt(out));
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+/// The name of the root is dropped, since SNBT doesn't have a way to write it.
+impl ToSnbt for borrow::BaseNbt<'_> {
+    fn write_snbt(&self, out: &mut String) {
+        self.as_compound().write_snbt(out);
+    }
+    fn write_pretty_snbt(&self, out: &mut String, opts: &PrettyOptions) {
+        self.write_pretty(&mut Printer::new(out, opts));
+    }
+}
+
+fn write_byte(out: &mut String, value: i8) {
+    write!(out, "{value}b").unwrap();
+}
+fn write_short(out: &mut String, value: i16) {
+    write!(out, "{value}s").unwrap();
+}
+fn write_int(out: &mut String, value: i32) {
+    write!(out, "{value}").unwrap();
+}
+fn write_long(out: &mut String, value: i64) {
+    write!(out, "{value}L").unwrap();
+}
+// rust writes NaN as `NaN` like java does, but infinity is `inf` instead of
+// `Infinity`. `parse_number` accepts all of them as long as they have a suffix.
+fn write_float(out: &mut String, value: f32) {
+    if value.is_infinite() {
+        write!(out, "{}f", infinity(value.is_sign_positive())).unwrap();
+    } else {
+        write!(out, "{value}f").unwrap();
+    }
+}
+fn write_double(out: &mut String, value: f64) {
+    if value.is_infinite() {
+        write!(out, "{}d", infinity(value.is_sign_positive())).unwrap();
+    } else {
+        write!(out, "{value}d").unwrap();
+    }
+}
+fn infinity(positive: bool) -> &'static str {
+    if positive {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+fn write_array<T>(
+    out: &mut String,
+    prefix: &str,
+    values: &[T],
+    mut write_value: impl FnMut(&mut String, &T),
+) {
+    out.push_str(prefix);
+    for (i, value) in values.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        write_value(out, value);
+    }
+    out.push(']');
+}
+
+fn write_compound<'a, T>(
+    out: &mut String,
+    entries: impl Iterator<Item = (&'a Mutf8Str, T)>,
+    mut write_value: impl FnMut(&mut String, T),
+) {
+    out.push('{');
+    for (i, (key, value)) in entries.enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        write_key(out, key);
+        out.push(':');
+        write_value(out, value);
+    }
+    out.push('}');
+}
+
+fn write_key(out: &mut String, key: &Mutf8Str) {
+    let key = key.to_string_lossy();
+    if !key.is_empty() && key.bytes().all(is_unquoted_char) {
+        out.push_str(&key);
+    } else {
+        write_quoted(out, &key);
+    }
+}
+
+fn write_string(out: &mut String, string: &Mutf8Str) {
+    write_quoted(out, &string.to_string_lossy());
+}
+
+/// Write the string in quotes, using single quotes if that means we don't
+/// have to escape anything (which is what Minecraft does).
+fn write_quoted(out: &mut String, string: &str) {
+    let quote = if string.contains('"') && !string.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+    out.push(quote);
+    for c in string.chars() {
+        if c == quote || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push(quote);
+}
+
+fn is_unquoted_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.' | b'+')
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> SnbtError {
+        SnbtError {
+            message: message.into(),
+            position: self.pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace and then consume the given byte, or return an error if
+    /// it's something else.
+    fn expect(&mut self, expected: u8) -> Result<(), SnbtError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("Expected '{}'", expected as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect_end(&mut self) -> Result<(), SnbtError> {
+        self.skip_whitespace();
+        if self.pos != self.input.len() {
+            return Err(self.error("Trailing data"));
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<NbtTag, SnbtError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Too deeply nested"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_compound(depth + 1).map(NbtTag::Compound),
+            Some(b'[') => self.parse_list_or_array(depth + 1),
+            Some(b'"' | b'\'') => Ok(NbtTag::String(self.parse_quoted()?.into())),
+            _ => {
+                let start = self.pos;
+                let token = self.parse_unquoted()?;
+                parse_unquoted_value(token).ok_or_else(|| SnbtError {
+                    message: format!("Invalid value {token:?}"),
+                    position: start,
+                })
+            }
+        }
+    }
+
+    fn parse_compound(&mut self, depth: usize) -> Result<NbtCompound, SnbtError> {
+        self.expect(b'{')?;
+        let mut compound = NbtCompound::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(compound);
+        }
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some(b'"' | b'\'') => self.parse_quoted()?,
+                _ => self.parse_unquoted()?.to_owned(),
+            };
+            self.expect(b':')?;
+            let value = self.parse_value(depth)?;
+            compound.insert(key, value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(compound);
+                }
+                _ => return Err(self.error("Expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_list_or_array(&mut self, depth: usize) -> Result<NbtTag, SnbtError> {
+        self.expect(b'[')?;
+        let bytes = self.input.as_bytes();
+        let array_type = match (bytes.get(self.pos), bytes.get(self.pos + 1)) {
+            (Some(&array_type @ (b'B' | b'I' | b'L')), Some(b';')) => {
+                self.pos += 2;
+                Some(array_type)
+            }
+            _ => None,
+        };
+
+        let start = self.pos;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+        } else {
+            loop {
+                values.push(self.parse_value(depth)?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.pos += 1,
+                    Some(b']') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("Expected ',' or ']'")),
+                }
+            }
+        }
+
+        let error = |message: &str| SnbtError {
+            message: message.to_owned(),
+            position: start,
+        };
+        Ok(match array_type {
+            Some(b'B') => NbtTag::ByteArray(
+                values
+                    .into_iter()
+                    .map(|tag| tag.into_byte().map(|b| b as u8))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error("Byte arrays can only contain bytes"))?,
+            ),
+            Some(b'I') => NbtTag::IntArray(
+                values
+                    .into_iter()
+                    .map(NbtTag::into_int)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error("Int arrays can only contain ints"))?,
+            ),
+            Some(_) => NbtTag::LongArray(
+                values
+                    .into_iter()
+                    .map(NbtTag::into_long)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error("Long arrays can only contain longs"))?,
+            ),
+            None => NbtTag::List(
+                NbtList::from_tags(values)
+                    .ok_or_else(|| error("Lists can only contain one type of tag"))?,
+            ),
+        })
+    }
+
+    /// Parse a string in single or double quotes.
+    fn parse_quoted(&mut self) -> Result<String, SnbtError> {
+        let quote = self.input.as_bytes()[self.pos];
+        self.pos += 1;
+        let mut string = String::new();
+        let mut chars = self.input[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == quote as char {
+                self.pos += i + 1;
+                return Ok(string);
+            }
+            if c != '\\' {
+                string.push(c);
+                continue;
+            }
+            let escaped = match chars.next() {
+                Some((_, c @ ('\\' | '"' | '\''))) => c,
+                Some((_, 'n')) => '\n',
+                Some((_, 't')) => '\t',
+                Some((_, 'r')) => '\r',
+                Some((_, 'u')) => {
+                    let hex = chars.as_str().get(..4).unwrap_or_default();
+                    let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+                    let Some(c) = c.filter(|_| hex.len() == 4) else {
+                        self.pos += i;
+                        return Err(self.error("Invalid unicode escape"));
+                    };
+                    for _ in 0..4 {
+                        chars.next();
+                    }
+                    c
+                }
+                _ => {
+                    self.pos += i;
+                    return Err(self.error("Invalid escape"));
+                }
+            };
+            string.push(escaped);
+        }
+        self.pos = self.input.len();
+        Err(self.error("Unterminated string"))
+    }
+
+    fn parse_unquoted(&mut self) -> Result<&str, SnbtError> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("Expected a value"));
+        }
+        Ok(&self.input[start..self.pos])
+    }
+}
+
+/// Figure out what type of tag an unquoted value is. This only returns `None`
+/// if the token is empty.
+fn parse_unquoted_value(token: &str) -> Option<NbtTag> {
+    if token.is_empty() {
+        return None;
+    }
+    match token {
+        "true" => return Some(NbtTag::Byte(1)),
+        "false" => return Some(NbtTag::Byte(0)),
+        _ => {}
+    }
+    Some(parse_number(token).unwrap_or_else(|| NbtTag::String(token.into())))
+}
+
+fn parse_number(token: &str) -> Option<NbtTag> {
+    let (body, suffix) = token.split_at(token.len() - 1);
+    let suffix = suffix.as_bytes()[0].to_ascii_lowercase();
+    let is_integer = |s: &str| {
+        let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    // rust's float parsing also accepts things like "infinity" and "nan",
+    // which are strings in snbt. the only ones that are allowed are exactly
+    // what java and rust write for NaN and infinity, and only with a suffix.
+    let is_float = |s: &str| {
+        s.bytes().any(|b| b.is_ascii_digit())
+            && s.bytes()
+                .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+'))
+    };
+    let is_suffixed_float =
+        |s: &str| is_float(s) || matches!(s, "NaN" | "Infinity" | "-Infinity" | "inf" | "-inf");
+
+    match suffix {
+        b'b' if is_integer(body) => body.parse().ok().map(NbtTag::Byte),
+        b's' if is_integer(body) => body.parse().ok().map(NbtTag::Short),
+        b'l' if is_integer(body) => body.parse().ok().map(NbtTag::Long),
+        b'f' if is_suffixed_float(body) => body.parse().ok().map(NbtTag::Float),
+        b'd' if is_suffixed_float(body) => body.parse().ok().map(NbtTag::Double),
+        _ if is_integer(token) => token.parse().ok().map(NbtTag::Int),
+        _ if is_float(token) && token.contains('.') => token.parse().ok().map(NbtTag::Double),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::owned::BaseNbt;
+
+    #[test]
+    fn parse_every_type() {
+        let compound = parse_compound(
+            r#"{
+                byte: 1b, short: -2s, int: 3, long: 4L, float: 0.5f, double: 1.5,
+                bool: true, word: stone_bricks, "quoted key": 'it\'s "quoted"',
+                bytes: [B; 1b, -1b], ints: [I;], longs: [L; 5l],
+                list: [1s, 2s], empty: [], nested: [[1], [a, b]], compounds: [{}, {a: 1d}],
+                big: 2147483648, unicode: "é"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(compound.byte("byte"), Some(1));
+        assert_eq!(compound.short("short"), Some(-2));
+        assert_eq!(compound.int("int"), Some(3));
+        assert_eq!(compound.long("long"), Some(4));
+        assert_eq!(compound.float("float"), Some(0.5));
+        assert_eq!(compound.double("double"), Some(1.5));
+        assert_eq!(compound.byte("bool"), Some(1));
+        assert_eq!(compound.string("word").unwrap().to_str(), "stone_bricks");
+        assert_eq!(
+            compound.string("quoted key").unwrap().to_str(),
+            "it's \"quoted\""
+        );
+        assert_eq!(compound.byte_array("bytes"), Some(&[1, 255][..]));
+        assert_eq!(compound.int_array("ints"), Some(&[][..]));
+        assert_eq!(compound.long_array("longs"), Some(&[5][..]));
+        assert_eq!(compound.list("list"), Some(&NbtList::Short(vec![1, 2])));
+        assert_eq!(compound.list("empty"), Some(&NbtList::Empty));
+        assert_eq!(
+            compound.list("nested"),
+            Some(&NbtList::List(vec![
+                NbtList::Int(vec![1]),
+                NbtList::String(vec!["a".into(), "b".into()])
+            ]))
+        );
+        assert_eq!(compound.compound_list("compounds").unwrap().len(), 2);
+        // too big to be an int, so it's a string
+        assert_eq!(compound.string("big").unwrap().to_str(), "2147483648");
+        assert_eq!(compound.string("unicode").unwrap().to_str(), "é");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input| parse(input).unwrap_err();
+        assert_eq!(error("{a:1,}").position, 5);
+        assert_eq!(error("[1, 2b]").position, 1);
+        assert_eq!(error("[I; 1, 2L]").position, 3);
+        assert_eq!(error(r#""abc"#).message, "Unterminated string");
+        assert_eq!(error("{a:1} b").message, "Trailing data");
+        assert_eq!(error("{a 1}").message, "Expected ':'");
+        assert_eq!(error(&"[".repeat(1000)).message, "Too deeply nested");
+        assert!(parse_compound("[]").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let snbt = r#"{a:1b,b:[I;1,-2],"c d":[{e:"\"'"},{}],f:[[1.5f],[]],g:1.0E-7d,h:[L;]}"#;
+        let compound = parse_compound(snbt).unwrap();
+        let written = compound.to_snbt();
+        assert_eq!(
+            written,
+            r#"{a:1b,b:[I;1,-2],"c d":[{e:"\"'"},{}],f:[[1.5f],[]],g:0.0000001d,h:[L;]}"#
+        );
+        assert_eq!(parse_compound(&written).unwrap(), compound);
+
+        let data = BaseNbt::new("root", compound).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(nbt.to_snbt(), written);
+    }
+
+    #[test]
+    fn non_finite_round_trip() {
+        let mut compound = NbtCompound::new();
+        compound.insert("a", f32::NAN);
+        compound.insert("b", f32::INFINITY);
+        compound.insert("c", f32::NEG_INFINITY);
+        compound.insert("d", f64::NAN);
+        compound.insert("e", f64::INFINITY);
+        compound.insert("f", NbtList::Double(vec![f64::NEG_INFINITY]));
+        let written = compound.to_snbt();
+        assert_eq!(
+            written,
+            "{a:NaNf,b:Infinityf,c:-Infinityf,d:NaNd,e:Infinityd,f:[-Infinityd]}"
+        );
+
+        let parsed = parse_compound(&written).unwrap();
+        assert!(parsed.float("a").unwrap().is_nan());
+        assert_eq!(parsed.float("b"), Some(f32::INFINITY));
+        assert_eq!(parsed.float("c"), Some(f32::NEG_INFINITY));
+        assert!(parsed.double("d").unwrap().is_nan());
+        assert_eq!(parsed.double("e"), Some(f64::INFINITY));
+        assert_eq!(
+            parsed.list("f"),
+            Some(&NbtList::Double(vec![f64::NEG_INFINITY]))
+        );
+        assert_eq!(parsed.to_snbt(), written);
+
+        // rust's spelling is accepted too
+        let parsed = parse_compound("{a:inff,b:-infd}").unwrap();
+        assert_eq!(parsed.float("a"), Some(f32::INFINITY));
+        assert_eq!(parsed.double("b"), Some(f64::NEG_INFINITY));
+
+        // without a suffix they're still strings
+        let parsed = parse_compound("{a:NaN,b:inf,c:infinityf,d:Infinity}").unwrap();
+        assert_eq!(parsed.string("a").unwrap().to_str(), "NaN");
+        assert_eq!(parsed.string("b").unwrap().to_str(), "inf");
+        assert_eq!(parsed.string("c").unwrap().to_str(), "infinityf");
+        assert_eq!(parsed.string("d").unwrap().to_str(), "Infinity");
+    }
+}
@@ -0,0 +1,309 @@
+//! Writing SNBT over multiple lines with indentation.
+
+use std::fmt::Write;
+
+use super::{
+    write_byte, write_double, write_float, write_int, write_key, write_long, write_short,
+    write_string, ToSnbt,
+};
+use crate::{
+    borrow,
+    common::{
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+        LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+    },
+    owned::{NbtCompound, NbtList, NbtTag},
+};
+
+/// Options for [`ToSnbt::to_pretty_snbt`].
+///
+/// Compounds and lists of compounds, lists, strings, and arrays are written
+/// with one element per line, and arrays and lists of numbers are written on
+/// one line. The output can be parsed again as long as nothing was truncated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// How many spaces to indent each level by. Defaults to 4.
+    pub indent: usize,
+    /// How many levels of compounds and lists to write before replacing
+    /// their contents with `...`. Defaults to no limit.
+    pub max_depth: Option<usize>,
+    /// How many elements of each compound, list, and array to write before
+    /// writing how many more there are. Defaults to no limit.
+    pub max_items: Option<usize>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            max_depth: None,
+            max_items: None,
+        }
+    }
+}
+
+pub(super) struct Printer<'a> {
+    out: &'a mut String,
+    opts: &'a PrettyOptions,
+    depth: usize,
+}
+
+impl<'a> Printer<'a> {
+    pub fn new(out: &'a mut String, opts: &'a PrettyOptions) -> Self {
+        Self {
+            out,
+            opts,
+            depth: 0,
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.depth * self.opts.indent {
+            self.out.push(' ');
+        }
+    }
+
+    /// Write a compound or list with each item on its own line.
+    fn block<T>(
+        &mut self,
+        open: char,
+        close: char,
+        items: impl Iterator<Item = T>,
+        mut write_item: impl FnMut(&mut Self, T),
+    ) {
+        let mut items = items.peekable();
+        self.out.push(open);
+        if items.peek().is_none() {
+            self.out.push(close);
+            return;
+        }
+        if self.opts.max_depth.is_some_and(|max| self.depth >= max) {
+            self.out.push_str("...");
+            self.out.push(close);
+            return;
+        }
+
+        self.depth += 1;
+        let max_items = self.opts.max_items.unwrap_or(usize::MAX);
+        let mut written = 0;
+        while let Some(item) = items.next() {
+            if written == max_items {
+                let remaining = 1 + items.count();
+                self.newline();
+                write!(self.out, "... {remaining} more").unwrap();
+                break;
+            }
+            self.newline();
+            write_item(self, item);
+            written += 1;
+            if items.peek().is_some() {
+                self.out.push(',');
+            }
+        }
+        self.depth -= 1;
+        self.newline();
+        self.out.push(close);
+    }
+
+    /// Write an array or a list of numbers on one line.
+    fn inline<T>(
+        &mut self,
+        prefix: &str,
+        items: impl IntoIterator<Item = T>,
+        mut write_item: impl FnMut(&mut String, T),
+    ) {
+        let max_items = self.opts.max_items.unwrap_or(usize::MAX);
+        let mut items = items.into_iter().peekable();
+        if items.peek().is_none() {
+            // no space after the `;` in empty arrays
+            self.out.push_str(prefix.trim_end());
+            self.out.push(']');
+            return;
+        }
+        self.out.push_str(prefix);
+        let mut written = 0;
+        while let Some(item) = items.next() {
+            if written != 0 {
+                self.out.push_str(", ");
+            }
+            if written == max_items {
+                let remaining = 1 + items.count();
+                write!(self.out, "... {remaining} more").unwrap();
+                break;
+            }
+            write_item(self.out, item);
+            written += 1;
+        }
+        self.out.push(']');
+    }
+
+    fn byte_array(&mut self, bytes: &[u8]) {
+        self.inline("[B; ", bytes, |out, byte| write_byte(out, *byte as i8));
+    }
+    fn int_array(&mut self, ints: &[i32]) {
+        self.inline("[I; ", ints, |out, int| write_int(out, *int));
+    }
+    fn long_array(&mut self, longs: &[i64]) {
+        self.inline("[L; ", longs, |out, long| write_long(out, *long));
+    }
+}
+
+/// A type that can be written with a [`Printer`]. This is implemented for the
+/// same types as [`ToSnbt`].
+pub(super) trait Pretty {
+    fn write_pretty(&self, printer: &mut Printer);
+}
+
+impl Pretty for NbtTag {
+    fn write_pretty(&self, p: &mut Printer) {
+        match self {
+            NbtTag::ByteArray(bytes) => p.byte_array(bytes),
+            NbtTag::List(list) => list.write_pretty(p),
+            NbtTag::Compound(compound) => compound.write_pretty(p),
+            NbtTag::IntArray(ints) => p.int_array(ints),
+            NbtTag::LongArray(longs) => p.long_array(longs),
+            _ => self.write_snbt(p.out),
+        }
+    }
+}
+impl Pretty for NbtList {
+    fn write_pretty(&self, p: &mut Printer) {
+        match self {
+            NbtList::Empty => p.out.push_str("[]"),
+            NbtList::Byte(bytes) => p.inline("[", bytes, |out, b| write_byte(out, *b)),
+            NbtList::Short(shorts) => p.inline("[", shorts, |out, s| write_short(out, *s)),
+            NbtList::Int(ints) => p.inline("[", ints, |out, i| write_int(out, *i)),
+            NbtList::Long(longs) => p.inline("[", longs, |out, l| write_long(out, *l)),
+            NbtList::Float(floats) => p.inline("[", floats, |out, f| write_float(out, *f)),
+            NbtList::Double(doubles) => p.inline("[", doubles, |out, d| write_double(out, *d)),
+            NbtList::ByteArray(arrays) => p.block('[', ']', arrays.iter(), |p, a| p.byte_array(a)),
+            NbtList::String(strings) => {
+                p.block('[', ']', strings.iter(), |p, s| write_string(p.out, s))
+            }
+            NbtList::List(lists) => p.block('[', ']', lists.iter(), |p, l| l.write_pretty(p)),
+            NbtList::Compound(compounds) => {
+                p.block('[', ']', compounds.iter(), |p, c| c.write_pretty(p))
+            }
+            NbtList::IntArray(arrays) => p.block('[', ']', arrays.iter(), |p, a| p.int_array(a)),
+            NbtList::LongArray(arrays) => p.block('[', ']', arrays.iter(), |p, a| p.long_array(a)),
+        }
+    }
+}
+impl Pretty for NbtCompound {
+    fn write_pretty(&self, p: &mut Printer) {
+        p.block('{', '}', self.iter(), |p, (key, tag)| {
+            write_key(p.out, key);
+            p.out.push_str(": ");
+            tag.write_pretty(p);
+        });
+    }
+}
+
+impl Pretty for borrow::NbtTag<'_, '_> {
+    fn write_pretty(&self, p: &mut Printer) {
+        match self.id() {
+            BYTE_ARRAY_ID => p.byte_array(self.byte_array().unwrap()),
+            LIST_ID => self.list().unwrap().write_pretty(p),
+            COMPOUND_ID => self.compound().unwrap().write_pretty(p),
+            INT_ARRAY_ID => p.int_array(&self.int_array().unwrap()),
+            LONG_ARRAY_ID => p.long_array(&self.long_array().unwrap()),
+            _ => self.write_snbt(p.out),
+        }
+    }
+}
+impl Pretty for borrow::NbtList<'_, '_> {
+    fn write_pretty(&self, p: &mut Printer) {
+        match self.id() {
+            BYTE_ID | SHORT_ID | INT_ID | LONG_ID | FLOAT_ID | DOUBLE_ID => {
+                p.inline("[", self.iter(), |out, tag| tag.write_snbt(out))
+            }
+            STRING_ID => p.block('[', ']', self.iter(), |p, tag| tag.write_snbt(p.out)),
+            _ => p.block('[', ']', self.iter(), |p, tag| tag.write_pretty(p)),
+        }
+    }
+}
+impl Pretty for borrow::NbtCompound<'_, '_> {
+    fn write_pretty(&self, p: &mut Printer) {
+        p.block('{', '}', self.iter(), |p, (key, tag)| {
+            write_key(p.out, key);
+            p.out.push_str(": ");
+            tag.write_pretty(p);
+        });
+    }
+}
+impl Pretty for borrow::BaseNbt<'_> {
+    fn write_pretty(&self, p: &mut Printer) {
+        self.as_compound().write_pretty(p);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{owned::BaseNbt, snbt::parse_compound};
+
+    const PRETTY: &str = r#"{
+    a: 1b,
+    b: [I; 1, -2],
+    "c d": [
+        {
+            e: "\"'"
+        },
+        {}
+    ],
+    f: [
+        [1.5f],
+        []
+    ],
+    g: [L;],
+    h: [
+        "x",
+        "y"
+    ]
+}"#;
+
+    #[test]
+    fn pretty() {
+        let compound = parse_compound(PRETTY).unwrap();
+        let pretty = compound.to_pretty_snbt(&PrettyOptions::default());
+        assert_eq!(pretty, PRETTY);
+        assert_eq!(format!("{compound:#}"), PRETTY);
+
+        let data = BaseNbt::new("", compound).to_bytes();
+        let nbt = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(nbt.to_pretty_snbt(&PrettyOptions::default()), PRETTY);
+        assert_eq!(format!("{:#}", nbt.as_compound()), PRETTY);
+        assert_eq!(
+            format!("{:?}", nbt.get("b").unwrap()),
+            nbt.get("b").unwrap().to_snbt()
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let compound = parse_compound(PRETTY).unwrap();
+        let opts = PrettyOptions {
+            indent: 2,
+            max_depth: Some(1),
+            max_items: Some(2),
+        };
+        assert_eq!(
+            compound.to_pretty_snbt(&opts),
+            r#"{
+  a: 1b,
+  b: [I; 1, -2],
+  ... 4 more
+}"#
+        );
+        let mut ints = NbtCompound::new();
+        ints.insert("a", NbtList::Int(vec![1, 2, 3]));
+        ints.insert("b", NbtList::Compound(vec![NbtCompound::new()]));
+        assert_eq!(
+            ints.to_pretty_snbt(&opts),
+            "{\n  a: [1, 2, ... 1 more],\n  b: [...]\n}"
+        );
+    }
+}
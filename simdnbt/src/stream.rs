@@ -0,0 +1,316 @@
+//! A pull parser that reads NBT as a series of events, without building a
+//! tape or a tree.
+//!
+//! This is useful when you only need a few fields out of a large structure,
+//! since you can skip over anything you don't care about and stop as soon as
+//! you've found what you're looking for.
+//!
+//! ```
+//! # let mut root = simdnbt::owned::NbtCompound::new();
+//! # root.insert("DataVersion", 3953);
+//! # let data = simdnbt::owned::BaseNbt::new("", root).to_bytes();
+//! use simdnbt::stream::{Event, Parser, Value};
+//!
+//! let mut parser = Parser::new(&data);
+//! let mut data_version = None;
+//! while let Some(event) = parser.next_event().unwrap() {
+//!     match event {
+//!         Event::Tag(Some(name), Value::Int(value)) if name.to_str() == "DataVersion" => {
+//!             data_version = Some(value);
+//!             break;
+//!         }
+//!         // don't look inside of nested compounds and lists
+//!         Event::CompoundStart(_) | Event::ListStart(..) if parser.depth() > 1 => {
+//!             parser.skip_container().unwrap();
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!(data_version, Some(3953));
+//! ```
+
+use crate::{
+    common::{
+        read_int_array, read_length, read_long_array, read_string, read_u8_array, skip_tag,
+        BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, END_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID,
+        LIST_ID, LONG_ARRAY_ID, LONG_ID, MAX_DEPTH, SHORT_ID, STRING_ID,
+    },
+    error::NonRootError,
+    raw_list::RawList,
+    reader::Reader,
+    Error, Mutf8Str,
+};
+
+/// Something that the [`Parser`] found.
+///
+/// Tags directly inside of a compound have a name, and tags that are elements
+/// of a list don't.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    /// The start of a compound. This is followed by the events for each of
+    /// its tags and then an [`Event::End`].
+    CompoundStart(Option<&'a Mutf8Str>),
+    /// The start of a list with the given element tag id and length. This is
+    /// followed by the events for each element and then an [`Event::End`].
+    ListStart(Option<&'a Mutf8Str>, u8, usize),
+    /// A tag that isn't a compound or a list.
+    Tag(Option<&'a Mutf8Str>, Value<'a>),
+    /// The end of the most recent compound or list.
+    End,
+}
+
+/// The value of a tag that doesn't contain other tags.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [u8]),
+    String(&'a Mutf8Str),
+    IntArray(RawList<'a, i32>),
+    LongArray(RawList<'a, i64>),
+}
+
+enum Container {
+    Compound,
+    List { element_id: u8, remaining: usize },
+}
+
+/// A pull parser for NBT with a root compound. See the [module
+/// documentation](self) for an example.
+pub struct Parser<'a> {
+    data: &'a [u8],
+    reader: Reader<'a>,
+    stack: Vec<Container>,
+    started: bool,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            reader: Reader::new(data),
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// How many bytes of the data have been read.
+    pub fn position(&self) -> usize {
+        self.reader.cur as usize - self.data.as_ptr() as usize
+    }
+
+    /// How many compounds and lists we're currently inside of, including the
+    /// root compound.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Read the next event, or return `None` if the root compound is finished
+    /// (or if the data is empty NBT).
+    ///
+    /// After this returns an error the parser shouldn't be used anymore.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, Error> {
+        if !self.started {
+            self.started = true;
+            let root_type = self.reader.read_u8()?;
+            if root_type == END_ID {
+                return Ok(None);
+            }
+            if root_type != COMPOUND_ID {
+                return Err(Error::InvalidRootType(root_type));
+            }
+            let name = read_string(&mut self.reader)?;
+            self.stack.push(Container::Compound);
+            return Ok(Some(Event::CompoundStart(Some(name))));
+        }
+
+        let event = match self.stack.last_mut() {
+            None => return Ok(None),
+            Some(Container::Compound) => {
+                let tag_type = self.reader.read_u8()?;
+                if tag_type == END_ID {
+                    self.stack.pop();
+                    Event::End
+                } else {
+                    let name = read_string(&mut self.reader)?;
+                    self.read_tag(tag_type, Some(name))?
+                }
+            }
+            Some(Container::List {
+                element_id,
+                remaining,
+            }) => {
+                if *remaining == 0 {
+                    self.stack.pop();
+                    Event::End
+                } else {
+                    *remaining -= 1;
+                    let element_id = *element_id;
+                    self.read_tag(element_id, None)?
+                }
+            }
+        };
+        Ok(Some(event))
+    }
+
+    /// Skip the rest of the compound or list that we're currently in,
+    /// including its [`Event::End`].
+    ///
+    /// This is usually called right after an [`Event::CompoundStart`] or
+    /// [`Event::ListStart`] to skip the whole thing.
+    pub fn skip_container(&mut self) -> Result<(), Error> {
+        let depth = self.depth();
+        match self.stack.pop() {
+            None => {}
+            Some(Container::Compound) => skip_tag(&mut self.reader, COMPOUND_ID, depth)?,
+            Some(Container::List {
+                element_id,
+                remaining,
+            }) => {
+                for _ in 0..remaining {
+                    skip_tag(&mut self.reader, element_id, depth)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_tag(
+        &mut self,
+        tag_type: u8,
+        name: Option<&'a Mutf8Str>,
+    ) -> Result<Event<'a>, NonRootError> {
+        let reader = &mut self.reader;
+        let value = match tag_type {
+            BYTE_ID => Value::Byte(reader.read_i8()?),
+            SHORT_ID => Value::Short(reader.read_i16()?),
+            INT_ID => Value::Int(reader.read_i32()?),
+            LONG_ID => Value::Long(reader.read_i64()?),
+            FLOAT_ID => Value::Float(reader.read_f32()?),
+            DOUBLE_ID => Value::Double(reader.read_f64()?),
+            BYTE_ARRAY_ID => Value::ByteArray(read_u8_array(reader)?),
+            STRING_ID => Value::String(read_string(reader)?),
+            INT_ARRAY_ID => Value::IntArray(read_int_array(reader)?),
+            LONG_ARRAY_ID => Value::LongArray(read_long_array(reader)?),
+            COMPOUND_ID => {
+                self.push(Container::Compound)?;
+                return Ok(Event::CompoundStart(name));
+            }
+            LIST_ID => {
+                let element_id = reader.read_u8()?;
+                let length = read_length(reader)? as usize;
+                // the length is unused for lists of end tags
+                let remaining = if element_id == END_ID { 0 } else { length };
+                self.push(Container::List {
+                    element_id,
+                    remaining,
+                })?;
+                return Ok(Event::ListStart(name, element_id, remaining));
+            }
+            _ => return Err(NonRootError::unknown_tag_id(tag_type)),
+        };
+        Ok(Event::Tag(name, value))
+    }
+
+    fn push(&mut self, container: Container) -> Result<(), NonRootError> {
+        if self.stack.len() > MAX_DEPTH {
+            return Err(NonRootError::max_depth_exceeded());
+        }
+        self.stack.push(container);
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<Event<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{owned, test_fixtures::bigtest};
+
+    #[test]
+    fn events() {
+        let mut item = owned::NbtCompound::new();
+        item.insert("id", "minecraft:stone");
+        let mut root = owned::NbtCompound::new();
+        root.insert("count", 3i8);
+        root.insert("items", owned::NbtList::Compound(vec![item]));
+        root.insert("heights", owned::NbtTag::LongArray(vec![1, 2]));
+        root.insert("empty", owned::NbtList::Empty);
+        let data = owned::BaseNbt::new("root", root).to_bytes();
+
+        let events = Parser::new(&data)
+            .map(|event| match event.unwrap() {
+                Event::CompoundStart(n) => format!("{{ {:?}", n.map(|n| n.to_str())),
+                Event::ListStart(n, id, len) => {
+                    format!("[ {:?} {id} {len}", n.map(|n| n.to_str()))
+                }
+                Event::Tag(n, Value::LongArray(longs)) => {
+                    format!("{:?} {:?}", n.map(|n| n.to_str()), longs.to_vec())
+                }
+                Event::Tag(n, value) => format!("{:?} {value:?}", n.map(|n| n.to_str())),
+                Event::End => "end".to_owned(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                r#"{ Some("root")"#,
+                r#"Some("count") Byte(3)"#,
+                r#"[ Some("items") 10 1"#,
+                "{ None",
+                r#"Some("id") String(m"minecraft:stone")"#,
+                "end",
+                "end",
+                r#"Some("heights") [1, 2]"#,
+                r#"[ Some("empty") 0 0"#,
+                "end",
+                "end",
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_and_stop_early() {
+        let src = bigtest();
+        let nbt = crate::borrow::read(&mut Cursor::new(&src))
+            .unwrap()
+            .unwrap();
+
+        // count the top-level tags without looking inside of any of them
+        let mut parser = Parser::new(&src);
+        let mut count = 0;
+        parser.next_event().unwrap();
+        while let Some(event) = parser.next_event().unwrap() {
+            match event {
+                Event::CompoundStart(_) | Event::ListStart(..) => parser.skip_container().unwrap(),
+                Event::End => break,
+                Event::Tag(..) => {}
+            }
+            count += 1;
+        }
+        assert_eq!(count, nbt.as_compound().len());
+        assert_eq!(parser.position(), src.len());
+        assert_eq!(parser.next_event(), Ok(None));
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let data = [COMPOUND_ID, 0, 0, INT_ID, 0, 1, b'a', 0, 0];
+        let mut parser = Parser::new(&data);
+        assert!(parser.next_event().is_ok());
+        assert_eq!(parser.next_event(), Err(Error::UnexpectedEof));
+    }
+}
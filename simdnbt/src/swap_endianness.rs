@@ -1,4 +1,6 @@
-use std::{mem, simd::prelude::*};
+use std::mem;
+#[cfg(feature = "nightly")]
+use std::simd::prelude::*;
 
 // TODO: relying on auto-vectorization for swapping bits is significantly faster
 // than this code, we should figure out a way to make that work dynamically and
@@ -19,6 +21,7 @@ mod private {
 pub trait SwappableNumber: private::Sealed {}
 impl<T: private::Sealed> SwappableNumber for T {}
 
+#[cfg(feature = "nightly")]
 #[inline]
 fn swap_endianness_16bit(bytes: &mut [u8], num: usize) {
     for i in 0..num / 32 {
@@ -134,6 +137,7 @@ fn swap_endianness_16bit(bytes: &mut [u8], num: usize) {
     }
 }
 
+#[cfg(feature = "nightly")]
 #[inline]
 fn swap_endianness_32bit(bytes: &mut [u8], num: usize) {
     for i in 0..num / 16 {
@@ -209,6 +213,7 @@ fn swap_endianness_32bit(bytes: &mut [u8], num: usize) {
     }
 }
 
+#[cfg(feature = "nightly")]
 #[inline]
 fn swap_endianness_64bit(bytes: &mut [u8], num: usize) {
     for i in 0..num / 8 {
@@ -260,6 +265,30 @@ fn swap_endianness_64bit(bytes: &mut [u8], num: usize) {
     }
 }
 
+/// Without SIMD, the bytes of each number are reversed one number at a time.
+#[cfg(not(feature = "nightly"))]
+#[inline]
+fn swap_endianness_scalar<const WIDTH: usize>(bytes: &mut [u8], num: usize) {
+    for number in bytes[..num * WIDTH].chunks_exact_mut(WIDTH) {
+        number.reverse();
+    }
+}
+#[cfg(not(feature = "nightly"))]
+#[inline]
+fn swap_endianness_16bit(bytes: &mut [u8], num: usize) {
+    swap_endianness_scalar::<2>(bytes, num);
+}
+#[cfg(not(feature = "nightly"))]
+#[inline]
+fn swap_endianness_32bit(bytes: &mut [u8], num: usize) {
+    swap_endianness_scalar::<4>(bytes, num);
+}
+#[cfg(not(feature = "nightly"))]
+#[inline]
+fn swap_endianness_64bit(bytes: &mut [u8], num: usize) {
+    swap_endianness_scalar::<8>(bytes, num);
+}
+
 /// Swap the endianness of the given array (unless we're on a big-endian system)
 /// in-place depending on the width of the given type.
 fn swap_endianness_from_type<T: SwappableNumber>(items: &mut [u8]) {
@@ -276,6 +305,13 @@ fn swap_endianness_from_type<T: SwappableNumber>(items: &mut [u8]) {
     }
 }
 
+/// Swap the endianness of the numbers in the given data in-place. The length
+/// of the data must be a multiple of the width of `T`.
+#[inline]
+pub fn swap_endianness_in_place<T: SwappableNumber>(data: &mut [u8]) {
+    swap_endianness_from_type::<T>(data);
+}
+
 /// Swaps the endianness of the given data and return it as a `Vec<u8>`.
 #[inline]
 pub fn swap_endianness_as_u8<T: SwappableNumber>(data: &[u8]) -> Vec<u8> {
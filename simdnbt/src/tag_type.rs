@@ -0,0 +1,128 @@
+use std::fmt;
+
+use crate::common::{
+    BYTE_ARRAY_ID, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ARRAY_ID, INT_ID, LIST_ID,
+    LONG_ARRAY_ID, LONG_ID, SHORT_ID, STRING_ID,
+};
+
+/// The type of an NBT tag. This can be matched on exhaustively, unlike the
+/// numerical IDs that are returned by the `id` methods.
+///
+/// ```
+/// use simdnbt::{owned::NbtTag, TagType};
+///
+/// let tag = NbtTag::Int(1);
+/// assert_eq!(tag.tag_type(), TagType::Int);
+/// assert_eq!(tag.tag_type().id(), tag.id());
+/// assert_eq!(TagType::from_id(3), Some(TagType::Int));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum TagType {
+    Byte = BYTE_ID,
+    Short = SHORT_ID,
+    Int = INT_ID,
+    Long = LONG_ID,
+    Float = FLOAT_ID,
+    Double = DOUBLE_ID,
+    ByteArray = BYTE_ARRAY_ID,
+    String = STRING_ID,
+    List = LIST_ID,
+    Compound = COMPOUND_ID,
+    IntArray = INT_ARRAY_ID,
+    LongArray = LONG_ARRAY_ID,
+}
+
+impl TagType {
+    /// Every tag type, in the order of their IDs.
+    pub const ALL: [TagType; 12] = [
+        TagType::Byte,
+        TagType::Short,
+        TagType::Int,
+        TagType::Long,
+        TagType::Float,
+        TagType::Double,
+        TagType::ByteArray,
+        TagType::String,
+        TagType::List,
+        TagType::Compound,
+        TagType::IntArray,
+        TagType::LongArray,
+    ];
+
+    /// Get the tag type with the given numerical ID, or `None` if it's not a
+    /// valid ID. `TAG_End` (0) isn't a tag type, so it returns `None` too.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(id).checked_sub(1)?).copied()
+    }
+
+    /// Get the numerical ID of the tag type.
+    #[inline]
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// The name of the tag type in the NBT specification, like `TAG_Int`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TagType::Byte => "TAG_Byte",
+            TagType::Short => "TAG_Short",
+            TagType::Int => "TAG_Int",
+            TagType::Long => "TAG_Long",
+            TagType::Float => "TAG_Float",
+            TagType::Double => "TAG_Double",
+            TagType::ByteArray => "TAG_Byte_Array",
+            TagType::String => "TAG_String",
+            TagType::List => "TAG_List",
+            TagType::Compound => "TAG_Compound",
+            TagType::IntArray => "TAG_Int_Array",
+            TagType::LongArray => "TAG_Long_Array",
+        }
+    }
+}
+impl From<TagType> for u8 {
+    fn from(tag_type: TagType) -> Self {
+        tag_type.id()
+    }
+}
+impl fmt::Display for TagType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{borrow, compound, owned::BaseNbt};
+
+    #[test]
+    fn ids() {
+        assert_eq!(TagType::from_id(0), None);
+        assert_eq!(TagType::from_id(13), None);
+        for id in 1..=12 {
+            assert_eq!(TagType::from_id(id).unwrap().id(), id);
+        }
+        assert_eq!(TagType::LongArray.to_string(), "TAG_Long_Array");
+    }
+
+    #[test]
+    fn borrowed_and_owned() {
+        let nbt = BaseNbt::new(
+            "",
+            compound! {
+                "byte": 1i8, "short": 1i16, "int": 1, "long": 1i64, "float": 1f32,
+                "double": 1f64, "bytes": [B; 1], "string": "a", "list": [1],
+                "empty": [], "compound": {}, "ints": [I; 1], "longs": [L; 1],
+            },
+        );
+        let data = nbt.to_bytes();
+        let borrowed = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        for ((name, borrowed), (_, owned)) in borrowed.as_compound().iter().zip(nbt.iter()) {
+            assert_eq!(borrowed.tag_type(), owned.tag_type(), "{}", name.to_str());
+            assert_eq!(borrowed.tag_type().id(), borrowed.id());
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Test data and helpers that are shared by the unit tests.
+
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::owned::{BaseNbt, NbtCompound};
+
+/// Decompresses one of the gzipped files in the `tests` directory.
+pub fn gunzip(src: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    GzDecoder::new(src).read_to_end(&mut data).unwrap();
+    data
+}
+
+/// The uncompressed contents of `tests/complex_player.dat`.
+pub fn complex_player() -> Vec<u8> {
+    gunzip(include_bytes!("../tests/complex_player.dat"))
+}
+
+/// The uncompressed contents of `tests/bigtest.nbt`.
+pub fn bigtest() -> Vec<u8> {
+    gunzip(include_bytes!("../tests/bigtest.nbt"))
+}
+
+/// The uncompressed contents of `tests/level.dat`.
+pub fn level_dat() -> Vec<u8> {
+    gunzip(include_bytes!("../tests/level.dat"))
+}
+
+/// An item stack with the given count.
+pub fn item(count: i8) -> NbtCompound {
+    let mut item = NbtCompound::new();
+    item.insert("id", "minecraft:stone");
+    item.insert("Count", count);
+    item
+}
+
+/// Writes the compound as NBT with an empty root name.
+pub fn write(compound: NbtCompound) -> Vec<u8> {
+    BaseNbt::new("", compound).to_bytes()
+}
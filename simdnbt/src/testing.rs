@@ -0,0 +1,465 @@
+//! Utilities for comparing NBT in tests.
+//!
+//! The [`assert_nbt_eq!`](crate::assert_nbt_eq) and
+//! [`assert_nbt_matches!`](crate::assert_nbt_matches) macros compare compounds
+//! without caring about the order of their keys, and print every difference
+//! between the two values when the assertion fails.
+//!
+//! ```
+//! use simdnbt::{assert_nbt_eq, assert_nbt_matches, owned::NbtCompound};
+//!
+//! let mut left = NbtCompound::new();
+//! left.insert("a", 1);
+//! left.insert("b", 0.1f64 + 0.2);
+//! let mut right = NbtCompound::new();
+//! right.insert("b", 0.3f64);
+//! right.insert("a", 1);
+//!
+//! assert_nbt_eq!(left, right, epsilon = 1e-9);
+//!
+//! // assert_nbt_matches ignores keys that only exist on the left
+//! let mut expected = NbtCompound::new();
+//! expected.insert("a", 1);
+//! assert_nbt_matches!(left, expected);
+//! ```
+
+use std::fmt::{self, Display, Write};
+
+use crate::{borrow, owned};
+
+/// Options for how NBT values should be compared, used by [`diff`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompareOptions {
+    /// The maximum difference between two floats or doubles for them to be
+    /// considered equal. NaNs are always considered equal to each other.
+    pub epsilon: f64,
+    /// Whether keys that only exist in the left compound should be ignored.
+    /// This is how [`assert_nbt_matches!`](crate::assert_nbt_matches)
+    /// compares values.
+    pub partial: bool,
+}
+
+/// A single difference between two NBT values, returned by [`diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// The path to the value, separated by dots. Indexes into lists look like
+    /// `[0]`, and the path is empty if the root values are different.
+    pub path: String,
+    /// The value on the left, or `None` if it doesn't exist.
+    pub left: Option<owned::NbtTag>,
+    /// The value on the right, or `None` if it doesn't exist.
+    pub right: Option<owned::NbtTag>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "(root)"
+        } else {
+            &self.path
+        };
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => write!(f, "{path}: {left:?} != {right:?}"),
+            (Some(left), None) => write!(f, "{path}: {left:?} is missing on the right"),
+            (None, Some(right)) => write!(f, "{path}: {right:?} is missing on the left"),
+            (None, None) => write!(f, "{path}: missing on both sides"),
+        }
+    }
+}
+
+/// A value that can be compared with [`diff`] and the assertion macros.
+///
+/// This is implemented for the tags, lists, and compounds from both
+/// [`owned`] and [`borrow`]. For [`owned::BaseNbt`] and [`borrow::BaseNbt`],
+/// only the compound is compared and not the name.
+pub trait ToOwnedNbtTag {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag;
+}
+
+impl<T: ToOwnedNbtTag + ?Sized> ToOwnedNbtTag for &T {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        (**self).to_owned_nbt_tag()
+    }
+}
+impl ToOwnedNbtTag for owned::NbtTag {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        self.clone()
+    }
+}
+impl ToOwnedNbtTag for owned::NbtCompound {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::Compound(self.clone())
+    }
+}
+impl ToOwnedNbtTag for owned::NbtList {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::List(self.clone())
+    }
+}
+impl ToOwnedNbtTag for owned::BaseNbt {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::Compound((**self).clone())
+    }
+}
+impl ToOwnedNbtTag for owned::Nbt {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::Compound((***self).clone())
+    }
+}
+impl ToOwnedNbtTag for borrow::NbtTag<'_, '_> {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        self.to_owned()
+    }
+}
+impl ToOwnedNbtTag for borrow::NbtCompound<'_, '_> {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::Compound(self.to_owned())
+    }
+}
+impl ToOwnedNbtTag for borrow::NbtList<'_, '_> {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::List(self.to_owned())
+    }
+}
+impl ToOwnedNbtTag for borrow::BaseNbt<'_> {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        owned::NbtTag::Compound(self.as_compound().to_owned())
+    }
+}
+impl ToOwnedNbtTag for borrow::Nbt<'_> {
+    fn to_owned_nbt_tag(&self) -> owned::NbtTag {
+        match self {
+            borrow::Nbt::Some(nbt) => nbt.to_owned_nbt_tag(),
+            borrow::Nbt::None => owned::NbtTag::Compound(owned::NbtCompound::new()),
+        }
+    }
+}
+
+/// Returns every difference between the two values.
+///
+/// The order of keys in compounds is ignored, but the order of elements in
+/// lists isn't. Empty lists are considered equal to each other even if they
+/// have different types.
+pub fn diff(
+    left: &impl ToOwnedNbtTag,
+    right: &impl ToOwnedNbtTag,
+    options: CompareOptions,
+) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_tags(
+        &mut String::new(),
+        &left.to_owned_nbt_tag(),
+        &right.to_owned_nbt_tag(),
+        options,
+        &mut differences,
+    );
+    differences
+}
+
+fn diff_tags(
+    path: &mut String,
+    left: &owned::NbtTag,
+    right: &owned::NbtTag,
+    options: CompareOptions,
+    differences: &mut Vec<Difference>,
+) {
+    use owned::NbtTag;
+
+    let equal = match (left, right) {
+        (NbtTag::Compound(left), NbtTag::Compound(right)) => {
+            return diff_compounds(path, left, right, options, differences)
+        }
+        (NbtTag::List(left), NbtTag::List(right)) => {
+            return diff_lists(path, left, right, options, differences)
+        }
+        (left, right) => left.eq_with_epsilon(right, options.epsilon),
+    };
+    if !equal {
+        differences.push(Difference {
+            path: path.clone(),
+            left: Some(left.clone()),
+            right: Some(right.clone()),
+        });
+    }
+}
+
+fn diff_compounds(
+    path: &mut String,
+    left: &owned::NbtCompound,
+    right: &owned::NbtCompound,
+    options: CompareOptions,
+    differences: &mut Vec<Difference>,
+) {
+    let path_len = path.len();
+    for (key, left_tag) in left.iter() {
+        push_key(path, key);
+        match right.iter().find(|(right_key, _)| *right_key == key) {
+            Some((_, right_tag)) => diff_tags(path, left_tag, right_tag, options, differences),
+            None => {
+                if !options.partial {
+                    differences.push(Difference {
+                        path: path.clone(),
+                        left: Some(left_tag.clone()),
+                        right: None,
+                    });
+                }
+            }
+        }
+        path.truncate(path_len);
+    }
+    for (key, right_tag) in right.iter() {
+        if !left.iter().any(|(left_key, _)| left_key == key) {
+            push_key(path, key);
+            differences.push(Difference {
+                path: path.clone(),
+                left: None,
+                right: Some(right_tag.clone()),
+            });
+            path.truncate(path_len);
+        }
+    }
+}
+
+fn diff_lists(
+    path: &mut String,
+    left: &owned::NbtList,
+    right: &owned::NbtList,
+    options: CompareOptions,
+    differences: &mut Vec<Difference>,
+) {
+    let left_tags = left.as_nbt_tags();
+    let right_tags = right.as_nbt_tags();
+    if left_tags.is_empty() && right_tags.is_empty() {
+        return;
+    }
+    if left.id() != right.id() || left_tags.len() != right_tags.len() {
+        differences.push(Difference {
+            path: path.clone(),
+            left: Some(owned::NbtTag::List(left.clone())),
+            right: Some(owned::NbtTag::List(right.clone())),
+        });
+        return;
+    }
+
+    let path_len = path.len();
+    for (i, (left_tag, right_tag)) in left_tags.iter().zip(&right_tags).enumerate() {
+        write!(path, "[{i}]").unwrap();
+        diff_tags(path, left_tag, right_tag, options, differences);
+        path.truncate(path_len);
+    }
+}
+
+fn push_key(path: &mut String, key: &crate::Mutf8Str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(&key.to_string_lossy());
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_nbt(
+    left: &impl ToOwnedNbtTag,
+    right: &impl ToOwnedNbtTag,
+    options: CompareOptions,
+    assertion: &str,
+) {
+    let differences = diff(left, right, options);
+    if differences.is_empty() {
+        return;
+    }
+
+    let mut message = format!("assertion `{assertion}` failed, the NBT values differ:");
+    for difference in differences {
+        write!(message, "\n  {difference}").unwrap();
+    }
+    panic!("{message}");
+}
+
+/// Asserts that two NBT values are equal, ignoring the order of keys in
+/// compounds. On failure, every difference between the values is printed.
+///
+/// An `epsilon = ...` argument can be passed to allow floats and doubles to be
+/// slightly different. See the [`testing`](crate::testing) module for more
+/// details.
+#[macro_export]
+macro_rules! assert_nbt_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_nbt(
+            &$left,
+            &$right,
+            $crate::testing::CompareOptions::default(),
+            "left == right",
+        )
+    };
+    ($left:expr, $right:expr, epsilon = $epsilon:expr $(,)?) => {
+        $crate::testing::assert_nbt(
+            &$left,
+            &$right,
+            $crate::testing::CompareOptions {
+                epsilon: $epsilon,
+                partial: false,
+            },
+            "left == right",
+        )
+    };
+}
+
+/// Like [`assert_nbt_eq!`], but keys that only exist in the actual value (the
+/// first argument) are ignored.
+///
+/// This is useful for checking a few fields of a large value without having
+/// to write out the whole thing.
+#[macro_export]
+macro_rules! assert_nbt_matches {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::testing::assert_nbt(
+            &$actual,
+            &$expected,
+            $crate::testing::CompareOptions {
+                epsilon: 0.,
+                partial: true,
+            },
+            "actual matches expected",
+        )
+    };
+    ($actual:expr, $expected:expr, epsilon = $epsilon:expr $(,)?) => {
+        $crate::testing::assert_nbt(
+            &$actual,
+            &$expected,
+            $crate::testing::CompareOptions {
+                epsilon: $epsilon,
+                partial: true,
+            },
+            "actual matches expected",
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        owned::{BaseNbt, NbtCompound, NbtList, NbtTag},
+        test_fixtures::item,
+    };
+
+    #[test]
+    fn order_insensitive() {
+        let mut left = NbtCompound::new();
+        left.insert("a", 1);
+        left.insert("b", "hello");
+        let mut right = NbtCompound::new();
+        right.insert("b", "hello");
+        right.insert("a", 1);
+
+        assert_nbt_eq!(left, right);
+    }
+
+    #[test]
+    fn borrow_and_owned() {
+        let mut compound = NbtCompound::new();
+        compound.insert("Inventory", NbtList::Compound(vec![item(1), item(2)]));
+        let mut data = Vec::new();
+        BaseNbt::new("", compound.clone()).write(&mut data);
+        let nbt = crate::borrow::read(&mut Cursor::new(&data)).unwrap();
+
+        assert_nbt_eq!(nbt, compound);
+        assert_nbt_eq!(nbt.unwrap().as_compound(), &compound);
+    }
+
+    #[test]
+    fn differences() {
+        let mut left = NbtCompound::new();
+        left.insert("Inventory", NbtList::Compound(vec![item(1), item(2)]));
+        left.insert("only_left", 1);
+        left.insert("Pos", NbtList::Double(vec![0., 1.]));
+        let mut right = NbtCompound::new();
+        right.insert("Inventory", NbtList::Compound(vec![item(1), item(3)]));
+        right.insert("Pos", NbtList::Double(vec![0., 1., 2.]));
+        right.insert("only_right", 2);
+
+        let differences = diff(&left, &right, CompareOptions::default());
+        assert_eq!(
+            differences,
+            vec![
+                Difference {
+                    path: "Inventory[1].Count".to_owned(),
+                    left: Some(NbtTag::Byte(2)),
+                    right: Some(NbtTag::Byte(3)),
+                },
+                Difference {
+                    path: "only_left".to_owned(),
+                    left: Some(NbtTag::Int(1)),
+                    right: None,
+                },
+                Difference {
+                    path: "Pos".to_owned(),
+                    left: Some(NbtTag::List(NbtList::Double(vec![0., 1.]))),
+                    right: Some(NbtTag::List(NbtList::Double(vec![0., 1., 2.]))),
+                },
+                Difference {
+                    path: "only_right".to_owned(),
+                    left: None,
+                    right: Some(NbtTag::Int(2)),
+                },
+            ]
+        );
+        assert_eq!(
+            differences[0].to_string(),
+            "Inventory[1].Count: Byte(2) != Byte(3)"
+        );
+    }
+
+    #[test]
+    fn floats() {
+        let mut left = NbtCompound::new();
+        left.insert("a", 0.1f64 + 0.2);
+        left.insert("b", f32::NAN);
+        let mut right = NbtCompound::new();
+        right.insert("a", 0.3f64);
+        right.insert("b", f32::NAN);
+
+        assert_eq!(diff(&left, &right, CompareOptions::default()).len(), 1);
+        assert_nbt_eq!(left, right, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn matches() {
+        let mut actual = NbtCompound::new();
+        actual.insert("Inventory", NbtList::Compound(vec![item(1)]));
+        actual.insert("extra", 1);
+        let mut expected = NbtCompound::new();
+        let mut expected_item = NbtCompound::new();
+        expected_item.insert("Count", 1i8);
+        expected.insert("Inventory", NbtList::Compound(vec![expected_item]));
+
+        assert_nbt_matches!(actual, expected);
+        expected.insert("missing", 1);
+        assert_eq!(
+            diff(
+                &actual,
+                &expected,
+                CompareOptions {
+                    partial: true,
+                    ..Default::default()
+                }
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Inventory[0].Count: Byte(1) != Byte(2)")]
+    fn failure_message() {
+        let mut left = NbtCompound::new();
+        left.insert("Inventory", NbtList::Compound(vec![item(1)]));
+        let mut right = NbtCompound::new();
+        right.insert("Inventory", NbtList::Compound(vec![item(2)]));
+
+        assert_nbt_eq!(left, right);
+    }
+}
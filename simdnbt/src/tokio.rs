@@ -0,0 +1,210 @@
+//! Reading and writing NBT with Tokio's [`AsyncRead`] and [`AsyncWrite`].
+//!
+//! The parser is synchronous, so these functions read the whole payload into
+//! memory first and then parse it all at once. For the borrowed types, use
+//! [`read_bytes`] and then [`borrow::read`](crate::borrow::read) on the
+//! buffer.
+//!
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), simdnbt::tokio::ReadError> {
+//! use simdnbt::{owned, tokio::Framing};
+//!
+//! let mut compound = owned::NbtCompound::new();
+//! compound.insert("hello", "world");
+//! let nbt = owned::Nbt::Some(owned::BaseNbt::new("", compound));
+//!
+//! let mut stream = Vec::new();
+//! simdnbt::tokio::write_async(&mut stream, &nbt, Framing::LengthPrefixed).await?;
+//! let read = simdnbt::tokio::read_async(&mut &stream[..], Framing::LengthPrefixed, 1024).await?;
+//! assert_eq!(read, nbt);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Cursor};
+
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use thiserror::Error;
+
+use crate::{owned, Error};
+
+/// How the end of an NBT payload is found in a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// The payload is everything until the end of the stream.
+    UntilEof,
+    /// The payload is prefixed by its length in bytes, as a big-endian `u32`.
+    LengthPrefixed,
+}
+
+/// An error from [`read_async`].
+#[derive(Error, Debug)]
+pub enum ReadError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] Error),
+}
+
+/// Read a complete NBT payload from the reader without parsing it.
+///
+/// If the payload is longer than `max_length` bytes, an error with the kind
+/// [`io::ErrorKind::InvalidData`] is returned. Payloads that end before their
+/// length prefix says they should return [`io::ErrorKind::UnexpectedEof`].
+pub async fn read_bytes<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    framing: Framing,
+    max_length: usize,
+) -> io::Result<Vec<u8>> {
+    match framing {
+        Framing::UntilEof => {
+            let mut data = Vec::new();
+            // read one extra byte so we can tell whether it was too long
+            let limit = (max_length as u64).saturating_add(1);
+            (&mut *reader).take(limit).read_to_end(&mut data).await?;
+            if data.len() > max_length {
+                return Err(too_long(max_length));
+            }
+            Ok(data)
+        }
+        Framing::LengthPrefixed => {
+            let length = reader.read_u32().await? as usize;
+            if length > max_length {
+                return Err(too_long(max_length));
+            }
+            let mut data = vec![0; length];
+            reader.read_exact(&mut data).await?;
+            Ok(data)
+        }
+    }
+}
+
+/// Read a complete NBT payload from the reader and parse it as a normal root
+/// compound with [`owned::read`].
+///
+/// See [`read_bytes`] for what `max_length` does.
+pub async fn read_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    framing: Framing,
+    max_length: usize,
+) -> Result<owned::Nbt, ReadError> {
+    let data = read_bytes(reader, framing, max_length).await?;
+    Ok(owned::read(&mut Cursor::new(&data))?)
+}
+
+/// Write some already serialized NBT to the writer, and then flush it.
+///
+/// With [`Framing::LengthPrefixed`], data that's longer than [`u32::MAX`]
+/// returns an error with the kind [`io::ErrorKind::InvalidInput`].
+pub async fn write_bytes<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    data: &[u8],
+    framing: Framing,
+) -> io::Result<()> {
+    if framing == Framing::LengthPrefixed {
+        let length = u32::try_from(data.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "NBT is too long to be length-prefixed",
+            )
+        })?;
+        writer.write_u32(length).await?;
+    }
+    writer.write_all(data).await?;
+    writer.flush().await
+}
+
+/// Serialize the NBT and write it to the writer. See [`write_bytes`].
+pub async fn write_async<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    nbt: &owned::Nbt,
+    framing: Framing,
+) -> io::Result<()> {
+    let mut data = Vec::new();
+    nbt.write(&mut data);
+    write_bytes(writer, &data, framing).await
+}
+
+fn too_long(max_length: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("NBT is longer than the maximum of {max_length} bytes"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::borrow;
+
+    fn nbt() -> owned::Nbt {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("a", 1);
+        compound.insert("b", owned::NbtList::String(vec!["c".into()]));
+        owned::Nbt::Some(owned::BaseNbt::new("root", compound))
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let nbt = nbt();
+        for framing in [Framing::UntilEof, Framing::LengthPrefixed] {
+            let mut stream = Vec::new();
+            write_async(&mut stream, &nbt, framing).await.unwrap();
+            let read = read_async(&mut &stream[..], framing, 1024).await.unwrap();
+            assert_eq!(read, nbt);
+        }
+
+        // two payloads in a row
+        let mut stream = Vec::new();
+        write_async(&mut stream, &nbt, Framing::LengthPrefixed)
+            .await
+            .unwrap();
+        write_async(&mut stream, &owned::Nbt::None, Framing::LengthPrefixed)
+            .await
+            .unwrap();
+        let mut reader = &stream[..];
+        let data = read_bytes(&mut reader, Framing::LengthPrefixed, 1024)
+            .await
+            .unwrap();
+        let borrowed = borrow::read(&mut Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(borrowed, *nbt);
+        let read = read_async(&mut reader, Framing::LengthPrefixed, 1024)
+            .await
+            .unwrap();
+        assert_eq!(read, owned::Nbt::None);
+        assert!(reader.is_empty());
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let mut data = Vec::new();
+        nbt().write(&mut data);
+
+        let err = read_bytes(&mut &data[..], Framing::UntilEof, data.len() - 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_bytes(&mut &data[..], Framing::UntilEof, data.len())
+            .await
+            .is_ok());
+
+        let mut stream = Vec::new();
+        write_bytes(&mut stream, &data, Framing::LengthPrefixed)
+            .await
+            .unwrap();
+        let err = read_bytes(&mut &stream[..], Framing::LengthPrefixed, 4)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bytes(&mut &stream[..10], Framing::LengthPrefixed, 1024)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_async(&mut &data[..5], Framing::UntilEof, 1024)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadError::Nbt(Error::UnexpectedEof)));
+    }
+}
@@ -1,6 +1,24 @@
-use std::{collections::HashMap, fmt::Display, hash::Hash, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    hash::Hash,
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
-use crate::DeserializeError;
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+
+use crate::{
+    common::{
+        write_string, BYTE_ID, COMPOUND_ID, DOUBLE_ID, FLOAT_ID, INT_ID, LONG_ID, SHORT_ID,
+        STRING_ID,
+    },
+    DeserializeError, Mutf8Str, RawNbtValue, WriteOptions,
+};
 
 pub trait Deserialize: Sized {
     fn from_nbt(nbt: &crate::borrow::BaseNbt) -> Result<Self, DeserializeError> {
@@ -8,6 +26,16 @@ pub trait Deserialize: Sized {
     }
 
     fn from_compound(compound: crate::borrow::NbtCompound) -> Result<Self, DeserializeError>;
+
+    /// Returns whether the compound has any of the tags that this type reads.
+    /// This is used for `#[simdnbt(flatten)]` fields that are an `Option`,
+    /// which are `None` if none of their fields are in the compound.
+    ///
+    /// `#[derive(Deserialize)]` implements this for structs, and otherwise
+    /// it's true if the compound isn't empty.
+    fn has_any_field(compound: crate::borrow::NbtCompound) -> bool {
+        !compound.is_empty()
+    }
 }
 
 pub trait Serialize: Sized {
@@ -16,15 +44,62 @@ pub trait Serialize: Sized {
     }
 
     fn to_compound(self) -> crate::owned::NbtCompound;
+
+    /// Write the value as a root compound with an empty name. This writes
+    /// the same bytes as `self.to_nbt().write(data)`.
+    fn write_nbt(self, data: &mut Vec<u8>) {
+        data.extend_from_slice(&[COMPOUND_ID, 0, 0]);
+        self.write_compound(data);
+    }
+
+    /// Write the value as the payload of a compound tag, including the end
+    /// tag.
+    ///
+    /// `#[derive(Serialize)]` implements this by writing each field straight
+    /// to the buffer, so no [`owned::NbtCompound`](crate::owned::NbtCompound)
+    /// is built in between.
+    fn write_compound(self, data: &mut Vec<u8>) {
+        self.to_compound().write(data);
+    }
 }
 
 pub trait FromNbtTag: Sized {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self>;
+    /// Like [`Self::from_nbt_tag`], but accepts tags of other types that can
+    /// be converted to `Self`. This is used for fields with
+    /// `#[simdnbt(lenient)]`.
+    ///
+    /// Integers accept any narrower integer tag, floats accept integer tags
+    /// (and doubles accept floats), and bools accept any integer tag.
+    ///
+    /// This is the same as `from_nbt_tag` unless the type overrides it.
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Self::from_nbt_tag(tag)
+    }
+    /// Like [`Self::from_nbt_tag`], but keeps the errors from deserializing
+    /// compounds that are nested in the tag, so they can say where the error
+    /// happened. Returns `Ok(None)` if the tag is the wrong type.
+    ///
+    /// This is the same as `from_nbt_tag` unless the type overrides it.
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        Ok(Self::from_nbt_tag(tag))
+    }
+    /// Like [`Self::from_nbt_tag`], but converts an owned tag. This is used by
+    /// [`owned::NbtCompound::get_as`](crate::owned::NbtCompound::get_as).
+    ///
+    /// Unless the type overrides it, the tag is written and read again as a
+    /// borrowed tag, which is slower and fails if it's nested deeper than the
+    /// borrowed reader allows.
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        let raw = RawNbtValue::from_tag(tag);
+        let tag = raw.parse_borrowed().ok()?;
+        Self::from_nbt_tag(tag.as_tag())
+    }
     fn from_optional_nbt_tag(
         tag: Option<crate::borrow::NbtTag>,
     ) -> Result<Option<Self>, DeserializeError> {
         match tag {
-            Some(tag) => Ok(Self::from_nbt_tag(tag)),
+            Some(tag) => Self::try_from_nbt_tag(tag),
             None => Err(DeserializeError::MissingField),
         }
     }
@@ -35,39 +110,96 @@ pub trait ToNbtTag: Sized {
     fn to_optional_nbt_tag(self) -> Option<crate::owned::NbtTag> {
         Some(self.to_nbt_tag())
     }
+
+    /// Write the value as a tag with the given name, like it would be written
+    /// in a compound. Nothing is written if [`Self::to_optional_nbt_tag`]
+    /// would return `None`.
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        if let Some(tag) = self.to_optional_nbt_tag() {
+            write_tag_header(data, tag.id(), name);
+            data.reserve(4);
+            // SAFETY: we just reserved 4 bytes
+            unsafe { tag.write_without_tag_type_unchecked(data, &WriteOptions::default(), false) };
+        }
+    }
 }
 
-impl<K: Display + FromStr + Eq + Hash, V: FromNbtTag> Deserialize for HashMap<K, V> {
-    fn from_compound(compound: crate::borrow::NbtCompound) -> Result<Self, DeserializeError> {
-        let mut hashmap = HashMap::new();
+/// Read an integer tag that's no wider than the tag with the ID `max_id`, for
+/// [`FromNbtTag::from_nbt_tag_lenient`].
+fn lenient_integer(tag: crate::borrow::NbtTag, max_id: u8) -> Option<i64> {
+    match tag.id() {
+        BYTE_ID => tag.byte().map(i64::from),
+        SHORT_ID if max_id >= SHORT_ID => tag.short().map(i64::from),
+        INT_ID if max_id >= INT_ID => tag.int().map(i64::from),
+        LONG_ID if max_id >= LONG_ID => tag.long(),
+        _ => None,
+    }
+}
+/// Like [`lenient_integer`], but narrower tags are zero-extended since
+/// they're unsigned too.
+fn lenient_unsigned_integer(tag: crate::borrow::NbtTag, max_id: u8) -> Option<u64> {
+    match tag.id() {
+        BYTE_ID => tag.byte().map(|b| b as u8 as u64),
+        SHORT_ID if max_id >= SHORT_ID => tag.short().map(|s| s as u16 as u64),
+        INT_ID if max_id >= INT_ID => tag.int().map(|i| i as u32 as u64),
+        LONG_ID if max_id >= LONG_ID => tag.long().map(|l| l as u64),
+        _ => None,
+    }
+}
 
-        for (k, v) in compound.iter() {
-            let k_str = k.to_str();
-            let k_parsed = k_str
-                .parse()
-                .map_err(|_| DeserializeError::MismatchedFieldType("key".to_owned()))?;
+/// Write the tag ID and name that come before a tag's payload in a compound.
+#[inline]
+fn write_tag_header(data: &mut Vec<u8>, id: u8, name: &str) {
+    data.push(id);
+    write_string(data, &Mutf8Str::from_str(name));
+}
 
-            let v_parsed = V::from_nbt_tag(v).ok_or_else(|| {
-                DeserializeError::MismatchedFieldType(format!("value for key {k_str}"))
-            })?;
+// maps are compounds, and the keys are parsed from the names of the tags.
+// BTreeMap sorts the keys and IndexMap keeps them in the same order as the
+// compound.
+macro_rules! impl_nbt_for_map {
+    ($map:ident $(, $bound:path)*) => {
+        impl<K: Display + FromStr $(+ $bound)*, V: FromNbtTag> Deserialize for $map<K, V> {
+            fn from_compound(
+                compound: crate::borrow::NbtCompound,
+            ) -> Result<Self, DeserializeError> {
+                let mut map = $map::new();
 
-            hashmap.insert(k_parsed, v_parsed);
-        }
+                for (k, v) in compound.iter() {
+                    let k_str = k.to_str();
+                    let k_parsed = k_str
+                        .parse()
+                        .map_err(|_| DeserializeError::MismatchedFieldType("key".to_owned()))?;
 
-        Ok(hashmap)
-    }
-}
-impl<K: Display + FromStr + Eq + Hash, V: ToNbtTag> Serialize for HashMap<K, V> {
-    fn to_compound(self) -> crate::owned::NbtCompound {
-        let mut compound = crate::owned::NbtCompound::new();
+                    let v_parsed = V::try_from_nbt_tag(v)
+                        .map_err(|err| err.in_key(&k_str))?
+                        .ok_or_else(|| {
+                            DeserializeError::MismatchedFieldType(format!("value for key {k_str}"))
+                        })?;
+
+                    map.insert(k_parsed, v_parsed);
+                }
 
-        for (k, v) in self {
-            compound.insert(k.to_string(), v.to_nbt_tag());
+                Ok(map)
+            }
         }
+        impl<K: Display + FromStr $(+ $bound)*, V: ToNbtTag> Serialize for $map<K, V> {
+            fn to_compound(self) -> crate::owned::NbtCompound {
+                let mut compound = crate::owned::NbtCompound::new();
 
-        compound
-    }
+                for (k, v) in self {
+                    compound.insert(k.to_string(), v.to_nbt_tag());
+                }
+
+                compound
+            }
+        }
+    };
 }
+impl_nbt_for_map!(HashMap, Eq, Hash);
+impl_nbt_for_map!(BTreeMap, Ord);
+#[cfg(feature = "indexmap")]
+impl_nbt_for_map!(IndexMap, Eq, Hash);
 
 impl Deserialize for crate::owned::NbtCompound {
     fn from_compound(compound: crate::borrow::NbtCompound) -> Result<Self, DeserializeError> {
@@ -84,18 +216,28 @@ impl<T: Deserialize> FromNbtTag for T {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.compound().and_then(|c| Self::from_compound(c).ok())
     }
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        tag.compound().map(Self::from_compound).transpose()
+    }
 }
 
 impl<T: Serialize> ToNbtTag for T {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Compound(self.to_compound())
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, COMPOUND_ID, name);
+        self.write_compound(data);
+    }
 }
 
 impl FromNbtTag for crate::owned::NbtTag {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         Some(tag.to_owned())
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        Some(tag.clone())
+    }
 }
 impl ToNbtTag for crate::owned::NbtTag {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
@@ -108,83 +250,172 @@ impl FromNbtTag for i8 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.byte()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.byte()
+    }
 }
 impl ToNbtTag for i8 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Byte(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, BYTE_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for i16 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.short()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.short()
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_integer(tag, SHORT_ID).map(|v| v as i16)
+    }
 }
 impl ToNbtTag for i16 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Short(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, SHORT_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for i32 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.int()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.int()
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_integer(tag, INT_ID).map(|v| v as i32)
+    }
 }
 impl ToNbtTag for i32 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Int(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, INT_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for i64 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.long()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.long()
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_integer(tag, LONG_ID)
+    }
 }
 impl ToNbtTag for i64 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Long(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, LONG_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for f32 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.float()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.float()
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        match tag.id() {
+            FLOAT_ID => tag.float(),
+            _ => lenient_integer(tag, LONG_ID).map(|v| v as f32),
+        }
+    }
 }
 impl ToNbtTag for f32 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Float(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, FLOAT_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for f64 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.double()
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.double()
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        match tag.id() {
+            DOUBLE_ID => tag.double(),
+            FLOAT_ID => tag.float().map(f64::from),
+            _ => lenient_integer(tag, LONG_ID).map(|v| v as f64),
+        }
+    }
 }
 impl ToNbtTag for f64 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Double(self)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, DOUBLE_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for String {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.string().map(|s| s.to_string())
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.string().map(|s| s.to_string())
+    }
 }
 impl ToNbtTag for String {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::String(self.into())
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        self.as_str().write_named_nbt_tag(name, data);
+    }
 }
 
 impl ToNbtTag for &str {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::String(self.into())
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, STRING_ID, name);
+        write_string(data, &Mutf8Str::from_str(self));
+    }
+}
+
+// unlike String, this keeps the original bytes even if they're invalid
+impl FromNbtTag for crate::Mutf8String {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        tag.string().map(|s| s.to_owned())
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.string().map(|s| s.to_owned())
+    }
+}
+impl ToNbtTag for crate::Mutf8String {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::String(self)
+    }
 }
 
 // unsigned integers
@@ -192,44 +423,81 @@ impl FromNbtTag for u8 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.byte().map(|b| b as u8)
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.byte().map(|b| b as u8)
+    }
 }
 impl ToNbtTag for u8 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Byte(self as i8)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, BYTE_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for u16 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.short().map(|s| s as u16)
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.short().map(|s| s as u16)
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_unsigned_integer(tag, SHORT_ID).map(|v| v as u16)
+    }
 }
 impl ToNbtTag for u16 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Short(self as i16)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, SHORT_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for u32 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.int().map(|i| i as u32)
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.int().map(|i| i as u32)
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_unsigned_integer(tag, INT_ID).map(|v| v as u32)
+    }
 }
 impl ToNbtTag for u32 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Int(self as i32)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, INT_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 impl FromNbtTag for u64 {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.long().map(|l| l as u64)
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.long().map(|l| l as u64)
+    }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_unsigned_integer(tag, LONG_ID)
+    }
 }
 impl ToNbtTag for u64 {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Long(self as i64)
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, LONG_ID, name);
+        data.extend_from_slice(&self.to_be_bytes());
+    }
 }
 
 // lists
@@ -240,6 +508,12 @@ impl FromNbtTag for Vec<String> {
                 .map(|s| s.iter().map(|s| s.to_string()).collect())
         })
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.list().and_then(|l| {
+            l.strings()
+                .map(|s| s.iter().map(|s| s.to_string()).collect())
+        })
+    }
 }
 impl ToNbtTag for Vec<String> {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
@@ -254,6 +528,12 @@ impl<T: FromNbtTag> FromNbtTag for Option<T> {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         Some(T::from_nbt_tag(tag))
     }
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Some(T::from_nbt_tag_lenient(tag))
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        Some(T::from_owned_nbt_tag(tag))
+    }
     fn from_optional_nbt_tag(
         tag: Option<crate::borrow::NbtTag>,
     ) -> Result<Option<Self>, DeserializeError> {
@@ -270,23 +550,35 @@ impl<T: ToNbtTag> ToNbtTag for Option<T> {
     fn to_optional_nbt_tag(self) -> Option<crate::owned::NbtTag> {
         self.map(|t| t.to_nbt_tag())
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        if let Some(t) = self {
+            t.write_named_nbt_tag(name, data);
+        }
+    }
 }
 
 impl<T: Deserialize> FromNbtTag for Vec<Option<T>> {
     /// A list of compounds where `None` is an empty compound
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
-        let list = tag.list()?;
-        let list = list.compounds()?;
+        Self::try_from_nbt_tag(tag).ok().flatten()
+    }
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        let Some(list) = tag.list() else {
+            return Ok(None);
+        };
+        let Some(list) = list.compounds() else {
+            return Ok(None);
+        };
         let mut vec = Vec::with_capacity(list.approx_len() as usize);
-        for tag in list {
+        for (i, tag) in list.into_iter().enumerate() {
             if tag.is_empty() {
                 vec.push(None);
             } else {
-                vec.push(Some(T::from_compound(tag).ok()?));
+                vec.push(Some(T::from_compound(tag).map_err(|err| err.in_index(i))?));
             }
         }
 
-        Some(vec)
+        Ok(Some(vec))
     }
 }
 impl<T: Serialize> ToNbtTag for Vec<Option<T>> {
@@ -304,14 +596,21 @@ impl<T: Serialize> ToNbtTag for Vec<Option<T>> {
 
 impl<T: Deserialize> FromNbtTag for Vec<T> {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
-        let list = tag.list()?;
-        let list = list.compounds()?;
+        Self::try_from_nbt_tag(tag).ok().flatten()
+    }
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        let Some(list) = tag.list() else {
+            return Ok(None);
+        };
+        let Some(list) = list.compounds() else {
+            return Ok(None);
+        };
         let mut vec = Vec::with_capacity(list.approx_len() as usize);
-        for tag in list {
-            vec.push(T::from_compound(tag).ok()?);
+        for (i, tag) in list.into_iter().enumerate() {
+            vec.push(T::from_compound(tag).map_err(|err| err.in_index(i))?);
         }
 
-        Some(vec)
+        Ok(Some(vec))
     }
 }
 impl<T: Serialize> ToNbtTag for Vec<T> {
@@ -322,15 +621,439 @@ impl<T: Serialize> ToNbtTag for Vec<T> {
     }
 }
 
+// other collections are read and written like a Vec
+macro_rules! impl_nbt_tag_for_collection {
+    ($collection:ident $(, $bound:path)*) => {
+        impl<T: Deserialize $(+ $bound)*> FromNbtTag for $collection<T> {
+            fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                Vec::<T>::from_nbt_tag(tag).map(|v| v.into_iter().collect())
+            }
+            fn try_from_nbt_tag(
+                tag: crate::borrow::NbtTag,
+            ) -> Result<Option<Self>, DeserializeError> {
+                Ok(Vec::<T>::try_from_nbt_tag(tag)?.map(|v| v.into_iter().collect()))
+            }
+        }
+        impl<T: Serialize> ToNbtTag for $collection<T> {
+            fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                self.into_iter().collect::<Vec<T>>().to_nbt_tag()
+            }
+        }
+
+        impl FromNbtTag for $collection<String> {
+            fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                Vec::<String>::from_nbt_tag(tag).map(|v| v.into_iter().collect())
+            }
+        }
+        impl ToNbtTag for $collection<String> {
+            fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                self.into_iter().collect::<Vec<String>>().to_nbt_tag()
+            }
+        }
+    };
+}
+impl_nbt_tag_for_collection!(VecDeque);
+impl_nbt_tag_for_collection!(HashSet, Eq, Hash);
+impl_nbt_tag_for_collection!(BTreeSet, Ord);
+
+// tuples are lists with a fixed length. Lists can only have one type of
+// element, so every element of the tuple is the same type.
+macro_rules! impl_nbt_tag_for_tuple {
+    ($($i:tt),+) => {
+        impl<T: FromNbtTag> FromNbtTag for ($(impl_nbt_tag_for_tuple!(@element $i),)+) {
+            fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                let list = tag.list()?;
+                if list.len() != [$($i),+].len() {
+                    return None;
+                }
+                let mut tags = list.iter();
+                Some(($(impl_nbt_tag_for_tuple!(@read $i, tags),)+))
+            }
+        }
+        impl<T: ToNbtTag> ToNbtTag for ($(impl_nbt_tag_for_tuple!(@element $i),)+) {
+            /// # Panics
+            ///
+            /// Panics if `T` is a type like [`NbtTag`](crate::owned::NbtTag)
+            /// that can be converted to different types of tags, and the
+            /// elements aren't all the same type.
+            fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                let tags = vec![$(self.$i.to_nbt_tag()),+];
+                crate::owned::NbtTag::List(
+                    crate::owned::NbtList::from_tags(tags)
+                        .expect("all of the elements in a list must be the same type of tag"),
+                )
+            }
+        }
+    };
+    (@element $i:tt) => { T };
+    (@read $i:tt, $tags:ident) => { T::from_nbt_tag($tags.next()?)? };
+}
+impl_nbt_tag_for_tuple!(0);
+impl_nbt_tag_for_tuple!(0, 1);
+impl_nbt_tag_for_tuple!(0, 1, 2);
+impl_nbt_tag_for_tuple!(0, 1, 2, 3);
+impl_nbt_tag_for_tuple!(0, 1, 2, 3, 4);
+impl_nbt_tag_for_tuple!(0, 1, 2, 3, 4, 5);
+
+impl<T: Deserialize> FromNbtTag for Box<[T]> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Vec::<T>::from_nbt_tag(tag).map(Vec::into_boxed_slice)
+    }
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        Ok(Vec::<T>::try_from_nbt_tag(tag)?.map(Vec::into_boxed_slice))
+    }
+}
+impl<T: Serialize> ToNbtTag for Box<[T]> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        self.into_vec().to_nbt_tag()
+    }
+}
+impl FromNbtTag for Box<[String]> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Vec::<String>::from_nbt_tag(tag).map(Vec::into_boxed_slice)
+    }
+}
+impl ToNbtTag for Box<[String]> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        self.into_vec().to_nbt_tag()
+    }
+}
+
+// smart pointers are read and written like the value they point to
+macro_rules! impl_nbt_tag_for_pointer {
+    ($pointer:ident<$t:ident $(: $bound:path)?>, $into_inner:expr) => {
+        impl<$t: FromNbtTag $(+ $bound)?> FromNbtTag for $pointer<$t> {
+            fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                $t::from_nbt_tag(tag).map($pointer::new)
+            }
+            fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+                $t::from_nbt_tag_lenient(tag).map($pointer::new)
+            }
+            fn try_from_nbt_tag(
+                tag: crate::borrow::NbtTag,
+            ) -> Result<Option<Self>, DeserializeError> {
+                Ok($t::try_from_nbt_tag(tag)?.map($pointer::new))
+            }
+            fn from_optional_nbt_tag(
+                tag: Option<crate::borrow::NbtTag>,
+            ) -> Result<Option<Self>, DeserializeError> {
+                Ok($t::from_optional_nbt_tag(tag)?.map($pointer::new))
+            }
+        }
+        impl<$t: ToNbtTag + Clone $(+ $bound)?> ToNbtTag for $pointer<$t> {
+            fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                $into_inner(self).to_nbt_tag()
+            }
+            fn to_optional_nbt_tag(self) -> Option<crate::owned::NbtTag> {
+                $into_inner(self).to_optional_nbt_tag()
+            }
+            fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+                $into_inner(self).write_named_nbt_tag(name, data)
+            }
+        }
+    };
+}
+impl_nbt_tag_for_pointer!(Rc<T>, Rc::unwrap_or_clone);
+impl_nbt_tag_for_pointer!(Arc<T>, Arc::unwrap_or_clone);
+
+// Box<T> can't have the same generic impl since it would overlap with the one
+// for every T: Deserialize, so boxed compounds go through Deserialize and
+// Serialize and the other types are listed here
+impl<T: Deserialize> Deserialize for Box<T> {
+    fn from_compound(compound: crate::borrow::NbtCompound) -> Result<Self, DeserializeError> {
+        T::from_compound(compound).map(Box::new)
+    }
+}
+impl<T: Serialize> Serialize for Box<T> {
+    fn to_compound(self) -> crate::owned::NbtCompound {
+        (*self).to_compound()
+    }
+    fn write_compound(self, data: &mut Vec<u8>) {
+        (*self).write_compound(data);
+    }
+}
+macro_rules! impl_nbt_tag_for_box {
+    ($($t:ty),*) => {
+        $(
+            impl FromNbtTag for Box<$t> {
+                fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                    <$t>::from_nbt_tag(tag).map(Box::new)
+                }
+                fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+                    <$t>::from_nbt_tag_lenient(tag).map(Box::new)
+                }
+            }
+            impl ToNbtTag for Box<$t> {
+                fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                    (*self).to_nbt_tag()
+                }
+                fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+                    (*self).write_named_nbt_tag(name, data)
+                }
+            }
+        )*
+    };
+}
+impl_nbt_tag_for_box!(
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    u8,
+    u16,
+    u32,
+    u64,
+    bool,
+    String,
+    Vec<String>,
+    Vec<u8>,
+    Vec<i32>,
+    Vec<i64>,
+    Vec<f32>,
+    Vec<f64>,
+    crate::owned::NbtTag
+);
+
 impl FromNbtTag for bool {
     fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
         tag.byte().map(|b| b != 0)
     }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.byte().map(|b| b != 0)
+    }
+    /// Any integer tag is accepted, and it's true if it's not zero.
+    fn from_nbt_tag_lenient(tag: crate::borrow::NbtTag) -> Option<Self> {
+        lenient_integer(tag, LONG_ID).map(|v| v != 0)
+    }
 }
 impl ToNbtTag for bool {
     fn to_nbt_tag(self) -> crate::owned::NbtTag {
         crate::owned::NbtTag::Byte(if self { 1 } else { 0 })
     }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        write_tag_header(data, BYTE_ID, name);
+        data.push(self as u8);
+    }
+}
+
+// int and long arrays with a fixed length, like the 4 ints that UUIDs are
+// stored as
+impl<const N: usize> FromNbtTag for [i32; N] {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let array = tag.int_array_raw()?;
+        if array.len() != N {
+            return None;
+        }
+        let mut values = array.iter();
+        Some(std::array::from_fn(|_| values.next().unwrap()))
+    }
+}
+impl<const N: usize> ToNbtTag for [i32; N] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::IntArray(self.to_vec())
+    }
+}
+impl<const N: usize> FromNbtTag for [i64; N] {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let array = tag.long_array_raw()?;
+        if array.len() != N {
+            return None;
+        }
+        let mut values = array.iter();
+        Some(std::array::from_fn(|_| values.next().unwrap()))
+    }
+}
+impl<const N: usize> ToNbtTag for [i64; N] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::LongArray(self.to_vec())
+    }
+}
+
+// other arrays with a fixed length are read and written like a Vec, and fail
+// to be read if the length is different
+impl<T: Deserialize, const N: usize> FromNbtTag for [T; N] {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Vec::<T>::from_nbt_tag(tag)?.try_into().ok()
+    }
+    fn try_from_nbt_tag(tag: crate::borrow::NbtTag) -> Result<Option<Self>, DeserializeError> {
+        Ok(Vec::<T>::try_from_nbt_tag(tag)?.and_then(|v| v.try_into().ok()))
+    }
+}
+impl<T: Serialize, const N: usize> ToNbtTag for [T; N] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        Vec::from(self).to_nbt_tag()
+    }
+}
+macro_rules! impl_nbt_tag_for_array {
+    ($($t:ty),*) => {
+        $(
+            impl<const N: usize> FromNbtTag for [$t; N] {
+                fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+                    Vec::<$t>::from_nbt_tag(tag)?.try_into().ok()
+                }
+            }
+            impl<const N: usize> ToNbtTag for [$t; N] {
+                fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                    Vec::from(self).to_nbt_tag()
+                }
+            }
+        )*
+    };
+}
+impl_nbt_tag_for_array!(u8, f32, f64, String);
+
+// arrays and lists of numbers with any length
+impl FromNbtTag for Vec<u8> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        tag.byte_array().map(|array| array.to_vec())
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.byte_array().map(|array| array.to_vec())
+    }
+}
+impl ToNbtTag for Vec<u8> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::ByteArray(self)
+    }
+}
+impl FromNbtTag for Cow<'_, [u8]> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        Vec::<u8>::from_nbt_tag(tag).map(Cow::Owned)
+    }
+}
+impl ToNbtTag for Cow<'_, [u8]> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::ByteArray(self.into_owned())
+    }
+}
+impl FromNbtTag for Vec<i32> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        tag.int_array()
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.int_array().map(|array| array.to_vec())
+    }
+}
+impl ToNbtTag for Vec<i32> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::IntArray(self)
+    }
+}
+impl FromNbtTag for Vec<i64> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        tag.long_array()
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        tag.long_array().map(|array| array.to_vec())
+    }
+}
+impl ToNbtTag for Vec<i64> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::LongArray(self)
+    }
+}
+impl FromNbtTag for Vec<f32> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let list = tag.list()?;
+        if list.empty() {
+            return Some(Vec::new());
+        }
+        list.floats()
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        let list = tag.list()?;
+        if list.is_empty() {
+            return Some(Vec::new());
+        }
+        list.floats()
+    }
+}
+impl ToNbtTag for Vec<f32> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(crate::owned::NbtList::Float(self))
+    }
+}
+impl FromNbtTag for Vec<f64> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let list = tag.list()?;
+        if list.empty() {
+            return Some(Vec::new());
+        }
+        list.doubles()
+    }
+    fn from_owned_nbt_tag(tag: &crate::owned::NbtTag) -> Option<Self> {
+        let list = tag.list()?;
+        if list.is_empty() {
+            return Some(Vec::new());
+        }
+        list.doubles()
+    }
+}
+impl ToNbtTag for Vec<f64> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(crate::owned::NbtList::Double(self))
+    }
+}
+
+// timestamps are stored as a long of milliseconds since the unix epoch
+impl FromNbtTag for SystemTime {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let millis = tag.long()?;
+        if millis >= 0 {
+            SystemTime::UNIX_EPOCH.checked_add(Duration::from_millis(millis as u64))
+        } else {
+            SystemTime::UNIX_EPOCH.checked_sub(Duration::from_millis(millis.unsigned_abs()))
+        }
+    }
+}
+impl ToNbtTag for SystemTime {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        let millis = match self.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as i64,
+            Err(err) => -(err.duration().as_millis() as i64),
+        };
+        crate::owned::NbtTag::Long(millis)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl FromNbtTag for uuid::Uuid {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        let [a, b, c, d] = <[i32; 4]>::from_nbt_tag(tag)?;
+        let bits = (a as u32 as u128) << 96
+            | (b as u32 as u128) << 64
+            | (c as u32 as u128) << 32
+            | d as u32 as u128;
+        Some(uuid::Uuid::from_u128(bits))
+    }
+}
+#[cfg(feature = "uuid")]
+impl ToNbtTag for uuid::Uuid {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        let bits = self.as_u128();
+        [
+            (bits >> 96) as i32,
+            (bits >> 64) as i32,
+            (bits >> 32) as i32,
+            bits as i32,
+        ]
+        .to_nbt_tag()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromNbtTag for chrono::DateTime<chrono::Utc> {
+    fn from_nbt_tag(tag: crate::borrow::NbtTag) -> Option<Self> {
+        chrono::DateTime::from_timestamp_millis(tag.long()?)
+    }
+}
+#[cfg(feature = "chrono")]
+impl ToNbtTag for chrono::DateTime<chrono::Utc> {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::Long(self.timestamp_millis())
+    }
 }
 
 impl ToNbtTag for crate::owned::NbtList {
@@ -338,3 +1061,109 @@ impl ToNbtTag for crate::owned::NbtList {
         crate::owned::NbtTag::List(self)
     }
 }
+
+// references, so values that aren't owned can be written without cloning them
+// first
+macro_rules! impl_to_nbt_tag_for_ref {
+    ($($t:ty),*) => {
+        $(
+            impl ToNbtTag for &$t {
+                fn to_nbt_tag(self) -> crate::owned::NbtTag {
+                    (*self).to_nbt_tag()
+                }
+                fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+                    (*self).write_named_nbt_tag(name, data)
+                }
+            }
+        )*
+    };
+}
+impl_to_nbt_tag_for_ref!(i8, i16, i32, i64, f32, f64, u8, u16, u32, u64, bool, SystemTime);
+
+impl ToNbtTag for &String {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        self.as_str().to_nbt_tag()
+    }
+    fn write_named_nbt_tag(self, name: &str, data: &mut Vec<u8>) {
+        self.as_str().write_named_nbt_tag(name, data);
+    }
+}
+impl ToNbtTag for &crate::Mutf8Str {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::String(self.to_owned())
+    }
+}
+impl ToNbtTag for &crate::Mutf8String {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::String(self.clone())
+    }
+}
+
+impl ToNbtTag for &[String] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(crate::owned::NbtList::String(
+            self.iter().map(|s| s.as_str().into()).collect(),
+        ))
+    }
+}
+impl ToNbtTag for &[&str] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(crate::owned::NbtList::String(
+            self.iter().map(|&s| s.into()).collect(),
+        ))
+    }
+}
+impl ToNbtTag for &[crate::owned::NbtCompound] {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(crate::owned::NbtList::Compound(self.to_vec()))
+    }
+}
+
+impl ToNbtTag for &crate::owned::NbtTag {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        self.clone()
+    }
+}
+impl ToNbtTag for &crate::owned::NbtList {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::List(self.clone())
+    }
+}
+impl ToNbtTag for &crate::owned::NbtCompound {
+    fn to_nbt_tag(self) -> crate::owned::NbtTag {
+        crate::owned::NbtTag::Compound(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::owned::{NbtCompound, NbtList, NbtTag};
+
+    #[test]
+    fn references() {
+        let name = "Steve".to_owned();
+        let tags = ["a".to_owned(), "b".to_owned()];
+        let mut item = NbtCompound::new();
+        item.insert("Count", 1i8);
+        let inventory = [item];
+
+        assert_eq!((&20f32).to_nbt_tag(), NbtTag::Float(20.));
+        assert_eq!((&true).to_nbt_tag(), NbtTag::Byte(1));
+        assert_eq!((&name).to_nbt_tag(), NbtTag::String("Steve".into()));
+        assert_eq!(
+            tags[..].to_nbt_tag(),
+            NbtTag::List(NbtList::String(vec!["a".into(), "b".into()]))
+        );
+        assert_eq!(
+            inventory[..].to_nbt_tag(),
+            NbtTag::List(NbtList::Compound(inventory.to_vec()))
+        );
+        assert_eq!(
+            (&inventory[0]).to_nbt_tag(),
+            NbtTag::Compound(inventory[0].clone())
+        );
+        // the values are still usable since they were only borrowed
+        assert_eq!(name, "Steve");
+    }
+}
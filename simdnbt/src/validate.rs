@@ -0,0 +1,111 @@
+//! Checking that NBT is well-formed and skipping over tags without reading
+//! them into anything.
+//!
+//! These are useful when writing a parser by hand with a
+//! [`Reader`], for example to skip fields that you don't know about or to
+//! copy their bytes somewhere else without parsing them.
+//!
+//! ```
+//! use simdnbt::{reader::Reader, validate};
+//!
+//! // an int array with two elements, and then a byte
+//! let data = [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 5];
+//! let mut reader = Reader::new(&data);
+//! let len = validate::measure_tag(&reader, 11)?;
+//! assert_eq!(len, 12);
+//! let raw = reader.read_slice(len)?;
+//! assert_eq!(raw, &data[..12]);
+//! assert_eq!(reader.read_u8()?, 5);
+//! # Ok::<(), simdnbt::Error>(())
+//! ```
+
+use std::io::Cursor;
+
+use crate::{
+    common::{read_string, COMPOUND_ID, END_ID},
+    reader::{Reader, ReaderFromCursor},
+    Error,
+};
+
+/// Move past the payload of a tag with the given ID. The tag ID and name
+/// must've already been read.
+///
+/// Returns an error if the tag isn't valid, in the same cases that the normal
+/// readers would.
+pub fn skip_tag(reader: &mut Reader, tag_type: u8) -> Result<(), Error> {
+    reader.skip_tag(tag_type)
+}
+
+/// Get how many bytes the payload of a tag with the given ID takes up,
+/// without moving the reader. The tag ID and name must've already been read.
+pub fn measure_tag(reader: &Reader, tag_type: u8) -> Result<usize, Error> {
+    let mut end = reader.clone();
+    end.skip_tag(tag_type)?;
+    Ok(end.addr() - reader.addr())
+}
+
+/// Check that the data starts with a valid normal root NBT compound, and move
+/// the cursor past it. This is faster than reading it, since nothing is
+/// allocated.
+///
+/// ```
+/// # use std::io::Cursor;
+/// let data = simdnbt::owned::BaseNbt::new("", simdnbt::owned::NbtCompound::new()).to_bytes();
+/// assert!(simdnbt::validate::validate(&mut Cursor::new(&data)).is_ok());
+/// assert!(simdnbt::validate::validate(&mut Cursor::new(&data[..2])).is_err());
+/// ```
+pub fn validate(data: &mut Cursor<&[u8]>) -> Result<(), Error> {
+    let mut reader = ReaderFromCursor::new(data);
+    let root_type = reader.read_u8()?;
+    if root_type == END_ID {
+        return Ok(());
+    }
+    if root_type != COMPOUND_ID {
+        return Err(Error::InvalidRootType(root_type));
+    }
+    read_string(&mut reader)?;
+    reader.skip_tag(COMPOUND_ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{BYTE_ID, INT_ID, LIST_ID, STRING_ID},
+        test_fixtures::complex_player,
+    };
+
+    #[test]
+    fn measure() {
+        let data = [0, 2, b'h', b'i', 0];
+        let reader = Reader::new(&data);
+        assert_eq!(measure_tag(&reader, STRING_ID), Ok(4));
+        assert_eq!(measure_tag(&reader, BYTE_ID), Ok(1));
+        assert_eq!(measure_tag(&reader, INT_ID), Ok(4));
+        assert_eq!(reader.remaining(), 5);
+        // a list of end tags, which ignores the length
+        assert_eq!(measure_tag(&reader, LIST_ID), Ok(5));
+        assert_eq!(
+            measure_tag(&Reader::new(&data[..3]), STRING_ID),
+            Err(Error::UnexpectedEof)
+        );
+        assert_eq!(measure_tag(&reader, 13), Err(Error::UnknownTagId(13)));
+    }
+
+    #[test]
+    fn validate_files() {
+        let data = complex_player();
+        let mut cursor = Cursor::new(&data[..]);
+        validate(&mut cursor).unwrap();
+        assert_eq!(cursor.position() as usize, data.len());
+
+        // every way of cutting it off should fail
+        for len in 1..data.len() {
+            assert!(validate(&mut Cursor::new(&data[..len])).is_err());
+        }
+        assert_eq!(
+            validate(&mut Cursor::new(&[1][..])),
+            Err(Error::InvalidRootType(1))
+        );
+    }
+}
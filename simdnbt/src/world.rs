@@ -0,0 +1,494 @@
+//! Helpers for finding and reading the NBT files in a Minecraft world folder.
+//!
+//! This handles decompression and the naming conventions of the files, but
+//! most of it doesn't parse anything, so you can read the data with either
+//! [`borrow`](crate::borrow) or [`owned`](crate::owned). Chunks can also be
+//! deserialized straight into a struct with [`RegionFile::read_chunk_as`].
+//!
+//! ```no_run
+//! # use std::io::Cursor;
+//! # use simdnbt::world::{Dimension, World};
+//! let world = World::open("saves/New World")?;
+//! for region in world.regions(Dimension::Overworld)? {
+//!     let region = region?;
+//!     for chunk in region.chunks() {
+//!         let chunk = chunk?;
+//!         let nbt = simdnbt::borrow::read(&mut Cursor::new(&chunk.data)).unwrap();
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::Deserialize;
+
+const SECTOR_SIZE: usize = 4096;
+const CHUNKS_PER_REGION: usize = 32 * 32;
+
+/// A Minecraft world folder.
+#[derive(Debug, Clone)]
+pub struct World {
+    path: PathBuf,
+}
+
+/// A dimension in a world, which is used to find its region files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+    /// A custom dimension from a datapack, like `minecraft:overworld_caves`.
+    Custom(String),
+}
+
+impl World {
+    /// Open the world at the given path. This returns an error if the folder
+    /// doesn't have a `level.dat`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        if !path.join("level.dat").is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} doesn't have a level.dat", path.display()),
+            ));
+        }
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read and decompress the world's `level.dat`.
+    pub fn level_dat(&self) -> io::Result<Vec<u8>> {
+        read_gzip_file(&self.path.join("level.dat"))
+    }
+
+    /// Returns the folder that has the region files for the dimension.
+    pub fn region_dir(&self, dimension: &Dimension) -> PathBuf {
+        match dimension {
+            Dimension::Overworld => self.path.join("region"),
+            Dimension::Nether => self.path.join("DIM-1").join("region"),
+            Dimension::End => self.path.join("DIM1").join("region"),
+            Dimension::Custom(id) => {
+                let (namespace, path) = id.split_once(':').unwrap_or(("minecraft", id));
+                self.path
+                    .join("dimensions")
+                    .join(namespace)
+                    .join(path)
+                    .join("region")
+            }
+        }
+    }
+
+    /// Returns an iterator over every region file in the dimension. The files
+    /// are read when the iterator gets to them.
+    ///
+    /// If the dimension doesn't have a region folder, the iterator is empty.
+    pub fn regions(
+        &self,
+        dimension: Dimension,
+    ) -> io::Result<impl Iterator<Item = io::Result<RegionFile>>> {
+        let paths = files_with_extension(&self.region_dir(&dimension), "mca")?;
+        Ok(paths
+            .into_iter()
+            .filter(|path| parse_region_file_name(path).is_some())
+            .map(|path| RegionFile::open(&path)))
+    }
+
+    /// Returns an iterator over the `.dat` files in the `playerdata` folder.
+    pub fn players(&self) -> io::Result<impl Iterator<Item = PlayerFile>> {
+        let paths = files_with_extension(&self.path.join("playerdata"), "dat")?;
+        Ok(paths.into_iter().map(|path| PlayerFile {
+            uuid: path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            path,
+        }))
+    }
+}
+
+/// A player's `.dat` file. This is returned by [`World::players`].
+#[derive(Debug, Clone)]
+pub struct PlayerFile {
+    /// The player's UUID, which is the name of the file.
+    pub uuid: String,
+    pub path: PathBuf,
+}
+impl PlayerFile {
+    /// Read and decompress the file.
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        read_gzip_file(&self.path)
+    }
+}
+
+/// A region file (`r.<x>.<z>.mca`) that's been loaded into memory.
+#[derive(Debug, Clone)]
+pub struct RegionFile {
+    /// The x coordinate of the region. This is the chunk x divided by 32.
+    pub x: i32,
+    /// The z coordinate of the region. This is the chunk z divided by 32.
+    pub z: i32,
+    path: PathBuf,
+    data: Vec<u8>,
+}
+
+/// A chunk from a [`RegionFile`].
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// The absolute x coordinate of the chunk.
+    pub x: i32,
+    /// The absolute z coordinate of the chunk.
+    pub z: i32,
+    /// When the chunk was last saved, in seconds since the Unix epoch.
+    pub timestamp: u32,
+    /// The decompressed NBT data of the chunk.
+    pub data: Vec<u8>,
+}
+
+impl RegionFile {
+    /// Read the region file at the path. The name of the file must look like
+    /// `r.<x>.<z>.mca`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let (x, z) = parse_region_file_name(path).ok_or_else(|| {
+            invalid_data(format!("{} isn't a valid region file name", path.display()))
+        })?;
+        let data = fs::read(path)?;
+        // empty region files are sometimes left behind and are fine
+        if !data.is_empty() && data.len() < SECTOR_SIZE * 2 {
+            return Err(invalid_data("region file is too short for its header"));
+        }
+        Ok(Self {
+            x,
+            z,
+            path: path.to_owned(),
+            data,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read and decompress the chunk at the given coordinates relative to the
+    /// region, which must be between 0 and 31. Returns `Ok(None)` if the chunk
+    /// hasn't been generated.
+    pub fn chunk(&self, local_x: usize, local_z: usize) -> io::Result<Option<Chunk>> {
+        assert!(
+            local_x < 32 && local_z < 32,
+            "chunk coordinates out of range"
+        );
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+        let index = local_x + local_z * 32;
+        let header = &self.data[index * 4..index * 4 + 4];
+        let sector_offset = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let sector_count = header[3] as usize;
+        if sector_offset == 0 && sector_count == 0 {
+            return Ok(None);
+        }
+
+        let timestamp_start = SECTOR_SIZE + index * 4;
+        let timestamp = u32::from_be_bytes(
+            self.data[timestamp_start..timestamp_start + 4]
+                .try_into()
+                .unwrap(),
+        );
+
+        let outside = || invalid_data("chunk is outside of the region file");
+        // the header can't be trusted, so this might overflow on 32-bit targets
+        let start = sector_offset.checked_mul(SECTOR_SIZE).ok_or_else(outside)?;
+        let end = start
+            .checked_add(sector_count * SECTOR_SIZE)
+            .ok_or_else(outside)?;
+        let sectors = self
+            .data
+            .get(start..end)
+            // the last sector isn't always padded
+            .or_else(|| self.data.get(start..))
+            .filter(|sectors| sectors.len() >= 5)
+            .ok_or_else(outside)?;
+        let length = u32::from_be_bytes(sectors[..4].try_into().unwrap()) as usize;
+        if length == 0 || length > sectors.len() - 4 {
+            return Err(invalid_data("chunk length is invalid"));
+        }
+        let compression = sectors[4];
+        let x = self.x * 32 + local_x as i32;
+        let z = self.z * 32 + local_z as i32;
+
+        let external_data;
+        let compressed = if compression & 0x80 != 0 {
+            // the chunk was too big for the region file so it's stored separately
+            let external_path = self.path.with_file_name(format!("c.{x}.{z}.mcc"));
+            external_data = fs::read(external_path)?;
+            &external_data[..]
+        } else {
+            &sectors[5..length + 4]
+        };
+
+        let data = decompress(compression & 0x7f, compressed)?;
+        Ok(Some(Chunk {
+            x,
+            z,
+            timestamp,
+            data,
+        }))
+    }
+
+    /// Read the chunk at the given coordinates relative to the region and
+    /// deserialize it into `T`. The chunk is only parsed with
+    /// [`borrow`](crate::borrow), so no owned tree is built.
+    ///
+    /// Returns `Ok(None)` if the chunk hasn't been generated. Invalid NBT and
+    /// failed deserialization are returned as [`io::ErrorKind::InvalidData`].
+    pub fn read_chunk_as<T: Deserialize>(
+        &self,
+        local_x: usize,
+        local_z: usize,
+    ) -> io::Result<Option<T>> {
+        self.chunk(local_x, local_z)?
+            .map(|chunk| chunk.read_as())
+            .transpose()
+    }
+
+    /// Returns an iterator over every chunk in the region that's been
+    /// generated.
+    pub fn chunks(&self) -> impl Iterator<Item = io::Result<Chunk>> + '_ {
+        (0..CHUNKS_PER_REGION).filter_map(|index| self.chunk(index % 32, index / 32).transpose())
+    }
+
+    /// Returns a parallel iterator over every chunk in the region that's been
+    /// generated, which decompresses the chunks on the rayon thread pool. See
+    /// [`Self::chunks`].
+    ///
+    /// Use [`par::map_many`](crate::par::map_many) to parse many chunks in
+    /// parallel once they're decompressed.
+    #[cfg(feature = "rayon")]
+    pub fn par_chunks(&self) -> impl rayon::iter::ParallelIterator<Item = io::Result<Chunk>> + '_ {
+        use rayon::prelude::*;
+
+        (0..CHUNKS_PER_REGION)
+            .into_par_iter()
+            .filter_map(|index| self.chunk(index % 32, index / 32).transpose())
+    }
+}
+
+impl Chunk {
+    /// Deserialize the chunk's data into `T`. See
+    /// [`RegionFile::read_chunk_as`].
+    pub fn read_as<T: Deserialize>(&self) -> io::Result<T> {
+        let nbt = crate::borrow::read(&mut Cursor::new(&self.data))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let nbt = match nbt {
+            crate::borrow::Nbt::Some(nbt) => nbt,
+            crate::borrow::Nbt::None => return Err(invalid_data("chunk is empty")),
+        };
+        T::from_nbt(&nbt).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn decompress(compression: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match compression {
+        1 => GzDecoder::new(data).read_to_end(&mut decompressed)?,
+        2 => ZlibDecoder::new(data).read_to_end(&mut decompressed)?,
+        3 => return Ok(data.to_vec()),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unsupported chunk compression type {compression}"),
+            ))
+        }
+    };
+    Ok(decompressed)
+}
+
+fn read_gzip_file(path: &Path) -> io::Result<Vec<u8>> {
+    let compressed = fs::read(path)?;
+    let mut data = Vec::new();
+    GzDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Returns the paths of the files in the directory with the extension, sorted
+/// by name. If the directory doesn't exist, an empty list is returned.
+fn files_with_extension(dir: &Path, extension: &str) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == extension) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn parse_region_file_name(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((x, z))
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+    use crate::owned::{BaseNbt, NbtCompound};
+
+    #[derive(Debug)]
+    struct ChunkPos {
+        x: i32,
+        z: i32,
+    }
+    impl Deserialize for ChunkPos {
+        fn from_compound(
+            compound: crate::borrow::NbtCompound,
+        ) -> Result<Self, crate::DeserializeError> {
+            Ok(Self {
+                x: compound
+                    .int("xPos")
+                    .ok_or(crate::DeserializeError::MissingField)?,
+                z: compound
+                    .int("zPos")
+                    .ok_or(crate::DeserializeError::MissingField)?,
+            })
+        }
+    }
+
+    fn chunk_nbt(x: i32, z: i32) -> Vec<u8> {
+        let mut compound = NbtCompound::new();
+        compound.insert("xPos", x);
+        compound.insert("zPos", z);
+        let mut data = Vec::new();
+        BaseNbt::new("", compound).write(&mut data);
+        data
+    }
+
+    /// Make a region file with chunks at the given local coordinates.
+    fn region_file(region_x: i32, region_z: i32, chunks: &[(usize, usize)]) -> Vec<u8> {
+        let mut data = vec![0; SECTOR_SIZE * 2];
+        for &(local_x, local_z) in chunks {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(&chunk_nbt(
+                    region_x * 32 + local_x as i32,
+                    region_z * 32 + local_z as i32,
+                ))
+                .unwrap();
+            let compressed = encoder.finish().unwrap();
+
+            let index = local_x + local_z * 32;
+            let sector = data.len() / SECTOR_SIZE;
+            data[index * 4..index * 4 + 4]
+                .copy_from_slice(&((sector as u32) << 8 | 1).to_be_bytes());
+            data[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4]
+                .copy_from_slice(&1234u32.to_be_bytes());
+
+            let mut sector_data = Vec::new();
+            sector_data.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+            sector_data.push(2);
+            sector_data.extend_from_slice(&compressed);
+            sector_data.resize(SECTOR_SIZE, 0);
+            data.extend_from_slice(&sector_data);
+        }
+        data
+    }
+
+    #[test]
+    fn read_world() {
+        let path = std::env::temp_dir().join(format!("simdnbt-world-{}", std::process::id()));
+        fs::create_dir_all(path.join("region")).unwrap();
+        fs::create_dir_all(path.join("playerdata")).unwrap();
+        fs::write(path.join("level.dat"), include_bytes!("../tests/level.dat")).unwrap();
+        fs::write(
+            path.join("playerdata/8b6d4f0e-0000-0000-0000-000000000000.dat"),
+            include_bytes!("../tests/complex_player.dat"),
+        )
+        .unwrap();
+        fs::write(
+            path.join("region/r.-1.2.mca"),
+            region_file(-1, 2, &[(0, 0), (5, 31)]),
+        )
+        .unwrap();
+        fs::write(path.join("region/r.0.0.mca"), []).unwrap();
+        fs::write(path.join("region/notes.txt"), "hi").unwrap();
+
+        let world = World::open(&path).unwrap();
+        let level = world.level_dat().unwrap();
+        assert!(crate::borrow::read(&mut Cursor::new(&level))
+            .unwrap()
+            .is_some());
+
+        let players = world.players().unwrap().collect::<Vec<_>>();
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].uuid, "8b6d4f0e-0000-0000-0000-000000000000");
+        assert!(players[0].read().unwrap().len() > 100);
+
+        let regions = world
+            .regions(Dimension::Overworld)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(regions.len(), 2);
+        let region = &regions[0];
+        assert_eq!((region.x, region.z), (-1, 2));
+        let chunks = region.chunks().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(chunks.len(), 2);
+        for chunk in &chunks {
+            assert_eq!(chunk.data, chunk_nbt(chunk.x, chunk.z));
+            assert_eq!(chunk.timestamp, 1234);
+        }
+        assert_eq!((chunks[1].x, chunks[1].z), (-32 + 5, 64 + 31));
+        assert!(region.chunk(1, 0).unwrap().is_none());
+        assert_eq!(regions[1].chunks().count(), 0);
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::iter::ParallelIterator;
+
+            let par_chunks = region.par_chunks().collect::<io::Result<Vec<_>>>().unwrap();
+            assert_eq!(par_chunks.len(), 2);
+            assert_eq!(par_chunks[1].data, chunks[1].data);
+        }
+
+        assert_eq!(world.regions(Dimension::Nether).unwrap().count(), 0);
+
+        let chunk = region.read_chunk_as::<ChunkPos>(5, 31).unwrap().unwrap();
+        assert_eq!((chunk.x, chunk.z), (-32 + 5, 64 + 31));
+        assert!(region.read_chunk_as::<ChunkPos>(1, 0).unwrap().is_none());
+        let mut invalid = chunks[0].clone();
+        invalid.data.truncate(5);
+        let err = invalid.read_as::<ChunkPos>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
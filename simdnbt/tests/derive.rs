@@ -0,0 +1,983 @@
+#![cfg(feature = "derive")]
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet, VecDeque},
+    io::Cursor,
+    rc::Rc,
+    sync::Arc,
+};
+
+use simdnbt::{owned, Deserialize, FromNbtTag, RawNbtValue, Serialize, ToNbtTag};
+
+fn roundtrip<T: ToNbtTag + FromNbtTag>(value: T) -> Option<T> {
+    from_tag(value.to_nbt_tag())
+}
+
+/// Write the tag and read it back as a borrowed tag so it can be passed to
+/// `FromNbtTag::from_nbt_tag`.
+fn from_tag<T: FromNbtTag>(tag: owned::NbtTag) -> Option<T> {
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("value", tag);
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", compound).write(&mut data);
+
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+    T::from_nbt_tag(nbt.get("value").unwrap())
+}
+
+/// The error for when `error` happened at `path` in the compound that was
+/// being deserialized.
+fn at(path: &str, error: simdnbt::DeserializeError) -> simdnbt::DeserializeError {
+    simdnbt::DeserializeError::At {
+        path: path.parse().unwrap(),
+        error: Box::new(error),
+    }
+}
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+enum WoodType {
+    Oak,
+    #[simdnbt(rename = "dark_oak")]
+    DarkOak,
+    #[simdnbt(other)]
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+enum Biome {
+    Plains,
+    #[simdnbt(other)]
+    Other(String),
+}
+
+#[test]
+fn other_unit_variant() {
+    assert_eq!(roundtrip(WoodType::Oak), Some(WoodType::Oak));
+    assert_eq!(roundtrip(WoodType::DarkOak), Some(WoodType::DarkOak));
+    assert_eq!(
+        from_tag::<WoodType>(owned::NbtTag::String("pale_oak".into())),
+        Some(WoodType::Unknown)
+    );
+    assert_eq!(from_tag::<WoodType>(owned::NbtTag::Int(0)), None);
+}
+
+#[test]
+fn other_variant_with_value() {
+    assert_eq!(roundtrip(Biome::Plains), Some(Biome::Plains));
+    assert_eq!(
+        from_tag::<Biome>(owned::NbtTag::String("cherry_grove".into())),
+        Some(Biome::Other("cherry_grove".to_owned()))
+    );
+    assert_eq!(
+        Biome::Other("cherry_grove".to_owned()).to_nbt_tag(),
+        owned::NbtTag::String("cherry_grove".into())
+    );
+}
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+#[simdnbt(case_insensitive)]
+enum CaseInsensitiveWoodType {
+    Birch,
+    #[simdnbt(rename = "dark_oak")]
+    DarkOak,
+    #[simdnbt(other)]
+    Other(String),
+}
+
+#[test]
+fn case_insensitive() {
+    for name in ["birch", "Birch", "BIRCH"] {
+        assert_eq!(
+            from_tag::<CaseInsensitiveWoodType>(owned::NbtTag::String(name.into())),
+            Some(CaseInsensitiveWoodType::Birch)
+        );
+    }
+    assert_eq!(
+        from_tag::<CaseInsensitiveWoodType>(owned::NbtTag::String("Dark_Oak".into())),
+        Some(CaseInsensitiveWoodType::DarkOak)
+    );
+    // the original casing is kept for unknown values
+    assert_eq!(
+        from_tag::<CaseInsensitiveWoodType>(owned::NbtTag::String("Pale_Oak".into())),
+        Some(CaseInsensitiveWoodType::Other("Pale_Oak".to_owned()))
+    );
+    assert_eq!(
+        CaseInsensitiveWoodType::Birch.to_nbt_tag(),
+        owned::NbtTag::String("Birch".into())
+    );
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Item {
+    id: String,
+    tag: RawNbtValue,
+}
+
+#[test]
+fn raw_value() {
+    let mut tag = owned::NbtCompound::new();
+    tag.insert("Damage", 3);
+    let mut item = owned::NbtCompound::new();
+    item.insert("id", "minecraft:diamond_sword");
+    item.insert("tag", tag.clone());
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", item.clone()).write(&mut data);
+
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+    let deserialized = Item::from_nbt(&nbt).unwrap();
+    assert_eq!(
+        deserialized.tag.id(),
+        simdnbt::owned::NbtTag::Compound(tag.clone()).id()
+    );
+    assert_eq!(
+        deserialized.tag.parse().unwrap(),
+        owned::NbtTag::Compound(tag)
+    );
+    assert_eq!(deserialized.to_compound(), item);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+struct Slot {
+    count: i8,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Collections {
+    queue: VecDeque<Slot>,
+    set: HashSet<Slot>,
+    sorted: BTreeSet<String>,
+    boxed: Box<[Slot]>,
+}
+
+#[test]
+fn collections() {
+    let value = Collections {
+        queue: VecDeque::from([Slot { count: 1 }, Slot { count: 2 }]),
+        set: HashSet::from([Slot { count: 3 }]),
+        sorted: BTreeSet::from(["b".to_owned(), "a".to_owned()]),
+        boxed: vec![Slot { count: 4 }].into_boxed_slice(),
+    };
+    let compound = value.to_compound();
+    assert_eq!(
+        compound.list("sorted"),
+        Some(&owned::NbtList::String(vec!["a".into(), "b".into()]))
+    );
+
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", compound).write(&mut data);
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+    let read = Collections::from_nbt(&nbt).unwrap();
+    assert_eq!(read.queue, [Slot { count: 1 }, Slot { count: 2 }]);
+    assert!(read.set.contains(&Slot { count: 3 }));
+    assert_eq!(&*read.boxed, [Slot { count: 4 }]);
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Sign {
+    text: simdnbt::Mutf8String,
+    lossy: String,
+}
+
+#[test]
+fn invalid_strings_are_preserved() {
+    // a lone surrogate isn't valid mutf-8
+    let invalid = simdnbt::Mutf8String::from_vec(vec![b'a', 0xed, 0xa0, 0x80]);
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("text", invalid.clone());
+    compound.insert("lossy", invalid.clone());
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", compound).write(&mut data);
+
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+    let sign = Sign::from_nbt(&nbt).unwrap();
+    assert_eq!(sign.text, invalid);
+    assert_eq!(sign.lossy, "");
+
+    let compound = sign.to_compound();
+    assert_eq!(
+        compound.string("text").unwrap().as_bytes(),
+        invalid.as_bytes()
+    );
+}
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+#[simdnbt(case_insensitive)]
+enum Grass {
+    #[simdnbt(rename = "short_grass")]
+    #[simdnbt(alias = "grass")]
+    #[simdnbt(alias = "tall_grass_bottom")]
+    Short,
+    Fern,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Entity {
+    #[simdnbt(rename = "Health")]
+    #[simdnbt(alias = "HealF")]
+    #[simdnbt(alias = "HealthF")]
+    health: f32,
+    grass: Grass,
+}
+
+#[test]
+fn aliases() {
+    for name in ["short_grass", "Grass", "tall_grass_bottom"] {
+        assert_eq!(
+            from_tag::<Grass>(owned::NbtTag::String(name.into())),
+            Some(Grass::Short)
+        );
+    }
+    assert_eq!(
+        from_tag::<Grass>(owned::NbtTag::String("tall".into())),
+        None
+    );
+    // the primary name is always used when writing
+    assert_eq!(
+        Grass::Short.to_nbt_tag(),
+        owned::NbtTag::String("short_grass".into())
+    );
+
+    for (key, value) in [("Health", 1.), ("HealF", 2.), ("HealthF", 3.)] {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert(key, value as f32);
+        compound.insert("grass", "grass");
+        let mut data = Vec::new();
+        owned::BaseNbt::new("", compound).write(&mut data);
+        let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+            .unwrap()
+            .unwrap();
+        let entity = Entity::from_nbt(&nbt).unwrap();
+        assert_eq!(
+            entity,
+            Entity {
+                health: value as f32,
+                grass: Grass::Short
+            }
+        );
+        assert!(entity.to_compound().contains("Health"));
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Stack {
+    #[simdnbt(rename = "Count", alias = "count", alias = "amount")]
+    count: i8,
+}
+
+#[test]
+fn entries_separated_by_commas() {
+    for key in ["Count", "count", "amount"] {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert(key, 3i8);
+        assert_eq!(from_compound::<Stack>(compound), Ok(Stack { count: 3 }));
+    }
+    assert_eq!(Stack { count: 3 }.to_compound().byte("Count"), Some(3));
+}
+
+/// Write the compound and read it back with `T::from_nbt`.
+fn from_compound<T: Deserialize>(
+    compound: owned::NbtCompound,
+) -> Result<T, simdnbt::DeserializeError> {
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", compound).write(&mut data);
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+    T::from_nbt(&nbt)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Trim {
+    pattern: String,
+    #[simdnbt(alias = "mat")]
+    material: String,
+    show_in_tooltip: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Durability {
+    damage: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Armor {
+    id: String,
+    #[simdnbt(flatten)]
+    durability: Durability,
+    #[simdnbt(flatten)]
+    trim: Option<Trim>,
+}
+
+#[test]
+fn flatten_option() {
+    let armor = Armor {
+        id: "minecraft:iron_helmet".to_owned(),
+        durability: Durability { damage: 3 },
+        trim: Some(Trim {
+            pattern: "coast".to_owned(),
+            material: "gold".to_owned(),
+            show_in_tooltip: None,
+        }),
+    };
+    let compound = armor.clone().to_compound();
+    assert_eq!(
+        compound,
+        simdnbt::compound! {
+            "id": "minecraft:iron_helmet",
+            "damage": 3,
+            "pattern": "coast",
+            "material": "gold",
+        }
+    );
+    let mut data = Vec::new();
+    armor.clone().write_nbt(&mut data);
+    assert_eq!(data, armor.clone().to_nbt().to_bytes());
+    assert_eq!(from_compound::<Armor>(compound), Ok(armor));
+
+    // none of the fields are there
+    let compound = simdnbt::compound! { "id": "minecraft:iron_helmet", "damage": 0 };
+    assert_eq!(from_compound::<Armor>(compound).unwrap().trim, None);
+
+    // some of the fields are there, so the rest are required
+    let compound = simdnbt::compound! { "id": "a", "damage": 0, "mat": "gold" };
+    assert_eq!(
+        from_compound::<Armor>(compound),
+        Err(at("pattern", simdnbt::DeserializeError::MissingField))
+    );
+    // the fields that aren't flattened are still required
+    let compound = simdnbt::compound! { "id": "a", "pattern": "coast", "material": "gold" };
+    assert_eq!(
+        from_compound::<Armor>(compound),
+        Err(at("damage", simdnbt::DeserializeError::MissingField))
+    );
+}
+
+#[test]
+fn ordered_maps() {
+    use std::collections::BTreeMap;
+
+    let compound = simdnbt::compound! { "b": 2, "a": 1, "c": 3 };
+    let map = from_compound::<BTreeMap<String, i32>>(compound.clone()).unwrap();
+    assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+    let keys = map
+        .to_compound()
+        .keys()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["a", "b", "c"]);
+
+    let map = from_compound::<BTreeMap<u8, i32>>(simdnbt::compound! { "10": 1, "9": 2 }).unwrap();
+    assert_eq!(map.into_iter().collect::<Vec<_>>(), [(9, 2), (10, 1)]);
+    assert!(from_compound::<BTreeMap<u8, i32>>(compound.clone()).is_err());
+
+    #[cfg(feature = "indexmap")]
+    {
+        let map = from_compound::<indexmap::IndexMap<String, i32>>(compound.clone()).unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(map.to_compound(), compound);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[simdnbt(tag = "type")]
+enum Component {
+    #[simdnbt(rename = "minecraft:damage")]
+    Damage { amount: i32 },
+    #[simdnbt(rename = "minecraft:slot")]
+    Slot(Slot),
+    #[simdnbt(rename = "minecraft:unbreakable")]
+    #[simdnbt(alias = "unbreakable")]
+    Unbreakable,
+}
+
+#[test]
+fn internally_tagged_enum() {
+    let compound = Component::Damage { amount: 2 }.to_compound();
+    assert_eq!(
+        compound.string("type").unwrap().to_str(),
+        "minecraft:damage"
+    );
+    assert_eq!(compound.int("amount"), Some(2));
+
+    for value in [
+        Component::Damage { amount: 2 },
+        Component::Slot(Slot { count: 3 }),
+        Component::Unbreakable,
+    ] {
+        let compound = value.clone().to_compound();
+        assert_eq!(from_compound::<Component>(compound), Ok(value));
+    }
+
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("type", "unbreakable");
+    assert_eq!(from_compound(compound), Ok(Component::Unbreakable));
+
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("type", "minecraft:unknown");
+    assert_eq!(
+        from_compound::<Component>(compound),
+        Err(simdnbt::DeserializeError::UnknownVariant(
+            "minecraft:unknown".to_owned()
+        ))
+    );
+    assert_eq!(
+        from_compound::<Component>(owned::NbtCompound::new()),
+        Err(simdnbt::DeserializeError::MissingField)
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+enum Shape {
+    Circle { radius: f32 },
+    Named(String),
+    Point,
+}
+
+#[test]
+fn externally_tagged_enum() {
+    let compound = Shape::Circle { radius: 1.5 }.to_compound();
+    assert_eq!(
+        compound.compound("Circle").unwrap().float("radius"),
+        Some(1.5)
+    );
+
+    for value in [
+        Shape::Circle { radius: 1.5 },
+        Shape::Named("square".to_owned()),
+        Shape::Point,
+    ] {
+        let compound = value.clone().to_compound();
+        assert_eq!(from_compound::<Shape>(compound), Ok(value));
+    }
+
+    // the variant has to be the only key
+    let mut compound = Shape::Point.to_compound();
+    compound.insert("Named", "square");
+    assert!(from_compound::<Shape>(compound).is_err());
+}
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+#[simdnbt(transparent)]
+struct BlockPos(i64);
+
+#[derive(Debug, PartialEq, FromNbtTag, ToNbtTag)]
+struct Motion(f64, f64, f64);
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Player {
+    pos: BlockPos,
+    #[simdnbt(rename = "Motion")]
+    motion: Motion,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Wrapper(Player);
+
+#[test]
+fn tuple_structs() {
+    assert_eq!(BlockPos(5).to_nbt_tag(), owned::NbtTag::Long(5));
+    assert_eq!(roundtrip(BlockPos(5)), Some(BlockPos(5)));
+    assert_eq!(
+        Motion(0., 1., 2.).to_nbt_tag(),
+        owned::NbtTag::List(owned::NbtList::Double(vec![0., 1., 2.]))
+    );
+    assert_eq!(roundtrip(Motion(0., 1., 2.)), Some(Motion(0., 1., 2.)));
+    // the list has to have exactly the right length
+    for doubles in [vec![0., 1.], vec![0., 1., 2., 3.]] {
+        assert_eq!(
+            from_tag::<Motion>(owned::NbtTag::List(owned::NbtList::Double(doubles))),
+            None
+        );
+    }
+
+    // newtypes around compounds are transparent
+    let player = Player {
+        pos: BlockPos(1),
+        motion: Motion(0., 0., 0.),
+    };
+    let compound = Wrapper(player).to_compound();
+    assert_eq!(compound.long("pos"), Some(1));
+    assert_eq!(
+        from_compound::<Wrapper>(compound),
+        Ok(Wrapper(Player {
+            pos: BlockPos(1),
+            motion: Motion(0., 0., 0.),
+        }))
+    );
+}
+
+/// Four 16-bit numbers packed into each long.
+mod packed_shorts {
+    use simdnbt::{borrow, owned};
+
+    pub fn from_nbt_tag(tag: borrow::NbtTag) -> Option<Vec<u16>> {
+        let longs = tag.long_array()?;
+        Some(
+            longs
+                .into_iter()
+                .flat_map(|long| (0..4).map(move |i| (long as u64 >> (i * 16)) as u16))
+                .collect(),
+        )
+    }
+
+    pub fn to_nbt_tag(values: Vec<u16>) -> owned::NbtTag {
+        owned::NbtTag::LongArray(
+            values
+                .chunks(4)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |long, (i, &value)| long | (value as u64) << (i * 16))
+                        as i64
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Heightmap {
+    #[simdnbt(with = "packed_shorts")]
+    heights: Vec<u16>,
+}
+
+#[test]
+fn with_module() {
+    let heightmap = Heightmap {
+        heights: vec![1, 2, 3, 4, 5, 6, 7, 8],
+    };
+    let compound = heightmap.to_compound();
+    assert_eq!(
+        compound.long_array("heights"),
+        Some(&[0x0004_0003_0002_0001, 0x0008_0007_0006_0005][..])
+    );
+    assert_eq!(
+        from_compound::<Heightmap>(compound),
+        Ok(Heightmap {
+            heights: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        })
+    );
+
+    assert_eq!(
+        from_compound::<Heightmap>(owned::NbtCompound::new()),
+        Err(at("heights", simdnbt::DeserializeError::MissingField))
+    );
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("heights", 1);
+    assert_eq!(
+        from_compound::<Heightmap>(compound),
+        Err(at(
+            "heights",
+            simdnbt::DeserializeError::MismatchedFieldType("Heightmap::heights".to_owned())
+        ))
+    );
+}
+
+#[test]
+fn deserialize_sub_compound() {
+    let mut inner = owned::NbtCompound::new();
+    inner.insert("count", 3i8);
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("slot", inner);
+    compound.insert("empty", owned::NbtCompound::new());
+    compound.insert("count", 1i8);
+    let mut data = Vec::new();
+    owned::BaseNbt::new("", compound).write(&mut data);
+    let nbt = simdnbt::borrow::read(&mut Cursor::new(&data))
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(nbt.as_compound().compound_as("slot"), Ok(Slot { count: 3 }));
+    assert_eq!(nbt.as_compound().deserialize(), Ok(Slot { count: 1 }));
+    assert_eq!(
+        nbt.as_compound().compound_as::<Slot>("empty"),
+        Err(at("empty.count", simdnbt::DeserializeError::MissingField))
+    );
+    assert_eq!(
+        nbt.as_compound().compound_as::<Slot>("count"),
+        Err(at(
+            "count",
+            simdnbt::DeserializeError::MismatchedFieldType("count".to_owned())
+        ))
+    );
+    assert_eq!(
+        nbt.as_compound().compound_as::<Slot>("missing"),
+        Err(at("missing", simdnbt::DeserializeError::MissingField))
+    );
+}
+
+#[derive(Deserialize, Serialize)]
+struct Packet {
+    #[simdnbt(rename = "Name")]
+    name: String,
+    on_ground: bool,
+    port: u16,
+    health: Option<f32>,
+    missing: Option<i64>,
+    slot: Slot,
+    slots: Vec<Slot>,
+    tags: Vec<String>,
+    heightmap: Heightmap,
+    grass: Grass,
+    raw: owned::NbtTag,
+}
+
+#[test]
+fn write_without_owned_compound() {
+    let packet = || Packet {
+        name: "Steve".to_owned(),
+        on_ground: true,
+        port: 25565,
+        health: Some(20.),
+        missing: None,
+        slot: Slot { count: 1 },
+        slots: vec![Slot { count: 2 }, Slot { count: 3 }],
+        tags: vec!["a".to_owned()],
+        heightmap: Heightmap {
+            heights: vec![1, 2, 3, 4],
+        },
+        grass: Grass::Fern,
+        raw: owned::NbtTag::IntArray(vec![1, 2]),
+    };
+
+    let mut data = Vec::new();
+    packet().write_nbt(&mut data);
+    assert_eq!(data, packet().to_nbt().to_bytes());
+}
+
+#[test]
+fn fixed_length_arrays() {
+    assert_eq!(roundtrip([1, -2, 3, 4]), Some([1, -2, 3, 4]));
+    assert_eq!(roundtrip([i64::MAX, 0]), Some([i64::MAX, 0]));
+    assert_eq!(
+        from_tag::<[i32; 4]>(owned::NbtTag::IntArray(vec![1, 2, 3])),
+        None
+    );
+    assert_eq!(from_tag::<[i64; 1]>(owned::NbtTag::IntArray(vec![1])), None);
+}
+
+#[test]
+fn variable_length_arrays() {
+    assert_eq!(
+        vec![1u8, 2].to_nbt_tag(),
+        owned::NbtTag::ByteArray(vec![1, 2])
+    );
+    assert_eq!(roundtrip(vec![1u8, 255]), Some(vec![1, 255]));
+    assert_eq!(roundtrip(vec![1, -2, 3]), Some(vec![1, -2, 3]));
+    assert_eq!(roundtrip(vec![i64::MIN]), Some(vec![i64::MIN]));
+    // ints and longs are arrays, but floats and doubles are lists
+    assert_eq!(
+        from_tag::<Vec<i32>>(owned::NbtTag::List(vec![1, 2].into())),
+        None
+    );
+    assert_eq!(
+        vec![1.5f32].to_nbt_tag(),
+        owned::NbtTag::List(owned::NbtList::Float(vec![1.5]))
+    );
+    assert_eq!(roundtrip(vec![1.5f32, -2.]), Some(vec![1.5, -2.]));
+    assert_eq!(roundtrip(vec![0.25f64]), Some(vec![0.25]));
+    assert_eq!(
+        from_tag::<Vec<f64>>(owned::NbtTag::List(owned::NbtList::Empty)),
+        Some(Vec::new())
+    );
+    assert_eq!(
+        from_tag::<Vec<f32>>(owned::NbtTag::List(vec![1., 2.].into())),
+        None
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Passenger {
+    id: Rc<String>,
+    pos: [f64; 3],
+    rotation: (f32, f32),
+    tags: Arc<Vec<String>>,
+    data: Cow<'static, [u8]>,
+    health: Box<f32>,
+    passenger: Option<Box<Passenger>>,
+}
+
+#[test]
+fn more_std_types() {
+    let value = Passenger {
+        id: Rc::new("minecraft:pig".to_owned()),
+        pos: [1.5, 64., -3.],
+        rotation: (90., 0.),
+        tags: Arc::new(vec!["a".to_owned()]),
+        data: Cow::Borrowed(&[1, 2, 3]),
+        health: Box::new(10.),
+        passenger: Some(Box::new(Passenger {
+            id: Rc::new("minecraft:zombie".to_owned()),
+            pos: [0.; 3],
+            rotation: (0., 0.),
+            tags: Arc::default(),
+            data: Cow::Borrowed(&[]),
+            health: Box::new(20.),
+            passenger: None,
+        })),
+    };
+    let compound = value.clone().to_compound();
+    assert_eq!(
+        compound.list("pos"),
+        Some(&owned::NbtList::Double(vec![1.5, 64., -3.]))
+    );
+    assert_eq!(
+        compound.list("rotation"),
+        Some(&owned::NbtList::Float(vec![90., 0.]))
+    );
+    assert_eq!(compound.byte_array("data"), Some(&[1, 2, 3][..]));
+    assert_eq!(roundtrip(value.clone()), Some(value));
+
+    assert_eq!(roundtrip((1i8, 2i8, 3i8)), Some((1, 2, 3)));
+    assert_eq!(
+        from_tag::<[f64; 2]>(owned::NbtTag::List(vec![1., 2., 3.].into())),
+        None
+    );
+    assert_eq!(
+        from_tag::<(i32, i32)>(owned::NbtTag::List(vec![1, 2, 3].into())),
+        None
+    );
+}
+
+#[test]
+fn system_time() {
+    use std::time::{Duration, SystemTime};
+
+    let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    assert_eq!(time.to_nbt_tag(), owned::NbtTag::Long(1_700_000_000_123));
+    assert_eq!(roundtrip(time), Some(time));
+    let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_millis(5);
+    assert_eq!(roundtrip(before_epoch), Some(before_epoch));
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid() {
+    let uuid = uuid::Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+    assert_eq!(
+        uuid.to_nbt_tag(),
+        owned::NbtTag::IntArray(vec![
+            0x069a79f4,
+            0x44e94726,
+            0xa5befca9_u32 as i32,
+            0x0e38aaf5
+        ])
+    );
+    assert_eq!(roundtrip(uuid), Some(uuid));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_date_time() {
+    let time = chrono::DateTime::from_timestamp_millis(1_700_000_000_123).unwrap();
+    assert_eq!(time.to_nbt_tag(), owned::NbtTag::Long(1_700_000_000_123));
+    assert_eq!(roundtrip(time), Some(time));
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Flags {
+    strict: bool,
+    #[simdnbt(lenient)]
+    lenient: bool,
+    #[simdnbt(lenient)]
+    optional: Option<bool>,
+}
+
+#[test]
+fn lenient_bool() {
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("strict", 2i8);
+    compound.insert("lenient", 1i32);
+    assert_eq!(
+        from_compound::<Flags>(compound),
+        Ok(Flags {
+            strict: true,
+            lenient: true,
+            optional: None,
+        })
+    );
+
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("strict", 0i8);
+    compound.insert("lenient", 0i64);
+    compound.insert("optional", 3i16);
+    assert_eq!(
+        from_compound::<Flags>(compound),
+        Ok(Flags {
+            strict: false,
+            lenient: false,
+            optional: Some(true),
+        })
+    );
+
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("strict", 1i32);
+    compound.insert("lenient", 1i8);
+    assert_eq!(
+        from_compound::<Flags>(compound),
+        Err(at(
+            "strict",
+            simdnbt::DeserializeError::MismatchedFieldType("Flags::strict".to_owned())
+        ))
+    );
+
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("strict", 1i8);
+    compound.insert("lenient", "true");
+    assert_eq!(
+        from_compound::<Flags>(compound),
+        Err(at(
+            "lenient",
+            simdnbt::DeserializeError::MismatchedFieldType("Flags::lenient".to_owned())
+        ))
+    );
+    assert_eq!(
+        from_compound::<Flags>(owned::NbtCompound::new()),
+        Err(at("strict", simdnbt::DeserializeError::MissingField))
+    );
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[simdnbt(lenient)]
+struct OldSave {
+    version: i32,
+    seed: i64,
+    x: f64,
+    y: f32,
+    id: u32,
+}
+
+#[test]
+fn lenient_numbers() {
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("version", 19133i16);
+    compound.insert("seed", -5i8);
+    compound.insert("x", 1.5f32);
+    compound.insert("y", 64i32);
+    compound.insert("id", -1i16);
+    assert_eq!(
+        from_compound::<OldSave>(compound),
+        Ok(OldSave {
+            version: 19133,
+            seed: -5,
+            x: 1.5,
+            y: 64.,
+            id: 0xffff,
+        })
+    );
+
+    // wider tags are still rejected
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("version", 1i64);
+    compound.insert("seed", 0i64);
+    compound.insert("x", 0f64);
+    compound.insert("y", 0f32);
+    compound.insert("id", 0i32);
+    assert_eq!(
+        from_compound::<OldSave>(compound),
+        Err(at(
+            "version",
+            simdnbt::DeserializeError::MismatchedFieldType("OldSave::version".to_owned())
+        ))
+    );
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("version", 1i32);
+    compound.insert("seed", 0i64);
+    compound.insert("x", 0f64);
+    compound.insert("y", 0f64);
+    compound.insert("id", 0i32);
+    assert_eq!(
+        from_compound::<OldSave>(compound),
+        Err(at(
+            "y",
+            simdnbt::DeserializeError::MismatchedFieldType("OldSave::y".to_owned())
+        ))
+    );
+}
+
+#[derive(Debug, Deserialize)]
+struct Chunk {
+    #[simdnbt(rename = "Level")]
+    _level: ChunkLevel,
+}
+#[derive(Debug, Deserialize)]
+struct ChunkLevel {
+    #[simdnbt(rename = "Sections")]
+    _sections: Vec<ChunkSection>,
+}
+#[derive(Debug, Deserialize)]
+struct ChunkSection {
+    #[simdnbt(rename = "Palette")]
+    _palette: Vec<BlockState>,
+}
+#[derive(Debug, Deserialize)]
+struct BlockState {
+    #[simdnbt(rename = "Name")]
+    _name: String,
+}
+
+#[test]
+fn error_path() {
+    let block_state = |name: Option<&str>| {
+        let mut compound = owned::NbtCompound::new();
+        if let Some(name) = name {
+            compound.insert("Name", name);
+        }
+        compound
+    };
+    let section = |palette| {
+        let mut compound = owned::NbtCompound::new();
+        compound.insert("Palette", owned::NbtList::Compound(palette));
+        compound
+    };
+    let mut level = owned::NbtCompound::new();
+    level.insert(
+        "Sections",
+        owned::NbtList::Compound(vec![
+            section(vec![block_state(Some("minecraft:air"))]),
+            section(vec![
+                block_state(Some("minecraft:stone")),
+                block_state(None),
+            ]),
+        ]),
+    );
+    let mut chunk = owned::NbtCompound::new();
+    chunk.insert("Level", level);
+
+    let err = from_compound::<Chunk>(chunk).unwrap_err();
+    assert_eq!(
+        err,
+        at(
+            "Level.Sections[1].Palette[1].Name",
+            simdnbt::DeserializeError::MissingField
+        )
+    );
+    assert_eq!(err.inner(), &simdnbt::DeserializeError::MissingField);
+    assert_eq!(
+        err.to_string(),
+        "Missing field at Level.Sections[1].Palette[1].Name"
+    );
+
+    // externally tagged enums include the variant name
+    let mut circle = owned::NbtCompound::new();
+    circle.insert("radius", "big");
+    let mut compound = owned::NbtCompound::new();
+    compound.insert("Circle", circle);
+    assert_eq!(
+        from_compound::<Shape>(compound),
+        Err(at(
+            "Circle.radius",
+            simdnbt::DeserializeError::MismatchedFieldType("Shape::Circle::radius".to_owned())
+        ))
+    );
+}